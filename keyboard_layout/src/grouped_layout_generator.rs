@@ -155,6 +155,10 @@ impl GroupedLayoutGenerator {
 }
 
 impl LayoutGenerator for GroupedLayoutGenerator {
+    fn keyboard(&self) -> &Keyboard {
+        &self.keyboard
+    }
+
     /// Generate a Neo variant [`Layout`] from a given string representation of its base layer (only non-fixed keys)
     fn generate(&self, layout_keys: &str) -> Result<Layout> {
         let chars: Vec<char> = layout_keys.chars().collect();
@@ -1,12 +1,14 @@
 //! This module provides structs for representing physical properties of keys in a keyboard
 
+use crate::keyboard::{KeyIndex, KeyboardId};
+
 use ahash::AHashMap;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::slice;
 
 /// Row and columnar location on the keyboard
-#[derive(Clone, Copy, Default, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq, Hash, Debug)]
 pub struct MatrixPosition(
     /// Index of column
     pub u8,
@@ -39,7 +41,7 @@ impl Default for Position {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
 #[repr(u8)]
 pub enum Finger {
     Thumb,  // 0
@@ -69,7 +71,7 @@ impl Finger {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
 #[repr(u8)]
 pub enum Hand {
     Left,  // 0
@@ -292,7 +294,7 @@ impl<T: Copy + fmt::Display> fmt::Display for HandFingerMap<T> {
 }
 
 /// Represents which part of a cluster each key belongs to
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Debug)]
 #[repr(u8)]
 pub enum Direction {
     // finger
@@ -322,6 +324,12 @@ impl Default for Direction {
 /// "uncomfortable" it is to reach it (in terms of a cost valua), or if it forces the hand off the home row.
 #[derive(Default, PartialEq, Clone, Debug)]
 pub struct Key {
+    /// Id of the [`Keyboard`](crate::keyboard::Keyboard) the key belongs to (0 if none)
+    pub keyboard_id: KeyboardId,
+
+    /// Index of the key in the `keys` of its [`Keyboard`](crate::keyboard::Keyboard)
+    pub index: KeyIndex,
+
     /// Hand of the finger used to press the key
     pub hand: Hand,
 
@@ -5,7 +5,10 @@ use crate::key::{Finger, Hand, HandFingerMap, Key, MatrixPosition, Position, Dir
 use ahash::{AHashMap, AHashSet};
 use anyhow::Result;
 use serde::Deserialize;
-use std::fs::File;
+use std::{
+    fs::File,
+    sync::atomic::{AtomicU64, Ordering},
+};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -21,14 +24,26 @@ pub enum KeyboardError {
 /// The index of a [`Key`] in the `keys` vec of a [`Keyboard`]
 pub type KeyIndex = u8;
 
+/// Identifies a [`Keyboard`] (and its clones), see [`Keyboard::id`]
+pub type KeyboardId = u64;
+
+/// The id of the next keyboard (0 is left for keys that do not belong to a keyboard)
+static NEXT_KEYBOARD_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A new, unique [`KeyboardId`]
+pub(crate) fn next_keyboard_id() -> KeyboardId {
+    NEXT_KEYBOARD_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 /// A struct representing a keyboard as a list of keys
 #[derive(Clone, Debug)]
 pub struct Keyboard {
+    pub(crate) id: KeyboardId,
     /// The keys of the keyboard
     pub keys: Vec<Key>,
     pub finger_resting_positions: HandFingerMap<Position>,
-    plot_template: String,
-    plot_template_short: String,
+    pub(crate) plot_template: String,
+    pub(crate) plot_template_short: String,
 }
 
 /// A collection of all relevant properties for the keys on a keyboard (configuration).
@@ -95,11 +110,61 @@ impl KeyboardYAML {
 
         Ok(())
     }
+
+    /// Rows of the per-key lists (e.g. `key_costs`) that do not have as many entries as the
+    /// corresponding row of `matrix_positions`. As the lists are flattened before they are
+    /// combined, the entries of such rows (and all following ones) end up on other keys than
+    /// intended, even if the total numbers of entries match. Each message names the list, the
+    /// row (counted from 0) and the matrix positions of that row.
+    pub fn misaligned_rows(&self) -> Vec<String> {
+        let lists = [
+            ("positions", row_lengths(&self.positions)),
+            ("hands", row_lengths(&self.hands)),
+            ("fingers", row_lengths(&self.fingers)),
+            ("directions", row_lengths(&self.directions)),
+            ("key_costs", row_lengths(&self.key_costs)),
+            ("symmetries", row_lengths(&self.symmetries)),
+            (
+                "unbalancing_positions",
+                row_lengths(&self.unbalancing_positions),
+            ),
+        ];
+
+        let mut messages = Vec::new();
+        for (name, lengths) in lists.iter() {
+            let n_rows = lengths.len().max(self.matrix_positions.len());
+            for row in 0..n_rows {
+                let n_entries = lengths.get(row).copied().unwrap_or(0);
+                let matrix_positions = self.matrix_positions.get(row).cloned().unwrap_or_default();
+                if n_entries != matrix_positions.len() {
+                    let positions: Vec<String> = matrix_positions
+                        .iter()
+                        .map(|MatrixPosition(column, row)| format!("[{}, {}]", column, row))
+                        .collect();
+                    messages.push(format!(
+                        "`{}` row {} has {} entries for {} matrix positions ({})",
+                        name,
+                        row,
+                        n_entries,
+                        matrix_positions.len(),
+                        positions.join(", ")
+                    ));
+                }
+            }
+        }
+
+        messages
+    }
+}
+
+fn row_lengths<T>(rows: &[Vec<T>]) -> Vec<usize> {
+    rows.iter().map(|row| row.len()).collect()
 }
 
 impl Keyboard {
     /// Generate a [`Keyboard`] from a [`KeyboardYAML`] object
     pub fn from_yaml_object(k: KeyboardYAML) -> Self {
+        let id = next_keyboard_id();
         let keys = k
             .hands
             .into_iter()
@@ -111,11 +176,17 @@ impl Keyboard {
             .zip(k.symmetries.into_iter().flatten())
             .zip(k.key_costs.into_iter().flatten())
             .zip(k.unbalancing_positions.into_iter().flatten())
+            .enumerate()
             .map(
                 |(
-                    ((((((hand, finger), matrix_position), position), direction), symmetry_index), cost),
-                    unbalancing,
+                    index,
+                    (
+                        ((((((hand, finger), matrix_position), position), direction), symmetry_index), cost),
+                        unbalancing,
+                    ),
                 )| Key {
+                    keyboard_id: id,
+                    index: index as KeyIndex,
                     hand,
                     finger,
                     direction,
@@ -129,6 +200,7 @@ impl Keyboard {
             .collect();
 
         Keyboard {
+            id,
             keys,
             finger_resting_positions: HandFingerMap::with_hashmap(
                 &k.finger_resting_positions,
@@ -152,6 +224,11 @@ impl Keyboard {
         Ok(Keyboard::from_yaml_object(k))
     }
 
+    /// Unique to the keyboard and its clones, and stored in the `keyboard_id` of its keys
+    pub fn id(&self) -> KeyboardId {
+        self.id
+    }
+
     /// Plot a graphical representation of the keyboard with given key labels
     pub fn plot(&self, key_labels: &[String]) -> String {
         let mut reg = handlebars::Handlebars::new();
@@ -200,3 +277,67 @@ impl Keyboard {
         intended_loads
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A keyboard with two rows of two keys each and the given `key_costs`
+    fn keyboard_yaml(key_costs: &str) -> KeyboardYAML {
+        serde_yaml::from_str(&format!(
+            "
+            matrix_positions: [[[0, 0], [1, 0]], [[0, 1], [1, 1]]]
+            positions: [[[0.0, 0.0], [1.0, 0.0]], [[0.0, 1.0], [1.0, 1.0]]]
+            hands: [[Left, Left], [Left, Left]]
+            fingers: [[Ring, Middle], [Ring, Middle]]
+            directions: [[North, North], [South, South]]
+            key_costs: {}
+            symmetries: [[0, 1], [0, 1]]
+            unbalancing_positions: [[[0, 0], [0, 0]], [[0, 0], [0, 0]]]
+            finger_resting_positions: {{}}
+            plot_template: ''
+            plot_template_short: ''
+            ",
+            key_costs
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn aligned_rows_are_not_reported() {
+        assert!(keyboard_yaml("[[1.0, 2.0], [3.0, 4.0]]")
+            .misaligned_rows()
+            .is_empty());
+    }
+
+    #[test]
+    fn one_stale_entry_gives_one_warning() {
+        let messages = keyboard_yaml("[[1.0, 2.0], [3.0, 4.0, 5.0]]").misaligned_rows();
+
+        assert_eq!(
+            messages,
+            vec!["`key_costs` row 1 has 3 entries for 2 matrix positions ([0, 1], [1, 1])"]
+        );
+    }
+
+    #[test]
+    fn shifted_entries_are_reported_even_if_the_totals_match() {
+        let keyboard = keyboard_yaml("[[1.0], [2.0, 3.0, 4.0]]");
+
+        assert!(keyboard.validate().is_ok());
+        assert_eq!(keyboard.misaligned_rows().len(), 2);
+    }
+
+    #[test]
+    fn keys_know_their_keyboard() {
+        let keyboard = Keyboard::from_yaml_object(keyboard_yaml("[[1.0, 2.0], [3.0, 4.0]]"));
+        let other = Keyboard::from_yaml_object(keyboard_yaml("[[1.0, 2.0], [3.0, 4.0]]"));
+
+        assert_ne!(keyboard.id(), other.id());
+        assert_eq!(keyboard.clone().id(), keyboard.id());
+        assert!(keyboard
+            .keys
+            .iter()
+            .all(|key| key.keyboard_id == keyboard.id()));
+    }
+}
@@ -6,7 +6,7 @@ use crate::key::{Hand, Key, MatrixPosition};
 use crate::keyboard::{KeyIndex, Keyboard};
 
 use ahash::AHashMap;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use colored::Colorize;
 use core::slice;
 use serde::Deserialize;
@@ -197,6 +197,8 @@ pub struct Layout {
     key_layers: Vec<Vec<LayerKeyIndex>>,
     /// Map for retrieving the [`LayerKey`] for the symbol it generates
     key_map: Map<char, LayerKeyIndex>,
+    /// Locations of the modifiers of each layer (as given to [`Layout::new`])
+    modifiers: Vec<AHashMap<Hand, LayerModifierLocations>>,
 }
 
 impl fmt::Display for Layout {
@@ -363,9 +365,63 @@ impl Layout {
             keyboard,
             layerkey_to_key_index,
             key_map,
+            modifiers,
         })
     }
 
+    /// Swap the symbols (of all layers) of the keys `k1` and `k2` of the keyboard. Everything
+    /// else, including whether the keys are fixed, stays with the keys. Modifiers given as
+    /// symbols move with their symbols.
+    ///
+    /// Fails if a key index is out of bounds.
+    pub fn swap_keys(&mut self, k1: KeyIndex, k2: KeyIndex) -> Result<()> {
+        *self = self.swap_keys_new(k1, k2)?;
+        Ok(())
+    }
+
+    /// A copy of the layout with the symbols of the keys `k1` and `k2` swapped, see
+    /// [`Layout::swap_keys`].
+    pub fn swap_keys_new(&self, k1: KeyIndex, k2: KeyIndex) -> Result<Layout> {
+        for k in [k1, k2] {
+            if k as usize >= self.key_layers.len() {
+                bail!(
+                    "Key index {} is out of bounds (the layout has {} keys)",
+                    k,
+                    self.key_layers.len()
+                );
+            }
+        }
+
+        let mut key_chars: Vec<Vec<char>> = self
+            .key_layers
+            .iter()
+            .map(|layerkeys| {
+                layerkeys
+                    .iter()
+                    .map(|lk| self.get_layerkey(lk).symbol)
+                    .collect()
+            })
+            .collect();
+        let fixed_keys: Vec<bool> = self
+            .key_layers
+            .iter()
+            .map(|layerkeys| {
+                layerkeys
+                    .first()
+                    .map(|lk| self.get_layerkey(lk).is_fixed)
+                    .unwrap_or(false)
+            })
+            .collect();
+        key_chars.swap(k1 as usize, k2 as usize);
+
+        Layout::new(
+            key_chars,
+            fixed_keys,
+            self.keyboard.clone(),
+            self.modifiers.clone(),
+        )
+    }
+
     fn gen_key_map(layerkeys: &[LayerKey]) -> Map<char, LayerKeyIndex> {
         let mut m = Map::default();
         layerkeys
@@ -431,6 +487,14 @@ impl Layout {
         self.key_map.get(c).cloned()
     }
 
+    /// Get the index of the [`Key`] generating a given symbol, if it can be generated with the layout
+    #[inline(always)]
+    pub fn get_key_index_for_symbol(&self, c: &char) -> Option<KeyIndex> {
+        self.key_map
+            .get(c)
+            .map(|idx| self.layerkey_to_key_index[*idx as usize])
+    }
+
     /// Get the index of the "base" symbol (the one on the base layer, e.g. "A" -> "a") for a given [`LayerKeyIndex`]
     #[inline(always)]
     pub fn get_base_layerkey_index(&self, layerkey_index: &LayerKeyIndex) -> LayerKeyIndex {
@@ -522,6 +586,44 @@ impl Layout {
         self.keyboard.plot_compact(&key_chars)
     }
 
+    /// [`LayerKey`]s of all layers for each non-fixed key (in the order of the keyboard's keys)
+    pub fn non_fixed_layerkeys(&self) -> Vec<Vec<&LayerKey>> {
+        self.key_layers
+            .iter()
+            .filter(|layerkeys| {
+                layerkeys
+                    .first()
+                    .map(|lk| !self.get_layerkey(lk).is_fixed)
+                    .unwrap_or(false)
+            })
+            .map(|layerkeys| layerkeys.iter().map(|lk| self.get_layerkey(lk)).collect())
+            .collect()
+    }
+
+    /// Indices of the non-fixed keys of the keyboard (that have symbols), e.g. for
+    /// [`Layout::swap_keys`]
+    pub fn non_fixed_key_indices(&self) -> Vec<KeyIndex> {
+        self.key_layers
+            .iter()
+            .enumerate()
+            .filter(|(_, layerkeys)| {
+                layerkeys
+                    .first()
+                    .map(|lk| !self.get_layerkey(lk).is_fixed)
+                    .unwrap_or(false)
+            })
+            .map(|(key_index, _)| key_index as KeyIndex)
+            .collect()
+    }
+
+    /// Symbols of all layers for each non-fixed key (in the order of the keyboard's keys)
+    pub fn non_fixed_key_symbols(&self) -> Vec<Vec<char>> {
+        self.non_fixed_layerkeys()
+            .iter()
+            .map(|layerkeys| layerkeys.iter().map(|lk| lk.symbol).collect())
+            .collect()
+    }
+
     /// Concatenate all non-fixed keys into a string without any whitespace
     pub fn as_text(&self) -> String {
         self.key_layers
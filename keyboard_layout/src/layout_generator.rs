@@ -3,11 +3,14 @@
 
 use core::fmt;
 
-use crate::layout::Layout;
+use crate::{keyboard::Keyboard, layout::Layout};
 use anyhow::Result;
 
 pub trait LayoutGenerator: Send + Sync + LayoutGeneratorClone + fmt::Debug {
     fn generate(&self, layout_keys: &str) -> Result<Layout>;
+
+    /// The keyboard the layouts are generated for
+    fn keyboard(&self) -> &Keyboard;
 }
 
 impl Clone for Box<dyn LayoutGenerator> {
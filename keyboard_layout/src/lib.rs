@@ -13,6 +13,9 @@ pub mod keyboard;
 pub mod layout;
 pub mod layout_generator;
 pub mod neo_layout_generator;
+pub mod permalink;
+#[cfg(any(test, feature = "testing"))]
+pub mod test_utils;
 
 #[cfg(test)]
 mod tests {
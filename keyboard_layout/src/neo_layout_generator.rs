@@ -181,6 +181,10 @@ impl NeoLayoutGenerator {
 }
 
 impl LayoutGenerator for NeoLayoutGenerator {
+    fn keyboard(&self) -> &Keyboard {
+        &self.keyboard
+    }
+
     /// Generate a Neo variant [`Layout`] from a given string representation of its base layer (only non-fixed keys)
     fn generate(&self, layout_keys: &str) -> Result<Layout> {
         // XXX: sort of a hack, but: empty strings result in the default layout
@@ -0,0 +1,342 @@
+//! This module provides compact, URL-safe "permalink" tokens for sharing layouts.
+//!
+//! A token is the base64url encoding (without padding) of a small binary payload:
+//!
+//! ```text
+//! version (u8) | name length (u8) | keyboard name (UTF-8)
+//!              | key count (u16, big endian)
+//!              | per key: symbols length (u8) | symbols of all layers (UTF-8)
+//! ```
+//!
+//! Only non-fixed keys are stored. The symbols of the first layer form the layout string
+//! that can be passed to a [`LayoutGenerator`]; the remaining layers are kept so that a
+//! decoded token can be checked against the layout generated from the current configuration.
+
+use crate::layout::Layout;
+use crate::layout_generator::LayoutGenerator;
+
+use anyhow::Result;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use thiserror::Error;
+
+/// The current version of the permalink format
+pub const PERMALINK_VERSION: u8 = 1;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PermalinkError {
+    #[error("Invalid permalink: Empty token")]
+    Empty,
+    #[error("Invalid permalink: Not valid base64url: {0}")]
+    InvalidEncoding(String),
+    #[error("Invalid permalink: Unsupported format version {0} (supported: {PERMALINK_VERSION})")]
+    UnsupportedVersion(u8),
+    #[error("Invalid permalink: Token is truncated while reading {0}")]
+    Truncated(&'static str),
+    #[error("Invalid permalink: {0} is not valid UTF-8")]
+    InvalidUtf8(&'static str),
+    #[error("Invalid permalink: Key {0} has no symbols")]
+    EmptyKey(usize),
+    #[error("Invalid permalink: {0} unexpected trailing bytes")]
+    TrailingBytes(usize),
+    #[error("Can not create permalink: {0} is too long ({1} bytes)")]
+    TooLong(&'static str, usize),
+}
+
+/// A decoded permalink, i.e. the name of the keyboard and the symbols of all layers
+/// for each non-fixed key of the layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Permalink {
+    pub keyboard: String,
+    pub key_symbols: Vec<Vec<char>>,
+}
+
+/// Cursor over the payload bytes reporting truncations as [`PermalinkError`]s.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize, what: &'static str) -> Result<&'a [u8], PermalinkError> {
+        if self.data.len() - self.pos < n {
+            return Err(PermalinkError::Truncated(what));
+        }
+        let bytes = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn str(&mut self, n: usize, what: &'static str) -> Result<&'a str, PermalinkError> {
+        std::str::from_utf8(self.take(n, what)?).map_err(|_| PermalinkError::InvalidUtf8(what))
+    }
+}
+
+impl Permalink {
+    /// Collect the symbols of all non-fixed keys of a [`Layout`]
+    pub fn from_layout(keyboard: &str, layout: &Layout) -> Self {
+        Self {
+            keyboard: keyboard.to_string(),
+            key_symbols: layout.non_fixed_key_symbols(),
+        }
+    }
+
+    /// The layout string (base layer of the non-fixed keys) as accepted by a [`LayoutGenerator`]
+    pub fn layout_str(&self) -> String {
+        self.key_symbols
+            .iter()
+            .filter_map(|symbols| symbols.first())
+            .collect()
+    }
+
+    /// Encode into a URL-safe token
+    pub fn encode(&self) -> Result<String, PermalinkError> {
+        let name = self.keyboard.as_bytes();
+        if name.len() > u8::MAX as usize {
+            return Err(PermalinkError::TooLong("Keyboard name", name.len()));
+        }
+        if self.key_symbols.len() > u16::MAX as usize {
+            return Err(PermalinkError::TooLong("Key list", self.key_symbols.len()));
+        }
+
+        let mut payload = vec![PERMALINK_VERSION, name.len() as u8];
+        payload.extend_from_slice(name);
+        payload.extend_from_slice(&(self.key_symbols.len() as u16).to_be_bytes());
+        for symbols in self.key_symbols.iter() {
+            let symbols: String = symbols.iter().collect();
+            if symbols.len() > u8::MAX as usize {
+                return Err(PermalinkError::TooLong("Key symbols", symbols.len()));
+            }
+            payload.push(symbols.len() as u8);
+            payload.extend_from_slice(symbols.as_bytes());
+        }
+
+        Ok(URL_SAFE_NO_PAD.encode(payload))
+    }
+
+    /// Decode a token produced by [`Permalink::encode`]
+    pub fn decode(token: &str) -> Result<Self, PermalinkError> {
+        let token = token.trim();
+        if token.is_empty() {
+            return Err(PermalinkError::Empty);
+        }
+
+        let data = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|e| PermalinkError::InvalidEncoding(e.to_string()))?;
+        let mut reader = Reader {
+            data: &data,
+            pos: 0,
+        };
+
+        let version = reader.take(1, "version")?[0];
+        if version != PERMALINK_VERSION {
+            return Err(PermalinkError::UnsupportedVersion(version));
+        }
+
+        let name_len = reader.take(1, "keyboard name length")?[0] as usize;
+        let keyboard = reader.str(name_len, "keyboard name")?.to_string();
+
+        let n_keys = reader.take(2, "key count")?;
+        let n_keys = u16::from_be_bytes([n_keys[0], n_keys[1]]) as usize;
+
+        let mut key_symbols = Vec::with_capacity(n_keys);
+        for key_idx in 0..n_keys {
+            let len = reader.take(1, "key symbols length")?[0] as usize;
+            let symbols: Vec<char> = reader.str(len, "key symbols")?.chars().collect();
+            if symbols.is_empty() {
+                return Err(PermalinkError::EmptyKey(key_idx));
+            }
+            key_symbols.push(symbols);
+        }
+
+        if reader.pos != data.len() {
+            return Err(PermalinkError::TrailingBytes(data.len() - reader.pos));
+        }
+
+        Ok(Self {
+            keyboard,
+            key_symbols,
+        })
+    }
+
+    /// Generate the [`Layout`] described by the permalink. Higher layers that differ from
+    /// the ones produced by the generator (e.g. after a change of the layout config) are reported
+    /// as warnings; the generator's layers take precedence.
+    pub fn generate_layout(&self, layout_generator: &dyn LayoutGenerator) -> Result<Layout> {
+        let layout = layout_generator.generate(&self.layout_str())?;
+
+        let generated = layout.non_fixed_key_symbols();
+        if generated != self.key_symbols {
+            log::warn!(
+                "Higher layers of the permalink do not match the layout config; using the configured layers"
+            );
+        }
+
+        Ok(layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::toy_layout;
+
+    fn token(payload: &[u8]) -> String {
+        URL_SAFE_NO_PAD.encode(payload)
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let layout = toy_layout("abcdeäöü,.-ß");
+        let permalink = Permalink::from_layout("toy", &layout);
+        let decoded = Permalink::decode(&permalink.encode().unwrap()).unwrap();
+
+        assert_eq!(decoded, permalink);
+        assert_eq!(decoded.keyboard, "toy");
+        assert_eq!(decoded.layout_str(), "abcdeäöü,.-ß");
+    }
+
+    #[test]
+    fn roundtrip_keeps_all_layers() {
+        let permalink = Permalink {
+            keyboard: "sval".to_string(),
+            key_symbols: vec![vec!['a', 'A', '…'], vec!['□'], vec!['x', 'X']],
+        };
+        let token = permalink.encode().unwrap();
+
+        assert!(token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(Permalink::decode(&token).unwrap(), permalink);
+    }
+
+    #[test]
+    fn decode_ignores_surrounding_whitespace() {
+        let permalink = Permalink {
+            keyboard: "toy".to_string(),
+            key_symbols: vec![vec!['a']],
+        };
+        let token = format!("  {}\n", permalink.encode().unwrap());
+        assert_eq!(Permalink::decode(&token).unwrap(), permalink);
+    }
+
+    #[test]
+    fn decode_rejects_empty_token() {
+        assert_eq!(Permalink::decode(""), Err(PermalinkError::Empty));
+        assert_eq!(Permalink::decode(" \n"), Err(PermalinkError::Empty));
+    }
+
+    #[test]
+    fn decode_rejects_bad_base64() {
+        assert!(matches!(
+            Permalink::decode("not a token!"),
+            Err(PermalinkError::InvalidEncoding(_))
+        ));
+        // standard base64 characters that are not part of base64url
+        assert!(matches!(
+            Permalink::decode("AQN0b3kAAQFh+/"),
+            Err(PermalinkError::InvalidEncoding(_))
+        ));
+        // padding is not accepted
+        assert!(matches!(
+            Permalink::decode("AQ=="),
+            Err(PermalinkError::InvalidEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn decode_rejects_unsupported_version() {
+        assert_eq!(
+            Permalink::decode(&token(&[0, 0, 0, 0])),
+            Err(PermalinkError::UnsupportedVersion(0))
+        );
+        assert_eq!(
+            Permalink::decode(&token(&[PERMALINK_VERSION + 1, 0, 0, 0])),
+            Err(PermalinkError::UnsupportedVersion(PERMALINK_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn decode_rejects_truncated_tokens() {
+        let cases: Vec<(Vec<u8>, &str)> = vec![
+            (vec![PERMALINK_VERSION], "keyboard name length"),
+            // name length prefix claims more bytes than available
+            (vec![PERMALINK_VERSION, 5, b't', b'o'], "keyboard name"),
+            (vec![PERMALINK_VERSION, 3, b't', b'o', b'y'], "key count"),
+            (vec![PERMALINK_VERSION, 3, b't', b'o', b'y', 0], "key count"),
+            // two keys announced, only one present
+            (
+                vec![PERMALINK_VERSION, 3, b't', b'o', b'y', 0, 2, 1, b'a'],
+                "key symbols length",
+            ),
+            // symbols length prefix claims more bytes than available
+            (
+                vec![PERMALINK_VERSION, 3, b't', b'o', b'y', 0, 1, 3, b'a'],
+                "key symbols",
+            ),
+        ];
+
+        for (payload, what) in cases {
+            assert_eq!(
+                Permalink::decode(&token(&payload)),
+                Err(PermalinkError::Truncated(what)),
+                "payload {:?}",
+                payload
+            );
+        }
+    }
+
+    #[test]
+    fn decode_rejects_truncated_valid_token() {
+        let permalink = Permalink {
+            keyboard: "toy".to_string(),
+            key_symbols: vec![vec!['a', 'A'], vec!['b', 'B']],
+        };
+        let token = permalink.encode().unwrap();
+
+        for len in 1..token.len() {
+            assert!(
+                Permalink::decode(&token[..len]).is_err(),
+                "prefix of length {} was accepted",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn decode_rejects_invalid_utf8() {
+        assert_eq!(
+            Permalink::decode(&token(&[PERMALINK_VERSION, 2, 0xff, 0xfe, 0, 0])),
+            Err(PermalinkError::InvalidUtf8("keyboard name"))
+        );
+        // a truncated multi-byte character
+        assert_eq!(
+            Permalink::decode(&token(&[PERMALINK_VERSION, 0, 0, 1, 1, 0xc3])),
+            Err(PermalinkError::InvalidUtf8("key symbols"))
+        );
+    }
+
+    #[test]
+    fn decode_rejects_empty_keys_and_trailing_bytes() {
+        assert_eq!(
+            Permalink::decode(&token(&[PERMALINK_VERSION, 0, 0, 2, 1, b'a', 0])),
+            Err(PermalinkError::EmptyKey(1))
+        );
+        assert_eq!(
+            Permalink::decode(&token(&[PERMALINK_VERSION, 0, 0, 1, 1, b'a', b'b', b'c'])),
+            Err(PermalinkError::TrailingBytes(2))
+        );
+    }
+
+    #[test]
+    fn encode_rejects_too_long_keyboard_name() {
+        let permalink = Permalink {
+            keyboard: "k".repeat(256),
+            key_symbols: vec![vec!['a']],
+        };
+        assert_eq!(
+            permalink.encode(),
+            Err(PermalinkError::TooLong("Keyboard name", 256))
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! The `test_utils` module (behind the `testing` feature) helps writing tests for metrics: it
+//! builds [`Key`]s and [`LayerKey`]s from the few properties a test cares about and provides a
+//! small Svalboard-like keyboard and layouts on it.
+//!
+//! ```
+//! use keyboard_layout::{
+//!     key::{Direction, Finger, Hand},
+//!     test_utils::{key, layerkeys, toy_layout},
+//! };
+//!
+//! let k = key(Hand::Left, Finger::Index, Direction::North).cost(3.0).layerkey('e');
+//! assert_eq!(k.key.cost, 3.0);
+//!
+//! let layout = toy_layout("abcdefghijklmnopqrstuvwxyz,.;'-/ 1234567");
+//! let keys = layerkeys(&layout, "ab");
+//! assert_eq!(keys[0].key.finger, keys[1].key.finger);
+//! ```
+//!
+//! To use it in the tests of another crate, enable the feature for the dev-dependency:
+//! `keyboard_layout = { path = "../keyboard_layout", features = ["testing"] }`.
+
+use crate::{
+    key::{Direction, Finger, Hand, HandFingerMap, Key, MatrixPosition, Position},
+    keyboard::{next_keyboard_id, KeyIndex, Keyboard},
+    layout::{LayerKey, LayerModifierType, LayerModifiers, Layout},
+    layout_generator::LayoutGenerator,
+};
+
+use anyhow::Result;
+use std::sync::Arc;
+
+/// Builder for a [`Key`], see [`key`]
+#[derive(Clone, Debug)]
+pub struct KeyBuilder(Key);
+
+/// Start building a [`Key`] with the given hand, finger, and direction. All other properties
+/// are zero unless set.
+pub fn key(hand: Hand, finger: Finger, direction: Direction) -> KeyBuilder {
+    KeyBuilder(Key {
+        hand,
+        finger,
+        direction,
+        ..Key::default()
+    })
+}
+
+impl KeyBuilder {
+    /// Index of the key in the `keys` of its keyboard
+    pub fn index(mut self, index: KeyIndex) -> Self {
+        self.0.index = index;
+        self
+    }
+
+    pub fn cost(mut self, cost: f64) -> Self {
+        self.0.cost = cost;
+        self
+    }
+
+    /// Matrix position (column, row)
+    pub fn matrix_position(mut self, column: u8, row: u8) -> Self {
+        self.0.matrix_position = MatrixPosition(column, row);
+        self
+    }
+
+    /// 2D position (x, y)
+    pub fn position(mut self, x: f64, y: f64) -> Self {
+        self.0.position = Position(x, y);
+        self
+    }
+
+    pub fn symmetry_index(mut self, symmetry_index: u8) -> Self {
+        self.0.symmetry_index = symmetry_index;
+        self
+    }
+
+    /// How strongly the hand moves away from the home row (horizontally, vertically)
+    pub fn unbalancing(mut self, x: f64, y: f64) -> Self {
+        self.0.unbalancing = Position(x, y);
+        self
+    }
+
+    pub fn build(self) -> Key {
+        self.0
+    }
+
+    /// A [`LayerKey`] for `symbol` on the base layer of the key (without modifiers)
+    pub fn layerkey(self, symbol: char) -> LayerKey {
+        LayerKey::new(
+            0,
+            self.0,
+            symbol,
+            LayerModifiers::default(),
+            false,
+            LayerModifierType::None,
+        )
+    }
+}
+
+impl From<KeyBuilder> for Key {
+    fn from(builder: KeyBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Fingers of the left hand from the outside in
+const LEFT_FINGERS: [Finger; 4] = [Finger::Pinky, Finger::Ring, Finger::Middle, Finger::Index];
+
+/// Cost of the keys of a finger cluster of the toy keyboard
+fn toy_cost(direction: Direction) -> f64 {
+    match direction {
+        Direction::Center => 1.0,
+        Direction::North | Direction::South => 2.0,
+        _ => 3.0,
+    }
+}
+
+/// A minimal Svalboard-like keyboard with 42 keys: a cluster of five keys (North, West,
+/// Center, East, South) for each finger from the left pinky to the right pinky, followed by
+/// one `Pad` key for each thumb (left, right).
+///
+/// West is `Out` on the left hand and `In` on the right hand. The keys cost 1.0 (Center and the
+/// thumb pads), 2.0 (North, South), or 3.0 (In, Out). The matrix positions follow
+/// `config/keyboard/sval.yml`, with each cluster three columns wide.
+pub fn toy_keyboard() -> Keyboard {
+    let fingers = LEFT_FINGERS
+        .iter()
+        .map(|f| (Hand::Left, *f))
+        .chain(LEFT_FINGERS.iter().rev().map(|f| (Hand::Right, *f)));
+
+    let mut keys = Vec::new();
+    let mut finger_resting_positions = HandFingerMap::with_default(Position::default());
+    for (cluster, (hand, finger)) in fingers.enumerate() {
+        let (west, east) = match hand {
+            Hand::Left => (Direction::Out, Direction::In),
+            Hand::Right => (Direction::In, Direction::Out),
+        };
+        let center = 3 * cluster as u8 + 2;
+        let cluster_keys = [
+            (Direction::North, center, 1),
+            (west, center - 1, 2),
+            (Direction::Center, center, 2),
+            (east, center + 1, 2),
+            (Direction::South, center, 3),
+        ];
+        for (direction, column, row) in cluster_keys {
+            keys.push(
+                key(hand, finger, direction)
+                    .index(keys.len() as KeyIndex)
+                    .cost(toy_cost(direction))
+                    .matrix_position(column, row)
+                    .position(column as f64, row as f64)
+                    .symmetry_index(cluster.min(7 - cluster) as u8)
+                    .build(),
+            );
+        }
+        finger_resting_positions.set(&hand, &finger, Position(center as f64, 2.0));
+    }
+
+    for (hand, column) in [(Hand::Left, 11), (Hand::Right, 14)] {
+        keys.push(
+            key(hand, Finger::Thumb, Direction::Pad)
+                .index(keys.len() as KeyIndex)
+                .cost(1.0)
+                .matrix_position(column, 4)
+                .position(column as f64, 4.0)
+                .symmetry_index(4)
+                .build(),
+        );
+        finger_resting_positions.set(&hand, &Finger::Thumb, Position(column as f64, 4.0));
+    }
+
+    let id = next_keyboard_id();
+    for key in keys.iter_mut() {
+        key.keyboard_id = id;
+    }
+
+    let template: String = (0..keys.len()).map(|i| format!("{{{{{}}}}}", i)).collect();
+    Keyboard {
+        id,
+        keys,
+        finger_resting_positions,
+        plot_template: template.clone(),
+        plot_template_short: template,
+    }
+}
+
+/// A single-layer layout on the [`toy_keyboard`] with the symbols of `chars` in the order of
+/// its keys. Keys beyond the end of `chars` stay empty.
+///
+/// Panics if there are more symbols than keys.
+pub fn toy_layout(chars: &str) -> Layout {
+    let keyboard = toy_keyboard();
+    let key_chars: Vec<Vec<char>> = chars.chars().map(|c| vec![c]).collect();
+    assert!(
+        key_chars.len() <= keyboard.keys.len(),
+        "The toy keyboard has only {} keys",
+        keyboard.keys.len()
+    );
+    let fixed_keys = vec![false; key_chars.len()];
+
+    Layout::new(key_chars, fixed_keys, Arc::new(keyboard), Vec::new()).unwrap()
+}
+
+/// A [`LayoutGenerator`] for the [`toy_keyboard`] that places the symbols like [`toy_layout`],
+/// e.g. for running an optimizer in tests.
+#[derive(Clone, Debug)]
+pub struct ToyLayoutGenerator {
+    keyboard: Arc<Keyboard>,
+}
+
+impl Default for ToyLayoutGenerator {
+    fn default() -> Self {
+        Self {
+            keyboard: Arc::new(toy_keyboard()),
+        }
+    }
+}
+
+impl LayoutGenerator for ToyLayoutGenerator {
+    fn generate(&self, layout_keys: &str) -> Result<Layout> {
+        let key_chars: Vec<Vec<char>> = layout_keys.chars().map(|c| vec![c]).collect();
+        let fixed_keys = vec![false; key_chars.len()];
+
+        Layout::new(key_chars, fixed_keys, self.keyboard.clone(), Vec::new())
+    }
+
+    fn keyboard(&self) -> &Keyboard {
+        &self.keyboard
+    }
+}
+
+/// The [`LayerKey`]s of the symbols of `ngram` in `layout`, e.g. for passing a bigram to a
+/// metric.
+///
+/// Panics if a symbol is not on the layout.
+pub fn layerkeys<'a>(layout: &'a Layout, ngram: &str) -> Vec<&'a LayerKey> {
+    ngram
+        .chars()
+        .map(|c| {
+            layout
+                .get_layerkey_for_symbol(&c)
+                .unwrap_or_else(|| panic!("Symbol '{}' is not on the layout", c))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toy_keyboard_indices_match_positions() {
+        let keyboard = toy_keyboard();
+        assert_eq!(keyboard.keys.len(), 42);
+        for (i, key) in keyboard.keys.iter().enumerate() {
+            assert_eq!(key.index as usize, i);
+        }
+    }
+
+    #[test]
+    fn toy_keyboard_clusters_are_mirrored() {
+        let keyboard = toy_keyboard();
+        for (left, right) in keyboard.keys[..20]
+            .iter()
+            .zip(keyboard.keys[20..40].chunks(5).rev().flatten())
+        {
+            assert_eq!(left.hand, Hand::Left);
+            assert_eq!(right.hand, Hand::Right);
+            assert_eq!(left.finger, right.finger);
+            assert_eq!(left.symmetry_index, right.symmetry_index);
+        }
+    }
+
+    #[test]
+    fn toy_layout_places_symbols_in_key_order() {
+        let layout = toy_layout("abcdef");
+        let keys = layerkeys(&layout, "aef");
+        assert_eq!(
+            (keys[0].key.hand, keys[0].key.finger, keys[0].key.direction),
+            (Hand::Left, Finger::Pinky, Direction::North)
+        );
+        assert_eq!(
+            (keys[1].key.hand, keys[1].key.finger, keys[1].key.direction),
+            (Hand::Left, Finger::Pinky, Direction::South)
+        );
+        assert_eq!(
+            (keys[2].key.hand, keys[2].key.finger, keys[2].key.direction),
+            (Hand::Left, Finger::Ring, Direction::North)
+        );
+        assert_eq!(keys[0].key.cost, 2.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "not on the layout")]
+    fn layerkeys_panics_on_unknown_symbol() {
+        let layout = toy_layout("ab");
+        layerkeys(&layout, "z");
+    }
+}
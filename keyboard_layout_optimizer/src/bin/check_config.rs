@@ -0,0 +1,405 @@
+use keyboard_layout::{
+    config::LayoutConfig,
+    key::{Direction, MatrixPosition},
+    keyboard::Keyboard,
+    layout::Layout,
+    layout_generator::LayoutGenerator,
+};
+use keyboard_layout_optimizer::{
+    common,
+    config_check::{check_fields, visit_strings, Report, Severity},
+};
+use layout_evaluation::{config::EvaluationParameters, corpus::Corpus, presets};
+use layout_optimization_common::{corpus_weights::CorpusWeights, weight_schedule::WeightSchedules};
+use layout_optimization_genetic::optimization::Parameters as GeneticParameters;
+use layout_optimization_sa::optimization::Parameters as SaParameters;
+
+use ahash::AHashSet;
+use clap::Parser;
+use serde_yaml::Value;
+use std::{fs, process};
+
+#[derive(Parser, Debug)]
+#[clap(name = "Keyboard layout configuration check")]
+struct Options {
+    /// Evaluation parameters
+    #[clap(flatten)]
+    evaluation_parameters: common::CommonOptions,
+
+    /// Filename of a simulated annealing configuration file to check as well
+    #[clap(long)]
+    sa_parameters: Option<String>,
+
+    /// Filename of a genetic optimization configuration file to check as well
+    #[clap(long)]
+    genetic_parameters: Option<String>,
+
+    /// Check the characters used in the configurations against this layout (defaults to the
+    /// baseline layout of the evaluation configuration)
+    #[clap(long)]
+    layout: Option<String>,
+}
+
+/// Severity of likely mistakes that do not prevent using a configuration
+fn severity(strict: bool) -> Severity {
+    if strict {
+        Severity::Error
+    } else {
+        Severity::Warning
+    }
+}
+
+fn read(file: &str, report: &mut Report) -> Option<String> {
+    match fs::read_to_string(file) {
+        Ok(content) => Some(content),
+        Err(e) => {
+            report.error(file, "", format!("could not read file: {}", e));
+            None
+        }
+    }
+}
+
+/// Check the keyboard and base layout, returning the keyboard if it is valid.
+fn check_layout_config(file: &str, strict: bool, report: &mut Report) -> Option<Keyboard> {
+    let layout_config: LayoutConfig = check_fields(&read(file, report)?, file, report)?;
+    if let Err(e) = layout_config.validate() {
+        report.error(file, "", e.to_string());
+        return None;
+    }
+    for message in layout_config.keyboard.misaligned_rows() {
+        report.add(severity(strict), file, "keyboard", message);
+    }
+
+    Some(Keyboard::from_yaml_object(layout_config.keyboard))
+}
+
+/// Check the evaluation parameters (with the selected preset expanded, if any).
+fn check_eval_params(file: &str, report: &mut Report) -> Option<EvaluationParameters> {
+    let content = read(file, report)?;
+    let config: Value = match serde_yaml::from_str(&content) {
+        Ok(config) => config,
+        Err(e) => {
+            report.error(file, "", e.to_string());
+            return None;
+        }
+    };
+    let content = match presets::expand(config) {
+        Ok(Some(expanded)) => serde_yaml::to_string(&expanded).ok()?,
+        Ok(None) => content,
+        Err(e) => {
+            report.error(file, "", e.to_string());
+            return None;
+        }
+    };
+
+    check_fields(&content, file, report)
+}
+
+/// Warn about directions in the metric parameters that no key of the keyboard has.
+fn check_directions(content: &str, file: &str, keyboard: &Keyboard, report: &mut Report) {
+    let config: Value = match serde_yaml::from_str(content) {
+        Ok(config) => config,
+        Err(_) => return,
+    };
+    let metrics = match config.get("metrics") {
+        Some(metrics) => metrics,
+        None => return,
+    };
+    let directions: AHashSet<Direction> = keyboard.keys.iter().map(|k| k.direction).collect();
+
+    visit_strings(metrics, "metrics", &mut |path, s| {
+        let direction: Option<Direction> = serde_yaml::from_str(s).ok();
+        if let Some(direction) = direction {
+            if !directions.contains(&direction) {
+                report.warning(
+                    file,
+                    path,
+                    format!("direction '{}' does not occur on the keyboard", s),
+                );
+            }
+        }
+    });
+}
+
+/// Warn about characters in the metric parameters that are not on `layout`.
+fn check_characters(
+    eval_params: &EvaluationParameters,
+    file: &str,
+    layout: &Layout,
+    report: &mut Report,
+) {
+    let check = |path: &str, c: &char, report: &mut Report| {
+        if layout.get_layerkey_for_symbol(c).is_none() {
+            report.warning(
+                file,
+                path,
+                format!("character '{}' is not on the layout", c),
+            );
+        }
+    };
+
+    let metrics = &eval_params.metrics;
+    if let Some(params) = &metrics.character_constraints {
+        for c in params.params.costs.keys() {
+            let path = format!("metrics.character_constraints.params.costs.{}", c);
+            check(&path, c, report);
+        }
+    }
+    if let Some(params) = &metrics.no_handswitch_after_unbalancing_key {
+        let path = "metrics.no_handswitch_after_unbalancing_key.params.unbalancing_symbols";
+        params
+            .params
+            .unbalancing_symbols
+            .iter()
+            .for_each(|c| check(path, c, report));
+    }
+    if let Some(params) = &metrics.layout_similarity {
+        let path = "metrics.layout_similarity.params.key_weights";
+        params
+            .params
+            .key_weights
+            .keys()
+            .for_each(|c| check(path, c, report));
+    }
+}
+
+/// Check the parts that optimization configuration files have in common.
+fn check_optimization_params(
+    file: &str,
+    linked_pairs: &[(char, char)],
+    weight_schedules: &WeightSchedules,
+    layout: Option<&Layout>,
+    metric_names: Option<&[&str]>,
+    report: &mut Report,
+) {
+    if let Some(layout) = layout {
+        for (c1, c2) in linked_pairs.iter() {
+            for c in [c1, c2] {
+                if layout.get_layerkey_for_symbol(c).is_none() {
+                    report.error(
+                        file,
+                        "linked_pairs",
+                        format!("character '{}' is not on the layout", c),
+                    );
+                }
+            }
+        }
+    }
+
+    if let Some(metric_names) = metric_names {
+        for name in weight_schedules.metric_names() {
+            if !metric_names.contains(&name) {
+                report.error(
+                    file,
+                    &format!("weight_schedules.{}", name),
+                    format!(
+                        "unknown (or disabled) metric '{}' (enabled metrics: {})",
+                        name,
+                        metric_names.join(", ")
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// Check that the weighted corpora are corpora of the evaluation.
+fn check_corpus_weights(
+    file: &str,
+    corpus_weights: &CorpusWeights,
+    corpus_names: &[&str],
+    report: &mut Report,
+) {
+    for name in corpus_weights.corpus_names() {
+        if !corpus_names.contains(&name) {
+            report.error(
+                file,
+                &format!("corpus_weights.{}", name),
+                format!(
+                    "unknown corpus '{}' (corpora of the evaluation: {})",
+                    name,
+                    corpus_names.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+fn main() {
+    dotenv::dotenv().ok();
+    let options = Options::parse();
+    env_logger::init();
+    let common_options = &options.evaluation_parameters;
+    let mut report = Report::default();
+
+    let layout_file = &common_options.layout_config;
+    let keyboard = check_layout_config(layout_file, common_options.strict, &mut report);
+    let layout_generator: Option<Box<dyn LayoutGenerator>> = keyboard.as_ref().map(|_| {
+        // misaligned rows have already been reported
+        common::init_layout_generator(layout_file, common_options.grouped_layout_generator, false)
+    });
+
+    let eval_file = &common_options.eval_parameters;
+    let eval_params = check_eval_params(eval_file, &mut report);
+    if let (Some(keyboard), Ok(content)) = (&keyboard, fs::read_to_string(eval_file)) {
+        check_directions(&content, eval_file, keyboard, &mut report);
+    }
+
+    if let (Some(eval_params), Some(keyboard)) = (&eval_params, &keyboard) {
+        for (path, MatrixPosition(column, row)) in eval_params.unmatched_positions(keyboard) {
+            report.add(
+                severity(common_options.strict),
+                eval_file,
+                &path,
+                format!(
+                    "matrix position [{}, {}] is not on the keyboard",
+                    column, row
+                ),
+            );
+        }
+    }
+
+    let mut layout = None;
+    let mut baseline_ok = true;
+    if let (Some(eval_params), Some(layout_generator)) = (&eval_params, &layout_generator) {
+        if let Some(baseline_layout) = &eval_params.baseline_layout {
+            if let Err(e) = layout_generator.generate(baseline_layout) {
+                baseline_ok = false;
+                report.error(
+                    eval_file,
+                    "baseline_layout",
+                    format!("could not generate layout: {}", e),
+                );
+            }
+        }
+
+        let layout_str = options
+            .layout
+            .as_ref()
+            .or(eval_params.baseline_layout.as_ref());
+        layout = layout_str.and_then(|s| layout_generator.generate(s).ok());
+        match &layout {
+            Some(layout) => check_characters(eval_params, eval_file, layout, &mut report),
+            None => report.warning(
+                eval_file,
+                "",
+                "no (valid) layout given, skipping the checks of characters".to_string(),
+            ),
+        }
+    }
+
+    let corpora = eval_params
+        .as_ref()
+        .map(|p| p.corpora.as_slice())
+        .unwrap_or(&[]);
+    let ngrams_ok = match (&common_options.text, &common_options.corpus) {
+        (Some(_), _) => true,
+        (None, Some(corpus_file)) => read(corpus_file, &mut report).is_some(),
+        (None, None) if !corpora.is_empty() => {
+            corpora.iter().enumerate().fold(true, |ok, (idx, corpus)| {
+                match Corpus::open(&corpus.ngrams) {
+                    Ok(_) => ok,
+                    Err(e) => {
+                        report.error(
+                            eval_file,
+                            &format!("corpora[{}].ngrams", idx),
+                            format!("could not read ngram files '{}': {}", corpus.ngrams, e),
+                        );
+                        false
+                    }
+                }
+            })
+        }
+        (None, None) => match Corpus::open(&common_options.ngrams) {
+            Ok(_) => true,
+            Err(e) => {
+                report.error(
+                    &common_options.ngrams,
+                    "",
+                    format!("could not read ngram files: {}", e),
+                );
+                false
+            }
+        },
+    };
+
+    let sa_params = options.sa_parameters.as_ref().and_then(|file| {
+        let params: SaParameters = check_fields(&read(file, &mut report)?, file, &mut report)?;
+        for result in [
+            params.weight_schedules.validate(),
+            params.corpus_weights.validate(),
+        ] {
+            if let Err(e) = result {
+                report.error(file, "", e.to_string());
+            }
+        }
+        Some((file, params))
+    });
+    let genetic_params = options.genetic_parameters.as_ref().and_then(|file| {
+        let params: GeneticParameters = check_fields(&read(file, &mut report)?, file, &mut report)?;
+        if let Err(e) = params.validate() {
+            report.error(file, "", e.to_string());
+        }
+        Some((file, params))
+    });
+
+    // metric names are only known after creating the metrics (which requires all of the
+    // evaluation's inputs to be valid)
+    let can_evaluate = eval_params.is_some() && baseline_ok && ngrams_ok;
+    let has_schedules = sa_params
+        .iter()
+        .map(|(_, p)| &p.weight_schedules)
+        .chain(genetic_params.iter().map(|(_, p)| &p.weight_schedules))
+        .any(|s| !s.is_empty());
+    let evaluator = match &layout_generator {
+        Some(layout_generator) if has_schedules && can_evaluate => Some(common::init_evaluator(
+            common_options,
+            layout_generator.as_ref(),
+        )),
+        _ => None,
+    };
+    let metric_names = evaluator.as_ref().map(|e| e.metric_names());
+    let corpus_names: Option<Vec<&str>> = eval_params
+        .as_ref()
+        .map(|p| p.corpora.iter().map(|c| c.name.as_str()).collect());
+
+    if let Some((file, params)) = &sa_params {
+        check_optimization_params(
+            file,
+            &params.linked_pairs,
+            &params.weight_schedules,
+            layout.as_ref(),
+            metric_names.as_deref(),
+            &mut report,
+        );
+        if let Some(corpus_names) = &corpus_names {
+            check_corpus_weights(file, &params.corpus_weights, corpus_names, &mut report);
+        }
+    }
+    if let Some((file, params)) = &genetic_params {
+        check_optimization_params(
+            file,
+            &params.linked_pairs,
+            &params.weight_schedules,
+            layout.as_ref(),
+            metric_names.as_deref(),
+            &mut report,
+        );
+        if let Some(corpus_names) = &corpus_names {
+            check_corpus_weights(file, &params.corpus_weights, corpus_names, &mut report);
+        }
+    }
+
+    for finding in report.findings.iter() {
+        println!("{}", finding);
+    }
+    println!(
+        "{} error(s), {} warning(s)",
+        report.n_errors(),
+        report.n_warnings()
+    );
+
+    if report.n_errors() > 0 {
+        process::exit(1);
+    }
+}
@@ -1,6 +1,12 @@
-use keyboard_layout::layout::Layout;
+use keyboard_layout::{layout::Layout, permalink::Permalink};
 use keyboard_layout_optimizer::common;
-use layout_evaluation::{cache::Cache, results::EvaluationResult};
+use layout_evaluation::{
+    cache::Cache,
+    evaluation::{Evaluator, NgramCost},
+    output_style::{set_color_config, ColorConfig},
+    presets::PRESETS,
+    results::{EvaluationResult, MetricType},
+};
 
 use clap::Parser;
 use rayon::prelude::*;
@@ -14,6 +20,20 @@ use std::{
 struct LayoutEvaluation {
     details: EvaluationResult,
     total_cost: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    permalink: Option<String>,
+}
+
+/// A row of the file written with "--dump-costs"
+#[derive(Serialize)]
+struct CostRow<'a> {
+    layout: &'a str,
+    corpus: &'a str,
+    metric_type: MetricType,
+    metric: &'a str,
+    ngram: &'a str,
+    weight: f64,
+    cost: f64,
 }
 
 impl From<EvaluationResult> for LayoutEvaluation {
@@ -22,6 +42,7 @@ impl From<EvaluationResult> for LayoutEvaluation {
         Self {
             details,
             total_cost,
+            permalink: None,
         }
     }
 }
@@ -48,6 +69,10 @@ struct Options {
     #[clap(long)]
     json: bool,
 
+    /// Print results without colors and text decorations (always the case with "--json")
+    #[clap(long)]
+    plain: bool,
+
     /// Print only total costs
     #[clap(long)]
     only_total_costs: bool,
@@ -55,6 +80,71 @@ struct Options {
     /// Sort results by total costs
     #[clap(long)]
     sort: bool,
+
+    /// List metrics in the configured order instead of sorting them by their contribution
+    #[clap(long)]
+    keep_metric_order: bool,
+
+    /// Decode the given permalink token and evaluate the contained layout (can be repeated)
+    #[clap(long)]
+    decode: Vec<String>,
+
+    /// Print a shareable permalink token for each evaluated layout
+    #[clap(long)]
+    encode: bool,
+
+    /// Write the cost of each ngram for each metric of the evaluated layouts to this CSV file
+    #[clap(long)]
+    dump_costs: Option<String>,
+
+    /// Also write ngrams without cost to the "--dump-costs" file
+    #[clap(long)]
+    include_zero: bool,
+
+    /// List the available evaluation presets (to be selected with "preset: <name>" in the
+    /// evaluation parameters) and exit
+    #[clap(long)]
+    list_presets: bool,
+}
+
+/// Write the individual ngram costs of the layouts to a CSV file (row by row, such that large
+/// trigram dumps are not kept in memory).
+fn dump_costs(
+    filename: &str,
+    include_zero: bool,
+    evaluator: &Evaluator,
+    layouts: &[(String, Layout, EvaluationResult)],
+) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_path(filename)?;
+    let mut skipped = Vec::new();
+    for (layout_str, layout, _) in layouts.iter() {
+        let mut result = Ok(());
+        skipped = evaluator.individual_costs(layout, &mut |c: NgramCost| {
+            if result.is_err() || (c.cost == 0.0 && !include_zero) {
+                return;
+            }
+            result = writer.serialize(CostRow {
+                layout: layout_str,
+                corpus: c.corpus,
+                metric_type: c.metric_type,
+                metric: c.metric,
+                ngram: &c.ngram,
+                weight: c.weight,
+                cost: c.cost,
+            });
+        });
+        result?;
+    }
+    writer.flush()?;
+
+    if !skipped.is_empty() {
+        eprintln!(
+            "Skipped metrics without individual ngram costs: {}",
+            skipped.join(", ")
+        );
+    }
+
+    Ok(())
 }
 
 fn main() {
@@ -64,15 +154,31 @@ fn main() {
         // if the "json" option is set, we do not want any other log messages
         env_logger::init();
     }
+    // has to be set before any metric messages are generated
+    let color = if options.json || options.plain {
+        ColorConfig::Never
+    } else {
+        options.general_parameters.color
+    };
+    set_color_config(color);
+
+    if options.list_presets {
+        for preset in PRESETS.iter() {
+            println!("{:<20} {}", preset.name, preset.description);
+        }
+        return;
+    }
 
     let (layout_generator, evaluator) = common::init(&options.general_parameters);
+    let evaluator = evaluator.with_color_config(color);
 
     // collect layout strings to a vec
     let mut layout_strings = options.layout_str.to_vec();
     if let Some(filename) = &options.from_file {
         match File::open(filename) {
             Ok(file) => {
-                layout_strings.append(&mut BufReader::new(file).lines().flatten().collect());
+                layout_strings
+                    .append(&mut BufReader::new(file).lines().map_while(Result::ok).collect());
             }
             Err(e) => {
                 log::error!("Error reading layouts file {}: {:?}", filename, e);
@@ -81,6 +187,29 @@ fn main() {
         }
     }
 
+    let keyboard_name = common::keyboard_name(&options.general_parameters.layout_config);
+
+    // decode permalinks to layout strings
+    for token in options.decode.iter() {
+        let permalink = Permalink::decode(token).unwrap_or_else(|e| {
+            log::error!("Could not decode permalink '{}': {}", token, e);
+            panic!("{:?}", e);
+        });
+        if permalink.keyboard != keyboard_name {
+            log::warn!(
+                "Permalink was created for keyboard '{}', but evaluating with '{}'",
+                permalink.keyboard,
+                keyboard_name
+            );
+        }
+        // check the higher layers against the current layout config
+        if let Err(e) = permalink.generate_layout(layout_generator.as_ref()) {
+            log::error!("Error in generating layout from permalink: {:?}", e);
+            panic!("{:?}", e);
+        }
+        layout_strings.push(permalink.layout_str());
+    }
+
     let result_cache: Cache<EvaluationResult> = Cache::new();
 
     // evaluate layouts
@@ -98,8 +227,11 @@ fn main() {
                     panic!("{:?}", e);
                 }
             };
-            let evaluation_result =
+            let mut evaluation_result =
                 result_cache.get_or_insert_with(&layout_str, || evaluator.evaluate_layout(&layout));
+            if !options.keep_metric_order {
+                evaluation_result.sort_by_contribution();
+            }
             (layout_str, layout, evaluation_result)
         })
         .collect();
@@ -111,17 +243,54 @@ fn main() {
         });
     }
 
+    if let Some(filename) = &options.dump_costs {
+        if let Err(e) = dump_costs(filename, options.include_zero, &evaluator, &results) {
+            log::error!("Could not write costs to {}: {}", filename, e);
+            panic!("{:?}", e);
+        }
+    }
+
+    let permalink = |layout: &Layout| -> Option<String> {
+        if !options.encode {
+            return None;
+        }
+        match Permalink::from_layout(&keyboard_name, layout).encode() {
+            Ok(token) => Some(token),
+            Err(e) => {
+                log::error!("Could not encode permalink: {}", e);
+                None
+            }
+        }
+    };
+
     // print results
     if options.json {
-        let results: Vec<LayoutEvaluation> =
-            results.into_iter().map(|(_, _, res)| res.into()).collect();
+        let results: Vec<LayoutEvaluation> = results
+            .into_iter()
+            .map(|(_, layout, res)| {
+                let mut evaluation: LayoutEvaluation = res.into();
+                evaluation.permalink = permalink(&layout);
+                evaluation
+            })
+            .collect();
         println!("{}", serde_json::to_string(&results).unwrap());
     } else {
         for (layout_str, layout, evaluation_result) in results {
+            let token = permalink(&layout);
             if !options.only_total_costs {
                 println!("Layout (layer 1):\n{}", layout.plot_layer(0));
                 println!("Layout string (layer 1):\n{}\n", layout);
+                if let Some(token) = token {
+                    println!("Permalink:\n{}\n", token);
+                }
                 println!("{}", evaluation_result);
+            } else if let Some(token) = token {
+                println!(
+                    "{} {:4.2} {}",
+                    layout_str,
+                    evaluation_result.total_cost(),
+                    token
+                );
             } else {
                 println!("{} {:4.2}", layout_str, evaluation_result.total_cost());
             }
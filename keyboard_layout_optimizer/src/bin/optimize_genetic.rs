@@ -1,9 +1,9 @@
 use keyboard_layout_optimizer::common;
-use layout_evaluation::cache::Cache;
+use layout_evaluation::{cache::Cache, metrics::worst_ngrams, output_style::set_color_config};
 use layout_optimization_genetic::optimization;
 
 use clap::Parser;
-use std::{env, process};
+use std::{env, process, sync::Arc};
 
 #[derive(Parser, Debug)]
 #[clap(name = "Keyboard layout optimization - Genetic Algorithm")]
@@ -51,6 +51,9 @@ struct Options {
     /// Publishing options
     #[clap(flatten)]
     publishing_options: common::PublishingOptions,
+
+    #[clap(flatten)]
+    progress_options: common::ProgressOptions,
 }
 
 fn main() {
@@ -75,6 +78,7 @@ fn main() {
     .expect("Error setting Ctrl-C handler");
 
     let options = Options::parse();
+    set_color_config(options.evaluation_parameters.color);
 
     let fix_from: String = options
         .fix_from
@@ -88,15 +92,15 @@ fn main() {
             .collect::<String>()
     });
 
-    let (layout_generator, evaluator) = common::init(&options.evaluation_parameters);
+    let (layout_generator, mut evaluator) = common::init(&options.evaluation_parameters);
 
     let mut optimization_params = optimization::Parameters::from_yaml(
         &options.optimization_parameters,
     )
-    .unwrap_or_else(|_| {
+    .unwrap_or_else(|e| {
         panic!(
-            "Could not read optimization parameters from {}.",
-            &options.optimization_parameters
+            "Could not read optimization parameters from {}: {}",
+            &options.optimization_parameters, e
         )
     });
 
@@ -104,6 +108,13 @@ fn main() {
         optimization_params.generation_limit = generation_limit
     }
 
+    // the final evaluation uses the end weights of the weight schedules
+    optimization_params
+        .weight_schedules
+        .apply_end_weights(&mut evaluator);
+    optimization_params.corpus_weights.apply(&mut evaluator);
+    let evaluator = Arc::new(evaluator);
+
     let fix_from = start_layout.as_ref().unwrap_or(&fix_from).to_string();
 
     loop {
@@ -115,8 +126,15 @@ fn main() {
             &options.fix.clone().unwrap_or_default(),
             start_layout.is_some(),
             !options.no_cache_results,
+            common::init_progress_reporter(
+                &options.progress_options,
+                &evaluator,
+                layout_generator.as_ref(),
+            ),
         );
+        worst_ngrams::begin_final_evaluation();
         let evaluation_result = evaluator.evaluate_layout(&layout);
+        worst_ngrams::end_final_evaluation();
         let cost = evaluation_result.total_cost();
         let _ = final_results.get_or_insert_with(&layout_str, || cost);
 
@@ -133,13 +151,15 @@ fn main() {
 
         // Publish to webservice.
         let o = &options.publishing_options;
-        if o.publish_as.is_some() && cost < o.publish_if_cost_below.unwrap_or(f64::INFINITY) {
-            common::publish_to_webservice(
-                &layout_str,
-                o.publish_as.as_ref().unwrap(),
-                &o.publish_to,
-                &o.publish_layout_config,
-            );
+        if let Some(publish_name) = &o.publish_as {
+            if cost < o.publish_if_cost_below.unwrap_or(f64::INFINITY) {
+                common::publish_to_webservice(
+                    &layout_str,
+                    publish_name,
+                    &o.publish_to,
+                    &o.publish_layout_config,
+                );
+            }
         }
 
         if !options.run_forever {
@@ -1,11 +1,11 @@
 use keyboard_layout_optimizer::common;
-use layout_evaluation::cache::Cache;
+use layout_evaluation::{cache::Cache, metrics::worst_ngrams, output_style::set_color_config};
 use layout_optimization_sa::optimization;
 
 use clap::Parser;
 use colored::Colorize;
 use rayon::iter::{ParallelBridge, ParallelIterator};
-use std::{env, process};
+use std::{env, process, sync::Arc};
 
 #[derive(Parser, Debug)]
 #[clap(name = "Keyboard layout optimization - Simulated Annealing")]
@@ -61,6 +61,9 @@ struct Options {
     /// Publishing options
     #[clap(flatten)]
     publishing_options: common::PublishingOptions,
+
+    #[clap(flatten)]
+    progress_options: common::ProgressOptions,
 }
 
 /// An iterator for layouts to feed into the optimizer.
@@ -128,6 +131,7 @@ fn main() {
     .expect("Error setting Ctrl-C handler");
 
     let options = Options::parse();
+    set_color_config(options.evaluation_parameters.color);
 
     let fix_from: String = options
         .fix_from
@@ -145,7 +149,7 @@ fn main() {
         })
         .collect();
 
-    let (layout_generator, evaluator) = common::init(&options.evaluation_parameters);
+    let (layout_generator, mut evaluator) = common::init(&options.evaluation_parameters);
 
     let mut optimization_params = optimization::Parameters::from_yaml(
         &options.optimization_parameters,
@@ -163,6 +167,13 @@ fn main() {
     }
     optimization_params.correct_init_temp();
 
+    // the final evaluation uses the end weights of the weight schedules
+    optimization_params
+        .weight_schedules
+        .apply_end_weights(&mut evaluator);
+    optimization_params.corpus_weights.apply(&mut evaluator);
+    let evaluator = Arc::new(evaluator);
+
     let mut layouts: Vec<String> = start_layouts.to_vec();
     if layouts.is_empty() {
         layouts = vec![fix_from];
@@ -205,8 +216,15 @@ fn main() {
                 options.log_everything,
                 cache.clone(),
                 None,
+                common::init_progress_reporter(
+                    &options.progress_options,
+                    &evaluator,
+                    layout_generator.as_ref(),
+                ),
             );
+            worst_ngrams::begin_final_evaluation();
             let evaluation_result = evaluator.evaluate_layout(&layout);
+            worst_ngrams::end_final_evaluation();
             let cost = evaluation_result.total_cost();
             let _ = final_results.get_or_insert_with(&layout_str, || cost);
 
@@ -229,13 +247,15 @@ fn main() {
 
             // Publish to webservice.
             let o = &options.publishing_options;
-            if o.publish_as.is_some() && cost < o.publish_if_cost_below.unwrap_or(f64::INFINITY) {
-                common::publish_to_webservice(
-                    &layout_str,
-                    o.publish_as.as_ref().unwrap(),
-                    &o.publish_to,
-                    &o.publish_layout_config,
-                );
+            if let Some(publish_name) = &o.publish_as {
+                if cost < o.publish_if_cost_below.unwrap_or(f64::INFINITY) {
+                    common::publish_to_webservice(
+                        &layout_str,
+                        publish_name,
+                        &o.publish_to,
+                        &o.publish_layout_config,
+                    );
+                }
             }
         });
 }
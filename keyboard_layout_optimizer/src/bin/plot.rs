@@ -31,8 +31,11 @@ fn main() {
         .chars()
         .filter(|c| options.do_not_remove_whitespace || !c.is_whitespace())
         .collect();
-    let layout_generator =
-        common::init_layout_generator(&options.layout_config, options.grouped_layout_generator);
+    let layout_generator = common::init_layout_generator(
+        &options.layout_config,
+        options.grouped_layout_generator,
+        false,
+    );
 
     let layout = match layout_generator.generate(&layout_str) {
         Ok(layout) => layout,
@@ -2,6 +2,7 @@ use clap::Parser;
 use rand::{self, seq::SliceRandom};
 
 use keyboard_layout_optimizer::common;
+use layout_evaluation::output_style::set_color_config;
 
 #[derive(Parser, Debug)]
 #[clap(name = "Random keyboard layout evaluation")]
@@ -18,6 +19,7 @@ fn main() {
     dotenv::dotenv().ok();
     env_logger::init();
     let options = Options::parse();
+    set_color_config(options.evaluation_parameters.color);
 
     let (layout_generator, evaluator) = common::init(&options.evaluation_parameters);
 
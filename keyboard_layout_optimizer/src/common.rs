@@ -1,13 +1,17 @@
 use keyboard_layout::{
-    config::LayoutConfig, grouped_layout_generator::GroupedLayoutGenerator, keyboard::Keyboard,
-    layout_generator::LayoutGenerator, neo_layout_generator::NeoLayoutGenerator,
+    config::LayoutConfig, grouped_layout_generator::GroupedLayoutGenerator, key::MatrixPosition,
+    keyboard::Keyboard, layout_generator::LayoutGenerator,
+    neo_layout_generator::NeoLayoutGenerator,
 };
 use layout_evaluation::{
     config::EvaluationParameters,
+    corpus::Corpus,
     evaluation::Evaluator,
-    ngram_mapper::on_demand_ngram_mapper::OnDemandNgramMapper,
-    ngrams::{Bigrams, Trigrams, Unigrams},
+    ngram_mapper::{on_demand_ngram_mapper::OnDemandNgramMapper, NgramMapper},
+    ngrams::{Bigrams, NgramsConfig, Trigrams, Unigrams},
+    output_style::ColorConfig,
 };
+use layout_optimization_common::progress::{JsonlObserver, LogObserver, ProgressReporter};
 
 use ahash::AHashMap;
 use clap::Parser;
@@ -64,6 +68,15 @@ pub struct CommonOptions {
     /// Interpred given layout string using the "grouped" logic
     #[clap(long)]
     pub grouped_layout_generator: bool,
+
+    /// When to use colors in the output: always, never, or auto (if stdout is a terminal)
+    #[clap(long, default_value = "auto")]
+    pub color: ColorConfig,
+
+    /// Fail on likely mistakes in the configuration files (e.g. key costs or metric parameters
+    /// for matrix positions the keyboard does not have) instead of warning about them
+    #[clap(long)]
+    pub strict: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -87,19 +100,75 @@ pub struct PublishingOptions {
     pub publish_to: String,
 }
 
+#[derive(Parser, Debug)]
+#[clap(name = "Optimization progress reports")]
+pub struct ProgressOptions {
+    /// Report the best layout found so far (with some statistics) every N iterations
+    #[clap(long)]
+    pub report_every: Option<u64>,
+
+    /// Append the progress reports as JSON lines to this file
+    #[clap(long, requires = "report_every")]
+    pub report_to: Option<String>,
+}
+
+/// Create a progress reporter according to the given options (if reports are requested).
+pub fn init_progress_reporter(
+    options: &ProgressOptions,
+    evaluator: &Arc<Evaluator>,
+    layout_generator: &dyn LayoutGenerator,
+) -> Option<ProgressReporter> {
+    let interval = options.report_every?;
+    let mut reporter =
+        ProgressReporter::new(interval, evaluator.clone(), layout_generator.clone_box())
+            .with_observer(Box::new(LogObserver));
+    if let Some(filename) = &options.report_to {
+        match JsonlObserver::new(filename) {
+            Ok(observer) => reporter = reporter.with_observer(Box::new(observer)),
+            Err(e) => log::error!("Could not open progress report file {}: {}", filename, e),
+        }
+    }
+
+    Some(reporter)
+}
+
 pub fn init(options: &CommonOptions) -> (Box<dyn LayoutGenerator>, Evaluator) {
-    (
-        init_layout_generator(&options.layout_config, options.grouped_layout_generator),
-        init_evaluator(options),
-    )
+    let layout_generator = init_layout_generator(
+        &options.layout_config,
+        options.grouped_layout_generator,
+        options.strict,
+    );
+    let evaluator = init_evaluator(options, layout_generator.as_ref());
+
+    (layout_generator, evaluator)
+}
+
+/// Log likely mistakes found in a configuration file, or panic if `strict` is set.
+fn report_config_warnings(filename: &str, warnings: &[String], strict: bool) {
+    if strict && !warnings.is_empty() {
+        panic!(
+            "Configuration file {} is likely wrong:\n{}",
+            filename,
+            warnings.join("\n")
+        );
+    }
+    for warning in warnings {
+        log::warn!("{}: {}", filename, warning);
+    }
 }
 
 pub fn init_layout_generator(
-    layout_config: &str,
+    layout_config_file: &str,
     grouped_layout_generator: bool,
+    strict: bool,
 ) -> Box<dyn LayoutGenerator> {
-    let layout_config = LayoutConfig::from_yaml(layout_config)
-        .unwrap_or_else(|e| panic!("Could not load config file {}: {}", layout_config, e));
+    let layout_config = LayoutConfig::from_yaml(layout_config_file)
+        .unwrap_or_else(|e| panic!("Could not load config file {}: {}", layout_config_file, e));
+    report_config_warnings(
+        layout_config_file,
+        &layout_config.keyboard.misaligned_rows(),
+        strict,
+    );
 
     let keyboard = Arc::new(Keyboard::from_yaml_object(layout_config.keyboard));
     log::info!("A-priori estimations from key_costs:");
@@ -132,7 +201,10 @@ pub fn init_layout_generator(
     }
 }
 
-pub fn init_evaluator(options: &CommonOptions) -> Evaluator {
+pub fn init_evaluator(
+    options: &CommonOptions,
+    layout_generator: &dyn LayoutGenerator,
+) -> Evaluator {
     let eval_params =
         EvaluationParameters::from_yaml(&options.eval_parameters).unwrap_or_else(|e| {
             panic!(
@@ -140,6 +212,21 @@ pub fn init_evaluator(options: &CommonOptions) -> Evaluator {
                 options.eval_parameters, e
             )
         });
+    let unmatched_positions: Vec<String> = eval_params
+        .unmatched_positions(layout_generator.keyboard())
+        .into_iter()
+        .map(|(path, MatrixPosition(column, row))| {
+            format!(
+                "{}: matrix position [{}, {}] is not on the keyboard",
+                path, column, row
+            )
+        })
+        .collect();
+    report_config_warnings(
+        &options.eval_parameters,
+        &unmatched_positions,
+        options.strict,
+    );
 
     let text = options.text.as_ref().cloned().or_else(|| {
         options.corpus.as_ref().map(|corpus_file| {
@@ -158,7 +245,18 @@ pub fn init_evaluator(options: &CommonOptions) -> Evaluator {
         ngrams_config.increase_common_ngrams.enabled = false;
     }
 
-    let (mut unigrams, mut bigrams, mut trigrams) = match text {
+    let load = |ngrams: (Unigrams, Bigrams, Trigrams)| -> Box<dyn NgramMapper> {
+        let (unigrams, bigrams, trigrams) = prepare_ngrams(ngrams, options, &ngrams_config);
+        Box::new(OnDemandNgramMapper::with_ngrams(
+            unigrams,
+            bigrams,
+            trigrams,
+            ngram_mapper_config.clone(),
+        ))
+    };
+
+    // the corpora of the configuration apply unless a text or corpus file is given explicitly
+    let evaluator = match text {
         Some(txt) => {
             let unigrams =
                 Unigrams::from_text(&txt).expect("Could not generate unigrams from text.");
@@ -166,25 +264,70 @@ pub fn init_evaluator(options: &CommonOptions) -> Evaluator {
             let trigrams =
                 Trigrams::from_text(&txt).expect("Could not generate trigrams from text.");
 
-            (unigrams, bigrams, trigrams)
+            Evaluator::default(load((unigrams, bigrams, trigrams)))
+        }
+        None if !eval_params.corpora.is_empty() => {
+            let corpora = eval_params
+                .corpora
+                .iter()
+                .map(|corpus| {
+                    (
+                        corpus.name.clone(),
+                        corpus.weight,
+                        load(read_ngrams(&corpus.ngrams)),
+                    )
+                })
+                .collect();
+
+            Evaluator::with_corpora(corpora)
+        }
+        None => Evaluator::default(load(read_ngrams(&options.ngrams))),
+    }
+    .default_metrics(&eval_params.metrics)
+    .precompute(layout_generator.keyboard())
+    .with_color_config(options.color);
+
+    match &eval_params.baseline_layout {
+        Some(baseline_layout) => {
+            let layout = layout_generator
+                .generate(baseline_layout)
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "Could not generate baseline layout '{}': {:?}",
+                        baseline_layout, e
+                    )
+                });
+            log::info!("Evaluating baseline layout '{}'", baseline_layout);
+            evaluator.with_baseline(&layout)
         }
         None => {
-            let p = Path::new(&options.ngrams).join("1-grams.txt");
-            log::info!("Reading unigram file: '{:?}'", p);
-            let unigrams = Unigrams::from_file(p.to_str().unwrap())
-                .unwrap_or_else(|_| panic!("Could not read 1-gramme file from '{:?}'.", &p));
-            let p = Path::new(&options.ngrams).join("2-grams.txt");
-            log::info!("Reading bigram file: '{:?}'", p);
-            let bigrams = Bigrams::from_file(p.to_str().unwrap())
-                .unwrap_or_else(|_| panic!("Could not read 2-gramme file from '{:?}'.", &p));
-            let p = Path::new(&options.ngrams).join("3-grams.txt");
-            log::info!("Reading trigram file: '{:?}'", p);
-            let trigrams = Trigrams::from_file(p.to_str().unwrap())
-                .unwrap_or_else(|_| panic!("Could not read 3-gramme file from '{:?}'.", &p));
-
-            (unigrams, bigrams, trigrams)
+            if evaluator.uses_baseline() {
+                log::warn!(
+                    "Metrics use baseline normalization, but no baseline_layout is configured. \
+                    Falling back to fixed normalization."
+                );
+            }
+            evaluator
         }
-    };
+    }
+}
+
+/// Read the ngram files from the directory `ngrams`.
+fn read_ngrams(ngrams: &str) -> (Unigrams, Bigrams, Trigrams) {
+    log::info!("Reading ngram files from: '{}'", ngrams);
+    let corpus = Corpus::open(ngrams)
+        .unwrap_or_else(|e| panic!("Could not read ngram files from '{}': {:?}", ngrams, e));
+
+    corpus.to_ngrams()
+}
+
+/// Apply the filters of the command line options and the ngram configuration to `ngrams`.
+fn prepare_ngrams(
+    ngrams: (Unigrams, Bigrams, Trigrams),
+    options: &CommonOptions,
+    ngrams_config: &NgramsConfig,
+) -> (Unigrams, Bigrams, Trigrams) {
+    let (mut unigrams, mut bigrams, mut trigrams) = ngrams;
 
     if let Some(exclude_chars) = &options.exclude_chars {
         for exclude_char in exclude_chars.chars() {
@@ -206,10 +349,15 @@ pub fn init_evaluator(options: &CommonOptions) -> Evaluator {
         trigrams = trigrams.tops(tops);
     }
 
-    let ngram_provider =
-        OnDemandNgramMapper::with_ngrams(unigrams, bigrams, trigrams, ngram_mapper_config);
+    (unigrams, bigrams, trigrams)
+}
 
-    Evaluator::default(Box::new(ngram_provider)).default_metrics(&eval_params.metrics)
+/// Name of the keyboard used in layout permalinks (the file stem of the layout config).
+pub fn keyboard_name(layout_config: &str) -> String {
+    Path::new(layout_config)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| layout_config.to_string())
 }
 
 /// Appends a layout-string to a file.
@@ -0,0 +1,258 @@
+//! The `config_check` module validates configuration files without running an evaluation or
+//! optimization. It reports findings with the file and the key path they refer to.
+//!
+//! The configuration structs accept (and silently ignore) unknown fields, which makes typos in
+//! the deeply nested metric parameters easy to miss. [`check_fields`] detects them by
+//! replacing the value of each key with values no field could accept: if the configuration
+//! still deserializes, the key is ignored and therefore unknown. Unlike serde's
+//! `deny_unknown_fields`, this also covers structs with flattened fields.
+
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+
+/// Severity of a [`Finding`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found in a configuration file
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub severity: Severity,
+    pub file: String,
+    /// Path of the offending key, e.g. "metrics.sfb.params.costs" (empty for the whole file)
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}: {}: {}", self.severity, self.file, self.message)
+        } else {
+            write!(
+                f,
+                "{}: {}: {}: {}",
+                self.severity, self.file, self.path, self.message
+            )
+        }
+    }
+}
+
+/// Collection of the findings of a check
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    pub fn add(&mut self, severity: Severity, file: &str, path: &str, message: String) {
+        self.findings.push(Finding {
+            severity,
+            file: file.to_string(),
+            path: path.to_string(),
+            message,
+        });
+    }
+
+    pub fn error(&mut self, file: &str, path: &str, message: String) {
+        self.add(Severity::Error, file, path, message);
+    }
+
+    pub fn warning(&mut self, file: &str, path: &str, message: String) {
+        self.add(Severity::Warning, file, path, message);
+    }
+
+    pub fn n_errors(&self) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+            .count()
+    }
+
+    pub fn n_warnings(&self) -> usize {
+        self.findings.len() - self.n_errors()
+    }
+}
+
+/// Path of the child `key` of `path`
+pub fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Printable name of a mapping key
+fn key_name(key: &Value) -> String {
+    match key {
+        Value::String(s) => s.clone(),
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        other => serde_yaml::to_string(other)
+            .map(|s| s.trim().replace('\n', " "))
+            .unwrap_or_else(|_| format!("{:?}", other)),
+    }
+}
+
+/// Replace `null` keys (the placeholders of empty parameters, which deserialize from a string
+/// but not from a [`Value`]) by the string "null".
+fn normalize(value: Value) -> Value {
+    match value {
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .into_iter()
+                .map(|(k, v)| {
+                    let k = match k {
+                        Value::Null => Value::String("null".to_string()),
+                        k => k,
+                    };
+                    (k, normalize(v))
+                })
+                .collect(),
+        ),
+        Value::Sequence(seq) => Value::Sequence(seq.into_iter().map(normalize).collect()),
+        other => other,
+    }
+}
+
+fn deserializes<T: DeserializeOwned>(config: &Value) -> bool {
+    serde_yaml::from_value::<T>(config.clone()).is_ok()
+}
+
+/// Location of a value within a configuration
+#[derive(Clone, Copy)]
+enum Step<'a> {
+    Key(&'a Value),
+    Index(usize),
+}
+
+fn get_mut<'a>(value: &'a mut Value, steps: &[Step]) -> Option<&'a mut Value> {
+    steps.iter().try_fold(value, |value, step| match step {
+        Step::Key(key) => value.as_mapping_mut()?.get_mut(*key),
+        Step::Index(idx) => value.as_sequence_mut()?.get_mut(*idx),
+    })
+}
+
+/// Whether `T` ignores the value at `steps`, i.e. whether the configuration deserializes with
+/// any value there.
+fn is_ignored<T: DeserializeOwned>(config: &Value, steps: &[Step]) -> bool {
+    let probes = [
+        Value::String("\u{0}check_config".to_string()),
+        Value::Sequence(vec![Value::Mapping(Mapping::new())]),
+    ];
+
+    probes.iter().all(|probe| {
+        let mut probed = config.clone();
+        match get_mut(&mut probed, steps) {
+            Some(value) => *value = probe.clone(),
+            None => return false,
+        }
+        deserializes::<T>(&probed)
+    })
+}
+
+/// Report the keys of `value` (located at `steps` of `config`) that `T` ignores.
+fn find_unknown_fields<'a, T: DeserializeOwned>(
+    config: &Value,
+    value: &'a Value,
+    steps: &mut Vec<Step<'a>>,
+    path: &str,
+    file: &str,
+    report: &mut Report,
+) {
+    match value {
+        Value::Mapping(mapping) => {
+            for (key, child) in mapping.iter() {
+                let name = key_name(key);
+                // YAML anchors and placeholders of empty parameters are expected to be ignored
+                let is_anchor = steps.is_empty() && name.starts_with('_');
+                let is_placeholder = name == "null" && child.is_null();
+                if is_anchor || is_placeholder {
+                    continue;
+                }
+
+                let child_path = child_path(path, &name);
+                steps.push(Step::Key(key));
+                if is_ignored::<T>(config, steps) {
+                    report.error(file, &child_path, "unknown field".to_string());
+                } else {
+                    find_unknown_fields::<T>(config, child, steps, &child_path, file, report);
+                }
+                steps.pop();
+            }
+        }
+        Value::Sequence(seq) => {
+            for (idx, child) in seq.iter().enumerate() {
+                steps.push(Step::Index(idx));
+                let child_path = format!("{}[{}]", path, idx);
+                find_unknown_fields::<T>(config, child, steps, &child_path, file, report);
+                steps.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Deserialize `T` from the configuration `config_str` (of `file`), reporting errors and
+/// unknown fields. Returns the configuration if it could be deserialized.
+pub fn check_fields<T: DeserializeOwned>(
+    config_str: &str,
+    file: &str,
+    report: &mut Report,
+) -> Option<T> {
+    let parsed = match serde_yaml::from_str::<T>(config_str) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            report.error(file, "", e.to_string());
+            return None;
+        }
+    };
+
+    let config = serde_yaml::from_str::<Value>(config_str)
+        .ok()
+        .map(normalize)
+        .filter(deserializes::<T>);
+    match config {
+        Some(config) => {
+            find_unknown_fields::<T>(&config, &config, &mut Vec::new(), "", file, report)
+        }
+        None => report.warning(file, "", "could not check for unknown fields".to_string()),
+    }
+
+    Some(parsed)
+}
+
+/// Call `visit` with the path and value of all strings (keys and values) within `value`.
+pub fn visit_strings(value: &Value, path: &str, visit: &mut dyn FnMut(&str, &str)) {
+    match value {
+        Value::Mapping(mapping) => {
+            for (key, child) in mapping.iter() {
+                let child_path = child_path(path, &key_name(key));
+                visit_strings(key, &child_path, visit);
+                visit_strings(child, &child_path, visit);
+            }
+        }
+        Value::Sequence(seq) => {
+            for (idx, child) in seq.iter().enumerate() {
+                visit_strings(child, &format!("{}[{}]", path, idx), visit);
+            }
+        }
+        Value::String(s) => visit(path, s),
+        _ => {}
+    }
+}
@@ -0,0 +1,113 @@
+//! Cloning an [`Evaluator`] with the metrics of `config/evaluation/sval.yml`, as the optimizers
+//! do for each run. Besides the time, the heap allocations of a clone are counted, once with
+//! and once without the metrics (the difference is due to the metrics).
+
+use keyboard_layout::{
+    config::LayoutConfig, keyboard::Keyboard, layout_generator::LayoutGenerator,
+    neo_layout_generator::NeoLayoutGenerator,
+};
+use layout_evaluation::{
+    config::EvaluationParameters,
+    evaluation::Evaluator,
+    ngram_mapper::on_demand_ngram_mapper::OnDemandNgramMapper,
+    ngrams::{Bigrams, Trigrams, Unigrams},
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+const LAYOUT_CONFIG: &str = "../config/keyboard/sval.yml";
+const EVALUATION_PARAMETERS: &str = "../config/evaluation/sval.yml";
+const TEXT: &str = "the quick brown fox jumps over the lazy dog";
+
+/// The system allocator, counting the allocations and allocated bytes
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Number of allocations and allocated bytes of `f`
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (usize, usize) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let result = f();
+    let counts = (
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_bytes,
+    );
+    drop(result);
+
+    counts
+}
+
+fn evaluator(keyboard: &Keyboard, with_metrics: bool) -> Evaluator {
+    let eval_params = EvaluationParameters::from_yaml(EVALUATION_PARAMETERS).unwrap_or_else(|e| {
+        panic!(
+            "Could not read evaluation parameters '{}': {:?}",
+            EVALUATION_PARAMETERS, e
+        )
+    });
+
+    let ngram_mapper = OnDemandNgramMapper::with_ngrams(
+        Unigrams::from_text(TEXT).unwrap(),
+        Bigrams::from_text(TEXT).unwrap(),
+        Trigrams::from_text(TEXT).unwrap(),
+        eval_params.ngram_mapper,
+    );
+    let evaluator = Evaluator::default(Box::new(ngram_mapper));
+    if with_metrics {
+        evaluator
+            .default_metrics(&eval_params.metrics)
+            .precompute(keyboard)
+    } else {
+        evaluator
+    }
+}
+
+pub fn clone_metrics_bench(c: &mut Criterion) {
+    let layout_config = LayoutConfig::from_yaml(LAYOUT_CONFIG)
+        .unwrap_or_else(|e| panic!("Could not load config file '{}': {}", LAYOUT_CONFIG, e));
+    let keyboard = Arc::new(Keyboard::from_yaml_object(layout_config.keyboard));
+    let layout_generator = NeoLayoutGenerator::from_object(layout_config.base_layout, keyboard);
+
+    let evaluator_with_metrics = evaluator(layout_generator.keyboard(), true);
+    let evaluator_without_metrics = evaluator(layout_generator.keyboard(), false);
+
+    let (allocations, allocated_bytes) = count_allocations(|| evaluator_with_metrics.clone());
+    let (corpus_allocations, corpus_allocated_bytes) =
+        count_allocations(|| evaluator_without_metrics.clone());
+    println!(
+        "Cloning the metrics ({}): {} allocations, {} bytes",
+        evaluator_with_metrics.metric_names().len(),
+        allocations - corpus_allocations,
+        allocated_bytes - corpus_allocated_bytes
+    );
+
+    c.bench_function("clone_metrics", |b| {
+        b.iter(|| evaluator_with_metrics.clone());
+    });
+}
+
+criterion_group!(benches, clone_metrics_bench);
+criterion_main!(benches);
@@ -0,0 +1,103 @@
+//! Evaluation of a layout with and without the precomputed key-pair tables of the bigram
+//! metrics (see `BigramMetric::precompute`): the full evaluation (i.e. the optimizer
+//! throughput) and the precomputing metrics alone.
+
+use keyboard_layout::{
+    config::LayoutConfig, keyboard::Keyboard, layout::LayerKey, layout_generator::LayoutGenerator,
+    neo_layout_generator::NeoLayoutGenerator,
+};
+use layout_evaluation::{
+    config::EvaluationParameters,
+    corpus::Corpus,
+    evaluation::Evaluator,
+    metrics::bigram_metrics::{fsb::Fsb, hsb::Hsb, sfb::Sfb, BigramMetric},
+    ngram_mapper::on_demand_ngram_mapper::OnDemandNgramMapper,
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::sync::Arc;
+
+const NGRAMS: &str = "../ngrams/deu_mixed_wiki_web_0.6_eng_news_typical_wiki_web_0.4";
+const LAYOUT_CONFIG: &str = "../config/keyboard/sval.yml";
+const EVALUATION_PARAMETERS: &str = "../config/evaluation/sval.yml";
+const LAYOUT: &str = "?öcäb-üiqy')ezo.,a(umkhjlg{txdw□n}pv□s□fr";
+const N_BIGRAMS: usize = 1_000_000;
+
+/// A deterministic pseudo-random sequence (xorshift), so that all runs use the same ngrams
+fn pseudo_random(mut state: u64) -> impl Iterator<Item = u64> {
+    std::iter::repeat_with(move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    })
+}
+
+pub fn precompute_bench(c: &mut Criterion) {
+    let layout_config = LayoutConfig::from_yaml(LAYOUT_CONFIG)
+        .unwrap_or_else(|e| panic!("Could not load config file '{}': {}", LAYOUT_CONFIG, e));
+    let keyboard = Arc::new(Keyboard::from_yaml_object(layout_config.keyboard));
+    let layout_generator = NeoLayoutGenerator::from_object(layout_config.base_layout, keyboard);
+
+    let eval_params = EvaluationParameters::from_yaml(EVALUATION_PARAMETERS).unwrap_or_else(|e| {
+        panic!(
+            "Could not read evaluation yaml file '{}': {}",
+            EVALUATION_PARAMETERS, e
+        )
+    });
+
+    let layout = layout_generator
+        .generate(LAYOUT)
+        .unwrap_or_else(|e| panic!("Could not generate layout: {:?}", e));
+
+    let corpus = Corpus::open(NGRAMS)
+        .unwrap_or_else(|e| panic!("Could not open the ngrams in '{}': {}", NGRAMS, e));
+    let (unigrams, bigrams, trigrams) = corpus.to_ngrams();
+    let ngram_provider =
+        OnDemandNgramMapper::with_ngrams(unigrams, bigrams, trigrams, eval_params.ngram_mapper);
+
+    let direct =
+        Evaluator::default(Box::new(ngram_provider.clone())).default_metrics(&eval_params.metrics);
+    let precomputed = Evaluator::default(Box::new(ngram_provider))
+        .default_metrics(&eval_params.metrics)
+        .precompute(&layout.keyboard);
+
+    let mut group = c.benchmark_group("precompute");
+    group.bench_function("direct", |b| {
+        b.iter(|| direct.evaluate_layout(&layout));
+    });
+    group.bench_function("precomputed", |b| {
+        b.iter(|| precomputed.evaluate_layout(&layout));
+    });
+    group.finish();
+
+    let keys: &[LayerKey] = &layout.layerkeys;
+    let n_keys = keys.len() as u64;
+    let mut random = pseudo_random(0x9e37_79b9_7f4a_7c15);
+    let mut key = || &keys[(random.next().unwrap() % n_keys) as usize];
+    let bigrams: Vec<((&LayerKey, &LayerKey), f64)> = (0..N_BIGRAMS)
+        .map(|i| ((key(), key()), 1.0 + (i % 100) as f64))
+        .collect();
+
+    let metrics: Vec<Box<dyn BigramMetric>> = vec![
+        Box::new(Sfb::new(&eval_params.metrics.sfb.unwrap().params)),
+        Box::new(Fsb::new(&eval_params.metrics.fsb.unwrap().params)),
+        Box::new(Hsb::new(&eval_params.metrics.hsb.unwrap().params)),
+    ];
+    let mut group = c.benchmark_group("precompute_bigram_metrics");
+    group.sample_size(10);
+    for mut metric in metrics {
+        let name = metric.name().to_string();
+        group.bench_function(format!("{} direct", name), |b| {
+            b.iter(|| metric.total_cost(&bigrams, None, &layout));
+        });
+        metric.precompute(&layout.keyboard);
+        group.bench_function(format!("{} precomputed", name), |b| {
+            b.iter(|| metric.total_cost(&bigrams, None, &layout));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, precompute_bench);
+criterion_main!(benches);
@@ -0,0 +1,84 @@
+use keyboard_layout::{
+    config::LayoutConfig,
+    keyboard::Keyboard,
+    layout::{LayerKey, Layout},
+    layout_generator::LayoutGenerator,
+    neo_layout_generator::NeoLayoutGenerator,
+};
+use layout_evaluation::metrics::{
+    bigram_metrics::{bigram_stats::BigramStats, BigramMetric},
+    trigram_metrics::{trigram_stats::TrigramStats, TrigramMetric},
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::sync::Arc;
+
+const LAYOUT_CONFIG: &str = "../config/keyboard/sval.yml";
+const LAYOUT: &str = "?öcäb-üiqy')ezo.,a(umkhjlg{txdw□n}pv□s□fr";
+const N_TRIGRAMS: usize = 5_000_000;
+
+const BIGRAM_STATS_PARAMS: &str = "
+ignore_thumbs: true
+ignore_modifiers: true
+";
+
+const TRIGRAM_STATS_PARAMS: &str = "
+ignore_thumbs: true
+ignore_modifiers: true
+same_finger_rolls:
+  - [Center, South]
+  - [In, South]
+";
+
+/// A deterministic pseudo-random sequence (xorshift), so that all runs use the same ngrams
+fn pseudo_random(mut state: u64) -> impl Iterator<Item = u64> {
+    std::iter::repeat_with(move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    })
+}
+
+fn layout() -> Layout {
+    let layout_config = LayoutConfig::from_yaml(LAYOUT_CONFIG)
+        .unwrap_or_else(|e| panic!("Could not load config file '{}': {}", LAYOUT_CONFIG, e));
+    let keyboard = Arc::new(Keyboard::from_yaml_object(layout_config.keyboard));
+    let layout_generator = NeoLayoutGenerator::from_object(layout_config.base_layout, keyboard);
+
+    layout_generator
+        .generate(LAYOUT)
+        .unwrap_or_else(|e| panic!("Could not generate layout: {:?}", e))
+}
+
+pub fn statistics_bench(c: &mut Criterion) {
+    let layout = layout();
+    let keys: &[LayerKey] = &layout.layerkeys;
+    let n_keys = keys.len() as u64;
+
+    let mut random = pseudo_random(0x9e37_79b9_7f4a_7c15);
+    let mut key = || &keys[(random.next().unwrap() % n_keys) as usize];
+    let trigrams: Vec<((&LayerKey, &LayerKey, &LayerKey), f64)> = (0..N_TRIGRAMS)
+        .map(|i| ((key(), key(), key()), 1.0 + (i % 100) as f64))
+        .collect();
+    let bigrams: Vec<((&LayerKey, &LayerKey), f64)> = trigrams
+        .iter()
+        .map(|((k1, k2, _), weight)| ((*k1, *k2), *weight))
+        .collect();
+
+    let trigram_stats = TrigramStats::new(&serde_yaml::from_str(TRIGRAM_STATS_PARAMS).unwrap());
+    let bigram_stats = BigramStats::new(&serde_yaml::from_str(BIGRAM_STATS_PARAMS).unwrap());
+
+    let mut group = c.benchmark_group("statistics");
+    group.sample_size(10);
+    group.bench_function("trigram_stats", |b| {
+        b.iter(|| trigram_stats.total_cost(&trigrams, None, &layout));
+    });
+    group.bench_function("bigram_stats", |b| {
+        b.iter(|| bigram_stats.total_cost(&bigrams, None, &layout));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, statistics_bench);
+criterion_main!(benches);
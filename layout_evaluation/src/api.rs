@@ -0,0 +1,234 @@
+//! The `api` module provides a high-level facade for embedding the evaluation in other tools,
+//! e.g. a web service. It bundles the steps the binaries perform (reading the keyboard and
+//! evaluation configurations, preparing the ngrams, setting up the metrics and the baseline)
+//! behind an [`Evaluator`] that evaluates layout strings.
+//!
+//! ```no_run
+//! use layout_evaluation::api::{Evaluator, NgramSource};
+//!
+//! let evaluator = Evaluator::from_config_strs(
+//!     &std::fs::read_to_string("config/keyboard/sval.yml").unwrap(),
+//!     &std::fs::read_to_string("config/evaluation/sval.yml").unwrap(),
+//!     NgramSource::Text("the quick brown fox jumps over the lazy dog"),
+//! )
+//! .unwrap();
+//! let result = evaluator
+//!     .evaluate("?öcäb-üiqy')ezo.,a(umkhjlg{txdw□n}pv□s□fr")
+//!     .unwrap();
+//! println!("{}", result.total_cost);
+//! ```
+//!
+//! [`Evaluator`] is `Send + Sync` and evaluates through `&self`, so a server can share a
+//! single instance (e.g. in an `Arc`) between its threads.
+//!
+//! The `corpora` of an evaluation configuration refer to ngram files and are therefore ignored
+//! here; the ngrams are always given as [`NgramSource`].
+
+use crate::{
+    config::EvaluationParameters,
+    evaluation,
+    ngram_mapper::on_demand_ngram_mapper::OnDemandNgramMapper,
+    ngrams::{Bigrams, Trigrams, Unigrams},
+    results::{self, MetricType},
+};
+
+use keyboard_layout::{
+    config::LayoutConfig, keyboard::Keyboard, layout_generator::LayoutGenerator,
+    neo_layout_generator::NeoLayoutGenerator,
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::{str::FromStr, sync::Arc};
+
+/// The ngrams layouts are evaluated on
+#[derive(Clone, Copy, Debug)]
+pub enum NgramSource<'a> {
+    /// Count the ngrams of a text
+    Text(&'a str),
+    /// Ngram frequencies in the format of the ngram files (`1-grams.txt` etc.)
+    Frequencies {
+        unigrams: &'a str,
+        bigrams: &'a str,
+        trigrams: &'a str,
+    },
+}
+
+/// The cost of a single metric
+#[derive(Clone, Debug, Serialize)]
+pub struct MetricEntry {
+    pub metric_type: MetricType,
+    pub name: String,
+    /// Normalized and weighted cost (the metric's share of the total cost)
+    pub weighted_cost: f64,
+    /// Normalized cost without the weight
+    pub unweighted_cost: f64,
+    /// Cost as computed by the metric (before normalization)
+    pub raw_cost: f64,
+    pub weight: f64,
+    /// Share of the total cost in percent (if the total cost is positive)
+    pub contribution: Option<f64>,
+    /// The metric's message, e.g. its worst ngrams
+    pub message: Option<String>,
+    /// Descriptions of the exceeded caps of the metric
+    pub violations: Vec<String>,
+}
+
+/// A named share (in percent) of the bigram and trigram statistics, e.g. "sfb" or "roll_in"
+#[derive(Clone, Debug, Serialize)]
+pub struct Statistic {
+    pub name: String,
+    pub percent: f64,
+}
+
+/// The result of evaluating a layout with [`Evaluator::evaluate`]
+#[derive(Clone, Debug, Serialize)]
+pub struct EvaluationResult {
+    pub layout: String,
+    pub total_cost: f64,
+    pub metrics: Vec<MetricEntry>,
+    pub statistics: Vec<Statistic>,
+    /// The complete result, as printed by the binaries
+    pub details: results::EvaluationResult,
+}
+
+impl From<(results::EvaluationResult, Vec<(&'static str, f64)>)> for EvaluationResult {
+    fn from((details, statistics): (results::EvaluationResult, Vec<(&'static str, f64)>)) -> Self {
+        let metrics = details
+            .iter()
+            .flat_map(|results| {
+                results.metric_costs.iter().map(move |mc| MetricEntry {
+                    metric_type: results.metric_type.clone(),
+                    name: mc.core.name.clone(),
+                    weighted_cost: mc.weighted_cost,
+                    unweighted_cost: mc.unweighted_cost,
+                    raw_cost: mc.core.cost,
+                    weight: mc.core.weight,
+                    contribution: mc.contribution,
+                    message: mc.core.message.clone(),
+                    violations: mc.core.violations.clone(),
+                })
+            })
+            .collect();
+
+        Self {
+            layout: details.layout().to_string(),
+            total_cost: details.total_cost(),
+            metrics,
+            statistics: statistics
+                .into_iter()
+                .map(|(name, percent)| Statistic {
+                    name: name.to_string(),
+                    percent,
+                })
+                .collect(),
+            details,
+        }
+    }
+}
+
+/// Evaluates layout strings for a keyboard, a set of metrics, and ngrams.
+#[derive(Clone, Debug)]
+pub struct Evaluator {
+    layout_generator: NeoLayoutGenerator,
+    evaluator: evaluation::Evaluator,
+}
+
+impl Evaluator {
+    /// Set up the evaluation from the contents of a keyboard (layout) configuration, e.g.
+    /// `config/keyboard/sval.yml`, and an evaluation configuration, e.g.
+    /// `config/evaluation/sval.yml`.
+    pub fn from_config_strs(
+        layout_config: &str,
+        evaluation_config: &str,
+        ngrams: NgramSource,
+    ) -> Result<Self> {
+        let layout_config = LayoutConfig::from_str(layout_config)
+            .map_err(|e| anyhow!("Could not read layout config: {}", e))?;
+        let keyboard = Arc::new(Keyboard::from_yaml_object(layout_config.keyboard));
+        let layout_generator = NeoLayoutGenerator::from_object(layout_config.base_layout, keyboard);
+
+        let eval_params = EvaluationParameters::from_str(evaluation_config)
+            .context("Could not read evaluation parameters")?;
+
+        let (mut unigrams, mut bigrams, mut trigrams) = match ngrams {
+            NgramSource::Text(text) => (
+                Unigrams::from_text(text).context("Could not generate unigrams from text")?,
+                Bigrams::from_text(text).context("Could not generate bigrams from text")?,
+                Trigrams::from_text(text).context("Could not generate trigrams from text")?,
+            ),
+            NgramSource::Frequencies {
+                unigrams,
+                bigrams,
+                trigrams,
+            } => (
+                Unigrams::from_frequencies_str(unigrams).context("Could not load unigrams")?,
+                Bigrams::from_frequencies_str(bigrams).context("Could not load bigrams")?,
+                Trigrams::from_frequencies_str(trigrams).context("Could not load trigrams")?,
+            ),
+        };
+
+        let increase_common_ngrams = &eval_params.ngrams.increase_common_ngrams;
+        if increase_common_ngrams.enabled {
+            unigrams = unigrams.increase_common(increase_common_ngrams);
+            bigrams = bigrams.increase_common(increase_common_ngrams);
+            trigrams = trigrams.increase_common(increase_common_ngrams);
+        }
+
+        let ngram_mapper =
+            OnDemandNgramMapper::with_ngrams(unigrams, bigrams, trigrams, eval_params.ngram_mapper);
+        let mut evaluator = evaluation::Evaluator::default(Box::new(ngram_mapper))
+            .default_metrics(&eval_params.metrics)
+            .precompute(layout_generator.keyboard());
+
+        if let Some(baseline_layout) = &eval_params.baseline_layout {
+            let layout = layout_generator
+                .generate(baseline_layout)
+                .with_context(|| {
+                    format!("Could not generate baseline layout '{}'", baseline_layout)
+                })?;
+            evaluator = evaluator.with_baseline(&layout);
+        }
+
+        Ok(Self {
+            layout_generator,
+            evaluator,
+        })
+    }
+
+    /// Evaluate the layout given by its string representation (the symbols of the permutable
+    /// keys from left to right, top to bottom).
+    pub fn evaluate(&self, layout_str: &str) -> Result<EvaluationResult> {
+        let layout = self
+            .layout_generator
+            .generate(layout_str)
+            .with_context(|| format!("Could not generate layout '{}'", layout_str))?;
+        let details = self.evaluator.evaluate_layout(&layout);
+        let statistics = self.evaluator.statistics(&layout);
+
+        Ok((details, statistics).into())
+    }
+
+    /// Names of the enabled metrics.
+    pub fn metric_names(&self) -> Vec<&str> {
+        self.evaluator.metric_names()
+    }
+
+    /// The underlying [`evaluation::Evaluator`], e.g. for running an optimization.
+    pub fn evaluator(&self) -> &evaluation::Evaluator {
+        &self.evaluator
+    }
+
+    /// The layout generator interpreting the layout strings.
+    pub fn layout_generator(&self) -> &NeoLayoutGenerator {
+        &self.layout_generator
+    }
+}
+
+/// The facade must be shareable between the threads of a server.
+#[allow(dead_code)]
+fn assert_send_sync() {
+    fn is_send_sync<T: Send + Sync>() {}
+    is_send_sync::<Evaluator>();
+    is_send_sync::<EvaluationResult>();
+}
@@ -1,5 +1,6 @@
+use crate::output_style::bold;
+
 use ahash::AHashMap;
-use colored::Colorize;
 use parking_lot::Mutex;
 use std::{fmt, sync::Arc};
 
@@ -67,7 +68,7 @@ impl<T: Clone + fmt::Display + PartialOrd> Cache<T> {
                     output_string.push_str(&format!(
                         "\n{:>width$}. {} (current)",
                         i + 1,
-                        result_line.bold(),
+                        bold(&result_line),
                         width = enumeration_length,
                     ));
                 } else {
@@ -1,33 +1,161 @@
 use crate::{
     evaluation::MetricParameters, ngram_mapper::on_demand_ngram_mapper::NgramMapperConfig,
-    ngrams::NgramsConfig,
+    ngrams::NgramsConfig, presets,
 };
 
+use keyboard_layout::{key::MatrixPosition, keyboard::Keyboard};
+
 use anyhow::Result;
 use serde::Deserialize;
-use std::{fs::File, str::FromStr};
+use serde_yaml::Value;
+use std::{collections::BTreeSet, str::FromStr};
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct EvaluationParameters {
     pub metrics: MetricParameters,
     pub ngrams: NgramsConfig,
     pub ngram_mapper: NgramMapperConfig,
+    /// Layout string of the baseline layout for metrics with `baseline` normalization
+    pub baseline_layout: Option<String>,
+    /// Corpora to evaluate layouts on (instead of the ngrams given on the command line)
+    #[serde(default)]
+    pub corpora: Vec<CorpusConfig>,
+}
+
+/// A named and weighted corpus, see [`EvaluationParameters::corpora`]
+#[derive(Clone, Deserialize, Debug)]
+pub struct CorpusConfig {
+    pub name: String,
+    /// Path to the ngram files
+    pub ngrams: String,
+    /// Weight of the corpus relative to the other corpora
+    #[serde(default = "default_corpus_weight")]
+    pub weight: f64,
+}
+
+fn default_corpus_weight() -> f64 {
+    1.0
 }
 
 impl EvaluationParameters {
+    #[cfg(feature = "fs")]
     pub fn from_yaml(filename: &str) -> Result<Self> {
-        let f = File::open(filename)?;
-        let k: EvaluationParameters = serde_yaml::from_reader(f)?;
+        std::fs::read_to_string(filename)?.parse()
+    }
+
+    /// Matrix positions in the metric parameters that no key of `keyboard` has (and that
+    /// therefore never apply), together with the path of the parameter listing them.
+    pub fn unmatched_positions(&self, keyboard: &Keyboard) -> Vec<(String, MatrixPosition)> {
+        let mut positions: Vec<(String, (u8, u8))> = Vec::new();
 
-        Ok(k)
+        if let Some(params) = &self.metrics.character_constraints {
+            for (c, costs) in params.params.costs.iter() {
+                let path = format!("metrics.character_constraints.params.costs.{}", c);
+                let c_positions: BTreeSet<&(u8, u8)> = costs.keys().collect();
+                positions.extend(c_positions.into_iter().map(|pos| (path.clone(), *pos)));
+            }
+        }
+        if let Some(params) = &self.metrics.manual_bigram_penalty {
+            let path = "metrics.manual_bigram_penalty.params.matrix_positions";
+            let pair_positions: BTreeSet<(u8, u8)> = params
+                .params
+                .matrix_positions
+                .keys()
+                .flat_map(|(pos1, pos2)| [*pos1, *pos2])
+                .collect();
+            positions.extend(
+                pair_positions
+                    .into_iter()
+                    .map(|pos| (path.to_string(), pos)),
+            );
+        }
+
+        positions.sort();
+        positions
+            .into_iter()
+            .map(|(path, (column, row))| (path, MatrixPosition(column, row)))
+            .filter(|(_, pos)| !keyboard.keys.iter().any(|k| k.matrix_position == *pos))
+            .collect()
     }
 }
 
 impl FromStr for EvaluationParameters {
-    type Err = serde_yaml::Error;
+    type Err = anyhow::Error;
     fn from_str(evaluation_params_str: &str) -> Result<Self, Self::Err> {
-        let cfg: EvaluationParameters = serde_yaml::from_str(evaluation_params_str)?;
+        let config: Value = serde_yaml::from_str(evaluation_params_str)?;
+        let cfg: EvaluationParameters = match presets::expand(config)? {
+            // deserializing from a string (unlike from a `Value`) accepts the `null: null`
+            // placeholders of empty parameters
+            Some(params) => serde_yaml::from_str(&serde_yaml::to_string(&params)?)?,
+            None => serde_yaml::from_str(evaluation_params_str)?,
+        };
 
         Ok(cfg)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::toy_keyboard;
+
+    /// Metric parameters with matrix positions of the toy keyboard: the left pinky's cluster is
+    /// centered at [2, 2], the left thumb at [11, 4]. Position [40, 2] does not exist.
+    fn parameters(character_positions: &str) -> EvaluationParameters {
+        format!(
+            "
+            metrics:
+              character_constraints:
+                enabled: true
+                weight: 1.0
+                normalization: {{type: fixed, value: 1.0}}
+                params:
+                  costs:
+                    e: {{{}}}
+              manual_bigram_penalty:
+                enabled: true
+                weight: 1.0
+                normalization: {{type: fixed, value: 1.0}}
+                params:
+                  add_mirrored: true
+                  matrix_positions:
+                    [[2, 1], [11, 4]]: 1.0
+            ngrams:
+              increase_common_ngrams:
+                enabled: false
+                critical_fraction: 0.001
+                factor: 2.0
+                total_weight_threshold: 20.0
+            ngram_mapper:
+              exclude_line_breaks: true
+              split_modifiers:
+                enabled: true
+                same_key_mod_factor: 0.03125
+            ",
+            character_positions
+        )
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn matching_positions_are_not_reported() {
+        let params = parameters("[2, 2]: 1.0, [2, 3]: 2.0");
+        assert!(params.unmatched_positions(&toy_keyboard()).is_empty());
+    }
+
+    #[test]
+    fn one_stale_entry_gives_one_warning() {
+        let params = parameters("[2, 2]: 1.0, [40, 2]: 2.0");
+        let unmatched = params.unmatched_positions(&toy_keyboard());
+
+        assert_eq!(
+            unmatched,
+            vec![(
+                "metrics.character_constraints.params.costs.e".to_string(),
+                MatrixPosition(40, 2)
+            )]
+        );
+    }
+}
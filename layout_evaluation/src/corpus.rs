@@ -0,0 +1,390 @@
+//! The `corpus` module provides a [`Corpus`] that gives streaming access to ngram
+//! frequency files without reading them into memory up front.
+//!
+//! The files (`1-grams.txt`, `2-grams.txt`, `3-grams.txt` in a corpus directory) are
+//! memory-mapped and each line is only parsed when the corresponding iterator reaches it.
+//! The iterators can be collected into [`Unigrams`], [`Bigrams`], and [`Trigrams`] for use
+//! with the existing ngram mappers.
+//!
+//! For tests and benchmarks, a corpus can also be created from a text in memory with
+//! [`Corpus::from_text`]. Corpora (e.g. of several languages) are combined with
+//! [`Corpus::merge`] and [`Corpus::merge_many`]. Opening ngram files requires the `fs` feature.
+//!
+//! A [`LayoutEvaluator`](crate::evaluator::LayoutEvaluator) evaluates layouts on a corpus. For
+//! this, the ngrams are collected once on first use and shared by all following evaluations.
+
+use crate::ngrams::{
+    process_special_characters, process_special_characters_inverse, Bigrams, Trigrams, Unigrams,
+};
+
+use ahash::AHashMap;
+#[cfg(feature = "fs")]
+use anyhow::{Context, Result};
+#[cfg(feature = "fs")]
+use memmap2::Mmap;
+use once_cell::sync::OnceCell;
+use std::{fmt::Write, hash::Hash, ops::Deref, sync::Arc};
+#[cfg(feature = "fs")]
+use std::{fs::File, path::Path};
+
+/// A symbol of an ngram.
+pub type Symbol = char;
+
+/// The ngram frequency files of a corpus (usually memory-mapped).
+#[derive(Debug)]
+pub struct Corpus {
+    unigrams: NgramData,
+    bigrams: NgramData,
+    trigrams: NgramData,
+    /// The collected ngrams (see [`Corpus::shared_ngrams`])
+    shared: OnceCell<SharedNgrams>,
+}
+
+/// The ngrams of a [`Corpus`] collected for the ngram mappers. Clones share the ngrams.
+#[derive(Clone, Debug)]
+pub struct SharedNgrams {
+    pub unigrams: Arc<Unigrams>,
+    pub bigrams: Arc<Bigrams>,
+    pub trigrams: Arc<Trigrams>,
+}
+
+/// The contents of an ngram frequency file
+#[derive(Debug)]
+enum NgramData {
+    #[cfg(feature = "fs")]
+    Mapped(Mmap),
+    /// Frequencies computed in memory (see [`Corpus::from_text`])
+    Owned(String),
+}
+
+impl Deref for NgramData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            #[cfg(feature = "fs")]
+            NgramData::Mapped(mmap) => mmap,
+            NgramData::Owned(data) => data.as_bytes(),
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+fn map_file(path: &Path) -> Result<NgramData> {
+    let file =
+        File::open(path).with_context(|| format!("Could not open ngram file '{:?}'", path))?;
+    // SAFETY: The mapping is read-only. Modifying the ngram files while a corpus is in use
+    // is not supported (as it would be when reading them with `fs::read_to_string`).
+    let mmap = unsafe { Mmap::map(&file) }
+        .with_context(|| format!("Could not memory-map ngram file '{:?}'", path))?;
+
+    Ok(NgramData::Mapped(mmap))
+}
+
+/// Format ngrams as the lines of a frequency file ("<weight> <ngram>"), with the weights
+/// normalized to sum up to 1
+fn frequencies<T: Eq + Hash>(
+    grams: &AHashMap<T, f64>,
+    symbols: impl Fn(&T) -> Vec<Symbol>,
+) -> NgramData {
+    let total: f64 = grams.values().sum();
+    let mut data = String::new();
+    for (gram, weight) in grams {
+        let ngram: String = symbols(gram)
+            .iter()
+            .map(|c| process_special_characters_inverse(&c.to_string()))
+            .collect();
+        writeln!(data, "{} {}", weight / total, ngram).unwrap();
+    }
+
+    NgramData::Owned(data)
+}
+
+/// Sum up the ngram frequencies of the `sources` multiplied with their weights. The ngrams of
+/// each source are normalized to sum up to 1 before, so that the weights are relative to the
+/// whole corpus regardless of its size. Sources without a positive weight are skipped.
+fn weighted_sum<T: Eq + Hash>(
+    sources: &[(&Corpus, f64)],
+    ngrams: impl Fn(&Corpus) -> Vec<(T, f64)>,
+) -> AHashMap<T, f64> {
+    let mut grams = AHashMap::default();
+    for (corpus, source_weight) in sources {
+        if *source_weight <= 0.0 {
+            continue;
+        }
+
+        let source_grams = ngrams(corpus);
+        let total: f64 = source_grams.iter().map(|(_, w)| w).sum();
+        if total <= 0.0 {
+            continue;
+        }
+
+        for (gram, weight) in source_grams {
+            *grams.entry(gram).or_insert(0.0) += source_weight * weight / total;
+        }
+    }
+
+    grams
+}
+
+/// Parse the lines of a frequency file ("<weight> <ngram>") into weights and ngram symbols.
+/// Lines that can not be parsed are skipped with a warning.
+fn weighted_ngrams(data: &[u8], n: usize) -> impl Iterator<Item = (Vec<Symbol>, f64)> + '_ {
+    data.split(|b| *b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .filter_map(move |line| {
+            let line = match std::str::from_utf8(line) {
+                Ok(line) => line,
+                Err(e) => {
+                    log::warn!("Skipping {}-gram line that is not valid UTF-8: {}", n, e);
+                    return None;
+                }
+            };
+
+            let mut parts = line.trim_start().splitn(2, ' ');
+            let weight: Option<f64> = parts.next().and_then(|w| w.parse().ok());
+            let symbols: Option<Vec<Symbol>> = parts
+                .next()
+                .map(|ngram| process_special_characters(ngram).chars().collect());
+
+            match (weight, symbols) {
+                (Some(weight), Some(symbols)) if symbols.len() >= n => {
+                    if symbols.len() != n {
+                        log::info!("Len of {}-gram '{}' is unequal {}", n, line, n);
+                    }
+                    Some((symbols, weight))
+                }
+                _ => {
+                    log::warn!("Skipping invalid {}-gram line: '{}'", n, line);
+                    None
+                }
+            }
+        })
+}
+
+impl Corpus {
+    fn new(unigrams: NgramData, bigrams: NgramData, trigrams: NgramData) -> Self {
+        Self {
+            unigrams,
+            bigrams,
+            trigrams,
+            shared: OnceCell::new(),
+        }
+    }
+
+    /// Map the ngram frequency files of the corpus in the given directory.
+    #[cfg(feature = "fs")]
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        Ok(Self::new(
+            map_file(&dir.join("1-grams.txt"))?,
+            map_file(&dir.join("2-grams.txt"))?,
+            map_file(&dir.join("3-grams.txt"))?,
+        ))
+    }
+
+    /// Count the unigrams, bigrams, and trigrams of `text` (by `char`, ignoring `'\r'`). The
+    /// weights of each ngram length are normalized to sum up to 1.
+    pub fn from_text(text: &str) -> Self {
+        let unigrams = Unigrams::from_text(text).unwrap();
+        let bigrams = Bigrams::from_text(text).unwrap();
+        let trigrams = Trigrams::from_text(text).unwrap();
+
+        Self::new(
+            frequencies(&unigrams.grams, |c| vec![*c]),
+            frequencies(&bigrams.grams, |(c1, c2)| vec![*c1, *c2]),
+            frequencies(&trigrams.grams, |(c1, c2, c3)| vec![*c1, *c2, *c3]),
+        )
+    }
+
+    /// Combine two corpora, see [`Corpus::merge_many`].
+    pub fn merge(a: &Corpus, b: &Corpus, weight_a: f64, weight_b: f64) -> Self {
+        Self::merge_weighted(&[(a, weight_a), (b, weight_b)])
+    }
+
+    /// Combine corpora into a new one whose ngram frequencies are the sum of their
+    /// frequencies multiplied with the given weights (corpora without a positive weight are
+    /// left out). The weights of each ngram length are normalized to sum up to 1.
+    pub fn merge_many(sources: &[(Corpus, f64)]) -> Self {
+        let sources: Vec<(&Corpus, f64)> = sources.iter().map(|(c, w)| (c, *w)).collect();
+        Self::merge_weighted(&sources)
+    }
+
+    fn merge_weighted(sources: &[(&Corpus, f64)]) -> Self {
+        let unigrams = weighted_sum(sources, |c| c.unigrams().collect());
+        let bigrams = weighted_sum(sources, |c| c.bigrams().collect());
+        let trigrams = weighted_sum(sources, |c| c.trigrams().collect());
+
+        Self::new(
+            frequencies(&unigrams, |c| vec![*c]),
+            frequencies(&bigrams, |(c1, c2)| vec![*c1, *c2]),
+            frequencies(&trigrams, |(c1, c2, c3)| vec![*c1, *c2, *c3]),
+        )
+    }
+
+    /// Stream the weighted unigrams of the corpus.
+    pub fn unigrams(&self) -> impl Iterator<Item = (Symbol, f64)> + '_ {
+        weighted_ngrams(&self.unigrams, 1).map(|(s, w)| (s[0], w))
+    }
+
+    /// Stream the weighted bigrams of the corpus.
+    pub fn bigrams(&self) -> impl Iterator<Item = ((Symbol, Symbol), f64)> + '_ {
+        weighted_ngrams(&self.bigrams, 2).map(|(s, w)| ((s[0], s[1]), w))
+    }
+
+    /// Stream the weighted trigrams of the corpus.
+    pub fn trigrams(&self) -> impl Iterator<Item = ((Symbol, Symbol, Symbol), f64)> + '_ {
+        weighted_ngrams(&self.trigrams, 3).map(|(s, w)| ((s[0], s[1], s[2]), w))
+    }
+
+    /// Collect all ngrams of the corpus for use with the ngram mappers.
+    pub fn to_ngrams(&self) -> (Unigrams, Bigrams, Trigrams) {
+        (
+            self.unigrams().collect(),
+            self.bigrams().collect(),
+            self.trigrams().collect(),
+        )
+    }
+
+    /// The ngrams of the corpus, collected on the first call and shared afterwards.
+    pub fn shared_ngrams(&self) -> &SharedNgrams {
+        self.shared.get_or_init(|| {
+            let (unigrams, bigrams, trigrams) = self.to_ngrams();
+            SharedNgrams {
+                unigrams: Arc::new(unigrams),
+                bigrams: Arc::new(bigrams),
+                trigrams: Arc::new(trigrams),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted<T: PartialOrd>(mut grams: Vec<(T, f64)>) -> Vec<(T, f64)> {
+        grams.sort_by(|(g1, _), (g2, _)| g1.partial_cmp(g2).unwrap());
+        grams
+    }
+
+    #[test]
+    fn text_frequencies_are_normalized() {
+        let corpus = Corpus::from_text("abab");
+
+        assert_eq!(
+            sorted(corpus.unigrams().collect()),
+            vec![('a', 0.5), ('b', 0.5)]
+        );
+        assert_eq!(
+            sorted(corpus.bigrams().collect()),
+            vec![(('a', 'b'), 2.0 / 3.0), (('b', 'a'), 1.0 / 3.0)]
+        );
+        assert_eq!(
+            sorted(corpus.trigrams().collect()),
+            vec![(('a', 'b', 'a'), 0.5), (('b', 'a', 'b'), 0.5)]
+        );
+    }
+
+    #[test]
+    fn text_ngrams_keep_special_characters() {
+        // multi-byte characters, spaces, newlines, and backslashes survive the frequency lines;
+        // carriage returns are dropped
+        let corpus = Corpus::from_text("ä ö\r\n\\é");
+
+        let unigrams: Vec<Symbol> = sorted(corpus.unigrams().collect())
+            .into_iter()
+            .map(|(c, _)| c)
+            .collect();
+        assert_eq!(unigrams, vec!['\n', ' ', '\\', 'ä', 'é', 'ö']);
+
+        let bigrams: Vec<(Symbol, Symbol)> = sorted(corpus.bigrams().collect())
+            .into_iter()
+            .map(|(b, _)| b)
+            .collect();
+        assert_eq!(
+            bigrams,
+            vec![
+                ('\n', '\\'),
+                (' ', 'ö'),
+                ('\\', 'é'),
+                ('ä', ' '),
+                ('ö', '\n')
+            ]
+        );
+        assert_eq!(corpus.trigrams().count(), 4);
+    }
+
+    #[test]
+    fn text_without_ngrams_gives_an_empty_corpus() {
+        let corpus = Corpus::from_text("a");
+
+        assert_eq!(corpus.unigrams().collect::<Vec<_>>(), vec![('a', 1.0)]);
+        assert_eq!(corpus.bigrams().count(), 0);
+        assert_eq!(corpus.trigrams().count(), 0);
+    }
+
+    #[test]
+    fn escaped_backslashes_are_not_newlines() {
+        // a backslash followed by an "n"
+        let corpus = Corpus::from_text("\\n");
+
+        assert_eq!(
+            corpus.bigrams().collect::<Vec<_>>(),
+            vec![(('\\', 'n'), 1.0)]
+        );
+    }
+
+    #[test]
+    fn equal_weights_average_the_frequencies() {
+        let merged = Corpus::merge(&Corpus::from_text("ab"), &Corpus::from_text("cc"), 2.0, 2.0);
+
+        assert_eq!(
+            sorted(merged.unigrams().collect()),
+            vec![('a', 0.25), ('b', 0.25), ('c', 0.5)]
+        );
+        assert_eq!(
+            sorted(merged.bigrams().collect()),
+            vec![(('a', 'b'), 0.5), (('c', 'c'), 0.5)]
+        );
+    }
+
+    #[test]
+    fn extreme_weights_reproduce_one_corpus() {
+        let a = Corpus::from_text("abcab");
+        let b = Corpus::from_text("xyz");
+
+        let merged = Corpus::merge(&a, &b, 1.0, 0.0);
+        assert_eq!(
+            sorted(merged.unigrams().collect()),
+            sorted(a.unigrams().collect())
+        );
+        assert_eq!(
+            sorted(merged.trigrams().collect()),
+            sorted(a.trigrams().collect())
+        );
+
+        let merged = Corpus::merge_many(&[(a, -1.0), (b, 3.0)]);
+        assert_eq!(
+            sorted(merged.bigrams().collect()),
+            vec![(('x', 'y'), 0.5), (('y', 'z'), 0.5)]
+        );
+    }
+
+    #[test]
+    fn merged_frequencies_sum_up_to_one() {
+        let merged = Corpus::merge_many(&[
+            (Corpus::from_text("the quick brown fox"), 0.2),
+            (Corpus::from_text("jumps over the lazy dog"), 0.5),
+            (Corpus::from_text("äöü ß"), 3.0),
+        ]);
+
+        let unigrams: f64 = merged.unigrams().map(|(_, w)| w).sum();
+        let bigrams: f64 = merged.bigrams().map(|(_, w)| w).sum();
+        let trigrams: f64 = merged.trigrams().map(|(_, w)| w).sum();
+        for total in [unigrams, bigrams, trigrams] {
+            assert!((total - 1.0).abs() < 1e-12, "{}", total);
+        }
+    }
+}
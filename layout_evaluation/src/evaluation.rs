@@ -6,16 +6,26 @@
 //!
 //! The ngram mapper is responsible for mapping char-based ngrams (as read from input data)
 //! to singles, pairs, and triplets of [`LayerKey`]s that can then be analysed by the individual metrics.
+//!
+//! An evaluator may hold several weighted corpora (each with its own ngram mapper). Then, the
+//! metrics evaluate a layout on each of them and the result combines their weighted means
+//! (see [`EvaluationResult::combine`]).
 
 use crate::results::{
-    EvaluationResult, MetricResult, MetricResults, MetricType, NormalizationType,
+    CorpusResult, CostCurve, EvaluationResult, MaxAllowed, MetricResult, MetricResults, MetricType,
+    NormalizationType,
 };
 use crate::{
     metrics::{bigram_metrics::*, layout_metrics::*, trigram_metrics::*, unigram_metrics::*},
     ngram_mapper::NgramMapper,
+    output_style::ColorConfig,
 };
 
-use keyboard_layout::layout::{LayerKey, Layout};
+use ahash::AHashMap;
+use keyboard_layout::{
+    keyboard::Keyboard,
+    layout::{LayerKey, Layout},
+};
 
 use serde::Deserialize;
 
@@ -30,6 +40,10 @@ pub struct WeightedParams<T> {
     pub weight: f64,
     /// The normalization strategy to use.
     pub normalization: NormalizationType,
+    /// The curve to apply to the normalized cost before weighting (linear if None).
+    pub cost_curve: Option<CostCurve>,
+    /// Caps that invalidate layouts exceeding them.
+    pub max_allowed: Option<MaxAllowed>,
     /// The metric's individual parameters.
     pub params: T,
 }
@@ -41,20 +55,30 @@ pub struct MetricParameters {
     pub shortcut_keys: Option<WeightedParams<shortcut_keys::Parameters>>,
     pub similar_letters: Option<WeightedParams<similar_letters::Parameters>>,
     pub similar_letter_groups: Option<WeightedParams<similar_letter_groups::Parameters>>,
+    pub layout_similarity: Option<WeightedParams<layout_similarity::Parameters>>,
+    pub mirrored_pairs: Option<WeightedParams<mirrored_pairs::Parameters>>,
+    pub hand_symmetry: Option<WeightedParams<hand_symmetry::Parameters>>,
 
     pub finger_balance: Option<WeightedParams<finger_balance::Parameters>>,
     pub character_constraints: Option<WeightedParams<character_constraints::Parameters>>,
     pub hand_disbalance: Option<WeightedParams<hand_disbalance::Parameters>>,
+    pub hand_grouping: Option<WeightedParams<hand_grouping::Parameters>>,
     pub row_loads: Option<WeightedParams<row_loads::Parameters>>,
     pub key_costs: Option<WeightedParams<key_costs::Parameters>>,
+    pub layer_key_costs: Option<WeightedParams<layer_key_costs::Parameters>>,
     pub modifier_usage: Option<WeightedParams<modifier_usage::Parameters>>,
 
     pub bigram_stats: Option<WeightedParams<bigram_stats::Parameters>>,
     pub fsb: Option<WeightedParams<fsb::Parameters>>,
     pub hsb: Option<WeightedParams<hsb::Parameters>>,
+    pub lsb: Option<WeightedParams<lsb::Parameters>>,
+    pub scissors: Option<WeightedParams<scissors::Parameters>>,
+    pub off_center_chaining: Option<WeightedParams<off_center_chaining::Parameters>>,
     pub sfb: Option<WeightedParams<sfb::Parameters>>,
     pub symmetric_handswitches: Option<WeightedParams<symmetric_handswitches::Parameters>>,
     pub finger_repeats: Option<WeightedParams<finger_repeats::Parameters>>,
+    pub consecutive_finger_use: Option<WeightedParams<consecutive_finger_use::Parameters>>,
+    pub key_repeats: Option<WeightedParams<key_repeats::Parameters>>,
     pub manual_bigram_penalty: Option<WeightedParams<manual_bigram_penalty::Parameters>>,
     pub movement_pattern: Option<WeightedParams<movement_pattern::Parameters>>,
     pub no_handswitch_after_unbalancing_key:
@@ -67,6 +91,7 @@ pub struct MetricParameters {
     pub sfs: Option<WeightedParams<sfs::Parameters>>,
     pub redirects: Option<WeightedParams<redirects::Parameters>>,
     pub weak_redirect: Option<WeightedParams<weak_redirect::Parameters>>,
+    pub roll_redirect: Option<WeightedParams<roll_redirect::Parameters>>,
     pub trigram_finger_repeats: Option<WeightedParams<trigram_finger_repeats::Parameters>>,
     pub trigram_rolls: Option<WeightedParams<trigram_rolls::Parameters>>,
 
@@ -91,6 +116,42 @@ pub struct MetricParameters {
     pub oxey_bad_redirects: Option<WeightedParams<oxey_bad_redirects::Parameters>>,
 }
 
+/// The cost of a single ngram for a metric, see [`Evaluator::individual_costs`]
+#[derive(Clone, Debug)]
+pub struct NgramCost<'a> {
+    /// Name of the corpus (empty if there is only one)
+    pub corpus: &'a str,
+    pub metric_type: MetricType,
+    pub metric: &'a str,
+    /// The symbols of the ngram (modifiers in brackets)
+    pub ngram: String,
+    /// Weight (frequency) of the ngram
+    pub weight: f64,
+    /// Cost as computed by the metric (before normalization and weighting)
+    pub cost: f64,
+}
+
+/// A corpus (given by its ngram mapper) that layouts are evaluated on.
+#[derive(Clone, Debug)]
+struct EvaluationCorpus {
+    name: String,
+    weight: f64,
+    ngram_mapper: Box<dyn NgramMapper>,
+    /// Costs of the baseline layout per metric type and index of the metric
+    baseline_costs: AHashMap<(MetricType, usize), f64>,
+}
+
+impl EvaluationCorpus {
+    fn new(name: String, weight: f64, ngram_mapper: Box<dyn NgramMapper>) -> Self {
+        Self {
+            name,
+            weight,
+            ngram_mapper,
+            baseline_costs: AHashMap::default(),
+        }
+    }
+}
+
 /// The [`Evaluator`] object is responsible for evaluating multiple metrics with respect to given ngram data.
 /// The metrics are handled as dynamically dispatched trait objects for the metric traits in the `metrics` module.
 #[derive(Clone, Debug)]
@@ -99,23 +160,55 @@ pub struct Evaluator {
     unigram_metrics: Vec<(f64, NormalizationType, Box<dyn UnigramMetric>)>,
     bigram_metrics: Vec<(f64, NormalizationType, Box<dyn BigramMetric>)>,
     trigram_metrics: Vec<(f64, NormalizationType, Box<dyn TrigramMetric>)>,
-    ngram_mapper: Box<dyn NgramMapper>,
+    corpora: Vec<EvaluationCorpus>,
+    /// Cost curves per metric type and index of the metric
+    cost_curves: AHashMap<(MetricType, usize), CostCurve>,
+    /// Caps per metric type and index of the metric
+    max_allowed: AHashMap<(MetricType, usize), MaxAllowed>,
 }
 
 impl Evaluator {
     /// Generate an "empty" [`Evaluator`] object without any metric.
     pub fn default(ngram_mapper: Box<dyn NgramMapper>) -> Self {
+        Self::with_corpora(vec![(String::new(), 1.0, ngram_mapper)])
+    }
+
+    /// Generate an "empty" [`Evaluator`] object without any metric that evaluates layouts on
+    /// several corpora, given by their names, weights and ngram mappers.
+    pub fn with_corpora(corpora: Vec<(String, f64, Box<dyn NgramMapper>)>) -> Self {
         Evaluator {
             layout_metrics: Vec::new(),
             unigram_metrics: Vec::new(),
             bigram_metrics: Vec::new(),
             trigram_metrics: Vec::new(),
-            ngram_mapper,
+            corpora: corpora
+                .into_iter()
+                .map(|(name, weight, ngram_mapper)| {
+                    EvaluationCorpus::new(name, weight, ngram_mapper)
+                })
+                .collect(),
+            cost_curves: AHashMap::default(),
+            max_allowed: AHashMap::default(),
         }
     }
 
     /// Add all "default" metrics to the evaluator.
     pub fn default_metrics(mut self, params: &MetricParameters) -> Self {
+        macro_rules! metric_type {
+            (layout_metric) => {
+                MetricType::Layout
+            };
+            (unigram_metric) => {
+                MetricType::Unigram
+            };
+            (bigram_metric) => {
+                MetricType::Bigram
+            };
+            (trigram_metric) => {
+                MetricType::Trigram
+            };
+        }
+
         macro_rules! add_metric {
             ($metric_type:ident, $metric_name:ident, $metric_struct:ident) => {
                 if let Some(p) = &params.$metric_name {
@@ -125,6 +218,11 @@ impl Evaluator {
                             p.weight,
                             p.normalization.clone(),
                         );
+                        self.configure_last_metric(
+                            metric_type!($metric_type),
+                            &p.cost_curve,
+                            &p.max_allowed,
+                        );
                     }
                 }
             };
@@ -139,6 +237,11 @@ impl Evaluator {
                             p.weight,
                             p.normalization.clone(),
                         );
+                        self.configure_last_metric(
+                            metric_type!($metric_type),
+                            &p.cost_curve,
+                            &p.max_allowed,
+                        );
                     }
                 }
             };
@@ -148,11 +251,16 @@ impl Evaluator {
         add_metric!(layout_metric, shortcut_keys, ShortcutKeys);
         add_metric!(layout_metric, similar_letters, SimilarLetters);
         add_metric!(layout_metric, similar_letter_groups, SimilarLetterGroups);
+        add_metric!(layout_metric, layout_similarity, LayoutSimilarity);
+        add_metric!(layout_metric, mirrored_pairs, MirroredPairs);
+        add_metric!(layout_metric, hand_symmetry, HandSymmetry);
 
         // unigram metrics
         add_metric!(unigram_metric, hand_disbalance, HandDisbalance);
+        add_metric!(unigram_metric, hand_grouping, HandGrouping);
         add_metric!(unigram_metric, finger_balance, FingerBalance);
         add_metric!(unigram_metric, key_costs, KeyCost);
+        add_metric!(unigram_metric, layer_key_costs, LayerKeyCosts);
         add_metric!(unigram_metric, character_constraints, CharacterConstraints);
         //add_metric!(unigram_metric, modifier_usage, ModifierUsage);
 
@@ -161,21 +269,27 @@ impl Evaluator {
         add_metric!(bigram_metric, sfb, Sfb);
         add_metric!(bigram_metric, fsb, Fsb);
         add_metric!(bigram_metric, hsb, Hsb);
+        add_metric!(bigram_metric, lsb, Lsb);
+        add_metric!(bigram_metric, scissors, Scissors);
+        add_metric!(bigram_metric, off_center_chaining, OffCenterChaining);
         add_metric!(bigram_metric, finger_repeats, FingerRepeats);
+        add_metric!(bigram_metric, consecutive_finger_use, ConsecutiveFingerUse);
+        add_metric!(bigram_metric, key_repeats, KeyRepeats);
         add_metric!(bigram_metric, movement_pattern, MovementPattern);
         add_metric!(bigram_metric, manual_bigram_penalty, ManualBigramPenalty);
-        //add_metric!(
-        //    bigram_metric,
-        //    no_handswitch_after_unbalancing_key,
-        //    NoHandSwitchAfterUnbalancingKey
-        //);
-        //add_metric!(bigram_metric, symmetric_handswitches, SymmetricHandswitches);
+        add_metric!(
+            bigram_metric,
+            no_handswitch_after_unbalancing_key,
+            NoHandSwitchAfterUnbalancingKey
+        );
+        add_metric!(bigram_metric, symmetric_handswitches, SymmetricHandswitches);
 
         // trigram_metrics
         add_metric!(trigram_metric, trigram_stats, TrigramStats);
         add_metric!(trigram_metric, sfs, Sfs);
         add_metric!(trigram_metric, redirects, Redirects);
         add_metric!(trigram_metric, weak_redirect, WeakRedirect);
+        add_metric!(trigram_metric, roll_redirect, RollRedirect);
         add_metric!(
             trigram_metric,
             no_handswitch_in_trigram,
@@ -220,6 +334,113 @@ impl Evaluator {
         self
     }
 
+    /// Use the costs the given baseline layout achieves as reference for all metrics with
+    /// [`NormalizationType::Baseline`]. The baseline is evaluated only once, here.
+    /// With several corpora, each corpus has its own baseline costs.
+    pub fn with_baseline(mut self, layout: &Layout) -> Self {
+        for idx in 0..self.corpora.len() {
+            self.corpora[idx].baseline_costs.clear();
+            let baseline_costs = self
+                .evaluate_corpus(layout, &self.corpora[idx])
+                .iter()
+                .flat_map(|results| {
+                    results
+                        .metric_costs
+                        .iter()
+                        .enumerate()
+                        .map(move |(i, mc)| ((results.metric_type.clone(), i), mc.core.cost))
+                })
+                .collect();
+            self.corpora[idx].baseline_costs = baseline_costs;
+        }
+
+        self
+    }
+
+    /// Let the bigram and trigram metrics precompute what does not depend on the layout, e.g.
+    /// the costs of all pairs of keys (see [`BigramMetric::precompute`] and
+    /// [`TrigramMetric::precompute`]). Only layouts on `keyboard` (or a clone of it) profit from
+    /// it. The results are the same as without precomputation.
+    pub fn precompute(mut self, keyboard: &Keyboard) -> Self {
+        for (_, _, metric) in self.bigram_metrics.iter_mut() {
+            metric.precompute(keyboard);
+        }
+
+        let mut tables = SharedTables::new(keyboard);
+        for (_, _, metric) in self.trigram_metrics.iter_mut() {
+            metric.precompute(&mut tables);
+        }
+
+        self
+    }
+
+    /// Style the metric messages according to `color` instead of the global output style
+    /// (see [`BigramMetric::set_color_config`]).
+    pub fn with_color_config(mut self, color: ColorConfig) -> Self {
+        for (_, _, metric) in self.bigram_metrics.iter_mut() {
+            metric.set_color_config(color);
+        }
+
+        self
+    }
+
+    /// Set the cost curve and caps of the most recently added metric of the given type.
+    fn configure_last_metric(
+        &mut self,
+        metric_type: MetricType,
+        cost_curve: &Option<CostCurve>,
+        max_allowed: &Option<MaxAllowed>,
+    ) {
+        let n_metrics = match metric_type {
+            MetricType::Layout => self.layout_metrics.len(),
+            MetricType::Unigram => self.unigram_metrics.len(),
+            MetricType::Bigram => self.bigram_metrics.len(),
+            MetricType::Trigram => self.trigram_metrics.len(),
+        };
+        if n_metrics == 0 {
+            return;
+        }
+
+        let key = (metric_type, n_metrics - 1);
+        if let Some(cost_curve) = cost_curve {
+            self.cost_curves.insert(key.clone(), cost_curve.clone());
+        }
+        if let Some(max_allowed) = max_allowed {
+            self.max_allowed.insert(key, max_allowed.clone());
+        }
+    }
+
+    /// Add the baseline cost (on `corpus`), cost curve and exceeded caps of the metric with the
+    /// given type and index to its result. The percentages are only computed if there are percentage caps.
+    fn complete_result(
+        &self,
+        corpus: &EvaluationCorpus,
+        key: (MetricType, usize),
+        mut result: MetricResult,
+        percentages: impl FnOnce() -> Vec<(&'static str, f64)>,
+    ) -> MetricResult {
+        result.baseline_cost = corpus.baseline_costs.get(&key).copied();
+        result.cost_curve = self.cost_curves.get(&key).cloned();
+        if let Some(max_allowed) = self.max_allowed.get(&key) {
+            result.violations = max_allowed.violations(result.cost, percentages);
+            if !result.violations.is_empty() {
+                result.cap_penalty = max_allowed.penalty;
+            }
+        }
+
+        result
+    }
+
+    /// Whether any metric is normalized with respect to a baseline layout.
+    pub fn uses_baseline(&self) -> bool {
+        let is_baseline = |n: &NormalizationType| matches!(n, NormalizationType::Baseline(_));
+
+        self.layout_metrics.iter().any(|(_, n, _)| is_baseline(n))
+            || self.unigram_metrics.iter().any(|(_, n, _)| is_baseline(n))
+            || self.bigram_metrics.iter().any(|(_, n, _)| is_baseline(n))
+            || self.trigram_metrics.iter().any(|(_, n, _)| is_baseline(n))
+    }
+
     /// Add a metric that operates only on the layout itself ("layout metric").
     pub fn layout_metric(
         &mut self,
@@ -260,8 +481,65 @@ impl Evaluator {
         self.trigram_metrics.push((weight, normalization, metric));
     }
 
+    /// Names of all (enabled) metrics.
+    pub fn metric_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        names.extend(self.layout_metrics.iter().map(|(_, _, m)| m.name()));
+        names.extend(self.unigram_metrics.iter().map(|(_, _, m)| m.name()));
+        names.extend(self.bigram_metrics.iter().map(|(_, _, m)| m.name()));
+        names.extend(self.trigram_metrics.iter().map(|(_, _, m)| m.name()));
+
+        names
+    }
+
+    /// Set the weight of the metric(s) named `name`. Returns whether there is such a metric.
+    pub fn set_metric_weight(&mut self, name: &str, weight: f64) -> bool {
+        let mut found = false;
+        let mut set = |metric_name: &str, w: &mut f64| {
+            if metric_name == name {
+                *w = weight;
+                found = true;
+            }
+        };
+        self.layout_metrics
+            .iter_mut()
+            .for_each(|(w, _, m)| set(m.name(), w));
+        self.unigram_metrics
+            .iter_mut()
+            .for_each(|(w, _, m)| set(m.name(), w));
+        self.bigram_metrics
+            .iter_mut()
+            .for_each(|(w, _, m)| set(m.name(), w));
+        self.trigram_metrics
+            .iter_mut()
+            .for_each(|(w, _, m)| set(m.name(), w));
+
+        found
+    }
+
+    /// Names of the corpora the layouts are evaluated on.
+    pub fn corpus_names(&self) -> Vec<&str> {
+        self.corpora.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    /// Set the weight of the corpus named `name` (relative to the other corpora). Returns
+    /// whether there is such a corpus.
+    pub fn set_corpus_weight(&mut self, name: &str, weight: f64) -> bool {
+        match self.corpora.iter_mut().find(|c| c.name == name) {
+            Some(corpus) => {
+                corpus.weight = weight;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Evaluate all layout metrics for a layout.
-    fn evaluate_layout_metrics(&self, layout: &Layout) -> Vec<MetricResult> {
+    fn evaluate_layout_metrics(
+        &self,
+        corpus: &EvaluationCorpus,
+        layout: &Layout,
+    ) -> Vec<MetricResult> {
         if self.layout_metrics.is_empty() {
             return Vec::new();
         }
@@ -269,15 +547,12 @@ impl Evaluator {
         let metric_costs: Vec<MetricResult> = self
             .layout_metrics
             .iter()
-            .map(|(weight, normalization, metric)| {
-                let (cost, message) = metric.total_cost(layout);
-                MetricResult {
-                    name: metric.name().to_string(),
-                    cost,
-                    weight: *weight,
-                    normalization: normalization.clone(),
-                    message,
-                }
+            .enumerate()
+            .map(|(i, (weight, normalization, metric))| {
+                let (cost, message) = metric.cost(layout);
+                let result =
+                    MetricResult::new(metric.name(), cost, message, *weight, normalization.clone());
+                self.complete_result(corpus, (MetricType::Layout, i), result, Vec::new)
             })
             .collect();
 
@@ -285,28 +560,36 @@ impl Evaluator {
     }
 
     /// Evaluate all unigram metrics for a layout.
+    ///
+    /// The `unsplit_keys` (without resolved modifiers) are passed to the metrics that request
+    /// them and are only required if there are any.
     fn evaluate_unigram_metrics(
         &self,
+        corpus: &EvaluationCorpus,
         layout: &Layout,
         keys: &[(&LayerKey, f64)],
+        unsplit_keys: Option<&[(&LayerKey, f64)]>,
     ) -> Vec<MetricResult> {
         if self.unigram_metrics.is_empty() {
             return Vec::new();
         }
 
         let total_weight = keys.iter().map(|(_, w)| w).sum();
+        let unsplit_total_weight = unsplit_keys.map(|k| k.iter().map(|(_, w)| w).sum());
         let metric_costs: Vec<MetricResult> = self
             .unigram_metrics
             .iter()
-            .map(|(weight, normalization, metric)| {
-                let (cost, message) = metric.total_cost(keys, Some(total_weight), layout);
-                MetricResult {
-                    name: metric.name().to_string(),
-                    cost,
-                    weight: *weight,
-                    normalization: normalization.clone(),
-                    message,
-                }
+            .enumerate()
+            .map(|(i, (weight, normalization, metric))| {
+                let (cost, message) = match (metric.uses_unsplit_unigrams(), unsplit_keys) {
+                    (true, Some(unsplit_keys)) => {
+                        metric.total_cost(unsplit_keys, unsplit_total_weight, layout)
+                    }
+                    _ => metric.total_cost(keys, Some(total_weight), layout),
+                };
+                let result =
+                    MetricResult::new(metric.name(), cost, message, *weight, normalization.clone());
+                self.complete_result(corpus, (MetricType::Unigram, i), result, Vec::new)
             })
             .collect();
 
@@ -316,6 +599,7 @@ impl Evaluator {
     /// Evaluate all bigram metrics for a layout.
     fn evaluate_bigram_metrics(
         &self,
+        corpus: &EvaluationCorpus,
         layout: &Layout,
         keys: &[((&LayerKey, &LayerKey), f64)],
     ) -> Vec<MetricResult> {
@@ -327,15 +611,14 @@ impl Evaluator {
         let metric_costs: Vec<MetricResult> = self
             .bigram_metrics
             .iter()
-            .map(|(weight, normalization, metric)| {
+            .enumerate()
+            .map(|(i, (weight, normalization, metric))| {
                 let (cost, message) = metric.total_cost(keys, Some(total_weight), layout);
-                MetricResult {
-                    name: metric.name().to_string(),
-                    cost,
-                    weight: *weight,
-                    normalization: normalization.clone(),
-                    message,
-                }
+                let result =
+                    MetricResult::new(metric.name(), cost, message, *weight, normalization.clone());
+                self.complete_result(corpus, (MetricType::Bigram, i), result, || {
+                    metric.percentages(keys, Some(total_weight), layout)
+                })
             })
             .collect();
 
@@ -345,6 +628,7 @@ impl Evaluator {
     /// Evaluate all trigram metrics for a layout.
     fn evaluate_trigram_metrics(
         &self,
+        corpus: &EvaluationCorpus,
         layout: &Layout,
         keys: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
     ) -> Vec<MetricResult> {
@@ -356,28 +640,249 @@ impl Evaluator {
         let metric_costs: Vec<MetricResult> = self
             .trigram_metrics
             .iter()
-            .map(|(weight, normalization, metric)| {
+            .enumerate()
+            .map(|(i, (weight, normalization, metric))| {
                 let (cost, message) = metric.total_cost(keys, Some(total_weight), layout);
-                MetricResult {
-                    name: metric.name().to_string(),
-                    cost,
-                    weight: *weight,
-                    normalization: normalization.clone(),
-                    message,
-                }
+                let result =
+                    MetricResult::new(metric.name(), cost, message, *weight, normalization.clone());
+                self.complete_result(corpus, (MetricType::Trigram, i), result, || {
+                    metric.percentages(keys, Some(total_weight), layout)
+                })
             })
             .collect();
 
         metric_costs
     }
 
-    /// Evaluate all metrics for a layout.
+    /// Collect the named shares (in percent) of all bigram and trigram metrics providing any,
+    /// e.g. the bigram and trigram statistics. This maps the ngrams for the layout, but does
+    /// not compute any costs. With several corpora, the shares are the weighted means of the
+    /// corpora's shares.
+    pub fn statistics(&self, layout: &Layout) -> Vec<(&'static str, f64)> {
+        let total_weight: f64 = self.corpora.iter().map(|c| c.weight).sum();
+        let mut statistics: Vec<(&'static str, f64)> = Vec::new();
+        for corpus in self.corpora.iter() {
+            let share = match total_weight > 0.0 {
+                true => corpus.weight / total_weight,
+                false => 1.0 / self.corpora.len() as f64,
+            };
+            let corpus_statistics = self.corpus_statistics(corpus, layout);
+            if statistics.is_empty() {
+                statistics = corpus_statistics
+                    .into_iter()
+                    .map(|(name, value)| (name, share * value))
+                    .collect();
+            } else {
+                statistics
+                    .iter_mut()
+                    .zip(corpus_statistics)
+                    .for_each(|((_, value), (_, corpus_value))| *value += share * corpus_value);
+            }
+        }
+
+        statistics
+    }
+
+    /// The statistics (see [`Evaluator::statistics`]) of a single corpus.
+    fn corpus_statistics(
+        &self,
+        corpus: &EvaluationCorpus,
+        layout: &Layout,
+    ) -> Vec<(&'static str, f64)> {
+        let mut statistics = Vec::new();
+
+        if !self.bigram_metrics.is_empty() {
+            let mapped_bigrams = corpus.ngram_mapper.map_bigrams(layout);
+            let total_weight = mapped_bigrams.grams.iter().map(|(_, w)| w).sum();
+            self.bigram_metrics.iter().for_each(|(_, _, metric)| {
+                statistics.extend(metric.percentages(
+                    &mapped_bigrams.grams,
+                    Some(total_weight),
+                    layout,
+                ))
+            });
+        }
+
+        if !self.trigram_metrics.is_empty() {
+            let mapped_trigrams = corpus.ngram_mapper.map_trigrams(layout);
+            let total_weight = mapped_trigrams.grams.iter().map(|(_, w)| w).sum();
+            self.trigram_metrics.iter().for_each(|(_, _, metric)| {
+                statistics.extend(metric.percentages(
+                    &mapped_trigrams.grams,
+                    Some(total_weight),
+                    layout,
+                ))
+            });
+        }
+
+        statistics
+    }
+
+    /// Compute the individual cost of each mapped ngram for all unigram, bigram, and trigram
+    /// metrics and pass them to `visit` (one corpus after the other), e.g. for writing them to
+    /// a file without keeping them in memory.
+    ///
+    /// Returns the names of the metrics without individual costs: the layout metrics and the
+    /// metrics that only compute a total cost (e.g. the statistics).
+    pub fn individual_costs(
+        &self,
+        layout: &Layout,
+        visit: &mut dyn FnMut(NgramCost),
+    ) -> Vec<String> {
+        let mut skipped: Vec<String> = self
+            .layout_metrics
+            .iter()
+            .map(|(_, _, metric)| metric.name().to_string())
+            .collect();
+        let mut unigram_has_costs = vec![false; self.unigram_metrics.len()];
+        let mut bigram_has_costs = vec![false; self.bigram_metrics.len()];
+        let mut trigram_has_costs = vec![false; self.trigram_metrics.len()];
+
+        for corpus in self.corpora.iter() {
+            if !self.unigram_metrics.is_empty() {
+                let mapped_unigrams = corpus.ngram_mapper.map_unigrams(layout);
+                let unsplit_unigrams = self
+                    .unigram_metrics
+                    .iter()
+                    .any(|(_, _, m)| m.uses_unsplit_unigrams())
+                    .then(|| corpus.ngram_mapper.map_unigrams_unsplit(layout));
+                for ((_, _, metric), has_costs) in self
+                    .unigram_metrics
+                    .iter()
+                    .zip(unigram_has_costs.iter_mut())
+                {
+                    let unigrams = match (metric.uses_unsplit_unigrams(), &unsplit_unigrams) {
+                        (true, Some(unsplit_unigrams)) => &unsplit_unigrams.grams,
+                        _ => &mapped_unigrams.grams,
+                    };
+                    let total_weight = unigrams.iter().map(|(_, w)| w).sum();
+                    for (k, weight) in unigrams.iter() {
+                        if let Some(cost) = metric.individual_cost(k, *weight, total_weight, layout)
+                        {
+                            *has_costs = true;
+                            visit(NgramCost {
+                                corpus: &corpus.name,
+                                metric_type: MetricType::Unigram,
+                                metric: metric.name(),
+                                ngram: k.to_string(),
+                                weight: *weight,
+                                cost,
+                            });
+                        }
+                    }
+                }
+            }
+
+            if !self.bigram_metrics.is_empty() {
+                let mapped_bigrams = corpus.ngram_mapper.map_bigrams(layout);
+                let total_weight = mapped_bigrams.grams.iter().map(|(_, w)| w).sum();
+                for ((_, _, metric), has_costs) in
+                    self.bigram_metrics.iter().zip(bigram_has_costs.iter_mut())
+                {
+                    for ((k1, k2), weight) in mapped_bigrams.grams.iter() {
+                        if let Some(cost) =
+                            metric.individual_cost(k1, k2, *weight, total_weight, layout)
+                        {
+                            *has_costs = true;
+                            visit(NgramCost {
+                                corpus: &corpus.name,
+                                metric_type: MetricType::Bigram,
+                                metric: metric.name(),
+                                ngram: format!("{}{}", k1, k2),
+                                weight: *weight,
+                                cost,
+                            });
+                        }
+                    }
+                }
+            }
+
+            if !self.trigram_metrics.is_empty() {
+                let mapped_trigrams = corpus.ngram_mapper.map_trigrams(layout);
+                let total_weight = mapped_trigrams.grams.iter().map(|(_, w)| w).sum();
+                for ((_, _, metric), has_costs) in self
+                    .trigram_metrics
+                    .iter()
+                    .zip(trigram_has_costs.iter_mut())
+                {
+                    for ((k1, k2, k3), weight) in mapped_trigrams.grams.iter() {
+                        if let Some(cost) =
+                            metric.individual_cost(k1, k2, k3, *weight, total_weight, layout)
+                        {
+                            *has_costs = true;
+                            visit(NgramCost {
+                                corpus: &corpus.name,
+                                metric_type: MetricType::Trigram,
+                                metric: metric.name(),
+                                ngram: format!("{}{}{}", k1, k2, k3),
+                                weight: *weight,
+                                cost,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        let names_without_costs = |names: Vec<&str>, has_costs: Vec<bool>| {
+            names
+                .into_iter()
+                .zip(has_costs)
+                .filter(|(_, has_costs)| !has_costs)
+                .map(|(name, _)| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        skipped.extend(names_without_costs(
+            self.unigram_metrics
+                .iter()
+                .map(|(_, _, m)| m.name())
+                .collect(),
+            unigram_has_costs,
+        ));
+        skipped.extend(names_without_costs(
+            self.bigram_metrics
+                .iter()
+                .map(|(_, _, m)| m.name())
+                .collect(),
+            bigram_has_costs,
+        ));
+        skipped.extend(names_without_costs(
+            self.trigram_metrics
+                .iter()
+                .map(|(_, _, m)| m.name())
+                .collect(),
+            trigram_has_costs,
+        ));
+
+        skipped
+    }
+
+    /// Evaluate all metrics for a layout. With several corpora, the result combines the
+    /// weighted means of the corpora's results.
     pub fn evaluate_layout(&self, layout: &Layout) -> EvaluationResult {
+        match self.corpora.as_slice() {
+            [corpus] => self.evaluate_corpus(layout, corpus),
+            corpora => EvaluationResult::combine(
+                layout.as_text(),
+                corpora
+                    .iter()
+                    .map(|corpus| CorpusResult {
+                        name: corpus.name.clone(),
+                        weight: corpus.weight,
+                        result: self.evaluate_corpus(layout, corpus),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Evaluate all metrics for a layout on a single corpus.
+    fn evaluate_corpus(&self, layout: &Layout, corpus: &EvaluationCorpus) -> EvaluationResult {
         let mut results: Vec<MetricResults> = Vec::new();
 
         // Layout metrics
         if !self.layout_metrics.is_empty() {
-            let metric_costs = self.evaluate_layout_metrics(layout);
+            let metric_costs = self.evaluate_layout_metrics(corpus, layout);
             let mut layout_costs = MetricResults::new(MetricType::Layout, 1.0, 0.0);
             metric_costs
                 .into_iter()
@@ -387,8 +892,22 @@ impl Evaluator {
 
         // Unigram metrics
         if !self.unigram_metrics.is_empty() {
-            let mapped_unigrams = self.ngram_mapper.map_unigrams(layout);
-            let metric_costs = self.evaluate_unigram_metrics(layout, &mapped_unigrams.grams);
+            let mapped_unigrams = corpus.ngram_mapper.map_unigrams(layout);
+            let unsplit_unigrams = if self
+                .unigram_metrics
+                .iter()
+                .any(|(_, _, m)| m.uses_unsplit_unigrams())
+            {
+                Some(corpus.ngram_mapper.map_unigrams_unsplit(layout))
+            } else {
+                None
+            };
+            let metric_costs = self.evaluate_unigram_metrics(
+                corpus,
+                layout,
+                &mapped_unigrams.grams,
+                unsplit_unigrams.as_ref().map(|m| m.grams.as_slice()),
+            );
             let mut unigram_costs = MetricResults::new(
                 MetricType::Unigram,
                 mapped_unigrams.weight_found,
@@ -403,8 +922,8 @@ impl Evaluator {
 
         // Bigram metrics
         if !self.bigram_metrics.is_empty() {
-            let mapped_bigrams = self.ngram_mapper.map_bigrams(layout);
-            let metric_costs = self.evaluate_bigram_metrics(layout, &mapped_bigrams.grams);
+            let mapped_bigrams = corpus.ngram_mapper.map_bigrams(layout);
+            let metric_costs = self.evaluate_bigram_metrics(corpus, layout, &mapped_bigrams.grams);
             let mut bigram_costs = MetricResults::new(
                 MetricType::Bigram,
                 mapped_bigrams.weight_found,
@@ -419,8 +938,9 @@ impl Evaluator {
 
         // Trigram metrics
         if !self.trigram_metrics.is_empty() {
-            let mapped_trigrams = self.ngram_mapper.map_trigrams(layout);
-            let metric_costs = self.evaluate_trigram_metrics(layout, &mapped_trigrams.grams);
+            let mapped_trigrams = corpus.ngram_mapper.map_trigrams(layout);
+            let metric_costs =
+                self.evaluate_trigram_metrics(corpus, layout, &mapped_trigrams.grams);
             let mut trigram_costs = MetricResults::new(
                 MetricType::Trigram,
                 mapped_trigrams.weight_found,
@@ -436,3 +956,90 @@ impl Evaluator {
         EvaluationResult::new(layout.as_text(), results)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::EvaluationParameters, corpus::Corpus,
+        ngram_mapper::on_demand_ngram_mapper::OnDemandNgramMapper,
+    };
+
+    use keyboard_layout::{
+        config::LayoutConfig, keyboard::KeyIndex, layout_generator::LayoutGenerator,
+        neo_layout_generator::NeoLayoutGenerator,
+    };
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use std::{str::FromStr, sync::Arc};
+
+    const LAYOUT_CONFIG: &str = include_str!("../../config/keyboard/sval.yml");
+    const EVALUATION_CONFIG: &str = include_str!("../../config/evaluation/sval.yml");
+
+    /// Upper case letters and punctuation are on higher layers
+    const TEXT: &str = "The quick brown fox jumps over the lazy dog. Über den Wolken muss die \
+        Freiheit wohl grenzenlos sein (sagt man)! Zwölf Boxkämpfer jagen Viktor quer über den \
+        großen Sylter Deich; {x} [y] -- \"z\"?";
+
+    fn layout() -> Layout {
+        let config = LayoutConfig::from_str(LAYOUT_CONFIG).unwrap();
+        let keyboard = Arc::new(Keyboard::from_yaml_object(config.keyboard));
+        NeoLayoutGenerator::from_object(config.base_layout, keyboard)
+            .generate("?öcäb-üiqy')ezo.,a(umkhjlg{txdw□n}pv□s□fr")
+            .unwrap()
+    }
+
+    /// The metrics of `config/evaluation/sval.yml` on [`TEXT`]
+    fn evaluator() -> Evaluator {
+        let params = EvaluationParameters::from_str(EVALUATION_CONFIG).unwrap();
+        let (unigrams, bigrams, trigrams) = Corpus::from_text(TEXT).to_ngrams();
+        let ngram_mapper =
+            OnDemandNgramMapper::with_ngrams(unigrams, bigrams, trigrams, params.ngram_mapper);
+
+        Evaluator::default(Box::new(ngram_mapper)).default_metrics(&params.metrics)
+    }
+
+    fn costs(result: &EvaluationResult) -> Vec<(String, f64)> {
+        result
+            .iter()
+            .flat_map(|results| results.metric_costs.iter())
+            .map(|mc| (mc.core.name.clone(), mc.weighted_cost))
+            .collect()
+    }
+
+    /// Equal up to the order of summation (costs that cancel out may only be close to 0.0)
+    fn assert_close(left: f64, right: f64) {
+        assert!(
+            (left - right).abs() <= 1e-12 * (1.0 + left.abs().max(right.abs())),
+            "{} != {}",
+            left,
+            right
+        );
+    }
+
+    #[test]
+    fn precomputation_gives_identical_totals() {
+        let mut layout = layout();
+        let precomputed = evaluator().precompute(&layout.keyboard);
+        let direct = evaluator();
+        let n_keys = layout.keyboard.keys.len() as KeyIndex;
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(610);
+
+        for _ in 0..50 {
+            let result = precomputed.evaluate_layout(&layout);
+            let direct_result = direct.evaluate_layout(&layout);
+
+            for ((name, cost), (direct_name, direct_cost)) in
+                costs(&result).into_iter().zip(costs(&direct_result))
+            {
+                assert_eq!(name, direct_name);
+                assert_close(cost, direct_cost);
+            }
+            assert_close(result.total_cost(), direct_result.total_cost());
+
+            let k1 = rng.random_range(0..n_keys);
+            let k2 = rng.random_range(0..n_keys);
+            layout = layout.swap_keys_new(k1, k2).unwrap();
+        }
+    }
+}
@@ -0,0 +1,660 @@
+//! The `evaluator` module provides a [`LayoutEvaluator`] that runs all of its metrics
+//! in a single evaluation pass.
+//!
+//! In contrast to the [`Evaluator`](crate::evaluation::Evaluator), it is assembled with a
+//! [`LayoutEvaluatorBuilder`], does not own the ngram data (the [`Corpus`] is passed to each
+//! evaluation), and computes the individual metrics in parallel. Swaps of keys can be evaluated
+//! incrementally, see [`incremental`].
+
+pub mod incremental;
+
+use crate::{
+    corpus::Corpus,
+    metrics::{
+        bigram_metrics::BigramMetric,
+        layout_metrics::LayoutMetric,
+        trigram_metrics::{SharedTables, TrigramMetric},
+        unigram_metrics::UnigramMetric,
+    },
+    ngram_mapper::{
+        on_demand_ngram_mapper::{NgramMapperConfig, OnDemandNgramMapper},
+        NgramMapper,
+    },
+    results::{EvaluationResult, MetricResult, MetricResults, MetricType, NormalizationType},
+};
+
+use keyboard_layout::{
+    keyboard::{KeyIndex, Keyboard},
+    layout::{LayerKey, Layout},
+};
+
+use ahash::AHashSet;
+use rayon::prelude::*;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// The result of a [`LayoutEvaluator`] run.
+pub type LayoutScore = EvaluationResult;
+
+/// Cost differences below this share of the costs are treated as ties. The ngram maps are
+/// hashed with random seeds, so the summation order, and with it the last bits of a cost, varies
+/// between runs (and incrementally updated costs differ from complete evaluations in the last
+/// bits). Without the tolerance, such differences would decide comparisons (e.g. in the
+/// optimizers) and break reproducibility. Being relative, it does not depend on the scale of
+/// the costs.
+const COST_TOLERANCE: f64 = 1e-10;
+
+/// Difference of two costs, rounded to zero if it is below [`COST_TOLERANCE`] relative to the
+/// larger one
+pub(crate) fn cost_delta(new: f64, old: f64) -> f64 {
+    let delta = new - old;
+    if delta.abs() <= COST_TOLERANCE * new.abs().max(old.abs()) {
+        0.0
+    } else {
+        delta
+    }
+}
+
+/// How the costs change from one layout to another, e.g. by swapping two keys (see
+/// [`LayoutEvaluator::compare_swap`])
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapAnalysis {
+    /// Change of the total cost (negative if the second layout is better)
+    pub delta_total: f64,
+    /// Change of the weighted cost of each metric (in the order of the results)
+    pub per_metric_delta: Vec<(String, f64)>,
+    /// Names of the metrics whose cost decreased
+    pub improved_metrics: Vec<String>,
+}
+
+impl SwapAnalysis {
+    /// Compare two scores of the same [`LayoutEvaluator`], e.g. of the initial and the
+    /// optimized layout.
+    pub fn between(before: &LayoutScore, after: &LayoutScore) -> Self {
+        let weighted_costs = |score: &LayoutScore| -> Vec<(String, f64)> {
+            score
+                .iter()
+                .flat_map(|results| results.metric_costs.iter())
+                .map(|mc| (mc.core.name.clone(), mc.weighted_cost))
+                .collect()
+        };
+
+        let per_metric_delta: Vec<(String, f64)> = weighted_costs(before)
+            .into_iter()
+            .zip(weighted_costs(after))
+            .map(|((name, cost_before), (_, cost_after))| {
+                (name, cost_delta(cost_after, cost_before))
+            })
+            .collect();
+        let improved_metrics = per_metric_delta
+            .iter()
+            .filter(|(_, delta)| *delta < 0.0)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        Self {
+            delta_total: cost_delta(after.total_cost(), before.total_cost()),
+            per_metric_delta,
+            improved_metrics,
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum LayoutEvaluatorError {
+    #[error("Invalid evaluator: No metric configured")]
+    NoMetrics,
+    #[error("Invalid evaluator: No keyboard given")]
+    NoKeyboard,
+    #[error("Invalid evaluator: Multiple metrics named '{0}'")]
+    DuplicateMetricName(String),
+}
+
+/// A metric together with its weight and normalization.
+type Weighted<M> = (f64, NormalizationType, Box<M>);
+
+/// Collects the metrics of a [`LayoutEvaluator`] and the keyboard it evaluates layouts for.
+#[derive(Clone, Debug, Default)]
+pub struct LayoutEvaluatorBuilder {
+    keyboard: Option<Arc<Keyboard>>,
+    ngram_mapper_config: NgramMapperConfig,
+    layout_metrics: Vec<Weighted<dyn LayoutMetric>>,
+    unigram_metrics: Vec<Weighted<dyn UnigramMetric>>,
+    bigram_metrics: Vec<Weighted<dyn BigramMetric>>,
+    trigram_metrics: Vec<Weighted<dyn TrigramMetric>>,
+}
+
+impl LayoutEvaluatorBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The keyboard of the layouts to evaluate. The metrics precompute their key-pair tables
+    /// for it in [`LayoutEvaluatorBuilder::build`] (required).
+    pub fn with_keyboard(mut self, keyboard: Arc<Keyboard>) -> Self {
+        self.keyboard = Some(keyboard);
+        self
+    }
+
+    /// How the ngrams of a corpus are mapped to the keys of a layout (defaults to the settings
+    /// of `config/evaluation/sval.yml`).
+    pub fn with_ngram_mapper_config(mut self, config: NgramMapperConfig) -> Self {
+        self.ngram_mapper_config = config;
+        self
+    }
+
+    /// Add a metric that operates only on the layout itself ("layout metric").
+    pub fn with_layout_metric(
+        mut self,
+        metric: Box<dyn LayoutMetric>,
+        weight: f64,
+        normalization: NormalizationType,
+    ) -> Self {
+        self.layout_metrics.push((weight, normalization, metric));
+        self
+    }
+
+    /// Add a metric that operates on the unigram data ("unigram metric").
+    pub fn with_unigram_metric(
+        mut self,
+        metric: Box<dyn UnigramMetric>,
+        weight: f64,
+        normalization: NormalizationType,
+    ) -> Self {
+        self.unigram_metrics.push((weight, normalization, metric));
+        self
+    }
+
+    /// Add a metric that operates on the bigram data ("bigram metric").
+    pub fn with_bigram_metric(
+        mut self,
+        metric: Box<dyn BigramMetric>,
+        weight: f64,
+        normalization: NormalizationType,
+    ) -> Self {
+        self.bigram_metrics.push((weight, normalization, metric));
+        self
+    }
+
+    /// Add a metric that operates on the trigram data ("trigram metric").
+    pub fn with_trigram_metric(
+        mut self,
+        metric: Box<dyn TrigramMetric>,
+        weight: f64,
+        normalization: NormalizationType,
+    ) -> Self {
+        self.trigram_metrics.push((weight, normalization, metric));
+        self
+    }
+
+    /// Check that at least one metric is configured and that all metric names are unique
+    /// (they identify the metrics in the results).
+    fn validate(&self) -> Result<(), LayoutEvaluatorError> {
+        let names = self
+            .layout_metrics
+            .iter()
+            .map(|(_, _, m)| m.name())
+            .chain(self.unigram_metrics.iter().map(|(_, _, m)| m.name()))
+            .chain(self.bigram_metrics.iter().map(|(_, _, m)| m.name()))
+            .chain(self.trigram_metrics.iter().map(|(_, _, m)| m.name()));
+
+        let mut seen = AHashSet::default();
+        for name in names {
+            if !seen.insert(name) {
+                return Err(LayoutEvaluatorError::DuplicateMetricName(name.to_string()));
+            }
+        }
+
+        if seen.is_empty() {
+            return Err(LayoutEvaluatorError::NoMetrics);
+        }
+
+        Ok(())
+    }
+
+    /// Validate the configuration and precompute the metrics for the keyboard.
+    pub fn build(mut self) -> Result<LayoutEvaluator, LayoutEvaluatorError> {
+        self.validate()?;
+        let keyboard = self.keyboard.ok_or(LayoutEvaluatorError::NoKeyboard)?;
+
+        self.bigram_metrics
+            .iter_mut()
+            .for_each(|(_, _, metric)| metric.precompute(&keyboard));
+
+        let mut tables = SharedTables::new(&keyboard);
+        self.trigram_metrics
+            .iter_mut()
+            .for_each(|(_, _, metric)| metric.precompute(&mut tables));
+
+        Ok(LayoutEvaluator {
+            keyboard,
+            ngram_mapper_config: self.ngram_mapper_config,
+            layout_metrics: self.layout_metrics,
+            unigram_metrics: self.unigram_metrics,
+            bigram_metrics: self.bigram_metrics,
+            trigram_metrics: self.trigram_metrics,
+        })
+    }
+}
+
+/// Evaluates a fixed set of metrics for layouts on a keyboard and given ngram data.
+#[derive(Clone, Debug)]
+pub struct LayoutEvaluator {
+    keyboard: Arc<Keyboard>,
+    ngram_mapper_config: NgramMapperConfig,
+    layout_metrics: Vec<Weighted<dyn LayoutMetric>>,
+    unigram_metrics: Vec<Weighted<dyn UnigramMetric>>,
+    bigram_metrics: Vec<Weighted<dyn BigramMetric>>,
+    trigram_metrics: Vec<Weighted<dyn TrigramMetric>>,
+}
+
+/// Run the metrics of one type in parallel, keeping their configured order.
+fn evaluate_metrics<M: ?Sized + Send + Sync>(
+    metrics: &[Weighted<M>],
+    name: impl Fn(&M) -> &str + Sync,
+    cost: impl Fn(&M) -> (f64, Option<String>) + Sync,
+) -> Vec<MetricResult> {
+    metrics
+        .par_iter()
+        .map(|(weight, normalization, metric)| {
+            let (cost, message) = cost(metric);
+            MetricResult::new(name(metric), cost, message, *weight, normalization.clone())
+        })
+        .collect()
+}
+
+impl LayoutEvaluator {
+    pub fn builder() -> LayoutEvaluatorBuilder {
+        LayoutEvaluatorBuilder::new()
+    }
+
+    /// The keyboard the metrics were precomputed for.
+    pub fn keyboard(&self) -> &Arc<Keyboard> {
+        &self.keyboard
+    }
+
+    /// A mapper of the ngrams of `corpus` (sharing them with the corpus).
+    fn ngram_mapper(&self, corpus: &Corpus) -> OnDemandNgramMapper {
+        let ngrams = corpus.shared_ngrams();
+        OnDemandNgramMapper::with_shared_ngrams(
+            ngrams.unigrams.clone(),
+            ngrams.bigrams.clone(),
+            ngrams.trigrams.clone(),
+            self.ngram_mapper_config.clone(),
+        )
+    }
+
+    fn evaluate_layout_metrics(&self, layout: &Layout) -> Option<MetricResults> {
+        if self.layout_metrics.is_empty() {
+            return None;
+        }
+
+        let mut results = MetricResults::new(MetricType::Layout, 1.0, 0.0);
+        evaluate_metrics(&self.layout_metrics, |m| m.name(), |m| m.cost(layout))
+            .into_iter()
+            .for_each(|mc| results.add_result(mc));
+
+        Some(results)
+    }
+
+    fn evaluate_unigram_metrics(
+        &self,
+        layout: &Layout,
+        corpus: &dyn NgramMapper,
+    ) -> Option<(MetricResults, f64)> {
+        if self.unigram_metrics.is_empty() {
+            return None;
+        }
+
+        let mapped = corpus.map_unigrams(layout);
+        let total_weight: f64 = mapped.grams.iter().map(|(_, w)| w).sum();
+        let unsplit = if self
+            .unigram_metrics
+            .iter()
+            .any(|(_, _, m)| m.uses_unsplit_unigrams())
+        {
+            let unsplit = corpus.map_unigrams_unsplit(layout);
+            let unsplit_total_weight: f64 = unsplit.grams.iter().map(|(_, w)| w).sum();
+            Some((unsplit, unsplit_total_weight))
+        } else {
+            None
+        };
+        let mut results = MetricResults::new(
+            MetricType::Unigram,
+            mapped.weight_found,
+            mapped.weight_not_found,
+        );
+        evaluate_metrics(
+            &self.unigram_metrics,
+            |m| m.name(),
+            |m| match &unsplit {
+                Some((unsplit, unsplit_total_weight)) if m.uses_unsplit_unigrams() => {
+                    m.total_cost(&unsplit.grams, Some(*unsplit_total_weight), layout)
+                }
+                _ => m.total_cost(&mapped.grams, Some(total_weight), layout),
+            },
+        )
+        .into_iter()
+        .for_each(|mc| results.add_result(mc));
+
+        Some((results, total_weight))
+    }
+
+    fn evaluate_bigram_metrics(
+        &self,
+        layout: &Layout,
+        corpus: &dyn NgramMapper,
+    ) -> Option<(MetricResults, f64)> {
+        if self.bigram_metrics.is_empty() {
+            return None;
+        }
+
+        let mapped = corpus.map_bigrams(layout);
+        let total_weight: f64 = mapped.grams.iter().map(|(_, w)| w).sum();
+        let mut results = MetricResults::new(
+            MetricType::Bigram,
+            mapped.weight_found,
+            mapped.weight_not_found,
+        );
+        evaluate_metrics(
+            &self.bigram_metrics,
+            |m| m.name(),
+            |m| m.total_cost(&mapped.grams, Some(total_weight), layout),
+        )
+        .into_iter()
+        .for_each(|mc| results.add_result(mc));
+
+        Some((results, total_weight))
+    }
+
+    fn evaluate_trigram_metrics(
+        &self,
+        layout: &Layout,
+        corpus: &dyn NgramMapper,
+    ) -> Option<(MetricResults, f64)> {
+        if self.trigram_metrics.is_empty() {
+            return None;
+        }
+
+        let mapped = corpus.map_trigrams(layout);
+        let total_weight: f64 = mapped.grams.iter().map(|(_, w)| w).sum();
+        let mut results = MetricResults::new(
+            MetricType::Trigram,
+            mapped.weight_found,
+            mapped.weight_not_found,
+        );
+        evaluate_metrics(
+            &self.trigram_metrics,
+            |m| m.name(),
+            |m| m.total_cost(&mapped.grams, Some(total_weight), layout),
+        )
+        .into_iter()
+        .for_each(|mc| results.add_result(mc));
+
+        Some((results, total_weight))
+    }
+
+    /// Evaluate all metrics for a layout with respect to the ngrams of `corpus`. The ngram
+    /// types are mapped and evaluated concurrently.
+    pub fn evaluate(&self, layout: &Layout, corpus: &Corpus) -> LayoutScore {
+        self.evaluate_mapped(layout, &self.ngram_mapper(corpus))
+    }
+
+    fn evaluate_mapped(&self, layout: &Layout, corpus: &dyn NgramMapper) -> LayoutScore {
+        self.evaluate_with_weights(layout, corpus).0
+    }
+
+    /// Like [`LayoutEvaluator::evaluate_mapped`], additionally returning the total weights of
+    /// the mapped unigrams, bigrams, and trigrams given to the metrics (0 for ngram types
+    /// without metrics).
+    fn evaluate_with_weights(
+        &self,
+        layout: &Layout,
+        corpus: &dyn NgramMapper,
+    ) -> (LayoutScore, [f64; 3]) {
+        let ((layout_results, unigram_results), (bigram_results, trigram_results)) = rayon::join(
+            || {
+                rayon::join(
+                    || self.evaluate_layout_metrics(layout),
+                    || self.evaluate_unigram_metrics(layout, corpus),
+                )
+            },
+            || {
+                rayon::join(
+                    || self.evaluate_bigram_metrics(layout, corpus),
+                    || self.evaluate_trigram_metrics(layout, corpus),
+                )
+            },
+        );
+
+        let mut results: Vec<MetricResults> = layout_results.into_iter().collect();
+        let mut total_weights = [0.0; 3];
+        let ngram_results = vec![unigram_results, bigram_results, trigram_results];
+        for (i, ngram_results) in ngram_results.into_iter().enumerate() {
+            if let Some((ngram_results, total_weight)) = ngram_results {
+                results.push(ngram_results);
+                total_weights[i] = total_weight;
+            }
+        }
+
+        (
+            EvaluationResult::new(layout.as_text(), results),
+            total_weights,
+        )
+    }
+
+    /// Analyze how swapping the symbols of the keys `k1` and `k2` changes the costs of `layout`.
+    /// Both layouts are evaluated completely.
+    pub fn compare_swap(
+        &self,
+        layout: &Layout,
+        corpus: &Corpus,
+        k1: KeyIndex,
+        k2: KeyIndex,
+    ) -> anyhow::Result<SwapAnalysis> {
+        let swapped = layout.swap_keys_new(k1, k2)?;
+        let ngram_mapper = self.ngram_mapper(corpus);
+
+        Ok(SwapAnalysis::between(
+            &self.evaluate_mapped(layout, &ngram_mapper),
+            &self.evaluate_mapped(&swapped, &ngram_mapper),
+        ))
+    }
+
+    /// Attribute the weighted individual costs of the unigram, bigram, and trigram metrics to
+    /// the keys of the layout (indexed by [`KeyIndex`]), splitting the cost of an ngram evenly
+    /// among its symbols. This is an approximation: layout metrics and metrics without
+    /// individual costs are left out and normalizations are ignored.
+    pub fn key_costs(&self, layout: &Layout, corpus: &Corpus) -> Vec<f64> {
+        let corpus = self.ngram_mapper(corpus);
+        let mut costs = vec![0.0; layout.keyboard.keys.len()];
+        let mut add = |keys: &[&LayerKey], cost: f64| {
+            for k in keys {
+                if let Some(key_index) = layout.get_key_index_for_symbol(&k.symbol) {
+                    costs[key_index as usize] += cost / keys.len() as f64;
+                }
+            }
+        };
+
+        if !self.unigram_metrics.is_empty() {
+            let mapped = corpus.map_unigrams(layout);
+            let unsplit = corpus.map_unigrams_unsplit(layout);
+            for (metric_weight, _, metric) in self.unigram_metrics.iter() {
+                let unigrams = if metric.uses_unsplit_unigrams() {
+                    &unsplit.grams
+                } else {
+                    &mapped.grams
+                };
+                let total_weight = unigrams.iter().map(|(_, w)| w).sum();
+                for (k, weight) in unigrams.iter() {
+                    if let Some(cost) = metric.individual_cost(k, *weight, total_weight, layout) {
+                        add(&[k], metric_weight * cost);
+                    }
+                }
+            }
+        }
+
+        if !self.bigram_metrics.is_empty() {
+            let mapped = corpus.map_bigrams(layout);
+            let total_weight = mapped.grams.iter().map(|(_, w)| w).sum();
+            for (metric_weight, _, metric) in self.bigram_metrics.iter() {
+                for ((k1, k2), weight) in mapped.grams.iter() {
+                    if let Some(cost) =
+                        metric.individual_cost(k1, k2, *weight, total_weight, layout)
+                    {
+                        add(&[k1, k2], metric_weight * cost);
+                    }
+                }
+            }
+        }
+
+        if !self.trigram_metrics.is_empty() {
+            let mapped = corpus.map_trigrams(layout);
+            let total_weight = mapped.grams.iter().map(|(_, w)| w).sum();
+            for (metric_weight, _, metric) in self.trigram_metrics.iter() {
+                for ((k1, k2, k3), weight) in mapped.grams.iter() {
+                    if let Some(cost) =
+                        metric.individual_cost(k1, k2, k3, *weight, total_weight, layout)
+                    {
+                        add(&[k1, k2, k3], metric_weight * cost);
+                    }
+                }
+            }
+        }
+
+        costs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{bigram_metrics::sfb, unigram_metrics::key_costs};
+
+    use keyboard_layout::test_utils::toy_layout;
+
+    /// SFBs from `Center` to `North` cost 2.0, all others 1.0
+    fn sfb() -> Box<dyn BigramMetric> {
+        let params: sfb::Parameters = serde_yaml::from_str(
+            "
+            default_cost: 1.0
+            ignore_thumbs: false
+            costs:
+              Center:
+                North: 2.0
+            ",
+        )
+        .unwrap();
+        Box::new(sfb::Sfb::new(&params))
+    }
+
+    fn evaluator(layout: &Layout) -> LayoutEvaluator {
+        LayoutEvaluator::builder()
+            .with_keyboard(layout.keyboard.clone())
+            .with_unigram_metric(
+                Box::new(key_costs::KeyCost::new(&key_costs::Parameters {})),
+                1.0,
+                NormalizationType::Fixed(1.0),
+            )
+            .with_bigram_metric(sfb(), 1.0, NormalizationType::Fixed(1.0))
+            .build()
+            .unwrap()
+    }
+
+    /// The symbols of the left pinky are "b" (North), "x" (Out), "a" (Center), "y" (In), and
+    /// "z" (South); "c" is on the North key of the left ring finger.
+    fn layout() -> Layout {
+        toy_layout("bxayzc")
+    }
+
+    #[test]
+    fn build_requires_keyboard_and_metrics() {
+        let layout = layout();
+        assert!(matches!(
+            LayoutEvaluator::builder()
+                .with_keyboard(layout.keyboard.clone())
+                .build(),
+            Err(LayoutEvaluatorError::NoMetrics)
+        ));
+        assert!(matches!(
+            LayoutEvaluator::builder()
+                .with_bigram_metric(sfb(), 1.0, NormalizationType::Fixed(1.0))
+                .build(),
+            Err(LayoutEvaluatorError::NoKeyboard)
+        ));
+        assert!(matches!(
+            LayoutEvaluator::builder()
+                .with_keyboard(layout.keyboard.clone())
+                .with_bigram_metric(sfb(), 1.0, NormalizationType::Fixed(1.0))
+                .with_bigram_metric(sfb(), 1.0, NormalizationType::Fixed(1.0))
+                .build(),
+            Err(LayoutEvaluatorError::DuplicateMetricName(_))
+        ));
+    }
+
+    #[test]
+    fn evaluate_maps_the_corpus() {
+        let layout = layout();
+        let evaluator = evaluator(&layout);
+
+        // "ab": SFB from Center to North; "a" and "b" each cost 1.0 + 2.0 per half
+        let score = evaluator.evaluate(&layout, &Corpus::from_text("ab"));
+        let costs: Vec<(&str, f64)> = score
+            .iter()
+            .flat_map(|results| results.metric_costs.iter())
+            .map(|mc| (mc.core.name.as_str(), mc.weighted_cost))
+            .collect();
+        assert_eq!(costs, vec![("Key Costs", 1.5), ("SFB", 2.0)]);
+
+        // "ac": no SFB
+        let score = evaluator.evaluate(&layout, &Corpus::from_text("ac"));
+        assert!((score.total_cost() - 1.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn precomputed_metrics_give_the_same_costs() {
+        let layout = layout();
+        let precomputed = evaluator(&layout);
+        let direct = LayoutEvaluator {
+            bigram_metrics: vec![(1.0, NormalizationType::Fixed(1.0), sfb())],
+            ..precomputed.clone()
+        };
+
+        // The ngrams are summed in the (random) order of their hash maps
+        let corpus = Corpus::from_text("abxzy bay zab cab yazbx");
+        for layout in [layout.clone(), layout.swap_keys_new(2, 5).unwrap()] {
+            let cost = precomputed.evaluate(&layout, &corpus).total_cost();
+            assert!(cost > 0.0);
+            assert!((cost - direct.evaluate(&layout, &corpus).total_cost()).abs() < 1e-12 * cost);
+        }
+    }
+
+    #[test]
+    fn compare_swap_reports_the_delta() {
+        let layout = layout();
+        let evaluator = evaluator(&layout);
+        let corpus = Corpus::from_text("ab");
+
+        // Moving "b" to the ring finger removes the SFB ("c" has the same key cost)
+        let analysis = evaluator.compare_swap(&layout, &corpus, 0, 5).unwrap();
+        assert_eq!(analysis.delta_total, -2.0);
+        assert_eq!(analysis.improved_metrics, vec!["SFB".to_string()]);
+        assert_eq!(
+            analysis.per_metric_delta,
+            vec![("Key Costs".to_string(), 0.0), ("SFB".to_string(), -2.0)]
+        );
+    }
+
+    #[test]
+    fn key_costs_are_split_among_the_keys() {
+        let layout = layout();
+        let evaluator = evaluator(&layout);
+
+        let costs = evaluator.key_costs(&layout, &Corpus::from_text("ab"));
+        assert_eq!(costs.len(), layout.keyboard.keys.len());
+        // "a": half its key cost, half the SFB; "b": half its key cost, half the SFB
+        assert_eq!(costs[2], 0.5 * 1.0 + 1.0);
+        assert_eq!(costs[0], 0.5 * 2.0 + 1.0);
+        assert_eq!(costs.iter().sum::<f64>(), 3.5);
+    }
+}
@@ -0,0 +1,590 @@
+//! Incremental evaluation of key swaps: [`LayoutEvaluator::update_swap`] updates the score of
+//! a layout for a swap of two keys from the ngrams containing their symbols instead of
+//! evaluating the whole corpus again.
+//!
+//! This works for the additive metrics (see e.g. [`BigramMetric::is_additive`]): their cost
+//! changes by the difference of their costs on the changed ngrams after and before the swap.
+//! The other metrics, including the layout metrics, are evaluated completely, and so are all
+//! metrics if a swapped key holds a modifier (which changes the keys of all symbols of its
+//! layer). The updated costs differ from those of a complete evaluation only by rounding, but
+//! the messages of the additive metrics are dropped, as they would need all ngrams.
+//!
+//! [`BigramMetric::is_additive`]: crate::metrics::bigram_metrics::BigramMetric::is_additive
+
+use super::{LayoutEvaluator, LayoutScore, Weighted};
+use crate::{
+    corpus::{Corpus, SharedNgrams},
+    metrics::unigram_metrics::UnigramMetric,
+    ngram_mapper::{on_demand_ngram_mapper::OnDemandNgramMapper, NgramMapper},
+    ngrams::{Bigrams, Trigrams, Unigrams},
+    results::{EvaluationResult, MetricResult, MetricResults, MetricType},
+};
+
+use keyboard_layout::{keyboard::KeyIndex, layout::Layout};
+
+use ahash::AHashMap;
+use anyhow::Result;
+use rayon::prelude::*;
+use std::sync::Arc;
+
+/// The ngrams of a corpus together with the positions of the ngrams containing each symbol.
+/// The ngrams are sorted, so that the changed ngrams of a swap are always collected in the
+/// same order.
+#[derive(Debug)]
+struct NgramIndex {
+    unigrams: Vec<(char, f64)>,
+    bigrams: Vec<((char, char), f64)>,
+    trigrams: Vec<((char, char, char), f64)>,
+    /// Positions of the unigrams, bigrams, and trigrams containing a symbol
+    positions: AHashMap<char, [Vec<usize>; 3]>,
+}
+
+impl NgramIndex {
+    fn new(ngrams: &SharedNgrams) -> Self {
+        let mut unigrams: Vec<(char, f64)> = ngrams
+            .unigrams
+            .grams
+            .iter()
+            .map(|(g, w)| (*g, *w))
+            .collect();
+        let mut bigrams: Vec<((char, char), f64)> =
+            ngrams.bigrams.grams.iter().map(|(g, w)| (*g, *w)).collect();
+        let mut trigrams: Vec<((char, char, char), f64)> = ngrams
+            .trigrams
+            .grams
+            .iter()
+            .map(|(g, w)| (*g, *w))
+            .collect();
+        unigrams.sort_by_key(|(gram, _)| *gram);
+        bigrams.sort_by_key(|(gram, _)| *gram);
+        trigrams.sort_by_key(|(gram, _)| *gram);
+
+        let mut positions: AHashMap<char, [Vec<usize>; 3]> = AHashMap::default();
+        for (i, (c, _)) in unigrams.iter().enumerate() {
+            positions.entry(*c).or_default()[0].push(i);
+        }
+        for (i, ((c1, c2), _)) in bigrams.iter().enumerate() {
+            for c in [c1, c2] {
+                positions.entry(*c).or_default()[1].push(i);
+            }
+        }
+        for (i, ((c1, c2, c3), _)) in trigrams.iter().enumerate() {
+            for c in [c1, c2, c3] {
+                positions.entry(*c).or_default()[2].push(i);
+            }
+        }
+
+        Self {
+            unigrams,
+            bigrams,
+            trigrams,
+            positions,
+        }
+    }
+
+    /// The ngrams containing at least one of `symbols`
+    fn containing(&self, symbols: &[char]) -> (Unigrams, Bigrams, Trigrams) {
+        let positions = |n: usize| -> Vec<usize> {
+            let mut positions: Vec<usize> = symbols
+                .iter()
+                .filter_map(|c| self.positions.get(c))
+                .flat_map(|positions| positions[n].iter().copied())
+                .collect();
+            positions.sort_unstable();
+            positions.dedup();
+            positions
+        };
+
+        (
+            Unigrams {
+                grams: positions(0).into_iter().map(|i| self.unigrams[i]).collect(),
+            },
+            Bigrams {
+                grams: positions(1).into_iter().map(|i| self.bigrams[i]).collect(),
+            },
+            Trigrams {
+                grams: positions(2).into_iter().map(|i| self.trigrams[i]).collect(),
+            },
+        )
+    }
+}
+
+/// The score of a layout that can be updated for swaps of keys, see
+/// [`LayoutEvaluator::update_swap`]. Clones share the ngrams of the corpus.
+#[derive(Clone, Debug)]
+pub struct IncrementalScore {
+    layout: Layout,
+    score: LayoutScore,
+    /// Total weights of the mapped unigrams, bigrams, and trigrams given to the metrics
+    total_weights: [f64; 3],
+    corpus: OnDemandNgramMapper,
+    index: Arc<NgramIndex>,
+}
+
+impl IncrementalScore {
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    pub fn score(&self) -> &LayoutScore {
+        &self.score
+    }
+
+    pub fn cost(&self) -> f64 {
+        self.score.total_cost()
+    }
+
+    pub fn into_layout(self) -> Layout {
+        self.layout
+    }
+
+    /// The results of the metrics of type `metric_type`
+    fn results(&self, metric_type: MetricType) -> &MetricResults {
+        self.score
+            .iter()
+            .find(|results| results.metric_type == metric_type)
+            .expect("Results of all metric types of the evaluator")
+    }
+}
+
+/// Sum of the weights of `grams` (in their order)
+fn weight<G>(grams: &[(G, f64)]) -> f64 {
+    grams.iter().map(|(_, w)| w).sum()
+}
+
+/// Whether the cost of a unigram metric can be updated incrementally (the unsplit unigrams are
+/// not tracked)
+fn is_additive_unigram_metric(metric: &(dyn UnigramMetric + 'static)) -> bool {
+    metric.is_additive() && !metric.uses_unsplit_unigrams()
+}
+
+/// The results of the metrics of one ngram type after a swap. Additive metrics change their
+/// cost by `delta`, the others are evaluated completely with `cost`.
+fn update_metrics<M: ?Sized + Send + Sync>(
+    results: &MetricResults,
+    metrics: &[Weighted<M>],
+    is_additive: impl Fn(&M) -> bool + Sync,
+    delta: impl Fn(&M) -> f64 + Sync,
+    cost: impl Fn(&M) -> (f64, Option<String>) + Sync,
+) -> MetricResults {
+    let mut updated = MetricResults::new(
+        results.metric_type.clone(),
+        results.found_weight,
+        results.not_found_weight,
+    );
+    metrics
+        .par_iter()
+        .zip(results.metric_costs.par_iter())
+        .map(|((weight, normalization, metric), previous)| {
+            let (cost, message) = if is_additive(metric) {
+                (previous.core.cost + delta(metric), None)
+            } else {
+                cost(metric)
+            };
+            MetricResult::new(
+                &previous.core.name,
+                cost,
+                message,
+                *weight,
+                normalization.clone(),
+            )
+        })
+        .collect::<Vec<MetricResult>>()
+        .into_iter()
+        .for_each(|mc| updated.add_result(mc));
+
+    updated
+}
+
+impl LayoutEvaluator {
+    /// Evaluate `layout` with respect to the ngrams of `corpus` like
+    /// [`LayoutEvaluator::evaluate`], such that the score can be updated for swaps of keys
+    /// with [`LayoutEvaluator::update_swap`].
+    pub fn evaluate_incremental(&self, layout: Layout, corpus: &Corpus) -> IncrementalScore {
+        let index = Arc::new(NgramIndex::new(corpus.shared_ngrams()));
+        self.incremental_score(layout, self.ngram_mapper(corpus), index)
+    }
+
+    fn incremental_score(
+        &self,
+        layout: Layout,
+        corpus: OnDemandNgramMapper,
+        index: Arc<NgramIndex>,
+    ) -> IncrementalScore {
+        let (score, total_weights) = self.evaluate_with_weights(&layout, &corpus);
+        IncrementalScore {
+            layout,
+            score,
+            total_weights,
+            corpus,
+            index,
+        }
+    }
+
+    /// The score of the layout of `score` (created by this evaluator) with the symbols of the
+    /// keys `k1` and `k2` swapped. Only the ngrams containing the swapped symbols are mapped
+    /// and evaluated for the additive metrics (see the [module docs](self)).
+    ///
+    /// Fails if a key index is out of bounds.
+    pub fn update_swap(
+        &self,
+        score: &IncrementalScore,
+        k1: KeyIndex,
+        k2: KeyIndex,
+    ) -> Result<IncrementalScore> {
+        let layout = score.layout.swap_keys_new(k1, k2)?;
+
+        let swapped: Vec<_> = score
+            .layout
+            .layerkeys
+            .iter()
+            .filter(|layerkey| layerkey.key.index == k1 || layerkey.key.index == k2)
+            .collect();
+        if swapped
+            .iter()
+            .any(|layerkey| layerkey.is_modifier.is_some())
+        {
+            return Ok(self.incremental_score(layout, score.corpus.clone(), score.index.clone()));
+        }
+
+        let symbols: Vec<char> = swapped.iter().map(|layerkey| layerkey.symbol).collect();
+        let (unigrams, bigrams, trigrams) = score.index.containing(&symbols);
+        let changed = OnDemandNgramMapper::with_ngrams(
+            unigrams,
+            bigrams,
+            trigrams,
+            self.ngram_mapper_config.clone(),
+        );
+
+        let ((layout_results, unigram_results), (bigram_results, trigram_results)) = rayon::join(
+            || {
+                rayon::join(
+                    || self.evaluate_layout_metrics(&layout),
+                    || self.update_unigram_metrics(score, &layout, &changed),
+                )
+            },
+            || {
+                rayon::join(
+                    || self.update_bigram_metrics(score, &layout, &changed),
+                    || self.update_trigram_metrics(score, &layout, &changed),
+                )
+            },
+        );
+
+        let mut results: Vec<_> = layout_results.into_iter().collect();
+        let mut total_weights = score.total_weights;
+        let ngram_results = vec![unigram_results, bigram_results, trigram_results];
+        for (i, ngram_results) in ngram_results.into_iter().enumerate() {
+            if let Some((ngram_results, total_weight)) = ngram_results {
+                results.push(ngram_results);
+                total_weights[i] = total_weight;
+            }
+        }
+
+        Ok(IncrementalScore {
+            score: EvaluationResult::new(layout.as_text(), results),
+            layout,
+            total_weights,
+            corpus: score.corpus.clone(),
+            index: score.index.clone(),
+        })
+    }
+
+    /// Update the unigram metrics of `score` for the swap giving `layout`, mapping the
+    /// `changed` unigrams with the layouts before and after the swap.
+    fn update_unigram_metrics(
+        &self,
+        score: &IncrementalScore,
+        layout: &Layout,
+        changed: &OnDemandNgramMapper,
+    ) -> Option<(MetricResults, f64)> {
+        if self.unigram_metrics.is_empty() {
+            return None;
+        }
+
+        let before = changed.map_unigrams(&score.layout);
+        let after = changed.map_unigrams(layout);
+        let mut total_weight =
+            score.total_weights[0] + weight(&after.grams) - weight(&before.grams);
+        let complete = if self
+            .unigram_metrics
+            .iter()
+            .all(|(_, _, m)| is_additive_unigram_metric(m.as_ref()))
+        {
+            None
+        } else {
+            let mapped = score.corpus.map_unigrams(layout);
+            total_weight = weight(&mapped.grams);
+            let unsplit = score.corpus.map_unigrams_unsplit(layout);
+            let unsplit_total_weight = weight(&unsplit.grams);
+            Some((mapped, unsplit, unsplit_total_weight))
+        };
+
+        let results = update_metrics(
+            score.results(MetricType::Unigram),
+            &self.unigram_metrics,
+            is_additive_unigram_metric,
+            |m| {
+                m.total_cost(&after.grams, Some(total_weight), layout).0
+                    - m.total_cost(&before.grams, Some(total_weight), &score.layout)
+                        .0
+            },
+            |m| match &complete {
+                Some((_, unsplit, unsplit_total_weight)) if m.uses_unsplit_unigrams() => {
+                    m.total_cost(&unsplit.grams, Some(*unsplit_total_weight), layout)
+                }
+                Some((mapped, _, _)) => m.total_cost(&mapped.grams, Some(total_weight), layout),
+                None => unreachable!("All unigram metrics are additive"),
+            },
+        );
+
+        Some((results, total_weight))
+    }
+
+    /// Update the bigram metrics of `score` for the swap giving `layout`, see
+    /// [`LayoutEvaluator::update_unigram_metrics`].
+    fn update_bigram_metrics(
+        &self,
+        score: &IncrementalScore,
+        layout: &Layout,
+        changed: &OnDemandNgramMapper,
+    ) -> Option<(MetricResults, f64)> {
+        if self.bigram_metrics.is_empty() {
+            return None;
+        }
+
+        let before = changed.map_bigrams(&score.layout);
+        let after = changed.map_bigrams(layout);
+        let mut total_weight =
+            score.total_weights[1] + weight(&after.grams) - weight(&before.grams);
+        let complete = if self.bigram_metrics.iter().all(|(_, _, m)| m.is_additive()) {
+            None
+        } else {
+            let mapped = score.corpus.map_bigrams(layout);
+            total_weight = weight(&mapped.grams);
+            Some(mapped)
+        };
+
+        let results = update_metrics(
+            score.results(MetricType::Bigram),
+            &self.bigram_metrics,
+            |m| m.is_additive(),
+            |m| {
+                m.total_cost(&after.grams, Some(total_weight), layout).0
+                    - m.total_cost(&before.grams, Some(total_weight), &score.layout)
+                        .0
+            },
+            |m| match &complete {
+                Some(mapped) => m.total_cost(&mapped.grams, Some(total_weight), layout),
+                None => unreachable!("All bigram metrics are additive"),
+            },
+        );
+
+        Some((results, total_weight))
+    }
+
+    /// Update the trigram metrics of `score` for the swap giving `layout`, see
+    /// [`LayoutEvaluator::update_unigram_metrics`].
+    fn update_trigram_metrics(
+        &self,
+        score: &IncrementalScore,
+        layout: &Layout,
+        changed: &OnDemandNgramMapper,
+    ) -> Option<(MetricResults, f64)> {
+        if self.trigram_metrics.is_empty() {
+            return None;
+        }
+
+        let before = changed.map_trigrams(&score.layout);
+        let after = changed.map_trigrams(layout);
+        let mut total_weight =
+            score.total_weights[2] + weight(&after.grams) - weight(&before.grams);
+        let complete = if self.trigram_metrics.iter().all(|(_, _, m)| m.is_additive()) {
+            None
+        } else {
+            let mapped = score.corpus.map_trigrams(layout);
+            total_weight = weight(&mapped.grams);
+            Some(mapped)
+        };
+
+        let results = update_metrics(
+            score.results(MetricType::Trigram),
+            &self.trigram_metrics,
+            |m| m.is_additive(),
+            |m| {
+                m.total_cost(&after.grams, Some(total_weight), layout).0
+                    - m.total_cost(&before.grams, Some(total_weight), &score.layout)
+                        .0
+            },
+            |m| match &complete {
+                Some(mapped) => m.total_cost(&mapped.grams, Some(total_weight), layout),
+                None => unreachable!("All trigram metrics are additive"),
+            },
+        );
+
+        Some((results, total_weight))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metrics::{
+            bigram_metrics::{scissors, sfb, BigramMetric},
+            trigram_metrics::sfs,
+            unigram_metrics::key_costs,
+        },
+        results::NormalizationType,
+    };
+
+    use keyboard_layout::{
+        config::LayoutConfig, keyboard::Keyboard, layout_generator::LayoutGenerator,
+        neo_layout_generator::NeoLayoutGenerator,
+    };
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+    use std::str::FromStr;
+
+    const LAYOUT_CONFIG: &str = include_str!("../../../config/keyboard/sval.yml");
+
+    /// Upper case letters and punctuation are on higher layers
+    const TEXT: &str = "The quick brown fox jumps over the lazy dog. Über den Wolken muss die \
+        Freiheit wohl grenzenlos sein (sagt man)! Zwölf Boxkämpfer jagen Viktor quer über den \
+        großen Sylter Deich; {x} [y] -- \"z\"?";
+
+    /// The Svalboard layout of `config/keyboard/sval.yml` (with layers and modifiers)
+    fn layout() -> Layout {
+        let config = LayoutConfig::from_str(LAYOUT_CONFIG).unwrap();
+        let keyboard = Arc::new(Keyboard::from_yaml_object(config.keyboard));
+        NeoLayoutGenerator::from_object(config.base_layout, keyboard)
+            .generate("?öcäb-üiqy')ezo.,a(umkhjlg{txdw□n}pv□s□fr")
+            .unwrap()
+    }
+
+    /// Additive and non-additive (the scissors with critical bigrams) metrics of all types
+    fn evaluator(layout: &Layout) -> LayoutEvaluator {
+        let sfb: sfb::Parameters = serde_yaml::from_str(
+            "{default_cost: 1.0, costs: {Center: {North: 2.0}}, ignore_thumbs: false}",
+        )
+        .unwrap();
+        let scissors: scissors::Parameters = serde_yaml::from_str(
+            "{costs: {Vertical: 2.0, Diagonal: 1.0}, critical_bigram: {fraction: 0.01, factor: 5.0}}",
+        )
+        .unwrap();
+        let sfs: sfs::Parameters = serde_yaml::from_str("{ignore_thumbs: true}").unwrap();
+
+        let sfb = Box::new(sfb::Sfb::new(&sfb));
+        let scissors = Box::new(scissors::Scissors::new(&scissors));
+        assert!(sfb.is_additive());
+        assert!(!scissors.is_additive());
+
+        LayoutEvaluator::builder()
+            .with_keyboard(layout.keyboard.clone())
+            .with_unigram_metric(
+                Box::new(key_costs::KeyCost::new(&key_costs::Parameters {})),
+                1.0,
+                NormalizationType::WeightFound(1.0),
+            )
+            .with_bigram_metric(sfb, 2.0, NormalizationType::WeightFound(1.0))
+            .with_bigram_metric(scissors, 1.0, NormalizationType::WeightFound(1.0))
+            .with_trigram_metric(
+                Box::new(sfs::Sfs::new(&sfs)),
+                0.5,
+                NormalizationType::Fixed(1.0),
+            )
+            .build()
+            .unwrap()
+    }
+
+    fn costs(score: &LayoutScore) -> Vec<(String, f64)> {
+        score
+            .iter()
+            .flat_map(|results| results.metric_costs.iter())
+            .map(|mc| (mc.core.name.clone(), mc.weighted_cost))
+            .collect()
+    }
+
+    fn assert_close(left: f64, right: f64) {
+        assert!(
+            (left - right).abs() <= 1e-9 * left.abs().max(right.abs()),
+            "{} != {}",
+            left,
+            right
+        );
+    }
+
+    #[test]
+    fn updates_match_complete_evaluations() {
+        let layout = layout();
+        let evaluator = evaluator(&layout);
+        let corpus = Corpus::from_text(TEXT);
+        let n_keys = layout.keyboard.keys.len() as KeyIndex;
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(603);
+
+        let mut score = evaluator.evaluate_incremental(layout, &corpus);
+        let mut modifier_swaps = 0;
+        for _ in 0..300 {
+            // all keys, including the fixed ones and those of the modifiers
+            let k1 = rng.random_range(0..n_keys);
+            let k2 = rng.random_range(0..n_keys);
+            if score.layout().layerkeys.iter().any(|layerkey| {
+                (layerkey.key.index == k1 || layerkey.key.index == k2)
+                    && layerkey.is_modifier.is_some()
+            }) {
+                modifier_swaps += 1;
+            }
+
+            score = evaluator.update_swap(&score, k1, k2).unwrap();
+            let complete = evaluator.evaluate(score.layout(), &corpus);
+
+            assert_eq!(score.score().layout(), complete.layout());
+            for ((name, cost), (complete_name, complete_cost)) in
+                costs(score.score()).into_iter().zip(costs(&complete))
+            {
+                assert_eq!(name, complete_name);
+                assert_close(cost, complete_cost);
+            }
+            assert_close(score.cost(), complete.total_cost());
+        }
+
+        assert!(modifier_swaps > 0);
+    }
+
+    #[test]
+    fn messages_of_additive_metrics_are_dropped() {
+        let layout = layout();
+        let evaluator = evaluator(&layout);
+        let corpus = Corpus::from_text(TEXT);
+        let keys = layout.non_fixed_key_indices();
+
+        let score = evaluator.evaluate_incremental(layout, &corpus);
+        let score = evaluator.update_swap(&score, keys[0], keys[1]).unwrap();
+        let messages: Vec<(&str, bool)> = score
+            .score()
+            .iter()
+            .flat_map(|results| results.metric_costs.iter())
+            .map(|mc| (mc.core.name.as_str(), mc.core.message.is_some()))
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                ("Key Costs", false),
+                ("SFB", false),
+                ("Scissors", true),
+                ("SFS", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_keys_are_errors() {
+        let layout = layout();
+        let evaluator = evaluator(&layout);
+        let n_keys = layout.keyboard.keys.len() as KeyIndex;
+
+        let score = evaluator.evaluate_incremental(layout, &Corpus::from_text(TEXT));
+        assert!(evaluator.update_swap(&score, 0, n_keys).is_err());
+    }
+}
@@ -1,9 +1,15 @@
+pub mod api;
 pub mod cache;
 pub mod config;
+pub mod corpus;
 pub mod evaluation;
+pub mod evaluator;
 pub mod metrics;
 pub mod ngram_mapper;
 pub mod ngrams;
+pub mod optimizer;
+pub mod output_style;
+pub mod presets;
 pub mod results;
 
 #[cfg(test)]
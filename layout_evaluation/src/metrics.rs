@@ -1,19 +1,96 @@
-//! The `metrics` module provides traits for layout, unigram, bigram, and trigram metrics.
+//! The `metrics` module provides traits for layout, unigram, bigram, trigram, and quadgram
+//! metrics.
 
 pub mod bigram_metrics;
 pub mod format_utils;
+pub mod key_pair_table;
 pub mod layout_metrics;
+pub mod quadgram_metrics;
+pub mod same_finger_filter;
 pub mod trigram_metrics;
 pub mod unigram_metrics;
+pub mod utils;
+pub mod worst_ngrams;
 
-/// Helper function to convert weight to percentage
+use ahash::AHashMap;
+use keyboard_layout::{
+    key::{Finger, FingerMap, Hand},
+    layout::LayerKey,
+};
+use serde::Deserialize;
+
+/// Per-finger cost multipliers as configured in metric parameters.
+///
+/// Fingers without an entry (and all fingers of an unconfigured, i.e. default, instance)
+/// have a factor of 1.0.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct FingerFactors(pub AHashMap<Finger, f64>);
+
+impl FingerFactors {
+    /// The factor for the given finger (1.0 if not configured)
+    #[inline]
+    pub fn get(&self, finger: Finger) -> f64 {
+        self.0.get(&finger).copied().unwrap_or(1.0)
+    }
+
+    /// The factor for a bigram of two fingers: the maximum of both factors, since the weaker
+    /// finger dominates comfort
+    #[inline]
+    pub fn pair(&self, k1: &LayerKey, k2: &LayerKey) -> f64 {
+        self.get(k1.key.finger).max(self.get(k2.key.finger))
+    }
+
+    /// Convert into a [`FingerMap`] for fast lookups in hot loops
+    pub fn to_finger_map(&self) -> FingerMap<f64> {
+        FingerMap::with_hashmap(&self.0, 1.0)
+    }
+}
+
+/// Whether moving from `k1` to `k2` goes inwards, i.e. towards the center of the keyboard
 ///
-/// This is used by stats metrics to calculate percentages from frequencies.
-#[inline]
-pub(crate) fn to_percentage(weight: f64, total: f64) -> f64 {
-    if total > 0.0 {
-        (weight / total) * 100.0
+/// This means increasing matrix columns on the left hand and decreasing ones on the right
+/// hand. The result is only meaningful for keys on the same hand.
+#[inline(always)]
+pub(crate) fn inwards(k1: &LayerKey, k2: &LayerKey) -> bool {
+    if k1.key.hand == Hand::Left {
+        k1.key.matrix_position.0 < k2.key.matrix_position.0
     } else {
-        0.0
+        k1.key.matrix_position.0 > k2.key.matrix_position.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_layout};
+
+    fn is_inwards(bigram: &str) -> bool {
+        let layout = toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP");
+        let keys = layerkeys(&layout, bigram);
+        inwards(keys[0], keys[1])
+    }
+
+    #[test]
+    fn inwards_on_the_left_hand() {
+        // pinky to index finger and Center to In of the pinky
+        assert!(is_inwards("cr"));
+        assert!(is_inwards("cd"));
+        assert!(!is_inwards("rc"));
+        assert!(!is_inwards("cb"));
+        // same column
+        assert!(!is_inwards("ca"));
+    }
+
+    #[test]
+    fn inwards_on_the_right_hand() {
+        // pinky to index finger and Center to In of the index finger
+        assert!(is_inwards("Lw"));
+        assert!(is_inwards("wv"));
+        assert!(!is_inwards("wL"));
+        assert!(!is_inwards("wx"));
+        // same column
+        assert!(!is_inwards("wu"));
     }
 }
@@ -1,34 +1,71 @@
 //! The `metrics` module provides a trait for bigram metrics.
-use keyboard_layout::layout::{LayerKey, Layout};
+use keyboard_layout::{
+    keyboard::Keyboard,
+    layout::{LayerKey, Layout},
+};
 
-use super::format_utils::{format_percentages, visualize_whitespace};
-use ordered_float::OrderedFloat;
-use priority_queue::DoublePriorityQueue;
-use std::{env, fmt};
+use super::{
+    format_utils::{format_applicable_weight, format_percentages, visualize_ngram},
+    worst_ngrams::{WorstParameters, WorstTracker},
+};
+use crate::{output_style::ColorConfig, results::NormalizationType};
+use serde::Deserialize;
+use std::fmt;
 
 pub mod bigram_stats;
+pub mod classification;
+pub mod consecutive_finger_use;
 pub mod finger_repeats;
 pub mod fsb;
 pub mod hsb;
+pub mod key_repeats;
 pub mod kla_distance;
 pub mod kla_finger_usage;
 pub mod kla_same_finger;
 pub mod kla_same_hand;
+pub mod lsb;
 pub mod manual_bigram_penalty;
 pub mod movement_pattern;
 pub mod no_handswitch_after_unbalancing_key;
+pub mod off_center_chaining;
 pub mod oxey_lsbs;
 pub mod oxey_sfbs;
-mod scissor_base;
+pub mod scissor_base;
+pub mod scissors;
 pub mod sfb;
 pub mod symmetric_handswitches;
 
+/// Heavier penalty for ngrams that make up a large share of the corpus
+#[derive(Clone, Copy, Deserialize, Debug, Default)]
+pub struct CriticalBigramParams {
+    /// Minimum relative frequency to apply the penalty (as fraction, e.g., 0.0004 = 0.04%)
+    pub fraction: Option<f64>,
+    /// Multiplier for ngrams above `fraction` (e.g., 100.0 = 100x penalty)
+    pub factor: Option<f64>,
+}
+
+impl CriticalBigramParams {
+    /// `factor` if `weight` exceeds `fraction` of `total_weight` (and both are configured),
+    /// 1 otherwise.
+    #[inline]
+    pub fn multiplier(&self, weight: f64, total_weight: f64) -> f64 {
+        match (self.fraction, self.factor) {
+            (Some(fraction), Some(factor)) if weight / total_weight > fraction => factor,
+            _ => 1.0,
+        }
+    }
+}
+
 /// BigramMetric is a trait for metrics that iterates over weighted bigrams.
 pub trait BigramMetric: Send + Sync + BigramMetricClone + fmt::Debug {
     /// Return the name of the metric.
     fn name(&self) -> &str;
 
     /// Compute the cost of one bigram (if that is possible, otherwise, return `None`).
+    ///
+    /// Bigrams the metric does not apply to (e.g. those on different hands for a same-finger
+    /// metric) should return `None` rather than `Some(0.0)`, so that their weight is not part
+    /// of the applicable weight in the message of [`BigramMetric::total_cost`].
     #[inline(always)]
     fn individual_cost(
         &self,
@@ -41,7 +78,47 @@ pub trait BigramMetric: Send + Sync + BigramMetricClone + fmt::Debug {
         None
     }
 
+    /// Whether [`BigramMetric::total_cost`] is the sum of the individual costs and each individual cost
+    /// is proportional to the weight of its bigram. Then, the cost can be updated from the
+    /// bigrams that change when two keys are swapped, see
+    /// [`LayoutEvaluator::update_swap`](crate::evaluator::LayoutEvaluator::update_swap).
+    fn is_additive(&self) -> bool {
+        false
+    }
+
+    /// Precompute what does not depend on the layout for the keys of `keyboard`, e.g. a
+    /// [`KeyPairTable`](crate::metrics::key_pair_table::KeyPairTable) of base costs. Called
+    /// once by [`Evaluator::precompute`](crate::evaluation::Evaluator::precompute). Keys of
+    /// other keyboards are not in the tables and are costed directly, so the costs do not
+    /// change by precomputing.
+    fn precompute(&mut self, _keyboard: &Keyboard) {}
+
+    /// Style the message of [`BigramMetric::total_cost`] according to `color` instead of the
+    /// global output style (see [`crate::output_style`]). Called by
+    /// [`Evaluator::with_color_config`](crate::evaluation::Evaluator::with_color_config).
+    fn set_color_config(&mut self, _color: ColorConfig) {}
+
+    /// Which of the most expensive bigrams to list in the message of [`BigramMetric::total_cost`].
+    fn worst_parameters(&self) -> WorstParameters {
+        WorstParameters::default()
+    }
+
+    /// Named shares (in percent) that can be capped with `max_allowed` in the evaluation
+    /// config, see [`crate::results::MaxAllowed`].
+    fn percentages(
+        &self,
+        _bigrams: &[((&LayerKey, &LayerKey), f64)],
+        _total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> Vec<(&'static str, f64)> {
+        Vec::new()
+    }
+
     /// Compute the total cost for the metric.
+    ///
+    /// The message lists the worst bigrams with their shares of the total cost and of the
+    /// applicable weight (that of the bigrams with an individual cost). If the metric does not
+    /// apply to all bigrams, the applicable share of the total weight is listed first.
     fn total_cost(
         &self,
         bigrams: &[((&LayerKey, &LayerKey), f64)],
@@ -49,73 +126,66 @@ pub trait BigramMetric: Send + Sync + BigramMetricClone + fmt::Debug {
         total_weight: Option<f64>,
         layout: &Layout,
     ) -> (f64, Option<String>) {
-        let show_worst: bool = env::var("SHOW_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(true);
-        let n_worst: usize = env::var("N_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(3);
+        let mut worst = WorstTracker::new(self.worst_parameters().selection());
 
         let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
-        let cost_iter = bigrams
-            .iter()
-            .enumerate()
-            .filter_map(|(i, (bigram, weight))| {
-                let cost_option =
-                    self.individual_cost(bigram.0, bigram.1, *weight, total_weight, layout);
-
-                cost_option.map(|cost| (i, bigram, cost))
-            });
-
-        let (total_cost, msg) = if show_worst {
-            let (total_cost, worst) = cost_iter.fold(
-                (0.0, DoublePriorityQueue::new()),
-                |(mut total_cost, mut worst), (i, _bigram, cost)| {
-                    total_cost += cost;
 
-                    worst.push(i, OrderedFloat(cost));
+        if !worst.is_enabled() {
+            let total_cost: f64 = bigrams
+                .iter()
+                .filter_map(|(bigram, weight)| {
+                    self.individual_cost(bigram.0, bigram.1, *weight, total_weight, layout)
+                })
+                .sum();
 
-                    if worst.len() > n_worst {
-                        worst.pop_min();
-                    }
+            return (total_cost, None);
+        }
 
-                    (total_cost, worst)
-                },
-            );
-
-            let worst_msgs: Vec<String> = worst
-                .into_sorted_iter()
-                .rev()
-                .filter(|(_, cost)| cost.into_inner() > 0.0)
-                .map(|(i, cost)| {
-                    let (gram, weight) = bigrams[i];
-                    let freq_pct = 100.0 * weight / total_weight;
-                    let cost_pct = 100.0 * cost.into_inner() / total_cost;
-                    let percentages = format_percentages(cost_pct, freq_pct);
-                    let bigram_str = format!("{}{}", gram.0, gram.1);
-                    format!("{} {}", visualize_whitespace(&bigram_str), percentages)
-                })
-                .collect();
+        let mut total_cost = 0.0;
+        let mut applicable_weight = 0.0;
+        let mut all_applicable = true;
+        for (i, (bigram, weight)) in bigrams.iter().enumerate() {
+            match self.individual_cost(bigram.0, bigram.1, *weight, total_weight, layout) {
+                Some(cost) => {
+                    worst.push(i, cost);
+                    total_cost += cost;
+                    applicable_weight += weight;
+                }
+                None => all_applicable = false,
+            }
+        }
 
-            let msg = if !worst_msgs.is_empty() {
-                Some(worst_msgs.join(", "))
-            } else {
-                None
-            };
+        let worst_msgs: Vec<String> = worst
+            .into_sorted(total_cost)
+            .into_iter()
+            .map(|(i, cost)| {
+                let (gram, weight) = bigrams[i];
+                let percentages = format_percentages(cost, total_cost, weight, applicable_weight);
+                format!("{} {}", visualize_ngram(&[gram.0, gram.1]), percentages)
+            })
+            .collect();
 
-            (total_cost, msg)
-        } else {
-            let total_cost: f64 = cost_iter.map(|(_, _, c)| c).sum();
+        let mut msg_parts = Vec::new();
+        if !all_applicable {
+            msg_parts.push(format_applicable_weight(applicable_weight, total_weight));
+        }
+        if !worst_msgs.is_empty() {
+            msg_parts.push(worst_msgs.join(", "));
+        }
 
-            (total_cost, None)
+        let msg = if !msg_parts.is_empty() {
+            Some(msg_parts.join("; "))
+        } else {
+            None
         };
 
         (total_cost, msg)
     }
 }
 
+/// A bigram metric with its weight and normalization, as configured in the evaluator
+pub type WeightedBigramMetric = (f64, NormalizationType, Box<dyn BigramMetric>);
+
 impl Clone for Box<dyn BigramMetric> {
     fn clone(&self) -> Box<dyn BigramMetric> {
         self.clone_box()
@@ -135,3 +205,160 @@ where
         Box::new(self.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_layout};
+
+    /// Costs the bigram "ab" `weight` and all others `-factor * weight`
+    #[derive(Clone, Debug)]
+    struct SignedCost {
+        factor: f64,
+        worst: WorstParameters,
+    }
+
+    impl SignedCost {
+        fn new(factor: f64) -> Self {
+            Self {
+                factor,
+                worst: WorstParameters {
+                    n_worst: Some(3),
+                    worst_threshold_pct: None,
+                },
+            }
+        }
+    }
+
+    impl BigramMetric for SignedCost {
+        fn name(&self) -> &str {
+            "Signed Cost"
+        }
+
+        fn individual_cost(
+            &self,
+            key1: &LayerKey,
+            key2: &LayerKey,
+            weight: f64,
+            _total_weight: f64,
+            _layout: &Layout,
+        ) -> Option<f64> {
+            if (key1.symbol, key2.symbol) == ('a', 'b') {
+                Some(weight)
+            } else {
+                Some(-self.factor * weight)
+            }
+        }
+
+        fn worst_parameters(&self) -> WorstParameters {
+            self.worst
+        }
+    }
+
+    fn total_cost_message(factor: f64) -> (f64, String) {
+        let layout = toy_layout("ab");
+        let ab = layerkeys(&layout, "ab");
+        let ba = layerkeys(&layout, "ba");
+        let bigrams = [((ab[0], ab[1]), 1.0), ((ba[0], ba[1]), 1.0)];
+
+        let (cost, message) = SignedCost::new(factor).total_cost(&bigrams, None, &layout);
+        (cost, message.unwrap())
+    }
+
+    #[test]
+    fn worst_bigrams_of_zero_total_cost() {
+        let (cost, message) = total_cost_message(1.0);
+        assert_eq!(cost, 0.0);
+        assert!(message.contains("ab") && message.contains("0.0%|50.00%"));
+        assert!(!message.contains("NaN") && !message.contains("inf"));
+    }
+
+    #[test]
+    fn worst_bigrams_of_negative_total_cost() {
+        let (cost, message) = total_cost_message(2.0);
+        assert_eq!(cost, -1.0);
+        assert!(message.contains("ab") && message.contains("0.0%|50.00%"));
+        assert!(!message.contains("NaN") && !message.contains("-"));
+    }
+
+    #[test]
+    fn worst_bigrams_stay_on_one_line() {
+        let layout = toy_layout("\na");
+        let newlines = layerkeys(&layout, "\n\n");
+        let bigrams = [((newlines[0], newlines[1]), 1.0)];
+
+        let (_, message) = SignedCost::new(-1.0).total_cost(&bigrams, None, &layout);
+        let message = message.unwrap();
+        assert!(message.contains("⏎⏎"), "{}", message);
+        assert!(!message.contains('\n'));
+    }
+
+    #[test]
+    fn worst_bigrams_above_threshold() {
+        let layout = toy_layout("abc");
+        let bigrams: Vec<Vec<&LayerKey>> = ["ab", "ba", "ca"]
+            .iter()
+            .map(|b| layerkeys(&layout, b))
+            .collect();
+        // costs 1.0, 3.0, 6.0 (total 10.0)
+        let bigrams = [
+            ((bigrams[0][0], bigrams[0][1]), 1.0),
+            ((bigrams[1][0], bigrams[1][1]), 1.0),
+            ((bigrams[2][0], bigrams[2][1]), 2.0),
+        ];
+
+        let metric = SignedCost {
+            factor: -3.0,
+            worst: WorstParameters {
+                n_worst: Some(1),
+                worst_threshold_pct: Some(20.0),
+            },
+        };
+        let (cost, message) = metric.total_cost(&bigrams, None, &layout);
+        assert_eq!(cost, 10.0);
+        let message = message.unwrap();
+        assert!(message.starts_with("ca"), "{}", message);
+        assert!(message.contains(", ba") && !message.contains("ab"));
+    }
+
+    /// Costs the bigrams starting with "a" their weight and does not apply to the others
+    #[derive(Clone, Debug)]
+    struct StartingWithA;
+
+    impl BigramMetric for StartingWithA {
+        fn name(&self) -> &str {
+            "Starting With A"
+        }
+
+        fn individual_cost(
+            &self,
+            key1: &LayerKey,
+            _key2: &LayerKey,
+            weight: f64,
+            _total_weight: f64,
+            _layout: &Layout,
+        ) -> Option<f64> {
+            (key1.symbol == 'a').then(|| weight)
+        }
+
+        fn worst_parameters(&self) -> WorstParameters {
+            SignedCost::new(1.0).worst
+        }
+    }
+
+    #[test]
+    fn worst_bigrams_relate_to_the_applicable_weight() {
+        let layout = toy_layout("ab");
+        let ab = layerkeys(&layout, "ab");
+        let ba = layerkeys(&layout, "ba");
+        let bigrams = [((ab[0], ab[1]), 1.0), ((ba[0], ba[1]), 3.0)];
+
+        let (cost, message) = StartingWithA.total_cost(&bigrams, None, &layout);
+        let message = message.unwrap();
+
+        assert_eq!(cost, 1.0);
+        assert!(message.starts_with("applicable: 25.0%"), "{}", message);
+        assert!(message.contains("ab") && message.contains("100.0%|100.00%"));
+    }
+}
@@ -1,37 +1,81 @@
 //! Bigram statistics metric that tracks percentages of various bigram categories.
-//! This is informational only and not used for optimization.
+//! By default, this is informational only and not used for optimization. With
+//! `category_costs`, the weights of the categories are combined into a cost.
+//!
+//! The statistics are also available as [`BigramStatistics`] via
+//! [`BigramStats::compute_statistics`] for programmatic use. The categories are determined by
+//! [`classify_bigram`]. Whether a same-finger bigram counts as SFB is decided by the
+//! [`SameFingerFilter`] shared with [`super::sfb::Sfb`], so with the same filter options the SFB
+//! share covers exactly the bigrams the SFB metric charges.
 
 use super::{
+    classification::{classify_bigram, BigramClass},
     scissor_base::{classify_scissor, ScissorType},
     BigramMetric,
 };
-
-use colored::Colorize;
-use keyboard_layout::{
-    key::{Direction, Finger},
-    layout::{LayerKey, Layout},
+use crate::{
+    metrics::same_finger_filter::{self, SameFingerFilter},
+    output_style::underline,
 };
 
-use serde::Deserialize;
+use ahash::AHashMap;
+use keyboard_layout::layout::{LayerKey, Layout};
+
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
-    pub ignore_thumbs: bool,
-    pub ignore_modifiers: bool,
-    /// List of SFB movements to ignore from the count (e.g., [[Center, South], [In, South]])
-    #[serde(default = "default_ignore_movements")]
-    pub ignore_movements: Vec<(Direction, Direction)>,
+    /// Which bigrams are skipped (`ignore_thumbs` and the modifier options apply to all
+    /// categories, the others to the SFB share only)
+    #[serde(flatten)]
+    pub filter: same_finger_filter::Parameters,
+    /// Cost per unit of bigram weight of each category (e.g. `Sfb: 1.0`, `Lateral: 0.2`).
+    /// Without it, the metric is informational only and costs nothing.
+    #[serde(default)]
+    pub category_costs: Option<AHashMap<BigramStatCategory, f64>>,
+}
+
+/// Categories of the bigram statistics
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum BigramStatCategory {
+    #[serde(alias = "SFB")]
+    Sfb,
+    Vertical,
+    Squeeze,
+    Splay,
+    Diagonal,
+    Lateral,
+    #[serde(alias = "LSB")]
+    Lsb,
+    ExtendUp,
+    ExtendDown,
+}
+
+impl BigramStatCategory {
+    /// Number of categories, e.g. for arrays indexed by `category as usize`
+    pub const COUNT: usize = 9;
 }
 
-fn default_ignore_movements() -> Vec<(Direction, Direction)> {
-    vec![]
+/// Shares of the bigram categories in percent of the total bigram weight
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BigramStatistics {
+    pub sfb_percent: f64,
+    pub vertical_percent: f64,
+    pub squeeze_percent: f64,
+    pub splay_percent: f64,
+    pub diagonal_percent: f64,
+    pub lateral_percent: f64,
+    pub lsb_percent: f64,
+    /// Adjacent fingers both pressing North (rolls or other bigrams in [`classify_bigram`])
+    pub extend_up_percent: f64,
+    /// Adjacent fingers both pressing South (rolls or other bigrams in [`classify_bigram`])
+    pub extend_down_percent: f64,
 }
 
 #[derive(Clone, Debug)]
 pub struct BigramStats {
-    ignore_thumbs: bool,
-    ignore_modifiers: bool,
-    ignore_movements: Vec<(Direction, Direction)>,
+    filter: SameFingerFilter,
+    category_costs: Option<AHashMap<BigramStatCategory, f64>>,
 }
 
 /// Format a percentage with up to 2 meaningful decimal places (strips trailing zeros)
@@ -45,23 +89,91 @@ fn format_percentage(value: f64) -> String {
 impl BigramStats {
     pub fn new(params: &Parameters) -> Self {
         Self {
-            ignore_thumbs: params.ignore_thumbs,
-            ignore_modifiers: params.ignore_modifiers,
-            ignore_movements: params.ignore_movements.clone(),
+            filter: SameFingerFilter::new(&params.filter),
+            category_costs: params.category_costs.clone(),
         }
     }
 
-    fn should_ignore_key(&self, key: &LayerKey) -> bool {
-        (self.ignore_thumbs && key.key.finger == Finger::Thumb)
-            || (self.ignore_modifiers && key.is_modifier.is_some())
+    /// Compute the shares of all bigram categories.
+    pub fn compute_statistics(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+    ) -> BigramStatistics {
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+
+        Self::statistics(&self.category_weights(bigrams), total_weight)
     }
 
-    /// Check if this SFB movement should be ignored from statistics
-    fn should_ignore_movement(&self, k1: &LayerKey, k2: &LayerKey) -> bool {
-        let dir_from = k1.key.direction;
-        let dir_to = k2.key.direction;
+    /// Sum up the bigram weights per category (indexed by `category as usize`).
+    fn category_weights(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+    ) -> [f64; BigramStatCategory::COUNT] {
+        let mut weights = [0.0; BigramStatCategory::COUNT];
+
+        for ((k1, k2), weight) in bigrams {
+            // Skip ignored keys for all categories
+            if self.filter.excludes_keys(k1, k2) {
+                continue;
+            }
 
-        self.ignore_movements.contains(&(dir_from, dir_to))
+            let category = match classify_bigram(k1, k2) {
+                BigramClass::SameKeyRepeat | BigramClass::Sfb => {
+                    if !self.filter.is_same_finger(k1, k2) {
+                        continue;
+                    }
+                    BigramStatCategory::Sfb
+                }
+                BigramClass::Scissor(scissor_type) => Self::scissor_category(scissor_type),
+                BigramClass::LateralStretch => BigramStatCategory::Lsb,
+                // extensions are not scissors in `classify_bigram`, but counted separately
+                BigramClass::Roll { adjacent: true, .. } | BigramClass::Other => {
+                    match classify_scissor(k1, k2) {
+                        Some(scissor_type) => Self::scissor_category(scissor_type),
+                        None => continue,
+                    }
+                }
+                // all other classes are not part of the statistics
+                _ => continue,
+            };
+            weights[category as usize] += weight;
+        }
+
+        weights
+    }
+
+    fn scissor_category(scissor_type: ScissorType) -> BigramStatCategory {
+        match scissor_type {
+            ScissorType::Vertical => BigramStatCategory::Vertical,
+            ScissorType::Squeeze => BigramStatCategory::Squeeze,
+            ScissorType::Splay => BigramStatCategory::Splay,
+            ScissorType::Diagonal => BigramStatCategory::Diagonal,
+            ScissorType::Lateral => BigramStatCategory::Lateral,
+            ScissorType::ExtendUp => BigramStatCategory::ExtendUp,
+            ScissorType::ExtendDown => BigramStatCategory::ExtendDown,
+        }
+    }
+
+    fn statistics(
+        weights: &[f64; BigramStatCategory::COUNT],
+        total_weight: f64,
+    ) -> BigramStatistics {
+        let to_pct = |category: BigramStatCategory| {
+            crate::metrics::utils::to_percentage(weights[category as usize], total_weight)
+        };
+
+        BigramStatistics {
+            sfb_percent: to_pct(BigramStatCategory::Sfb),
+            vertical_percent: to_pct(BigramStatCategory::Vertical),
+            squeeze_percent: to_pct(BigramStatCategory::Squeeze),
+            splay_percent: to_pct(BigramStatCategory::Splay),
+            diagonal_percent: to_pct(BigramStatCategory::Diagonal),
+            lateral_percent: to_pct(BigramStatCategory::Lateral),
+            lsb_percent: to_pct(BigramStatCategory::Lsb),
+            extend_up_percent: to_pct(BigramStatCategory::ExtendUp),
+            extend_down_percent: to_pct(BigramStatCategory::ExtendDown),
+        }
     }
 }
 
@@ -70,92 +182,70 @@ impl BigramMetric for BigramStats {
         "Bigram Statistics"
     }
 
+    fn percentages(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> Vec<(&'static str, f64)> {
+        let stats = self.compute_statistics(bigrams, total_weight);
+
+        vec![
+            ("sfb", stats.sfb_percent),
+            ("vertical", stats.vertical_percent),
+            ("squeeze", stats.squeeze_percent),
+            ("splay", stats.splay_percent),
+            ("diagonal", stats.diagonal_percent),
+            ("lateral", stats.lateral_percent),
+            ("lsb", stats.lsb_percent),
+            ("extend_up", stats.extend_up_percent),
+            ("extend_down", stats.extend_down_percent),
+        ]
+    }
+
     fn total_cost(
         &self,
         bigrams: &[((&LayerKey, &LayerKey), f64)],
         total_weight: Option<f64>,
         _layout: &Layout,
     ) -> (f64, Option<String>) {
-        let mut sfb_weight = 0.0;
-        let mut full_vertical_weight = 0.0;
-        let mut squeeze_weight = 0.0;
-        let mut splay_weight = 0.0;
-        let mut diagonal_weight = 0.0;
-        let mut lateral_weight = 0.0;
-
         let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
-
-        for ((k1, k2), weight) in bigrams {
-            // Skip same-key repeats
-            if k1 == k2 {
-                continue;
-            }
-
-            // Skip ignored keys for all metrics
-            if self.should_ignore_key(k1) || self.should_ignore_key(k2) {
-                continue;
-            }
-
-            // Check for SFB
-            if k1.key.hand == k2.key.hand && k1.key.finger == k2.key.finger {
-                if !self.should_ignore_movement(k1, k2) {
-                    sfb_weight += weight;
-                }
-            }
-
-            // Check for scissor categories using shared classification function
-            if let Some(scissor_type) = classify_scissor(k1, k2) {
-                match scissor_type {
-                    ScissorType::Vertical => full_vertical_weight += weight,
-                    ScissorType::Squeeze => squeeze_weight += weight,
-                    ScissorType::Splay => splay_weight += weight,
-                    ScissorType::Diagonal => diagonal_weight += weight,
-                    ScissorType::Lateral => lateral_weight += weight,
-                }
-            }
-        }
-
-        let sfb_percentage = crate::metrics::to_percentage(sfb_weight, total_weight);
-        let full_vertical_percentage =
-            crate::metrics::to_percentage(full_vertical_weight, total_weight);
-        let squeeze_percentage = crate::metrics::to_percentage(squeeze_weight, total_weight);
-        let splay_percentage = crate::metrics::to_percentage(splay_weight, total_weight);
-        let diagonal_percentage = crate::metrics::to_percentage(diagonal_weight, total_weight);
-        let lateral_percentage = crate::metrics::to_percentage(lateral_weight, total_weight);
+        let weights = self.category_weights(bigrams);
+        let stats = Self::statistics(&weights, total_weight);
 
         // Build message with category groups separated by semicolons
         let mut groups = Vec::new();
 
         // SFB group
-        if sfb_percentage > 0.0 {
+        if stats.sfb_percent > 0.0 {
             groups.push(format!(
                 "{}: {}%",
-                "SFB".underline(),
-                format_percentage(sfb_percentage)
+                underline("SFB"),
+                format_percentage(stats.sfb_percent)
             ));
         }
 
         // Full Scissors group (Vertical, Squeeze, Splay)
         let mut full_scissors = Vec::new();
-        if full_vertical_percentage > 0.0 {
+        if stats.vertical_percent > 0.0 {
             full_scissors.push(format!(
                 "{}: {}%",
-                "Vertical".underline(),
-                format_percentage(full_vertical_percentage)
+                underline("Vertical"),
+                format_percentage(stats.vertical_percent)
             ));
         }
-        if squeeze_percentage > 0.0 {
+        if stats.squeeze_percent > 0.0 {
             full_scissors.push(format!(
                 "{}: {}%",
-                "Squeeze".underline(),
-                format_percentage(squeeze_percentage)
+                underline("Squeeze"),
+                format_percentage(stats.squeeze_percent)
             ));
         }
-        if splay_percentage > 0.0 {
+        if stats.splay_percent > 0.0 {
             full_scissors.push(format!(
                 "{}: {}%",
-                "Splay".underline(),
-                format_percentage(splay_percentage)
+                underline("Splay"),
+                format_percentage(stats.splay_percent)
             ));
         }
         if !full_scissors.is_empty() {
@@ -164,27 +254,116 @@ impl BigramMetric for BigramStats {
 
         // Half Scissors group (Diagonal, Lateral)
         let mut half_scissors = Vec::new();
-        if diagonal_percentage > 0.0 {
+        if stats.diagonal_percent > 0.0 {
             half_scissors.push(format!(
                 "{}: {}%",
-                "Diagonal".underline(),
-                format_percentage(diagonal_percentage)
+                underline("Diagonal"),
+                format_percentage(stats.diagonal_percent)
             ));
         }
-        if lateral_percentage > 0.0 {
+        if stats.lateral_percent > 0.0 {
             half_scissors.push(format!(
                 "{}: {}%",
-                "Lateral".underline(),
-                format_percentage(lateral_percentage)
+                underline("Lateral"),
+                format_percentage(stats.lateral_percent)
             ));
         }
         if !half_scissors.is_empty() {
             groups.push(half_scissors.join(", "));
         }
 
+        // Lateral stretch group
+        if stats.lsb_percent > 0.0 {
+            groups.push(format!(
+                "{}: {}%",
+                underline("LSB"),
+                format_percentage(stats.lsb_percent)
+            ));
+        }
+
+        // Extension group (ExtendUp, ExtendDown)
+        let mut extensions = Vec::new();
+        if stats.extend_up_percent > 0.0 {
+            extensions.push(format!(
+                "{}: {}%",
+                underline("Extend Up"),
+                format_percentage(stats.extend_up_percent)
+            ));
+        }
+        if stats.extend_down_percent > 0.0 {
+            extensions.push(format!(
+                "{}: {}%",
+                underline("Extend Down"),
+                format_percentage(stats.extend_down_percent)
+            ));
+        }
+        if !extensions.is_empty() {
+            groups.push(extensions.join(", "));
+        }
+
         let message = groups.join("; ");
 
-        // Return 0 cost since this is informational only
-        (0.0, Some(message))
+        // Without category costs, the metric is informational only
+        let cost = self.category_costs.as_ref().map_or(0.0, |category_costs| {
+            category_costs
+                .iter()
+                .map(|(category, cost)| weights[*category as usize] * cost)
+                .sum()
+        });
+
+        (cost, Some(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_layout};
+
+    fn bigram_stats(category_costs: &str) -> BigramStats {
+        let params: Parameters = serde_yaml::from_str(&format!(
+            "
+            ignore_thumbs: false
+            category_costs: {}
+            ",
+            category_costs
+        ))
+        .unwrap();
+        BigramStats::new(&params)
+    }
+
+    /// The statistics of bigrams with weight 1.0 each. The left pinky has "a" (North), "b"
+    /// (Out), "c" (Center), "d" (In), and "e" (South), the left ring finger "f" to "j".
+    fn statistics(metric: &BigramStats, bigrams: &[&str]) -> BigramStatistics {
+        let layout = toy_layout("abcdefghij");
+        let keys: Vec<Vec<&LayerKey>> = bigrams.iter().map(|b| layerkeys(&layout, b)).collect();
+        let bigrams: Vec<((&LayerKey, &LayerKey), f64)> =
+            keys.iter().map(|k| ((k[0], k[1]), 1.0)).collect();
+        metric.compute_statistics(&bigrams, None)
+    }
+
+    #[test]
+    fn extensions_are_counted_separately() {
+        let stats = statistics(&bigram_stats("null"), &["af", "fa", "ej", "aj"]);
+        assert_eq!(stats.extend_up_percent, 50.0);
+        assert_eq!(stats.extend_down_percent, 25.0);
+        assert_eq!(stats.vertical_percent, 25.0);
+        assert_eq!(stats.diagonal_percent, 0.0);
+    }
+
+    #[test]
+    fn extensions_can_have_category_costs() {
+        let layout = toy_layout("abcdefghij");
+        let af = layerkeys(&layout, "af");
+        let ej = layerkeys(&layout, "ej");
+        let bigrams = [((af[0], af[1]), 1.0), ((ej[0], ej[1]), 2.0)];
+
+        let metric = bigram_stats("{ExtendUp: 1.0, ExtendDown: 0.5}");
+        let (cost, message) = metric.total_cost(&bigrams, None, &layout);
+        assert_eq!(cost, 2.0);
+        let message = message.unwrap();
+        assert!(message.contains("Extend Up") && message.contains("33.33%"));
+        assert!(message.contains("Extend Down") && message.contains("66.67%"));
     }
 }
@@ -0,0 +1,320 @@
+//! Classification of bigrams into same-finger bigrams, scissors, rolls and the like.
+//!
+//! This is the bigram counterpart of [`crate::metrics::trigram_metrics::classification`].
+//! [`super::bigram_stats::BigramStats`] is built on top of it, so its statistics always agree
+//! with custom analyses using [`classify_bigram`].
+
+use super::scissor_base::{classify_scissor, is_lateral_stretch, ScissorType};
+use crate::metrics::inwards;
+
+use keyboard_layout::{key::Finger, layout::LayerKey};
+
+/// Class of a bigram
+///
+/// The classes are mutually exclusive and checked in the order of the variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BigramClass {
+    /// The same key twice (e.g. "ee" in "feed")
+    SameKeyRepeat,
+    /// Two keys on different hands
+    HandSwitch,
+    /// Two different keys of the same finger (including thumbs)
+    Sfb,
+    /// Two different fingers of the same hand, at least one of them a thumb
+    ThumbInvolved,
+    /// Adjacent fingers performing a scissor movement, see [`classify_scissor`] (except
+    /// [`ScissorType::ExtendUp`] and [`ScissorType::ExtendDown`], which are rolls or other
+    /// bigrams in the same vertical direction)
+    Scissor(ScissorType),
+    /// Adjacent fingers both pulled sideways in the same sense, see [`is_lateral_stretch`]
+    LateralStretch,
+    /// Two different fingers of the same hand moving inwards or outwards
+    Roll {
+        /// Whether the roll goes towards the center of the keyboard
+        inward: bool,
+        /// Whether the fingers are neighbors
+        adjacent: bool,
+    },
+    /// Anything else, e.g. two fingers in the same matrix column
+    Other,
+}
+
+/// Classify a bigram into its [`BigramClass`].
+pub fn classify_bigram(k1: &LayerKey, k2: &LayerKey) -> BigramClass {
+    if k1 == k2 {
+        return BigramClass::SameKeyRepeat;
+    }
+
+    if k1.key.hand != k2.key.hand {
+        return BigramClass::HandSwitch;
+    }
+
+    if k1.key.finger == k2.key.finger {
+        return BigramClass::Sfb;
+    }
+
+    if k1.key.finger == Finger::Thumb || k2.key.finger == Finger::Thumb {
+        return BigramClass::ThumbInvolved;
+    }
+
+    match classify_scissor(k1, k2) {
+        Some(ScissorType::ExtendUp) | Some(ScissorType::ExtendDown) | None => {}
+        Some(scissor_type) => return BigramClass::Scissor(scissor_type),
+    }
+
+    if is_lateral_stretch(k1, k2) {
+        return BigramClass::LateralStretch;
+    }
+
+    let adjacent = k1.key.finger.distance(&k2.key.finger) == 1;
+    if inwards(k1, k2) {
+        BigramClass::Roll {
+            inward: true,
+            adjacent,
+        }
+    } else if inwards(k2, k1) {
+        BigramClass::Roll {
+            inward: false,
+            adjacent,
+        }
+    } else {
+        BigramClass::Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::metrics::bigram_metrics::bigram_stats::{self, BigramStats};
+    use keyboard_layout::{
+        key::{Direction, Hand},
+        layout::Layout,
+        test_utils::{key, layerkeys, toy_layout},
+    };
+
+    /// Left hand: pinky "a" (North), "b" (Out), "c" (Center), "d" (In), and "e" (South), ring
+    /// "f" to "j", middle "k" to "o", index "p" to "t"; right hand: index "u" to "y", middle
+    /// "z" to "D", ring "E" to "I", pinky "J" to "N"; thumbs "O" (left) and "P" (right).
+    fn layout() -> Layout {
+        toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP")
+    }
+
+    fn classify(bigram: &str) -> BigramClass {
+        let layout = layout();
+        let keys = layerkeys(&layout, bigram);
+        classify_bigram(keys[0], keys[1])
+    }
+
+    const ROLL_IN: BigramClass = BigramClass::Roll {
+        inward: true,
+        adjacent: true,
+    };
+
+    #[test]
+    fn all_direction_pairs_of_adjacent_fingers() {
+        use BigramClass::{LateralStretch as Lsb, Scissor};
+        use ScissorType::*;
+
+        // from the left ring finger (rows) to the left middle finger (columns), both in the
+        // order North, Out, Center, In, South
+        let table = [
+            [
+                ROLL_IN,
+                Scissor(Diagonal),
+                ROLL_IN,
+                Scissor(Diagonal),
+                Scissor(Vertical),
+            ],
+            [
+                Scissor(Diagonal),
+                Lsb,
+                Scissor(Lateral),
+                Scissor(Splay),
+                Scissor(Diagonal),
+            ],
+            [
+                ROLL_IN,
+                Scissor(Lateral),
+                ROLL_IN,
+                Scissor(Lateral),
+                ROLL_IN,
+            ],
+            [
+                Scissor(Diagonal),
+                Scissor(Squeeze),
+                Scissor(Lateral),
+                Lsb,
+                Scissor(Diagonal),
+            ],
+            [
+                Scissor(Vertical),
+                Scissor(Diagonal),
+                ROLL_IN,
+                Scissor(Diagonal),
+                ROLL_IN,
+            ],
+        ];
+
+        let ring = ['f', 'g', 'h', 'i', 'j'];
+        let middle = ['k', 'l', 'm', 'n', 'o'];
+        for (row, from) in table.iter().zip(ring.iter()) {
+            for (expected, to) in row.iter().zip(middle.iter()) {
+                let bigram: String = [*from, *to].iter().collect();
+                assert_eq!(classify(&bigram), *expected, "{}", bigram);
+            }
+        }
+    }
+
+    #[test]
+    fn hands_and_fingers() {
+        let cases = [
+            ("cc", BigramClass::SameKeyRepeat),
+            ("OO", BigramClass::SameKeyRepeat),
+            ("cw", BigramClass::HandSwitch),
+            ("OP", BigramClass::HandSwitch),
+            ("ac", BigramClass::Sfb),
+            ("wy", BigramClass::Sfb),
+            ("cO", BigramClass::ThumbInvolved),
+            ("Or", BigramClass::ThumbInvolved),
+            ("hm", ROLL_IN),
+            ("GB", ROLL_IN),
+            (
+                "mh",
+                BigramClass::Roll {
+                    inward: false,
+                    adjacent: true,
+                },
+            ),
+            (
+                "cm",
+                BigramClass::Roll {
+                    inward: true,
+                    adjacent: false,
+                },
+            ),
+            (
+                "Lw",
+                BigramClass::Roll {
+                    inward: true,
+                    adjacent: false,
+                },
+            ),
+            (
+                "wL",
+                BigramClass::Roll {
+                    inward: false,
+                    adjacent: false,
+                },
+            ),
+            // mirrored on the right hand (West is In there)
+            ("gm", classify("HB")),
+            ("ik", classify("Fz")),
+        ];
+
+        for (bigram, expected) in cases.iter() {
+            assert_eq!(classify(bigram), *expected, "{}", bigram);
+        }
+    }
+
+    #[test]
+    fn fingers_in_the_same_column_are_other() {
+        let k1 = key(Hand::Left, Finger::Ring, Direction::Center).layerkey('a');
+        let k2 = key(Hand::Left, Finger::Pinky, Direction::Center).layerkey('b');
+        assert_eq!(classify_bigram(&k1, &k2), BigramClass::Other);
+    }
+
+    /// The category of [`BigramStats`] a bigram of the given class falls into
+    fn expected_stats_category(
+        class: BigramClass,
+        k1: &LayerKey,
+        k2: &LayerKey,
+    ) -> Option<bigram_stats::BigramStatCategory> {
+        use bigram_stats::BigramStatCategory::*;
+
+        match class {
+            BigramClass::SameKeyRepeat | BigramClass::Sfb => Some(Sfb),
+            BigramClass::LateralStretch => Some(Lsb),
+            BigramClass::Scissor(_) | BigramClass::Roll { adjacent: true, .. } => {
+                classify_scissor(k1, k2).map(|scissor_type| match scissor_type {
+                    ScissorType::Vertical => Vertical,
+                    ScissorType::Squeeze => Squeeze,
+                    ScissorType::Splay => Splay,
+                    ScissorType::Diagonal => Diagonal,
+                    ScissorType::Lateral => Lateral,
+                    ScissorType::ExtendUp => ExtendUp,
+                    ScissorType::ExtendDown => ExtendDown,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn bigram_stats_agree_with_the_classification() {
+        let layout = layout();
+        let params: bigram_stats::Parameters =
+            serde_yaml::from_str("{ignore_thumbs: false, exclude_same_position_repeats: false}")
+                .unwrap();
+        let stats = BigramStats::new(&params);
+
+        for k1 in layout.layerkeys.iter() {
+            for k2 in layout.layerkeys.iter() {
+                let class = classify_bigram(k1, k2);
+                let s = stats.compute_statistics(&[((k1, k2), 1.0)], None);
+                let shares = [
+                    (bigram_stats::BigramStatCategory::Sfb, s.sfb_percent),
+                    (
+                        bigram_stats::BigramStatCategory::Vertical,
+                        s.vertical_percent,
+                    ),
+                    (bigram_stats::BigramStatCategory::Squeeze, s.squeeze_percent),
+                    (bigram_stats::BigramStatCategory::Splay, s.splay_percent),
+                    (
+                        bigram_stats::BigramStatCategory::Diagonal,
+                        s.diagonal_percent,
+                    ),
+                    (bigram_stats::BigramStatCategory::Lateral, s.lateral_percent),
+                    (bigram_stats::BigramStatCategory::Lsb, s.lsb_percent),
+                    (
+                        bigram_stats::BigramStatCategory::ExtendUp,
+                        s.extend_up_percent,
+                    ),
+                    (
+                        bigram_stats::BigramStatCategory::ExtendDown,
+                        s.extend_down_percent,
+                    ),
+                ];
+                let counted: Vec<_> = shares
+                    .iter()
+                    .filter(|(_, share)| *share > 0.0)
+                    .map(|(category, _)| *category)
+                    .collect();
+
+                let expected: Vec<_> = expected_stats_category(class, k1, k2).into_iter().collect();
+                assert_eq!(
+                    counted, expected,
+                    "{:?} {:?} {:?}",
+                    k1.symbol, k2.symbol, class
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn extensions_are_not_scissors() {
+        let inward = BigramClass::Roll {
+            inward: true,
+            adjacent: true,
+        };
+        let outward = BigramClass::Roll {
+            inward: false,
+            adjacent: true,
+        };
+        assert_eq!(classify("af"), inward);
+        assert_eq!(classify("ej"), inward);
+        assert_eq!(classify("fa"), outward);
+        assert_eq!(classify("oj"), outward);
+        assert_eq!(classify("aj"), BigramClass::Scissor(ScissorType::Vertical));
+    }
+}
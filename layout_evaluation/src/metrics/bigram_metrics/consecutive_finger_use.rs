@@ -0,0 +1,166 @@
+//! The bigram metric [`ConsecutiveFingerUse`] incurs a cost whenever both keys of a bigram are
+//! hit with the same finger of the same hand, modelling the fatigue of using a finger twice in
+//! close succession.
+//!
+//! In contrast to [`super::sfb::Sfb`], repeated presses of the same key (e.g. "ll", "ss") can be
+//! counted as well. Bigrams involving a Center key can be made cheaper, as pressing the center
+//! of a cluster requires little movement.
+//!
+//! The message lists, for each finger, the share of its consecutive uses that are
+//! same-key repeats.
+//!
+//! ## Parameters
+//! - `cost`: Base cost of a consecutive use
+//! - `finger_factors`: Per-finger multipliers (optional, default 1.0)
+//! - `center_factor`: Multiplier if either key is a Center key (optional, default 1.0)
+//! - `count_repeats`: Whether same-key repeats are counted
+//! - `ignore_thumbs`: Whether to exclude thumbs
+//! - `ignore_modifiers`: Whether to skip bigrams involving modifier keys
+
+use super::BigramMetric;
+use crate::{metrics::FingerFactors, output_style::underline};
+
+use keyboard_layout::{
+    key::{Direction, Finger, FingerMap},
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Base cost of using the same finger twice in a row
+    pub cost: f64,
+    /// Per-finger multipliers (e.g., ring finger tires faster than index)
+    #[serde(default)]
+    pub finger_factors: FingerFactors,
+    /// Multiplier if either key of the bigram is a Center key
+    pub center_factor: Option<f64>,
+    /// Count repeated presses of the same key
+    pub count_repeats: bool,
+    pub ignore_thumbs: bool,
+    pub ignore_modifiers: Option<bool>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConsecutiveFingerUse {
+    cost: f64,
+    finger_factors: FingerMap<f64>,
+    center_factor: f64,
+    count_repeats: bool,
+    ignore_thumbs: bool,
+    ignore_modifiers: bool,
+}
+
+impl ConsecutiveFingerUse {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            cost: params.cost,
+            finger_factors: params.finger_factors.to_finger_map(),
+            center_factor: params.center_factor.unwrap_or(1.0),
+            count_repeats: params.count_repeats,
+            ignore_thumbs: params.ignore_thumbs,
+            ignore_modifiers: params.ignore_modifiers.unwrap_or(false),
+        }
+    }
+
+    /// Whether the bigram is a consecutive use of the same finger that is to be counted
+    #[inline(always)]
+    fn is_counted(&self, k1: &LayerKey, k2: &LayerKey) -> bool {
+        if k1.key.hand != k2.key.hand || k1.key.finger != k2.key.finger {
+            return false;
+        }
+
+        if self.ignore_thumbs && k1.key.finger == Finger::Thumb {
+            return false;
+        }
+
+        if self.ignore_modifiers && (k1.is_modifier.is_some() || k2.is_modifier.is_some()) {
+            return false;
+        }
+
+        self.count_repeats || k1 != k2
+    }
+}
+
+impl BigramMetric for ConsecutiveFingerUse {
+    fn name(&self) -> &str {
+        "Consecutive Finger Use"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        if !self.is_counted(k1, k2) {
+            return Some(0.0);
+        }
+
+        let center_factor =
+            if k1.key.direction == Direction::Center || k2.key.direction == Direction::Center {
+                self.center_factor
+            } else {
+                1.0
+            };
+        let finger_factor = self.finger_factors.get(&k1.key.finger);
+
+        Some(weight * self.cost * finger_factor * center_factor)
+    }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+
+        let mut finger_weights = FingerMap::with_default(0.0);
+        let mut repeat_weights = FingerMap::with_default(0.0);
+        let mut total_cost = 0.0;
+
+        for ((k1, k2), weight) in bigrams.iter() {
+            if !self.is_counted(k1, k2) {
+                continue;
+            }
+
+            let finger = k1.key.finger;
+            finger_weights.set(&finger, finger_weights.get(&finger) + weight);
+            if k1 == k2 {
+                repeat_weights.set(&finger, repeat_weights.get(&finger) + weight);
+            }
+
+            total_cost += self
+                .individual_cost(k1, k2, *weight, total_weight, layout)
+                .unwrap_or(0.0);
+        }
+
+        let finger_msgs: Vec<String> = FingerMap::<f64>::keys()
+            .iter()
+            .filter(|finger| *finger_weights.get(finger) > 0.0)
+            .map(|finger| {
+                format!(
+                    "{}: {:.1}% repeats",
+                    underline(&format!("{:?}", finger)),
+                    crate::metrics::utils::to_percentage(
+                        *repeat_weights.get(finger),
+                        *finger_weights.get(finger)
+                    )
+                )
+            })
+            .collect();
+
+        let msg = if finger_msgs.is_empty() {
+            None
+        } else {
+            Some(finger_msgs.join(", "))
+        };
+
+        (total_cost, msg)
+    }
+}
@@ -1,26 +1,49 @@
 //! The bigram metric [`FingerRepeats`] incurrs a cost for bigram that uses the same finger
 //! for different keys (thumb excluded). If the finger is the index, the cost may be multiplied
-//! with a configurable factor (usually lessening the cost).
+//! with a configurable factor (usually lessening the cost). Optionally, `direction_costs`
+//! adds a cost depending on the direction of the repeated key (the second one).
+//!
+//! The worst repeats are listed grouped by that direction. Repeats below `min_weight_threshold`
+//! (fraction of all bigrams) are left out of the listing.
 //!
 //! *Note:* In contrast to ArneBab's version of the metric, thumbs are excluded.
 
 use super::BigramMetric;
+use crate::{
+    metrics::{
+        format_utils::{format_percentages, visualize_ngram},
+        inwards,
+        worst_ngrams::{WorstParameters, WorstTracker},
+        FingerFactors,
+    },
+    output_style::underline,
+};
 
 use ahash::AHashMap;
 use keyboard_layout::{
-    key::{Finger, FingerMap, Hand},
+    key::{Direction, Finger, FingerMap},
     layout::{LayerKey, Layout},
 };
 
 use serde::Deserialize;
+use std::sync::Arc;
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
-    pub finger_factors: AHashMap<Finger, f64>,
+    pub finger_factors: FingerFactors,
     pub stretch_factor: f64,
     pub curl_factor: f64,
     pub lateral_factor: f64,
     pub same_key_offset: f64,
+    /// Additional cost by the direction of the repeated key
+    #[serde(default)]
+    pub direction_costs: AHashMap<Direction, f64>,
+    /// Repeats with a smaller relative weight are not listed among the worst
+    #[serde(default)]
+    pub min_weight_threshold: f64,
+    /// Which of the worst repeats to list per direction (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
 }
 
 #[derive(Clone, Debug)]
@@ -30,16 +53,22 @@ pub struct FingerRepeats {
     curl_factor: f64,
     lateral_factor: f64,
     same_key_offset: f64,
+    direction_costs: Arc<AHashMap<Direction, f64>>,
+    min_weight_threshold: f64,
+    worst: WorstParameters,
 }
 
 impl FingerRepeats {
     pub fn new(params: &Parameters) -> Self {
         Self {
-            finger_factors: FingerMap::with_hashmap(&params.finger_factors, 1.0),
+            finger_factors: params.finger_factors.to_finger_map(),
             stretch_factor: params.stretch_factor,
             curl_factor: params.curl_factor,
             lateral_factor: params.lateral_factor,
             same_key_offset: params.same_key_offset,
+            direction_costs: Arc::new(params.direction_costs.clone()),
+            min_weight_threshold: params.min_weight_threshold,
+            worst: params.worst,
         }
     }
 }
@@ -71,16 +100,8 @@ impl BigramMetric for FingerRepeats {
 
         let upwards: bool = pos2.1 < pos1.1;
         let downwards: bool = pos2.1 > pos1.1;
-        let inwards: bool = if k1.key.hand == Hand::Left {
-            pos1.0 < pos2.0
-        } else {
-            pos1.0 > pos2.0
-        };
-        let outwards: bool = if k1.key.hand == Hand::Left {
-            pos1.0 > pos2.0
-        } else {
-            pos1.0 < pos2.0
-        };
+        let outwards: bool = inwards(k2, k1);
+        let inwards: bool = inwards(k1, k2);
 
         let dist_in_line = if is_thumb {
             pos1.0.abs_diff(pos2.0) as f64
@@ -102,12 +123,159 @@ impl BigramMetric for FingerRepeats {
         };
 
         let finger_factor = self.finger_factors.get(&k1.key.finger);
+        let direction_cost = self
+            .direction_costs
+            .get(&k2.key.direction)
+            .copied()
+            .unwrap_or(0.0);
 
         let cost = finger_factor
             * (self.same_key_offset
+                + direction_cost
                 + direction_factor * dist_in_line
                 + self.lateral_factor * dist_lateral);
 
         Some(weight * cost)
     }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let selection = self.worst.selection();
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+
+        let tracking = WorstTracker::new(selection).is_enabled();
+        let mut direction_trackers: AHashMap<Direction, WorstTracker> = AHashMap::new();
+        let mut total_cost = 0.0;
+
+        for (i, ((k1, k2), weight)) in bigrams.iter().enumerate() {
+            let cost = self
+                .individual_cost(k1, k2, *weight, total_weight, layout)
+                .unwrap_or(0.0);
+            total_cost += cost;
+
+            if tracking && cost > 0.0 && *weight >= self.min_weight_threshold * total_weight {
+                direction_trackers
+                    .entry(k2.key.direction)
+                    .or_insert_with(|| WorstTracker::new(selection))
+                    .push(i, cost);
+            }
+        }
+
+        let mut directions: Vec<Direction> = direction_trackers.keys().copied().collect();
+        directions.sort_by_key(|direction| *direction as u8);
+
+        let direction_msgs: Vec<String> = directions
+            .into_iter()
+            .filter_map(|direction| {
+                let worst_msgs: Vec<String> = direction_trackers
+                    .remove(&direction)?
+                    .into_sorted(total_cost)
+                    .into_iter()
+                    .map(|(i, cost)| {
+                        let ((k1, k2), weight) = bigrams[i];
+                        let percentages =
+                            format_percentages(cost, total_cost, weight, total_weight);
+                        format!("{} {}", visualize_ngram(&[k1, k2]), percentages)
+                    })
+                    .collect();
+
+                if worst_msgs.is_empty() {
+                    None
+                } else {
+                    Some(format!(
+                        "{}: {}",
+                        underline(&format!("{:?}", direction)),
+                        worst_msgs.join(", ")
+                    ))
+                }
+            })
+            .collect();
+
+        let msg = if direction_msgs.is_empty() {
+            None
+        } else {
+            Some(direction_msgs.join("; "))
+        };
+
+        (total_cost, msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_layout};
+
+    fn finger_repeats(min_weight_threshold: f64) -> FingerRepeats {
+        let params: Parameters = serde_yaml::from_str(
+            "
+            finger_factors: {}
+            stretch_factor: 1.0
+            curl_factor: 1.0
+            lateral_factor: 1.0
+            same_key_offset: 0.0
+            direction_costs:
+              North: 5.0
+            ",
+        )
+        .unwrap();
+        FingerRepeats::new(&Parameters {
+            min_weight_threshold,
+            ..params
+        })
+    }
+
+    /// The left pinky has "a" (North), "b" (Out), "c" (Center), "d" (In), and "e" (South), the
+    /// left ring finger "f" to "j".
+    fn layout() -> Layout {
+        toy_layout("abcdefghij")
+    }
+
+    fn cost(metric: &FingerRepeats, layout: &Layout, bigram: &str) -> Option<f64> {
+        let keys = layerkeys(layout, bigram);
+        metric.individual_cost(keys[0], keys[1], 1.0, 1.0, layout)
+    }
+
+    #[test]
+    fn direction_costs_depend_on_the_second_key() {
+        let layout = layout();
+        let metric = finger_repeats(0.0);
+
+        assert_eq!(cost(&metric, &layout, "ca"), Some(6.0));
+        assert_eq!(cost(&metric, &layout, "ac"), Some(1.0));
+        assert_eq!(cost(&metric, &layout, "bd"), Some(2.0));
+        assert_eq!(cost(&metric, &layout, "af"), Some(0.0));
+    }
+
+    #[test]
+    fn worst_repeats_are_grouped_by_direction() {
+        let layout = layout();
+        let metric = finger_repeats(0.1);
+        let bigrams: Vec<((&LayerKey, &LayerKey), f64)> = ["ca", "ac", "bd"]
+            .iter()
+            .zip([1.0, 1.0, 0.01].iter())
+            .map(|(bigram, weight)| {
+                let keys = layerkeys(&layout, bigram);
+                ((keys[0], keys[1]), *weight)
+            })
+            .collect();
+
+        let (cost, msg) = metric.total_cost(&bigrams, None, &layout);
+        let msg = msg.unwrap();
+
+        assert!((cost - 7.02).abs() < 1e-12);
+        let north = msg.find("North").expect(&msg);
+        let center = msg.find("Center").expect(&msg);
+        assert!(north < center, "{}", msg);
+        assert!(msg[north..center].contains("ca"), "{}", msg);
+        assert!(msg[center..].contains("ac"), "{}", msg);
+        // below the minimum weight
+        assert!(!msg.contains("bd"), "{}", msg);
+        assert!(!msg.contains("In"), "{}", msg);
+    }
 }
@@ -6,13 +6,14 @@
 //! directions. Penalties are based purely on the biomechanical discomfort of the motion
 //! pattern itself, independent of key costs:
 //!
-//! ```
+//! ```text
 //! penalty = cost × finger_factor × freq_multiplier
 //! ```
 //!
 //! Where:
 //! - `cost`: Base cost representing inherent biomechanical discomfort of the motion type
-//! - `finger_factor`: Max of the two fingers' factors (weaker finger dominates)
+//! - `finger_factor`: Max of the two fingers' factors (weaker finger dominates), taken from the
+//!   `finger_factors` of the movement type
 //! - `freq_multiplier`: Optional high-frequency bigram penalty
 //!
 //! Costs are configured per movement type in the evaluation metrics configuration.
@@ -22,6 +23,8 @@
 //! **Full Scissor Vertical** - Opposite vertical directions (North ↔ South)
 //! **Full Scissor Squeeze** - Fingers moving toward each other (In ↔ Out, inward motion - more uncomfortable)
 //! **Full Scissor Splay** - Fingers moving apart (In ↔ Out, outward motion - less uncomfortable)
+//! **Extend Up / Extend Down** - Both fingers pressing the same vertical direction (North → North,
+//! South → South), only penalized if configured
 //!
 //! ## Configuration
 //!
@@ -30,28 +33,41 @@
 //! - `squeeze.cost`: Base cost for squeeze motion (fingers moving inward)
 //! - `splay.cost`: Base cost for splay motion (fingers moving outward)
 //! - `<type>.finger_factors`: Optional per-finger multipliers (e.g., pinky scissors worse than index)
-//! - `critical_bigram_fraction`: Frequency threshold for high-penalty bigrams (optional)
-//! - `critical_bigram_factor`: Multiplier for high-frequency bigrams (optional)
+//! - `extend_up_factor` / `extend_down_factor`: Cost of North → North / South → South motions
+//!   relative to `vertical.cost` (optional, not penalized if omitted); these use the
+//!   `vertical.finger_factors`
+//! - `critical_bigram.fraction`: Frequency threshold for high-penalty bigrams (optional)
+//! - `critical_bigram.factor`: Multiplier for high-frequency bigrams (optional)
+//! - `tracking_mode`: When to list the worst bigrams (`Always`, `Never` or `FinalOnly`, optional)
+//! - `display_order`: Order in which the categories are listed (optional, unlisted ones are hidden)
 
 use super::{
     scissor_base::{is_adjacent_fingers, ScissorCategory, ScissorCompute, ScissorMetric},
-    BigramMetric,
+    BigramMetric, CriticalBigramParams,
+};
+use crate::{
+    metrics::{
+        worst_ngrams::{TrackingMode, WorstParameters},
+        FingerFactors,
+    },
+    output_style::{underline_with, ColorConfig},
 };
 
-use ahash::AHashMap;
-use colored::Colorize;
 use keyboard_layout::{
-    key::{Direction::*, Finger},
+    key::{Direction::*, FingerMap},
+    keyboard::Keyboard,
     layout::{LayerKey, Layout},
 };
 
 use serde::Deserialize;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
 pub enum FsbCategory {
     Vertical,
     Squeeze,
     Splay,
+    ExtendUp,
+    ExtendDown,
 }
 
 impl ScissorCategory for FsbCategory {
@@ -60,14 +76,18 @@ impl ScissorCategory for FsbCategory {
             FsbCategory::Vertical,
             FsbCategory::Squeeze,
             FsbCategory::Splay,
+            FsbCategory::ExtendUp,
+            FsbCategory::ExtendDown,
         ]
     }
 
-    fn display_name(&self) -> String {
+    fn display_name(&self, color: ColorConfig) -> String {
         match self {
-            FsbCategory::Vertical => "Vertical".underline().to_string(),
-            FsbCategory::Squeeze => "Squeeze".underline().to_string(),
-            FsbCategory::Splay => "Splay".underline().to_string(),
+            FsbCategory::Vertical => underline_with("Vertical", color),
+            FsbCategory::Squeeze => underline_with("Squeeze", color),
+            FsbCategory::Splay => underline_with("Splay", color),
+            FsbCategory::ExtendUp => underline_with("Extend Up", color),
+            FsbCategory::ExtendDown => underline_with("Extend Down", color),
         }
     }
 }
@@ -77,9 +97,9 @@ pub struct CategoryParams {
     /// Base cost representing inherent biomechanical discomfort
     pub cost: f64,
     /// Optional per-finger multipliers (e.g., pinky: 1.5, index: 0.75)
-    /// Defaults to None (all fingers treated equally)
+    /// Defaults to all fingers being treated equally
     #[serde(default)]
-    pub finger_factors: Option<AHashMap<Finger, f64>>,
+    pub finger_factors: FingerFactors,
 }
 
 #[derive(Clone, Deserialize, Debug)]
@@ -90,10 +110,24 @@ pub struct Parameters {
     pub squeeze: CategoryParams,
     /// Configuration for Splay scissors (fingers moving outward)
     pub splay: CategoryParams,
-    /// Minimum relative bigram frequency to apply heavy penalty (as fraction, e.g., 0.0004 = 0.04%)
-    pub critical_bigram_fraction: Option<f64>,
-    /// Multiplier for bigrams above critical_bigram_fraction (e.g., 100.0 = 100x penalty)
-    pub critical_bigram_factor: Option<f64>,
+    /// Cost of both fingers pressing North relative to the vertical cost (not penalized if None)
+    #[serde(default)]
+    pub extend_up_factor: Option<f64>,
+    /// Cost of both fingers pressing South relative to the vertical cost (not penalized if None)
+    #[serde(default)]
+    pub extend_down_factor: Option<f64>,
+    /// Heavier penalty for frequent bigrams (`fraction` and `factor`)
+    #[serde(default)]
+    pub critical_bigram: Option<CriticalBigramParams>,
+    /// Which of the worst ngrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
+    /// When to track the worst bigrams (`Always`, `Never` or `FinalOnly`)
+    #[serde(default)]
+    pub tracking_mode: TrackingMode,
+    /// Order in which the categories are listed (all in the default order if None)
+    #[serde(default)]
+    pub display_order: Option<Vec<FsbCategory>>,
 }
 
 #[derive(Clone, Debug)]
@@ -101,10 +135,15 @@ struct FsbCompute {
     vertical_cost: f64,
     squeeze_cost: f64,
     splay_cost: f64,
+    extend_up_cost: Option<f64>,
+    extend_down_cost: Option<f64>,
+    vertical_finger_factors: FingerMap<f64>,
+    squeeze_finger_factors: FingerMap<f64>,
+    splay_finger_factors: FingerMap<f64>,
 }
 
 impl ScissorCompute<FsbCategory> for FsbCompute {
-    fn compute_cost(&self, k1: &LayerKey, k2: &LayerKey, _layout: &Layout) -> Option<(f64, FsbCategory)> {
+    fn compute_cost(&self, k1: &LayerKey, k2: &LayerKey) -> Option<(f64, FsbCategory)> {
         if !is_adjacent_fingers(k1, k2) {
             return None;
         }
@@ -112,11 +151,9 @@ impl ScissorCompute<FsbCategory> for FsbCompute {
         let dir_from = k1.key.direction;
         let dir_to = k2.key.direction;
 
-        match (dir_from, dir_to) {
+        let (cost, category) = match (dir_from, dir_to) {
             // FSB: Full Scissor Vertical - North-South opposition
-            (South, North) | (North, South) => {
-                Some((self.vertical_cost, FsbCategory::Vertical))
-            }
+            (South, North) | (North, South) => Some((self.vertical_cost, FsbCategory::Vertical)),
 
             // FSB: Full Scissor Lateral - In-Out opposition (squeeze/splay)
             (In, Out) | (Out, In) => {
@@ -134,9 +171,32 @@ impl ScissorCompute<FsbCategory> for FsbCompute {
                 Some((cost, category))
             }
 
+            // Same vertical direction (only if configured)
+            (North, North) => self
+                .extend_up_cost
+                .map(|cost| (cost, FsbCategory::ExtendUp)),
+            (South, South) => self
+                .extend_down_cost
+                .map(|cost| (cost, FsbCategory::ExtendDown)),
+
             // All other combinations: not full scissors
             _ => None,
-        }
+        }?;
+
+        let finger_factors = match category {
+            FsbCategory::Vertical | FsbCategory::ExtendUp | FsbCategory::ExtendDown => {
+                &self.vertical_finger_factors
+            }
+            FsbCategory::Squeeze => &self.squeeze_finger_factors,
+            FsbCategory::Splay => &self.splay_finger_factors,
+        };
+
+        // the weaker finger dominates
+        let finger_factor = finger_factors
+            .get(&k1.key.finger)
+            .max(*finger_factors.get(&k2.key.finger));
+
+        Some((cost * finger_factor, category))
     }
 }
 
@@ -145,46 +205,24 @@ pub struct Fsb {
     inner: ScissorMetric<FsbCategory, FsbCompute>,
 }
 
-/// Merge finger_factors from multiple categories
-/// Returns None if all categories have None, otherwise returns union of all factors
-fn merge_finger_factors(
-    category_factors: &[Option<&AHashMap<Finger, f64>>],
-) -> Option<AHashMap<Finger, f64>> {
-    let has_any = category_factors.iter().any(|f| f.is_some());
-    if !has_any {
-        return None;
-    }
-
-    let mut merged = AHashMap::new();
-    for factors in category_factors.iter().filter_map(|f| *f) {
-        merged.extend(factors.iter().map(|(k, v)| (*k, *v)));
-    }
-    Some(merged)
-}
-
 impl Fsb {
     pub fn new(params: &Parameters) -> Self {
         let compute = FsbCompute {
             vertical_cost: params.vertical.cost,
             squeeze_cost: params.squeeze.cost,
             splay_cost: params.splay.cost,
+            extend_up_cost: params.extend_up_factor.map(|f| f * params.vertical.cost),
+            extend_down_cost: params.extend_down_factor.map(|f| f * params.vertical.cost),
+            vertical_finger_factors: params.vertical.finger_factors.to_finger_map(),
+            squeeze_finger_factors: params.squeeze.finger_factors.to_finger_map(),
+            splay_finger_factors: params.splay.finger_factors.to_finger_map(),
         };
 
-        // Merge finger_factors from all categories
-        let merged_finger_factors = merge_finger_factors(&[
-            params.vertical.finger_factors.as_ref(),
-            params.squeeze.finger_factors.as_ref(),
-            params.splay.finger_factors.as_ref(),
-        ]);
-
         Self {
-            inner: ScissorMetric::new(
-                "FSB",
-                params.critical_bigram_fraction,
-                params.critical_bigram_factor,
-                merged_finger_factors,
-                compute,
-            ),
+            inner: ScissorMetric::new("FSB", params.critical_bigram, compute)
+                .with_worst_parameters(params.worst)
+                .with_tracking_mode(params.tracking_mode)
+                .with_display_order(params.display_order.clone()),
         }
     }
 }
@@ -194,6 +232,18 @@ impl BigramMetric for Fsb {
         self.inner.name()
     }
 
+    fn is_additive(&self) -> bool {
+        self.inner.is_additive()
+    }
+
+    fn precompute(&mut self, keyboard: &Keyboard) {
+        self.inner.precompute(keyboard)
+    }
+
+    fn set_color_config(&mut self, color: ColorConfig) {
+        self.inner.set_color_config(color)
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
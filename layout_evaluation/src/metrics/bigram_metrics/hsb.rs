@@ -6,13 +6,14 @@
 //! partially opposing directions. Penalties are based purely on the biomechanical discomfort
 //! of the motion pattern itself, independent of key costs:
 //!
-//! ```
+//! ```text
 //! penalty = cost × finger_factor × freq_multiplier
 //! ```
 //!
 //! Where:
 //! - `cost`: Base cost representing inherent biomechanical discomfort of the motion type
-//! - `finger_factor`: Max of the two fingers' factors (weaker finger dominates)
+//! - `finger_factor`: Max of the two fingers' factors (weaker finger dominates), taken from the
+//!   `finger_factors` of the movement type
 //! - `freq_multiplier`: Optional high-frequency bigram penalty
 //!
 //! Costs are configured per movement type in the evaluation metrics configuration.
@@ -25,44 +26,64 @@
 //! **Lateral** - Lateral displacement:
 //! - Lateral + Center: One finger moves laterally (In/Out), other presses Center
 //!
+//! **Extension** - Same vertical direction, only penalized if configured:
+//! - North + North / South + South: Both fingers extend (or contract) together, which the
+//!   Svalboard's key clusters make uncomfortable. [`super::fsb`]'s `extend_up_factor` and
+//!   `extend_down_factor` cover the same motions, so configure only one of them.
+//!
 //! ## Configuration
 //!
 //! Each movement type has its own configuration:
 //! - `diagonal.cost`: Base cost for diagonal movements (lateral+vertical)
 //! - `lateral.cost`: Base cost for lateral movements (lateral+center)
+//! - `extension.cost`: Base cost for extensions (same vertical direction, optional)
 //! - `<type>.finger_factors`: Optional per-finger multipliers (e.g., pinky scissors worse than index)
-//! - `critical_bigram_fraction`: Frequency threshold for high-penalty bigrams (optional)
-//! - `critical_bigram_factor`: Multiplier for high-frequency bigrams (optional)
+//! - `critical_bigram.fraction`: Frequency threshold for high-penalty bigrams (optional)
+//! - `critical_bigram.factor`: Multiplier for high-frequency bigrams (optional)
+//! - `tracking_mode`: When to list the worst bigrams (`Always`, `Never` or `FinalOnly`, optional)
+//! - `display_order`: Order in which the categories are listed (optional, unlisted ones are hidden)
 
 use super::{
     scissor_base::{is_adjacent_fingers, ScissorCategory, ScissorCompute, ScissorMetric},
-    BigramMetric,
+    BigramMetric, CriticalBigramParams,
+};
+use crate::{
+    metrics::{
+        worst_ngrams::{TrackingMode, WorstParameters},
+        FingerFactors,
+    },
+    output_style::{underline_with, ColorConfig},
 };
 
-use ahash::AHashMap;
-use colored::Colorize;
 use keyboard_layout::{
-    key::{Direction::*, Finger},
+    key::{Direction::*, FingerMap},
+    keyboard::Keyboard,
     layout::{LayerKey, Layout},
 };
 
 use serde::Deserialize;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
 pub enum HsbCategory {
     Diagonal,
     Lateral,
+    Extension,
 }
 
 impl ScissorCategory for HsbCategory {
     fn display_order() -> &'static [Self] {
-        &[HsbCategory::Diagonal, HsbCategory::Lateral]
+        &[
+            HsbCategory::Diagonal,
+            HsbCategory::Lateral,
+            HsbCategory::Extension,
+        ]
     }
 
-    fn display_name(&self) -> String {
+    fn display_name(&self, color: ColorConfig) -> String {
         match self {
-            HsbCategory::Diagonal => "Diagonal".underline().to_string(),
-            HsbCategory::Lateral => "Lateral".underline().to_string(),
+            HsbCategory::Diagonal => underline_with("Diagonal", color),
+            HsbCategory::Lateral => underline_with("Lateral", color),
+            HsbCategory::Extension => underline_with("Extension", color),
         }
     }
 }
@@ -72,9 +93,9 @@ pub struct CategoryParams {
     /// Base cost representing inherent biomechanical discomfort
     pub cost: f64,
     /// Optional per-finger multipliers (e.g., pinky: 1.5, index: 0.75)
-    /// Defaults to None (all fingers treated equally)
+    /// Defaults to all fingers being treated equally
     #[serde(default)]
-    pub finger_factors: Option<AHashMap<Finger, f64>>,
+    pub finger_factors: FingerFactors,
 }
 
 #[derive(Clone, Deserialize, Debug)]
@@ -83,25 +104,35 @@ pub struct Parameters {
     pub diagonal: CategoryParams,
     /// Configuration for Lateral scissors (lateral+center)
     pub lateral: CategoryParams,
-    /// Minimum relative bigram frequency to apply heavy penalty (as fraction, e.g., 0.0004 = 0.04%)
-    pub critical_bigram_fraction: Option<f64>,
-    /// Multiplier for bigrams above critical_bigram_fraction (e.g., 100.0 = 100x penalty)
-    pub critical_bigram_factor: Option<f64>,
+    /// Configuration for Extensions (same vertical direction, not penalized if None)
+    #[serde(default)]
+    pub extension: Option<CategoryParams>,
+    /// Heavier penalty for frequent bigrams (`fraction` and `factor`)
+    #[serde(default)]
+    pub critical_bigram: Option<CriticalBigramParams>,
+    /// Which of the worst ngrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
+    /// When to track the worst bigrams (`Always`, `Never` or `FinalOnly`)
+    #[serde(default)]
+    pub tracking_mode: TrackingMode,
+    /// Order in which the categories are listed (all in the default order if None)
+    #[serde(default)]
+    pub display_order: Option<Vec<HsbCategory>>,
 }
 
 #[derive(Clone, Debug)]
 struct HsbCompute {
     diagonal_cost: f64,
     lateral_cost: f64,
+    extension_cost: Option<f64>,
+    diagonal_finger_factors: FingerMap<f64>,
+    lateral_finger_factors: FingerMap<f64>,
+    extension_finger_factors: FingerMap<f64>,
 }
 
 impl ScissorCompute<HsbCategory> for HsbCompute {
-    fn compute_cost(
-        &self,
-        k1: &LayerKey,
-        k2: &LayerKey,
-        _layout: &Layout,
-    ) -> Option<(f64, HsbCategory)> {
+    fn compute_cost(&self, k1: &LayerKey, k2: &LayerKey) -> Option<(f64, HsbCategory)> {
         if !is_adjacent_fingers(k1, k2) {
             return None;
         }
@@ -109,7 +140,7 @@ impl ScissorCompute<HsbCategory> for HsbCompute {
         let dir_from = k1.key.direction;
         let dir_to = k2.key.direction;
 
-        match (dir_from, dir_to) {
+        let (cost, category) = match (dir_from, dir_to) {
             // HSB: Half Scissor - Diagonal movements (lateral + vertical)
             (In, North)
             | (Out, North)
@@ -125,9 +156,27 @@ impl ScissorCompute<HsbCategory> for HsbCompute {
                 Some((self.lateral_cost, HsbCategory::Lateral))
             }
 
+            // Extension - Same vertical direction (only if configured)
+            (North, North) | (South, South) => self
+                .extension_cost
+                .map(|cost| (cost, HsbCategory::Extension)),
+
             // All other combinations: not considered half scissors or lateral
             _ => None,
-        }
+        }?;
+
+        let finger_factors = match category {
+            HsbCategory::Diagonal => &self.diagonal_finger_factors,
+            HsbCategory::Lateral => &self.lateral_finger_factors,
+            HsbCategory::Extension => &self.extension_finger_factors,
+        };
+
+        // the weaker finger dominates
+        let finger_factor = finger_factors
+            .get(&k1.key.finger)
+            .max(*finger_factors.get(&k2.key.finger));
+
+        Some((cost * finger_factor, category))
     }
 }
 
@@ -136,44 +185,26 @@ pub struct Hsb {
     inner: ScissorMetric<HsbCategory, HsbCompute>,
 }
 
-/// Merge finger_factors from multiple categories
-/// Returns None if all categories have None, otherwise returns union of all factors
-fn merge_finger_factors(
-    category_factors: &[Option<&AHashMap<Finger, f64>>],
-) -> Option<AHashMap<Finger, f64>> {
-    let has_any = category_factors.iter().any(|f| f.is_some());
-    if !has_any {
-        return None;
-    }
-
-    let mut merged = AHashMap::new();
-    for factors in category_factors.iter().filter_map(|f| *f) {
-        merged.extend(factors.iter().map(|(k, v)| (*k, *v)));
-    }
-    Some(merged)
-}
-
 impl Hsb {
     pub fn new(params: &Parameters) -> Self {
         let compute = HsbCompute {
             diagonal_cost: params.diagonal.cost,
             lateral_cost: params.lateral.cost,
+            extension_cost: params.extension.as_ref().map(|e| e.cost),
+            diagonal_finger_factors: params.diagonal.finger_factors.to_finger_map(),
+            lateral_finger_factors: params.lateral.finger_factors.to_finger_map(),
+            extension_finger_factors: params
+                .extension
+                .as_ref()
+                .map(|e| e.finger_factors.to_finger_map())
+                .unwrap_or_else(|| FingerMap::with_default(1.0)),
         };
 
-        // Merge finger_factors from all categories
-        let merged_finger_factors = merge_finger_factors(&[
-            params.diagonal.finger_factors.as_ref(),
-            params.lateral.finger_factors.as_ref(),
-        ]);
-
         Self {
-            inner: ScissorMetric::new(
-                "HSB",
-                params.critical_bigram_fraction,
-                params.critical_bigram_factor,
-                merged_finger_factors,
-                compute,
-            ),
+            inner: ScissorMetric::new("HSB", params.critical_bigram, compute)
+                .with_worst_parameters(params.worst)
+                .with_tracking_mode(params.tracking_mode)
+                .with_display_order(params.display_order.clone()),
         }
     }
 }
@@ -183,6 +214,18 @@ impl BigramMetric for Hsb {
         self.inner.name()
     }
 
+    fn is_additive(&self) -> bool {
+        self.inner.is_additive()
+    }
+
+    fn precompute(&mut self, keyboard: &Keyboard) {
+        self.inner.precompute(keyboard)
+    }
+
+    fn set_color_config(&mut self, color: ColorConfig) {
+        self.inner.set_color_config(color)
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
@@ -0,0 +1,87 @@
+//! The bigram metric [`KeyRepeats`] incurs a cost for repeated presses of the same key
+//! (e.g. "ee", "ll"). On the Svalboard, repeating a North pull is noticeably more tiring
+//! than repeating a Center press, so the cost depends on the key's direction.
+//!
+//! Only exact same-key bigrams are counted. These are skipped by [`super::sfb::Sfb`], so both
+//! metrics can be enabled without counting a bigram twice. (Avoid enabling `count_repeats` of
+//! [`super::consecutive_finger_use::ConsecutiveFingerUse`] at the same time, though.)
+//! Repeats of modifiers (e.g. holding shift) are excluded.
+//!
+//! ## Parameters
+//! - `costs`: Cost per key direction (directions without an entry are free)
+//! - `finger_factors`: Per-finger multipliers (optional, default 1.0)
+//! - `n_worst` / `worst_threshold_pct`: Which of the most costly repeats to list
+
+use super::BigramMetric;
+use crate::metrics::{worst_ngrams::WorstParameters, FingerFactors};
+
+use ahash::AHashMap;
+use keyboard_layout::{
+    key::{Direction, FingerMap},
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Cost of repeating a key, by its direction
+    pub costs: AHashMap<Direction, f64>,
+    /// Per-finger multipliers (e.g., pinky repeats are worse than index repeats)
+    #[serde(default)]
+    pub finger_factors: FingerFactors,
+    /// Which of the worst repeats to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
+}
+
+#[derive(Clone, Debug)]
+pub struct KeyRepeats {
+    costs: Arc<AHashMap<Direction, f64>>,
+    finger_factors: FingerMap<f64>,
+    worst: WorstParameters,
+}
+
+impl KeyRepeats {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            costs: Arc::new(params.costs.clone()),
+            finger_factors: params.finger_factors.to_finger_map(),
+            worst: params.worst,
+        }
+    }
+}
+
+impl BigramMetric for KeyRepeats {
+    fn name(&self) -> &str {
+        "Key Repeats"
+    }
+
+    fn is_additive(&self) -> bool {
+        true
+    }
+
+    fn worst_parameters(&self) -> WorstParameters {
+        self.worst
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        if k1 != k2 || k1.is_modifier.is_some() {
+            return Some(0.0);
+        }
+
+        let cost = self.costs.get(&k1.key.direction).copied().unwrap_or(0.0);
+        let finger_factor = *self.finger_factors.get(&k1.key.finger);
+
+        Some(weight * cost * finger_factor)
+    }
+}
@@ -10,6 +10,37 @@ use keyboard_layout::{
 
 use serde::Deserialize;
 
+/// How the distance between two key positions is measured
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Eq)]
+pub enum DistanceFormula {
+    /// Sum of the horizontal and vertical distances
+    Manhattan,
+    /// Straight-line distance
+    Euclidean,
+    /// Larger one of the horizontal and vertical distances
+    Chebyshev,
+}
+
+impl Default for DistanceFormula {
+    fn default() -> Self {
+        DistanceFormula::Euclidean
+    }
+}
+
+impl DistanceFormula {
+    #[inline(always)]
+    fn distance(&self, p1: &Position, p2: &Position) -> f64 {
+        let dx = (p1.0 - p2.0).abs();
+        let dy = (p1.1 - p2.1).abs();
+
+        match self {
+            DistanceFormula::Manhattan => dx + dy,
+            DistanceFormula::Euclidean => p1.distance(p2),
+            DistanceFormula::Chebyshev => dx.max(dy),
+        }
+    }
+}
+
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
     pub ignore_modifiers: bool,
@@ -17,6 +48,9 @@ pub struct Parameters {
     pub keydown_distance: f64,
     pub dscoring: AHashMap<Hand, AHashMap<Finger, f64>>,
     pub hscoring: AHashMap<Hand, f64>,
+    /// How distances between keys are measured (default: Euclidean)
+    #[serde(default)]
+    pub distance_formula: DistanceFormula,
 }
 
 #[derive(Clone, Debug)]
@@ -26,6 +60,7 @@ pub struct KLADistance {
     keydown_distance: f64,
     dscoring: HandFingerMap<f64>,
     hscoring: HandMap<f64>,
+    distance_formula: DistanceFormula,
 }
 
 impl KLADistance {
@@ -36,6 +71,7 @@ impl KLADistance {
             keydown_distance: params.keydown_distance,
             dscoring: HandFingerMap::with_hashmap(&params.dscoring, 1.0),
             hscoring: HandMap::with_hashmap(&params.hscoring, 1.0),
+            distance_formula: params.distance_formula,
         }
     }
 }
@@ -116,7 +152,9 @@ impl BigramMetric for KLADistance {
 
                         // move previously idle finger to key press it
                         (KeyUsage::Idle(prev_pos), KeyUsage::Used(curr_key)) => {
-                            let dist = prev_pos.distance(&curr_key.key.position)
+                            let dist = self
+                                .distance_formula
+                                .distance(prev_pos, &curr_key.key.position)
                                 + self.keydown_distance
                                 + self.keyup_distance;
                             *finger_values.get_mut(&curr_key.key.hand, &curr_key.key.finger) +=
@@ -125,7 +163,9 @@ impl BigramMetric for KLADistance {
 
                         // return finger from previous key press to home row
                         (KeyUsage::Used(prev_key), KeyUsage::Idle(curr_pos)) => {
-                            let dist = prev_key.key.position.distance(curr_pos);
+                            let dist = self
+                                .distance_formula
+                                .distance(&prev_key.key.position, curr_pos);
                             *finger_values.get_mut(&prev_key.key.hand, &prev_key.key.finger) +=
                                 dist * weight;
                         }
@@ -134,7 +174,9 @@ impl BigramMetric for KLADistance {
                         (KeyUsage::Used(prev_key), KeyUsage::Used(curr_key)) => {
                             // if both keys are identical and are mods it is a hold -> no cost
                             if !(prev_key == curr_key && curr_key.is_modifier.is_some()) {
-                                let dist = curr_key.key.position.distance(&prev_key.key.position)
+                                let dist = self
+                                    .distance_formula
+                                    .distance(&curr_key.key.position, &prev_key.key.position)
                                     + self.keydown_distance
                                     + self.keyup_distance;
                                 *finger_values.get_mut(&curr_key.key.hand, &curr_key.key.finger) +=
@@ -173,3 +215,61 @@ impl BigramMetric for KLADistance {
         (cost, Some(message))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_layout};
+
+    fn kla_distance(distance_formula: DistanceFormula) -> KLADistance {
+        KLADistance::new(&Parameters {
+            ignore_modifiers: true,
+            keyup_distance: 0.0,
+            keydown_distance: 0.0,
+            dscoring: AHashMap::default(),
+            hscoring: AHashMap::default(),
+            distance_formula,
+        })
+    }
+
+    /// The left pinky has "a" (North, at (2, 1)), "b" (Out, at (1, 2)), and "c" (Center, at
+    /// (2, 2)).
+    fn cost(distance_formula: DistanceFormula, bigram: &str) -> f64 {
+        let layout = toy_layout("abc");
+        let keys = layerkeys(&layout, bigram);
+        kla_distance(distance_formula)
+            .total_cost(&[((keys[0], keys[1]), 1.0)], None, &layout)
+            .0
+    }
+
+    #[test]
+    fn formulas_differ_for_diagonal_movements() {
+        let p1 = Position(0.0, 0.0);
+        let p2 = Position(3.0, 4.0);
+        assert_eq!(DistanceFormula::Manhattan.distance(&p1, &p2), 7.0);
+        assert_eq!(DistanceFormula::Euclidean.distance(&p1, &p2), 5.0);
+        assert_eq!(DistanceFormula::Chebyshev.distance(&p1, &p2), 4.0);
+
+        assert_eq!(cost(DistanceFormula::Manhattan, "ab"), 2.0);
+        assert_eq!(cost(DistanceFormula::Euclidean, "ab"), 2f64.sqrt());
+        assert_eq!(cost(DistanceFormula::Chebyshev, "ab"), 1.0);
+    }
+
+    #[test]
+    fn formulas_agree_for_axis_aligned_movements() {
+        for formula in [
+            DistanceFormula::Manhattan,
+            DistanceFormula::Euclidean,
+            DistanceFormula::Chebyshev,
+        ]
+        .iter()
+        {
+            assert_eq!(
+                formula.distance(&Position(1.0, 2.0), &Position(1.0, -1.0)),
+                3.0
+            );
+            assert_eq!(cost(*formula, "ac"), 1.0);
+        }
+    }
+}
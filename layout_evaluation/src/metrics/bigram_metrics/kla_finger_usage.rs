@@ -1,8 +1,16 @@
+//! Finger usage metric that tries to resemble one from an older version of klanext.
+//!
+//! The cost is the usage of each finger weighted with `fscoring` and `hscoring`. If a
+//! `target_distribution` (percent per finger, both hands combined) is given, the deviation
+//! of the actual usage from it is added, scaled with `target_deviation_factor`. This penalizes
+//! both under- and overused fingers.
+
 use super::BigramMetric;
+use crate::output_style::underline;
 
 use ahash::{AHashMap, AHashSet};
 use keyboard_layout::{
-    key::{Finger, Hand, HandFingerMap, HandMap},
+    key::{Finger, FingerMap, Hand, HandFingerMap, HandMap},
     layout::{LayerKey, LayerKeyIndex, Layout},
 };
 
@@ -13,6 +21,16 @@ pub struct Parameters {
     pub ignore_modifiers: bool,
     pub fscoring: AHashMap<Hand, AHashMap<Finger, f64>>,
     pub hscoring: AHashMap<Hand, f64>,
+    /// Targeted usage of each finger in percent
+    #[serde(default)]
+    pub target_distribution: Option<AHashMap<Finger, f64>>,
+    /// Cost per deviation from the target distribution (in units of usage)
+    #[serde(default = "default_target_deviation_factor")]
+    pub target_deviation_factor: f64,
+}
+
+fn default_target_deviation_factor() -> f64 {
+    1.0
 }
 
 #[derive(Clone, Debug)]
@@ -20,6 +38,8 @@ pub struct KLAFingerUsage {
     ignore_modifiers: bool,
     fscoring: HandFingerMap<f64>,
     hscoring: HandMap<f64>,
+    target_distribution: Option<AHashMap<Finger, f64>>,
+    target_deviation_factor: f64,
 }
 
 impl KLAFingerUsage {
@@ -28,6 +48,8 @@ impl KLAFingerUsage {
             ignore_modifiers: params.ignore_modifiers,
             fscoring: HandFingerMap::with_hashmap(&params.fscoring, 1.0),
             hscoring: HandMap::with_hashmap(&params.hscoring, 1.0),
+            target_distribution: params.target_distribution.clone(),
+            target_deviation_factor: params.target_deviation_factor,
         }
     }
 }
@@ -71,19 +93,45 @@ impl BigramMetric for KLAFingerUsage {
             }
         });
 
-        let message = format!(
-            "Per finger (unweighted): {:4.1} {:4.1} {:4.1} {:4.1} | {:>4.1} - {:<4.1} | {:4.1} {:4.1} {:4.1} {:4.1}",
-            finger_values.get(&Hand::Left, &Finger::Pinky),
-            finger_values.get(&Hand::Left, &Finger::Ring),
-            finger_values.get(&Hand::Left, &Finger::Middle),
-            finger_values.get(&Hand::Left, &Finger::Index),
-            finger_values.get(&Hand::Left, &Finger::Thumb),
-            finger_values.get(&Hand::Right, &Finger::Thumb),
-            finger_values.get(&Hand::Right, &Finger::Index),
-            finger_values.get(&Hand::Right, &Finger::Middle),
-            finger_values.get(&Hand::Right, &Finger::Ring),
-            finger_values.get(&Hand::Right, &Finger::Pinky),
-        );
+        // usage per finger (both hands combined)
+        let mut usage: FingerMap<f64> = FingerMap::with_default(0.0);
+        finger_values
+            .iter()
+            .zip(HandFingerMap::<f64>::keys().iter())
+            .for_each(|(v, (_, finger))| usage.set(finger, usage.get(finger) + v));
+        let total_usage: f64 = usage.iter().sum();
+
+        let mut deviation_cost = 0.0;
+        let finger_msgs: Vec<String> = FingerMap::<f64>::keys()
+            .iter()
+            .map(|finger| {
+                let percentage =
+                    crate::metrics::utils::to_percentage(*usage.get(finger), total_usage);
+                let target = self
+                    .target_distribution
+                    .as_ref()
+                    .and_then(|targets| targets.get(finger));
+
+                match target {
+                    Some(target) => {
+                        deviation_cost += self.target_deviation_factor
+                            * (usage.get(finger) - target / 100.0 * total_usage).abs();
+                        format!(
+                            "{}: {:.1}% (target: {}%)",
+                            underline(&format!("{:?}", finger)),
+                            percentage,
+                            target
+                        )
+                    }
+                    None => format!(
+                        "{}: {:.1}%",
+                        underline(&format!("{:?}", finger)),
+                        percentage
+                    ),
+                }
+            })
+            .collect();
+        let message = finger_msgs.join(", ");
 
         finger_values
             .iter_mut()
@@ -94,7 +142,7 @@ impl BigramMetric for KLAFingerUsage {
                 *c *= fscore * hscore
             });
 
-        let cost = finger_values.iter().sum();
+        let cost = finger_values.iter().sum::<f64>() + deviation_cost;
 
         (cost, Some(message))
     }
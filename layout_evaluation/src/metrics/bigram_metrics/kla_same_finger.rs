@@ -1,18 +1,57 @@
+//! Same finger metric that tries to resemble the one on klanext.keyboard-design.com.
+//!
+//! Each activation of the same finger for consecutive symbols (including modifiers) costs its
+//! weight times an effort that models the time needed for the finger to travel:
+//!
+//! ```text
+//! effort = max((travel(dir_1) + travel(dir_2)) ^ distance_exponent, min_tap_time)
+//! ```
+//!
+//! where `travel` is the configured lever throw of a key direction. The defaults
+//! (`distance_exponent` 0, all travels 1, `min_tap_time` 0) result in an effort of 1 for
+//! every same finger activation.
+//!
+//! ## Differences to [`super::sfb::Sfb`]
+//!
+//! Both metrics see the same bigrams, but they do not count the same ones:
+//! - Repeats of the same key count here (only held modifiers are free), whereas `Sfb` skips
+//!   them by default (`exclude_same_position_repeats`).
+//! - Modifiers are resolved from each symbol's `modifiers`, so a finger holding a modifier
+//!   for one symbol and typing the next one is a same finger activation. `Sfb` only compares
+//!   the two bigram keys.
+//! - Thumbs are always included; `Sfb` can ignore them (`ignore_thumbs`).
+//! - The cost is weighted by `fscoring`/`hscoring` instead of direction-pair costs and finger
+//!   factors.
+//!
+//! With `ignore_modifiers`, no thumb bigrams, `exclude_same_position_repeats: false`, no
+//! critical bigram factor and all scorings and `Sfb` costs set to 1.0, both metrics count the
+//! same bigrams.
+
 use super::BigramMetric;
 
 use ahash::AHashMap;
 use keyboard_layout::{
-    key::{Finger, Hand, HandFingerMap, HandMap},
+    key::{Direction, Finger, Hand, HandFingerMap, HandMap},
     layout::{LayerKey, Layout},
 };
 
 use serde::Deserialize;
+use std::sync::Arc;
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
     pub ignore_modifiers: bool,
     pub fscoring: AHashMap<Hand, AHashMap<Finger, f64>>,
     pub hscoring: AHashMap<Hand, f64>,
+    /// Exponent applied to the travel distance of a same finger activation
+    #[serde(default)]
+    pub distance_exponent: f64,
+    /// Travel distance (lever throw, e.g. in millimeters) of each key direction (default 1.0)
+    #[serde(default)]
+    pub direction_distances: AHashMap<Direction, f64>,
+    /// Minimum effort of a same finger activation
+    #[serde(default)]
+    pub min_tap_time: f64,
 }
 
 #[derive(Clone, Debug)]
@@ -20,6 +59,9 @@ pub struct KLASameFinger {
     ignore_modifiers: bool,
     fscoring: HandFingerMap<f64>,
     hscoring: HandMap<f64>,
+    distance_exponent: f64,
+    direction_distances: Arc<AHashMap<Direction, f64>>,
+    min_tap_time: f64,
 }
 
 impl KLASameFinger {
@@ -28,8 +70,25 @@ impl KLASameFinger {
             ignore_modifiers: params.ignore_modifiers,
             fscoring: HandFingerMap::with_hashmap(&params.fscoring, 1.0),
             hscoring: HandMap::with_hashmap(&params.hscoring, 1.0),
+            distance_exponent: params.distance_exponent,
+            direction_distances: Arc::new(params.direction_distances.clone()),
+            min_tap_time: params.min_tap_time,
         }
     }
+
+    /// Effort of activating the same finger for both keys
+    fn effort(&self, prev_key: &LayerKey, curr_key: &LayerKey) -> f64 {
+        let travel = |key: &LayerKey| {
+            self.direction_distances
+                .get(&key.key.direction)
+                .copied()
+                .unwrap_or(1.0)
+        };
+
+        (travel(prev_key) + travel(curr_key))
+            .powf(self.distance_exponent)
+            .max(self.min_tap_time)
+    }
 }
 
 impl BigramMetric for KLASameFinger {
@@ -87,7 +146,7 @@ impl BigramMetric for KLASameFinger {
                         {
                             *finger_values
                                 .get_mut(&curr_used_key.key.hand, &curr_used_key.key.finger) +=
-                                *weight;
+                                *weight * self.effort(prev_used_key, curr_used_key);
                         }
                     }
                 });
@@ -121,3 +180,72 @@ impl BigramMetric for KLASameFinger {
         (cost, Some(message))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_layout};
+
+    fn parameters(north_distance: f64) -> Parameters {
+        let params: Parameters = serde_yaml::from_str(
+            "
+            ignore_modifiers: true
+            fscoring: {}
+            hscoring: {}
+            distance_exponent: 1.0
+            direction_distances:
+              Center: 1.0
+              South: 2.0
+            ",
+        )
+        .unwrap();
+        let mut direction_distances = params.direction_distances.clone();
+        direction_distances.insert(Direction::North, north_distance);
+
+        Parameters {
+            direction_distances,
+            ..params
+        }
+    }
+
+    /// The left pinky has "a" (North), "b" (Out), "c" (Center), "d" (In), and "e" (South).
+    fn cost(metric: &KLASameFinger, bigram: &str) -> f64 {
+        let layout = toy_layout("abcde");
+        let keys = layerkeys(&layout, bigram);
+        metric
+            .total_cost(&[((keys[0], keys[1]), 1.0)], None, &layout)
+            .0
+    }
+
+    #[test]
+    fn defaults_cost_one_per_activation() {
+        let params: Parameters =
+            serde_yaml::from_str("{ignore_modifiers: false, fscoring: {}, hscoring: {}}").unwrap();
+        let metric = KLASameFinger::new(&params);
+
+        assert_eq!(cost(&metric, "ae"), 1.0);
+        assert_eq!(cost(&metric, "cc"), 1.0);
+    }
+
+    #[test]
+    fn north_travel_only_affects_north_bigrams() {
+        let short = KLASameFinger::new(&parameters(2.0));
+        let long = KLASameFinger::new(&parameters(4.0));
+
+        assert_eq!(cost(&short, "ac"), 3.0);
+        assert_eq!(cost(&long, "ac"), 5.0);
+        assert_eq!(cost(&short, "cc"), cost(&long, "cc"));
+        assert_eq!(cost(&long, "ce"), 3.0);
+    }
+
+    #[test]
+    fn min_tap_time_is_a_floor() {
+        let metric = KLASameFinger::new(&Parameters {
+            min_tap_time: 2.5,
+            ..parameters(2.0)
+        });
+        assert_eq!(cost(&metric, "cc"), 2.5);
+        assert_eq!(cost(&metric, "ae"), 4.0);
+    }
+}
@@ -1,3 +1,20 @@
+//! Same hand metric that tries to resemble the one on klanext.keyboard-design.com.
+//!
+//! Each pair of consecutive symbols that both use a hand (including their modifiers) adds its
+//! weight to that hand. The per-hand sums are weighted by `hscoring`.
+//!
+//! ## Notes
+//!
+//! There is no other same-hand bigram metric in this crate to compare against. When relating
+//! the values to other statistics, keep in mind:
+//! - A bigram whose symbols use both hands (e.g. through a modifier on the other hand) counts
+//!   for both hands, so the sum can exceed the total weight. Without modifiers, each bigram
+//!   counts for at most one hand.
+//! - Same-finger bigrams and key repeats are counted as well.
+//! - With `ignore_thumbs`, only the thumb key itself is dropped; a modifier held by the thumb
+//!   still counts (unless `ignore_modifiers`).
+//! - The values are absolute weights, not percentages of the total weight.
+
 use super::BigramMetric;
 
 use ahash::AHashMap;
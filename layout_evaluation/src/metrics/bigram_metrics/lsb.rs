@@ -0,0 +1,200 @@
+//! Lateral Stretch Bigram (LSB) metric adapted to the Svalboard's key clusters.
+//!
+//! In contrast to [`super::oxey_lsbs::OxeyLsbs`], which looks at column offsets of a flat
+//! keyboard, a lateral stretch is a bigram on adjacent fingers of the same hand where both
+//! keys are pulled sideways in the same sense (In → In or Out → Out), see
+//! [`is_lateral_stretch`]. Such bigrams are not counted by [`super::hsb::Hsb`] (whose Lateral
+//! category covers In/Out combined with Center) nor by [`super::fsb::Fsb`].
+//!
+//! ## Configuration
+//! - `cost`: Cost of a lateral stretch
+//! - `finger_factors`: Per-finger multipliers, the larger factor of both fingers is used (optional)
+//! - `ignore_modifiers`: Whether to skip bigrams involving modifier keys
+//! - `n_worst` / `worst_threshold_pct`: Which of the worst bigrams to list
+
+use super::{scissor_base::is_lateral_stretch, BigramMetric};
+use crate::metrics::{worst_ngrams::WorstParameters, FingerFactors};
+
+use keyboard_layout::{
+    key::FingerMap,
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Cost of a lateral stretch
+    pub cost: f64,
+    /// Per-finger multipliers (e.g., stretching to the pinky is worse)
+    #[serde(default)]
+    pub finger_factors: FingerFactors,
+    pub ignore_modifiers: Option<bool>,
+    /// Which of the worst ngrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
+}
+
+#[derive(Clone, Debug)]
+pub struct Lsb {
+    cost: f64,
+    finger_factors: FingerMap<f64>,
+    ignore_modifiers: bool,
+    worst: WorstParameters,
+}
+
+impl Lsb {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            cost: params.cost,
+            finger_factors: params.finger_factors.to_finger_map(),
+            ignore_modifiers: params.ignore_modifiers.unwrap_or(false),
+            worst: params.worst,
+        }
+    }
+}
+
+impl BigramMetric for Lsb {
+    fn name(&self) -> &str {
+        "LSB"
+    }
+
+    fn is_additive(&self) -> bool {
+        true
+    }
+
+    fn worst_parameters(&self) -> WorstParameters {
+        self.worst
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        if self.ignore_modifiers && (k1.is_modifier.is_some() || k2.is_modifier.is_some()) {
+            return Some(0.0);
+        }
+
+        if !is_lateral_stretch(k1, k2) {
+            return Some(0.0);
+        }
+
+        let finger_factor = self
+            .finger_factors
+            .get(&k1.key.finger)
+            .max(*self.finger_factors.get(&k2.key.finger));
+
+        Some(weight * self.cost * finger_factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::bigram_metrics::{bigram_stats, hsb};
+
+    use keyboard_layout::{
+        key::Direction,
+        test_utils::{layerkeys, toy_layout},
+    };
+
+    fn lsb() -> Lsb {
+        let params: Parameters = serde_yaml::from_str(
+            "
+            cost: 2.0
+            finger_factors:
+              Pinky: 1.5
+            ",
+        )
+        .unwrap();
+        Lsb::new(&params)
+    }
+
+    /// An HSB metric that only penalizes its Lateral category
+    fn hsb_lateral() -> hsb::Hsb {
+        let params: hsb::Parameters = serde_yaml::from_str(
+            "
+            diagonal:
+              cost: 0.0
+            lateral:
+              cost: 1.0
+            ",
+        )
+        .unwrap();
+        hsb::Hsb::new(&params)
+    }
+
+    /// The left pinky has "a" (North), "b" (Out), "c" (Center), "d" (In), and "e" (South), the
+    /// left ring finger "f" to "j", and the left middle finger "k" to "o".
+    fn layout() -> Layout {
+        toy_layout("abcdefghijklmno")
+    }
+
+    fn cost(metric: &dyn BigramMetric, layout: &Layout, bigram: &str) -> Option<f64> {
+        let keys = layerkeys(layout, bigram);
+        metric.individual_cost(keys[0], keys[1], 1.0, 1.0, layout)
+    }
+
+    #[test]
+    fn lateral_stretches_in_the_same_sense() {
+        let layout = layout();
+        let lsb = lsb();
+
+        // Out → Out and In → In, the pinky factor dominates
+        assert_eq!(cost(&lsb, &layout, "bg"), Some(3.0));
+        assert_eq!(cost(&lsb, &layout, "in"), Some(2.0));
+        // opposite senses, with the Center, and not on adjacent fingers
+        assert_eq!(cost(&lsb, &layout, "bi"), Some(0.0));
+        assert_eq!(cost(&lsb, &layout, "bh"), Some(0.0));
+        assert_eq!(cost(&lsb, &layout, "bl"), Some(0.0));
+    }
+
+    #[test]
+    fn lsb_and_hsb_lateral_do_not_overlap() {
+        use Direction::*;
+
+        let layout = layout();
+        let lsb = lsb();
+        let hsb = hsb_lateral();
+
+        let lateral = |d: Direction| matches!(d, In | Out);
+        for k1 in layout.layerkeys.iter() {
+            for k2 in layout.layerkeys.iter() {
+                let bigram = format!("{}{}", k1.symbol, k2.symbol);
+                let is_lsb = cost(&lsb, &layout, &bigram).unwrap_or(0.0) > 0.0;
+                let is_hsb = cost(&hsb, &layout, &bigram).unwrap_or(0.0) > 0.0;
+                assert!(!(is_lsb && is_hsb), "{} counted twice", bigram);
+
+                // lateral bigrams on adjacent fingers are counted by exactly one of both, unless
+                // they are full scissors (squeeze or splay)
+                let (d1, d2) = (k1.key.direction, k2.key.direction);
+                let lateral_bigram = (lateral(d1) || lateral(d2))
+                    && (lateral(d1) || d1 == Center)
+                    && (lateral(d2) || d2 == Center)
+                    && !matches!((d1, d2), (In, Out) | (Out, In));
+                if k1.key.finger.distance(&k2.key.finger) == 1 && lateral_bigram {
+                    assert!(is_lsb || is_hsb, "{} not counted", bigram);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bigram_stats_report_lsbs() {
+        let layout = layout();
+        let params: bigram_stats::Parameters =
+            serde_yaml::from_str("ignore_thumbs: false").unwrap();
+        let bg = layerkeys(&layout, "bg");
+        let bh = layerkeys(&layout, "bh");
+        let bigrams = [((bg[0], bg[1]), 1.0), ((bh[0], bh[1]), 3.0)];
+
+        let stats = bigram_stats::BigramStats::new(&params).compute_statistics(&bigrams, None);
+        assert_eq!(stats.lsb_percent, 25.0);
+        assert_eq!(stats.lateral_percent, 75.0);
+    }
+}
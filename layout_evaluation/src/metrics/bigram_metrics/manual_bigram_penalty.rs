@@ -1,12 +1,21 @@
 //! The bigram metric [`ManualBigramPenalty`] incurrs costs if the bigram is mapped
 //! to one of a list of configurable "bad" key pairs (in terms of key locations).
+//!
+//! The message lists the worst penalized bigrams with their penalty and the number of
+//! configured key pairs that no bigram of the corpus is mapped to (which might indicate
+//! positions that do not exist on the keyboard).
 
 use super::BigramMetric;
+use crate::metrics::{
+    format_utils::{format_percentages, visualize_ngram},
+    worst_ngrams::{WorstParameters, WorstTracker},
+};
 
 use keyboard_layout::layout::{LayerKey, Layout};
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use serde::Deserialize;
+use std::sync::Arc;
 
 /// A tuple, structured the following way: (Column, Row)
 type MatrixPosition = (u8, u8);
@@ -15,11 +24,15 @@ type MatrixPosition = (u8, u8);
 pub struct Parameters {
     pub add_mirrored: bool,
     pub matrix_positions: AHashMap<(MatrixPosition, MatrixPosition), f64>,
+    /// Which of the worst penalized bigrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
 }
 
 #[derive(Clone, Debug)]
 pub struct ManualBigramPenalty {
-    matrix_positions: AHashMap<(MatrixPosition, MatrixPosition), f64>,
+    matrix_positions: Arc<AHashMap<(MatrixPosition, MatrixPosition), f64>>,
+    worst: WorstParameters,
 }
 
 impl ManualBigramPenalty {
@@ -36,7 +49,20 @@ impl ManualBigramPenalty {
             );
         }
 
-        Self { matrix_positions }
+        Self {
+            matrix_positions: Arc::new(matrix_positions),
+            worst: params.worst,
+        }
+    }
+
+    #[inline(always)]
+    fn penalty(&self, k1: &LayerKey, k2: &LayerKey) -> Option<f64> {
+        let x1 = k1.key.matrix_position.0;
+        let y1 = k1.key.matrix_position.1;
+        let x2 = k2.key.matrix_position.0;
+        let y2 = k2.key.matrix_position.1;
+
+        self.matrix_positions.get(&((x1, y1), (x2, y2))).copied()
     }
 }
 
@@ -54,15 +80,70 @@ impl BigramMetric for ManualBigramPenalty {
         _total_weight: f64,
         _layout: &Layout,
     ) -> Option<f64> {
-        let x1 = k1.key.matrix_position.0;
-        let y1 = k1.key.matrix_position.1;
-        let x2 = k2.key.matrix_position.0;
-        let y2 = k2.key.matrix_position.1;
+        Some(weight * self.penalty(k1, k2).unwrap_or(0.0))
+    }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let mut worst = WorstTracker::new(self.worst.selection());
+        if !worst.is_enabled() {
+            let total_cost = bigrams
+                .iter()
+                .filter_map(|((k1, k2), weight)| self.penalty(k1, k2).map(|p| weight * p))
+                .sum();
+
+            return (total_cost, None);
+        }
+
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+        let mut triggered = AHashSet::default();
+        let mut penalties = Vec::new();
+        let mut total_cost = 0.0;
+
+        for (i, ((k1, k2), weight)) in bigrams.iter().enumerate() {
+            if let Some(penalty) = self.penalty(k1, k2) {
+                let cost = weight * penalty;
+                total_cost += cost;
+                worst.push(penalties.len(), cost);
+                penalties.push((i, penalty));
+                triggered.insert((k1.key.matrix_position, k2.key.matrix_position));
+            }
+        }
+
+        let mut msgs: Vec<String> = worst
+            .into_sorted(total_cost)
+            .into_iter()
+            .map(|(j, cost)| {
+                let (i, penalty) = penalties[j];
+                let ((k1, k2), weight) = bigrams[i];
+                format!(
+                    "{} (penalty {}) {}",
+                    visualize_ngram(&[k1, k2]),
+                    penalty,
+                    format_percentages(cost, total_cost, weight, total_weight)
+                )
+            })
+            .collect();
 
-        if let Some(val) = self.matrix_positions.get(&((x1, y1), (x2, y2))) {
-            return Some(weight * *val);
+        let n_untriggered = self.matrix_positions.len() - triggered.len();
+        if n_untriggered > 0 {
+            msgs.push(format!(
+                "{} of {} key pairs not triggered",
+                n_untriggered,
+                self.matrix_positions.len()
+            ));
         }
 
-        Some(0.0)
+        let msg = if !msgs.is_empty() {
+            Some(msgs.join(", "))
+        } else {
+            None
+        };
+
+        (total_cost, msg)
     }
 }
@@ -1,32 +1,59 @@
 //! The bigram metric [`MovementPattern`] puts cost on each bigram that is mapped to
 //! (almost) neighboring fingers. Which finger combinations come with which costs is
-//! configurable.
+//! configurable. Each finger combination may additionally carry a table of costs by the
+//! directions of both keys (`direction_costs`) that overrides its cost for those directions.
+//!
+//! Optionally, the metric also covers the remaining kinds of (non-thumb) movements:
+//! - same finger, same hand: costs per pair of key directions (`costs`)
+//! - different fingers, same hand: the finger switch cost scaled by `finger_pair_multipliers`
+//! - hand switches: costs per pair of key directions (`cross_hand_costs`)
 
 use super::BigramMetric;
 
+use ahash::AHashMap;
 use keyboard_layout::{
-    key::{Finger, Hand, HandFingerMap},
+    key::{Direction, Finger, Hand, HandFingerMap},
     layout::{LayerKey, Layout},
 };
 
 use serde::Deserialize;
+use std::sync::Arc;
 
-#[derive(Copy, Clone, Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 pub struct FingerSwitchCost {
     pub from: (Hand, Finger),
     pub to: (Hand, Finger),
     pub cost: f64,
+    /// Costs overriding `cost` for specific directions of the two keys
+    #[serde(default)]
+    pub direction_costs: AHashMap<(Direction, Direction), f64>,
 }
 
+type FingerPairDirectionCosts = AHashMap<((Hand, Finger), (Hand, Finger)), DirectionCosts>;
+type DirectionCosts = AHashMap<(Direction, Direction), f64>;
+
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
     /// Cost associated with bigrams from a finger to another one
     finger_switch_factor: Vec<FingerSwitchCost>,
+    /// Cost of same-finger bigrams on the same hand, by the directions of the two keys
+    #[serde(default)]
+    pub costs: AHashMap<(Direction, Direction), f64>,
+    /// Multipliers for the finger switch cost of specific finger pairs (default 1.0)
+    #[serde(default)]
+    pub finger_pair_multipliers: AHashMap<(Finger, Finger), f64>,
+    /// Cost of bigrams switching hands, by the directions of the two keys
+    #[serde(default)]
+    pub cross_hand_costs: AHashMap<(Direction, Direction), f64>,
 }
 
 #[derive(Clone, Debug)]
 pub struct MovementPattern {
     finger_switch_factor: HandFingerMap<HandFingerMap<f64>>,
+    direction_costs: Arc<FingerPairDirectionCosts>,
+    costs: Arc<AHashMap<(Direction, Direction), f64>>,
+    finger_pair_multipliers: Arc<AHashMap<(Finger, Finger), f64>>,
+    cross_hand_costs: Arc<AHashMap<(Direction, Direction), f64>>,
 }
 
 impl MovementPattern {
@@ -38,8 +65,19 @@ impl MovementPattern {
             m.set(&fsc.to.0, &fsc.to.1, fsc.cost);
         });
 
+        let direction_costs = params
+            .finger_switch_factor
+            .iter()
+            .filter(|fsc| !fsc.direction_costs.is_empty())
+            .map(|fsc| ((fsc.from, fsc.to), fsc.direction_costs.clone()))
+            .collect();
+
         Self {
             finger_switch_factor,
+            direction_costs: Arc::new(direction_costs),
+            costs: Arc::new(params.costs.clone()),
+            finger_pair_multipliers: Arc::new(params.finger_pair_multipliers.clone()),
+            cross_hand_costs: Arc::new(params.cross_hand_costs.clone()),
         }
     }
 }
@@ -49,6 +87,10 @@ impl BigramMetric for MovementPattern {
         "Movement Pattern"
     }
 
+    fn is_additive(&self) -> bool {
+        true
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
@@ -63,12 +105,103 @@ impl BigramMetric for MovementPattern {
         let h1 = k1.key.hand;
         let h2 = k2.key.hand;
 
-        if f1 == Finger::Thumb || f2 == Finger::Thumb || h1 != h2 || f1 == f2 {
+        if f1 == Finger::Thumb || f2 == Finger::Thumb {
             return Some(0.0);
         }
 
-        let cost = self.finger_switch_factor.get(&h1, &f1).get(&h2, &f2);
+        let directions = (k1.key.direction, k2.key.direction);
+        let cost = if h1 != h2 {
+            self.cross_hand_costs
+                .get(&directions)
+                .copied()
+                .unwrap_or(0.0)
+        } else if f1 == f2 {
+            self.costs.get(&directions).copied().unwrap_or(0.0)
+        } else {
+            let multiplier = self
+                .finger_pair_multipliers
+                .get(&(f1, f2))
+                .copied()
+                .unwrap_or(1.0);
+            let switch_cost = self
+                .direction_costs
+                .get(&((h1, f1), (h2, f2)))
+                .and_then(|costs| costs.get(&directions))
+                .copied()
+                .unwrap_or_else(|| *self.finger_switch_factor.get(&h1, &f1).get(&h2, &f2));
+            multiplier * switch_cost
+        };
 
         Some(weight * cost)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_layout};
+
+    fn movement_pattern(params: &str) -> MovementPattern {
+        let params: Parameters = serde_yaml::from_str(params).unwrap();
+        MovementPattern::new(&params)
+    }
+
+    /// The left middle finger has "k" (North), "l" (Out), "m" (Center), "n" (In), and "o"
+    /// (South), the left index finger "p" to "t".
+    fn cost(metric: &MovementPattern, bigram: &str) -> Option<f64> {
+        let layout = toy_layout("abcdefghijklmnopqrst");
+        let keys = layerkeys(&layout, bigram);
+        metric.individual_cost(keys[0], keys[1], 1.0, 1.0, &layout)
+    }
+
+    #[test]
+    fn finger_pairs_without_direction_costs() {
+        let metric = movement_pattern(
+            "
+            finger_switch_factor:
+              - { from: [Left, Index], to: [Left, Middle], cost: 1.5 }
+            ",
+        );
+
+        for bigram in ["sl", "qn", "rm", "pk"] {
+            assert_eq!(cost(&metric, bigram), Some(1.5), "{}", bigram);
+        }
+        // only the configured direction of the finger pair
+        assert_eq!(cost(&metric, "ls"), Some(0.0));
+    }
+
+    #[test]
+    fn direction_costs_override_the_finger_pair_cost() {
+        let metric = movement_pattern(
+            "
+            finger_switch_factor:
+              - { from: [Left, Index], to: [Left, Middle], cost: 1.5,
+                  direction_costs: { [In, Out]: 0.2, [Out, In]: 3.0 } }
+            ",
+        );
+
+        // index In → middle Out is fine, index Out → middle In is bad
+        assert_eq!(cost(&metric, "sl"), Some(0.2));
+        assert_eq!(cost(&metric, "qn"), Some(3.0));
+        // other directions fall back to the finger pair cost
+        assert_eq!(cost(&metric, "rm"), Some(1.5));
+        assert_eq!(cost(&metric, "ql"), Some(1.5));
+    }
+
+    #[test]
+    fn direction_costs_are_scaled_by_finger_pair_multipliers() {
+        let metric = movement_pattern(
+            "
+            finger_switch_factor:
+              - { from: [Left, Index], to: [Left, Middle], cost: 1.5,
+                  direction_costs: { [Out, In]: 3.0 } }
+            finger_pair_multipliers:
+              [Index, Middle]: 2.0
+            ",
+        );
+
+        assert_eq!(cost(&metric, "qn"), Some(6.0));
+        assert_eq!(cost(&metric, "sl"), Some(3.0));
+    }
+}
@@ -2,25 +2,106 @@
 //! that starts with an unbalancing key and ends on the same hand (no thumbs). The cost increases
 //! with the square of the vertical distance. If the second key is unbalancing as well
 //! and horizontally far away (more than three keys), the cost is increased even further.
+//!
+//! By default, the unbalancing of a key is taken from the keyboard's `unbalancing_positions`.
+//! As these don't fit every keyboard (on the Svalboard, the unbalancing keys are e.g. the North
+//! and Out pulls of pinky and ring), the unbalancing keys can instead be declared by any of
+//! the following parameters. A key is unbalancing if it matches at least one of them:
+//! - `unbalancing_positions`: Matrix positions of unbalancing keys
+//! - `unbalancing_keys`: Pairs of finger and direction, e.g. `[Pinky, North]` (both hands)
+//! - `key_cost_threshold`: Keys whose cost is at least this value
+//! - `unbalancing_symbols`: Symbols whose keys are unbalancing, wherever they are placed
+//!
+//! Same-hand bigrams starting with such a key then cost `cost` (default 1.0), multiplied with
+//! `1 + center_distance_factor` if the second key is not a Center key.
+//!
+//! In both modes, `cost_by_hand` scales the cost by the hand of the unbalancing key
+//! (default 1.0), e.g. to model an asymmetric setup.
 
 use super::BigramMetric;
 
+use ahash::{AHashMap, AHashSet};
 use keyboard_layout::{
-    key::Finger,
+    key::{Direction, Finger, Hand, MatrixPosition},
     layout::{LayerKey, Layout},
 };
 
 use serde::Deserialize;
+use std::sync::Arc;
 
 #[derive(Clone, Deserialize, Debug)]
-pub struct Parameters {}
+pub struct Parameters {
+    /// Matrix positions of unbalancing keys
+    #[serde(default)]
+    pub unbalancing_positions: Vec<MatrixPosition>,
+    /// Fingers and directions of unbalancing keys (applies to both hands)
+    #[serde(default)]
+    pub unbalancing_keys: Vec<(Finger, Direction)>,
+    /// Keys with at least this cost are unbalancing
+    pub key_cost_threshold: Option<f64>,
+    /// Symbols whose keys are unbalancing
+    #[serde(default)]
+    pub unbalancing_symbols: AHashSet<char>,
+    /// Cost of a bigram after a declared unbalancing key
+    pub cost: Option<f64>,
+    /// Additional relative cost if the second key is away from the cluster's Center
+    pub center_distance_factor: Option<f64>,
+    /// Multiplier by the hand of the unbalancing key
+    #[serde(default)]
+    pub cost_by_hand: AHashMap<Hand, f64>,
+}
 
 #[derive(Clone, Debug)]
-pub struct NoHandSwitchAfterUnbalancingKey {}
+pub struct NoHandSwitchAfterUnbalancingKey {
+    unbalancing_positions: Vec<MatrixPosition>,
+    unbalancing_keys: Vec<(Finger, Direction)>,
+    key_cost_threshold: Option<f64>,
+    unbalancing_symbols: AHashSet<char>,
+    cost: f64,
+    center_distance_factor: f64,
+    cost_by_hand: Arc<AHashMap<Hand, f64>>,
+}
 
 impl NoHandSwitchAfterUnbalancingKey {
-    pub fn new(_params: &Parameters) -> Self {
-        Self {}
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            unbalancing_positions: params.unbalancing_positions.clone(),
+            unbalancing_keys: params.unbalancing_keys.clone(),
+            key_cost_threshold: params.key_cost_threshold,
+            unbalancing_symbols: params.unbalancing_symbols.clone(),
+            cost: params.cost.unwrap_or(1.0),
+            center_distance_factor: params.center_distance_factor.unwrap_or(0.0),
+            cost_by_hand: Arc::new(params.cost_by_hand.clone()),
+        }
+    }
+
+    /// Whether the unbalancing keys are declared in the parameters (instead of the keyboard)
+    fn has_declared_keys(&self) -> bool {
+        !self.unbalancing_positions.is_empty()
+            || !self.unbalancing_keys.is_empty()
+            || self.key_cost_threshold.is_some()
+            || !self.unbalancing_symbols.is_empty()
+    }
+
+    fn is_unbalancing(&self, key: &LayerKey) -> bool {
+        self.unbalancing_positions
+            .contains(&key.key.matrix_position)
+            || self
+                .unbalancing_keys
+                .contains(&(key.key.finger, key.key.direction))
+            || self
+                .key_cost_threshold
+                .map_or(false, |threshold| key.key.cost >= threshold)
+            || self.unbalancing_symbols.contains(&key.symbol)
+    }
+
+    /// Distance of the key from the Center of its cluster (in keys)
+    fn center_distance(key: &LayerKey) -> f64 {
+        if key.key.direction == Direction::Center {
+            0.0
+        } else {
+            1.0
+        }
     }
 }
 
@@ -29,6 +110,10 @@ impl BigramMetric for NoHandSwitchAfterUnbalancingKey {
         "No Handswitch After Unbalancing Key"
     }
 
+    fn is_additive(&self) -> bool {
+        true
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
@@ -46,10 +131,106 @@ impl BigramMetric for NoHandSwitchAfterUnbalancingKey {
             return Some(0.0);
         }
 
+        let hand_factor = self.cost_by_hand.get(&k1.key.hand).copied().unwrap_or(1.0);
+
+        if self.has_declared_keys() {
+            if k1 == k2 || !self.is_unbalancing(k1) {
+                return Some(0.0);
+            }
+
+            let distance_factor = 1.0 + self.center_distance_factor * Self::center_distance(k2);
+            return Some(weight * self.cost * distance_factor * hand_factor);
+        }
+
         let dunbx = (k1.key.unbalancing.0 - k2.key.unbalancing.0).abs();
         let dunby = (k1.key.unbalancing.1 - k2.key.unbalancing.1).abs();
 
         let cost = dunbx + dunby;
-        Some(weight * cost)
+        Some(weight * cost * hand_factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{key, layerkeys, toy_layout};
+
+    fn metric(params: &str) -> NoHandSwitchAfterUnbalancingKey {
+        let params: Parameters = serde_yaml::from_str(params).unwrap();
+        NoHandSwitchAfterUnbalancingKey::new(&params)
+    }
+
+    /// The left pinky has "a" (North), "b" (Out), "c" (Center), "d" (In), and "e" (South), the
+    /// left ring finger "f" to "j", and the left middle finger "k" to "o". The right pinky has
+    /// "J" (North) to "N" (South), the left thumb "O".
+    fn cost(metric: &NoHandSwitchAfterUnbalancingKey, bigram: &str) -> Option<f64> {
+        let layout = toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNO");
+        let keys = layerkeys(&layout, bigram);
+        metric.individual_cost(keys[0], keys[1], 1.0, 1.0, &layout)
+    }
+
+    #[test]
+    fn unbalancing_finger_directions() {
+        let metric = metric(
+            "
+            unbalancing_keys: [[Pinky, North], [Ring, Out]]
+            cost: 2.0
+            center_distance_factor: 0.5
+            ",
+        );
+
+        // followed by a Center key or a key away from the Center
+        assert_eq!(cost(&metric, "am"), Some(2.0));
+        assert_eq!(cost(&metric, "gm"), Some(2.0));
+        assert_eq!(cost(&metric, "ak"), Some(3.0));
+        assert_eq!(cost(&metric, "gb"), Some(3.0));
+        // on both hands
+        assert_eq!(cost(&metric, "JL"), Some(2.0));
+    }
+
+    #[test]
+    fn other_keys_are_balanced() {
+        let metric = metric("unbalancing_keys: [[Pinky, North], [Ring, Out]]");
+
+        // other directions of the fingers, other fingers, and the reverse bigram
+        assert_eq!(cost(&metric, "cm"), Some(0.0));
+        assert_eq!(cost(&metric, "fm"), Some(0.0));
+        assert_eq!(cost(&metric, "lm"), Some(0.0));
+        assert_eq!(cost(&metric, "ma"), Some(0.0));
+        // repeats, hand switches, and thumbs
+        assert_eq!(cost(&metric, "aa"), Some(0.0));
+        assert_eq!(cost(&metric, "aL"), Some(0.0));
+        assert_eq!(cost(&metric, "aO"), Some(0.0));
+    }
+
+    #[test]
+    fn hand_factors_scale_declared_keys() {
+        let metric = metric(
+            "
+            unbalancing_keys: [[Pinky, North]]
+            cost_by_hand: { Right: 0.5 }
+            ",
+        );
+
+        assert_eq!(cost(&metric, "am"), Some(1.0));
+        assert_eq!(cost(&metric, "JL"), Some(0.5));
+    }
+
+    #[test]
+    fn keyboard_unbalancing_without_declared_keys() {
+        let metric = metric("{}");
+        let layout = toy_layout("");
+        let k1 = key(Hand::Left, Finger::Pinky, Direction::North)
+            .unbalancing(1.0, 2.0)
+            .layerkey('a');
+        let k2 = key(Hand::Left, Finger::Middle, Direction::Center)
+            .unbalancing(0.5, 0.0)
+            .layerkey('b');
+
+        assert_eq!(
+            metric.individual_cost(&k1, &k2, 1.0, 1.0, &layout),
+            Some(2.5)
+        );
     }
 }
@@ -0,0 +1,176 @@
+//! The bigram metric [`OffCenterChaining`] incurs a cost for bigrams on different fingers of
+//! the same hand where both keys are off-Center (e.g. a North pull followed by an In pull).
+//! After such a keystroke, the finger has to return to the Center of its cluster while the
+//! next finger is already pulling, so the whole hand "floats" away from its home position.
+//! Key costs alone do not capture this, as each key is judged on its own.
+//!
+//! The cost of a bigram is `cost * severity(dir_1) * severity(dir_2)`. Direction pairs listed
+//! in `comfortable_pairs` are exempt. Thumbs are excluded.
+//!
+//! The message reports the share of same-hand bigrams (different fingers) that are fully
+//! off-Center, followed by the worst bigrams.
+//!
+//! ## Parameters
+//! - `cost`: Base cost of a chained off-Center bigram
+//! - `severities`: Factor per key direction (default 1.0)
+//! - `comfortable_pairs`: Direction pairs without cost, e.g. `[South, South]`
+//! - `ignore_modifiers`: Whether to skip bigrams involving modifier keys
+//! - `n_worst` / `worst_threshold_pct`: Which of the worst bigrams to list
+
+use super::BigramMetric;
+use crate::{
+    metrics::{
+        format_utils::{format_percentages, visualize_ngram},
+        worst_ngrams::{WorstParameters, WorstTracker},
+    },
+    output_style::underline,
+};
+
+use ahash::AHashMap;
+use keyboard_layout::{
+    key::{Direction, Finger},
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Base cost of a chained off-Center bigram
+    pub cost: f64,
+    /// Factor per key direction (default 1.0)
+    #[serde(default)]
+    pub severities: AHashMap<Direction, f64>,
+    /// Direction pairs that are exempt from the cost
+    #[serde(default)]
+    pub comfortable_pairs: Vec<(Direction, Direction)>,
+    pub ignore_modifiers: Option<bool>,
+    /// Which of the worst bigrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
+}
+
+#[derive(Clone, Debug)]
+pub struct OffCenterChaining {
+    cost: f64,
+    severities: Arc<AHashMap<Direction, f64>>,
+    comfortable_pairs: Vec<(Direction, Direction)>,
+    ignore_modifiers: bool,
+    worst: WorstParameters,
+}
+
+impl OffCenterChaining {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            cost: params.cost,
+            severities: Arc::new(params.severities.clone()),
+            comfortable_pairs: params.comfortable_pairs.clone(),
+            ignore_modifiers: params.ignore_modifiers.unwrap_or(false),
+            worst: params.worst,
+        }
+    }
+
+    /// Whether the bigram is on different fingers of the same hand (no thumbs)
+    #[inline(always)]
+    fn is_same_hand_switch(&self, k1: &LayerKey, k2: &LayerKey) -> bool {
+        k1.key.hand == k2.key.hand
+            && k1.key.finger != k2.key.finger
+            && k1.key.finger != Finger::Thumb
+            && k2.key.finger != Finger::Thumb
+            && !(self.ignore_modifiers && (k1.is_modifier.is_some() || k2.is_modifier.is_some()))
+    }
+
+    #[inline(always)]
+    fn is_off_center(k1: &LayerKey, k2: &LayerKey) -> bool {
+        k1.key.direction != Direction::Center && k2.key.direction != Direction::Center
+    }
+
+    #[inline(always)]
+    fn severity(&self, direction: Direction) -> f64 {
+        self.severities.get(&direction).copied().unwrap_or(1.0)
+    }
+}
+
+impl BigramMetric for OffCenterChaining {
+    fn name(&self) -> &str {
+        "Off-Center Chaining"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        if !self.is_same_hand_switch(k1, k2) || !Self::is_off_center(k1, k2) {
+            return Some(0.0);
+        }
+
+        let directions = (k1.key.direction, k2.key.direction);
+        if self.comfortable_pairs.contains(&directions) {
+            return Some(0.0);
+        }
+
+        Some(weight * self.cost * self.severity(directions.0) * self.severity(directions.1))
+    }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+        let mut worst = WorstTracker::new(self.worst.selection());
+
+        let mut same_hand_weight = 0.0;
+        let mut off_center_weight = 0.0;
+        let mut total_cost = 0.0;
+
+        for (i, ((k1, k2), weight)) in bigrams.iter().enumerate() {
+            if !self.is_same_hand_switch(k1, k2) {
+                continue;
+            }
+
+            same_hand_weight += weight;
+            if !Self::is_off_center(k1, k2) {
+                continue;
+            }
+            off_center_weight += weight;
+
+            let cost = self
+                .individual_cost(k1, k2, *weight, total_weight, layout)
+                .unwrap_or(0.0);
+            total_cost += cost;
+            worst.push(i, cost);
+        }
+
+        let mut msg = format!(
+            "{}: {:.1}% of same-hand bigrams",
+            underline("Off-Center"),
+            crate::metrics::utils::to_percentage(off_center_weight, same_hand_weight)
+        );
+
+        let worst_msgs: Vec<String> = worst
+            .into_sorted(total_cost)
+            .into_iter()
+            .map(|(i, cost)| {
+                let ((k1, k2), weight) = bigrams[i];
+                format!(
+                    "{} {}",
+                    visualize_ngram(&[k1, k2]),
+                    format_percentages(cost, total_cost, weight, total_weight)
+                )
+            })
+            .collect();
+        if !worst_msgs.is_empty() {
+            msg.push_str(&format!("; {}", worst_msgs.join(", ")));
+        }
+
+        (total_cost, Some(msg))
+    }
+}
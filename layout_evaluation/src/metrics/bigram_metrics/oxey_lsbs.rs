@@ -1,18 +1,46 @@
+//! Lateral stretch bigram metric as used by Oxey's layout analyzer. A bigram on adjacent
+//! fingers of the same hand is a lateral stretch if its keys are at least
+//! `min_column_distance` matrix columns apart (default 2).
+//!
+//! Each stretch costs its weight times the factor of its finger pair in `finger_pair_costs`
+//! (in either order, default 1.0). As only adjacent fingers are considered, the pairs to
+//! configure are e.g. `[Ring, Pinky]` or `[Index, Middle]`.
+//!
+//! The worst bigrams are listed together with their column distance.
+
 use super::BigramMetric;
+use crate::metrics::{
+    format_utils::{format_percentages, visualize_ngram},
+    worst_ngrams::{WorstParameters, WorstTracker},
+};
 
-use ahash::AHashSet;
+use ahash::{AHashMap, AHashSet};
 use keyboard_layout::{
     key::Finger,
     layout::{LayerKey, Layout},
 };
 
 use serde::Deserialize;
+use std::sync::Arc;
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
     exclude_thumbs: bool,
     exclude_modifiers: bool,
     exclude_chars: Vec<char>,
+    /// Minimum number of matrix columns between the keys of a stretch
+    #[serde(default = "default_min_column_distance")]
+    min_column_distance: u8,
+    /// Cost factors per finger pair (default 1.0)
+    #[serde(default)]
+    finger_pair_costs: AHashMap<(Finger, Finger), f64>,
+    /// Which of the worst bigrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    worst: WorstParameters,
+}
+
+fn default_min_column_distance() -> u8 {
+    2
 }
 
 #[derive(Clone, Debug)]
@@ -20,6 +48,9 @@ pub struct OxeyLsbs {
     exclude_thumbs: bool,
     exclude_modifiers: bool,
     exclude_chars: AHashSet<char>,
+    min_column_distance: u8,
+    finger_pair_costs: Arc<AHashMap<(Finger, Finger), f64>>,
+    worst: WorstParameters,
 }
 
 impl OxeyLsbs {
@@ -28,8 +59,24 @@ impl OxeyLsbs {
             exclude_thumbs: params.exclude_thumbs,
             exclude_modifiers: params.exclude_modifiers,
             exclude_chars: params.exclude_chars.iter().cloned().collect(),
+            min_column_distance: params.min_column_distance,
+            finger_pair_costs: Arc::new(params.finger_pair_costs.clone()),
+            worst: params.worst,
         }
     }
+
+    #[inline(always)]
+    fn column_distance(k1: &LayerKey, k2: &LayerKey) -> u8 {
+        k1.key.matrix_position.0.abs_diff(k2.key.matrix_position.0)
+    }
+
+    fn finger_pair_cost(&self, f1: Finger, f2: Finger) -> f64 {
+        self.finger_pair_costs
+            .get(&(f1, f2))
+            .or_else(|| self.finger_pair_costs.get(&(f2, f1)))
+            .copied()
+            .unwrap_or(1.0)
+    }
 }
 
 impl BigramMetric for OxeyLsbs {
@@ -73,11 +120,51 @@ impl BigramMetric for OxeyLsbs {
             return Some(0.0);
         }
 
-        if f1.distance(&f2) == 1 && k1.key.matrix_position.0.abs_diff(k2.key.matrix_position.0) > 1
-        {
-            Some(weight)
+        if f1.distance(&f2) == 1 && Self::column_distance(k1, k2) >= self.min_column_distance {
+            Some(weight * self.finger_pair_cost(f1, f2))
         } else {
             Some(0.0)
         }
     }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+        let mut worst = WorstTracker::new(self.worst.selection());
+
+        let mut total_cost = 0.0;
+        for (i, ((k1, k2), weight)) in bigrams.iter().enumerate() {
+            let cost = self
+                .individual_cost(k1, k2, *weight, total_weight, layout)
+                .unwrap_or(0.0);
+            total_cost += cost;
+            worst.push(i, cost);
+        }
+
+        let worst_msgs: Vec<String> = worst
+            .into_sorted(total_cost)
+            .into_iter()
+            .map(|(i, cost)| {
+                let ((k1, k2), weight) = bigrams[i];
+                format!(
+                    "{} ({} cols) {}",
+                    visualize_ngram(&[k1, k2]),
+                    Self::column_distance(k1, k2),
+                    format_percentages(cost, total_cost, weight, total_weight)
+                )
+            })
+            .collect();
+
+        let msg = if worst_msgs.is_empty() {
+            None
+        } else {
+            Some(worst_msgs.join(", "))
+        };
+
+        (total_cost, msg)
+    }
 }
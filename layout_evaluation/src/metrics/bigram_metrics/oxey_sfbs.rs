@@ -1,8 +1,31 @@
+//! Same finger bigram metric as used by Oxey's layout analyzer. Each same finger bigram costs
+//! its weight, i.e. the result is the share of SFBs.
+//!
+//! ## Differences to [`super::sfb::Sfb`]
+//!
+//! - All SFBs cost the same; there are no direction-pair costs, finger factors or critical
+//!   bigram factors.
+//! - Repeats of the same key are always skipped (`Sfb`: `exclude_same_position_repeats`), but
+//!   the same symbol on different layers always counts (`Sfb`: `exclude_same_symbol_repeats`).
+//! - Bigrams containing one of the `exclude_chars` are skipped.
+//!
+//! Modifiers and thumbs are treated the same way by both metrics.
+//!
+//! With `direction_breakdown`, the message lists the share of SFBs per direction pair. The
+//! worst bigrams are listed together with their direction pair.
+
 use super::BigramMetric;
+use crate::{
+    metrics::{
+        format_utils::{format_percentages, visualize_ngram},
+        worst_ngrams::{WorstParameters, WorstTracker},
+    },
+    output_style::underline,
+};
 
-use ahash::AHashSet;
+use ahash::{AHashMap, AHashSet};
 use keyboard_layout::{
-    key::Finger,
+    key::{Direction, Finger},
     layout::{LayerKey, Layout},
 };
 
@@ -13,6 +36,12 @@ pub struct Parameters {
     exclude_thumbs: bool,
     exclude_modifiers: bool,
     exclude_chars: Vec<char>,
+    /// List the share of SFBs per direction pair in the message
+    #[serde(default)]
+    direction_breakdown: bool,
+    /// Which of the worst bigrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    worst: WorstParameters,
 }
 
 #[derive(Clone, Debug)]
@@ -20,6 +49,8 @@ pub struct OxeySfbs {
     exclude_thumbs: bool,
     exclude_modifiers: bool,
     exclude_chars: AHashSet<char>,
+    direction_breakdown: bool,
+    worst: WorstParameters,
 }
 
 impl OxeySfbs {
@@ -28,6 +59,8 @@ impl OxeySfbs {
             exclude_thumbs: params.exclude_thumbs,
             exclude_modifiers: params.exclude_modifiers,
             exclude_chars: params.exclude_chars.iter().cloned().collect(),
+            direction_breakdown: params.direction_breakdown,
+            worst: params.worst,
         }
     }
 }
@@ -79,4 +112,202 @@ impl BigramMetric for OxeySfbs {
             Some(0.0)
         }
     }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+        let mut worst = WorstTracker::new(self.worst.selection());
+        let mut direction_costs: AHashMap<(Direction, Direction), f64> = AHashMap::new();
+
+        let mut total_cost = 0.0;
+        for (i, ((k1, k2), weight)) in bigrams.iter().enumerate() {
+            let cost = self
+                .individual_cost(k1, k2, *weight, total_weight, layout)
+                .unwrap_or(0.0);
+            if cost == 0.0 {
+                continue;
+            }
+
+            total_cost += cost;
+            worst.push(i, cost);
+            if self.direction_breakdown {
+                *direction_costs
+                    .entry((k1.key.direction, k2.key.direction))
+                    .or_insert(0.0) += cost;
+            }
+        }
+
+        let mut groups = Vec::new();
+
+        if self.direction_breakdown && !direction_costs.is_empty() {
+            let mut direction_costs: Vec<((Direction, Direction), f64)> =
+                direction_costs.into_iter().collect();
+            #[allow(clippy::incompatible_msrv)]
+            direction_costs.sort_by(|(_, c1), (_, c2)| c2.total_cmp(c1));
+
+            let breakdown: Vec<String> = direction_costs
+                .into_iter()
+                .map(|((from, to), cost)| {
+                    format!(
+                        "{:?}→{:?}: {:.2}%",
+                        from,
+                        to,
+                        crate::metrics::utils::to_percentage(cost, total_weight)
+                    )
+                })
+                .collect();
+            groups.push(format!(
+                "{}: {}",
+                underline("Directions"),
+                breakdown.join(", ")
+            ));
+        }
+
+        let worst_msgs: Vec<String> = worst
+            .into_sorted(total_cost)
+            .into_iter()
+            .map(|(i, cost)| {
+                let ((k1, k2), weight) = bigrams[i];
+                format!(
+                    "{} ({:?}→{:?}) {}",
+                    visualize_ngram(&[k1, k2]),
+                    k1.key.direction,
+                    k2.key.direction,
+                    format_percentages(cost, total_cost, weight, total_weight)
+                )
+            })
+            .collect();
+        if !worst_msgs.is_empty() {
+            groups.push(worst_msgs.join(", "));
+        }
+
+        let msg = if groups.is_empty() {
+            None
+        } else {
+            Some(groups.join("; "))
+        };
+
+        (total_cost, msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::metrics::bigram_metrics::sfb::{self, Sfb};
+    use keyboard_layout::{
+        layout::{LayerModifierType, LayerModifiers},
+        test_utils::{layerkeys, toy_layout},
+    };
+
+    fn oxey_sfbs(exclude_chars: &str, direction_breakdown: bool) -> OxeySfbs {
+        OxeySfbs::new(&Parameters {
+            exclude_thumbs: true,
+            exclude_modifiers: true,
+            exclude_chars: exclude_chars.chars().collect(),
+            direction_breakdown,
+            worst: WorstParameters::default(),
+        })
+    }
+
+    fn sfb(exclude_same_symbol_repeats: bool) -> Sfb {
+        let params: sfb::Parameters = serde_yaml::from_str(&format!(
+            "{{default_cost: 1.0, costs: {{}}, ignore_thumbs: true, \
+             exclude_same_symbol_repeats: {}}}",
+            exclude_same_symbol_repeats
+        ))
+        .unwrap();
+        Sfb::new(&params)
+    }
+
+    /// The left pinky has "a" (North), "b" (Out), "c" (Center), "d" (In), and "e" (South).
+    fn layout() -> Layout {
+        toy_layout("abcdefghij")
+    }
+
+    fn cost(metric: &dyn BigramMetric, layout: &Layout, k1: &LayerKey, k2: &LayerKey) -> f64 {
+        metric
+            .individual_cost(k1, k2, 1.0, 1.0, layout)
+            .unwrap_or(0.0)
+    }
+
+    #[test]
+    fn sfbs_cost_their_weight() {
+        let layout = layout();
+        let metric = oxey_sfbs("", false);
+        let keys = layerkeys(&layout, "acf");
+
+        assert_eq!(cost(&metric, &layout, keys[0], keys[1]), 1.0);
+        assert_eq!(cost(&metric, &layout, keys[0], keys[2]), 0.0);
+        // same as `Sfb` with unit costs
+        assert_eq!(cost(&sfb(false), &layout, keys[0], keys[1]), 1.0);
+    }
+
+    #[test]
+    fn same_key_repeats_are_skipped_like_in_sfb() {
+        let layout = layout();
+        let keys = layerkeys(&layout, "a");
+
+        assert_eq!(cost(&oxey_sfbs("", false), &layout, keys[0], keys[0]), 0.0);
+        assert_eq!(cost(&sfb(false), &layout, keys[0], keys[0]), 0.0);
+    }
+
+    #[test]
+    fn same_symbol_on_other_layer_always_counts() {
+        let layout = layout();
+        let base = layerkeys(&layout, "a")[0];
+        let upper = LayerKey::new(
+            1,
+            base.key.clone(),
+            base.symbol,
+            LayerModifiers::default(),
+            false,
+            LayerModifierType::None,
+        );
+
+        assert_eq!(cost(&oxey_sfbs("", false), &layout, base, &upper), 1.0);
+        assert_eq!(cost(&sfb(false), &layout, base, &upper), 1.0);
+        // `Sfb` can skip them
+        assert_eq!(cost(&sfb(true), &layout, base, &upper), 0.0);
+    }
+
+    #[test]
+    fn excluded_chars_are_skipped() {
+        let layout = layout();
+        let keys = layerkeys(&layout, "ac");
+
+        assert_eq!(cost(&oxey_sfbs("c", false), &layout, keys[0], keys[1]), 0.0);
+        // `Sfb` has no such option
+        assert_eq!(cost(&sfb(false), &layout, keys[0], keys[1]), 1.0);
+    }
+
+    #[test]
+    fn message_lists_direction_pairs() {
+        let layout = layout();
+        let ac = layerkeys(&layout, "ac");
+        let ae = layerkeys(&layout, "ae");
+        let af = layerkeys(&layout, "af");
+        let bigrams = [
+            ((ac[0], ac[1]), 1.0),
+            ((ae[0], ae[1]), 3.0),
+            ((af[0], af[1]), 4.0),
+        ];
+
+        let (cost, msg) = oxey_sfbs("", false).total_cost(&bigrams, None, &layout);
+        let msg = msg.unwrap();
+        assert_eq!(cost, 4.0);
+        assert!(msg.contains("ae (North→South)"), "{}", msg);
+        assert!(msg.contains("ac (North→Center)"), "{}", msg);
+        assert!(!msg.contains("Directions"), "{}", msg);
+
+        let (_, msg) = oxey_sfbs("", true).total_cost(&bigrams, None, &layout);
+        let msg = msg.unwrap();
+        let directions = msg.find("North→South: 37.50%").expect(&msg);
+        assert!(directions < msg.find("North→Center: 12.50%").expect(&msg));
+    }
 }
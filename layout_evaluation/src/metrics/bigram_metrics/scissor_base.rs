@@ -3,31 +3,70 @@
 //! This module provides generic infrastructure for scissor metrics that:
 //! - Track worst bigrams by category (e.g., Vertical, Squeeze, Diagonal)
 //! - Apply optional frequency-based multipliers for critical bigrams
-//! - Apply optional finger-specific multipliers
+//!
+//! Finger-specific multipliers are part of the category costs, so that each category can have
+//! its own (see [`ScissorCompute::compute_cost`]).
 //! - Format output with consistent whitespace visualization and percentage display
-use super::BigramMetric;
-use crate::metrics::format_utils::{format_percentages, visualize_whitespace};
-use ahash::AHashMap;
+use super::{BigramMetric, CriticalBigramParams};
+use crate::{
+    metrics::{
+        format_utils::{format_applicable_weight, format_percentages, visualize_ngram},
+        key_pair_table::KeyPairTable,
+        worst_ngrams::{TrackingMode, WorstParameters, WorstTracker},
+    },
+    output_style::ColorConfig,
+};
 use keyboard_layout::{
-    key::Finger,
+    keyboard::Keyboard,
     layout::{LayerKey, Layout},
 };
-use ordered_float::OrderedFloat;
-use priority_queue::DoublePriorityQueue;
-use std::{collections::HashMap, env, fmt::Debug, hash::Hash};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt::Debug, hash::Hash};
 
 /// Trait for scissor metric categories (Vertical, Squeeze, Diagonal, etc.)
 pub trait ScissorCategory: Clone + Debug + PartialEq + Eq + Hash + Send + Sync {
-    /// Get all categories in display order
+    /// Get all categories in display order (see also [`ScissorMetric::with_display_order`])
     fn display_order() -> &'static [Self];
 
-    /// Get the display name for this category
-    fn display_name(&self) -> String;
+    /// Get the display name for this category (styled according to `color`)
+    fn display_name(&self, color: ColorConfig) -> String;
 }
 
 /// Trait for computing scissor costs
-pub trait ScissorCompute<C: ScissorCategory>: Clone + Debug + Send + Sync {
-    fn compute_cost(&self, k1: &LayerKey, k2: &LayerKey, layout: &Layout) -> Option<(f64, C)>;
+pub trait ScissorCompute<C: ScissorCategory>: ScissorComputeClone<C> + Debug + Send + Sync {
+    /// The cost (including the finger factors of the category) and the category of a bigram,
+    /// if it is a scissor. It may only depend on the physical keys of `k1` and `k2`, as it is
+    /// precomputed for all pairs of keys (see [`BigramMetric::precompute`]).
+    fn compute_cost(&self, k1: &LayerKey, k2: &LayerKey) -> Option<(f64, C)>;
+}
+
+impl<C: ScissorCategory + 'static> Clone for Box<dyn ScissorCompute<C>> {
+    fn clone(&self) -> Box<dyn ScissorCompute<C>> {
+        self.clone_box()
+    }
+}
+
+/// Helper trait for realizing clonability for `Box<dyn ScissorCompute<C>>`.
+pub trait ScissorComputeClone<C: ScissorCategory> {
+    fn clone_box(&self) -> Box<dyn ScissorCompute<C>>;
+}
+
+impl<C, T> ScissorComputeClone<C> for T
+where
+    C: ScissorCategory,
+    T: 'static + ScissorCompute<C> + Clone,
+{
+    fn clone_box(&self) -> Box<dyn ScissorCompute<C>> {
+        Box::new(self.clone())
+    }
+}
+
+/// Allows choosing the cost computation at runtime, see [`DynScissorMetric`].
+impl<C: ScissorCategory + 'static> ScissorCompute<C> for Box<dyn ScissorCompute<C>> {
+    #[inline(always)]
+    fn compute_cost(&self, k1: &LayerKey, k2: &LayerKey) -> Option<(f64, C)> {
+        self.as_ref().compute_cost(k1, k2)
+    }
 }
 
 /// Check if two keys represent adjacent non-thumb fingers on the same hand
@@ -49,7 +88,7 @@ pub fn is_adjacent_fingers(k1: &LayerKey, k2: &LayerKey) -> bool {
 }
 
 /// Classification of scissor movement types
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
 pub enum ScissorType {
     /// Full Scissor Vertical - North-South opposition
     Vertical,
@@ -61,13 +100,23 @@ pub enum ScissorType {
     Diagonal,
     /// Lateral - Lateral displacement with center
     Lateral,
+    /// Extend Up - Both fingers pressing North
+    ExtendUp,
+    /// Extend Down - Both fingers pressing South
+    ExtendDown,
 }
 
 /// Classify a bigram as a scissor movement type
 ///
 /// Returns `Some(ScissorType)` if the bigram represents a scissor movement,
 /// or `None` if it's not a scissor (e.g., rolling motion, different hands, etc.)
-#[inline]
+///
+/// For adjacent fingers, all direction pairs except the following are scissors:
+/// - `(In, In)` and `(Out, Out)`: lateral stretches, see [`is_lateral_stretch`]
+/// - `(Center, Center)`: both fingers at rest
+/// - `(Center, North)`, `(North, Center)`, `(Center, South)` and `(South, Center)`: only one
+///   finger moves vertically
+#[inline(always)]
 pub fn classify_scissor(k1: &LayerKey, k2: &LayerKey) -> Option<ScissorType> {
     use keyboard_layout::key::{Direction::*, Finger};
 
@@ -86,7 +135,7 @@ pub fn classify_scissor(k1: &LayerKey, k2: &LayerKey) -> Option<ScissorType> {
     let dir_to = k2.key.direction;
 
     match (dir_from, dir_to) {
-        // NOT a scissor: just rolling (same lateral direction)
+        // NOT a scissor: same lateral direction (lateral stretch, see `is_lateral_stretch`)
         (In, In) | (Out, Out) => None,
 
         // Full Scissor Vertical - North-South opposition
@@ -117,92 +166,159 @@ pub fn classify_scissor(k1: &LayerKey, k2: &LayerKey) -> Option<ScissorType> {
         // Lateral - Lateral displacement with center
         (In, Center) | (Out, Center) | (Center, In) | (Center, Out) => Some(ScissorType::Lateral),
 
-        // All other combinations: not considered scissors
+        // Same vertical direction - awkward on the Svalboard due to the key cluster geometry
+        (North, North) => Some(ScissorType::ExtendUp),
+        (South, South) => Some(ScissorType::ExtendDown),
+
+        // NOT a scissor: no movement or a vertical movement of only one finger
+        (Center, Center)
+        | (Center, North)
+        | (North, Center)
+        | (Center, South)
+        | (South, Center) => None,
+
+        // Thumb directions (excluded above)
         _ => None,
     }
 }
 
+/// Check if a bigram is a lateral stretch (LSB) on the Svalboard
+///
+/// A lateral stretch uses adjacent non-thumb fingers of the same hand that are both pulled
+/// sideways in the same sense (In → In or Out → Out). These bigrams are never classified
+/// by [`classify_scissor`], so LSBs and (half) scissors do not overlap.
+#[inline]
+pub fn is_lateral_stretch(k1: &LayerKey, k2: &LayerKey) -> bool {
+    use keyboard_layout::key::Direction::*;
+
+    is_adjacent_fingers(k1, k2)
+        && matches!((k1.key.direction, k2.key.direction), (In, In) | (Out, Out))
+}
+
+/// `None` if the keys of a bigram are not on adjacent fingers (the bigram is not applicable),
+/// otherwise the cost and category of the bigram if it is a scissor
+type KeyPairCost<C> = Option<Option<(f64, C)>>;
+
 /// Generic scissor metric implementation
 #[derive(Clone, Debug)]
 pub struct ScissorMetric<C: ScissorCategory, T: ScissorCompute<C>> {
     name: &'static str,
-    critical_bigram_fraction: Option<f64>,
-    critical_bigram_factor: Option<f64>,
-    finger_factors: Option<AHashMap<Finger, f64>>,
+    critical_bigram: Option<CriticalBigramParams>,
+    worst: WorstParameters,
+    tracking_mode: TrackingMode,
+    display_order: Option<Vec<C>>,
+    /// Styling of the category labels (the global output style if `None`)
+    color: Option<ColorConfig>,
     compute: T,
+    /// Results of [`ScissorMetric::key_pair_cost`] for all pairs of keys (see
+    /// [`BigramMetric::precompute`])
+    key_pair_costs: Option<KeyPairTable<KeyPairCost<C>>>,
     _phantom: std::marker::PhantomData<C>,
 }
 
+/// A [`ScissorMetric`] whose cost computation is a trait object, e.g. for composing metrics
+/// from configuration at runtime. Apart from the dynamic dispatch, it behaves exactly like
+/// the statically dispatched variant used by [`super::fsb::Fsb`] and [`super::hsb::Hsb`].
+pub type DynScissorMetric<C> = ScissorMetric<C, Box<dyn ScissorCompute<C>>>;
+
 impl<C: ScissorCategory, T: ScissorCompute<C>> ScissorMetric<C, T> {
     pub fn new(
         name: &'static str,
-        critical_bigram_fraction: Option<f64>,
-        critical_bigram_factor: Option<f64>,
-        finger_factors: Option<AHashMap<Finger, f64>>,
+        critical_bigram: Option<CriticalBigramParams>,
         compute: T,
     ) -> Self {
         Self {
             name,
-            critical_bigram_fraction,
-            critical_bigram_factor,
-            finger_factors,
+            critical_bigram,
+            worst: WorstParameters::default(),
+            tracking_mode: TrackingMode::default(),
+            display_order: None,
+            color: None,
             compute,
+            key_pair_costs: None,
             _phantom: std::marker::PhantomData,
         }
     }
 
+    /// Configure which of the worst bigrams of each category are listed.
+    pub fn with_worst_parameters(mut self, worst: WorstParameters) -> Self {
+        self.worst = worst;
+        self
+    }
+
+    /// Configure when the worst bigrams are tracked. Without tracking, no per-category
+    /// trackers are allocated.
+    pub fn with_tracking_mode(mut self, tracking_mode: TrackingMode) -> Self {
+        self.tracking_mode = tracking_mode;
+        self
+    }
+
+    /// Override the order in which the categories are listed ([`ScissorCategory::display_order`]
+    /// if `None`). Categories that are not contained are not listed, but still count towards
+    /// the cost.
+    pub fn with_display_order(mut self, display_order: Option<Vec<C>>) -> Self {
+        self.display_order = display_order;
+        self
+    }
+
+    /// Style the category labels according to `color` instead of the global output style.
+    pub fn with_color_config(mut self, color: ColorConfig) -> Self {
+        self.color = Some(color);
+        self
+    }
+
     /// Calculate frequency multiplier for critical bigrams
     #[inline]
     fn frequency_multiplier(&self, weight: f64, total_weight: f64) -> f64 {
-        if let (Some(threshold), Some(factor)) =
-            (self.critical_bigram_fraction, self.critical_bigram_factor)
-        {
-            let relative_weight = weight / total_weight;
-            if relative_weight > threshold {
-                factor
-            } else {
-                1.0
-            }
-        } else {
-            1.0
-        }
+        self.critical_bigram.map_or(1.0, |critical_bigram| {
+            critical_bigram.multiplier(weight, total_weight)
+        })
     }
 
-    /// Calculate finger multiplier based on both fingers involved
-    /// Uses the maximum factor since the weaker finger dominates comfort
-    #[inline]
-    fn finger_multiplier(&self, k1: &LayerKey, k2: &LayerKey) -> f64 {
-        if let Some(ref factors) = self.finger_factors {
-            let factor1 = factors.get(&k1.key.finger).copied().unwrap_or(1.0);
-            let factor2 = factors.get(&k2.key.finger).copied().unwrap_or(1.0);
-            factor1.max(factor2)
-        } else {
-            1.0
+    /// The [`KeyPairCost`] of a bigram
+    fn key_pair_cost(&self, k1: &LayerKey, k2: &LayerKey) -> KeyPairCost<C> {
+        if !is_adjacent_fingers(k1, k2) {
+            return None;
         }
-    }
 
-    fn bigram_cost_with_category(
-        &self,
-        k1: &LayerKey,
-        k2: &LayerKey,
-        layout: &Layout,
-    ) -> Option<(f64, C)> {
-        self.compute.compute_cost(k1, k2, layout)
+        Some(self.compute.compute_cost(k1, k2))
     }
 
-    fn bigram_cost(&self, k1: &LayerKey, k2: &LayerKey, layout: &Layout) -> Option<f64> {
-        self.bigram_cost_with_category(k1, k2, layout)
-            .map(|(cost, _)| cost)
+    /// [`ScissorMetric::key_pair_cost`], looked up in the precomputed table if possible
+    #[inline(always)]
+    fn bigram_cost_with_category(&self, k1: &LayerKey, k2: &LayerKey) -> KeyPairCost<C> {
+        match self
+            .key_pair_costs
+            .as_ref()
+            .and_then(|table| table.get(&k1.key, &k2.key))
+        {
+            Some(key_pair_cost) => key_pair_cost.clone(),
+            None => self.key_pair_cost(k1, k2),
+        }
     }
 }
 
-impl<C: ScissorCategory + 'static, T: ScissorCompute<C> + 'static> BigramMetric
+impl<C: ScissorCategory + 'static, T: ScissorCompute<C> + Clone + 'static> BigramMetric
     for ScissorMetric<C, T>
 {
     fn name(&self) -> &str {
         self.name
     }
 
+    fn is_additive(&self) -> bool {
+        self.critical_bigram.is_none()
+    }
+
+    fn precompute(&mut self, keyboard: &Keyboard) {
+        self.key_pair_costs = Some(KeyPairTable::from_layerkeys(keyboard, |k1, k2| {
+            self.key_pair_cost(k1, k2)
+        }));
+    }
+
+    fn set_color_config(&mut self, color: ColorConfig) {
+        self.color = Some(color);
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
@@ -210,13 +326,13 @@ impl<C: ScissorCategory + 'static, T: ScissorCompute<C> + 'static> BigramMetric
         k2: &LayerKey,
         weight: f64,
         total_weight: f64,
-        layout: &Layout,
+        _layout: &Layout,
     ) -> Option<f64> {
-        match self.bigram_cost(k1, k2, layout) {
-            Some(base_cost) => {
+        // only bigrams on adjacent fingers can be scissors
+        match self.bigram_cost_with_category(k1, k2)? {
+            Some((base_cost, _)) => {
                 let frequency_multiplier = self.frequency_multiplier(weight, total_weight);
-                let finger_multiplier = self.finger_multiplier(k1, k2);
-                Some(weight * base_cost * finger_multiplier * frequency_multiplier)
+                Some(weight * base_cost * frequency_multiplier)
             }
             None => Some(0.0),
         }
@@ -228,18 +344,11 @@ impl<C: ScissorCategory + 'static, T: ScissorCompute<C> + 'static> BigramMetric
         total_weight: Option<f64>,
         layout: &Layout,
     ) -> (f64, Option<String>) {
-        let show_worst: bool = env::var("SHOW_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(true);
-        let n_worst: usize = env::var("N_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(3);
+        let selection = self.worst.selection_for_mode(self.tracking_mode);
 
         let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
 
-        if !show_worst {
+        if !WorstTracker::new(selection).is_enabled() {
             let total_cost: f64 = bigrams
                 .iter()
                 .filter_map(|(bigram, weight)| {
@@ -250,50 +359,60 @@ impl<C: ScissorCategory + 'static, T: ScissorCompute<C> + 'static> BigramMetric
         }
 
         // Track worst bigrams by category
-        let mut category_queues: HashMap<C, DoublePriorityQueue<usize, OrderedFloat<f64>>> =
-            HashMap::new();
+        let mut category_trackers: HashMap<C, WorstTracker> = HashMap::new();
         let mut total_cost = 0.0;
+        let mut applicable_weight = 0.0;
+        let mut all_applicable = true;
 
         for (i, (bigram, weight)) in bigrams.iter().enumerate() {
-            if let Some((base_cost, category)) =
-                self.bigram_cost_with_category(bigram.0, bigram.1, layout)
-            {
+            let scissor = match self.bigram_cost_with_category(bigram.0, bigram.1) {
+                Some(scissor) => scissor,
+                None => {
+                    all_applicable = false;
+                    continue;
+                }
+            };
+            applicable_weight += weight;
+
+            if let Some((base_cost, category)) = scissor {
                 let frequency_multiplier = self.frequency_multiplier(*weight, total_weight);
                 let cost = weight * base_cost * frequency_multiplier;
                 total_cost += cost;
 
-                let queue = category_queues.entry(category).or_default();
-                queue.push(i, OrderedFloat(cost));
-
-                if queue.len() > n_worst {
-                    queue.pop_min();
-                }
+                category_trackers
+                    .entry(category)
+                    .or_insert_with(|| WorstTracker::new(selection))
+                    .push(i, cost);
             }
         }
 
         let mut category_msgs: Vec<String> = Vec::new();
-
-        for category in C::display_order() {
-            if let Some(queue) = category_queues.get(category) {
-                let worst_msgs: Vec<String> = queue
-                    .clone()
-                    .into_sorted_iter()
-                    .rev()
-                    .filter(|(_, cost)| cost.into_inner() > 0.0)
+        if !all_applicable {
+            category_msgs.push(format_applicable_weight(applicable_weight, total_weight));
+        }
+        let color = self.color.unwrap_or_else(ColorConfig::current);
+
+        let display_order = self
+            .display_order
+            .as_deref()
+            .unwrap_or_else(|| C::display_order());
+        for category in display_order {
+            if let Some(tracker) = category_trackers.remove(category) {
+                let worst_msgs: Vec<String> = tracker
+                    .into_sorted(total_cost)
+                    .into_iter()
                     .map(|(i, cost)| {
                         let (gram, weight) = bigrams[i];
-                        let freq_pct = 100.0 * weight / total_weight;
-                        let cost_pct = 100.0 * cost.into_inner() / total_cost;
-                        let percentages = format_percentages(cost_pct, freq_pct);
-                        let bigram_str = format!("{}{}", gram.0, gram.1);
-                        format!("{} {}", visualize_whitespace(&bigram_str), percentages)
+                        let percentages =
+                            format_percentages(cost, total_cost, weight, applicable_weight);
+                        format!("{} {}", visualize_ngram(&[gram.0, gram.1]), percentages)
                     })
                     .collect();
 
                 if !worst_msgs.is_empty() {
                     category_msgs.push(format!(
                         "{}: {}",
-                        category.display_name(),
+                        category.display_name(color),
                         worst_msgs.join(", ")
                     ));
                 }
@@ -309,3 +428,196 @@ impl<C: ScissorCategory + 'static, T: ScissorCompute<C> + 'static> BigramMetric
         (total_cost, msg)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::{
+        key::Finger,
+        test_utils::{layerkeys, toy_layout},
+    };
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct Scissor;
+
+    impl ScissorCategory for Scissor {
+        fn display_order() -> &'static [Self] {
+            &[Scissor]
+        }
+
+        fn display_name(&self, _color: ColorConfig) -> String {
+            "Scissor".to_string()
+        }
+    }
+
+    /// Costs bigrams starting on the pinky `pinky_cost` and all others `other_cost`
+    #[derive(Clone, Debug)]
+    struct FingerCost {
+        pinky_cost: f64,
+        other_cost: f64,
+    }
+
+    impl ScissorCompute<Scissor> for FingerCost {
+        fn compute_cost(&self, k1: &LayerKey, _k2: &LayerKey) -> Option<(f64, Scissor)> {
+            if k1.key.finger == Finger::Pinky {
+                Some((self.pinky_cost, Scissor))
+            } else {
+                Some((self.other_cost, Scissor))
+            }
+        }
+    }
+
+    /// Costs of the bigrams pinky → ring ("ch") and ring → pinky ("hc"), each with weight 1.0
+    fn total_cost_message(pinky_cost: f64, other_cost: f64) -> (f64, String) {
+        let worst = WorstParameters {
+            n_worst: Some(3),
+            worst_threshold_pct: None,
+        };
+        total_cost_message_with(pinky_cost, other_cost, worst)
+    }
+
+    fn total_cost_message_with(
+        pinky_cost: f64,
+        other_cost: f64,
+        worst: WorstParameters,
+    ) -> (f64, String) {
+        let layout = toy_layout("abcdefghij");
+        let ch = layerkeys(&layout, "ch");
+        let hc = layerkeys(&layout, "hc");
+        let bigrams = [((ch[0], ch[1]), 1.0), ((hc[0], hc[1]), 1.0)];
+
+        let compute = FingerCost {
+            pinky_cost,
+            other_cost,
+        };
+        let metric = ScissorMetric::new("Scissors", None, compute)
+            .with_worst_parameters(worst)
+            .with_tracking_mode(TrackingMode::Always);
+        let (cost, message) = metric.total_cost(&bigrams, None, &layout);
+        (cost, message.unwrap())
+    }
+
+    #[test]
+    fn worst_scissors_of_zero_total_cost() {
+        let (cost, message) = total_cost_message(1.0, -1.0);
+        assert_eq!(cost, 0.0);
+        assert!(message.contains("ch") && message.contains("0.0%|50.00%"));
+        assert!(!message.contains("NaN") && !message.contains("inf"));
+    }
+
+    #[test]
+    fn worst_scissors_of_negative_total_cost() {
+        let (cost, message) = total_cost_message(1.0, -2.0);
+        assert_eq!(cost, -1.0);
+        assert!(message.contains("ch") && message.contains("0.0%|50.00%"));
+        assert!(!message.contains("NaN") && !message.contains("-"));
+    }
+
+    #[test]
+    fn worst_scissors_above_threshold() {
+        let worst = WorstParameters {
+            n_worst: None,
+            worst_threshold_pct: Some(50.0),
+        };
+        let (cost, message) = total_cost_message_with(3.0, 1.0, worst);
+        assert_eq!(cost, 4.0);
+        assert!(message.contains("ch") && message.contains("75.0%"));
+        assert!(!message.contains("hc"), "{}", message);
+    }
+
+    #[test]
+    fn worst_scissors_relate_to_the_applicable_weight() {
+        let layout = toy_layout("abcdefghijklmno");
+        let ch = layerkeys(&layout, "ch");
+        // pinky → middle finger, not a scissor
+        let cm = layerkeys(&layout, "cm");
+        let bigrams = [((ch[0], ch[1]), 1.0), ((cm[0], cm[1]), 3.0)];
+
+        let compute = FingerCost {
+            pinky_cost: 1.0,
+            other_cost: 1.0,
+        };
+        let metric = ScissorMetric::new("Scissors", None, compute)
+            .with_worst_parameters(WorstParameters {
+                n_worst: Some(3),
+                worst_threshold_pct: None,
+            })
+            .with_tracking_mode(TrackingMode::Always);
+        let (cost, message) = metric.total_cost(&bigrams, None, &layout);
+        let message = message.unwrap();
+
+        assert_eq!(cost, 1.0);
+        assert!(message.starts_with("applicable: 25.0%"), "{}", message);
+        assert!(message.contains("100.0%|100.00%"), "{}", message);
+    }
+
+    mod classify_scissor_tests {
+        use super::*;
+
+        use ScissorType::*;
+
+        /// All 25 direction pairs of a bigram from the left ring finger ("f" North, "g" Out,
+        /// "h" Center, "i" In, "j" South) to the left middle finger ("k" to "o" in the same
+        /// order), i.e. an inward motion
+        const RING_TO_MIDDLE: [(&str, Option<ScissorType>); 25] = [
+            ("fk", Some(ExtendUp)),
+            ("fl", Some(Diagonal)),
+            ("fm", None),
+            ("fn", Some(Diagonal)),
+            ("fo", Some(Vertical)),
+            ("gk", Some(Diagonal)),
+            ("gl", None),
+            ("gm", Some(Lateral)),
+            ("gn", Some(Splay)),
+            ("go", Some(Diagonal)),
+            ("hk", None),
+            ("hl", Some(Lateral)),
+            ("hm", None),
+            ("hn", Some(Lateral)),
+            ("ho", None),
+            ("ik", Some(Diagonal)),
+            ("il", Some(Squeeze)),
+            ("im", Some(Lateral)),
+            ("in", None),
+            ("io", Some(Diagonal)),
+            ("jk", Some(Vertical)),
+            ("jl", Some(Diagonal)),
+            ("jm", None),
+            ("jn", Some(Diagonal)),
+            ("jo", Some(ExtendDown)),
+        ];
+
+        fn classify(layout: &Layout, bigram: &str) -> Option<ScissorType> {
+            let keys = layerkeys(layout, bigram);
+            classify_scissor(keys[0], keys[1])
+        }
+
+        #[test]
+        fn all_direction_pairs_of_adjacent_fingers() {
+            let layout = toy_layout("abcdefghijklmno");
+            for (bigram, expected) in RING_TO_MIDDLE {
+                assert_eq!(classify(&layout, bigram), expected, "{}", bigram);
+            }
+        }
+
+        /// The reversed bigram (middle to ring finger) moves the fingers in the same way.
+        #[test]
+        fn outward_motion_has_the_same_types() {
+            let layout = toy_layout("abcdefghijklmno");
+            for (bigram, expected) in RING_TO_MIDDLE {
+                let reversed: String = bigram.chars().rev().collect();
+                assert_eq!(classify(&layout, &reversed), expected, "{}", reversed);
+            }
+        }
+
+        #[test]
+        fn no_scissors_on_other_fingers() {
+            let layout = toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP");
+            // same finger, non-adjacent fingers, different hands, and thumbs
+            for bigram in ["fg", "ak", "fu", "pO", "Ou"] {
+                assert_eq!(classify(&layout, bigram), None, "{}", bigram);
+            }
+        }
+    }
+}
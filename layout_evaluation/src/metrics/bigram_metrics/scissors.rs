@@ -0,0 +1,289 @@
+//! Scissor metric covering all movement types of [`classify_scissor`].
+//!
+//! ## Core Principle
+//!
+//! Where [`super::fsb::Fsb`] and [`super::hsb::Hsb`] split the scissors of adjacent fingers
+//! into full and half scissors with separate weights, this metric penalizes all of them in one
+//! place, each [`ScissorType`] with its own base cost:
+//!
+//! ```text
+//! penalty = cost × finger_factor × freq_multiplier
+//! ```
+//!
+//! Where `finger_factor` is the larger factor of both fingers (the weaker finger dominates)
+//! and `freq_multiplier` the optional high-frequency bigram penalty.
+//!
+//! ## Configuration
+//!
+//! - `costs`: Base cost per scissor type (`Vertical`, `Squeeze`, `Splay`, `Diagonal`,
+//!   `Lateral`); types without a cost are not penalized
+//! - `extend_up_factor` / `extend_down_factor`: Cost of both fingers pressing North / South
+//!   relative to the `Vertical` cost (optional, not penalized if omitted)
+//! - `finger_factors`: Per-finger multipliers (optional)
+//! - `critical_bigram.fraction`: Frequency threshold for high-penalty bigrams (optional)
+//! - `critical_bigram.factor`: Multiplier for high-frequency bigrams (optional)
+//! - `tracking_mode`: When to list the worst bigrams (`Always`, `Never` or `FinalOnly`, optional)
+//! - `display_order`: Order in which the types are listed (optional, unlisted ones are hidden)
+
+use super::{
+    scissor_base::{classify_scissor, ScissorCategory, ScissorCompute, ScissorMetric, ScissorType},
+    BigramMetric, CriticalBigramParams,
+};
+use crate::{
+    metrics::{
+        worst_ngrams::{TrackingMode, WorstParameters},
+        FingerFactors,
+    },
+    output_style::{underline_with, ColorConfig},
+};
+
+use ahash::AHashMap;
+use keyboard_layout::{
+    key::FingerMap,
+    keyboard::Keyboard,
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+impl ScissorCategory for ScissorType {
+    fn display_order() -> &'static [Self] {
+        &[
+            ScissorType::Vertical,
+            ScissorType::Squeeze,
+            ScissorType::Splay,
+            ScissorType::Diagonal,
+            ScissorType::Lateral,
+            ScissorType::ExtendUp,
+            ScissorType::ExtendDown,
+        ]
+    }
+
+    fn display_name(&self, color: ColorConfig) -> String {
+        match self {
+            ScissorType::Vertical => underline_with("Vertical", color),
+            ScissorType::Squeeze => underline_with("Squeeze", color),
+            ScissorType::Splay => underline_with("Splay", color),
+            ScissorType::Diagonal => underline_with("Diagonal", color),
+            ScissorType::Lateral => underline_with("Lateral", color),
+            ScissorType::ExtendUp => underline_with("Extend Up", color),
+            ScissorType::ExtendDown => underline_with("Extend Down", color),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Base cost per scissor type (the extensions are configured by their factors)
+    pub costs: AHashMap<ScissorType, f64>,
+    /// Cost of both fingers pressing North relative to the vertical cost (not penalized if None)
+    #[serde(default)]
+    pub extend_up_factor: Option<f64>,
+    /// Cost of both fingers pressing South relative to the vertical cost (not penalized if None)
+    #[serde(default)]
+    pub extend_down_factor: Option<f64>,
+    /// Per-finger multipliers (e.g., pinky scissors are worse than index ones)
+    #[serde(default)]
+    pub finger_factors: FingerFactors,
+    /// Heavier penalty for frequent bigrams (`fraction` and `factor`)
+    #[serde(default)]
+    pub critical_bigram: Option<CriticalBigramParams>,
+    /// Which of the worst ngrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
+    /// When to track the worst bigrams (`Always`, `Never` or `FinalOnly`)
+    #[serde(default)]
+    pub tracking_mode: TrackingMode,
+    /// Order in which the types are listed (all in the default order if None)
+    #[serde(default)]
+    pub display_order: Option<Vec<ScissorType>>,
+}
+
+#[derive(Clone, Debug)]
+struct ScissorsCompute {
+    costs: AHashMap<ScissorType, f64>,
+    finger_factors: FingerMap<f64>,
+}
+
+impl ScissorCompute<ScissorType> for ScissorsCompute {
+    fn compute_cost(&self, k1: &LayerKey, k2: &LayerKey) -> Option<(f64, ScissorType)> {
+        let scissor_type = classify_scissor(k1, k2)?;
+        let cost = self.costs.get(&scissor_type)?;
+
+        // the weaker finger dominates
+        let finger_factor = self
+            .finger_factors
+            .get(&k1.key.finger)
+            .max(*self.finger_factors.get(&k2.key.finger));
+
+        Some((cost * finger_factor, scissor_type))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Scissors {
+    inner: ScissorMetric<ScissorType, ScissorsCompute>,
+}
+
+impl Scissors {
+    pub fn new(params: &Parameters) -> Self {
+        let mut costs = params.costs.clone();
+        costs.remove(&ScissorType::ExtendUp);
+        costs.remove(&ScissorType::ExtendDown);
+
+        let vertical_cost = costs.get(&ScissorType::Vertical).copied().unwrap_or(0.0);
+        if let Some(factor) = params.extend_up_factor {
+            costs.insert(ScissorType::ExtendUp, factor * vertical_cost);
+        }
+        if let Some(factor) = params.extend_down_factor {
+            costs.insert(ScissorType::ExtendDown, factor * vertical_cost);
+        }
+
+        let compute = ScissorsCompute {
+            costs,
+            finger_factors: params.finger_factors.to_finger_map(),
+        };
+
+        Self {
+            inner: ScissorMetric::new("Scissors", params.critical_bigram, compute)
+                .with_worst_parameters(params.worst)
+                .with_tracking_mode(params.tracking_mode)
+                .with_display_order(params.display_order.clone()),
+        }
+    }
+}
+
+impl BigramMetric for Scissors {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_additive(&self) -> bool {
+        self.inner.is_additive()
+    }
+
+    fn precompute(&mut self, keyboard: &Keyboard) {
+        self.inner.precompute(keyboard)
+    }
+
+    fn set_color_config(&mut self, color: ColorConfig) {
+        self.inner.set_color_config(color)
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        weight: f64,
+        total_weight: f64,
+        layout: &Layout,
+    ) -> Option<f64> {
+        self.inner
+            .individual_cost(k1, k2, weight, total_weight, layout)
+    }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        self.inner.total_cost(bigrams, total_weight, layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_layout};
+
+    fn scissors(extend_up_factor: Option<f64>) -> Scissors {
+        let params: Parameters = serde_yaml::from_str(
+            "
+            costs:
+              Vertical: 2.0
+              Diagonal: 1.0
+            finger_factors:
+              Pinky: 1.5
+            ",
+        )
+        .unwrap();
+        Scissors::new(&Parameters {
+            extend_up_factor,
+            ..params
+        })
+    }
+
+    /// The left pinky has "a" (North), "b" (Out), "c" (Center), "d" (In), and "e" (South), the
+    /// left ring finger "f" to "j", and the left middle finger "k" to "o".
+    fn layout() -> Layout {
+        toy_layout("abcdefghijklmno")
+    }
+
+    fn cost(metric: &Scissors, layout: &Layout, bigram: &str) -> Option<f64> {
+        let keys = layerkeys(layout, bigram);
+        metric.individual_cost(keys[0], keys[1], 1.0, 1.0, layout)
+    }
+
+    #[test]
+    fn scissor_types_have_their_costs() {
+        let layout = layout();
+        let metric = scissors(None);
+
+        // Vertical and Diagonal, the pinky factor dominates
+        assert_eq!(cost(&metric, &layout, "aj"), Some(3.0));
+        assert_eq!(cost(&metric, &layout, "jk"), Some(2.0));
+        assert_eq!(cost(&metric, &layout, "gk"), Some(1.0));
+        // Lateral without a cost, no scissor, and not on adjacent fingers
+        assert_eq!(cost(&metric, &layout, "hl"), Some(0.0));
+        assert_eq!(cost(&metric, &layout, "hm"), Some(0.0));
+        assert_eq!(cost(&metric, &layout, "cm"), None);
+    }
+
+    #[test]
+    fn extensions_are_only_penalized_if_configured() {
+        let layout = layout();
+
+        assert_eq!(cost(&scissors(None), &layout, "fk"), Some(0.0));
+        assert_eq!(cost(&scissors(Some(0.5)), &layout, "fk"), Some(1.0));
+        // ExtendDown stays free
+        assert_eq!(cost(&scissors(Some(0.5)), &layout, "jo"), Some(0.0));
+    }
+
+    #[test]
+    fn precomputed_costs_are_identical() {
+        let layout = layout();
+        let metric = scissors(Some(0.5));
+        let mut precomputed = metric.clone();
+        precomputed.precompute(&layout.keyboard);
+
+        for k1 in layout.layerkeys.iter() {
+            for k2 in layout.layerkeys.iter() {
+                assert_eq!(
+                    precomputed.individual_cost(k1, k2, 1.0, 1.0, &layout),
+                    metric.individual_cost(k1, k2, 1.0, 1.0, &layout)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn labels_follow_the_color_config() {
+        let layout = layout();
+        let keys = layerkeys(&layout, "aj");
+        let bigrams = [((keys[0], keys[1]), 1.0)];
+        let message = |color: ColorConfig| {
+            let mut metric = Scissors::new(&Parameters {
+                tracking_mode: TrackingMode::Always,
+                ..serde_yaml::from_str("{costs: {Vertical: 1.0}, n_worst: 3}").unwrap()
+            });
+            metric.set_color_config(color);
+            metric.total_cost(&bigrams, None, &layout).1.unwrap()
+        };
+
+        assert!(message(ColorConfig::Always).contains("\x1b[4mVertical\x1b[0m"));
+        assert!(message(ColorConfig::Never).starts_with("Vertical: "));
+    }
+}
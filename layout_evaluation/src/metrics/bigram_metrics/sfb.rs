@@ -24,55 +24,119 @@
 //! - `default_cost`: Fallback cost for undefined direction pairs
 //! - `costs`: Direction-pair cost matrix
 //! - `finger_factors`: Per-finger multipliers (e.g., index finger may handle SFBs better)
-//! - `ignore_thumbs`: Whether to exclude thumb SFBs from calculation
-//! - `ignore_modifiers`: Whether to skip bigrams involving modifier keys
-//! - `critical_bigram_fraction`: Frequency threshold for high-penalty bigrams (optional)
-//! - `critical_bigram_factor`: Multiplier for high-frequency bigrams (optional)
-use super::BigramMetric;
+//! - `ignore_thumbs`, `ignore_modifiers`, `modifier_handling`, `exclude_same_position_repeats`,
+//!   `exclude_same_symbol_repeats`, `ignore_movements`: Which bigrams to skip, see
+//!   [`crate::metrics::same_finger_filter`] (shared with the SFB share of
+//!   [`super::bigram_stats::BigramStats`])
+//! - `critical_bigram.fraction`: Frequency threshold for high-penalty bigrams (optional)
+//! - `critical_bigram.factor`: Multiplier for high-frequency bigrams (optional)
+//! - `roll_movements`: Direction pairs that are comfortable same-finger rolls (optional)
+//!
+//! ## Same-Finger Rolls
+//!
+//! Some direction pairs (e.g. Center → South) can be typed as a single rolling motion of the
+//! finger, while others (e.g. North → South) are uncomfortable stretches. If `roll_movements`
+//! is given, only the listed pairs may have negative costs (bonuses) in the cost matrix; the
+//! costs of all other pairs are never below zero, i.e. they are penalized or free. The
+//! critical bigram factor only amplifies penalties, not bonuses.
+//!
+//! ## Message
+//!
+//! As a few frequent SFBs may cost as much as many rare ones, the message states the number of
+//! distinct symbol pairs with non-zero cost besides the worst bigrams.
+use super::{BigramMetric, CriticalBigramParams};
+use crate::metrics::{
+    format_utils::{format_applicable_weight, format_percentages, visualize_ngram},
+    key_pair_table::KeyPairTable,
+    same_finger_filter::{self, SameFingerFilter},
+    worst_ngrams::{WorstParameters, WorstTracker},
+    FingerFactors,
+};
 
-use ahash::AHashMap;
+pub use crate::metrics::same_finger_filter::ModifierHandling;
+
+use ahash::{AHashMap, AHashSet};
 use keyboard_layout::{
-    key::{Direction, Finger},
+    key::{Direction, FingerMap, Key},
+    keyboard::Keyboard,
     layout::{LayerKey, Layout},
 };
 
 use serde::Deserialize;
+use std::sync::Arc;
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
     pub default_cost: f64,
-    pub ignore_thumbs: bool,
-    pub ignore_modifiers: Option<bool>,
+    /// Which bigrams are skipped
+    #[serde(flatten)]
+    pub filter: same_finger_filter::Parameters,
     pub costs: AHashMap<Direction, AHashMap<Direction, f64>>,
-    pub finger_factors: Option<AHashMap<Finger, f64>>,
-    /// Minimum relative bigram frequency to apply heavy penalty (as fraction, e.g., 0.0004 = 0.04%)
-    pub critical_bigram_fraction: Option<f64>,
-    /// Multiplier for bigrams above critical_bigram_fraction (e.g., 100.0 = 100x penalty)
-    pub critical_bigram_factor: Option<f64>,
+    #[serde(default)]
+    pub finger_factors: FingerFactors,
+    /// Heavier penalty for frequent bigrams (`fraction` and `factor`)
+    #[serde(default)]
+    pub critical_bigram: Option<CriticalBigramParams>,
+    /// Direction pairs that are same-finger rolls (and may therefore have negative costs)
+    pub roll_movements: Option<Vec<(Direction, Direction)>>,
+    /// Which of the worst ngrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
 }
 
 #[derive(Clone, Debug)]
 pub struct Sfb {
     default_cost: f64,
-    ignore_thumbs: bool,
-    ignore_modifiers: bool,
-    costs: AHashMap<Direction, AHashMap<Direction, f64>>,
-    finger_factors: Option<AHashMap<Finger, f64>>,
-    critical_bigram_fraction: Option<f64>,
-    critical_bigram_factor: Option<f64>,
+    filter: SameFingerFilter,
+    costs: Arc<AHashMap<Direction, AHashMap<Direction, f64>>>,
+    finger_factors: FingerMap<f64>,
+    critical_bigram: Option<CriticalBigramParams>,
+    roll_movements: Option<Arc<[(Direction, Direction)]>>,
+    worst: WorstParameters,
+    /// Results of [`Sfb::key_pair_cost`] for all pairs of keys (see [`BigramMetric::precompute`])
+    key_pair_costs: Option<KeyPairTable<Option<(f64, f64)>>>,
 }
 
 impl Sfb {
     pub fn new(params: &Parameters) -> Self {
         Self {
-            costs: params.costs.clone(),
-            ignore_thumbs: params.ignore_thumbs,
-            ignore_modifiers: params.ignore_modifiers.unwrap_or(false),
+            costs: Arc::new(params.costs.clone()),
+            filter: SameFingerFilter::new(&params.filter),
             default_cost: params.default_cost,
-            finger_factors: params.finger_factors.clone(),
-            critical_bigram_fraction: params.critical_bigram_fraction,
-            critical_bigram_factor: params.critical_bigram_factor,
+            finger_factors: params.finger_factors.to_finger_map(),
+            critical_bigram: params.critical_bigram,
+            roll_movements: params.roll_movements.as_deref().map(Arc::from),
+            worst: params.worst,
+            key_pair_costs: None,
+        }
+    }
+
+    /// The base cost (restricted to penalties for non-rolls) and the finger factor of a pair of
+    /// keys, if it is a same-finger pair that is not skipped because of its physical keys
+    fn key_pair_cost(&self, key1: &Key, key2: &Key) -> Option<(f64, f64)> {
+        if !self.filter.is_same_finger_key_pair(key1, key2) {
+            return None;
         }
+
+        let dir_from = key1.direction;
+        let dir_to = key2.direction;
+
+        let base_cost = self
+            .costs
+            .get(&dir_from)
+            .and_then(|m| m.get(&dir_to))
+            .copied()
+            .unwrap_or(self.default_cost);
+
+        // Only same-finger rolls may give a bonus
+        let base_cost = match &self.roll_movements {
+            Some(roll_movements) if !roll_movements.contains(&(dir_from, dir_to)) => {
+                base_cost.max(0.0)
+            }
+            _ => base_cost,
+        };
+
+        Some((base_cost, *self.finger_factors.get(&key1.finger))) // same finger for key1, key2
     }
 }
 
@@ -81,6 +145,16 @@ impl BigramMetric for Sfb {
         "SFB"
     }
 
+    fn is_additive(&self) -> bool {
+        self.critical_bigram.is_none()
+    }
+
+    fn precompute(&mut self, keyboard: &Keyboard) {
+        self.key_pair_costs = Some(KeyPairTable::new(keyboard, |k1, k2| {
+            self.key_pair_cost(k1, k2)
+        }));
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
@@ -90,64 +164,223 @@ impl BigramMetric for Sfb {
         total_weight: f64,
         _layout: &Layout,
     ) -> Option<f64> {
-        // Skip modifiers if configured
-        if self.ignore_modifiers && (k1.is_modifier.is_some() || k2.is_modifier.is_some()) {
-            return Some(0.0);
+        let key_pair_cost = match self
+            .key_pair_costs
+            .as_ref()
+            .and_then(|table| table.get(&k1.key, &k2.key))
+        {
+            Some(key_pair_cost) => *key_pair_cost,
+            None => self.key_pair_cost(&k1.key, &k2.key),
+        };
+
+        // Not an SFB or skipped (thumbs, ignored movements, modifiers, repeats)
+        let (base_cost, finger_multiplier) = key_pair_cost?;
+        if self.filter.excludes_layerkeys(k1, k2) {
+            return None;
         }
 
-        // Skip same-key repeats (e.g., "ee" in "feed")
-        if k1 == k2 {
-            return Some(0.0);
+        // Apply frequency-based multiplier if configured (not to same-finger roll bonuses)
+        let frequency_multiplier = match &self.critical_bigram {
+            Some(critical_bigram) if self.roll_movements.is_none() || base_cost > 0.0 => {
+                critical_bigram.multiplier(weight, total_weight)
+            }
+            _ => 1.0,
+        };
+
+        let cost = weight * base_cost * finger_multiplier * frequency_multiplier;
+
+        Some(cost)
+    }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+        let mut worst = WorstTracker::new(self.worst.selection());
+
+        if !worst.is_enabled() {
+            let total_cost = bigrams
+                .iter()
+                .filter_map(|((k1, k2), weight)| {
+                    self.individual_cost(k1, k2, *weight, total_weight, layout)
+                })
+                .sum();
+
+            return (total_cost, None);
         }
 
-        // Different hands - not an SFB
-        if k1.key.hand != k2.key.hand {
-            return Some(0.0);
+        // distinct symbol pairs with non-zero cost
+        let mut pairs: AHashSet<(char, char)> = AHashSet::default();
+        let mut total_cost = 0.0;
+        let mut applicable_weight = 0.0;
+        let mut all_applicable = true;
+        for (i, ((k1, k2), weight)) in bigrams.iter().enumerate() {
+            let cost = match self.individual_cost(k1, k2, *weight, total_weight, layout) {
+                Some(cost) => cost,
+                None => {
+                    all_applicable = false;
+                    continue;
+                }
+            };
+            if cost != 0.0 {
+                pairs.insert((k1.symbol, k2.symbol));
+            }
+            total_cost += cost;
+            applicable_weight += weight;
+            worst.push(i, cost);
         }
 
-        // Different fingers - not an SFB
-        if k1.key.finger != k2.key.finger {
-            return Some(0.0);
+        let mut msg = format!("{} distinct pairs", pairs.len());
+        if !all_applicable {
+            msg.push_str(&format!(
+                "; {}",
+                format_applicable_weight(applicable_weight, total_weight)
+            ));
         }
 
-        // Skip thumbs if configured
-        if self.ignore_thumbs && k1.key.finger == Finger::Thumb {
-            return Some(0.0);
+        let worst_msgs: Vec<String> = worst
+            .into_sorted(total_cost)
+            .into_iter()
+            .map(|(i, cost)| {
+                let ((k1, k2), weight) = bigrams[i];
+                format!(
+                    "{} {}",
+                    visualize_ngram(&[k1, k2]),
+                    format_percentages(cost, total_cost, weight, applicable_weight)
+                )
+            })
+            .collect();
+        if !worst_msgs.is_empty() {
+            msg.push_str(&format!("; {}", worst_msgs.join(", ")));
         }
 
-        let finger = k1.key.finger; // same for k1, k2
-        let dir_from = k1.key.direction;
-        let dir_to = k2.key.direction;
+        (total_cost, Some(msg))
+    }
+}
 
-        let base_cost = self
-            .costs
-            .get(&dir_from)
-            .and_then(|m| m.get(&dir_to))
-            .copied()
-            .unwrap_or(self.default_cost);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let finger_multiplier = self
-            .finger_factors
-            .as_ref()
-            .and_then(|factors| factors.get(&finger).copied())
-            .unwrap_or(1.0);
+    use keyboard_layout::{
+        layout::{LayerModifierType, LayerModifiers},
+        test_utils::{layerkeys, toy_keyboard, toy_layout},
+    };
 
-        // Apply frequency-based multiplier if configured
-        let frequency_multiplier = if let (Some(threshold), Some(factor)) =
-            (self.critical_bigram_fraction, self.critical_bigram_factor)
-        {
-            let relative_weight = weight / total_weight;
-            if relative_weight > threshold {
-                factor
-            } else {
-                1.0
-            }
-        } else {
-            1.0
-        };
+    fn sfb(exclude_same_position_repeats: bool, exclude_same_symbol_repeats: bool) -> Sfb {
+        let params: Parameters = serde_yaml::from_str(&format!(
+            "
+            default_cost: 1.0
+            ignore_thumbs: false
+            exclude_same_position_repeats: {}
+            exclude_same_symbol_repeats: {}
+            costs:
+              Center:
+                North: 2.0
+            ",
+            exclude_same_position_repeats, exclude_same_symbol_repeats
+        ))
+        .unwrap();
+        Sfb::new(&params)
+    }
 
-        let cost = weight * base_cost * finger_multiplier * frequency_multiplier;
+    /// `symbol` on `layer` of the left pinky's North (`Direction::North`) or Center key of the
+    /// toy keyboard
+    fn pinky(direction: Direction, layer: u8, symbol: char) -> LayerKey {
+        let index = if direction == Direction::North { 0 } else { 2 };
+        LayerKey::new(
+            layer,
+            toy_keyboard().keys[index].clone(),
+            symbol,
+            LayerModifiers::default(),
+            false,
+            LayerModifierType::None,
+        )
+    }
 
-        Some(cost)
+    /// The costs of a same position repeat ("aa") and a same symbol repeat ("a" on the base
+    /// layer followed by "a" on the North key's second layer)
+    fn repeat_costs(sfb: &Sfb) -> (Option<f64>, Option<f64>) {
+        let layout = toy_layout("a");
+        let a = pinky(Direction::Center, 0, 'a');
+        let a_north = pinky(Direction::North, 1, 'a');
+
+        (
+            sfb.individual_cost(&a, &a, 1.0, 1.0, &layout),
+            sfb.individual_cost(&a, &a_north, 1.0, 1.0, &layout),
+        )
+    }
+
+    #[test]
+    fn same_position_repeats_are_excluded_by_default() {
+        let params: Parameters = serde_yaml::from_str(
+            "
+            default_cost: 1.0
+            ignore_thumbs: false
+            costs: {}
+            ",
+        )
+        .unwrap();
+        assert!(params.filter.exclude_same_position_repeats);
+        assert!(!params.filter.exclude_same_symbol_repeats);
+        assert_eq!(repeat_costs(&Sfb::new(&params)), (None, Some(1.0)));
+    }
+
+    #[test]
+    fn repeat_flag_combinations() {
+        assert_eq!(repeat_costs(&sfb(false, false)), (Some(1.0), Some(2.0)));
+        assert_eq!(repeat_costs(&sfb(true, false)), (None, Some(2.0)));
+        assert_eq!(repeat_costs(&sfb(false, true)), (Some(1.0), None));
+        assert_eq!(repeat_costs(&sfb(true, true)), (None, None));
+    }
+
+    #[test]
+    fn same_symbol_on_the_same_layer_is_a_position_repeat() {
+        // different keys, but the same layer: not a same symbol repeat
+        let layout = toy_layout("a");
+        let a = pinky(Direction::Center, 0, 'a');
+        let a_north = pinky(Direction::North, 0, 'a');
+        assert_eq!(
+            sfb(false, true).individual_cost(&a, &a_north, 1.0, 1.0, &layout),
+            Some(2.0)
+        );
+    }
+
+    #[test]
+    fn precomputed_costs_honor_the_flags() {
+        let layout = toy_layout("a");
+        for (position, symbol) in [(false, false), (true, false), (false, true), (true, true)] {
+            let mut precomputed = sfb(position, symbol);
+            precomputed.precompute(&layout.keyboard);
+            assert_eq!(
+                repeat_costs(&precomputed),
+                repeat_costs(&sfb(position, symbol))
+            );
+        }
+    }
+
+    #[test]
+    fn worst_bigrams_relate_to_the_applicable_weight() {
+        let layout = toy_layout("abcdefghij");
+        let ab = layerkeys(&layout, "ab");
+        // pinky → ring finger, not a same-finger bigram
+        let af = layerkeys(&layout, "af");
+        let bigrams = [((ab[0], ab[1]), 1.0), ((af[0], af[1]), 3.0)];
+
+        let metric = Sfb::new(
+            &serde_yaml::from_str(
+                "{default_cost: 1.0, costs: {}, ignore_thumbs: false, n_worst: 3}",
+            )
+            .unwrap(),
+        );
+        let (cost, message) = metric.total_cost(&bigrams, None, &layout);
+        let message = message.unwrap();
+
+        assert_eq!(cost, 1.0);
+        assert!(message.contains("; applicable: 25.0%; "), "{}", message);
+        assert!(message.contains("ab") && message.contains("100.0%|100.00%"));
     }
 }
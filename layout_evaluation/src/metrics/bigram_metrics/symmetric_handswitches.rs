@@ -1,27 +1,134 @@
 //! The bigram metric [`SymmetricHandswitches`] metric assigns a negative cost to each bigram
 //! for which the two keys are symmetrical on each hand (thumbs are excluded).
 //!
+//! ## Motivation
+//!
+//! When switching hands, the second hand starts moving while the first one is still finishing
+//! its keystroke. If both hands perform the mirrored motion (e.g. both index fingers pulling
+//! North), the movement can be planned as one bilateral pattern, which is known to be easier
+//! to coordinate than two unrelated motions. Rewarding such handswitches therefore favors
+//! layouts whose frequent handswitches feel "in sync". As the effect is small compared to
+//! same-hand discomfort, the metric is meant to act as a tie-breaker with a low weight.
+//!
+//! Which keys are symmetrical is determined by the `mode` parameter:
+//! - `FingerDirection` (default): Same finger and direction on both hands, e.g. left index North
+//!   and right index North. Keyboards without direction data (all keys Center) fall back to
+//!   the symmetry indices.
+//! - `SymmetryIndex`: The symmetry indices of the keyboard configuration
+//!
+//! The fallback is resolved once per keyboard in [`BigramMetric::precompute`].
+//!
+//! ## Parameters
+//! - `mode`: How symmetrical keys are identified (see above)
+//! - `symmetry_bonus`: Reward per symmetric handswitch relative to its weight (default 1.0)
+//! - `n_worst` / `worst_threshold_pct`: Which of the most frequent asymmetric handswitches to list
+//!
+//! The message reports the share of (non-thumb) handswitches that are symmetrical, followed by
+//! the most frequent asymmetric ones.
+//!
 //! *Note*: In contrast to ArneBab's version, this gives negative costs to symmetric handswitches
 //! instead of positive costs to all other bigrams. Also, thumbs are excluded.
 
 use super::BigramMetric;
+use crate::{
+    metrics::{
+        format_utils::{format_percentages, visualize_ngram},
+        worst_ngrams::{WorstParameters, WorstTracker},
+    },
+    output_style::underline,
+};
 
 use keyboard_layout::{
-    key::Finger,
+    key::{Direction, Finger},
+    keyboard::Keyboard,
     layout::{LayerKey, Layout},
 };
 
 use serde::Deserialize;
 
+/// How symmetrical keys are identified
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Eq)]
+pub enum SymmetryMode {
+    /// Same finger and direction on both hands
+    FingerDirection,
+    /// Identical symmetry index in the keyboard configuration
+    SymmetryIndex,
+}
+
+impl Default for SymmetryMode {
+    fn default() -> Self {
+        SymmetryMode::FingerDirection
+    }
+}
+
 #[derive(Clone, Deserialize, Debug)]
-pub struct Parameters {}
+pub struct Parameters {
+    #[serde(default)]
+    pub mode: SymmetryMode,
+    /// Reward per symmetric handswitch relative to its weight
+    pub symmetry_bonus: Option<f64>,
+    /// Which of the most frequent asymmetric handswitches to list
+    #[serde(flatten)]
+    pub worst: WorstParameters,
+}
 
 #[derive(Clone, Debug)]
-pub struct SymmetricHandswitches {}
+pub struct SymmetricHandswitches {
+    mode: SymmetryMode,
+    /// `mode` with the fallback for the keyboard applied (see [`BigramMetric::precompute`])
+    keyboard_mode: Option<SymmetryMode>,
+    symmetry_bonus: f64,
+    worst: WorstParameters,
+}
 
 impl SymmetricHandswitches {
-    pub fn new(_params: &Parameters) -> Self {
-        Self {}
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            mode: params.mode,
+            keyboard_mode: None,
+            symmetry_bonus: params.symmetry_bonus.unwrap_or(1.0),
+            worst: params.worst,
+        }
+    }
+
+    /// The mode to use for `keyboard` (falling back to `SymmetryIndex` if the keyboard has no
+    /// direction data)
+    fn mode_for_keyboard(&self, keyboard: &Keyboard) -> SymmetryMode {
+        if self.mode == SymmetryMode::FingerDirection
+            && keyboard
+                .keys
+                .iter()
+                .all(|key| key.finger == Finger::Thumb || key.direction == Direction::Center)
+        {
+            SymmetryMode::SymmetryIndex
+        } else {
+            self.mode
+        }
+    }
+
+    /// The mode to use for the layout's keyboard (resolved in [`BigramMetric::precompute`] if
+    /// possible)
+    #[inline(always)]
+    fn effective_mode(&self, layout: &Layout) -> SymmetryMode {
+        self.keyboard_mode
+            .unwrap_or_else(|| self.mode_for_keyboard(&layout.keyboard))
+    }
+
+    #[inline(always)]
+    fn is_handswitch(k1: &LayerKey, k2: &LayerKey) -> bool {
+        k1.key.hand != k2.key.hand
+            && k1.key.finger != Finger::Thumb
+            && k2.key.finger != Finger::Thumb
+    }
+
+    #[inline(always)]
+    fn is_symmetric(mode: SymmetryMode, k1: &LayerKey, k2: &LayerKey) -> bool {
+        match mode {
+            SymmetryMode::FingerDirection => {
+                k1.key.finger == k2.key.finger && k1.key.direction == k2.key.direction
+            }
+            SymmetryMode::SymmetryIndex => k1.key.symmetry_index == k2.key.symmetry_index,
+        }
     }
 }
 
@@ -30,6 +137,10 @@ impl BigramMetric for SymmetricHandswitches {
         "Symmetric Handswitches"
     }
 
+    fn precompute(&mut self, keyboard: &Keyboard) {
+        self.keyboard_mode = Some(self.mode_for_keyboard(keyboard));
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
@@ -37,16 +148,181 @@ impl BigramMetric for SymmetricHandswitches {
         k2: &LayerKey,
         weight: f64,
         _total_weight: f64,
-        _layout: &Layout,
+        layout: &Layout,
     ) -> Option<f64> {
-        if k1.key.hand != k2.key.hand
-            && k1.key.symmetry_index == k2.key.symmetry_index
-            && k1.key.finger != Finger::Thumb
-            && k2.key.finger != Finger::Thumb
-        {
-            Some(-weight)
+        if Self::is_handswitch(k1, k2) && Self::is_symmetric(self.effective_mode(layout), k1, k2) {
+            Some(-weight * self.symmetry_bonus)
         } else {
             Some(0.0)
         }
     }
+
+    fn total_cost(
+        &self,
+        bigrams: &[((&LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let mode = self.effective_mode(layout);
+        let mut asymmetric = WorstTracker::new(self.worst.selection());
+
+        let mut handswitch_weight = 0.0;
+        let mut symmetric_weight = 0.0;
+
+        for (i, ((k1, k2), weight)) in bigrams.iter().enumerate() {
+            if !Self::is_handswitch(k1, k2) {
+                continue;
+            }
+
+            handswitch_weight += weight;
+            if Self::is_symmetric(mode, k1, k2) {
+                symmetric_weight += weight;
+            } else {
+                asymmetric.push(i, *weight);
+            }
+        }
+
+        let mut msg = format!(
+            "{}: {:.1}% of handswitches",
+            underline("Symmetric"),
+            crate::metrics::utils::to_percentage(symmetric_weight, handswitch_weight)
+        );
+
+        let total_weight = total_weight.unwrap_or_else(|| bigrams.iter().map(|(_, w)| w).sum());
+        let asymmetric_weight = handswitch_weight - symmetric_weight;
+        let asymmetric_msgs: Vec<String> = asymmetric
+            .into_sorted(asymmetric_weight)
+            .into_iter()
+            .map(|(i, weight)| {
+                let ((k1, k2), _) = bigrams[i];
+                format!(
+                    "{} {}",
+                    visualize_ngram(&[k1, k2]),
+                    format_percentages(weight, asymmetric_weight, weight, total_weight)
+                )
+            })
+            .collect();
+        if !asymmetric_msgs.is_empty() {
+            msg.push_str(&format!(
+                "; {}: {}",
+                underline("Asymmetric"),
+                asymmetric_msgs.join(", ")
+            ));
+        }
+
+        (-symmetric_weight * self.symmetry_bonus, Some(msg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_keyboard, toy_layout};
+    use std::sync::Arc;
+
+    /// The left index finger has "p" (North) to "t" (South), the right one "u" (North) to "y"
+    /// (South).
+    const CHARS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP";
+
+    fn metric(mode: SymmetryMode) -> SymmetricHandswitches {
+        SymmetricHandswitches::new(&Parameters {
+            mode,
+            symmetry_bonus: Some(2.0),
+            worst: WorstParameters::default(),
+        })
+    }
+
+    fn cost(metric: &SymmetricHandswitches, layout: &Layout, bigram: &str) -> Option<f64> {
+        let keys = layerkeys(layout, bigram);
+        metric.individual_cost(keys[0], keys[1], 1.0, 1.0, layout)
+    }
+
+    /// A layout on a keyboard without direction data, where the right index South key ("y")
+    /// has a symmetry index of its own
+    fn layout_without_directions() -> Layout {
+        let mut keyboard = toy_keyboard();
+        keyboard
+            .keys
+            .iter_mut()
+            .filter(|key| key.finger != Finger::Thumb)
+            .for_each(|key| key.direction = Direction::Center);
+        keyboard.keys[24].symmetry_index = 9;
+
+        let key_chars: Vec<Vec<char>> = CHARS.chars().map(|c| vec![c]).collect();
+        let fixed_keys = vec![false; key_chars.len()];
+        Layout::new(key_chars, fixed_keys, Arc::new(keyboard), Vec::new()).unwrap()
+    }
+
+    #[test]
+    fn mirrored_handswitches_are_rewarded() {
+        let layout = toy_layout(CHARS);
+        let finger_direction = metric(SymmetryMode::FingerDirection);
+        let symmetry_index = metric(SymmetryMode::SymmetryIndex);
+
+        assert_eq!(cost(&finger_direction, &layout, "pu"), Some(-2.0));
+        assert_eq!(cost(&symmetry_index, &layout, "pu"), Some(-2.0));
+        // same finger, different directions
+        assert_eq!(cost(&finger_direction, &layout, "py"), Some(0.0));
+        assert_eq!(cost(&symmetry_index, &layout, "py"), Some(-2.0));
+        // no handswitch, or a thumb involved
+        assert_eq!(cost(&finger_direction, &layout, "pp"), Some(0.0));
+        assert_eq!(cost(&symmetry_index, &layout, "OP"), Some(0.0));
+    }
+
+    #[test]
+    fn keyboards_without_directions_fall_back_to_symmetry_indices() {
+        let layout = layout_without_directions();
+        let mut metric = metric(SymmetryMode::FingerDirection);
+
+        assert_eq!(cost(&metric, &layout, "pu"), Some(-2.0));
+        assert_eq!(cost(&metric, &layout, "py"), Some(0.0));
+
+        metric.precompute(&layout.keyboard);
+        assert_eq!(metric.keyboard_mode, Some(SymmetryMode::SymmetryIndex));
+        assert_eq!(cost(&metric, &layout, "py"), Some(0.0));
+    }
+
+    #[test]
+    fn precomputed_costs_are_identical() {
+        let layout = toy_layout(CHARS);
+        let metric = metric(SymmetryMode::FingerDirection);
+        let mut precomputed = metric.clone();
+        precomputed.precompute(&layout.keyboard);
+        assert_eq!(
+            precomputed.keyboard_mode,
+            Some(SymmetryMode::FingerDirection)
+        );
+
+        for k1 in layout.layerkeys.iter() {
+            for k2 in layout.layerkeys.iter() {
+                assert_eq!(
+                    precomputed.individual_cost(k1, k2, 1.0, 1.0, &layout),
+                    metric.individual_cost(k1, k2, 1.0, 1.0, &layout)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn message_reports_the_share_of_symmetric_handswitches() {
+        let layout = toy_layout(CHARS);
+        let metric = metric(SymmetryMode::FingerDirection);
+        let pu = layerkeys(&layout, "pu");
+        let py = layerkeys(&layout, "py");
+        let pq = layerkeys(&layout, "pq");
+        let bigrams = [
+            ((pu[0], pu[1]), 3.0),
+            ((py[0], py[1]), 1.0),
+            ((pq[0], pq[1]), 4.0),
+        ];
+
+        let (cost, msg) = metric.total_cost(&bigrams, None, &layout);
+
+        assert_eq!(cost, -6.0);
+        let msg = msg.unwrap();
+        assert!(msg.contains("75.0% of handswitches"), "{}", msg);
+        assert!(msg.contains("py"), "{}", msg);
+        assert!(!msg.contains("pu"), "{}", msg);
+    }
 }
@@ -1,22 +1,112 @@
 //! Utility functions for formatting metric output
 
-use colored::Colorize;
+use super::utils::to_percentage;
+use crate::output_style::gray;
+
+use keyboard_layout::layout::LayerKey;
 
 /// Format cost and frequency percentages with dimmed color
 ///
-/// Returns a formatted string like "27.5%|0.05%" with dimmed gray color
-pub fn format_percentages(cost_percent: f64, freq_percent: f64) -> String {
-    format!(
+/// Takes the raw values and their totals, e.g. a bigram's cost and the metric's total cost.
+/// Returns a formatted string like "27.5%|0.05%" with dimmed gray color (plain if requested).
+/// A total that is zero, negative, or not finite yields 0.0% instead of NaN.
+pub fn format_percentages(cost: f64, total_cost: f64, weight: f64, total_weight: f64) -> String {
+    gray(&format!(
         "{:.1}%|{:.2}%",
-        cost_percent,
-        freq_percent
-    ).truecolor(150, 150, 150)
-    .to_string()
+        to_percentage(cost, total_cost),
+        to_percentage(weight, total_weight)
+    ))
+}
+
+/// Format the share of the ngram weight a metric applies to, i.e. of the ngrams for which its
+/// `individual_cost` is not `None`, e.g. "applicable: 12.3%"
+pub fn format_applicable_weight(applicable_weight: f64, total_weight: f64) -> String {
+    format!(
+        "applicable: {:.1}%",
+        to_percentage(applicable_weight, total_weight)
+    )
 }
 
-/// Replace whitespace characters with visible symbols for display
+/// Replace invisible characters with visible symbols for display
 ///
-/// Replaces space with "␣" to make whitespace visible in output
+/// Replaces space with "␣", newline with "⏎", tab with "⇥", and carriage return with "␍".
+/// Other control and zero-width characters are shown as their code point, e.g. "�{U+200B}",
+/// so that a message always stays on a single line.
 pub fn visualize_whitespace(s: &str) -> String {
-    s.replace(' ', "␣")
+    s.chars()
+        .map(|c| match c {
+            ' ' => "␣".to_string(),
+            '\n' => "⏎".to_string(),
+            '\t' => "⇥".to_string(),
+            '\r' => "␍".to_string(),
+            c if c.is_control() || is_zero_width(c) => format!("�{{U+{:04X}}}", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// The symbols of an ngram for display, with invisible characters replaced as in
+/// [`visualize_whitespace`] and modifiers in brackets, e.g. "[⇧]a␣"
+pub fn visualize_ngram(keys: &[&LayerKey]) -> String {
+    keys.iter()
+        .map(|k| {
+            let symbol = visualize_whitespace(&k.symbol.to_string());
+            if k.is_modifier.is_some() {
+                format!("[{}]", symbol)
+            } else {
+                symbol
+            }
+        })
+        .collect()
+}
+
+/// Characters that are rendered without any width (and are thus invisible)
+fn is_zero_width(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{2028}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{FEFF}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_layout};
+
+    #[test]
+    fn format_percentages_of_positive_totals() {
+        let formatted = format_percentages(1.0, 4.0, 1.0, 8.0);
+        assert!(formatted.contains("25.0%|12.50%"), "{}", formatted);
+    }
+
+    #[test]
+    fn format_percentages_of_zero_and_negative_totals() {
+        for total in [0.0, -0.0, -4.0, f64::NAN, f64::INFINITY] {
+            let formatted = format_percentages(1.0, total, 1.0, total);
+            assert!(formatted.contains("0.0%|0.00%"), "{}", formatted);
+            assert!(!formatted.contains("NaN") && !formatted.contains("inf"));
+        }
+    }
+
+    #[test]
+    fn visualize_whitespace_replaces_invisible_characters() {
+        assert_eq!(visualize_whitespace("a b"), "a␣b");
+        assert_eq!(visualize_whitespace("\n\n"), "⏎⏎");
+        assert_eq!(visualize_whitespace("\ta\r"), "⇥a␍");
+        assert_eq!(visualize_whitespace("a\u{200B}"), "a�{U+200B}");
+        assert_eq!(visualize_whitespace("\u{1B}"), "�{U+001B}");
+        assert_eq!(visualize_whitespace("äß"), "äß");
+    }
+
+    #[test]
+    fn visualize_ngram_keeps_ngrams_on_one_line() {
+        let layout = toy_layout("\n\ta ");
+        assert_eq!(visualize_ngram(&layerkeys(&layout, "\n\n")), "⏎⏎");
+        assert_eq!(visualize_ngram(&layerkeys(&layout, "a\t ")), "a⇥␣");
+    }
 }
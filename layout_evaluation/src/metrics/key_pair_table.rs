@@ -0,0 +1,131 @@
+//! Values for all pairs of keys of a keyboard, e.g. the base costs of a bigram metric that only
+//! depend on the physical keys. Metrics compute such tables in
+//! [`BigramMetric::precompute`](super::bigram_metrics::BigramMetric::precompute), so that their
+//! `individual_cost` looks the value up instead of classifying the keys for every bigram.
+
+use keyboard_layout::{
+    key::Key,
+    keyboard::{Keyboard, KeyboardId},
+    layout::{LayerKey, LayerModifierType, LayerModifiers},
+};
+
+use std::sync::Arc;
+
+/// Values indexed by the [`Key::index`] of two keys of one keyboard. Clones share the values.
+#[derive(Clone, Debug)]
+pub struct KeyPairTable<T> {
+    keyboard_id: KeyboardId,
+    n_keys: usize,
+    values: Arc<[T]>,
+}
+
+impl<T> KeyPairTable<T> {
+    /// Compute the values of all pairs of keys of `keyboard`
+    pub fn new(keyboard: &Keyboard, value: impl Fn(&Key, &Key) -> T) -> Self {
+        let value = &value;
+        let values = keyboard
+            .keys
+            .iter()
+            .flat_map(|k1| keyboard.keys.iter().map(move |k2| value(k1, k2)))
+            .collect();
+
+        Self {
+            keyboard_id: keyboard.id(),
+            n_keys: keyboard.keys.len(),
+            values,
+        }
+    }
+
+    /// Compute the values of all pairs of keys of `keyboard` from [`LayerKey`]s on the base
+    /// layer of the keys (without symbols). The values must not depend on anything but the
+    /// physical keys.
+    pub fn from_layerkeys(keyboard: &Keyboard, value: impl Fn(&LayerKey, &LayerKey) -> T) -> Self {
+        let layerkeys: Vec<LayerKey> = keyboard
+            .keys
+            .iter()
+            .map(|key| {
+                LayerKey::new(
+                    0,
+                    key.clone(),
+                    '\0',
+                    LayerModifiers::default(),
+                    false,
+                    LayerModifierType::None,
+                )
+            })
+            .collect();
+
+        let value = &value;
+        let values = layerkeys
+            .iter()
+            .flat_map(|k1| layerkeys.iter().map(move |k2| value(k1, k2)))
+            .collect();
+
+        Self {
+            keyboard_id: keyboard.id(),
+            n_keys: layerkeys.len(),
+            values,
+        }
+    }
+
+    /// The value of the pair of keys (`None` if one of them is not a key of the keyboard the
+    /// table was computed for, see [`Key::keyboard_id`])
+    #[inline(always)]
+    pub fn get(&self, k1: &Key, k2: &Key) -> Option<&T> {
+        let (i1, i2) = (k1.index as usize, k2.index as usize);
+        if k1.keyboard_id == self.keyboard_id
+            && k2.keyboard_id == self.keyboard_id
+            && i1 < self.n_keys
+            && i2 < self.n_keys
+        {
+            Some(&self.values[i1 * self.n_keys + i2])
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::{
+        key::{Direction, Finger, Hand},
+        test_utils::{key, toy_keyboard},
+    };
+
+    fn index_pairs(keyboard: &Keyboard) -> KeyPairTable<(usize, usize)> {
+        KeyPairTable::new(keyboard, |k1, k2| (k1.index as usize, k2.index as usize))
+    }
+
+    #[test]
+    fn values_are_indexed_by_the_keys() {
+        let keyboard = toy_keyboard();
+        let table = index_pairs(&keyboard);
+
+        for k1 in keyboard.keys.iter() {
+            for k2 in keyboard.keys.iter() {
+                assert_eq!(
+                    table.get(k1, k2),
+                    Some(&(k1.index as usize, k2.index as usize))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn keys_of_other_keyboards_are_not_found() {
+        let keyboard = toy_keyboard();
+        let table = index_pairs(&keyboard);
+        // the same size, but another keyboard
+        let other = toy_keyboard();
+        let (k1, k2) = (&keyboard.keys[0], &keyboard.keys[1]);
+
+        assert_eq!(table.get(&other.keys[0], k2), None);
+        assert_eq!(table.get(k1, &other.keys[1]), None);
+        assert_eq!(index_pairs(&keyboard.clone()).get(k1, k2), Some(&(0, 1)));
+
+        let loose_key = key(Hand::Left, Finger::Pinky, Direction::North).build();
+        assert_eq!(table.get(&loose_key, k2), None);
+    }
+}
@@ -3,8 +3,11 @@ use keyboard_layout::layout::Layout;
 
 use std::fmt;
 
+pub mod hand_symmetry;
 pub mod kla_home_key_words;
 pub mod kla_same_finger_words;
+pub mod layout_similarity;
+pub mod mirrored_pairs;
 pub mod shortcut_keys;
 pub mod similar_letter_groups;
 pub mod similar_letters;
@@ -13,8 +16,8 @@ pub mod similar_letters;
 pub trait LayoutMetric: Send + Sync + LayoutMetricClone + fmt::Debug {
     /// Return the name of the metric
     fn name(&self) -> &str;
-    /// Compute the total cost for the metric
-    fn total_cost(&self, layout: &Layout) -> (f64, Option<String>);
+    /// Compute the cost for the metric from the layout alone (without any ngrams)
+    fn cost(&self, layout: &Layout) -> (f64, Option<String>);
 }
 
 // in order to implement clone for Box<dyn LayoutMetric>, the following trick is necessary
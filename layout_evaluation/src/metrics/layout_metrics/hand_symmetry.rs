@@ -0,0 +1,166 @@
+//! The layout metric [`HandSymmetry`] compares how the symbols are distributed over the keys
+//! of both hands. On the Svalboard, the clusters of both hands are mirror images of each other
+//! (`In` and `Out` are relative to the center of the keyboard), so ideally each finger and
+//! direction carries the same share of its hand's symbols on both hands.
+//!
+//! For each hand, the weights of the symbols are summed per (finger, direction) pair and
+//! divided by the total weight of the hand. Each pair of the left hand is compared to the same
+//! pair of the right hand: its cost is `cost` times the squared difference of the shares (in
+//! percentage points). The sum of squared differences is used rather than the KL divergence,
+//! as the latter is undefined for pairs without any weight on one of the hands. How much each
+//! hand carries in total is left to `hand_disbalance`.
+//!
+//! The weight of a symbol is taken from `key_weights`. If it is not listed there, its relative
+//! frequency in `unigrams_filename` is used (if given), otherwise 1.0. The symbols of all
+//! layers count, modifiers are skipped.
+//!
+//! ## Parameters
+//! - `cost`: Factor of the squared differences
+//! - `ignore_thumbs`: Skip the keys of the thumbs
+//! - `key_weights`: Weight per character
+//! - `unigrams_filename`: Unigram file providing the weights of all other characters
+//! - `n_worst` / `worst_threshold_pct`: Which of the most asymmetric pairs to list
+
+use super::{layout_similarity::unigram_weights, LayoutMetric};
+use crate::{
+    metrics::worst_ngrams::{WorstParameters, WorstTracker},
+    output_style::underline,
+};
+
+use ahash::AHashMap;
+use keyboard_layout::{
+    key::{Direction, Finger, Hand},
+    layout::Layout,
+};
+
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Factor of the squared differences of the shares (in percentage points)
+    pub cost: f64,
+    /// Skip the keys of the thumbs
+    pub ignore_thumbs: bool,
+    /// Weight per character
+    #[serde(default)]
+    pub key_weights: AHashMap<char, f64>,
+    /// Unigram file providing the weights of characters not listed in `key_weights`
+    pub unigrams_filename: Option<String>,
+    /// Which of the most asymmetric pairs to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
+}
+
+#[derive(Clone, Debug)]
+pub struct HandSymmetry {
+    cost: f64,
+    ignore_thumbs: bool,
+    key_weights: AHashMap<char, f64>,
+    unigram_weights: Option<Arc<AHashMap<char, f64>>>,
+    worst: WorstParameters,
+}
+
+impl HandSymmetry {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            cost: params.cost,
+            ignore_thumbs: params.ignore_thumbs,
+            key_weights: params.key_weights.clone(),
+            unigram_weights: unigram_weights(&params.unigrams_filename),
+            worst: params.worst,
+        }
+    }
+
+    fn weight(&self, c: char) -> f64 {
+        if let Some(weight) = self.key_weights.get(&c) {
+            *weight
+        } else if let Some(unigram_weights) = &self.unigram_weights {
+            unigram_weights.get(&c).copied().unwrap_or(0.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// The share of each (finger, direction) pair in the total weight of the symbols of `hand`
+    /// (in percent)
+    fn shares(&self, layout: &Layout, hand: Hand) -> AHashMap<(Finger, Direction), f64> {
+        let mut weights: AHashMap<(Finger, Direction), f64> = AHashMap::default();
+        for layerkey in layout.layerkeys.iter() {
+            let key = &layerkey.key;
+            if key.hand != hand
+                || layerkey.is_modifier.is_some()
+                || (self.ignore_thumbs && key.finger == Finger::Thumb)
+            {
+                continue;
+            }
+
+            *weights.entry((key.finger, key.direction)).or_insert(0.0) +=
+                self.weight(layerkey.symbol);
+        }
+
+        let total_weight: f64 = weights.values().sum();
+        if total_weight > 0.0 {
+            weights
+                .values_mut()
+                .for_each(|w| *w = 100.0 * *w / total_weight);
+        }
+
+        weights
+    }
+}
+
+impl LayoutMetric for HandSymmetry {
+    fn name(&self) -> &str {
+        "Hand Symmetry"
+    }
+
+    fn cost(&self, layout: &Layout) -> (f64, Option<String>) {
+        let left = self.shares(layout, Hand::Left);
+        let right = self.shares(layout, Hand::Right);
+
+        let mut pairs: Vec<(Finger, Direction)> =
+            left.keys().chain(right.keys()).copied().collect();
+        pairs.sort_by_key(|(finger, direction)| (*finger as u8, *direction as u8));
+        pairs.dedup();
+
+        let mut worst = WorstTracker::new(self.worst.selection());
+        let mut total_cost = 0.0;
+        let mut shares: Vec<(f64, f64)> = Vec::with_capacity(pairs.len());
+        for (i, pair) in pairs.iter().enumerate() {
+            let share_left = left.get(pair).copied().unwrap_or(0.0);
+            let share_right = right.get(pair).copied().unwrap_or(0.0);
+            let cost = self.cost * (share_left - share_right).powi(2);
+            total_cost += cost;
+            if cost > 0.0 {
+                worst.push(i, cost);
+            }
+            shares.push((share_left, share_right));
+        }
+
+        let worst_msgs: Vec<String> = worst
+            .into_sorted(total_cost)
+            .into_iter()
+            .map(|(i, _)| {
+                let (finger, direction) = pairs[i];
+                let (share_left, share_right) = shares[i];
+                format!(
+                    "{:?} {:?} {:.1}%|{:.1}%",
+                    finger, direction, share_left, share_right
+                )
+            })
+            .collect();
+
+        let message = if worst_msgs.is_empty() {
+            None
+        } else {
+            Some(format!(
+                "{}: {}",
+                underline("Most asymmetric (Left|Right)"),
+                worst_msgs.join(", ")
+            ))
+        };
+
+        (total_cost, message)
+    }
+}
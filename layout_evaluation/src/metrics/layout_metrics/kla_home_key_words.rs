@@ -62,7 +62,7 @@ impl LayoutMetric for KLAHomeKeyWords {
         "Home Key Words"
     }
 
-    fn total_cost(&self, layout: &Layout) -> (f64, Option<String>) {
+    fn cost(&self, layout: &Layout) -> (f64, Option<String>) {
         let mut found_weight = 0.0;
         let mut found_words = 0;
 
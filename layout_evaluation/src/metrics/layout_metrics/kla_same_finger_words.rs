@@ -55,7 +55,7 @@ impl LayoutMetric for KLASameFingerWords {
         "Same Finger Words"
     }
 
-    fn total_cost(&self, layout: &Layout) -> (f64, Option<String>) {
+    fn cost(&self, layout: &Layout) -> (f64, Option<String>) {
         let mut found_char_weight = 0.0;
         let mut found_words = 0;
 
@@ -0,0 +1,226 @@
+//! The layout metric [`LayoutSimilarity`] compares the layout to a reference layout (e.g. the
+//! one currently in use) and incurs a cost for each character that moved to another key.
+//! This allows to trade familiarity ("muscle memory") against raw performance.
+//!
+//! The cost of a moved character is its weight times
+//! - `same_finger_factor` if it is still typed with the same finger,
+//! - `same_hand_factor` if it is still typed with the same hand,
+//! - 1.0 otherwise.
+//!
+//! The weight of a character is taken from `key_weights`. If it is not listed there, its
+//! relative frequency in `unigrams_filename` is used (if given), otherwise 1.0. Without the
+//! `fs` feature, `unigrams_filename` is ignored.
+//!
+//! Only the characters of the base layer (as in the layout string) are compared. Characters
+//! occurring several times in the reference layout (e.g. placeholders for unused keys) are
+//! skipped.
+//!
+//! ## Parameters
+//! - `reference_layout`: Layout string of the reference layout
+//! - `key_weights`: Weight per character
+//! - `unigrams_filename`: Unigram file providing the weights of all other characters
+//! - `same_finger_factor`: Relative cost if a character stayed on the same finger (default 0.25)
+//! - `same_hand_factor`: Relative cost if a character stayed on the same hand (default 0.5)
+//! - `n_worst` / `worst_threshold_pct`: Which of the moved characters to list
+
+use super::LayoutMetric;
+use crate::{
+    metrics::worst_ngrams::{WorstParameters, WorstTracker},
+    ngrams::Unigrams,
+    output_style::underline,
+};
+
+use ahash::AHashMap;
+use keyboard_layout::{
+    key::Key,
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Layout string of the reference layout
+    pub reference_layout: String,
+    /// Weight per character
+    #[serde(default)]
+    pub key_weights: AHashMap<char, f64>,
+    /// Unigram file providing the weights of characters not listed in `key_weights`
+    pub unigrams_filename: Option<String>,
+    /// Relative cost if a character stayed on the same finger
+    pub same_finger_factor: Option<f64>,
+    /// Relative cost if a character stayed on the same hand
+    pub same_hand_factor: Option<f64>,
+    /// Which of the moved characters to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
+}
+
+#[cfg(feature = "fs")]
+fn read_unigrams(filename: &str) -> Option<Unigrams> {
+    Some(
+        Unigrams::from_file(filename)
+            .unwrap_or_else(|_| panic!("Could not read unigrams file {}", filename)),
+    )
+}
+
+#[cfg(not(feature = "fs"))]
+fn read_unigrams(filename: &str) -> Option<Unigrams> {
+    log::warn!(
+        "Ignoring unigrams file {} (reading files requires the 'fs' feature)",
+        filename
+    );
+    None
+}
+
+/// The relative frequencies of the unigrams in `unigrams_filename` (if given)
+pub(super) fn unigram_weights(
+    unigrams_filename: &Option<String>,
+) -> Option<Arc<AHashMap<char, f64>>> {
+    unigrams_filename
+        .as_ref()
+        .and_then(|filename| read_unigrams(filename))
+        .map(|unigrams| {
+            let total_weight = unigrams.total_weight();
+            Arc::new(
+                unigrams
+                    .grams
+                    .iter()
+                    .map(|(c, w)| (*c, w / total_weight))
+                    .collect(),
+            )
+        })
+}
+
+#[derive(Clone, Debug)]
+pub struct LayoutSimilarity {
+    reference_layout: Vec<char>,
+    key_weights: AHashMap<char, f64>,
+    unigram_weights: Option<Arc<AHashMap<char, f64>>>,
+    same_finger_factor: f64,
+    same_hand_factor: f64,
+    worst: WorstParameters,
+}
+
+impl LayoutSimilarity {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            reference_layout: params.reference_layout.chars().collect(),
+            key_weights: params.key_weights.clone(),
+            unigram_weights: unigram_weights(&params.unigrams_filename),
+            same_finger_factor: params.same_finger_factor.unwrap_or(0.25),
+            same_hand_factor: params.same_hand_factor.unwrap_or(0.5),
+            worst: params.worst,
+        }
+    }
+
+    fn weight(&self, c: char) -> f64 {
+        if let Some(weight) = self.key_weights.get(&c) {
+            *weight
+        } else if let Some(unigram_weights) = &self.unigram_weights {
+            unigram_weights.get(&c).copied().unwrap_or(0.0)
+        } else {
+            1.0
+        }
+    }
+
+    fn relative_cost(&self, reference_key: &Key, key: &Key) -> f64 {
+        if reference_key.hand != key.hand {
+            1.0
+        } else if reference_key.finger != key.finger {
+            self.same_hand_factor
+        } else {
+            self.same_finger_factor
+        }
+    }
+
+    fn describe_key(key: &Key) -> String {
+        format!("{:?} {:?} {:?}", key.hand, key.finger, key.direction)
+    }
+}
+
+impl LayoutMetric for LayoutSimilarity {
+    fn name(&self) -> &str {
+        "Layout Similarity"
+    }
+
+    fn cost(&self, layout: &Layout) -> (f64, Option<String>) {
+        // the base layer keys in the order of the layout string
+        let base_keys: Vec<&LayerKey> = layout
+            .layerkeys
+            .iter()
+            .filter(|k| k.layer == 0 && !k.is_fixed && k.is_modifier.is_none())
+            .collect();
+
+        if base_keys.len() != self.reference_layout.len() {
+            log::warn!(
+                "Layout Similarity: reference layout has {} keys, layout has {}",
+                self.reference_layout.len(),
+                base_keys.len()
+            );
+        }
+
+        let positions: AHashMap<char, usize> = base_keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (k.symbol, i))
+            .collect();
+
+        let mut worst = WorstTracker::new(self.worst.selection());
+        let mut moved: Vec<(char, usize, usize)> = Vec::new();
+        let mut n_moved = 0;
+        let mut total_cost = 0.0;
+
+        let mut occurrences: AHashMap<char, usize> = AHashMap::default();
+        self.reference_layout
+            .iter()
+            .for_each(|c| *occurrences.entry(*c).or_insert(0) += 1);
+
+        for (reference_pos, c) in self.reference_layout.iter().enumerate() {
+            if occurrences[c] > 1 {
+                continue;
+            }
+
+            let (pos, reference_key) = match (positions.get(c), base_keys.get(reference_pos)) {
+                (Some(pos), Some(reference_key)) => (*pos, &reference_key.key),
+                _ => continue,
+            };
+            if pos == reference_pos {
+                continue;
+            }
+
+            n_moved += 1;
+            let cost = self.weight(*c) * self.relative_cost(reference_key, &base_keys[pos].key);
+            total_cost += cost;
+            worst.push(moved.len(), cost);
+            moved.push((*c, reference_pos, pos));
+        }
+
+        let mut msg = format!(
+            "{}: {} of {} keys",
+            underline("Moved"),
+            n_moved,
+            occurrences.values().filter(|n| **n == 1).count()
+        );
+
+        let worst_msgs: Vec<String> = worst
+            .into_sorted(total_cost)
+            .into_iter()
+            .map(|(i, _)| {
+                let (c, reference_pos, pos) = moved[i];
+                format!(
+                    "{}: {} → {}",
+                    c.escape_debug(),
+                    Self::describe_key(&base_keys[reference_pos].key),
+                    Self::describe_key(&base_keys[pos].key)
+                )
+            })
+            .collect();
+        if !worst_msgs.is_empty() {
+            msg.push_str(&format!("; {}", worst_msgs.join(", ")));
+        }
+
+        (total_cost, Some(msg))
+    }
+}
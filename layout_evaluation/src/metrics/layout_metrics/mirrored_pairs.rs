@@ -0,0 +1,100 @@
+//! The layout metric [`MirroredPairs`] checks that paired symbols (e.g. "()", "[]", or "<>")
+//! are placed on mirrored positions of both hands, i.e. on different hands with the same
+//! finger and the same direction. The symbols may be on any layer.
+//!
+//! The pairs are rated the following way:
+//! - 0 cost if they are mirrored
+//! - `finger_only_cost` if they are on the same finger of both hands, but in different directions
+//! - `cost` otherwise (including pairs on the same hand)
+//!
+//! Pairs with a symbol that is not part of the layout are skipped.
+
+use super::LayoutMetric;
+use crate::output_style::underline;
+
+use keyboard_layout::layout::Layout;
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Pairs of symbols that shall be placed on mirrored positions
+    pub symbol_pairs: Vec<(char, char)>,
+    /// Cost of a pair that is not mirrored at all
+    pub cost: f64,
+    /// Cost of a pair on the same finger of both hands, but in different directions
+    pub finger_only_cost: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MirroredPairs {
+    symbol_pairs: Vec<(char, char)>,
+    cost: f64,
+    finger_only_cost: f64,
+}
+
+impl MirroredPairs {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            symbol_pairs: params.symbol_pairs.clone(),
+            cost: params.cost,
+            finger_only_cost: params.finger_only_cost,
+        }
+    }
+}
+
+impl LayoutMetric for MirroredPairs {
+    fn name(&self) -> &str {
+        "Mirrored Pairs"
+    }
+
+    fn cost(&self, layout: &Layout) -> (f64, Option<String>) {
+        let mut cost = 0.0;
+        let mut mirrored: Vec<String> = Vec::new();
+        let mut finger_only: Vec<String> = Vec::new();
+        let mut not_mirrored: Vec<String> = Vec::new();
+
+        for (c1, c2) in &self.symbol_pairs {
+            let (key1, key2) = match (
+                layout.get_layerkey_for_symbol(c1),
+                layout.get_layerkey_for_symbol(c2),
+            ) {
+                (Some(layerkey1), Some(layerkey2)) => (&layerkey1.key, &layerkey2.key),
+                _ => {
+                    log::debug!("Mirrored Pairs: '{}{}' is not part of the layout", c1, c2);
+                    continue;
+                }
+            };
+
+            let pair = format!("{}{}", c1, c2);
+            if key1.hand == key2.hand || key1.finger != key2.finger {
+                cost += self.cost;
+                not_mirrored.push(pair);
+            } else if key1.direction != key2.direction {
+                cost += self.finger_only_cost;
+                finger_only.push(pair);
+            } else {
+                mirrored.push(pair);
+            }
+        }
+
+        let message = [
+            ("Mirrored", mirrored),
+            ("Finger only", finger_only),
+            ("Not mirrored", not_mirrored),
+        ]
+        .iter()
+        .filter(|(_, pairs)| !pairs.is_empty())
+        .map(|(label, pairs)| format!("{}: {}", underline(label), pairs.join(" ")))
+        .collect::<Vec<String>>()
+        .join("; ");
+
+        let message = if message.is_empty() {
+            None
+        } else {
+            Some(message)
+        };
+
+        (cost, message)
+    }
+}
@@ -37,7 +37,7 @@ impl LayoutMetric for ShortcutKeys {
         "Badly Positioned Shortcut Keys"
     }
 
-    fn total_cost(&self, layout: &Layout) -> (f64, Option<String>) {
+    fn cost(&self, layout: &Layout) -> (f64, Option<String>) {
         let mut cost = 0.0;
         let mut bad_keys = Vec::new();
         self.shortcut_chars.iter().for_each(|c| {
@@ -49,7 +49,7 @@ impl LayoutMetric for ShortcutKeys {
                     bad_keys.push(*c);
                     log::trace!(
                         "Shorcut: {}, Finger: {:>13}, Matrix Position: {:.0} (is > {}), Cost: {:>2.2}",
-                        c.escape_debug().to_string(),
+                        c.escape_debug(),
                         format!("{:?} {:?}", k.key.hand, k.key.finger),
                         k.key.matrix_position.0,
                         self.within_n_leftmost_cols,
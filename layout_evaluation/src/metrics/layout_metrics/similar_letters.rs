@@ -45,7 +45,7 @@ impl LayoutMetric for SimilarLetters {
         "Similar Letters"
     }
 
-    fn total_cost(&self, layout: &Layout) -> (f64, Option<String>) {
+    fn cost(&self, layout: &Layout) -> (f64, Option<String>) {
         let mut cost = 0.0;
         let mut bad_pairs: Vec<String> = Vec::new();
 
@@ -0,0 +1,66 @@
+//! The `metrics` module provides a trait for quadgram metrics.
+use keyboard_layout::layout::{LayerKey, Layout};
+
+use std::fmt;
+
+pub mod fourgram_redirect;
+
+/// The four keys of a quadgram (in typing order)
+pub type Quadgram<'a> = (&'a LayerKey, &'a LayerKey, &'a LayerKey, &'a LayerKey);
+
+/// QuadgramMetric is a trait for metrics that iterates over weighted quadgrams.
+pub trait QuadgramMetric: Send + Sync + QuadgramMetricClone + fmt::Debug {
+    /// Return the name of the metric.
+    fn name(&self) -> &str;
+
+    /// Compute the cost of one quadgram (if that is possible, otherwise, return `None`).
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        _quadgram: Quadgram,
+        _weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        None
+    }
+
+    /// Compute the total cost for the metric.
+    fn total_cost(
+        &self,
+        quadgrams: &[(Quadgram, f64)],
+        // total_weight is optional for performance reasons (it can be computed from quadgrams)
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let total_weight = total_weight.unwrap_or_else(|| quadgrams.iter().map(|(_, w)| w).sum());
+        let total_cost: f64 = quadgrams
+            .iter()
+            .filter_map(|(quadgram, weight)| {
+                self.individual_cost(*quadgram, *weight, total_weight, layout)
+            })
+            .sum();
+
+        (total_cost, None)
+    }
+}
+
+impl Clone for Box<dyn QuadgramMetric> {
+    fn clone(&self) -> Box<dyn QuadgramMetric> {
+        self.clone_box()
+    }
+}
+
+/// Helper trait for realizing clonability for `Box<dyn QuadgramMetric>`.
+pub trait QuadgramMetricClone {
+    fn clone_box(&self) -> Box<dyn QuadgramMetric>;
+}
+
+impl<T> QuadgramMetricClone for T
+where
+    T: 'static + QuadgramMetric + Clone,
+{
+    fn clone_box(&self) -> Box<dyn QuadgramMetric> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,263 @@
+//! Redirect metric for quadgrams that change direction twice on one hand.
+//! A fourgram redirect is a quadgram whose first three and last three keys are both redirects
+//! as classified by [`classify_redirect`] (e.g., inward->outward->inward). Like
+//! [`crate::metrics::trigram_metrics::redirects::Redirects`], only those involving a strong
+//! finger (`strong_fingers`, by default the index finger or thumb) are counted.
+
+use super::{Quadgram, QuadgramMetric};
+use crate::metrics::{
+    format_utils::{format_applicable_weight, format_percentages, visualize_ngram},
+    trigram_metrics::classification::{classify_redirect, default_strong_fingers},
+    worst_ngrams::{WorstParameters, WorstTracker},
+};
+use ahash::AHashMap;
+use keyboard_layout::{
+    key::{Finger, Hand, HandMap},
+    layout::{LayerKey, Layout},
+};
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Base cost multiplier for each fourgram redirect. Default: 1.0
+    pub base_cost: Option<f64>,
+    /// Cost multiplier per hand, replacing `base_cost` for the listed hands
+    #[serde(default)]
+    pub hand_costs: AHashMap<Hand, f64>,
+    /// Ignore fourgram redirects involving thumb keys. Default: true
+    pub ignore_thumbs: Option<bool>,
+    /// Ignore fourgram redirects involving modifier keys. Default: true
+    pub ignore_modifiers: Option<bool>,
+    /// Fingers whose involvement makes a fourgram redirect count.
+    /// Default: [Index, Thumb]
+    #[serde(default = "default_strong_fingers")]
+    pub strong_fingers: Vec<Finger>,
+    /// Which of the worst ngrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
+}
+
+#[derive(Clone, Debug)]
+pub struct FourgramRedirect {
+    /// Cost multiplier per hand (the base cost unless configured differently)
+    hand_costs: HandMap<f64>,
+    ignore_thumbs: bool,
+    ignore_modifiers: bool,
+    strong_fingers: Vec<Finger>,
+    worst: WorstParameters,
+}
+
+impl FourgramRedirect {
+    pub fn new(params: &Parameters) -> Self {
+        let mut hand_costs = HandMap::with_default(params.base_cost.unwrap_or(1.0));
+        for (hand, cost) in params.hand_costs.iter() {
+            hand_costs.set(hand, *cost);
+        }
+
+        Self {
+            hand_costs,
+            ignore_thumbs: params.ignore_thumbs.unwrap_or(true),
+            ignore_modifiers: params.ignore_modifiers.unwrap_or(true),
+            strong_fingers: params.strong_fingers.clone(),
+            worst: params.worst,
+        }
+    }
+
+    fn should_ignore_key(&self, key: &LayerKey) -> bool {
+        (self.ignore_thumbs && key.key.finger == Finger::Thumb)
+            || (self.ignore_modifiers && key.is_modifier.is_some())
+    }
+
+    /// Whether the metric applies to the quadgram at all, i.e. none of its keys is ignored and
+    /// all are on the same hand
+    fn is_applicable(&self, keys: [&LayerKey; 4]) -> bool {
+        !keys.iter().any(|k| self.should_ignore_key(k))
+            && keys.iter().all(|k| k.key.hand == keys[0].key.hand)
+    }
+
+    /// Whether both overlapping trigrams are redirects and one of them involves a strong finger
+    fn is_fourgram_redirect(&self, keys: [&LayerKey; 4]) -> bool {
+        let (first, first_weak) =
+            classify_redirect(keys[0], keys[1], keys[2], &self.strong_fingers);
+        let (second, second_weak) =
+            classify_redirect(keys[1], keys[2], keys[3], &self.strong_fingers);
+
+        first && second && !(first_weak && second_weak)
+    }
+}
+
+impl QuadgramMetric for FourgramRedirect {
+    fn name(&self) -> &str {
+        "Fourgram Redirects"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        quadgram: Quadgram,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        let (k1, k2, k3, k4) = quadgram;
+        let keys = [k1, k2, k3, k4];
+        if !self.is_applicable(keys) {
+            return None;
+        }
+
+        if !self.is_fourgram_redirect(keys) {
+            return Some(0.0);
+        }
+
+        // all keys of a fourgram redirect are on the same hand
+        Some(weight * self.hand_costs.get(&k1.key.hand))
+    }
+
+    fn total_cost(
+        &self,
+        quadgrams: &[(Quadgram, f64)],
+        total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let mut worst = WorstTracker::new(self.worst.selection());
+
+        let total_weight = total_weight.unwrap_or_else(|| quadgrams.iter().map(|(_, w)| w).sum());
+
+        let mut total_cost = 0.0;
+        let mut applicable_weight = 0.0;
+        let mut all_applicable = true;
+
+        for (i, (quadgram, weight)) in quadgrams.iter().enumerate() {
+            let keys = [quadgram.0, quadgram.1, quadgram.2, quadgram.3];
+            if !self.is_applicable(keys) {
+                all_applicable = false;
+                continue;
+            }
+            applicable_weight += weight;
+
+            if !self.is_fourgram_redirect(keys) {
+                continue;
+            }
+
+            let cost = weight * self.hand_costs.get(&quadgram.0.key.hand);
+            total_cost += cost;
+
+            if worst.is_enabled() {
+                worst.push(i, cost);
+            }
+        }
+
+        let worst_msgs: Vec<String> = worst
+            .into_sorted(total_cost)
+            .into_iter()
+            .map(|(i, cost)| {
+                let (gram, weight) = quadgrams[i];
+                let percentages = format_percentages(cost, total_cost, weight, applicable_weight);
+                format!(
+                    "{} {}",
+                    visualize_ngram(&[gram.0, gram.1, gram.2, gram.3]),
+                    percentages
+                )
+            })
+            .collect();
+
+        let mut msg_parts = Vec::new();
+        if !all_applicable {
+            msg_parts.push(format_applicable_weight(applicable_weight, total_weight));
+        }
+        if !worst_msgs.is_empty() {
+            msg_parts.push(worst_msgs.join(", "));
+        }
+
+        let msg = if msg_parts.is_empty() {
+            None
+        } else {
+            Some(msg_parts.join("; "))
+        };
+
+        (total_cost, msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_layout};
+
+    /// Left hand: pinky "a" to "e", ring "f" to "j", middle "k" to "o", index "p" to "t";
+    /// right hand: index "u" to "y", ..., pinky "J" to "N"; thumbs "O" (left) and "P" (right).
+    fn layout() -> Layout {
+        toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP")
+    }
+
+    fn metric(yaml: &str) -> FourgramRedirect {
+        FourgramRedirect::new(&serde_yaml::from_str(yaml).unwrap())
+    }
+
+    fn cost(metric: &FourgramRedirect, layout: &Layout, quadgram: &str) -> Option<f64> {
+        let keys = layerkeys(layout, quadgram);
+        metric.individual_cost((keys[0], keys[1], keys[2], keys[3]), 1.0, 1.0, layout)
+    }
+
+    #[test]
+    fn fires_only_if_both_trigrams_are_redirects() {
+        let layout = layout();
+        let metric = metric("{}");
+
+        // index → pinky → middle → pinky: out, in, out
+        assert_eq!(cost(&metric, &layout, "rcmc"), Some(1.0));
+        // the last trigram (pinky → middle → index) rolls inwards
+        assert_eq!(cost(&metric, &layout, "rcmr"), Some(0.0));
+        // the first trigram (pinky → middle → index) rolls inwards
+        assert_eq!(cost(&metric, &layout, "cmrc"), Some(0.0));
+        // not on one hand or with a thumb
+        assert_eq!(cost(&metric, &layout, "rcmw"), None);
+        assert_eq!(cost(&metric, &layout, "rcmO"), None);
+    }
+
+    #[test]
+    fn weak_fourgram_redirects_are_not_counted() {
+        let layout = layout();
+
+        // ring → pinky → middle → pinky without a strong finger
+        assert_eq!(cost(&metric("{}"), &layout, "hcmc"), Some(0.0));
+        assert_eq!(
+            cost(&metric("{strong_fingers: [Middle]}"), &layout, "hcmc"),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn hand_costs_replace_the_base_cost() {
+        let layout = layout();
+        let metric = metric("{base_cost: 2.0, hand_costs: {Right: 3.0}}");
+
+        assert_eq!(cost(&metric, &layout, "rcmc"), Some(2.0));
+        // right index → pinky → middle → pinky
+        assert_eq!(cost(&metric, &layout, "wLBL"), Some(3.0));
+    }
+
+    #[test]
+    fn worst_list_shows_the_quadgrams() {
+        let layout = layout();
+        let metric = metric("{n_worst: 3}");
+        let rcmc = layerkeys(&layout, "rcmc");
+        let rcmr = layerkeys(&layout, "rcmr");
+        let quadgrams = [
+            ((rcmc[0], rcmc[1], rcmc[2], rcmc[3]), 3.0),
+            ((rcmr[0], rcmr[1], rcmr[2], rcmr[3]), 1.0),
+        ];
+
+        let (cost, message) = metric.total_cost(&quadgrams, None, &layout);
+        let message = message.unwrap();
+
+        assert_eq!(cost, 3.0);
+        assert!(
+            message.contains("rcmc") && message.contains("100.0%"),
+            "{}",
+            message
+        );
+        assert!(!message.contains("rcmr"), "{}", message);
+    }
+}
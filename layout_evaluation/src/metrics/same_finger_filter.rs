@@ -0,0 +1,353 @@
+//! Filter options shared by the same-finger cost metrics ([`Sfb`](super::bigram_metrics::sfb),
+//! [`Sfs`](super::trigram_metrics::sfs)) and the corresponding statistics
+//! ([`BigramStats`](super::bigram_metrics::bigram_stats),
+//! [`TrigramStats`](super::trigram_metrics::trigram_stats)).
+//!
+//! All of them decide with [`SameFingerFilter::is_same_finger`] whether a pair of keys counts
+//! as a same-finger pair. Given the same options, a pair is part of the SFB (SFS) share of the
+//! statistics exactly if the cost metric charges its base cost.
+//!
+//! ## Configuration
+//!
+//! - `ignore_thumbs`: Skip pairs involving a thumb
+//! - `ignore_modifiers`: Skip pairs involving a modifier key
+//! - `modifier_handling`: Finer control over pairs involving modifiers (overrides `ignore_modifiers`)
+//! - `exclude_same_position_repeats`: Skip repeats of the same key (default true)
+//! - `exclude_same_symbol_repeats`: Skip repeats of a symbol on different layers (default false)
+//! - `ignore_movements`: Skip these direction pairs, e.g. `[[Center, South]]` (default none)
+
+use super::utils::{matches_movement, MovementPattern};
+
+use keyboard_layout::{
+    key::{Finger, Key},
+    layout::LayerKey,
+};
+
+use serde::Deserialize;
+
+/// How pairs involving modifier keys are treated
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Eq)]
+pub enum ModifierHandling {
+    /// Skip all pairs involving a modifier
+    Exclude,
+    /// Treat modifiers like any other key
+    Include,
+    /// Skip pairs whose first key is a held modifier, as it is still held while the second
+    /// key is pressed (a chord rather than a sequential SFB). One-shot modifiers, which are
+    /// released before the next key, are included.
+    ExcludeHeld,
+}
+
+impl ModifierHandling {
+    /// Whether the pair shall be skipped
+    #[inline(always)]
+    fn excludes(&self, k1: &LayerKey, k2: &LayerKey) -> bool {
+        match self {
+            ModifierHandling::Exclude => k1.is_modifier.is_some() || k2.is_modifier.is_some(),
+            ModifierHandling::Include => false,
+            ModifierHandling::ExcludeHeld => k1.is_modifier.is_hold(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    pub ignore_thumbs: bool,
+    pub ignore_modifiers: Option<bool>,
+    /// How pairs involving modifiers are treated (overrides `ignore_modifiers`)
+    pub modifier_handling: Option<ModifierHandling>,
+    /// Skip repeated presses of the same key on the same layer, e.g. "ee". Default: true
+    #[serde(default = "default_exclude_same_position_repeats")]
+    pub exclude_same_position_repeats: bool,
+    /// Skip pairs of the same symbol reached via different layers. Default: false
+    #[serde(default)]
+    pub exclude_same_symbol_repeats: bool,
+    /// Same-finger movements to skip (e.g., [[Center, South], [In, South]]). A missing
+    /// direction (`null`) matches any direction, e.g. [Center, null] skips all movements
+    /// starting from `Center`.
+    #[serde(default)]
+    pub ignore_movements: Vec<MovementPattern>,
+}
+
+fn default_exclude_same_position_repeats() -> bool {
+    true
+}
+
+#[derive(Clone, Debug)]
+pub struct SameFingerFilter {
+    ignore_thumbs: bool,
+    modifier_handling: ModifierHandling,
+    exclude_same_position_repeats: bool,
+    exclude_same_symbol_repeats: bool,
+    ignore_movements: Vec<MovementPattern>,
+}
+
+impl SameFingerFilter {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            ignore_thumbs: params.ignore_thumbs,
+            modifier_handling: params.modifier_handling.unwrap_or(
+                if params.ignore_modifiers.unwrap_or(false) {
+                    ModifierHandling::Exclude
+                } else {
+                    ModifierHandling::Include
+                },
+            ),
+            exclude_same_position_repeats: params.exclude_same_position_repeats,
+            exclude_same_symbol_repeats: params.exclude_same_symbol_repeats,
+            ignore_movements: params.ignore_movements.clone(),
+        }
+    }
+
+    /// Whether a pair is skipped because of its keys (thumbs and modifiers), regardless of the
+    /// fingers. The statistics skip such pairs for all of their categories.
+    #[inline(always)]
+    pub fn excludes_keys(&self, k1: &LayerKey, k2: &LayerKey) -> bool {
+        self.modifier_handling.excludes(k1, k2)
+            || (self.ignore_thumbs
+                && (k1.key.finger == Finger::Thumb || k2.key.finger == Finger::Thumb))
+    }
+
+    /// Whether `k1` followed by `k2` (directly or with keys in between) is a same-finger pair
+    /// that is not skipped
+    #[inline(always)]
+    pub fn is_same_finger(&self, k1: &LayerKey, k2: &LayerKey) -> bool {
+        self.is_same_finger_key_pair(&k1.key, &k2.key) && !self.excludes_layerkeys(k1, k2)
+    }
+
+    /// The part of [`SameFingerFilter::is_same_finger`] that only depends on the physical keys
+    /// (fingers, thumbs, and movements), e.g. for precomputing it per pair of keys
+    #[inline(always)]
+    pub fn is_same_finger_key_pair(&self, k1: &Key, k2: &Key) -> bool {
+        if k1.hand != k2.hand || k1.finger != k2.finger {
+            return false;
+        }
+
+        if self.ignore_thumbs && (k1.finger == Finger::Thumb || k2.finger == Finger::Thumb) {
+            return false;
+        }
+
+        !self
+            .ignore_movements
+            .iter()
+            .any(|pattern| matches_movement(pattern, k1.direction, k2.direction))
+    }
+
+    /// The part of [`SameFingerFilter::is_same_finger`] that depends on the symbols and layers
+    /// of the keys: whether the pair is skipped because of modifiers or as a repeat
+    #[inline(always)]
+    pub fn excludes_layerkeys(&self, k1: &LayerKey, k2: &LayerKey) -> bool {
+        self.modifier_handling.excludes(k1, k2)
+            || (self.exclude_same_position_repeats && k1 == k2)
+            || (self.exclude_same_symbol_repeats && k1.symbol == k2.symbol && k1.layer != k2.layer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{
+        bigram_metrics::{bigram_stats, sfb, BigramMetric},
+        trigram_metrics::{
+            classification::default_strong_fingers, sfs, trigram_stats, TrigramMetric,
+        },
+        worst_ngrams::WorstParameters,
+    };
+
+    use keyboard_layout::{
+        key::Direction,
+        layout::{LayerModifierType, LayerModifiers, Layout},
+        test_utils::toy_layout,
+    };
+    use rand::{seq::IndexedRandom, Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    const DIRECTIONS: [Option<Direction>; 6] = [
+        None,
+        Some(Direction::North),
+        Some(Direction::South),
+        Some(Direction::Center),
+        Some(Direction::In),
+        Some(Direction::Out),
+    ];
+
+    const MODIFIER_TYPES: [LayerModifierType; 4] = [
+        LayerModifierType::None,
+        LayerModifierType::Hold,
+        LayerModifierType::OneShot,
+        LayerModifierType::LongPress,
+    ];
+
+    fn random_parameters(rng: &mut impl Rng) -> Parameters {
+        let modifier_handling = [
+            None,
+            Some(ModifierHandling::Exclude),
+            Some(ModifierHandling::Include),
+            Some(ModifierHandling::ExcludeHeld),
+        ];
+        let ignore_movements = (0..rng.random_range(0..3))
+            .map(|_| {
+                (
+                    *DIRECTIONS.choose(rng).unwrap(),
+                    *DIRECTIONS.choose(rng).unwrap(),
+                )
+            })
+            .collect();
+
+        Parameters {
+            ignore_thumbs: rng.random(),
+            ignore_modifiers: *[None, Some(false), Some(true)].choose(rng).unwrap(),
+            modifier_handling: *modifier_handling.choose(rng).unwrap(),
+            exclude_same_position_repeats: rng.random(),
+            exclude_same_symbol_repeats: rng.random(),
+            ignore_movements,
+        }
+    }
+
+    /// Keys of the left pinky, the left ring finger, and the left thumb of the toy keyboard on
+    /// random layers, with few symbols and random modifier types, such that repeats of keys and
+    /// symbols are frequent
+    fn random_layerkeys(layout: &Layout, rng: &mut impl Rng) -> Vec<LayerKey> {
+        let keys: Vec<&Key> = layout.keyboard.keys[..10]
+            .iter()
+            .chain(
+                layout
+                    .keyboard
+                    .keys
+                    .iter()
+                    .filter(|k| k.finger == Finger::Thumb),
+            )
+            .collect();
+
+        (0..24)
+            .map(|_| {
+                LayerKey::new(
+                    rng.random_range(0..3),
+                    (*keys.choose(rng).unwrap()).clone(),
+                    *['a', 'b', 'c'].choose(rng).unwrap(),
+                    LayerModifiers::default(),
+                    false,
+                    *MODIFIER_TYPES.choose(rng).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn metrics(filter: &Parameters) -> (sfb::Sfb, bigram_stats::BigramStats) {
+        let sfb = sfb::Sfb::new(&sfb::Parameters {
+            default_cost: 1.0,
+            filter: filter.clone(),
+            costs: Default::default(),
+            finger_factors: Default::default(),
+            critical_bigram: None,
+            roll_movements: None,
+            worst: WorstParameters::default(),
+        });
+        let bigram_stats = bigram_stats::BigramStats::new(&bigram_stats::Parameters {
+            filter: filter.clone(),
+            category_costs: None,
+        });
+        (sfb, bigram_stats)
+    }
+
+    fn trigram_metrics(filter: &Parameters) -> (sfs::Sfs, trigram_stats::TrigramStats) {
+        let sfs = sfs::Sfs::new(&sfs::Parameters {
+            filter: filter.clone(),
+            finger_factors: Default::default(),
+            critical_bigram: None,
+        });
+        let trigram_stats = trigram_stats::TrigramStats::new(&trigram_stats::Parameters {
+            filter: filter.clone(),
+            same_finger_rolls: Vec::new(),
+            category_costs: None,
+            fraction_of: Default::default(),
+            strong_fingers: default_strong_fingers(),
+        });
+        (sfs, trigram_stats)
+    }
+
+    #[test]
+    fn sfb_costs_exactly_the_sfb_share() {
+        let layout = toy_layout("");
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(604);
+        let (mut counted, mut skipped) = (0, 0);
+
+        for _ in 0..200 {
+            let filter = random_parameters(&mut rng);
+            let (sfb, bigram_stats) = metrics(&filter);
+            let mut precomputed = sfb.clone();
+            precomputed.precompute(&layout.keyboard);
+            let layerkeys = random_layerkeys(&layout, &mut rng);
+
+            for _ in 0..50 {
+                let k1 = layerkeys.choose(&mut rng).unwrap();
+                let k2 = layerkeys.choose(&mut rng).unwrap();
+                let in_share = bigram_stats
+                    .compute_statistics(&[((k1, k2), 1.0)], None)
+                    .sfb_percent
+                    > 0.0;
+
+                for metric in [&sfb, &precomputed] {
+                    let cost = metric.individual_cost(k1, k2, 1.0, 1.0, &layout);
+                    assert_eq!(
+                        cost.map_or(false, |cost| cost != 0.0),
+                        in_share,
+                        "{:?}\n{:?}\n{:?}",
+                        filter,
+                        k1,
+                        k2
+                    );
+                }
+
+                if in_share {
+                    counted += 1;
+                } else {
+                    skipped += 1;
+                }
+            }
+        }
+
+        assert!(counted > 100 && skipped > 100, "{} {}", counted, skipped);
+    }
+
+    #[test]
+    fn sfs_costs_exactly_the_sfs_share() {
+        let layout = toy_layout("");
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(604);
+        let (mut counted, mut skipped) = (0, 0);
+
+        for _ in 0..200 {
+            let filter = random_parameters(&mut rng);
+            let (sfs, trigram_stats) = trigram_metrics(&filter);
+            let layerkeys = random_layerkeys(&layout, &mut rng);
+
+            for _ in 0..50 {
+                let k1 = layerkeys.choose(&mut rng).unwrap();
+                let k2 = layerkeys.choose(&mut rng).unwrap();
+                let k3 = layerkeys.choose(&mut rng).unwrap();
+                let in_share = trigram_stats
+                    .compute_statistics(&[((k1, k2, k3), 1.0)], None)
+                    .sfs_percent
+                    > 0.0;
+
+                let cost = sfs.individual_cost(k1, k2, k3, 1.0, 1.0, &layout);
+                assert_eq!(
+                    cost.map_or(false, |cost| cost != 0.0),
+                    in_share,
+                    "{:?}\n{:?}\n{:?}",
+                    filter,
+                    k1,
+                    k3
+                );
+
+                if in_share {
+                    counted += 1;
+                } else {
+                    skipped += 1;
+                }
+            }
+        }
+
+        assert!(counted > 100 && skipped > 100, "{} {}", counted, skipped);
+    }
+}
@@ -1,10 +1,16 @@
 //! The `metrics` module provides a trait for trigram metrics.
-use keyboard_layout::layout::{LayerKey, Layout};
+use keyboard_layout::{
+    keyboard::Keyboard,
+    layout::{LayerKey, Layout},
+};
+
+use super::{format_utils::visualize_ngram, utils::to_percentage, worst_ngrams::env_setting};
 
 use ordered_float::OrderedFloat;
 use priority_queue::DoublePriorityQueue;
-use std::{env, fmt};
+use std::fmt;
 
+pub mod classification;
 pub mod irregularity;
 pub mod no_handswitch_in_trigram;
 pub mod oxey_alternates;
@@ -17,7 +23,9 @@ pub mod oxey_onehands;
 pub mod oxey_outward_rolls;
 pub mod oxey_redirects;
 mod redirect_base; // Private module - shared base for redirect metrics
+pub mod redirect_classifier;
 pub mod redirects;
+pub mod roll_redirect;
 pub mod weak_redirect;
 pub mod secondary_bigrams;
 pub mod sfs;
@@ -25,6 +33,39 @@ pub mod trigram_finger_repeats;
 pub mod trigram_rolls;
 pub mod trigram_stats;
 
+pub use classification::{classify_trigram, TrigramClass};
+
+use redirect_classifier::RedirectClassifier;
+
+/// Tables that several trigram metrics need, computed at most once per keyboard and shared by
+/// the metrics (see [`TrigramMetric::precompute`]).
+#[derive(Debug)]
+pub struct SharedTables<'a> {
+    keyboard: &'a Keyboard,
+    redirect_classifier: Option<RedirectClassifier>,
+}
+
+impl<'a> SharedTables<'a> {
+    pub fn new(keyboard: &'a Keyboard) -> Self {
+        Self {
+            keyboard,
+            redirect_classifier: None,
+        }
+    }
+
+    pub fn keyboard(&self) -> &Keyboard {
+        self.keyboard
+    }
+
+    /// The [`RedirectClassifier`] of the keyboard (computed on the first call)
+    pub fn redirect_classifier(&mut self) -> RedirectClassifier {
+        let keyboard = self.keyboard;
+        self.redirect_classifier
+            .get_or_insert_with(|| RedirectClassifier::new(keyboard))
+            .clone()
+    }
+}
+
 /// TrigramMetric is a trait for metrics that iterates over weighted trigrams.
 pub trait TrigramMetric: Send + Sync + TrigramMetricClone + fmt::Debug {
     /// Return the name of the metric.
@@ -44,6 +85,31 @@ pub trait TrigramMetric: Send + Sync + TrigramMetricClone + fmt::Debug {
         None
     }
 
+    /// Whether [`TrigramMetric::total_cost`] is the sum of the individual costs and each individual cost
+    /// is proportional to the weight of its trigram. Then, the cost can be updated from the
+    /// trigrams that change when two keys are swapped, see
+    /// [`LayoutEvaluator::update_swap`](crate::evaluator::LayoutEvaluator::update_swap).
+    fn is_additive(&self) -> bool {
+        false
+    }
+
+    /// Precompute what does not depend on the layout for the keys of the keyboard of `tables`,
+    /// taking tables that other metrics need as well from `tables`. Called once by
+    /// [`Evaluator::precompute`](crate::evaluation::Evaluator::precompute). Keys of other
+    /// keyboards are costed directly, so the costs do not change by precomputing.
+    fn precompute(&mut self, _tables: &mut SharedTables) {}
+
+    /// Named shares (in percent) that can be capped with `max_allowed` in the evaluation
+    /// config, see [`crate::results::MaxAllowed`].
+    fn percentages(
+        &self,
+        _trigrams: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
+        _total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> Vec<(&'static str, f64)> {
+        Vec::new()
+    }
+
     /// Compute the total cost for the metric.
     fn total_cost(
         &self,
@@ -52,14 +118,8 @@ pub trait TrigramMetric: Send + Sync + TrigramMetricClone + fmt::Debug {
         total_weight: Option<f64>,
         layout: &Layout,
     ) -> (f64, Option<String>) {
-        let show_worst: bool = env::var("SHOW_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(true);
-        let n_worst: usize = env::var("N_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(3);
+        let show_worst: bool = env_setting("SHOW_WORST").unwrap_or(true);
+        let n_worst: usize = env_setting("N_WORST").unwrap_or(3);
 
         let total_weight = total_weight.unwrap_or_else(|| trigrams.iter().map(|(_, w)| w).sum());
         let cost_iter = trigrams
@@ -108,11 +168,9 @@ pub trait TrigramMetric: Send + Sync + TrigramMetricClone + fmt::Debug {
                     .map(|(i, cost)| {
                         let (gram, _) = trigrams[i];
                         format!(
-                            "{}{}{} ({:>5.2}%)",
-                            gram.0,
-                            gram.1,
-                            gram.2,
-                            100.0 * cost.into_inner() / total_cost,
+                            "{} ({:>5.2}%)",
+                            visualize_ngram(&[gram.0, gram.1, gram.2]),
+                            to_percentage(cost.into_inner(), total_cost),
                         )
                     })
                     .collect();
@@ -0,0 +1,482 @@
+//! Classification of trigrams into rolls, redirects, alternations and the like.
+//!
+//! This is the single implementation shared by [`super::trigram_stats::TrigramStats`] and the
+//! redirect metrics ([`super::redirects`], [`super::weak_redirect`] and
+//! [`super::roll_redirect`]). It can also be used directly for custom analyses or metrics:
+//! [`classify_trigram`] (re-exported from [`super`]) gives the [`TrigramClass`] of a trigram as
+//! counted by the statistics.
+//!
+//! "Inwards" means towards the center of the keyboard, i.e. increasing matrix columns on the
+//! left hand and decreasing ones on the right hand.
+
+use crate::metrics::{
+    inwards,
+    utils::{matches_movement, MovementPattern},
+};
+
+use keyboard_layout::{key::Finger, layout::LayerKey};
+use serde::Deserialize;
+
+/// Category of a trigram with respect to rolls, redirects and hand alternation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum TrigramCategory {
+    /// Two keys on one hand rolling inwards, one key on the other hand
+    BigramRollIn,
+    /// Two keys on one hand rolling outwards, one key on the other hand
+    BigramRollOut,
+    /// Three keys on one hand rolling inwards
+    RollIn,
+    /// Three keys on one hand rolling outwards
+    RollOut,
+    /// First and last key on one hand, middle key on the other hand
+    Alternation,
+    /// Three keys on one hand changing direction (involving a strong finger)
+    Redirect,
+    /// Three keys on one hand changing direction (without strong fingers)
+    WeakRedirect,
+    /// Anything else, e.g. same-finger bigrams within the trigram
+    Other,
+}
+
+impl TrigramCategory {
+    /// Number of categories, e.g. for arrays indexed by `category as usize`
+    pub const COUNT: usize = 8;
+}
+
+/// Class of a trigram: its [`TrigramCategory`], unless its same-hand bigram is one of the
+/// same-finger rolls passed to [`classify_trigram`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrigramClass {
+    BigramRollIn,
+    BigramRollOut,
+    RollIn,
+    RollOut,
+    Alternation,
+    Redirect,
+    WeakRedirect,
+    Other,
+    /// Two keys on one hand forming the same-finger roll with this index in
+    /// `same_finger_rolls`, one key on the other hand
+    SameFingerRoll(usize),
+}
+
+impl TrigramClass {
+    /// The category of the class (same-finger rolls are [`TrigramCategory::Other`], as all
+    /// same-finger bigrams)
+    pub fn category(self) -> TrigramCategory {
+        match self {
+            TrigramClass::BigramRollIn => TrigramCategory::BigramRollIn,
+            TrigramClass::BigramRollOut => TrigramCategory::BigramRollOut,
+            TrigramClass::RollIn => TrigramCategory::RollIn,
+            TrigramClass::RollOut => TrigramCategory::RollOut,
+            TrigramClass::Alternation => TrigramCategory::Alternation,
+            TrigramClass::Redirect => TrigramCategory::Redirect,
+            TrigramClass::WeakRedirect => TrigramCategory::WeakRedirect,
+            TrigramClass::Other | TrigramClass::SameFingerRoll(_) => TrigramCategory::Other,
+        }
+    }
+}
+
+impl From<TrigramCategory> for TrigramClass {
+    fn from(category: TrigramCategory) -> Self {
+        match category {
+            TrigramCategory::BigramRollIn => TrigramClass::BigramRollIn,
+            TrigramCategory::BigramRollOut => TrigramClass::BigramRollOut,
+            TrigramCategory::RollIn => TrigramClass::RollIn,
+            TrigramCategory::RollOut => TrigramClass::RollOut,
+            TrigramCategory::Alternation => TrigramClass::Alternation,
+            TrigramCategory::Redirect => TrigramClass::Redirect,
+            TrigramCategory::WeakRedirect => TrigramClass::WeakRedirect,
+            TrigramCategory::Other => TrigramClass::Other,
+        }
+    }
+}
+
+/// Position of the same-hand bigram within a trigram whose keys are split 2:1 between the
+/// hands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BigramPosition {
+    /// The first two keys, followed by a hand switch
+    Leading,
+    /// The last two keys, after a hand switch
+    Trailing,
+}
+
+/// The position of the same-hand bigram, if the keys of the trigram are split 2:1 between the
+/// hands
+#[inline(always)]
+pub fn bigram_position(k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> Option<BigramPosition> {
+    let h1 = k1.key.hand;
+    let h2 = k2.key.hand;
+    let h3 = k3.key.hand;
+
+    if h1 == h2 && h2 != h3 {
+        Some(BigramPosition::Leading)
+    } else if h1 != h2 && h2 == h3 {
+        Some(BigramPosition::Trailing)
+    } else {
+        None
+    }
+}
+
+/// The fingers whose involvement makes a redirect a normal (not a weak) one, unless configured
+/// differently: index and thumb
+pub fn default_strong_fingers() -> Vec<Finger> {
+    vec![Finger::Index, Finger::Thumb]
+}
+
+/// Classify a trigram into its [`TrigramClass`]: a same-finger roll if its same-hand bigram
+/// (see [`bigram_position`]) is typed with one finger and matches one of `same_finger_rolls`
+/// (the first matching one), otherwise its category (see [`classify_trigram_category`]).
+#[inline(always)]
+pub fn classify_trigram(
+    k1: &LayerKey,
+    k2: &LayerKey,
+    k3: &LayerKey,
+    same_finger_rolls: &[MovementPattern],
+    strong_fingers: &[Finger],
+) -> TrigramClass {
+    let bigram = match bigram_position(k1, k2, k3) {
+        Some(BigramPosition::Leading) => Some((k1, k2)),
+        Some(BigramPosition::Trailing) => Some((k2, k3)),
+        None => None,
+    };
+
+    if let Some((kb1, kb2)) = bigram {
+        if kb1.key.finger == kb2.key.finger {
+            let roll = same_finger_rolls.iter().position(|pattern| {
+                matches_movement(pattern, kb1.key.direction, kb2.key.direction)
+            });
+            if let Some(roll) = roll {
+                return TrigramClass::SameFingerRoll(roll);
+            }
+        }
+    }
+
+    classify_trigram_category(k1, k2, k3, strong_fingers).into()
+}
+
+/// Classify a trigram into its [`TrigramCategory`]. Redirects without any of the
+/// `strong_fingers` are weak.
+pub fn classify_trigram_category(
+    k1: &LayerKey,
+    k2: &LayerKey,
+    k3: &LayerKey,
+    strong_fingers: &[Finger],
+) -> TrigramCategory {
+    let h1 = k1.key.hand;
+    let h2 = k2.key.hand;
+    let h3 = k3.key.hand;
+
+    if h1 == h2 && h2 == h3 {
+        // Same hand (all 3 keys) - check roll in/out or redirect
+        let (is_roll_in, is_roll_out) = classify_same_hand_roll(k1, k2, k3);
+
+        if is_roll_in {
+            return TrigramCategory::RollIn;
+        } else if is_roll_out {
+            return TrigramCategory::RollOut;
+        } else {
+            // Not a roll, check for redirect
+            let (is_redirect, is_weak) = classify_redirect(k1, k2, k3, strong_fingers);
+            if is_redirect {
+                return if is_weak {
+                    TrigramCategory::WeakRedirect
+                } else {
+                    TrigramCategory::Redirect
+                };
+            }
+        }
+    } else if h1 == h3 && h1 != h2 {
+        // Alternation (LRL or RLR)
+        return TrigramCategory::Alternation;
+    } else {
+        // Bigram pattern (2,1 or 1,2) - check bigram rolls
+        let (is_inward, is_outward) = classify_bigram_roll(k1, k2, k3);
+
+        if is_inward {
+            return TrigramCategory::BigramRollIn;
+        } else if is_outward {
+            return TrigramCategory::BigramRollOut;
+        }
+    }
+
+    TrigramCategory::Other
+}
+
+/// Classify the same-hand bigram within a trigram whose keys are split 2:1 between the hands.
+/// Same-finger bigrams are not considered rolls.
+/// Returns: (is_inward, is_outward)
+pub fn classify_bigram_roll(k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> (bool, bool) {
+    let h1 = k1.key.hand;
+    let h2 = k2.key.hand;
+    let h3 = k3.key.hand;
+
+    let first_roll = h1 == h2 && h2 != h3;
+    let second_roll = h1 != h2 && h2 == h3;
+
+    if !(first_roll || second_roll) {
+        return (false, false);
+    }
+
+    let (kr1, kr2) = if first_roll { (k1, k2) } else { (k2, k3) };
+
+    // Same-finger movements are not considered rolls
+    if kr1.key.finger == kr2.key.finger {
+        return (false, false);
+    }
+
+    if inwards(kr1, kr2) {
+        (true, false)
+    } else {
+        (false, true)
+    }
+}
+
+/// Check if a trigram is a same-hand roll (all 3 keys on same hand, different fingers, directional)
+/// Returns: (is_roll_in, is_roll_out)
+pub fn classify_same_hand_roll(k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> (bool, bool) {
+    let h1 = k1.key.hand;
+    let h2 = k2.key.hand;
+    let h3 = k3.key.hand;
+
+    // Must be same hand (one-handed trigram)
+    if !(h1 == h2 && h2 == h3) {
+        return (false, false);
+    }
+
+    let f1 = k1.key.finger;
+    let f2 = k2.key.finger;
+    let f3 = k3.key.finger;
+
+    // Must use different fingers (no same-finger bigrams)
+    if f1 == f2 || f2 == f3 {
+        return (false, false);
+    }
+
+    // Check if all three movements are in the same direction
+    let inwards1 = inwards(k1, k2);
+    let inwards2 = inwards(k2, k3);
+
+    let outwards1 = inwards(k2, k1);
+    let outwards2 = inwards(k3, k2);
+
+    // Roll in: both movements inward
+    if inwards1 && inwards2 {
+        return (true, false);
+    }
+
+    // Roll out: both movements outward
+    if outwards1 && outwards2 {
+        return (false, true);
+    }
+
+    (false, false)
+}
+
+/// Check if a trigram is a redirect (one-handed with direction change) and whether it's weak
+/// (involving none of the `strong_fingers`, see [`default_strong_fingers`])
+/// Returns: (is_redirect, is_weak_redirect)
+pub fn classify_redirect(
+    k1: &LayerKey,
+    k2: &LayerKey,
+    k3: &LayerKey,
+    strong_fingers: &[Finger],
+) -> (bool, bool) {
+    let is_redirect = is_redirect(k1, k2, k3);
+
+    (
+        is_redirect,
+        is_redirect && is_weak(k1, k2, k3, strong_fingers),
+    )
+}
+
+/// Whether a trigram is one-handed with a direction change (without same-finger bigrams)
+fn is_redirect(k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> bool {
+    let h1 = k1.key.hand;
+    let h2 = k2.key.hand;
+    let h3 = k3.key.hand;
+
+    // Must be same hand (one-handed trigram)
+    if !(h1 == h2 && h2 == h3) {
+        return false;
+    }
+
+    // Must use different fingers (no same-finger bigrams)
+    if k1.key.finger == k2.key.finger || k2.key.finger == k3.key.finger {
+        return false;
+    }
+
+    let inwards1 = inwards(k1, k2);
+    let inwards2 = inwards(k2, k3);
+
+    let outwards1 = inwards(k2, k1);
+    let outwards2 = inwards(k3, k2);
+
+    // Check for direction change: inward->outward or outward->inward
+    (inwards1 && outwards2) || (outwards1 && inwards2)
+}
+
+/// Whether none of the keys is typed with one of the `strong_fingers`
+#[inline(always)]
+fn is_weak(k1: &LayerKey, k2: &LayerKey, k3: &LayerKey, strong_fingers: &[Finger]) -> bool {
+    ![k1, k2, k3]
+        .iter()
+        .any(|k| strong_fingers.contains(&k.key.finger))
+}
+
+/// Check if a trigram is a roll that ends in a redirect: the first two keys roll across
+/// different fingers, the third key reverses the lateral direction and lands on yet another
+/// finger (in contrast to redirects returning to the first finger).
+///
+/// Every such trigram is also a redirect as classified by [`classify_redirect`].
+pub fn is_roll_then_redirect(k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> bool {
+    is_redirect(k1, k2, k3) && k1.key.finger != k3.key.finger
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ahash::AHashSet;
+    use keyboard_layout::{
+        key::Direction,
+        layout::Layout,
+        test_utils::{layerkeys, toy_layout},
+    };
+
+    /// Left hand: pinky "a" to "e", ring "f" to "j", middle "k" to "o", index "p" to "t"; right
+    /// hand: index "u" to "y", middle "z" to "D", ring "E" to "I", pinky "J" to "N"; thumbs "O"
+    /// (left) and "P" (right). The Center keys are "c", "h", "m", "r", "w", "B", "G", and "L".
+    fn layout() -> Layout {
+        toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP")
+    }
+
+    fn category(layout: &Layout, trigram: &str, strong_fingers: &[Finger]) -> TrigramCategory {
+        let keys = layerkeys(layout, trigram);
+        classify_trigram_category(keys[0], keys[1], keys[2], strong_fingers)
+    }
+
+    #[test]
+    fn every_category_is_classified() {
+        let layout = layout();
+        let strong = default_strong_fingers();
+        let cases = [
+            ("chm", TrigramCategory::RollIn),
+            ("LGB", TrigramCategory::RollIn),
+            ("mhc", TrigramCategory::RollOut),
+            ("chw", TrigramCategory::BigramRollIn),
+            ("wch", TrigramCategory::BigramRollIn),
+            ("hcw", TrigramCategory::BigramRollOut),
+            ("wBc", TrigramCategory::BigramRollOut),
+            ("cwc", TrigramCategory::Alternation),
+            ("cwh", TrigramCategory::Alternation),
+            ("rch", TrigramCategory::Redirect),
+            ("hcO", TrigramCategory::Redirect),
+            ("hcm", TrigramCategory::WeakRedirect),
+            // same-finger bigrams
+            ("abw", TrigramCategory::Other),
+            ("wab", TrigramCategory::Other),
+            ("cbh", TrigramCategory::Other),
+            // no lateral movement between index and thumb
+            ("hrO", TrigramCategory::Other),
+            // repeated key
+            ("ccc", TrigramCategory::Other),
+        ];
+
+        for (trigram, expected) in cases.iter() {
+            assert_eq!(
+                category(&layout, trigram, &strong),
+                *expected,
+                "{}",
+                trigram
+            );
+        }
+    }
+
+    #[test]
+    fn weak_redirects_depend_on_the_strong_fingers() {
+        let layout = layout();
+
+        assert_eq!(category(&layout, "hcm", &[]), TrigramCategory::WeakRedirect);
+        assert_eq!(category(&layout, "rch", &[]), TrigramCategory::WeakRedirect);
+        assert_eq!(
+            category(&layout, "hcm", &[Finger::Middle]),
+            TrigramCategory::Redirect
+        );
+        // the strong finger may be at any position
+        assert_eq!(
+            category(&layout, "mch", &[Finger::Middle]),
+            TrigramCategory::Redirect
+        );
+        assert_eq!(
+            category(&layout, "chc", &[Finger::Pinky]),
+            TrigramCategory::Redirect
+        );
+        assert_eq!(
+            category(&layout, "chc", &[Finger::Index]),
+            TrigramCategory::WeakRedirect
+        );
+    }
+
+    #[test]
+    fn same_finger_rolls_are_matched_in_order() {
+        let layout = layout();
+        let strong = default_strong_fingers();
+        let rolls = [
+            (Some(Direction::North), Some(Direction::South)),
+            (None, Some(Direction::South)),
+        ];
+        let class = |trigram: &str| {
+            let keys = layerkeys(&layout, trigram);
+            classify_trigram(keys[0], keys[1], keys[2], &rolls, &strong)
+        };
+
+        assert_eq!(class("aew"), TrigramClass::SameFingerRoll(0));
+        assert_eq!(class("wae"), TrigramClass::SameFingerRoll(0));
+        assert_eq!(class("cew"), TrigramClass::SameFingerRoll(1));
+        assert_eq!(class("eaw"), TrigramClass::Other);
+        // all keys on one hand
+        assert_eq!(class("aeh"), TrigramClass::Other);
+        assert_eq!(class("chw"), TrigramClass::BigramRollIn);
+        assert_eq!(class("aew").category(), TrigramCategory::Other);
+    }
+
+    #[test]
+    fn categories_are_consistent_for_all_trigrams() {
+        let layout = layout();
+        let strong = default_strong_fingers();
+        let mut seen = AHashSet::default();
+
+        for k1 in layout.layerkeys.iter() {
+            for k2 in layout.layerkeys.iter() {
+                for k3 in layout.layerkeys.iter() {
+                    let category = classify_trigram_category(k1, k2, k3, &strong);
+                    seen.insert(category);
+
+                    let (roll_in, roll_out) = classify_same_hand_roll(k1, k2, k3);
+                    let (bigram_in, bigram_out) = classify_bigram_roll(k1, k2, k3);
+                    let (redirect, weak) = classify_redirect(k1, k2, k3, &strong);
+                    let alternation = k1.key.hand == k3.key.hand && k1.key.hand != k2.key.hand;
+
+                    assert_eq!(category == TrigramCategory::RollIn, roll_in);
+                    assert_eq!(category == TrigramCategory::RollOut, roll_out);
+                    assert_eq!(category == TrigramCategory::BigramRollIn, bigram_in);
+                    assert_eq!(category == TrigramCategory::BigramRollOut, bigram_out);
+                    assert_eq!(category == TrigramCategory::Alternation, alternation);
+                    assert_eq!(category == TrigramCategory::Redirect, redirect && !weak);
+                    assert_eq!(category == TrigramCategory::WeakRedirect, weak);
+                    assert_eq!(
+                        classify_trigram(k1, k2, k3, &[], &strong),
+                        TrigramClass::from(category)
+                    );
+                    if is_roll_then_redirect(k1, k2, k3) {
+                        assert!(redirect);
+                    }
+                }
+            }
+        }
+
+        assert_eq!(seen.len(), TrigramCategory::COUNT);
+    }
+}
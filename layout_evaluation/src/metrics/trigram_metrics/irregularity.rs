@@ -6,29 +6,31 @@
 //! *Note:* ArneBab's irregularity does not include all bigram metrics (asymmetric bigrams is missing).
 
 use super::TrigramMetric;
-use crate::metrics::bigram_metrics::BigramMetric;
-use crate::results::NormalizationType;
+use crate::metrics::{
+    bigram_metrics::WeightedBigramMetric, format_utils::visualize_ngram, utils::to_percentage,
+    worst_ngrams::env_setting,
+};
 use keyboard_layout::layout::{LayerKey, Layout};
 
 use ordered_float::OrderedFloat;
 use priority_queue::DoublePriorityQueue;
 use serde::Deserialize;
-use std::env;
+use std::sync::Arc;
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {}
 
 #[derive(Clone, Debug)]
 pub struct Irregularity {
-    bigram_metrics: Vec<(f64, NormalizationType, Box<dyn BigramMetric>)>,
+    /// Shared by all clones of the metric
+    bigram_metrics: Arc<[WeightedBigramMetric]>,
 }
 
 impl Irregularity {
-    pub fn new(
-        bigram_metrics: Vec<(f64, NormalizationType, Box<dyn BigramMetric>)>,
-        _params: &Parameters,
-    ) -> Self {
-        Self { bigram_metrics }
+    pub fn new(bigram_metrics: Vec<WeightedBigramMetric>, _params: &Parameters) -> Self {
+        Self {
+            bigram_metrics: bigram_metrics.into(),
+        }
     }
 }
 
@@ -74,14 +76,8 @@ impl TrigramMetric for Irregularity {
         total_weight: Option<f64>,
         layout: &Layout,
     ) -> (f64, Option<String>) {
-        let show_worst: bool = env::var("SHOW_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(true);
-        let n_worst: usize = env::var("N_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(3);
+        let show_worst: bool = env_setting("SHOW_WORST").unwrap_or(true);
+        let n_worst: usize = env_setting("N_WORST").unwrap_or(3);
 
         // NOTE: ArneBab's solution does not involve all bigram metrics (the asymmetric bigrams metric is missing)
 
@@ -132,11 +128,9 @@ impl TrigramMetric for Irregularity {
                     .map(|(i, cost)| {
                         let (gram, _) = trigrams[i];
                         format!(
-                            "{}{}{} ({:>5.2}%)",
-                            gram.0,
-                            gram.1,
-                            gram.2,
-                            100.0 * cost.into_inner() / total_cost,
+                            "{} ({:>5.2}%)",
+                            visualize_ngram(&[gram.0, gram.1, gram.2]),
+                            to_percentage(cost.into_inner(), total_cost),
                         )
                     })
                     .collect();
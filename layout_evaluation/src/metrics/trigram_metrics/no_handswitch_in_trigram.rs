@@ -54,6 +54,10 @@ impl TrigramMetric for NoHandswitchInTrigram {
         "No Handswitch in Trigram"
     }
 
+    fn is_additive(&self) -> bool {
+        true
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
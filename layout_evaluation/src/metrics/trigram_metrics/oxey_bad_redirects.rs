@@ -1,8 +1,9 @@
 use super::TrigramMetric;
+use crate::metrics::inwards;
 
 use ahash::AHashSet;
 use keyboard_layout::{
-    key::{Finger, Hand},
+    key::Finger,
     layout::{LayerKey, Layout},
 };
 
@@ -32,20 +33,15 @@ impl OxeyBadRedirects {
     }
 }
 
-#[inline(always)]
-fn inwards(k1: &LayerKey, k2: &LayerKey) -> bool {
-    if k1.key.hand == Hand::Left {
-        k1.key.matrix_position.0 < k2.key.matrix_position.0
-    } else {
-        k1.key.matrix_position.0 > k2.key.matrix_position.0
-    }
-}
-
 impl TrigramMetric for OxeyBadRedirects {
     fn name(&self) -> &str {
         "Bad Redirects"
     }
 
+    fn is_additive(&self) -> bool {
+        true
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
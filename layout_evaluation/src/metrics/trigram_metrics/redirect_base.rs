@@ -1,70 +1,28 @@
 //! Base implementation for redirect-type metrics
 //!
 //! This module provides generic infrastructure for redirect metrics that:
-//! - Classify redirects (one-handed trigrams with direction changes)
-//! - Distinguish between normal redirects (with index/thumb) and weak redirects (without)
+//! - Count redirects (one-handed trigrams with direction changes, see
+//!   [`super::classification::classify_redirect`]), looked up in a
+//!   [`RedirectClassifier`] shared by all redirect metrics once precomputed
+//! - Distinguish between normal redirects (involving a strong finger, by default index or thumb)
+//!   and weak redirects (without)
 //! - Format output with consistent whitespace visualization and percentage display
 
-use super::TrigramMetric;
-use crate::metrics::format_utils::{format_percentages, visualize_whitespace};
+use super::{
+    classification::{classify_redirect, default_strong_fingers},
+    redirect_classifier::RedirectClassifier,
+    SharedTables, TrigramMetric,
+};
+use crate::metrics::{
+    format_utils::{format_applicable_weight, format_percentages, visualize_ngram},
+    worst_ngrams::{WorstParameters, WorstTracker},
+};
+use ahash::AHashMap;
 use keyboard_layout::{
-    key::{Finger, Hand},
+    key::{Finger, Hand, HandMap},
     layout::{LayerKey, Layout},
 };
-use ordered_float::OrderedFloat;
-use priority_queue::DoublePriorityQueue;
-use std::{env, fmt::Debug};
-
-#[inline(always)]
-fn inwards(k1: &LayerKey, k2: &LayerKey) -> bool {
-    if k1.key.hand == Hand::Left {
-        k1.key.matrix_position.0 < k2.key.matrix_position.0
-    } else {
-        k1.key.matrix_position.0 > k2.key.matrix_position.0
-    }
-}
-
-/// Check if a trigram is a redirect and whether it's weak
-/// Returns: (is_redirect, is_weak_redirect)
-pub fn classify_redirect(k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> (bool, bool) {
-    let h1 = k1.key.hand;
-    let h2 = k2.key.hand;
-    let h3 = k3.key.hand;
-
-    // Must be same hand (one-handed trigram)
-    if !(h1 == h2 && h2 == h3) {
-        return (false, false);
-    }
-
-    let f1 = k1.key.finger;
-    let f2 = k2.key.finger;
-    let f3 = k3.key.finger;
-
-    // Must use different fingers (no same-finger bigrams)
-    if f1 == f2 || f2 == f3 {
-        return (false, false);
-    }
-
-    let inwards1 = inwards(k1, k2);
-    let inwards2 = inwards(k2, k3);
-
-    let outwards1 = inwards(k2, k1);
-    let outwards2 = inwards(k3, k2);
-
-    // Check for direction change: inward->outward or outward->inward
-    let is_redirect = (inwards1 && outwards2) || (outwards1 && inwards2);
-
-    if !is_redirect {
-        return (false, false);
-    }
-
-    // Check if it's weak (no index finger or thumb)
-    let has_index_or_thumb = f1 == Finger::Index || f2 == Finger::Index || f3 == Finger::Index
-                          || f1 == Finger::Thumb || f2 == Finger::Thumb || f3 == Finger::Thumb;
-    let is_weak = !has_index_or_thumb;
-
-    (true, is_weak)
-}
+use std::fmt::Debug;
 
 /// Trait for filtering redirects based on weakness
 pub trait RedirectFilter: Clone + Debug + Send + Sync {
@@ -72,7 +30,7 @@ pub trait RedirectFilter: Clone + Debug + Send + Sync {
     fn should_count(&self, is_weak: bool) -> bool;
 }
 
-/// Filter for normal redirects (involving index/thumb)
+/// Filter for normal redirects (involving a strong finger)
 #[derive(Clone, Debug)]
 pub struct NormalRedirectFilter;
 
@@ -82,7 +40,7 @@ impl RedirectFilter for NormalRedirectFilter {
     }
 }
 
-/// Filter for weak redirects (not involving index/thumb)
+/// Filter for weak redirects (not involving a strong finger)
 #[derive(Clone, Debug)]
 pub struct WeakRedirectFilter;
 
@@ -97,9 +55,15 @@ impl RedirectFilter for WeakRedirectFilter {
 pub struct RedirectMetric<F: RedirectFilter> {
     name: &'static str,
     filter: F,
-    base_cost: f64,
+    /// Cost multiplier per hand (the base cost unless configured differently)
+    hand_costs: HandMap<f64>,
     ignore_thumbs: bool,
     ignore_modifiers: bool,
+    /// Fingers whose involvement makes a redirect a normal (not a weak) one
+    strong_fingers: Vec<Finger>,
+    worst: WorstParameters,
+    /// Redirect classifications of the keyboard's trigrams (see [`TrigramMetric::precompute`])
+    classifier: Option<RedirectClassifier>,
 }
 
 impl<F: RedirectFilter> RedirectMetric<F> {
@@ -113,16 +77,56 @@ impl<F: RedirectFilter> RedirectMetric<F> {
         Self {
             name,
             filter,
-            base_cost,
+            hand_costs: HandMap::with_default(base_cost),
             ignore_thumbs,
             ignore_modifiers,
+            strong_fingers: default_strong_fingers(),
+            worst: WorstParameters::default(),
+            classifier: None,
         }
     }
 
+    /// Configure which of the worst redirects are listed.
+    pub fn with_worst_parameters(mut self, worst: WorstParameters) -> Self {
+        self.worst = worst;
+        self
+    }
+
+    /// Configure which fingers make a redirect a normal (not a weak) one.
+    pub fn with_strong_fingers(mut self, strong_fingers: &[Finger]) -> Self {
+        self.strong_fingers = strong_fingers.to_vec();
+        self
+    }
+
+    /// Use hand-specific cost multipliers instead of the base cost for the given hands.
+    pub fn with_hand_costs(mut self, hand_costs: &AHashMap<Hand, f64>) -> Self {
+        for (hand, cost) in hand_costs {
+            self.hand_costs.set(hand, *cost);
+        }
+        self
+    }
+
     fn should_ignore_key(&self, key: &LayerKey) -> bool {
         (self.ignore_thumbs && key.key.finger == Finger::Thumb)
             || (self.ignore_modifiers && key.is_modifier.is_some())
     }
+
+    /// [`classify_redirect`], looked up in the precomputed classifications if possible
+    #[inline(always)]
+    fn classify(&self, k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> (bool, bool) {
+        match &self.classifier {
+            Some(classifier) => classifier.classify(k1, k2, k3, &self.strong_fingers),
+            None => classify_redirect(k1, k2, k3, &self.strong_fingers),
+        }
+    }
+
+    /// Whether the metric applies to the trigram at all, i.e. none of its keys is ignored and
+    /// all are on the same hand (otherwise, it can not be a redirect)
+    fn is_applicable(&self, k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> bool {
+        !(self.should_ignore_key(k1) || self.should_ignore_key(k2) || self.should_ignore_key(k3))
+            && k1.key.hand == k2.key.hand
+            && k2.key.hand == k3.key.hand
+    }
 }
 
 impl<F: RedirectFilter + 'static> TrigramMetric for RedirectMetric<F> {
@@ -140,18 +144,22 @@ impl<F: RedirectFilter + 'static> TrigramMetric for RedirectMetric<F> {
         _total_weight: f64,
         _layout: &Layout,
     ) -> Option<f64> {
-        // Skip if any key should be ignored
-        if self.should_ignore_key(k1) || self.should_ignore_key(k2) || self.should_ignore_key(k3) {
-            return Some(0.0);
+        if !self.is_applicable(k1, k2, k3) {
+            return None;
         }
 
-        let (is_redirect, is_weak) = classify_redirect(k1, k2, k3);
+        let (is_redirect, is_weak) = self.classify(k1, k2, k3);
 
         if !is_redirect || !self.filter.should_count(is_weak) {
             return Some(0.0);
         }
 
-        Some(weight * self.base_cost)
+        // all keys of a redirect are on the same hand
+        Some(weight * self.hand_costs.get(&k1.key.hand))
+    }
+
+    fn precompute(&mut self, tables: &mut SharedTables) {
+        self.classifier = Some(tables.redirect_classifier());
     }
 
     fn total_cost(
@@ -160,18 +168,11 @@ impl<F: RedirectFilter + 'static> TrigramMetric for RedirectMetric<F> {
         total_weight: Option<f64>,
         layout: &Layout,
     ) -> (f64, Option<String>) {
-        let show_worst: bool = env::var("SHOW_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(true);
-        let n_worst: usize = env::var("N_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(3);
+        let mut worst = WorstTracker::new(self.worst.selection());
 
         let total_weight = total_weight.unwrap_or_else(|| trigrams.iter().map(|(_, w)| w).sum());
 
-        if !show_worst {
+        if !worst.is_enabled() {
             let total_cost: f64 = trigrams
                 .iter()
                 .filter_map(|(trigram, weight)| {
@@ -189,55 +190,235 @@ impl<F: RedirectFilter + 'static> TrigramMetric for RedirectMetric<F> {
         }
 
         // Track worst redirects
-        let mut worst_queue: DoublePriorityQueue<usize, OrderedFloat<f64>> =
-            DoublePriorityQueue::new();
         let mut total_cost = 0.0;
+        let mut applicable_weight = 0.0;
+        let mut all_applicable = true;
 
         for (i, (trigram, weight)) in trigrams.iter().enumerate() {
-            // Skip if any key should be ignored
-            if self.should_ignore_key(trigram.0)
-                || self.should_ignore_key(trigram.1)
-                || self.should_ignore_key(trigram.2)
-            {
+            if !self.is_applicable(trigram.0, trigram.1, trigram.2) {
+                all_applicable = false;
                 continue;
             }
+            applicable_weight += weight;
 
-            let (is_redirect, is_weak) = classify_redirect(trigram.0, trigram.1, trigram.2);
+            let (is_redirect, is_weak) = self.classify(trigram.0, trigram.1, trigram.2);
 
             if !is_redirect || !self.filter.should_count(is_weak) {
                 continue;
             }
 
-            let cost = weight * self.base_cost;
+            let cost = weight * self.hand_costs.get(&trigram.0.key.hand);
             total_cost += cost;
 
-            worst_queue.push(i, OrderedFloat(cost));
-
-            if worst_queue.len() > n_worst {
-                worst_queue.pop_min();
-            }
+            worst.push(i, cost);
         }
 
-        let worst_msgs: Vec<String> = worst_queue
-            .into_sorted_iter()
-            .rev()
-            .filter(|(_, cost)| cost.into_inner() > 0.0)
+        let worst_msgs: Vec<String> = worst
+            .into_sorted(total_cost)
+            .into_iter()
             .map(|(i, cost)| {
                 let (gram, weight) = trigrams[i];
-                let freq_pct = 100.0 * weight / total_weight;
-                let cost_pct = 100.0 * cost.into_inner() / total_cost;
-                let percentages = format_percentages(cost_pct, freq_pct);
-                let trigram_str = format!("{}{}{}", gram.0, gram.1, gram.2);
-                format!("{} {}", visualize_whitespace(&trigram_str), percentages)
+                let percentages = format_percentages(cost, total_cost, weight, applicable_weight);
+                format!(
+                    "{} {}",
+                    visualize_ngram(&[gram.0, gram.1, gram.2]),
+                    percentages
+                )
             })
             .collect();
 
-        let msg = if worst_msgs.is_empty() {
+        let mut msg_parts = Vec::new();
+        if !all_applicable {
+            msg_parts.push(format_applicable_weight(applicable_weight, total_weight));
+        }
+        if !worst_msgs.is_empty() {
+            msg_parts.push(worst_msgs.join(", "));
+        }
+
+        let msg = if msg_parts.is_empty() {
             None
         } else {
-            Some(worst_msgs.join(", "))
+            Some(msg_parts.join("; "))
         };
 
         (total_cost, msg)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_layout};
+
+    /// Costs of the redirects index → pinky → middle on the left ("rcm") and the right hand
+    /// ("wLB"), each with weight 1.0
+    fn total_cost_message(left_cost: f64, right_cost: f64) -> (f64, String) {
+        let layout = toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP");
+        total_cost_message_of(&layout, "rcm", left_cost, right_cost)
+    }
+
+    fn total_cost_message_of(
+        layout: &Layout,
+        left: &str,
+        left_cost: f64,
+        right_cost: f64,
+    ) -> (f64, String) {
+        let worst = WorstParameters {
+            n_worst: Some(3),
+            worst_threshold_pct: None,
+        };
+        total_cost_message_with(layout, left, left_cost, right_cost, worst)
+    }
+
+    fn total_cost_message_with(
+        layout: &Layout,
+        left: &str,
+        left_cost: f64,
+        right_cost: f64,
+        worst: WorstParameters,
+    ) -> (f64, String) {
+        let left = layerkeys(layout, left);
+        let right = layerkeys(layout, "wLB");
+        let trigrams = [
+            ((left[0], left[1], left[2]), 1.0),
+            ((right[0], right[1], right[2]), 1.0),
+        ];
+
+        let hand_costs: AHashMap<Hand, f64> = [(Hand::Left, left_cost), (Hand::Right, right_cost)]
+            .iter()
+            .copied()
+            .collect();
+        let metric = RedirectMetric::new("Redirects", NormalRedirectFilter, 1.0, true, true)
+            .with_hand_costs(&hand_costs)
+            .with_worst_parameters(worst);
+        let (cost, message) = metric.total_cost(&trigrams, None, layout);
+        (cost, message.unwrap())
+    }
+
+    #[test]
+    fn worst_redirects_of_zero_total_cost() {
+        let (cost, message) = total_cost_message(1.0, -1.0);
+        assert_eq!(cost, 0.0);
+        assert!(message.contains("rcm") && message.contains("0.0%|50.00%"));
+        assert!(!message.contains("NaN") && !message.contains("inf"));
+    }
+
+    #[test]
+    fn worst_redirects_of_negative_total_cost() {
+        let (cost, message) = total_cost_message(1.0, -2.0);
+        assert_eq!(cost, -1.0);
+        assert!(message.contains("rcm") && message.contains("0.0%|50.00%"));
+        assert!(!message.contains("NaN") && !message.contains("-"));
+    }
+
+    #[test]
+    fn worst_redirects_stay_on_one_line() {
+        // a tab on the left index finger
+        let layout = toy_layout("abcdefghijklmnopq\tstuvwxyzABCDEFGHIJKLMNOP");
+        let (_, message) = total_cost_message_of(&layout, "\tcm", 1.0, 0.0);
+        assert!(message.contains("⇥cm"), "{}", message);
+        assert!(!message.contains('\t') && !message.contains('\n'));
+    }
+
+    #[test]
+    fn worst_redirects_above_threshold() {
+        let layout = toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP");
+        let worst = WorstParameters {
+            n_worst: None,
+            worst_threshold_pct: Some(50.0),
+        };
+        let (cost, message) = total_cost_message_with(&layout, "rcm", 3.0, 1.0, worst);
+        assert_eq!(cost, 4.0);
+        assert!(message.contains("rcm") && message.contains("75.0%"));
+        assert!(!message.contains("wLB"), "{}", message);
+    }
+
+    #[test]
+    fn worst_redirects_relate_to_the_applicable_weight() {
+        let layout = toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP");
+        let rcm = layerkeys(&layout, "rcm");
+        // not on one hand
+        let rcw = layerkeys(&layout, "rcw");
+        let trigrams = [
+            ((rcm[0], rcm[1], rcm[2]), 1.0),
+            ((rcw[0], rcw[1], rcw[2]), 1.0),
+        ];
+
+        let metric = RedirectMetric::new("Redirects", NormalRedirectFilter, 1.0, true, true)
+            .with_worst_parameters(WorstParameters {
+                n_worst: Some(3),
+                worst_threshold_pct: None,
+            });
+        let (cost, message) = metric.total_cost(&trigrams, None, &layout);
+        let message = message.unwrap();
+
+        assert_eq!(cost, 1.0);
+        assert!(message.starts_with("applicable: 50.0%"), "{}", message);
+        assert!(message.contains("rcm") && message.contains("100.0%|100.00%"));
+    }
+
+    #[test]
+    fn strong_fingers_make_redirects_normal() {
+        let layout = toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP");
+        // ring → pinky → middle
+        let keys = layerkeys(&layout, "hcm");
+        let cost = |metric: &dyn TrigramMetric| {
+            metric.individual_cost(keys[0], keys[1], keys[2], 1.0, 1.0, &layout)
+        };
+        let normal = RedirectMetric::new("Redirects", NormalRedirectFilter, 1.0, true, true);
+        let weak = RedirectMetric::new("Weak Redirect", WeakRedirectFilter, 1.0, true, true);
+
+        assert_eq!(cost(&normal), Some(0.0));
+        assert_eq!(cost(&weak), Some(1.0));
+
+        let strong_fingers = [Finger::Index, Finger::Middle];
+        assert_eq!(
+            cost(&normal.with_strong_fingers(&strong_fingers)),
+            Some(1.0)
+        );
+        assert_eq!(cost(&weak.with_strong_fingers(&strong_fingers)), Some(0.0));
+    }
+
+    #[test]
+    fn precomputed_costs_are_identical() {
+        let layout = toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP");
+        let keys = layerkeys(&layout, "abcdefghijklmnopqrstuvO");
+        let metrics: Vec<Box<dyn TrigramMetric>> = vec![
+            Box::new(RedirectMetric::new(
+                "Redirects",
+                NormalRedirectFilter,
+                1.0,
+                false,
+                true,
+            )),
+            Box::new(
+                RedirectMetric::new("Weak Redirect", WeakRedirectFilter, 1.0, false, true)
+                    .with_strong_fingers(&[Finger::Middle]),
+            ),
+        ];
+
+        let mut tables = SharedTables::new(&layout.keyboard);
+        let precomputed: Vec<Box<dyn TrigramMetric>> = metrics
+            .iter()
+            .map(|metric| {
+                let mut metric = metric.clone();
+                metric.precompute(&mut tables);
+                metric
+            })
+            .collect();
+
+        for (metric, precomputed) in metrics.iter().zip(precomputed.iter()) {
+            for k1 in keys.iter() {
+                for k2 in keys.iter() {
+                    for k3 in keys.iter() {
+                        assert_eq!(
+                            precomputed.individual_cost(k1, k2, k3, 1.0, 1.0, &layout),
+                            metric.individual_cost(k1, k2, k3, 1.0, 1.0, &layout),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
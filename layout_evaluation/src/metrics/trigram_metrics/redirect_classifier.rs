@@ -0,0 +1,177 @@
+//! Redirect classifications of all trigrams of keys of a keyboard, shared by the
+//! [`Redirects`](super::redirects::Redirects) and [`WeakRedirect`](super::weak_redirect::WeakRedirect)
+//! metrics (see [`SharedTables`]), so that a trigram is classified only once when precomputing
+//! instead of by each metric for every trigram.
+
+use super::classification::classify_redirect;
+
+use keyboard_layout::{
+    key::Finger,
+    keyboard::{Keyboard, KeyboardId},
+    layout::{LayerKey, LayerModifierType, LayerModifiers},
+};
+
+use std::sync::Arc;
+
+/// Whether the trigrams of keys of one keyboard are redirects (see [`classify_redirect`]),
+/// indexed by the [`Key::index`](keyboard_layout::key::Key::index) of the three keys. Clones
+/// share the classifications.
+#[derive(Clone, Debug)]
+pub struct RedirectClassifier {
+    keyboard_id: KeyboardId,
+    n_keys: usize,
+    is_redirect: Arc<[bool]>,
+}
+
+impl RedirectClassifier {
+    /// Classify all trigrams of keys of `keyboard`. Whether a trigram is a redirect only
+    /// depends on the physical keys, so [`LayerKey`]s on their base layer are classified.
+    pub fn new(keyboard: &Keyboard) -> Self {
+        let layerkeys: Vec<LayerKey> = keyboard
+            .keys
+            .iter()
+            .map(|key| {
+                LayerKey::new(
+                    0,
+                    key.clone(),
+                    '\0',
+                    LayerModifiers::default(),
+                    false,
+                    LayerModifierType::None,
+                )
+            })
+            .collect();
+
+        let mut is_redirect = Vec::with_capacity(layerkeys.len().pow(3));
+        for k1 in layerkeys.iter() {
+            for k2 in layerkeys.iter() {
+                for k3 in layerkeys.iter() {
+                    is_redirect.push(classify_redirect(k1, k2, k3, &[]).0);
+                }
+            }
+        }
+
+        Self {
+            keyboard_id: keyboard.id(),
+            n_keys: layerkeys.len(),
+            is_redirect: is_redirect.into(),
+        }
+    }
+
+    /// Same as [`classify_redirect`], but looked up for keys of the keyboard the
+    /// classifications were computed for (other keys are classified directly).
+    #[inline(always)]
+    pub fn classify(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        k3: &LayerKey,
+        strong_fingers: &[Finger],
+    ) -> (bool, bool) {
+        let is_redirect = match self.get(k1, k2, k3) {
+            Some(is_redirect) => is_redirect,
+            None => return classify_redirect(k1, k2, k3, strong_fingers),
+        };
+
+        let is_weak = is_redirect
+            && ![k1, k2, k3]
+                .iter()
+                .any(|k| strong_fingers.contains(&k.key.finger));
+
+        (is_redirect, is_weak)
+    }
+
+    #[inline(always)]
+    fn get(&self, k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> Option<bool> {
+        let keys = [&k1.key, &k2.key, &k3.key];
+        if keys
+            .iter()
+            .any(|k| k.keyboard_id != self.keyboard_id || k.index as usize >= self.n_keys)
+        {
+            return None;
+        }
+
+        let (i1, i2, i3) = (
+            k1.key.index as usize,
+            k2.key.index as usize,
+            k3.key.index as usize,
+        );
+        Some(self.is_redirect[(i1 * self.n_keys + i2) * self.n_keys + i3])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::metrics::trigram_metrics::SharedTables;
+
+    use keyboard_layout::{
+        key::{Direction, Hand},
+        test_utils::{key, layerkeys, toy_layout},
+    };
+
+    #[test]
+    fn classifications_are_identical() {
+        let layout = toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP");
+        let classifier = RedirectClassifier::new(&layout.keyboard);
+        let keys = layerkeys(&layout, "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP");
+        let strong_fingers = [
+            vec![],
+            vec![Finger::Index, Finger::Thumb],
+            vec![Finger::Ring],
+        ];
+
+        let mut n_redirects = 0;
+        for k1 in keys.iter() {
+            for k2 in keys.iter() {
+                for k3 in keys.iter() {
+                    assert!(classifier.get(k1, k2, k3).is_some());
+                    for strong in strong_fingers.iter() {
+                        let classification = classify_redirect(k1, k2, k3, strong);
+                        assert_eq!(classifier.classify(k1, k2, k3, strong), classification);
+                        if classification.0 {
+                            n_redirects += 1;
+                        }
+                    }
+                }
+            }
+        }
+        assert!(n_redirects > 0);
+    }
+
+    #[test]
+    fn classifications_are_shared() {
+        let layout = toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP");
+        let mut tables = SharedTables::new(&layout.keyboard);
+        let first = tables.redirect_classifier();
+        let second = tables.redirect_classifier();
+
+        assert!(Arc::ptr_eq(&first.is_redirect, &second.is_redirect));
+        assert_eq!(first.keyboard_id, layout.keyboard.id());
+    }
+
+    #[test]
+    fn keys_of_other_keyboards_are_classified_directly() {
+        let layout = toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP");
+        let other = toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP");
+        let classifier = RedirectClassifier::new(&layout.keyboard);
+        // ring → pinky → middle
+        let keys = layerkeys(&other, "hcm");
+        let strong = [Finger::Index];
+
+        assert_eq!(classifier.get(keys[0], keys[1], keys[2]), None);
+        assert_eq!(
+            classifier.classify(keys[0], keys[1], keys[2], &strong),
+            (true, true)
+        );
+
+        // a key of the keyboard, but not among the classified ones
+        let mut stray = key(Hand::Left, Finger::Middle, Direction::Center)
+            .index(200)
+            .layerkey('x');
+        stray.key.keyboard_id = layout.keyboard.id();
+        let keys = layerkeys(&layout, "hc");
+        assert_eq!(classifier.get(keys[0], keys[1], &stray), None);
+    }
+}
@@ -1,19 +1,38 @@
 //! Redirect metric that penalizes redirects (excluding weak redirects).
 //! A redirect is a one-handed trigram with a direction change (e.g., inward->outward or outward->inward)
-//! that involves the index finger or thumb.
+//! that involves a strong finger (`strong_fingers`, by default the index finger or thumb).
 
-use super::{redirect_base::{NormalRedirectFilter, RedirectMetric}, TrigramMetric};
-use keyboard_layout::layout::{LayerKey, Layout};
+use super::{
+    classification::default_strong_fingers,
+    redirect_base::{NormalRedirectFilter, RedirectMetric},
+    SharedTables, TrigramMetric,
+};
+use crate::metrics::worst_ngrams::WorstParameters;
+use ahash::AHashMap;
+use keyboard_layout::{
+    key::{Finger, Hand},
+    layout::{LayerKey, Layout},
+};
 use serde::Deserialize;
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
     /// Base cost multiplier for each redirect. Default: 1.0
     pub base_cost: Option<f64>,
+    /// Cost multiplier per hand, replacing `base_cost` for the listed hands
+    #[serde(default)]
+    pub hand_costs: AHashMap<Hand, f64>,
     /// Ignore redirects involving thumb keys. Default: true
     pub ignore_thumbs: Option<bool>,
     /// Ignore redirects involving modifier keys. Default: true
     pub ignore_modifiers: Option<bool>,
+    /// Fingers whose involvement makes a redirect a normal (not a weak) one.
+    /// Default: [Index, Thumb]
+    #[serde(default = "default_strong_fingers")]
+    pub strong_fingers: Vec<Finger>,
+    /// Which of the worst ngrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
 }
 
 #[derive(Clone, Debug)]
@@ -30,7 +49,10 @@ impl Redirects {
                 params.base_cost.unwrap_or(1.0),
                 params.ignore_thumbs.unwrap_or(true),
                 params.ignore_modifiers.unwrap_or(true),
-            ),
+            )
+            .with_strong_fingers(&params.strong_fingers)
+            .with_worst_parameters(params.worst)
+            .with_hand_costs(&params.hand_costs),
         }
     }
 }
@@ -53,6 +75,10 @@ impl TrigramMetric for Redirects {
         self.inner.individual_cost(k1, k2, k3, weight, total_weight, layout)
     }
 
+    fn precompute(&mut self, tables: &mut SharedTables) {
+        self.inner.precompute(tables)
+    }
+
     fn total_cost(
         &self,
         trigrams: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
@@ -0,0 +1,139 @@
+//! Roll redirect metric that penalizes rolls ending in a redirect ("bad roll endings").
+//! The first two keys roll across different fingers of one hand, then the third key on the same
+//! hand reverses the lateral direction and lands on yet another finger, breaking the flow of an
+//! otherwise good roll. Redirects returning to the first finger are not counted.
+//!
+//! *Note:* Every roll redirect is also a redirect, i.e. it is counted by [`super::redirects`] or
+//! [`super::weak_redirect`] (and as "Redirect" in [`super::trigram_stats`]) as well. The cost of
+//! this metric comes on top of those.
+//!
+//! ## Parameters
+//! - `base_cost`: Cost of each roll redirect (default 1.0)
+//! - `finger_factors`: Multipliers for the pivot finger, i.e. the one of the second key
+//! - `ignore_thumbs` / `ignore_modifiers`: Skip trigrams involving thumbs / modifiers (default true)
+//! - `n_worst` / `worst_threshold_pct`: Which of the worst trigrams to list
+
+use super::{classification::is_roll_then_redirect, TrigramMetric};
+use crate::metrics::{
+    format_utils::{format_percentages, visualize_ngram},
+    worst_ngrams::{WorstParameters, WorstTracker},
+    FingerFactors,
+};
+
+use keyboard_layout::{
+    key::{Finger, FingerMap},
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Base cost multiplier for each roll redirect. Default: 1.0
+    pub base_cost: Option<f64>,
+    /// Multipliers for the pivot finger (second key)
+    #[serde(default)]
+    pub finger_factors: FingerFactors,
+    /// Ignore trigrams involving thumb keys. Default: true
+    pub ignore_thumbs: Option<bool>,
+    /// Ignore trigrams involving modifier keys. Default: true
+    pub ignore_modifiers: Option<bool>,
+    /// Which of the worst ngrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
+}
+
+#[derive(Clone, Debug)]
+pub struct RollRedirect {
+    base_cost: f64,
+    finger_factors: FingerMap<f64>,
+    ignore_thumbs: bool,
+    ignore_modifiers: bool,
+    worst: WorstParameters,
+}
+
+impl RollRedirect {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            base_cost: params.base_cost.unwrap_or(1.0),
+            finger_factors: params.finger_factors.to_finger_map(),
+            ignore_thumbs: params.ignore_thumbs.unwrap_or(true),
+            ignore_modifiers: params.ignore_modifiers.unwrap_or(true),
+            worst: params.worst,
+        }
+    }
+
+    fn should_ignore_key(&self, key: &LayerKey) -> bool {
+        (self.ignore_thumbs && key.key.finger == Finger::Thumb)
+            || (self.ignore_modifiers && key.is_modifier.is_some())
+    }
+}
+
+impl TrigramMetric for RollRedirect {
+    fn name(&self) -> &str {
+        "Roll Redirect"
+    }
+
+    #[inline(always)]
+    fn individual_cost(
+        &self,
+        k1: &LayerKey,
+        k2: &LayerKey,
+        k3: &LayerKey,
+        weight: f64,
+        _total_weight: f64,
+        _layout: &Layout,
+    ) -> Option<f64> {
+        if self.should_ignore_key(k1) || self.should_ignore_key(k2) || self.should_ignore_key(k3) {
+            return Some(0.0);
+        }
+
+        if !is_roll_then_redirect(k1, k2, k3) {
+            return Some(0.0);
+        }
+
+        Some(weight * self.base_cost * *self.finger_factors.get(&k2.key.finger))
+    }
+
+    fn total_cost(
+        &self,
+        trigrams: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let mut worst = WorstTracker::new(self.worst.selection());
+
+        let total_weight = total_weight.unwrap_or_else(|| trigrams.iter().map(|(_, w)| w).sum());
+
+        let mut total_cost = 0.0;
+        for (i, ((k1, k2, k3), weight)) in trigrams.iter().enumerate() {
+            let cost = self
+                .individual_cost(k1, k2, k3, *weight, total_weight, layout)
+                .unwrap_or(0.0);
+            total_cost += cost;
+            worst.push(i, cost);
+        }
+
+        let worst_msgs: Vec<String> = worst
+            .into_sorted(total_cost)
+            .into_iter()
+            .map(|(i, cost)| {
+                let (gram, weight) = trigrams[i];
+                let percentages = format_percentages(cost, total_cost, weight, total_weight);
+                format!(
+                    "{} {}",
+                    visualize_ngram(&[gram.0, gram.1, gram.2]),
+                    percentages
+                )
+            })
+            .collect();
+
+        let msg = if worst_msgs.is_empty() {
+            None
+        } else {
+            Some(worst_msgs.join(", "))
+        };
+
+        (total_cost, msg)
+    }
+}
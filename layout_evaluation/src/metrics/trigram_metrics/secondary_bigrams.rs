@@ -3,13 +3,12 @@
 //! individual bigrams (`individual_cost` does not return `None`).
 
 use super::TrigramMetric;
-use crate::metrics::bigram_metrics::BigramMetric;
-
-use crate::results::NormalizationType;
+use crate::metrics::bigram_metrics::WeightedBigramMetric;
 
 use keyboard_layout::layout::{LayerKey, Layout};
 
 use serde::Deserialize;
+use std::sync::Arc;
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
@@ -23,19 +22,17 @@ pub struct Parameters {
 
 #[derive(Clone, Debug)]
 pub struct SecondaryBigrams {
-    bigram_metrics: Vec<(f64, NormalizationType, Box<dyn BigramMetric>)>,
+    /// Shared by all clones of the metric
+    bigram_metrics: Arc<[WeightedBigramMetric]>,
     factor_no_handswitch: f64,
     factor_handswitch: f64,
     initial_pause_indicators: Vec<char>,
 }
 
 impl SecondaryBigrams {
-    pub fn new(
-        bigram_metrics: Vec<(f64, NormalizationType, Box<dyn BigramMetric>)>,
-        params: &Parameters,
-    ) -> Self {
+    pub fn new(bigram_metrics: Vec<WeightedBigramMetric>, params: &Parameters) -> Self {
         Self {
-            bigram_metrics,
+            bigram_metrics: bigram_metrics.into(),
             factor_no_handswitch: params.factor_no_handswitch,
             factor_handswitch: params.factor_handswitch,
             initial_pause_indicators: params.initial_pause_indicators.clone(),
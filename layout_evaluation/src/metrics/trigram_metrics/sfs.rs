@@ -1,12 +1,23 @@
 //! SFS (Same Finger Skipgram) metric that evaluates skipgrams (k1_k3 patterns).
 //! A skipgram is a sequence of two keystrokes separated by one keystroke.
 //! For example, in "mouse", m_u, o_s, and u_e are skipgrams.
+//!
+//! As for SFBs, skipgrams above `critical_bigram.fraction` of the total trigram weight
+//! can be penalized with the additional `critical_bigram.factor`.
+//!
+//! Which skipgrams are skipped is configured with the options of
+//! [`crate::metrics::same_finger_filter`], shared with the SFS share of
+//! [`super::trigram_stats::TrigramStats`].
 
 use super::TrigramMetric;
+use crate::metrics::{
+    bigram_metrics::CriticalBigramParams,
+    same_finger_filter::{self, SameFingerFilter},
+    FingerFactors,
+};
 
-use ahash::AHashMap;
 use keyboard_layout::{
-    key::Finger,
+    key::FingerMap,
     layout::{LayerKey, Layout},
 };
 
@@ -14,24 +25,30 @@ use serde::Deserialize;
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
-    pub ignore_thumbs: bool,
-    pub ignore_modifiers: Option<bool>,
-    pub finger_factors: Option<AHashMap<Finger, f64>>,
+    /// Which skipgrams are skipped
+    #[serde(flatten)]
+    pub filter: same_finger_filter::Parameters,
+    #[serde(default)]
+    pub finger_factors: FingerFactors,
+    /// Heavier penalty for frequent skipgrams (`fraction` of the total trigram weight and
+    /// `factor`)
+    #[serde(default)]
+    pub critical_bigram: Option<CriticalBigramParams>,
 }
 
 #[derive(Clone, Debug)]
 pub struct Sfs {
-    ignore_thumbs: bool,
-    ignore_modifiers: bool,
-    finger_factors: Option<AHashMap<Finger, f64>>,
+    filter: SameFingerFilter,
+    finger_factors: FingerMap<f64>,
+    critical_bigram: Option<CriticalBigramParams>,
 }
 
 impl Sfs {
     pub fn new(params: &Parameters) -> Self {
         Self {
-            ignore_thumbs: params.ignore_thumbs,
-            ignore_modifiers: params.ignore_modifiers.unwrap_or(false),
-            finger_factors: params.finger_factors.clone(),
+            filter: SameFingerFilter::new(&params.filter),
+            finger_factors: params.finger_factors.to_finger_map(),
+            critical_bigram: params.critical_bigram,
         }
     }
 }
@@ -41,6 +58,10 @@ impl TrigramMetric for Sfs {
         "SFS"
     }
 
+    fn is_additive(&self) -> bool {
+        self.critical_bigram.is_none()
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
@@ -48,41 +69,23 @@ impl TrigramMetric for Sfs {
         _k2: &LayerKey,
         k3: &LayerKey,
         weight: f64,
-        _total_weight: f64,
+        total_weight: f64,
         _layout: &Layout,
     ) -> Option<f64> {
-        // Skip modifiers if configured
-        if self.ignore_modifiers && (k1.is_modifier.is_some() || k3.is_modifier.is_some()) {
-            return Some(0.0);
-        }
-
-        // Skip same-key repeats (e.g., holding a modifier)
-        if k1 == k3 {
+        // Not an SFS or skipped (modifiers, thumbs, repeats, ignored movements)
+        if !self.filter.is_same_finger(k1, k3) {
             return Some(0.0);
         }
 
-        // Different hands - not an SFS
-        if k1.key.hand != k3.key.hand {
-            return Some(0.0);
-        }
+        let finger = k1.key.finger;
+        let finger_multiplier = *self.finger_factors.get(&finger);
 
-        // Different fingers - not an SFS
-        if k1.key.finger != k3.key.finger {
-            return Some(0.0);
-        }
+        // Apply frequency-based multiplier if configured
+        let frequency_multiplier = self.critical_bigram.map_or(1.0, |critical_bigram| {
+            critical_bigram.multiplier(weight, total_weight)
+        });
 
-        // Skip thumbs if configured
-        if self.ignore_thumbs && k1.key.finger == Finger::Thumb {
-            return Some(0.0);
-        }
-
-        let finger = k1.key.finger;
-        let finger_multiplier = self
-            .finger_factors
-            .as_ref()
-            .and_then(|factors| factors.get(&finger).copied())
-            .unwrap_or(1.0);
-        let cost = weight * finger_multiplier;
+        let cost = weight * finger_multiplier * frequency_multiplier;
 
         Some(cost)
     }
@@ -39,6 +39,10 @@ impl TrigramMetric for TrigramRolls {
         "Trigram Rolls"
     }
 
+    fn is_additive(&self) -> bool {
+        true
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
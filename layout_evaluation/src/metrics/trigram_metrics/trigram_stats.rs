@@ -1,287 +1,154 @@
-use super::TrigramMetric;
+use super::{
+    classification::{
+        bigram_position, classify_trigram, default_strong_fingers, BigramPosition, TrigramCategory,
+        TrigramClass,
+    },
+    TrigramMetric,
+};
+use crate::{
+    metrics::{
+        same_finger_filter::{self, SameFingerFilter},
+        utils::{movement_label, MovementPattern},
+    },
+    output_style::underline,
+};
 
-use colored::Colorize;
+use ahash::AHashMap;
 use keyboard_layout::{
-    key::{Direction, Finger, Hand},
+    key::Finger,
     layout::{LayerKey, Layout},
 };
 
-use serde::Deserialize;
-use std::collections::HashMap;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum TrigramCategory {
-    BigramRollIn,
-    BigramRollOut,
-    RollIn,
-    RollOut,
-    Alternation,
-    Redirect,
-    WeakRedirect,
-    Other,
+use serde::{Deserialize, Serialize};
+
+/// Shares of the trigram categories in percent of the trigram weight selected by
+/// [`FractionBase`] (except for `sfs_percent`, which is always relative to the total trigram
+/// weight)
+///
+/// The categories are mutually exclusive: with [`FractionBase::ValidTrigrams`], the shares of
+/// bigram rolls (total), rolls in and out, alternations, redirects, and others sum up to 100%.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrigramStatistics {
+    /// Bigram rolls including the configured same-finger rolls
+    pub bigram_roll_total_percent: f64,
+    pub bigram_roll_in_percent: f64,
+    pub bigram_roll_out_percent: f64,
+    /// Bigram rolls (of the total) on the first two keys, i.e. rolls into a hand switch
+    pub bigram_roll_leading_percent: f64,
+    /// Bigram rolls (of the total) on the last two keys, i.e. rolls after a hand switch
+    pub bigram_roll_trailing_percent: f64,
+    /// Configured same-finger rolls in the order of `same_finger_rolls` (not part of
+    /// `other_percent`)
+    pub same_finger_roll_percents: Vec<(MovementPattern, f64)>,
+    pub roll_in_percent: f64,
+    pub roll_out_percent: f64,
+    pub alternation_percent: f64,
+    /// Redirects including weak redirects (and the roll redirects of
+    /// [`super::roll_redirect`])
+    pub redirect_percent: f64,
+    pub weak_redirect_percent: f64,
+    pub other_percent: f64,
+    pub sfs_percent: f64,
+}
+
+/// The trigram weight the shares of the categories are relative to
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Eq)]
+pub enum FractionBase {
+    /// The weight of the trigrams without ignored keys (the default)
+    ValidTrigrams,
+    /// The weight of all trigrams (like the SFS share), such that the shares are comparable
+    /// between layouts placing the ignored keys differently
+    AllTrigrams,
+}
+
+impl Default for FractionBase {
+    fn default() -> Self {
+        FractionBase::ValidTrigrams
+    }
 }
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
-    pub ignore_modifiers: bool,
-    pub ignore_thumbs: bool,
-    /// List of same-finger movements to track separately within bigram rolls (e.g., [[Center, South], [In, South]])
+    /// Which trigrams are skipped (`ignore_thumbs` and the modifier options apply to all
+    /// categories, the others to the SFS share only, like for [`super::sfs::Sfs`])
+    #[serde(flatten)]
+    pub filter: same_finger_filter::Parameters,
+    /// List of same-finger movements to track separately within bigram rolls (e.g., [[Center, South], [In, South]]).
+    /// A missing direction (`null`) matches any direction, e.g. [null, South] tracks all
+    /// movements ending in `South`. Each movement counts for the first matching entry only.
     #[serde(default = "default_same_finger_rolls")]
-    pub same_finger_rolls: Vec<(Direction, Direction)>,
+    pub same_finger_rolls: Vec<MovementPattern>,
+    /// Cost per unit of trigram weight of each category (e.g. `Redirect: 0.5`, `RollIn: -0.3`).
+    /// Without it, the metric is informational only and costs nothing. Trigrams with a
+    /// configured same-finger roll belong to none of the categories and cost nothing.
+    #[serde(default)]
+    pub category_costs: Option<AHashMap<TrigramCategory, f64>>,
+    /// The trigram weight the shares of the categories are relative to
+    #[serde(default)]
+    pub fraction_of: FractionBase,
+    /// Fingers whose involvement makes a redirect a normal (not a weak) one.
+    /// Default: [Index, Thumb]
+    #[serde(default = "default_strong_fingers")]
+    pub strong_fingers: Vec<Finger>,
 }
 
-fn default_same_finger_rolls() -> Vec<(Direction, Direction)> {
+fn default_same_finger_rolls() -> Vec<MovementPattern> {
     vec![]
 }
 
 #[derive(Clone, Debug)]
 pub struct TrigramStats {
-    ignore_modifiers: bool,
-    ignore_thumbs: bool,
-    same_finger_rolls: Vec<(Direction, Direction)>,
+    filter: SameFingerFilter,
+    same_finger_rolls: Vec<MovementPattern>,
+    category_costs: Option<AHashMap<TrigramCategory, f64>>,
+    fraction_of: FractionBase,
+    strong_fingers: Vec<Finger>,
+}
+
+/// Accumulated trigram weights the statistics are computed from
+struct TrigramWeights {
+    /// Indexed by `category as usize`
+    category_weights: [f64; TrigramCategory::COUNT],
+    /// Indexed like `same_finger_rolls`
+    same_finger_roll_weights: Vec<f64>,
+    /// Weights of the bigram rolls (including same-finger rolls) per position
+    leading_bigram_rolls_weight: f64,
+    trailing_bigram_rolls_weight: f64,
+    weak_redirects_weight: f64,
+    sfs_weight: f64,
+    valid_trigrams_weight: f64,
+    total_trigrams_weight: f64,
 }
 
 impl TrigramStats {
     pub fn new(params: &Parameters) -> Self {
         Self {
-            ignore_modifiers: params.ignore_modifiers,
-            ignore_thumbs: params.ignore_thumbs,
+            filter: SameFingerFilter::new(&params.filter),
             same_finger_rolls: params.same_finger_rolls.clone(),
+            category_costs: params.category_costs.clone(),
+            fraction_of: params.fraction_of,
+            strong_fingers: params.strong_fingers.clone(),
         }
     }
 
-    fn should_ignore_key(&self, key: &LayerKey) -> bool {
-        (self.ignore_thumbs && key.key.finger == Finger::Thumb)
-            || (self.ignore_modifiers && key.is_modifier.is_some())
-    }
-
-    /// Check if this same-finger movement matches any configured same-finger rolls
-    /// Returns Some((Direction, Direction)) if it matches, None otherwise
-    fn check_same_finger_roll(
-        &self,
-        k1: &LayerKey,
-        k2: &LayerKey,
-    ) -> Option<(Direction, Direction)> {
-        let dir_from = k1.key.direction;
-        let dir_to = k2.key.direction;
-
-        for &(from, to) in &self.same_finger_rolls {
-            if dir_from == from && dir_to == to {
-                return Some((from, to));
-            }
-        }
-        None
-    }
-
-    fn classify_trigram(&self, k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> TrigramCategory {
-        let h1 = k1.key.hand;
-        let h2 = k2.key.hand;
-        let h3 = k3.key.hand;
-
-        if h1 == h2 && h2 == h3 {
-            // Same hand (all 3 keys) - check roll in/out or redirect
-            let (is_roll_in, is_roll_out) = classify_same_hand_roll(k1, k2, k3);
-
-            if is_roll_in {
-                return TrigramCategory::RollIn;
-            } else if is_roll_out {
-                return TrigramCategory::RollOut;
-            } else {
-                // Not a roll, check for redirect
-                let (is_redirect, is_weak) = classify_redirect(k1, k2, k3);
-                if is_redirect {
-                    return if is_weak {
-                        TrigramCategory::WeakRedirect
-                    } else {
-                        TrigramCategory::Redirect
-                    };
-                }
-            }
-        } else if h1 == h3 && h1 != h2 {
-            // Alternation (LRL or RLR)
-            return TrigramCategory::Alternation;
-        } else {
-            // Bigram pattern (2,1 or 1,2) - check bigram rolls
-            let (is_inward, is_outward) = self.classify_roll(k1, k2, k3);
-
-            if is_inward {
-                return TrigramCategory::BigramRollIn;
-            } else if is_outward {
-                return TrigramCategory::BigramRollOut;
-            }
-        }
-
-        TrigramCategory::Other
-    }
-
-    /// Classify a trigram roll into its category
-    /// Returns: (is_inward, is_outward)
-    fn classify_roll(&self, k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> (bool, bool) {
-        let h1 = k1.key.hand;
-        let h2 = k2.key.hand;
-        let h3 = k3.key.hand;
-
-        let first_roll = h1 == h2 && h2 != h3;
-        let second_roll = h1 != h2 && h2 == h3;
-
-        if !(first_roll || second_roll) {
-            return (false, false);
-        }
-
-        let (kr1, kr2) = if first_roll { (k1, k2) } else { (k2, k3) };
-
-        // Same-finger movements are not considered rolls (handled separately as same_finger_rolls)
-        if kr1.key.finger == kr2.key.finger {
-            return (false, false);
-        }
-
-        // Different fingers: check inward vs outward
-        let inwards = match kr1.key.hand {
-            Hand::Left => kr1.key.matrix_position.0 < kr2.key.matrix_position.0,
-            Hand::Right => kr1.key.matrix_position.0 > kr2.key.matrix_position.0,
-        };
-
-        if inwards {
-            (true, false)
-        } else {
-            (false, true)
-        }
-    }
-
-    /// Extract the bigram pair from a trigram (either first two or last two keys)
-    /// Returns Some((k1, k2)) for the bigram part, or None if not a bigram pattern
-    fn extract_bigram_pair<'a>(
+    /// Compute the shares of all trigram categories.
+    pub fn compute_statistics(
         &self,
-        k1: &'a LayerKey,
-        k2: &'a LayerKey,
-        k3: &'a LayerKey,
-    ) -> Option<(&'a LayerKey, &'a LayerKey)> {
-        let h1 = k1.key.hand;
-        let h2 = k2.key.hand;
-        let h3 = k3.key.hand;
-
-        let first_roll = h1 == h2 && h2 != h3;
-        let second_roll = h1 != h2 && h2 == h3;
-
-        if first_roll {
-            Some((k1, k2))
-        } else if second_roll {
-            Some((k2, k3))
-        } else {
-            None
-        }
-    }
-}
-
-#[inline(always)]
-fn inwards(k1: &LayerKey, k2: &LayerKey) -> bool {
-    if k1.key.hand == Hand::Left {
-        k1.key.matrix_position.0 < k2.key.matrix_position.0
-    } else {
-        k1.key.matrix_position.0 > k2.key.matrix_position.0
-    }
-}
-
-/// Check if a trigram is a same-hand roll (all 3 keys on same hand, different fingers, directional)
-/// Returns: (is_roll_in, is_roll_out)
-fn classify_same_hand_roll(k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> (bool, bool) {
-    let h1 = k1.key.hand;
-    let h2 = k2.key.hand;
-    let h3 = k3.key.hand;
-
-    // Must be same hand (one-handed trigram)
-    if !(h1 == h2 && h2 == h3) {
-        return (false, false);
-    }
-
-    let f1 = k1.key.finger;
-    let f2 = k2.key.finger;
-    let f3 = k3.key.finger;
-
-    // Must use different fingers (no same-finger bigrams)
-    if f1 == f2 || f2 == f3 {
-        return (false, false);
-    }
-
-    // Check if all three movements are in the same direction
-    let inwards1 = inwards(k1, k2);
-    let inwards2 = inwards(k2, k3);
-
-    let outwards1 = inwards(k2, k1);
-    let outwards2 = inwards(k3, k2);
-
-    // Roll in: both movements inward
-    if inwards1 && inwards2 {
-        return (true, false);
-    }
-
-    // Roll out: both movements outward
-    if outwards1 && outwards2 {
-        return (false, true);
-    }
-
-    (false, false)
-}
-
-/// Check if a trigram is a redirect: one-handed with direction change
-/// Returns: (is_redirect, is_weak_redirect)
-fn classify_redirect(k1: &LayerKey, k2: &LayerKey, k3: &LayerKey) -> (bool, bool) {
-    let h1 = k1.key.hand;
-    let h2 = k2.key.hand;
-    let h3 = k3.key.hand;
-
-    // Must be same hand (one-handed trigram)
-    if !(h1 == h2 && h2 == h3) {
-        return (false, false);
-    }
-
-    let f1 = k1.key.finger;
-    let f2 = k2.key.finger;
-    let f3 = k3.key.finger;
-
-    // Must use different fingers (no same-finger bigrams)
-    if f1 == f2 || f2 == f3 {
-        return (false, false);
-    }
-
-    let inwards1 = inwards(k1, k2);
-    let inwards2 = inwards(k2, k3);
-
-    let outwards1 = inwards(k2, k1);
-    let outwards2 = inwards(k3, k2);
-
-    // Check for direction change: inward->outward or outward->inward
-    let is_redirect = (inwards1 && outwards2) || (outwards1 && inwards2);
-
-    if !is_redirect {
-        return (false, false);
-    }
-
-    // Check if it's weak (no index finger or thumb)
-    let has_index_or_thumb = f1 == Finger::Index
-        || f2 == Finger::Index
-        || f3 == Finger::Index
-        || f1 == Finger::Thumb
-        || f2 == Finger::Thumb
-        || f3 == Finger::Thumb;
-    let is_weak = !has_index_or_thumb;
-
-    (true, is_weak)
-}
-
-impl TrigramMetric for TrigramStats {
-    fn name(&self) -> &str {
-        "Trigram Statistics"
+        trigrams: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+    ) -> TrigramStatistics {
+        self.statistics(&self.weigh(trigrams, total_weight))
     }
 
-    fn total_cost(
+    fn weigh(
         &self,
         trigrams: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
         total_weight: Option<f64>,
-        _layout: &Layout,
-    ) -> (f64, Option<String>) {
-        let mut category_weights: HashMap<TrigramCategory, f64> = HashMap::new();
-        let mut same_finger_roll_weights: HashMap<(Direction, Direction), f64> = HashMap::new();
+    ) -> TrigramWeights {
+        let mut category_weights = [0.0; TrigramCategory::COUNT];
+        let mut same_finger_roll_weights = vec![0.0; self.same_finger_rolls.len()];
+        let mut leading_bigram_rolls_weight = 0.0;
+        let mut trailing_bigram_rolls_weight = 0.0;
         let mut weak_redirects_weight = 0.0;
         let mut sfs_weight = 0.0;
         let mut valid_trigrams_weight = 0.0;
@@ -291,66 +158,154 @@ impl TrigramMetric for TrigramStats {
 
         for ((k1, k2, k3), weight) in trigrams {
             // Check for SFS (Same Finger Skipgram) - k1 and k3 same finger
-            if !self.should_ignore_key(k1)
-                && !self.should_ignore_key(k3)
-                && k1 != k3 // Skip same-key repeats
-                && k1.key.hand == k3.key.hand
-                && k1.key.finger == k3.key.finger
-            {
+            if self.filter.is_same_finger(k1, k3) {
                 sfs_weight += weight;
             }
 
             // Skip ignored keys for other metrics
-            if self.should_ignore_key(k1)
-                || self.should_ignore_key(k2)
-                || self.should_ignore_key(k3)
-            {
+            if self.filter.excludes_keys(k1, k2) || self.filter.excludes_keys(k2, k3) {
                 continue;
             }
 
             valid_trigrams_weight += weight;
 
-            // Check if this trigram contains a same-finger bigram that matches same_finger_rolls
-            if let Some((kb1, kb2)) = self.extract_bigram_pair(k1, k2, k3) {
-                if kb1.key.hand == kb2.key.hand && kb1.key.finger == kb2.key.finger {
-                    if let Some(movement) = self.check_same_finger_roll(kb1, kb2) {
-                        *same_finger_roll_weights.entry(movement).or_insert(0.0) += weight;
+            // A trigram with a same-finger bigram that matches same_finger_rolls counts as a
+            // bigram roll only (and not as `Other`, the category of same-finger bigrams).
+            let position = bigram_position(k1, k2, k3);
+            let mut position_weight = |position| match position {
+                BigramPosition::Leading => leading_bigram_rolls_weight += weight,
+                BigramPosition::Trailing => trailing_bigram_rolls_weight += weight,
+            };
+            let category =
+                match classify_trigram(k1, k2, k3, &self.same_finger_rolls, &self.strong_fingers) {
+                    TrigramClass::SameFingerRoll(roll) => {
+                        same_finger_roll_weights[roll] += weight;
+                        if let Some(position) = position {
+                            position_weight(position);
+                        }
+                        continue;
                     }
-                }
+                    class => class.category(),
+                };
+            category_weights[category as usize] += weight;
+
+            if let (
+                TrigramCategory::BigramRollIn | TrigramCategory::BigramRollOut,
+                Some(position),
+            ) = (category, position)
+            {
+                position_weight(position);
             }
 
-            let category = self.classify_trigram(k1, k2, k3);
-            *category_weights.entry(category).or_insert(0.0) += weight;
-
             // Track weak redirects separately for the message
             if category == TrigramCategory::WeakRedirect {
                 weak_redirects_weight += weight;
             }
         }
 
+        TrigramWeights {
+            category_weights,
+            same_finger_roll_weights,
+            leading_bigram_rolls_weight,
+            trailing_bigram_rolls_weight,
+            weak_redirects_weight,
+            sfs_weight,
+            valid_trigrams_weight,
+            total_trigrams_weight,
+        }
+    }
+
+    fn statistics(&self, weights: &TrigramWeights) -> TrigramStatistics {
+        let TrigramWeights {
+            category_weights,
+            same_finger_roll_weights,
+            leading_bigram_rolls_weight,
+            trailing_bigram_rolls_weight,
+            weak_redirects_weight,
+            sfs_weight,
+            valid_trigrams_weight,
+            total_trigrams_weight,
+        } = weights;
+
         // Helper to get weight for a category
-        let get_weight = |cat: TrigramCategory| *category_weights.get(&cat).unwrap_or(&0.0);
+        let get_weight = |cat: TrigramCategory| category_weights[cat as usize];
 
         // Calculate percentages
-        let to_pct = |weight| crate::metrics::to_percentage(weight, valid_trigrams_weight);
-
-        let bigram_inward_percentage = to_pct(get_weight(TrigramCategory::BigramRollIn));
-        let bigram_outward_percentage = to_pct(get_weight(TrigramCategory::BigramRollOut));
-        let roll_in_percentage = to_pct(get_weight(TrigramCategory::RollIn));
-        let roll_out_percentage = to_pct(get_weight(TrigramCategory::RollOut));
-        let alternation_percentage = to_pct(get_weight(TrigramCategory::Alternation));
-        let redirect_percentage =
-            to_pct(get_weight(TrigramCategory::Redirect) + weak_redirects_weight);
-        let weak_redirect_percentage = to_pct(weak_redirects_weight);
-        let other_percentage = to_pct(get_weight(TrigramCategory::Other));
-        let sfs_percentage = crate::metrics::to_percentage(sfs_weight, total_trigrams_weight);
+        let base_weight = match self.fraction_of {
+            FractionBase::ValidTrigrams => *valid_trigrams_weight,
+            FractionBase::AllTrigrams => *total_trigrams_weight,
+        };
+        let to_pct = |weight| crate::metrics::utils::to_percentage(weight, base_weight);
 
         // Calculate total bigram roll weight (including same-finger rolls)
-        let same_finger_rolls_total: f64 = same_finger_roll_weights.values().sum();
+        let same_finger_rolls_total: f64 = same_finger_roll_weights.iter().sum();
         let total_bigram_rolls_weight = get_weight(TrigramCategory::BigramRollIn)
             + get_weight(TrigramCategory::BigramRollOut)
             + same_finger_rolls_total;
-        let total_bigram_rolls_percentage = to_pct(total_bigram_rolls_weight);
+
+        let same_finger_roll_percents = self
+            .same_finger_rolls
+            .iter()
+            .zip(same_finger_roll_weights.iter())
+            .filter(|(_, weight)| **weight > 0.0)
+            .map(|(movement, weight)| (*movement, to_pct(*weight)))
+            .collect();
+
+        TrigramStatistics {
+            bigram_roll_total_percent: to_pct(total_bigram_rolls_weight),
+            bigram_roll_in_percent: to_pct(get_weight(TrigramCategory::BigramRollIn)),
+            bigram_roll_out_percent: to_pct(get_weight(TrigramCategory::BigramRollOut)),
+            bigram_roll_leading_percent: to_pct(*leading_bigram_rolls_weight),
+            bigram_roll_trailing_percent: to_pct(*trailing_bigram_rolls_weight),
+            same_finger_roll_percents,
+            roll_in_percent: to_pct(get_weight(TrigramCategory::RollIn)),
+            roll_out_percent: to_pct(get_weight(TrigramCategory::RollOut)),
+            alternation_percent: to_pct(get_weight(TrigramCategory::Alternation)),
+            redirect_percent: to_pct(get_weight(TrigramCategory::Redirect) + weak_redirects_weight),
+            weak_redirect_percent: to_pct(*weak_redirects_weight),
+            other_percent: to_pct(get_weight(TrigramCategory::Other)),
+            sfs_percent: crate::metrics::utils::to_percentage(*sfs_weight, *total_trigrams_weight),
+        }
+    }
+}
+
+impl TrigramMetric for TrigramStats {
+    fn name(&self) -> &str {
+        "Trigram Statistics"
+    }
+
+    fn percentages(
+        &self,
+        trigrams: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> Vec<(&'static str, f64)> {
+        let stats = self.compute_statistics(trigrams, total_weight);
+
+        vec![
+            ("bigram_roll_total", stats.bigram_roll_total_percent),
+            ("bigram_roll_in", stats.bigram_roll_in_percent),
+            ("bigram_roll_out", stats.bigram_roll_out_percent),
+            ("bigram_roll_leading", stats.bigram_roll_leading_percent),
+            ("bigram_roll_trailing", stats.bigram_roll_trailing_percent),
+            ("roll_in", stats.roll_in_percent),
+            ("roll_out", stats.roll_out_percent),
+            ("alternation", stats.alternation_percent),
+            ("redirect", stats.redirect_percent),
+            ("weak_redirect", stats.weak_redirect_percent),
+            ("other", stats.other_percent),
+            ("sfs", stats.sfs_percent),
+        ]
+    }
+
+    fn total_cost(
+        &self,
+        trigrams: &[((&LayerKey, &LayerKey, &LayerKey), f64)],
+        total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> (f64, Option<String>) {
+        let weights = self.weigh(trigrams, total_weight);
+        let stats = self.statistics(&weights);
 
         // Build message with category groups separated by semicolons
         let mut groups = Vec::new();
@@ -359,34 +314,50 @@ impl TrigramMetric for TrigramStats {
         let mut roll_2_parts = Vec::new();
         roll_2_parts.push(format!(
             "{}: {:.1}%",
-            "2-Roll Total".underline(),
-            total_bigram_rolls_percentage
+            underline("2-Roll Total"),
+            stats.bigram_roll_total_percent
         ));
 
-        if bigram_inward_percentage > 0.0 {
+        if stats.bigram_roll_in_percent > 0.0 {
             roll_2_parts.push(format!(
                 "{}: {:.1}%",
-                "2-Roll In".underline(),
-                bigram_inward_percentage
+                underline("2-Roll In"),
+                stats.bigram_roll_in_percent
             ));
         }
 
-        if bigram_outward_percentage > 0.0 {
+        if stats.bigram_roll_out_percent > 0.0 {
             roll_2_parts.push(format!(
                 "{}: {:.1}%",
-                "2-Roll Out".underline(),
-                bigram_outward_percentage
+                underline("2-Roll Out"),
+                stats.bigram_roll_out_percent
+            ));
+        }
+
+        if stats.bigram_roll_leading_percent > 0.0 {
+            roll_2_parts.push(format!(
+                "{}: {:.1}%",
+                underline("2-Roll Leading"),
+                stats.bigram_roll_leading_percent
+            ));
+        }
+
+        if stats.bigram_roll_trailing_percent > 0.0 {
+            roll_2_parts.push(format!(
+                "{}: {:.1}%",
+                underline("2-Roll Trailing"),
+                stats.bigram_roll_trailing_percent
             ));
         }
 
         // Add same-finger roll movements to 2-Roll group
-        for ((dir_from, dir_to), weight) in same_finger_roll_weights.iter() {
-            let percentage = to_pct(*weight);
+        for (movement, percentage) in stats.same_finger_roll_percents.iter() {
+            let percentage = *percentage;
             if percentage > 0.0 {
-                let movement_label = format!("2-Roll {:?}→{:?}", dir_from, dir_to);
+                let movement_label = format!("2-Roll {}", movement_label(movement));
                 roll_2_parts.push(format!(
                     "{}: {:.1}%",
-                    movement_label.underline(),
+                    underline(&movement_label),
                     percentage
                 ));
             }
@@ -395,19 +366,19 @@ impl TrigramMetric for TrigramStats {
 
         // 3-Roll group
         let mut roll_3_parts = Vec::new();
-        if roll_in_percentage > 0.0 {
+        if stats.roll_in_percent > 0.0 {
             roll_3_parts.push(format!(
                 "{}: {:.1}%",
-                "3-Roll In".underline(),
-                roll_in_percentage
+                underline("3-Roll In"),
+                stats.roll_in_percent
             ));
         }
 
-        if roll_out_percentage > 0.0 {
+        if stats.roll_out_percent > 0.0 {
             roll_3_parts.push(format!(
                 "{}: {:.1}%",
-                "3-Roll Out".underline(),
-                roll_out_percentage
+                underline("3-Roll Out"),
+                stats.roll_out_percent
             ));
         }
         if !roll_3_parts.is_empty() {
@@ -415,29 +386,29 @@ impl TrigramMetric for TrigramStats {
         }
 
         // Alt group
-        if alternation_percentage > 0.0 {
+        if stats.alternation_percent > 0.0 {
             groups.push(format!(
                 "{}: {:.1}%",
-                "Alt".underline(),
-                alternation_percentage
+                underline("Alt"),
+                stats.alternation_percent
             ));
         }
 
         // Redirect group
         let mut redirect_parts = Vec::new();
-        if redirect_percentage > 0.0 {
+        if stats.redirect_percent > 0.0 {
             redirect_parts.push(format!(
                 "{}: {:.1}%",
-                "Redirect".underline(),
-                redirect_percentage
+                underline("Redirect"),
+                stats.redirect_percent
             ));
         }
 
-        if weak_redirect_percentage > 0.0 {
+        if stats.weak_redirect_percent > 0.0 {
             redirect_parts.push(format!(
                 "{}: {:.1}%",
-                "Weak redirect".underline(),
-                weak_redirect_percentage
+                underline("Weak redirect"),
+                stats.weak_redirect_percent
             ));
         }
         if !redirect_parts.is_empty() {
@@ -445,18 +416,102 @@ impl TrigramMetric for TrigramStats {
         }
 
         // Other group
-        if other_percentage > 0.0 {
-            groups.push(format!("{}: {:.1}%", "Other".underline(), other_percentage));
+        if stats.other_percent > 0.0 {
+            groups.push(format!(
+                "{}: {:.1}%",
+                underline("Other"),
+                stats.other_percent
+            ));
         }
 
         // SFS group
-        if sfs_percentage > 0.0 {
-            groups.push(format!("{}: {:.1}%", "SFS".underline(), sfs_percentage));
+        if stats.sfs_percent > 0.0 {
+            groups.push(format!("{}: {:.1}%", underline("SFS"), stats.sfs_percent));
         }
 
         let message = groups.join("; ");
 
-        // Return 0 cost since this is informational only
-        (0.0, Some(message))
+        // Without category costs, the metric is informational only
+        let cost = self.category_costs.as_ref().map_or(0.0, |category_costs| {
+            category_costs
+                .iter()
+                .map(|(category, cost)| weights.category_weights[*category as usize] * cost)
+                .sum()
+        });
+
+        (cost, Some(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::{
+        key::Direction,
+        test_utils::{layerkeys, toy_layout},
+    };
+
+    /// The left index finger has "p" (North) to "t" (South), the right index finger "u" to
+    /// "y"; the Center keys of the left pinky, index and middle finger are "c", "r", and "m".
+    fn layout() -> Layout {
+        toy_layout("abcdefghijklmnopqrstuvwxyz")
+    }
+
+    fn trigram_stats(category_costs: Option<AHashMap<TrigramCategory, f64>>) -> TrigramStats {
+        TrigramStats::new(&Parameters {
+            same_finger_rolls: vec![(Some(Direction::Center), Some(Direction::South))],
+            category_costs,
+            ..serde_yaml::from_str("{ignore_thumbs: false}").unwrap()
+        })
+    }
+
+    fn trigrams<'a>(
+        layout: &'a Layout,
+        weighted: &[(&str, f64)],
+    ) -> Vec<((&'a LayerKey, &'a LayerKey, &'a LayerKey), f64)> {
+        weighted
+            .iter()
+            .map(|(trigram, weight)| {
+                let keys = layerkeys(layout, trigram);
+                ((keys[0], keys[1], keys[2]), *weight)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_finger_rolls_are_counted_once() {
+        let layout = layout();
+        // A Center→South roll of the left index finger followed by the right hand, a roll in,
+        // and an alternation
+        let trigrams = trigrams(&layout, &[("rtw", 1.0), ("cmr", 1.0), ("cwc", 2.0)]);
+        let stats = trigram_stats(None).compute_statistics(&trigrams, None);
+
+        assert_eq!(
+            stats.same_finger_roll_percents,
+            vec![((Some(Direction::Center), Some(Direction::South)), 25.0)]
+        );
+        assert_eq!(stats.bigram_roll_total_percent, 25.0);
+        assert_eq!(stats.bigram_roll_leading_percent, 25.0);
+        assert_eq!(stats.other_percent, 0.0);
+
+        let categories_percent = stats.bigram_roll_total_percent
+            + stats.roll_in_percent
+            + stats.roll_out_percent
+            + stats.alternation_percent
+            + stats.redirect_percent
+            + stats.other_percent;
+        assert!((categories_percent - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn same_finger_rolls_cost_nothing() {
+        let layout = layout();
+        let trigrams = trigrams(&layout, &[("rtw", 1.0), ("abw", 2.0)]);
+        let costs = [(TrigramCategory::Other, 1.0)].iter().copied().collect();
+        let (cost, _) = trigram_stats(Some(costs)).total_cost(&trigrams, None, &layout);
+
+        // Only the same-finger bigram that is not a configured roll is charged as `Other`
+        assert_eq!(cost, 2.0);
     }
 }
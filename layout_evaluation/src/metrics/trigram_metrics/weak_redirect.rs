@@ -1,19 +1,39 @@
 //! Weak redirect metric that penalizes weak redirects only.
 //! A weak redirect is a one-handed trigram with a direction change that does NOT involve
-//! the index finger or thumb, making it harder to execute.
+//! a strong finger (`strong_fingers`, by default the index finger or thumb), making it harder
+//! to execute.
 
-use super::{redirect_base::{RedirectMetric, WeakRedirectFilter}, TrigramMetric};
-use keyboard_layout::layout::{LayerKey, Layout};
+use super::{
+    classification::default_strong_fingers,
+    redirect_base::{RedirectMetric, WeakRedirectFilter},
+    SharedTables, TrigramMetric,
+};
+use crate::metrics::worst_ngrams::WorstParameters;
+use ahash::AHashMap;
+use keyboard_layout::{
+    key::{Finger, Hand},
+    layout::{LayerKey, Layout},
+};
 use serde::Deserialize;
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
     /// Base cost multiplier for each weak redirect. Default: 1.0
     pub base_cost: Option<f64>,
+    /// Cost multiplier per hand, replacing `base_cost` for the listed hands
+    #[serde(default)]
+    pub hand_costs: AHashMap<Hand, f64>,
     /// Ignore redirects involving thumb keys. Default: true
     pub ignore_thumbs: Option<bool>,
     /// Ignore redirects involving modifier keys. Default: true
     pub ignore_modifiers: Option<bool>,
+    /// Fingers whose involvement makes a redirect a normal (not a weak) one.
+    /// Default: [Index, Thumb]
+    #[serde(default = "default_strong_fingers")]
+    pub strong_fingers: Vec<Finger>,
+    /// Which of the worst ngrams to list (`n_worst` or `worst_threshold_pct`)
+    #[serde(flatten)]
+    pub worst: WorstParameters,
 }
 
 #[derive(Clone, Debug)]
@@ -30,7 +50,10 @@ impl WeakRedirect {
                 params.base_cost.unwrap_or(1.0),
                 params.ignore_thumbs.unwrap_or(true),
                 params.ignore_modifiers.unwrap_or(true),
-            ),
+            )
+            .with_strong_fingers(&params.strong_fingers)
+            .with_worst_parameters(params.worst)
+            .with_hand_costs(&params.hand_costs),
         }
     }
 }
@@ -50,7 +73,12 @@ impl TrigramMetric for WeakRedirect {
         total_weight: f64,
         layout: &Layout,
     ) -> Option<f64> {
-        self.inner.individual_cost(k1, k2, k3, weight, total_weight, layout)
+        self.inner
+            .individual_cost(k1, k2, k3, weight, total_weight, layout)
+    }
+
+    fn precompute(&mut self, tables: &mut SharedTables) {
+        self.inner.precompute(tables)
     }
 
     fn total_cost(
@@ -62,3 +90,27 @@ impl TrigramMetric for WeakRedirect {
         self.inner.total_cost(trigrams, total_weight, layout)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::{layerkeys, toy_layout};
+
+    #[test]
+    fn strong_fingers_are_configurable() {
+        let layout = toy_layout("abcdefghijklmno");
+        // ring → pinky → middle
+        let keys = layerkeys(&layout, "hcm");
+        let cost = |params: &str| {
+            WeakRedirect::new(&serde_yaml::from_str(params).unwrap())
+                .individual_cost(keys[0], keys[1], keys[2], 1.0, 1.0, &layout)
+        };
+
+        assert_eq!(cost("{base_cost: 2.0}"), Some(2.0));
+        assert_eq!(
+            cost("{base_cost: 2.0, strong_fingers: [Index, Middle]}"),
+            Some(0.0)
+        );
+    }
+}
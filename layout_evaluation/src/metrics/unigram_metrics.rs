@@ -3,13 +3,18 @@ use keyboard_layout::layout::{LayerKey, Layout};
 use ordered_float::OrderedFloat;
 use priority_queue::DoublePriorityQueue;
 
-use super::format_utils::{format_percentages, visualize_whitespace};
-use std::{env, fmt};
+use super::{
+    format_utils::{format_percentages, visualize_ngram},
+    worst_ngrams::env_setting,
+};
+use std::fmt;
 
 pub mod finger_balance;
 pub mod character_constraints;
 pub mod hand_disbalance;
+pub mod hand_grouping;
 pub mod key_costs;
+pub mod layer_key_costs;
 pub mod modifier_usage;
 pub mod row_loads;
 
@@ -18,6 +23,20 @@ pub trait UnigramMetric: Send + Sync + UnigramMetricClone + fmt::Debug {
     /// Return the name of the metric
     fn name(&self) -> &str;
 
+    /// Whether the metric shall receive the unigrams without resolved modifiers, i.e. with
+    /// higher-layer symbols on their own layer (even if `split_modifiers` is enabled).
+    fn uses_unsplit_unigrams(&self) -> bool {
+        false
+    }
+
+    /// Whether [`UnigramMetric::total_cost`] is the sum of the individual costs and each individual cost
+    /// is proportional to the weight of its unigram. Then, the cost can be updated from the
+    /// unigrams that change when two keys are swapped, see
+    /// [`LayoutEvaluator::update_swap`](crate::evaluator::LayoutEvaluator::update_swap).
+    fn is_additive(&self) -> bool {
+        false
+    }
+
     /// Compute the cost of one unigram (if that is possible, otherwise, return `None`).
     #[inline(always)]
     fn individual_cost(
@@ -38,14 +57,8 @@ pub trait UnigramMetric: Send + Sync + UnigramMetricClone + fmt::Debug {
         total_weight: Option<f64>,
         layout: &Layout,
     ) -> (f64, Option<String>) {
-        let show_worst: bool = env::var("SHOW_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(true);
-        let n_worst: usize = env::var("N_WORST")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(3);
+        let show_worst: bool = env_setting("SHOW_WORST").unwrap_or(true);
+        let n_worst: usize = env_setting("N_WORST").unwrap_or(3);
 
         let total_weight = total_weight.unwrap_or_else(|| unigrams.iter().map(|(_, w)| w).sum());
         let cost_iter = unigrams
@@ -79,13 +92,11 @@ pub trait UnigramMetric: Send + Sync + UnigramMetricClone + fmt::Debug {
                 .filter(|(_, cost)| cost.into_inner() > 0.0)
                 .map(|(i, cost)| {
                     let (gram, weight) = unigrams[i];
-                    let freq_pct = 100.0 * weight / total_weight;
-                    let cost_pct = 100.0 * cost.into_inner() / total_cost;
-                    let percentages = format_percentages(cost_pct, freq_pct);
-                    let gram_str = format!("{}", gram);
+                    let percentages =
+                        format_percentages(cost.into_inner(), total_cost, weight, total_weight);
                     format!(
                         "{} {}",
-                        visualize_whitespace(&gram_str),
+                        visualize_ngram(&[gram]),
                         percentages
                     )
                 })
@@ -8,6 +8,7 @@ use keyboard_layout::layout::{LayerKey, Layout};
 
 use ahash::AHashMap;
 use serde::Deserialize;
+use std::sync::Arc;
 
 /// A tuple representing matrix position: (Column, Row)
 type MatrixPosition = (u8, u8);
@@ -20,13 +21,13 @@ pub struct Parameters {
 
 #[derive(Clone, Debug)]
 pub struct CharacterConstraints {
-    costs: AHashMap<char, AHashMap<MatrixPosition, f64>>,
+    costs: Arc<AHashMap<char, AHashMap<MatrixPosition, f64>>>,
 }
 
 impl CharacterConstraints {
     pub fn new(params: &Parameters) -> Self {
         Self {
-            costs: params.costs.clone(),
+            costs: Arc::new(params.costs.clone()),
         }
     }
 }
@@ -36,6 +37,10 @@ impl UnigramMetric for CharacterConstraints {
         "Character Constraints"
     }
 
+    fn is_additive(&self) -> bool {
+        true
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
@@ -6,9 +6,10 @@
 //! from the discrepancy computation.
 
 use super::UnigramMetric;
+use crate::metrics::FingerFactors;
 
 use keyboard_layout::{
-    key::{Finger, Hand, HandFingerMap},
+    key::{Finger, FingerMap, Hand, HandFingerMap},
     layout::{LayerKey, Layout},
 };
 
@@ -18,13 +19,14 @@ use serde::Deserialize;
 #[derive(Clone, Deserialize, Debug)]
 pub struct Parameters {
     pub intended_loads: AHashMap<(Hand, Finger), f64>,
-    pub finger_factors: Option<AHashMap<Finger, f64>>,
+    #[serde(default)]
+    pub finger_factors: FingerFactors,
 }
 
 #[derive(Clone, Debug)]
 pub struct FingerBalance {
     intended_loads: AHashMap<(Hand, Finger), f64>,
-    finger_factors: AHashMap<Finger, f64>,
+    finger_factors: FingerMap<f64>,
 }
 
 impl FingerBalance {
@@ -39,7 +41,7 @@ impl FingerBalance {
         intended_loads.values_mut().for_each(|l| {
             *l /= total_intended;
         });
-        let finger_factors = params.finger_factors.clone().unwrap_or_default();
+        let finger_factors = params.finger_factors.to_finger_map();
 
         Self { intended_loads, finger_factors }
     }
@@ -92,7 +94,7 @@ impl UnigramMetric for FingerBalance {
             .iter()
             .zip(self.intended_loads.iter().filter(|((_hand, finger), _intended_load)| *finger != Finger::Thumb))
             .map(|(fraction, ((_, finger), _))| {
-                let factor = self.finger_factors.get(finger).copied().unwrap_or(1.0);
+                let factor = self.finger_factors.get(finger);
                 let deviation = fraction - mean;
                 factor * deviation * deviation
             })
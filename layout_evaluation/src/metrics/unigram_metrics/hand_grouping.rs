@@ -0,0 +1,148 @@
+//! The unigram metric [`HandGrouping`] keeps named groups of characters on a target hand,
+//! e.g. all vowels on the right hand to maximize hand alternation.
+//!
+//! The cost of a group is the share of its unigram weight typed on the wrong hand. A character
+//! belongs to a group if the symbol on the base layer of its key does, so e.g. "A" counts
+//! for a group containing "a" if both are on the same key.
+//!
+//! With `hard_constraint`, each character on the wrong hand additionally costs
+//! [`HARD_CONSTRAINT_COST`], which effectively rejects such layouts during optimization.
+//!
+//! The message reports the share of each group on its target hand.
+
+use super::UnigramMetric;
+use crate::output_style::underline;
+
+use ahash::{AHashMap, AHashSet};
+use keyboard_layout::{
+    key::Hand,
+    layout::{LayerKey, Layout},
+};
+
+use serde::Deserialize;
+
+/// Cost of each misplaced character in hard constraint mode
+pub const HARD_CONSTRAINT_COST: f64 = 1_000_000.0;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct CharacterGroup {
+    /// Name of the group (used in the message)
+    pub name: String,
+    /// Characters belonging to the group
+    pub chars: String,
+    /// Hand that shall type the group
+    pub hand: Hand,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    pub groups: Vec<CharacterGroup>,
+    /// Whether misplaced characters shall be (effectively) forbidden
+    #[serde(default)]
+    pub hard_constraint: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Group {
+    name: String,
+    chars: AHashSet<char>,
+    hand: Hand,
+}
+
+#[derive(Clone, Debug)]
+pub struct HandGrouping {
+    groups: Vec<Group>,
+    hard_constraint: bool,
+}
+
+impl HandGrouping {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            groups: params
+                .groups
+                .iter()
+                .map(|g| Group {
+                    name: g.name.clone(),
+                    chars: g.chars.chars().collect(),
+                    hand: g.hand,
+                })
+                .collect(),
+            hard_constraint: params.hard_constraint,
+        }
+    }
+
+    /// Symbol on the base layer of the key generating `key`'s symbol
+    fn base_symbol(key: &LayerKey, layout: &Layout) -> char {
+        layout
+            .get_layerkey_index_for_symbol(&key.symbol)
+            .map(|idx| {
+                layout
+                    .get_layerkey(&layout.get_base_layerkey_index(&idx))
+                    .symbol
+            })
+            .unwrap_or(key.symbol)
+    }
+}
+
+impl UnigramMetric for HandGrouping {
+    fn name(&self) -> &str {
+        "Hand Grouping"
+    }
+
+    fn total_cost(
+        &self,
+        unigrams: &[(&LayerKey, f64)],
+        _total_weight: Option<f64>,
+        layout: &Layout,
+    ) -> (f64, Option<String>) {
+        // weight on the target hand and total weight of each group
+        let mut group_weights: AHashMap<usize, (f64, f64)> = AHashMap::default();
+        let mut n_misplaced = 0;
+
+        for (key, weight) in unigrams.iter().filter(|(k, _)| k.is_modifier.is_none()) {
+            let base_symbol = Self::base_symbol(key, layout);
+            for (i, group) in self.groups.iter().enumerate() {
+                if !group.chars.contains(&key.symbol) && !group.chars.contains(&base_symbol) {
+                    continue;
+                }
+
+                let entry = group_weights.entry(i).or_insert((0.0, 0.0));
+                entry.1 += weight;
+                if key.key.hand == group.hand {
+                    entry.0 += weight;
+                } else {
+                    n_misplaced += 1;
+                }
+            }
+        }
+
+        let mut cost = 0.0;
+        let mut msgs = Vec::new();
+        for (i, group) in self.groups.iter().enumerate() {
+            let (on_hand_weight, group_weight) = match group_weights.get(&i) {
+                Some(weights) if weights.1 > 0.0 => *weights,
+                _ => continue,
+            };
+
+            cost += 1.0 - on_hand_weight / group_weight;
+            msgs.push(format!(
+                "{}: {:.1}% {:?}",
+                underline(&group.name),
+                crate::metrics::utils::to_percentage(on_hand_weight, group_weight),
+                group.hand
+            ));
+        }
+
+        if self.hard_constraint {
+            cost += n_misplaced as f64 * HARD_CONSTRAINT_COST;
+        }
+
+        let message = if msgs.is_empty() {
+            None
+        } else {
+            Some(msgs.join(", "))
+        };
+
+        (cost, message)
+    }
+}
@@ -25,6 +25,10 @@ impl UnigramMetric for KeyCost {
         "Key Costs"
     }
 
+    fn is_additive(&self) -> bool {
+        true
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
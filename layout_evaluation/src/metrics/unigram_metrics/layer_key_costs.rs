@@ -0,0 +1,88 @@
+//! The unigram metric [`LayerKeyCosts`] computes the average key cost (weighted by the
+//! unigram frequencies) separately for each layer and sums them up, multiplied by a
+//! configurable factor per layer.
+//!
+//! In contrast to [`super::key_costs::KeyCost`], the symbols of the upper layers are not
+//! dominated by the much more frequent base-layer characters, so the optimizer also cleans up
+//! the placement on the symbol layers. Only the cost of the key itself is considered, not the
+//! one of the modifiers needed to reach the layer. Modifier keys themselves are skipped.
+//!
+//! The metric always receives the unigrams with higher-layer symbols on their own layer, even
+//! if `split_modifiers` is enabled.
+//!
+//! The message shows the average key cost per layer.
+
+use super::UnigramMetric;
+
+use ahash::AHashMap;
+use keyboard_layout::layout::{LayerKey, Layout};
+
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct Parameters {
+    /// Factor per layer, counted from 0 for the base layer (default 1.0)
+    #[serde(default)]
+    pub layer_factors: AHashMap<u8, f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LayerKeyCosts {
+    layer_factors: AHashMap<u8, f64>,
+}
+
+impl LayerKeyCosts {
+    pub fn new(params: &Parameters) -> Self {
+        Self {
+            layer_factors: params.layer_factors.clone(),
+        }
+    }
+}
+
+impl UnigramMetric for LayerKeyCosts {
+    fn name(&self) -> &str {
+        "Layer Key Costs"
+    }
+
+    fn uses_unsplit_unigrams(&self) -> bool {
+        true
+    }
+
+    fn total_cost(
+        &self,
+        unigrams: &[(&LayerKey, f64)],
+        _total_weight: Option<f64>,
+        _layout: &Layout,
+    ) -> (f64, Option<String>) {
+        // (weighted key cost, weight) per layer
+        let mut layer_costs: AHashMap<u8, (f64, f64)> = AHashMap::default();
+        unigrams
+            .iter()
+            .filter(|(key, _)| key.is_modifier.is_none())
+            .for_each(|(key, weight)| {
+                let entry = layer_costs.entry(key.layer).or_insert((0.0, 0.0));
+                entry.0 += weight * key.key.cost;
+                entry.1 += weight;
+            });
+
+        let mut layers: Vec<u8> = layer_costs.keys().copied().collect();
+        layers.sort_unstable();
+
+        let mut cost = 0.0;
+        let mut msgs = Vec::new();
+        for layer in layers {
+            let (layer_cost, layer_weight) = layer_costs[&layer];
+            if layer_weight <= 0.0 {
+                continue;
+            }
+
+            let average_cost = layer_cost / layer_weight;
+            cost += average_cost * self.layer_factors.get(&layer).copied().unwrap_or(1.0);
+            msgs.push(format!("Layer {}: {:.2}", layer, average_cost));
+        }
+
+        let message = format!("Average key cost per layer: {}", msgs.join(", "));
+
+        (cost, Some(message))
+    }
+}
@@ -35,6 +35,10 @@ impl UnigramMetric for ModifierUsage {
         "Modifier Usage"
     }
 
+    fn is_additive(&self) -> bool {
+        true
+    }
+
     #[inline(always)]
     fn individual_cost(
         &self,
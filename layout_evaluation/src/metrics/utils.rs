@@ -0,0 +1,71 @@
+//! General helper functions for metric implementations
+
+use keyboard_layout::key::Direction;
+
+/// Convert a weight to its percentage of `total`
+///
+/// This is used by stats metrics to calculate percentages from frequencies.
+///
+/// Edge case: if `total` is zero (as well as negative or not finite), the result is 0.0
+/// instead of NaN or infinity, e.g. for a metric that did not see any relevant ngram.
+#[inline]
+pub fn to_percentage(weight: f64, total: f64) -> f64 {
+    if total > 0.0 && total.is_finite() {
+        (weight / total) * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// A movement from one direction to another, where `None` matches any direction
+/// (e.g. `(None, Some(South))` for all movements ending in `South`)
+pub type MovementPattern = (Option<Direction>, Option<Direction>);
+
+/// Whether the movement from `from` to `to` matches `pattern`
+#[inline]
+pub fn matches_movement(pattern: &MovementPattern, from: Direction, to: Direction) -> bool {
+    let matches = |dir: Option<Direction>, actual: Direction| dir.map_or(true, |d| d == actual);
+    matches(pattern.0, from) && matches(pattern.1, to)
+}
+
+/// Label of a movement pattern, e.g. "Center→South" or "*→South"
+pub fn movement_label(pattern: &MovementPattern) -> String {
+    let label = |dir: Option<Direction>| dir.map_or("*".to_string(), |d| format!("{:?}", d));
+    format!("{}→{}", label(pattern.0), label(pattern.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::key::Direction::*;
+
+    #[test]
+    fn percentage_of_total() {
+        assert_eq!(to_percentage(1.0, 4.0), 25.0);
+        assert_eq!(to_percentage(0.0, 4.0), 0.0);
+        assert_eq!(to_percentage(4.0, 4.0), 100.0);
+    }
+
+    #[test]
+    fn percentage_of_zero_total() {
+        assert_eq!(to_percentage(0.0, 0.0), 0.0);
+        assert_eq!(to_percentage(1.0, 0.0), 0.0);
+        assert_eq!(to_percentage(1.0, -1.0), 0.0);
+        assert_eq!(to_percentage(1.0, f64::INFINITY), 0.0);
+        assert_eq!(to_percentage(1.0, f64::NAN), 0.0);
+    }
+
+    #[test]
+    fn movement_patterns() {
+        let center_south = (Some(Center), Some(South));
+        let to_south = (None, Some(South));
+
+        assert!(matches_movement(&center_south, Center, South));
+        assert!(!matches_movement(&center_south, In, South));
+        assert!(matches_movement(&to_south, In, South));
+        assert!(!matches_movement(&to_south, South, In));
+        assert_eq!(movement_label(&center_south), "Center→South");
+        assert_eq!(movement_label(&to_south), "*→South");
+    }
+}
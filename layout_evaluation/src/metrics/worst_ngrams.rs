@@ -0,0 +1,275 @@
+//! Selection of the "worst" ngrams (those with the highest cost) that metrics list in
+//! their messages.
+//!
+//! By default, the `n` worst ngrams are listed, where `n` is taken from the `N_WORST`
+//! environment variable (default 3). Metrics can override this with their `n_worst` parameter
+//! or list all ngrams above a share of their total cost with `worst_threshold_pct`.
+//! Setting `SHOW_WORST=false` disables the tracking altogether (e.g. during optimization).
+//! Without the `env` feature (e.g. in WebAssembly), the environment variables are not read
+//! (see [`env_setting`]).
+//!
+//! Metrics supporting a [`TrackingMode`] can instead track the worst ngrams only during a
+//! final evaluation, which the optimizers announce with [`begin_final_evaluation`] and
+//! [`end_final_evaluation`] around the evaluation of their result.
+
+use ordered_float::OrderedFloat;
+use priority_queue::DoublePriorityQueue;
+use serde::Deserialize;
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Default number of worst ngrams if neither configured nor set in `N_WORST`
+const DEFAULT_N_WORST: usize = 3;
+
+/// The value of the environment variable `name`, if it is set and can be parsed. Without the
+/// `env` feature, environment variables are never read.
+pub fn env_setting<T: FromStr>(name: &str) -> Option<T> {
+    #[cfg(feature = "env")]
+    {
+        std::env::var(name).ok().and_then(|s| s.parse().ok())
+    }
+    #[cfg(not(feature = "env"))]
+    {
+        let _ = name;
+        None
+    }
+}
+
+/// Number of final evaluations currently running (optimizations may run in parallel)
+static FINAL_EVALUATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Mark the start of a final evaluation, e.g. of an optimization result that is displayed.
+pub fn begin_final_evaluation() {
+    FINAL_EVALUATIONS.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Mark the end of a final evaluation started with [`begin_final_evaluation`].
+pub fn end_final_evaluation() {
+    let _ =
+        FINAL_EVALUATIONS.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1));
+}
+
+/// Whether a final evaluation is currently running
+pub fn is_final_evaluation() -> bool {
+    FINAL_EVALUATIONS.load(Ordering::SeqCst) > 0
+}
+
+/// When a metric tracks its worst ngrams.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum TrackingMode {
+    /// Whenever the `SHOW_WORST` environment variable allows it (the default)
+    Always,
+    /// Never, the message only contains what does not depend on individual ngrams
+    Never,
+    /// Only during a final evaluation (see [`begin_final_evaluation`]), regardless of
+    /// `SHOW_WORST`
+    FinalOnly,
+}
+
+impl Default for TrackingMode {
+    fn default() -> Self {
+        TrackingMode::Always
+    }
+}
+
+/// Which ngrams a metric lists in its message.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorstSelection {
+    /// Do not track worst ngrams
+    Disabled,
+    /// The given number of ngrams with the highest cost
+    Count(usize),
+    /// All ngrams whose cost exceeds the given percentage of the metric's total cost
+    Threshold(f64),
+}
+
+/// Metric parameters configuring the worst-ngram listing (to be flattened into a metric's
+/// `Parameters`).
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct WorstParameters {
+    /// Number of worst ngrams to list (overrides `N_WORST`)
+    #[serde(default)]
+    pub n_worst: Option<usize>,
+    /// List all ngrams contributing more than this percentage of the metric's cost instead
+    #[serde(default)]
+    pub worst_threshold_pct: Option<f64>,
+}
+
+impl WorstParameters {
+    /// Resolve the selection with respect to the `SHOW_WORST` and `N_WORST` environment variables.
+    pub fn selection(&self) -> WorstSelection {
+        let show_worst: bool = env_setting("SHOW_WORST").unwrap_or(true);
+        if !show_worst {
+            return WorstSelection::Disabled;
+        }
+
+        self.configured_selection()
+    }
+
+    /// Resolve the selection with respect to the given [`TrackingMode`].
+    pub fn selection_for_mode(&self, mode: TrackingMode) -> WorstSelection {
+        match mode {
+            TrackingMode::Always => self.selection(),
+            TrackingMode::Never => WorstSelection::Disabled,
+            TrackingMode::FinalOnly if is_final_evaluation() => self.configured_selection(),
+            TrackingMode::FinalOnly => WorstSelection::Disabled,
+        }
+    }
+
+    /// The selection as configured (or set in `N_WORST`), ignoring `SHOW_WORST`
+    fn configured_selection(&self) -> WorstSelection {
+        if let Some(threshold) = self.worst_threshold_pct {
+            return WorstSelection::Threshold(threshold);
+        }
+
+        let n_worst = self
+            .n_worst
+            .unwrap_or_else(|| env_setting("N_WORST").unwrap_or(DEFAULT_N_WORST));
+
+        WorstSelection::Count(n_worst)
+    }
+}
+
+/// Collects ngram indices and their costs according to a [`WorstSelection`].
+#[derive(Clone, Debug)]
+pub struct WorstTracker {
+    selection: WorstSelection,
+    queue: DoublePriorityQueue<usize, OrderedFloat<f64>>,
+    candidates: Vec<(usize, f64)>,
+}
+
+impl WorstTracker {
+    pub fn new(selection: WorstSelection) -> Self {
+        Self {
+            selection,
+            queue: DoublePriorityQueue::new(),
+            candidates: Vec::new(),
+        }
+    }
+
+    /// Whether any ngrams are tracked at all
+    pub fn is_enabled(&self) -> bool {
+        self.selection != WorstSelection::Disabled
+    }
+
+    /// Register the cost of the ngram with index `i`.
+    #[inline]
+    pub fn push(&mut self, i: usize, cost: f64) {
+        match self.selection {
+            WorstSelection::Disabled => {}
+            WorstSelection::Count(n) => {
+                self.queue.push(i, OrderedFloat(cost));
+                if self.queue.len() > n {
+                    self.queue.pop_min();
+                }
+            }
+            // the total cost is only known at the end, so all candidates have to be kept
+            WorstSelection::Threshold(_) => {
+                if cost > 0.0 {
+                    self.candidates.push((i, cost));
+                }
+            }
+        }
+    }
+
+    /// The selected ngrams with positive cost, sorted by decreasing cost.
+    pub fn into_sorted(self, total_cost: f64) -> Vec<(usize, f64)> {
+        match self.selection {
+            WorstSelection::Disabled => Vec::new(),
+            WorstSelection::Count(_) => self
+                .queue
+                .into_sorted_iter()
+                .rev()
+                .map(|(i, cost)| (i, cost.into_inner()))
+                .filter(|(_, cost)| *cost > 0.0)
+                .collect(),
+            WorstSelection::Threshold(threshold) => {
+                let mut selected: Vec<(usize, f64)> = self
+                    .candidates
+                    .into_iter()
+                    .filter(|(_, cost)| super::utils::to_percentage(*cost, total_cost) > threshold)
+                    .collect();
+                selected.sort_by_key(|(_, cost)| std::cmp::Reverse(OrderedFloat(*cost)));
+                selected
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracked(selection: WorstSelection, costs: &[f64]) -> Vec<(usize, f64)> {
+        let mut worst = WorstTracker::new(selection);
+        for (i, cost) in costs.iter().enumerate() {
+            worst.push(i, *cost);
+        }
+        worst.into_sorted(costs.iter().sum())
+    }
+
+    #[test]
+    fn threshold_takes_precedence_over_count() {
+        let both = WorstParameters {
+            n_worst: Some(5),
+            worst_threshold_pct: Some(1.0),
+        };
+        assert_eq!(both.configured_selection(), WorstSelection::Threshold(1.0));
+
+        let count = WorstParameters {
+            n_worst: Some(5),
+            worst_threshold_pct: None,
+        };
+        assert_eq!(count.configured_selection(), WorstSelection::Count(5));
+    }
+
+    #[test]
+    fn count_keeps_the_most_expensive() {
+        let costs = [1.0, 5.0, 0.0, 3.0, 4.0, -2.0];
+        assert_eq!(
+            tracked(WorstSelection::Count(3), &costs),
+            vec![(1, 5.0), (4, 4.0), (3, 3.0)]
+        );
+        // only positive costs are listed
+        assert_eq!(
+            tracked(WorstSelection::Count(10), &costs),
+            vec![(1, 5.0), (4, 4.0), (3, 3.0), (0, 1.0)]
+        );
+    }
+
+    #[test]
+    fn threshold_keeps_all_above_share_sorted_by_cost() {
+        // total 100: shares 2%, 50%, 0.5%, 10%, 37.5%
+        let costs = [2.0, 50.0, 0.5, 10.0, 37.5];
+        assert_eq!(
+            tracked(WorstSelection::Threshold(1.0), &costs),
+            vec![(1, 50.0), (4, 37.5), (3, 10.0), (0, 2.0)]
+        );
+        assert_eq!(
+            tracked(WorstSelection::Threshold(10.0), &costs),
+            vec![(1, 50.0), (4, 37.5)]
+        );
+        assert_eq!(tracked(WorstSelection::Threshold(60.0), &costs), vec![]);
+    }
+
+    #[test]
+    fn threshold_of_non_positive_total_lists_nothing() {
+        assert_eq!(
+            tracked(WorstSelection::Threshold(1.0), &[1.0, -1.0]),
+            vec![]
+        );
+        assert_eq!(
+            tracked(WorstSelection::Threshold(1.0), &[1.0, -3.0]),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn disabled_tracks_nothing() {
+        let worst = WorstTracker::new(WorstSelection::Disabled);
+        assert!(!worst.is_enabled());
+        assert_eq!(tracked(WorstSelection::Disabled, &[1.0, 2.0]), vec![]);
+    }
+}
@@ -78,6 +78,9 @@ pub struct MappedTrigrams<'s> {
 /// Provides ngrams in terms of a [`Layout`]'s [`LayerKey`]s.
 pub trait NgramMapper: Send + Sync + NgramMapperClone + fmt::Debug {
     fn map_unigrams<'s>(&self, layout: &'s Layout) -> MappedUnigrams<'s>;
+    /// Like [`NgramMapper::map_unigrams`], but without resolving the modifiers of higher-layer
+    /// symbols (these keep their layer).
+    fn map_unigrams_unsplit<'s>(&self, layout: &'s Layout) -> MappedUnigrams<'s>;
     fn map_bigrams<'s>(&self, layout: &'s Layout) -> MappedBigrams<'s>;
     fn map_trigrams<'s>(&self, layout: &'s Layout) -> MappedTrigrams<'s>;
 }
@@ -10,6 +10,7 @@ use crate::ngrams::{Bigrams, Trigrams, Unigrams};
 use keyboard_layout::layout::Layout;
 
 use serde::Deserialize;
+use std::sync::Arc;
 
 /// Configuration parameters for the modifier splitting process.
 #[derive(Clone, Deserialize, Debug)]
@@ -20,6 +21,15 @@ pub struct SplitModifiersConfig {
     pub same_key_mod_factor: f64,
 }
 
+impl Default for SplitModifiersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            same_key_mod_factor: 0.03125,
+        }
+    }
+}
+
 /// Configuration parameters for the [`OnDemandNgramMapper`].
 #[derive(Clone, Deserialize, Debug)]
 pub struct NgramMapperConfig {
@@ -29,12 +39,21 @@ pub struct NgramMapperConfig {
     pub exclude_line_breaks: bool,
 }
 
+impl Default for NgramMapperConfig {
+    fn default() -> Self {
+        Self {
+            split_modifiers: SplitModifiersConfig::default(),
+            exclude_line_breaks: true,
+        }
+    }
+}
+
 /// Implements the [`NgramMapper`] trait for generating ngrams in terms of [`LayerKey`]s for a given [`Layout`].
 #[derive(Clone, Debug)]
 pub struct OnDemandNgramMapper {
-    unigrams: Unigrams,
-    bigrams: Bigrams,
-    trigrams: Trigrams,
+    unigrams: Arc<Unigrams>,
+    bigrams: Arc<Bigrams>,
+    trigrams: Arc<Trigrams>,
     unigram_mapper: OnDemandUnigramMapper,
     bigram_mapper: OnDemandBigramMapper,
     trigram_mapper: OnDemandTrigramMapper,
@@ -48,6 +67,22 @@ impl OnDemandNgramMapper {
         bigrams: Bigrams,
         trigrams: Trigrams,
         config: NgramMapperConfig,
+    ) -> Self {
+        Self::with_shared_ngrams(
+            Arc::new(unigrams),
+            Arc::new(bigrams),
+            Arc::new(trigrams),
+            config,
+        )
+    }
+
+    /// Generate a [`OnDemandNgramMapper`] with char-based ngrams shared with other mappers,
+    /// e.g. those of a [`Corpus`](crate::corpus::Corpus).
+    pub fn with_shared_ngrams(
+        unigrams: Arc<Unigrams>,
+        bigrams: Arc<Bigrams>,
+        trigrams: Arc<Trigrams>,
+        config: NgramMapperConfig,
     ) -> Self {
         Self {
             unigrams,
@@ -77,6 +112,20 @@ impl NgramMapper for OnDemandNgramMapper {
         }
     }
 
+    fn map_unigrams_unsplit<'s>(&self, layout: &'s Layout) -> MappedUnigrams<'s> {
+        let (key_indices, weight_not_found) = self
+            .unigram_mapper
+            .layerkey_indices_unsplit(&self.unigrams, layout);
+        let weight_found = self.unigrams.total_weight() - weight_not_found;
+        let grams = OnDemandUnigramMapper::get_layerkeys(&key_indices, layout);
+
+        MappedUnigrams {
+            grams,
+            weight_not_found,
+            weight_found,
+        }
+    }
+
     fn map_bigrams<'s>(&self, layout: &'s Layout) -> MappedBigrams<'s> {
         // map char-based bigrams to LayerKeyIndex
         let (key_indices, weight_not_found) = self.bigram_mapper.layerkey_indices(
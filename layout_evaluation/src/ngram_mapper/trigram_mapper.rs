@@ -138,7 +138,6 @@ impl OnDemandTrigramMapper {
     /// of the involved base-keys and modifiers. Keys from the latter parts of the trigram will always be after
     /// former ones and modifers always come before their base key. The number of generated trigrams from a single
     /// trigram can be large (tens of trigrams) if multiple symbols of the trigram are accessed using multiple modifiers.
-
     // this is one of the most intensive functions of the layout evaluation
     fn process_hold_modifiers(
         &self,
@@ -69,6 +69,18 @@ impl OnDemandUnigramMapper {
         (unigram_keys, not_found_weight)
     }
 
+    /// For a given [`Layout`] generate [`LayerKeyIndex`]-based unigrams without resolving any
+    /// modifiers, i.e. higher-layer symbols keep their layer.
+    pub fn layerkey_indices_unsplit(
+        &self,
+        unigrams: &Unigrams,
+        layout: &Layout,
+    ) -> (UnigramIndices, f64) {
+        let (unigram_keys_vec, not_found_weight) = map_unigrams(unigrams, layout);
+
+        (unigram_keys_vec.into_iter().collect(), not_found_weight)
+    }
+
     /// Resolve &[`LayerKey`] references for [`LayerKeyIndex`]
     pub fn get_layerkeys<'s>(
         unigrams: &UnigramIndices,
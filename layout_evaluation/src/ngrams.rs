@@ -7,6 +7,8 @@ use crate::ngram_mapper::common::NgramMap;
 use ahash::AHashMap;
 use anyhow::Result;
 use serde::Deserialize;
+use std::iter::FromIterator;
+#[cfg(feature = "fs")]
 use std::{
     fs::{self, create_dir_all, File},
     io::{BufWriter, Write},
@@ -71,11 +73,29 @@ pub struct Unigrams {
     pub grams: AHashMap<char, f64>,
 }
 
-fn process_special_characters(s: &str) -> String {
-    s.replace("\\n", "\n").replace("\\\\", "\\")
+/// Unescape "\\n" and "\\\\" in one pass, such that an escaped backslash followed by an "n" does
+/// not turn into a newline
+pub(crate) fn process_special_characters(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some('n')) => {
+                chars.next();
+                result.push('\n');
+            }
+            ('\\', Some('\\')) => {
+                chars.next();
+                result.push('\\');
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
 }
 
-fn process_special_characters_inverse(s: &str) -> String {
+pub(crate) fn process_special_characters_inverse(s: &str) -> String {
     s.replace('\\', "\\\\").replace('\n', "\\n")
 }
 
@@ -113,6 +133,7 @@ impl Unigrams {
     }
 
     /// Read unigrams and weights from a file containing lines with unigrams and their weights.
+    #[cfg(feature = "fs")]
     pub fn from_file(filename: &str) -> Result<Self> {
         let data = fs::read_to_string(filename)?;
         Unigrams::from_frequencies_str(&data)
@@ -160,6 +181,7 @@ impl Unigrams {
     }
 
     /// Save frequencies to file
+    #[cfg(feature = "fs")]
     pub fn save_frequencies<T: AsRef<Path>>(&self, filename: T) -> Result<(), String> {
         let p = filename.as_ref();
         create_dir_all(p.parent().unwrap()).map_err(|e| {
@@ -191,6 +213,16 @@ impl Unigrams {
     }
 }
 
+impl FromIterator<(char, f64)> for Unigrams {
+    /// Collect unigrams from weighted unigrams, adding up the weights of duplicates.
+    fn from_iter<I: IntoIterator<Item = (char, f64)>>(iter: I) -> Self {
+        let mut grams = AHashMap::default();
+        iter.into_iter()
+            .for_each(|(c, w)| grams.insert_or_add_weight(c, w));
+        Self { grams }
+    }
+}
+
 /// Holds a hashmap of bigrams (two chars) with corresponding frequency (here often called "weight").
 #[derive(Clone, Debug)]
 pub struct Bigrams {
@@ -232,6 +264,7 @@ impl Bigrams {
     }
 
     /// Read bigrams and weights from a file containing lines with bigrams and their weights.
+    #[cfg(feature = "fs")]
     pub fn from_file(filename: &str) -> Result<Self> {
         let data = fs::read_to_string(filename)?;
         Bigrams::from_frequencies_str(&data)
@@ -285,6 +318,7 @@ impl Bigrams {
     }
 
     /// Save frequencies to file
+    #[cfg(feature = "fs")]
     pub fn save_frequencies<T: AsRef<Path>>(&self, filename: T) -> Result<(), String> {
         let p = filename.as_ref();
         create_dir_all(p.parent().unwrap()).map_err(|e| {
@@ -318,6 +352,16 @@ impl Bigrams {
     }
 }
 
+impl FromIterator<((char, char), f64)> for Bigrams {
+    /// Collect bigrams from weighted bigrams, adding up the weights of duplicates.
+    fn from_iter<I: IntoIterator<Item = ((char, char), f64)>>(iter: I) -> Self {
+        let mut grams = AHashMap::default();
+        iter.into_iter()
+            .for_each(|(c, w)| grams.insert_or_add_weight(c, w));
+        Self { grams }
+    }
+}
+
 /// Holds a hashmap of trigrams (three chars) with corresponding frequency (here often called "weight").
 #[derive(Clone, Debug)]
 pub struct Trigrams {
@@ -362,6 +406,7 @@ impl Trigrams {
     }
 
     /// Read trigrams and weights from a file containing lines with trigrams and their weights.
+    #[cfg(feature = "fs")]
     pub fn from_file(filename: &str) -> Result<Self> {
         let data = fs::read_to_string(filename)?;
         Trigrams::from_frequencies_str(&data)
@@ -416,6 +461,7 @@ impl Trigrams {
     }
 
     /// Save frequencies to file
+    #[cfg(feature = "fs")]
     pub fn save_frequencies<T: AsRef<Path>>(&self, filename: T) -> Result<(), String> {
         let p = filename.as_ref();
         create_dir_all(p.parent().unwrap()).map_err(|e| {
@@ -454,3 +500,13 @@ impl Trigrams {
         Self { grams }
     }
 }
+
+impl FromIterator<((char, char, char), f64)> for Trigrams {
+    /// Collect trigrams from weighted trigrams, adding up the weights of duplicates.
+    fn from_iter<I: IntoIterator<Item = ((char, char, char), f64)>>(iter: I) -> Self {
+        let mut grams = AHashMap::default();
+        iter.into_iter()
+            .for_each(|(c, w)| grams.insert_or_add_weight(c, w));
+        Self { grams }
+    }
+}
@@ -0,0 +1,52 @@
+//! The `optimizer` module provides simple local-search optimizers that improve a [`Layout`] by
+//! swapping keys ([`Layout::swap_keys_new`]) and scoring the candidates with a
+//! [`LayoutEvaluator`]. They are meant for embedding an optimization in other tools; the
+//! configurable optimizers of the binaries (with weight schedules, early stopping, position
+//! masks etc.) live in the `layout_optimization_*` crates.
+//!
+//! Only non-fixed keys are swapped, and only if the swap satisfies the
+//! [`constraints::ConstraintSet`] of the optimizer. The candidates are scored incrementally
+//! ([`LayoutEvaluator::update_swap`]), so for additive metrics, the cost of an iteration mostly
+//! depends on the ngrams containing the swapped symbols.
+
+pub mod constraints;
+pub mod hill_climb;
+pub mod sa;
+
+use crate::{
+    corpus::Corpus,
+    evaluator::{LayoutEvaluator, LayoutScore},
+};
+
+use keyboard_layout::layout::Layout;
+
+/// Receives the progress of an optimization.
+pub trait ProgressCallback {
+    /// Called after each iteration with the cost of the current layout and the temperature
+    /// (0 for optimizers without one).
+    fn progress(&mut self, iteration: usize, cost: f64, temperature: f64);
+}
+
+impl<F: FnMut(usize, f64, f64)> ProgressCallback for F {
+    fn progress(&mut self, iteration: usize, cost: f64, temperature: f64) {
+        self(iteration, cost, temperature)
+    }
+}
+
+/// A layout together with its evaluation
+#[derive(Clone, Debug)]
+pub struct ScoredLayout {
+    pub layout: Layout,
+    pub score: LayoutScore,
+}
+
+impl ScoredLayout {
+    fn evaluate(layout: Layout, evaluator: &LayoutEvaluator, corpus: &Corpus) -> Self {
+        let score = evaluator.evaluate(&layout, corpus);
+        Self { layout, score }
+    }
+
+    pub fn cost(&self) -> f64 {
+        self.score.total_cost()
+    }
+}
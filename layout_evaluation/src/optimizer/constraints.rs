@@ -0,0 +1,160 @@
+//! Constraints restricting which key swaps the optimizers may propose, e.g. for keeping a
+//! symbol on the home row of an index finger. A swap is only proposed if the layout after the
+//! swap satisfies all constraints concerning the swapped symbols. The initial layout is not
+//! checked, i.e. a symbol placed in violation of a constraint is not moved for that reason.
+
+use keyboard_layout::{
+    key::{Finger, Hand},
+    keyboard::KeyIndex,
+    layout::Layout,
+};
+
+/// A restriction of the positions of keys or symbols
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutConstraint {
+    /// The symbols of this key do not move
+    FixedPosition(KeyIndex),
+    /// The symbol stays on keys of this finger (of either hand)
+    FixedFinger(char, Finger),
+    /// The symbol stays on keys of this hand
+    FixedHand(char, Hand),
+    /// The symbol is never moved to this key
+    ExcludePosition(char, KeyIndex),
+}
+
+impl LayoutConstraint {
+    /// Whether moving the symbols of key `from` to key `to` in `layout` satisfies the
+    /// constraint
+    fn allows_move(&self, layout: &Layout, from: KeyIndex, to: KeyIndex) -> bool {
+        let moves = |c: &char| layout.get_key_index_for_symbol(c) == Some(from);
+        let target = &layout.keyboard.keys[to as usize];
+
+        match self {
+            LayoutConstraint::FixedPosition(k) => *k != from,
+            LayoutConstraint::FixedFinger(c, finger) => !moves(c) || target.finger == *finger,
+            LayoutConstraint::FixedHand(c, hand) => !moves(c) || target.hand == *hand,
+            LayoutConstraint::ExcludePosition(c, k) => !moves(c) || *k != to,
+        }
+    }
+}
+
+/// The constraints of an optimization (none by default)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConstraintSet {
+    constraints: Vec<LayoutConstraint>,
+}
+
+impl ConstraintSet {
+    pub fn new(constraints: Vec<LayoutConstraint>) -> Self {
+        Self { constraints }
+    }
+
+    pub fn constraints(&self) -> &[LayoutConstraint] {
+        &self.constraints
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty()
+    }
+
+    /// Whether swapping the symbols of the keys `k1` and `k2` in `layout` satisfies all
+    /// constraints
+    pub fn allows_swap(&self, layout: &Layout, k1: KeyIndex, k2: KeyIndex) -> bool {
+        self.constraints
+            .iter()
+            .all(|c| c.allows_move(layout, k1, k2) && c.allows_move(layout, k2, k1))
+    }
+
+    /// Whether the allowed swaps depend on the positions of the symbols, i.e. whether there
+    /// are constraints of symbols. Otherwise, [`ConstraintSet::allowed_swaps`] gives the same
+    /// swaps for all layouts.
+    pub fn depends_on_symbols(&self) -> bool {
+        self.constraints
+            .iter()
+            .any(|c| !matches!(c, LayoutConstraint::FixedPosition(_)))
+    }
+
+    /// All swaps of two of `keys` in `layout` that satisfy the constraints
+    pub fn allowed_swaps(&self, layout: &Layout, keys: &[KeyIndex]) -> Vec<(KeyIndex, KeyIndex)> {
+        keys.iter()
+            .enumerate()
+            .flat_map(|(i, k1)| keys[i + 1..].iter().map(move |k2| (*k1, *k2)))
+            .filter(|(k1, k2)| self.allows_swap(layout, *k1, *k2))
+            .collect()
+    }
+}
+
+/// The allowed swaps of the current layout of an optimization. They are only recomputed for a
+/// new layout if they depend on the positions of the symbols.
+#[derive(Clone, Debug)]
+pub(crate) struct AllowedSwaps<'c> {
+    constraints: &'c ConstraintSet,
+    keys: Vec<KeyIndex>,
+    swaps: Vec<(KeyIndex, KeyIndex)>,
+}
+
+impl<'c> AllowedSwaps<'c> {
+    /// The swaps of two of `keys` in `layout` allowed by `constraints`
+    pub(crate) fn new(
+        constraints: &'c ConstraintSet,
+        layout: &Layout,
+        keys: Vec<KeyIndex>,
+    ) -> Self {
+        let swaps = constraints.allowed_swaps(layout, &keys);
+        Self {
+            constraints,
+            keys,
+            swaps,
+        }
+    }
+
+    pub(crate) fn swaps(&self) -> &[(KeyIndex, KeyIndex)] {
+        &self.swaps
+    }
+
+    /// Update the swaps for `layout`, the new current layout
+    pub(crate) fn update(&mut self, layout: &Layout) {
+        if self.constraints.depends_on_symbols() {
+            self.swaps = self.constraints.allowed_swaps(layout, &self.keys);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::toy_layout;
+
+    /// The left pinky has "a" to "e", the left ring finger "f" to "j"
+    fn layout() -> Layout {
+        toy_layout("abcdefghij")
+    }
+
+    #[test]
+    fn swaps_without_symbol_constraints_are_kept() {
+        let layout = layout();
+        let constraints = ConstraintSet::new(vec![LayoutConstraint::FixedPosition(0)]);
+        assert!(!constraints.depends_on_symbols());
+
+        let mut swaps = AllowedSwaps::new(&constraints, &layout, vec![0, 1, 2]);
+        assert_eq!(swaps.swaps(), &[(1, 2)]);
+        swaps.update(&layout.swap_keys_new(1, 2).unwrap());
+        assert_eq!(swaps.swaps(), &[(1, 2)]);
+    }
+
+    #[test]
+    fn swaps_with_symbol_constraints_follow_the_symbols() {
+        let layout = layout();
+        // "a" stays on the pinky (keys 0 to 4)
+        let constraints =
+            ConstraintSet::new(vec![LayoutConstraint::FixedFinger('a', Finger::Pinky)]);
+        assert!(constraints.depends_on_symbols());
+
+        let mut swaps = AllowedSwaps::new(&constraints, &layout, vec![0, 1, 5]);
+        assert_eq!(swaps.swaps(), &[(0, 1), (1, 5)]);
+        // "a" on key 1 now
+        swaps.update(&layout.swap_keys_new(0, 1).unwrap());
+        assert_eq!(swaps.swaps(), &[(0, 1), (0, 5)]);
+    }
+}
@@ -0,0 +1,315 @@
+//! Hill climbing: in each step, all swaps of two keys are evaluated and the best improvement is
+//! applied, until no swap improves the layout any more. Then the climb is restarted from the
+//! best layout found so far, perturbed by `restart_swaps` swaps according to the
+//! [`RestartStrategy`]. Only swaps allowed by the [`ConstraintSet`] are considered.
+//!
+//! The candidates are scored incrementally with [`LayoutEvaluator::update_swap`]; only the best
+//! layout is evaluated completely in the end.
+
+use super::{
+    constraints::{AllowedSwaps, ConstraintSet},
+    ProgressCallback, ScoredLayout,
+};
+use crate::{
+    corpus::Corpus,
+    evaluator::{cost_delta, incremental::IncrementalScore, LayoutEvaluator},
+};
+
+use keyboard_layout::{keyboard::KeyIndex, layout::Layout};
+
+use anyhow::Result;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+/// How the layout is perturbed before a restart of the climb
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RestartStrategy {
+    /// Swap random keys
+    Random,
+    /// Move the keys contributing the most cost (see [`LayoutEvaluator::key_costs`]) to random
+    /// new positions, the most expensive one first
+    WorstKeyFirst,
+}
+
+#[derive(Clone, Debug)]
+pub struct HillClimber {
+    /// Number of climbs after the first one
+    pub max_restarts: usize,
+    pub restart_strategy: RestartStrategy,
+    /// Number of swaps perturbing the best layout before a restart (3 by default)
+    pub restart_swaps: usize,
+    /// Seed of the random number generator (the same seed gives the same result)
+    pub seed: u64,
+    pub constraints: ConstraintSet,
+}
+
+impl HillClimber {
+    pub fn new(max_restarts: usize, restart_strategy: RestartStrategy, seed: u64) -> Self {
+        Self {
+            max_restarts,
+            restart_strategy,
+            restart_swaps: 3,
+            seed,
+            constraints: ConstraintSet::default(),
+        }
+    }
+
+    /// Perturb the best layout with `restart_swaps` swaps before a restart
+    pub fn with_restart_swaps(mut self, restart_swaps: usize) -> Self {
+        self.restart_swaps = restart_swaps;
+        self
+    }
+
+    /// Restrict the swaps to those allowed by `constraints`
+    pub fn with_constraints(mut self, constraints: ConstraintSet) -> Self {
+        self.constraints = constraints;
+        self
+    }
+
+    /// Optimize `layout` with respect to the ngrams of `corpus`. Returns the best layout found
+    /// together with its score.
+    ///
+    /// Progress is reported after each applied swap (with temperature 0).
+    pub fn optimize(
+        &self,
+        evaluator: &LayoutEvaluator,
+        corpus: &Corpus,
+        layout: Layout,
+        progress: &mut dyn ProgressCallback,
+    ) -> Result<ScoredLayout> {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(self.seed);
+        let keys = layout.non_fixed_key_indices();
+        if keys.len() < 2 {
+            return Ok(ScoredLayout::evaluate(layout, evaluator, corpus));
+        }
+
+        let mut swaps = AllowedSwaps::new(&self.constraints, &layout, keys);
+        let mut best = evaluator.evaluate_incremental(layout, corpus);
+        let mut iteration = 0;
+        for restart in 0..=self.max_restarts {
+            let mut current = best.clone();
+            swaps.update(current.layout());
+            if restart > 0 {
+                if swaps.swaps().is_empty() {
+                    break;
+                }
+                current = self.restart(current, &mut swaps, evaluator, corpus, &mut rng)?;
+            }
+
+            while let Some(improved) = self.best_swap(&current, swaps.swaps(), evaluator)? {
+                current = improved;
+                swaps.update(current.layout());
+                progress.progress(iteration, current.cost(), 0.0);
+                iteration += 1;
+            }
+
+            if cost_delta(current.cost(), best.cost()) < 0.0 {
+                best = current;
+            }
+        }
+
+        // evaluate completely for the messages of all metrics
+        Ok(ScoredLayout::evaluate(
+            best.into_layout(),
+            evaluator,
+            corpus,
+        ))
+    }
+
+    /// Perturb `best` by `restart_swaps` allowed swaps according to the restart strategy
+    fn restart(
+        &self,
+        best: IncrementalScore,
+        swaps: &mut AllowedSwaps,
+        evaluator: &LayoutEvaluator,
+        corpus: &Corpus,
+        rng: &mut Xoshiro256PlusPlus,
+    ) -> Result<IncrementalScore> {
+        let key_costs = match self.restart_strategy {
+            RestartStrategy::Random => Vec::new(),
+            RestartStrategy::WorstKeyFirst => evaluator.key_costs(best.layout(), corpus),
+        };
+
+        let mut current = best;
+        let mut moved: Vec<KeyIndex> = Vec::with_capacity(self.restart_swaps);
+        for _ in 0..self.restart_swaps {
+            let (k1, k2) = match (self.restart_strategy, swaps.swaps()) {
+                (_, []) => break,
+                (RestartStrategy::Random, swaps) => swaps[rng.random_range(0..swaps.len())],
+                (RestartStrategy::WorstKeyFirst, swaps) => {
+                    // the most expensive key (of the original layout) that was not moved yet
+                    let cost = |k: KeyIndex| key_costs[k as usize];
+                    let mut worst: Option<KeyIndex> = None;
+                    for k in swaps.iter().flat_map(|(k1, k2)| [*k1, *k2]) {
+                        if !moved.contains(&k)
+                            && worst.map_or(true, |w| cost_delta(cost(k), cost(w)) > 0.0)
+                        {
+                            worst = Some(k);
+                        }
+                    }
+
+                    let worst_swaps: Vec<(KeyIndex, KeyIndex)> = match worst {
+                        Some(worst) => swaps
+                            .iter()
+                            .filter(|(k1, k2)| *k1 == worst || *k2 == worst)
+                            .copied()
+                            .collect(),
+                        None => swaps.to_vec(),
+                    };
+                    worst_swaps[rng.random_range(0..worst_swaps.len())]
+                }
+            };
+
+            moved.extend([k1, k2]);
+            current = evaluator.update_swap(&current, k1, k2)?;
+            swaps.update(current.layout());
+        }
+
+        Ok(current)
+    }
+
+    /// The best layout that differs from `current` by one of `swaps` and costs less, if any
+    fn best_swap(
+        &self,
+        current: &IncrementalScore,
+        swaps: &[(KeyIndex, KeyIndex)],
+        evaluator: &LayoutEvaluator,
+    ) -> Result<Option<IncrementalScore>> {
+        let mut best: Option<IncrementalScore> = None;
+        for (k1, k2) in swaps.iter() {
+            let candidate = evaluator.update_swap(current, *k1, *k2)?;
+            let reference = best.as_ref().unwrap_or(current);
+            if cost_delta(candidate.cost(), reference.cost()) < 0.0 {
+                best = Some(candidate);
+            }
+        }
+
+        Ok(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metrics::{bigram_metrics::sfb, unigram_metrics::key_costs},
+        results::NormalizationType,
+    };
+
+    use keyboard_layout::test_utils::toy_layout;
+
+    const TEXT: &str = "the quick brown fox jumps over the lazy dog";
+
+    fn layout() -> Layout {
+        toy_layout("abcdefghijklmnopqrstuvwxyz")
+    }
+
+    fn evaluator(layout: &Layout) -> LayoutEvaluator {
+        let sfb: sfb::Parameters =
+            serde_yaml::from_str("{default_cost: 1.0, costs: {}, ignore_thumbs: false}").unwrap();
+        LayoutEvaluator::builder()
+            .with_keyboard(layout.keyboard.clone())
+            .with_unigram_metric(
+                Box::new(key_costs::KeyCost::new(&key_costs::Parameters {})),
+                1.0,
+                NormalizationType::WeightFound(1.0),
+            )
+            .with_bigram_metric(
+                Box::new(sfb::Sfb::new(&sfb)),
+                5.0,
+                NormalizationType::WeightFound(1.0),
+            )
+            .build()
+            .unwrap()
+    }
+
+    /// The number of keys whose symbols differ in both layouts
+    fn moved_keys(layout1: &Layout, layout2: &Layout) -> usize {
+        layout1
+            .as_text()
+            .chars()
+            .zip(layout2.as_text().chars())
+            .filter(|(c1, c2)| c1 != c2)
+            .count()
+    }
+
+    fn restart(climber: &HillClimber) -> (IncrementalScore, IncrementalScore) {
+        let layout = layout();
+        let evaluator = evaluator(&layout);
+        let corpus = Corpus::from_text(TEXT);
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(climber.seed);
+
+        let mut swaps = AllowedSwaps::new(
+            &climber.constraints,
+            &layout,
+            layout.non_fixed_key_indices(),
+        );
+        let best = evaluator.evaluate_incremental(layout, &corpus);
+        let perturbed = climber
+            .restart(best.clone(), &mut swaps, &evaluator, &corpus, &mut rng)
+            .unwrap();
+
+        (best, perturbed)
+    }
+
+    #[test]
+    fn restarts_apply_the_configured_number_of_swaps() {
+        for strategy in [RestartStrategy::Random, RestartStrategy::WorstKeyFirst] {
+            let climber = HillClimber::new(1, strategy, 604);
+
+            let (best, perturbed) = restart(&climber.clone().with_restart_swaps(0));
+            assert_eq!(moved_keys(best.layout(), perturbed.layout()), 0);
+
+            let (best, perturbed) = restart(&climber.clone().with_restart_swaps(1));
+            assert_eq!(moved_keys(best.layout(), perturbed.layout()), 2);
+
+            // the worst-key swaps never move a key twice
+            let (best, perturbed) = restart(&climber.with_restart_swaps(3));
+            let moved = moved_keys(best.layout(), perturbed.layout());
+            match strategy {
+                RestartStrategy::Random => assert!((3..=6).contains(&moved), "{}", moved),
+                RestartStrategy::WorstKeyFirst => assert_eq!(moved, 6),
+            }
+        }
+    }
+
+    #[test]
+    fn worst_key_is_moved_first() {
+        let climber =
+            HillClimber::new(1, RestartStrategy::WorstKeyFirst, 604).with_restart_swaps(1);
+        let (best, perturbed) = restart(&climber);
+
+        let key_costs = evaluator(best.layout()).key_costs(best.layout(), &Corpus::from_text(TEXT));
+        let worst = (0..key_costs.len())
+            .max_by(|k1, k2| key_costs[*k1].partial_cmp(&key_costs[*k2]).unwrap())
+            .unwrap();
+        assert_ne!(
+            best.layout().as_text().chars().nth(worst),
+            perturbed.layout().as_text().chars().nth(worst)
+        );
+    }
+
+    #[test]
+    fn climbs_are_reproducible() {
+        let optimize = |strategy: RestartStrategy| {
+            let layout = layout();
+            let evaluator = evaluator(&layout);
+            let corpus = Corpus::from_text(TEXT);
+            let initial_cost = evaluator.evaluate(&layout, &corpus).total_cost();
+
+            let best = HillClimber::new(3, strategy, 7)
+                .optimize(&evaluator, &corpus, layout, &mut |_, _, _| {})
+                .unwrap();
+            assert!(best.cost() < initial_cost);
+            assert!(
+                (best.cost() - evaluator.evaluate(&best.layout, &corpus).total_cost()).abs() < 1e-9
+            );
+
+            best.layout.as_text()
+        };
+
+        for strategy in [RestartStrategy::Random, RestartStrategy::WorstKeyFirst] {
+            assert_eq!(optimize(strategy), optimize(strategy));
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Simulated annealing: in each iteration, two random keys are swapped. The swap is kept if it
+//! lowers the cost, otherwise with probability `exp(-delta / temperature)`. The temperature
+//! starts at `initial_temp` and is multiplied by `cooling_rate` after each iteration. Only swaps
+//! allowed by the [`ConstraintSet`] are proposed.
+//!
+//! The candidates are scored incrementally with [`LayoutEvaluator::update_swap`]; only the best
+//! layout is evaluated completely in the end.
+
+use super::{
+    constraints::{AllowedSwaps, ConstraintSet},
+    ProgressCallback, ScoredLayout,
+};
+use crate::{
+    corpus::Corpus,
+    evaluator::{cost_delta, LayoutEvaluator},
+};
+
+use keyboard_layout::layout::Layout;
+
+use anyhow::Result;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+#[derive(Clone, Debug)]
+pub struct SimulatedAnnealing {
+    /// Temperature of the first iteration
+    pub initial_temp: f64,
+    /// Factor the temperature is multiplied with after each iteration (e.g. 0.999)
+    pub cooling_rate: f64,
+    pub max_iterations: usize,
+    /// Seed of the random number generator (the same seed gives the same result)
+    pub seed: u64,
+    pub constraints: ConstraintSet,
+}
+
+impl SimulatedAnnealing {
+    pub fn new(initial_temp: f64, cooling_rate: f64, max_iterations: usize, seed: u64) -> Self {
+        Self {
+            initial_temp,
+            cooling_rate,
+            max_iterations,
+            seed,
+            constraints: ConstraintSet::default(),
+        }
+    }
+
+    /// Restrict the swaps to those allowed by `constraints`
+    pub fn with_constraints(mut self, constraints: ConstraintSet) -> Self {
+        self.constraints = constraints;
+        self
+    }
+
+    /// Optimize `layout` with respect to the ngrams of `corpus`. Returns the best layout found
+    /// together with its score.
+    pub fn optimize(
+        &self,
+        evaluator: &LayoutEvaluator,
+        corpus: &Corpus,
+        layout: Layout,
+        progress: &mut dyn ProgressCallback,
+    ) -> Result<ScoredLayout> {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(self.seed);
+        let keys = layout.non_fixed_key_indices();
+
+        if keys.len() < 2 {
+            return Ok(ScoredLayout::evaluate(layout, evaluator, corpus));
+        }
+
+        let mut swaps = AllowedSwaps::new(&self.constraints, &layout, keys);
+        let mut current = evaluator.evaluate_incremental(layout, corpus);
+        let mut best = current.clone();
+
+        let mut temperature = self.initial_temp;
+        for iteration in 0..self.max_iterations {
+            let (k1, k2) = match swaps.swaps() {
+                [] => break,
+                swaps => swaps[rng.random_range(0..swaps.len())],
+            };
+            let candidate = evaluator.update_swap(&current, k1, k2)?;
+
+            // always draw, so that the random sequence does not depend on the costs
+            let r: f64 = rng.random();
+            let delta = cost_delta(candidate.cost(), current.cost());
+            let accept = delta <= 0.0 || (temperature > 0.0 && r < (-delta / temperature).exp());
+            if accept {
+                current = candidate;
+                swaps.update(current.layout());
+                if cost_delta(current.cost(), best.cost()) < 0.0 {
+                    best = current.clone();
+                }
+            }
+
+            progress.progress(iteration, current.cost(), temperature);
+            temperature *= self.cooling_rate;
+        }
+
+        // evaluate completely for the messages of all metrics
+        Ok(ScoredLayout::evaluate(
+            best.into_layout(),
+            evaluator,
+            corpus,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        metrics::{bigram_metrics::sfb, unigram_metrics::key_costs},
+        results::NormalizationType,
+    };
+
+    use keyboard_layout::test_utils::toy_layout;
+
+    fn evaluator(layout: &Layout) -> LayoutEvaluator {
+        let sfb: sfb::Parameters =
+            serde_yaml::from_str("{default_cost: 1.0, costs: {}, ignore_thumbs: false}").unwrap();
+        LayoutEvaluator::builder()
+            .with_keyboard(layout.keyboard.clone())
+            .with_unigram_metric(
+                Box::new(key_costs::KeyCost::new(&key_costs::Parameters {})),
+                1.0,
+                NormalizationType::WeightFound(1.0),
+            )
+            .with_bigram_metric(
+                Box::new(sfb::Sfb::new(&sfb)),
+                5.0,
+                NormalizationType::WeightFound(1.0),
+            )
+            .build()
+            .unwrap()
+    }
+
+    fn optimize(seed: u64, costs: &mut Vec<f64>) -> ScoredLayout {
+        let layout = toy_layout("abcdefghijklmnopqrstuvwxyz");
+        let evaluator = evaluator(&layout);
+        let corpus = Corpus::from_text("the quick brown fox jumps over the lazy dog");
+
+        SimulatedAnnealing::new(1.0, 0.99, 300, seed)
+            .optimize(&evaluator, &corpus, layout, &mut |_, cost, _| {
+                costs.push(cost)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn best_layout_is_scored_completely() {
+        let layout = toy_layout("abcdefghijklmnopqrstuvwxyz");
+        let evaluator = evaluator(&layout);
+        let corpus = Corpus::from_text("the quick brown fox jumps over the lazy dog");
+        let initial_cost = evaluator.evaluate(&layout, &corpus).total_cost();
+
+        let mut costs = Vec::new();
+        let best = optimize(1, &mut costs);
+
+        assert_eq!(costs.len(), 300);
+        assert!(best.cost() < initial_cost);
+        assert!(costs.iter().all(|cost| best.cost() <= cost + 1e-9));
+        assert!(
+            (best.cost() - evaluator.evaluate(&best.layout, &corpus).total_cost()).abs() < 1e-9
+        );
+        // the messages are not dropped
+        assert!(best
+            .score
+            .iter()
+            .flat_map(|results| results.metric_costs.iter())
+            .all(|mc| mc.core.message.is_some()));
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_result() {
+        let (mut costs1, mut costs2) = (Vec::new(), Vec::new());
+        let best1 = optimize(42, &mut costs1);
+        let best2 = optimize(42, &mut costs2);
+
+        assert_eq!(best1.layout.as_text(), best2.layout.as_text());
+        assert_eq!(costs1.len(), costs2.len());
+        for (cost1, cost2) in costs1.iter().zip(costs2.iter()) {
+            assert!((cost1 - cost2).abs() <= 1e-9 * cost1.abs());
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! The `output_style` module controls whether textual output (metric messages, result
+//! listings) contains ANSI styling.
+//!
+//! Metrics and results do not use `colored` directly but go through the helpers of this module,
+//! which return unstyled text once [`OutputStyle::Plain`] has been requested. This keeps output
+//! that is written to files or serialized as JSON free of escape codes.
+//!
+//! Command line tools select the style with a [`ColorConfig`] (see [`set_color_config`]).
+
+use anyhow::anyhow;
+use colored::{ColoredString, Colorize};
+use serde::Deserialize;
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// How textual output shall be formatted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStyle {
+    /// Use colors and text decorations (ANSI escape codes)
+    Styled,
+    /// Plain text without any escape codes
+    Plain,
+}
+
+impl Default for OutputStyle {
+    fn default() -> Self {
+        Self::Styled
+    }
+}
+
+/// When to use colors and text decorations in terminal output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorConfig {
+    /// Always use colors, even if stdout is not a terminal
+    Always,
+    /// Never use colors
+    Never,
+    /// Use colors if stdout is a terminal (respecting `NO_COLOR`, `CLICOLOR` and
+    /// `CLICOLOR_FORCE`)
+    Auto,
+}
+
+impl Default for ColorConfig {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+impl FromStr for ColorConfig {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            "auto" => Ok(Self::Auto),
+            _ => Err(anyhow!(
+                "Invalid color config '{}' (valid: always, never, auto)",
+                s
+            )),
+        }
+    }
+}
+
+impl ColorConfig {
+    /// The configuration corresponding to the currently configured output style.
+    pub fn current() -> Self {
+        match output_style() {
+            OutputStyle::Styled => Self::Always,
+            OutputStyle::Plain => Self::Never,
+        }
+    }
+
+    /// The output style this configuration results in.
+    pub fn output_style(self) -> OutputStyle {
+        let styled = match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => colored::control::ShouldColorize::from_env().should_colorize(),
+        };
+        if styled {
+            OutputStyle::Styled
+        } else {
+            OutputStyle::Plain
+        }
+    }
+}
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Set the output style for all subsequently generated output.
+///
+/// Requesting [`OutputStyle::Plain`] also disables `colored` globally, so that output
+/// of other crates (e.g. layout plots) is plain as well.
+pub fn set_output_style(style: OutputStyle) {
+    PLAIN.store(style == OutputStyle::Plain, Ordering::Relaxed);
+    match style {
+        OutputStyle::Plain => colored::control::set_override(false),
+        OutputStyle::Styled => colored::control::unset_override(),
+    }
+}
+
+/// Set the output style for all subsequently generated output according to `config`.
+///
+/// Call this once at the entry point of a command line tool, before any output is generated.
+pub fn set_color_config(config: ColorConfig) {
+    match config {
+        ColorConfig::Always => {
+            PLAIN.store(false, Ordering::Relaxed);
+            colored::control::set_override(true);
+        }
+        ColorConfig::Never => set_output_style(OutputStyle::Plain),
+        ColorConfig::Auto => set_output_style(config.output_style()),
+    }
+}
+
+/// The currently configured output style.
+pub fn output_style() -> OutputStyle {
+    if PLAIN.load(Ordering::Relaxed) {
+        OutputStyle::Plain
+    } else {
+        OutputStyle::Styled
+    }
+}
+
+/// Apply `style` to `text` unless plain output is requested.
+pub fn paint(text: &str, style: impl FnOnce(ColoredString) -> ColoredString) -> String {
+    match output_style() {
+        OutputStyle::Plain => text.to_string(),
+        OutputStyle::Styled => style(text.into()).to_string(),
+    }
+}
+
+/// Underlined text (used for category labels in metric messages)
+pub fn underline(text: &str) -> String {
+    paint(text, |s| s.underline())
+}
+
+/// Underlined text, unless `color` requests plain output. Unlike [`underline`], the globally
+/// configured output style is not considered.
+pub fn underline_with(text: &str, color: ColorConfig) -> String {
+    match color.output_style() {
+        OutputStyle::Plain => text.to_string(),
+        OutputStyle::Styled => format!("\x1b[4m{}\x1b[0m", text),
+    }
+}
+
+/// Bold text
+pub fn bold(text: &str) -> String {
+    paint(text, |s| s.bold())
+}
+
+/// Gray text (used for secondary information like percentages)
+pub fn gray(text: &str) -> String {
+    paint(text, |s| s.truecolor(150, 150, 150))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        corpus::Corpus,
+        evaluator::LayoutEvaluator,
+        metrics::bigram_metrics::{bigram_stats, sfb},
+        results::NormalizationType,
+    };
+
+    use keyboard_layout::test_utils::toy_layout;
+    use parking_lot::{const_mutex, Mutex};
+
+    /// Serializes the tests changing the global output style
+    static STYLE: Mutex<()> = const_mutex(());
+
+    const ESCAPE: &str = "\x1b[";
+
+    /// The printed and the JSON output of an evaluation with messages using underlined and gray
+    /// text, generated in the given style
+    fn evaluation_output(color: ColorConfig) -> (String, String) {
+        set_color_config(color);
+
+        let layout = toy_layout("bxayzc");
+        let sfb: sfb::Parameters = serde_yaml::from_str(
+            "
+            default_cost: 1.0
+            ignore_thumbs: false
+            costs: {}
+            ",
+        )
+        .unwrap();
+        let bigram_stats: bigram_stats::Parameters =
+            serde_yaml::from_str("ignore_thumbs: false").unwrap();
+        let evaluator = LayoutEvaluator::builder()
+            .with_keyboard(layout.keyboard.clone())
+            .with_bigram_metric(
+                Box::new(sfb::Sfb::new(&sfb)),
+                1.0,
+                NormalizationType::Fixed(1.0),
+            )
+            .with_bigram_metric(
+                Box::new(bigram_stats::BigramStats::new(&bigram_stats)),
+                0.0,
+                NormalizationType::Fixed(1.0),
+            )
+            .build()
+            .unwrap();
+
+        let score = evaluator.evaluate(&layout, &Corpus::from_text("abacab"));
+        let printed = score.to_string();
+        let json = serde_json::to_string(&score).unwrap();
+
+        set_output_style(OutputStyle::Styled);
+        colored::control::unset_override();
+
+        (printed, json)
+    }
+
+    #[test]
+    fn plain_output_has_no_escape_codes() {
+        let _lock = STYLE.lock();
+
+        let (printed, json) = evaluation_output(ColorConfig::Never);
+        assert!(printed.contains("SFB") && printed.contains("Bigram Statistics"));
+        assert!(!printed.contains(ESCAPE), "{:?}", printed);
+        assert!(
+            !json.contains(ESCAPE) && !json.contains("\\u001b"),
+            "{}",
+            json
+        );
+    }
+
+    #[test]
+    fn styled_output_has_escape_codes() {
+        let _lock = STYLE.lock();
+
+        let (printed, _) = evaluation_output(ColorConfig::Always);
+        assert!(printed.contains(ESCAPE), "{:?}", printed);
+    }
+
+    #[test]
+    fn underline_with_ignores_the_global_style() {
+        let _lock = STYLE.lock();
+
+        set_output_style(OutputStyle::Plain);
+        let styled = underline_with("Label", ColorConfig::Always);
+        set_output_style(OutputStyle::Styled);
+        let plain = underline_with("Label", ColorConfig::Never);
+        colored::control::unset_override();
+
+        assert_eq!(styled, "\x1b[4mLabel\x1b[0m");
+        assert_eq!(plain, "Label");
+    }
+}
@@ -0,0 +1,142 @@
+//! The `presets` module provides named effort models, i.e. complete evaluation parameters
+//! tuned for a typing style, as a starting point for users who do not want to tune all metric
+//! weights themselves.
+//!
+//! All presets are based on the bundled Svalboard configuration (`config/evaluation/sval.yml`)
+//! with a few metrics changed. A configuration file selects a preset with `preset: <name>`
+//! and may override any field of it:
+//!
+//! ```yaml
+//! preset: low-sfb
+//! metrics:
+//!   sfb:
+//!     weight: 300.0
+//! ```
+//!
+//! The override is merged into the preset's (deserialized) parameters: mappings are merged
+//! recursively, all other values (including lists) are replaced. Fields that the preset does
+//! not set are rejected, such that misspelled fields do not go unnoticed.
+
+use anyhow::{anyhow, bail, Result};
+use serde_yaml::Value;
+
+/// Key selecting a preset in a configuration file
+const PRESET_KEY: &str = "preset";
+
+/// Configuration all presets are based on
+const BASE: &str = include_str!("../../config/evaluation/sval.yml");
+
+/// A named set of evaluation parameters
+#[derive(Clone, Debug)]
+pub struct Preset {
+    pub name: &'static str,
+    /// One-line description of the preset
+    pub description: &'static str,
+    /// Changes to the base configuration (YAML)
+    changes: &'static str,
+}
+
+/// All available presets
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "balanced",
+        description: "The bundled Svalboard configuration without changes",
+        changes: "{}",
+    },
+    Preset {
+        name: "rolling",
+        description: "Favors rolls by penalizing redirects and off-center chains more strongly",
+        changes: include_str!("../presets/rolling.yml"),
+    },
+    Preset {
+        name: "alternating",
+        description: "Favors hand alternation and keeps the hand loads balanced",
+        changes: include_str!("../presets/alternating.yml"),
+    },
+    Preset {
+        name: "low-sfb",
+        description: "Minimizes same-finger bigrams and skipgrams above all",
+        changes: include_str!("../presets/low-sfb.yml"),
+    },
+    Preset {
+        name: "beginner-friendly",
+        description: "Favors comfortable keys, few scissors and an even finger load",
+        changes: include_str!("../presets/beginner-friendly.yml"),
+    },
+];
+
+impl Preset {
+    /// Look up a preset by name.
+    pub fn by_name(name: &str) -> Result<&'static Preset> {
+        PRESETS.iter().find(|p| p.name == name).ok_or_else(|| {
+            let names: Vec<&str> = PRESETS.iter().map(|p| p.name).collect();
+            anyhow!(
+                "Unknown preset '{}' (available: {})",
+                name,
+                names.join(", ")
+            )
+        })
+    }
+
+    /// The complete evaluation parameters of the preset.
+    pub fn parameters(&self) -> Result<Value> {
+        let mut params: Value = serde_yaml::from_str(BASE)?;
+        let changes: Value = serde_yaml::from_str(self.changes)?;
+        merge(&mut params, changes, "", None)?;
+
+        Ok(params)
+    }
+}
+
+/// Expand the preset selected in `config` and apply the remaining fields of `config` on top of
+/// it. Returns `None` if `config` does not select a preset.
+pub fn expand(mut config: Value) -> Result<Option<Value>> {
+    let name = match config.as_mapping_mut().and_then(|m| m.remove(PRESET_KEY)) {
+        Some(Value::String(name)) => name,
+        Some(other) => bail!("Preset name must be a string, found {:?}", other),
+        None => return Ok(None),
+    };
+
+    let preset = Preset::by_name(&name)?;
+    let mut params = preset.parameters()?;
+    merge(&mut params, config, "", Some(preset))?;
+
+    Ok(Some(params))
+}
+
+/// Merge `changes` into `params`: mappings are merged recursively, all other values replace
+/// the ones in `params`. If the changes override a `preset`, fields that are not set in
+/// `params` are rejected (except for top-level fields starting with "_" that hold YAML anchors).
+fn merge(params: &mut Value, changes: Value, path: &str, preset: Option<&Preset>) -> Result<()> {
+    match (params, changes) {
+        (Value::Mapping(params), Value::Mapping(changes)) => {
+            for (key, value) in changes {
+                let name = match key.as_str() {
+                    Some(name) => name.to_string(),
+                    None => format!("{:?}", key),
+                };
+                let field = if path.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", path, name)
+                };
+
+                match (params.get_mut(&key), preset) {
+                    (Some(param), _) => merge(param, value, &field, preset)?,
+                    (None, Some(_)) if path.is_empty() && name.starts_with('_') => {}
+                    (None, Some(preset)) => bail!(
+                        "Unknown field '{}': not set by preset '{}'",
+                        field,
+                        preset.name
+                    ),
+                    (None, None) => {
+                        params.insert(key, value);
+                    }
+                }
+            }
+        }
+        (params, changes) => *params = changes,
+    }
+
+    Ok(())
+}
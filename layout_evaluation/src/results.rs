@@ -1,8 +1,10 @@
 //! The `results` module contains structs representing the results of metric evaluations.
 
+use crate::output_style::{bold, gray, paint};
+
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
-use std::{fmt, slice};
+use std::{collections::BTreeMap, fmt, slice};
 
 /// The [`NormalizationType`] specifies how the total cost of a metric evaluation shall be normalized.
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -15,10 +17,104 @@ pub enum NormalizationType {
     WeightFound(f64),
     /// Divide the metric result's cost value by the sum of all ngram weights and a given fixed value.
     WeightAll(f64),
+    /// Divide the metric result's cost value by the cost of a baseline layout (see
+    /// [`crate::evaluation::Evaluator::with_baseline`]) and a given fixed value. Falls back to
+    /// [`NormalizationType::Fixed`] if there is no baseline or its cost is zero.
+    Baseline(f64),
+}
+
+/// The [`CostCurve`] transforms the normalized cost of a metric before it is weighted, e.g. to
+/// penalize high costs progressively.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum CostCurve {
+    /// Raise the cost to the given power (keeping its sign).
+    Power { exponent: f64 },
+    /// No cost up to `free_below`, linear increase up to `steep_above` and a `steep_factor`
+    /// times steeper increase above.
+    Piecewise {
+        free_below: f64,
+        steep_above: f64,
+        steep_factor: f64,
+    },
+}
+
+impl CostCurve {
+    /// Apply the curve to a normalized cost value.
+    pub fn apply(&self, cost: f64) -> f64 {
+        match *self {
+            CostCurve::Power { exponent } => cost.signum() * cost.abs().powf(exponent),
+            CostCurve::Piecewise {
+                free_below,
+                steep_above,
+                steep_factor,
+            } => {
+                if cost <= free_below {
+                    0.0
+                } else if cost <= steep_above {
+                    cost - free_below
+                } else {
+                    (steep_above - free_below) + steep_factor * (cost - steep_above)
+                }
+            }
+        }
+    }
+}
+
+/// The [`MaxAllowed`] caps invalidate layouts exceeding them by adding a large penalty to the
+/// metric's weighted cost.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MaxAllowed {
+    /// Maximum cost of the metric (not normalized)
+    pub cost: Option<f64>,
+    /// Maximum of the metric's named shares in percent (e.g. `sfb` for the bigram statistics)
+    #[serde(default)]
+    pub percent: BTreeMap<String, f64>,
+    /// Penalty added to the weighted cost if any cap is exceeded
+    #[serde(default = "default_max_allowed_penalty")]
+    pub penalty: f64,
+}
+
+fn default_max_allowed_penalty() -> f64 {
+    1_000_000.0
+}
+
+impl MaxAllowed {
+    /// Describe all caps exceeded by the given cost and percentages. The latter are only
+    /// computed if there are any percentage caps.
+    pub fn violations(
+        &self,
+        cost: f64,
+        percentages: impl FnOnce() -> Vec<(&'static str, f64)>,
+    ) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        if let Some(max_cost) = self.cost {
+            if cost > max_cost {
+                violations.push(format!("cost {:.4} > {}", cost, max_cost));
+            }
+        }
+
+        if !self.percent.is_empty() {
+            let percentages = percentages();
+            for (name, max_percent) in self.percent.iter() {
+                match percentages.iter().find(|(n, _)| n == name) {
+                    Some((_, percent)) if percent > max_percent => {
+                        violations.push(format!("{} {:.2}% > {}%", name, percent, max_percent))
+                    }
+                    Some(_) => {}
+                    None => log::warn!("Unknown share '{}' in max_allowed", name),
+                }
+            }
+        }
+
+        violations
+    }
 }
 
 /// Specify which data a metric operates on.
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum MetricType {
     Layout,
     Unigram,
@@ -39,6 +135,41 @@ pub struct MetricResult {
     pub weight: f64,
     /// The normalization type to apply.
     pub normalization: NormalizationType,
+    /// Cost of the baseline layout (not normalized), if there is one.
+    #[serde(default)]
+    pub baseline_cost: Option<f64>,
+    /// The curve to apply to the normalized cost before weighting.
+    #[serde(default)]
+    pub cost_curve: Option<CostCurve>,
+    /// Descriptions of the exceeded [`MaxAllowed`] caps.
+    #[serde(default)]
+    pub violations: Vec<String>,
+    /// Penalty added to the weighted cost because of exceeded caps.
+    #[serde(default)]
+    pub cap_penalty: f64,
+}
+
+impl MetricResult {
+    /// A result without baseline cost, cost curve or exceeded caps.
+    pub fn new(
+        name: &str,
+        cost: f64,
+        message: Option<String>,
+        weight: f64,
+        normalization: NormalizationType,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            cost,
+            message,
+            weight,
+            normalization,
+            baseline_cost: None,
+            cost_curve: None,
+            violations: Vec::new(),
+            cap_penalty: 0.0,
+        }
+    }
 }
 
 /// Describes the normalized results of an individual metric evaluation
@@ -48,6 +179,13 @@ pub struct NormalizedMetricResult {
     pub core: MetricResult,
     pub weighted_cost: f64,
     pub unweighted_cost: f64,
+    /// Normalized cost before applying the [`CostCurve`] (if there is one).
+    #[serde(default)]
+    pub uncurved_cost: Option<f64>,
+    /// Share of the weighted cost in the total cost of the evaluation (in percent).
+    /// `None` if the total cost is not positive.
+    #[serde(default)]
+    pub contribution: Option<f64>,
 }
 
 /// Describes a list of metric evaluation results of the same [`MetricType`].
@@ -65,7 +203,7 @@ pub struct MetricResults {
 
 impl fmt::Display for MetricResults {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let header = format!("{:?} metrics:", self.metric_type).bold();
+        let header = bold(&format!("{:?} metrics:", self.metric_type));
         writeln!(f, "{}", header)?;
 
         if self.metric_type != MetricType::Layout {
@@ -77,12 +215,43 @@ impl fmt::Display for MetricResults {
             )?;
         }
         for metric_cost in self.metric_costs.iter() {
+            let contribution = match metric_cost.contribution {
+                Some(c) => format!("{:>6.2}%", c),
+                None => format!("{:>7}", "n/a"),
+            };
+            let core = &metric_cost.core;
+            let baseline = match (&core.normalization, core.baseline_cost) {
+                (NormalizationType::Baseline(_), Some(baseline_cost)) => gray(&format!(
+                    "[raw: {:.4}, baseline: {:.4}] ",
+                    core.cost, baseline_cost
+                )),
+                _ => "".to_string(),
+            };
+            let curve = match metric_cost.uncurved_cost {
+                Some(uncurved_cost) => gray(&format!(
+                    "[normalized: {:.4} → {:.4}] ",
+                    uncurved_cost, metric_cost.unweighted_cost
+                )),
+                None => "".to_string(),
+            };
+            let violations = if core.violations.is_empty() {
+                "".to_string()
+            } else {
+                paint(
+                    &format!("[exceeded: {}] ", core.violations.join(", ")),
+                    |s| s.red(),
+                )
+            };
             writeln!(
                 f,
-                "  {} {} | {}",
+                "  {} {} {} | {}{}{}{}",
                 // metric_cost.unweighted_cost,
-                format!("{:>7.2}", metric_cost.weighted_cost).green(),
-                format!("{:<35}", metric_cost.core.name).bold(),
+                paint(&format!("{:>7.2}", metric_cost.weighted_cost), |s| s.green()),
+                gray(&contribution),
+                bold(&format!("{:<35}", metric_cost.core.name)),
+                violations,
+                baseline,
+                curve,
                 metric_cost.core.message.as_ref().unwrap_or(&"".to_string()),
             )?;
         }
@@ -103,21 +272,31 @@ impl MetricResults {
     pub fn add_result(&mut self, metric_cost: MetricResult) {
         let weighted_cost = self.compute_metric_cost(&metric_cost, true, true);
         let unweighted_cost = self.compute_metric_cost(&metric_cost, true, false);
+        let uncurved_cost = metric_cost
+            .cost_curve
+            .as_ref()
+            .map(|_| self.normalize_value(metric_cost.cost, &metric_cost));
         self.metric_costs.push(NormalizedMetricResult {
             core: metric_cost,
             weighted_cost,
             unweighted_cost,
+            uncurved_cost,
+            contribution: None,
         })
     }
 
-    /// Normalize a metric's cost value with given normalization strategy.
-    fn normalize_value(&self, val: f64, normalization_type: &NormalizationType) -> f64 {
-        let mut res = match normalization_type {
+    /// Normalize a metric's cost value with the metric's normalization strategy.
+    fn normalize_value(&self, val: f64, metric_cost: &MetricResult) -> f64 {
+        let mut res = match metric_cost.normalization {
             NormalizationType::Fixed(t) => val / t,
             NormalizationType::WeightFound(t) => val / (t * self.found_weight),
             NormalizationType::WeightAll(t) => {
                 val / (t * (self.found_weight + self.not_found_weight))
             }
+            NormalizationType::Baseline(t) => match metric_cost.baseline_cost {
+                Some(baseline_cost) if baseline_cost != 0.0 => val / (t * baseline_cost),
+                _ => val / t,
+            },
         };
 
         // instead of NAN, we prefer having 0.0 cost
@@ -129,19 +308,34 @@ impl MetricResults {
     }
 
     /// Helper function for weighting and normalizing individual metric's results.
+    /// The cost curve (if any) is applied to normalized costs only.
     fn compute_metric_cost(
         &self,
         metric_cost: &MetricResult,
         normalize: bool,
         weight: bool,
     ) -> f64 {
-        let cost = match weight {
-            true => metric_cost.weight * metric_cost.cost,
-            false => metric_cost.cost,
+        let cost = if let (Some(cost_curve), true) = (&metric_cost.cost_curve, normalize) {
+            let cost = cost_curve.apply(self.normalize_value(metric_cost.cost, metric_cost));
+            match weight {
+                true => metric_cost.weight * cost,
+                false => cost,
+            }
+        } else {
+            let cost = match weight {
+                true => metric_cost.weight * metric_cost.cost,
+                false => metric_cost.cost,
+            };
+
+            match normalize {
+                true => self.normalize_value(cost, metric_cost),
+                false => cost,
+            }
         };
 
-        match normalize {
-            true => self.normalize_value(cost, &metric_cost.normalization),
+        // the penalty for exceeded caps is part of the final (weighted and normalized) cost only
+        match normalize && weight {
+            true => cost + metric_cost.cap_penalty,
             false => cost,
         }
     }
@@ -153,6 +347,18 @@ impl MetricResults {
         })
     }
 
+    /// Change the weights of the metrics for which `weight` returns one (given the metric's
+    /// name) and update their weighted costs.
+    pub fn reweight(&mut self, weight: &dyn Fn(&str) -> Option<f64>) {
+        for i in 0..self.metric_costs.len() {
+            if let Some(w) = weight(&self.metric_costs[i].core.name) {
+                self.metric_costs[i].core.weight = w;
+                self.metric_costs[i].weighted_cost =
+                    self.compute_metric_cost(&self.metric_costs[i].core, true, true);
+            }
+        }
+    }
+
     /// Compute the weighted and normalized total cost of all metrics.
     pub fn total_cost(&self) -> f64 {
         self.aggregate_metric_costs(true, true)
@@ -164,40 +370,244 @@ impl MetricResults {
     }
 }
 
+/// The evaluation of a layout on one of several corpora (see [`EvaluationResult::combine`]).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CorpusResult {
+    /// Name of the corpus.
+    pub name: String,
+    /// Relative weight of the corpus in the combined result.
+    pub weight: f64,
+    pub result: EvaluationResult,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct EvaluationResult {
     layout: String,
     individual_results: Vec<MetricResults>,
+    /// Results per corpus, if the individual results combine several corpora.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    corpora: Vec<CorpusResult>,
 }
 
 impl fmt::Display for EvaluationResult {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for corpus in self.corpora.iter() {
+            let header = format!("Corpus '{}' (weight {}):", corpus.name, corpus.weight);
+            writeln!(f, "{}", paint(&header, |s| s.bold().underline()))?;
+            writeln!(f, "{}", corpus.result)?;
+        }
+        if !self.corpora.is_empty() {
+            writeln!(f, "{}", paint("Combined:", |s| s.bold().underline()))?;
+        }
+
         self.individual_results
             .iter()
-            .fold(Ok(()), |acc, results| {
-                acc.and_then(|_| writeln!(f, "{}", results))
-            })?;
+            .try_for_each(|results| writeln!(f, "{}", results))?;
 
         writeln!(
             f,
             "Cost: {} (optimization score: {})",
-            format!("{:.2}", self.total_cost()).green().bold(),
+            paint(&format!("{:.2}", self.total_cost()), |s| s.green().bold()),
             self.optimization_score()
         )?;
 
+        let violations = self.violations();
+        if !violations.is_empty() {
+            writeln!(
+                f,
+                "{}",
+                paint(&format!("Exceeded caps: {}", violations.join("; ")), |s| s
+                    .red()
+                    .bold())
+            )?;
+        }
+
         Ok(())
     }
 }
 
 impl EvaluationResult {
     pub fn new(layout: String, individual_results: Vec<MetricResults>) -> Self {
-        Self {
+        let mut res = Self {
             layout,
             individual_results,
+            corpora: Vec::new(),
+        };
+        res.compute_contributions();
+
+        res
+    }
+
+    /// Combine the evaluations of a layout on several corpora (by the same metrics). The
+    /// combined costs of each metric are the means of its costs on the corpora, weighted with
+    /// the corpora's weights. The combined results keep no messages, as these are specific to
+    /// the corpora.
+    pub fn combine(layout: String, corpora: Vec<CorpusResult>) -> Self {
+        let mut res = Self {
+            layout,
+            individual_results: Vec::new(),
+            corpora,
+        };
+        res.individual_results = res.combined_results();
+        res.compute_contributions();
+
+        res
+    }
+
+    /// String representation of the evaluated layout.
+    pub fn layout(&self) -> &str {
+        &self.layout
+    }
+
+    /// Results per corpus (empty if the evaluation is based on a single corpus).
+    pub fn corpora(&self) -> &[CorpusResult] {
+        &self.corpora
+    }
+
+    /// The weighted means of the corpora's individual results (matched by metric type and
+    /// name, as the corpora may be sorted differently).
+    fn combined_results(&self) -> Vec<MetricResults> {
+        let first = match self.corpora.first() {
+            Some(corpus) => &corpus.result.individual_results,
+            None => return Vec::new(),
+        };
+        let total_weight: f64 = self.corpora.iter().map(|c| c.weight).sum();
+        let shares: Vec<f64> = self
+            .corpora
+            .iter()
+            .map(|c| match total_weight > 0.0 {
+                true => c.weight / total_weight,
+                false => 1.0 / self.corpora.len() as f64,
+            })
+            .collect();
+
+        first
+            .iter()
+            .map(|results| {
+                let per_corpus: Vec<&MetricResults> = self
+                    .corpora
+                    .iter()
+                    .filter_map(|c| {
+                        c.result
+                            .individual_results
+                            .iter()
+                            .find(|mc| mc.metric_type == results.metric_type)
+                    })
+                    .collect();
+                let mean = |value: &dyn Fn(&MetricResults) -> f64| -> f64 {
+                    per_corpus
+                        .iter()
+                        .zip(shares.iter())
+                        .map(|(mc, share)| share * value(mc))
+                        .sum()
+                };
+
+                let mut combined = results.clone();
+                combined.found_weight = mean(&|mc| mc.found_weight);
+                combined.not_found_weight = mean(&|mc| mc.not_found_weight);
+                for metric_cost in combined.metric_costs.iter_mut() {
+                    let name = metric_cost.core.name.clone();
+                    let metric = |mc: &MetricResults| -> Option<NormalizedMetricResult> {
+                        mc.metric_costs
+                            .iter()
+                            .find(|m| m.core.name == name)
+                            .cloned()
+                    };
+                    let value = |f: &dyn Fn(&NormalizedMetricResult) -> f64| {
+                        mean(&|mc| metric(mc).map(|m| f(&m)).unwrap_or(0.0))
+                    };
+
+                    metric_cost.weighted_cost = value(&|m| m.weighted_cost);
+                    metric_cost.unweighted_cost = value(&|m| m.unweighted_cost);
+                    if metric_cost.uncurved_cost.is_some() {
+                        metric_cost.uncurved_cost =
+                            Some(value(&|m| m.uncurved_cost.unwrap_or(0.0)));
+                    }
+                    metric_cost.core.cost = value(&|m| m.core.cost);
+                    metric_cost.core.cap_penalty = value(&|m| m.core.cap_penalty);
+                    metric_cost.core.message = None;
+                    metric_cost.core.baseline_cost = None;
+                    metric_cost.core.violations = per_corpus
+                        .iter()
+                        .zip(self.corpora.iter())
+                        .filter_map(|(mc, c)| metric(mc).map(|m| (m, &c.name)))
+                        .flat_map(|(m, name)| {
+                            m.core
+                                .violations
+                                .into_iter()
+                                .map(move |v| format!("{} ({})", v, name))
+                        })
+                        .collect();
+                }
+
+                combined
+            })
+            .collect()
+    }
+
+    /// Compute each metric's share of the total cost. If the total cost is zero, negative,
+    /// or not finite, the shares are not meaningful and remain `None`.
+    fn compute_contributions(&mut self) {
+        let total_cost = self.total_cost();
+        let valid_total = total_cost.is_finite() && total_cost > 0.0;
+        self.individual_results
+            .iter_mut()
+            .flat_map(|mc| mc.metric_costs.iter_mut())
+            .for_each(|metric_cost| {
+                metric_cost.contribution = if valid_total {
+                    Some(100.0 * metric_cost.weighted_cost / total_cost)
+                } else {
+                    None
+                };
+            });
+    }
+
+    /// Sort the metrics of each metric type by their contribution to the total cost
+    /// (largest first) instead of the configured order.
+    pub fn sort_by_contribution(&mut self) {
+        self.corpora
+            .iter_mut()
+            .for_each(|c| c.result.sort_by_contribution());
+        self.individual_results.iter_mut().for_each(|mc| {
+            mc.metric_costs.sort_by(|m1, m2| {
+                m2.weighted_cost
+                    .partial_cmp(&m1.weighted_cost)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+        });
+    }
+
+    /// Change the weights of the metrics for which `weight` returns one (given the metric's
+    /// name), e.g. to follow a weight schedule during optimization.
+    pub fn reweight(&mut self, weight: impl Fn(&str) -> Option<f64>) {
+        self.reweight_dyn(&weight);
+    }
+
+    fn reweight_dyn(&mut self, weight: &dyn Fn(&str) -> Option<f64>) {
+        if self.corpora.is_empty() {
+            self.individual_results
+                .iter_mut()
+                .for_each(|mc| mc.reweight(weight));
+        } else {
+            self.corpora
+                .iter_mut()
+                .for_each(|c| c.result.reweight_dyn(weight));
+            self.individual_results = self.combined_results();
         }
+        self.compute_contributions();
     }
 
     pub fn total_cost(&self) -> f64 {
+        // the combined results can not recompute their costs (see `combined_results`)
+        if !self.corpora.is_empty() {
+            return self
+                .individual_results
+                .iter()
+                .flat_map(|mc| mc.metric_costs.iter())
+                .map(|metric_cost| metric_cost.weighted_cost)
+                .sum();
+        }
+
         let mut cost = 0.0;
         self.individual_results
             .iter()
@@ -207,6 +617,21 @@ impl EvaluationResult {
         cost
     }
 
+    /// Descriptions of all exceeded [`MaxAllowed`] caps (prefixed with the metric's name).
+    pub fn violations(&self) -> Vec<String> {
+        self.individual_results
+            .iter()
+            .flat_map(|mc| mc.metric_costs.iter())
+            .flat_map(|metric_cost| {
+                metric_cost
+                    .core
+                    .violations
+                    .iter()
+                    .map(move |v| format!("{}: {}", metric_cost.core.name, v))
+            })
+            .collect()
+    }
+
     pub fn optimization_score(&self) -> usize {
         (1e8 / self.total_cost()) as usize
     }
@@ -215,3 +640,141 @@ impl EvaluationResult {
         self.individual_results.iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metric_results(metric_type: MetricType, costs: &[(&str, f64)]) -> MetricResults {
+        let mut results = MetricResults::new(metric_type, 1.0, 0.0);
+        for (name, cost) in costs {
+            results.add_result(MetricResult::new(
+                name,
+                *cost,
+                None,
+                1.0,
+                NormalizationType::Fixed(1.0),
+            ));
+        }
+        results
+    }
+
+    fn evaluation_result(costs: &[(&str, f64)], bigram_costs: &[(&str, f64)]) -> EvaluationResult {
+        EvaluationResult::new(
+            "layout".to_string(),
+            vec![
+                metric_results(MetricType::Unigram, costs),
+                metric_results(MetricType::Bigram, bigram_costs),
+            ],
+        )
+    }
+
+    fn contributions(result: &EvaluationResult) -> Vec<Option<f64>> {
+        result
+            .iter()
+            .flat_map(|mc| mc.metric_costs.iter())
+            .map(|m| m.contribution)
+            .collect()
+    }
+
+    #[test]
+    fn contributions_sum_to_100_percent() {
+        let result = evaluation_result(&[("a", 1.5), ("b", 0.25)], &[("c", 7.0), ("d", 0.0)]);
+        let contributions: Vec<f64> = contributions(&result)
+            .into_iter()
+            .map(|c| c.unwrap())
+            .collect();
+
+        let sum: f64 = contributions.iter().sum();
+        assert!((sum - 100.0).abs() < 1e-9, "sum of contributions: {}", sum);
+        assert!((contributions[2] - 80.0).abs() < 1e-9);
+        assert_eq!(contributions[3], 0.0);
+    }
+
+    #[test]
+    fn contributions_are_none_for_non_positive_totals() {
+        let zero = evaluation_result(&[("a", 0.0)], &[("b", 0.0)]);
+        assert_eq!(contributions(&zero), vec![None, None]);
+
+        let negative = evaluation_result(&[("a", 1.0)], &[("b", -3.0)]);
+        assert_eq!(contributions(&negative), vec![None, None]);
+
+        let text = negative.to_string();
+        assert!(text.contains("n/a"));
+        assert!(!text.contains("NaN"));
+    }
+
+    #[test]
+    fn contributions_are_serialized() {
+        let result = evaluation_result(&[("a", 1.0)], &[("b", 3.0)]);
+        let json: serde_json::Value = serde_json::to_value(&result).unwrap();
+        let contribution = &json["individual_results"][1]["metric_costs"][0]["contribution"];
+        assert_eq!(contribution.as_f64(), Some(75.0));
+    }
+
+    #[test]
+    fn contributions_follow_reweighting() {
+        let mut result = evaluation_result(&[("a", 1.0)], &[("b", 1.0)]);
+        result.reweight(|name| if name == "a" { Some(3.0) } else { None });
+        assert_eq!(contributions(&result), vec![Some(75.0), Some(25.0)]);
+    }
+
+    #[test]
+    fn sort_by_contribution_sorts_within_metric_types() {
+        let mut result = evaluation_result(&[("a", 1.0), ("b", 3.0), ("c", 2.0)], &[("d", 5.0)]);
+        result.sort_by_contribution();
+
+        let names: Vec<&str> = result
+            .iter()
+            .flat_map(|mc| mc.metric_costs.iter())
+            .map(|m| m.core.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["b", "c", "a", "d"]);
+    }
+
+    /// The total cost of a layout with the given SFB and redirect costs, with `sfb_curve`
+    /// applied to the former
+    fn curved_total_cost(sfb: f64, redirects: f64, sfb_curve: Option<CostCurve>) -> f64 {
+        let mut results = metric_results(MetricType::Trigram, &[("Redirects", redirects)]);
+        results.add_result(MetricResult {
+            cost_curve: sfb_curve,
+            ..MetricResult::new("SFB", sfb, None, 1.0, NormalizationType::Fixed(1.0))
+        });
+        EvaluationResult::new("layout".to_string(), vec![results]).total_cost()
+    }
+
+    #[test]
+    fn convex_cost_curve_can_change_the_ordering() {
+        let convex = || Some(CostCurve::Power { exponent: 2.0 });
+
+        // linear: the layout with more SFBs wins thanks to fewer redirects
+        assert!(curved_total_cost(3.0, 0.2, None) < curved_total_cost(1.0, 2.5, None));
+        // convex: the SFBs dominate
+        assert!(curved_total_cost(3.0, 0.2, convex()) > curved_total_cost(1.0, 2.5, convex()));
+    }
+
+    #[test]
+    fn cost_curves_transform_normalized_costs() {
+        let piecewise = CostCurve::Piecewise {
+            free_below: 1.0,
+            steep_above: 2.0,
+            steep_factor: 10.0,
+        };
+        assert_eq!(piecewise.apply(0.5), 0.0);
+        assert_eq!(piecewise.apply(1.5), 0.5);
+        assert_eq!(piecewise.apply(3.0), 11.0);
+        assert_eq!(CostCurve::Power { exponent: 2.0 }.apply(-3.0), -9.0);
+
+        let mut results = MetricResults::new(MetricType::Bigram, 2.0, 0.0);
+        results.add_result(MetricResult {
+            cost_curve: Some(piecewise),
+            ..MetricResult::new("SFB", 6.0, None, 2.0, NormalizationType::WeightFound(1.0))
+        });
+        let metric_cost = &results.metric_costs[0];
+
+        assert_eq!(metric_cost.uncurved_cost, Some(3.0));
+        assert_eq!(metric_cost.unweighted_cost, 11.0);
+        assert_eq!(metric_cost.weighted_cost, 22.0);
+        assert!(results.to_string().contains("normalized: 3.0000 → 11.0000"));
+    }
+}
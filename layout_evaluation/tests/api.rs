@@ -0,0 +1,105 @@
+//! The library facade [`layout_evaluation::api`] with the repository's Svalboard configuration,
+//! used as an embedding application would (only through the public API).
+
+use layout_evaluation::api::{Evaluator, NgramSource};
+
+use std::{sync::Arc, thread};
+
+const LAYOUT_CONFIG: &str = include_str!("../../config/keyboard/sval.yml");
+const EVALUATION_CONFIG: &str = include_str!("../../config/evaluation/sval.yml");
+
+const TEXT: &str = "the quick brown fox jumps over the lazy dog. Über den Wolken muss die \
+    Freiheit wohl grenzenlos sein (sagt man)!";
+const LAYOUT: &str = "?öcäb-üiqy')ezo.,a(umkhjlg{txdw□n}pv□s□fr";
+
+fn evaluator() -> Evaluator {
+    Evaluator::from_config_strs(LAYOUT_CONFIG, EVALUATION_CONFIG, NgramSource::Text(TEXT)).unwrap()
+}
+
+fn assert_close(left: f64, right: f64) {
+    assert!(
+        (left - right).abs() <= 1e-9 * left.abs().max(right.abs()),
+        "{} != {}",
+        left,
+        right
+    );
+}
+
+#[test]
+fn evaluation_lists_all_metrics() {
+    let evaluator = evaluator();
+    let result = evaluator.evaluate(LAYOUT).unwrap();
+
+    assert_eq!(result.layout, LAYOUT);
+    assert!(result.total_cost.is_finite() && result.total_cost > 0.0);
+
+    let mut names: Vec<&str> = result.metrics.iter().map(|m| m.name.as_str()).collect();
+    let mut metric_names = evaluator.metric_names();
+    names.sort_unstable();
+    metric_names.sort_unstable();
+    assert_eq!(names, metric_names);
+
+    let weighted_costs: f64 = result.metrics.iter().map(|m| m.weighted_cost).sum();
+    assert_close(weighted_costs, result.total_cost);
+    assert_close(result.details.total_cost(), result.total_cost);
+}
+
+#[test]
+fn statistics_are_shares() {
+    let result = evaluator().evaluate(LAYOUT).unwrap();
+
+    assert!(result.statistics.iter().any(|s| s.name == "sfb"));
+    for statistic in result.statistics.iter() {
+        assert!(
+            (0.0..=100.0).contains(&statistic.percent),
+            "{}: {}",
+            statistic.name,
+            statistic.percent
+        );
+    }
+}
+
+#[test]
+fn result_serializes_to_json() {
+    let result = evaluator().evaluate(LAYOUT).unwrap();
+    let json: serde_json::Value = serde_json::to_value(&result).unwrap();
+
+    assert_eq!(json["layout"], LAYOUT);
+    assert_eq!(json["total_cost"].as_f64(), Some(result.total_cost));
+    assert_eq!(
+        json["metrics"].as_array().map(|m| m.len()),
+        Some(result.metrics.len())
+    );
+    assert!(json["statistics"].is_array());
+}
+
+#[test]
+fn evaluator_is_shared_between_threads() {
+    let evaluator = Arc::new(evaluator());
+    let expected = evaluator.evaluate(LAYOUT).unwrap().total_cost;
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let evaluator = Arc::clone(&evaluator);
+            thread::spawn(move || evaluator.evaluate(LAYOUT).unwrap().total_cost)
+        })
+        .collect();
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap().to_bits(), expected.to_bits());
+    }
+}
+
+#[test]
+fn invalid_inputs_are_errors() {
+    let evaluator = evaluator();
+    assert!(evaluator.evaluate("abc").is_err());
+
+    assert!(
+        Evaluator::from_config_strs("keyboard: 1", EVALUATION_CONFIG, NgramSource::Text(TEXT))
+            .is_err()
+    );
+    assert!(
+        Evaluator::from_config_strs(LAYOUT_CONFIG, "metrics: 1", NgramSource::Text(TEXT)).is_err()
+    );
+}
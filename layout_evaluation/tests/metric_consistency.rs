@@ -0,0 +1,217 @@
+//! Consistency of the KLA metrics with their `layout_evaluation` counterparts: `kla_same_finger`
+//! against `sfb` and the SFB share of `bigram_stats`, and `kla_same_hand` against the
+//! same-hand bigrams of the corpus.
+//!
+//! The known differences (see the module docs of `kla_same_finger` and `kla_same_hand`) are
+//! checked to be exactly what the docs state.
+
+use ahash::AHashMap;
+use keyboard_layout::{
+    key::Finger,
+    layout::{LayerKey, Layout},
+    test_utils::toy_layout,
+};
+use layout_evaluation::metrics::bigram_metrics::{
+    bigram_stats::{self, BigramStats},
+    kla_same_finger::{self, KLASameFinger},
+    kla_same_hand::{self, KLASameHand},
+    sfb::{self, Sfb},
+    BigramMetric,
+};
+
+/// Spaces are typed with the left thumb ("O"), all other symbols are letters of the
+/// [`layout`]. The double space gives a same-finger bigram of the thumb.
+const CORPUS: &str = "the quick brown fox jumps over the lazy dog  and all good books need \
+    little effort to keep a committee meeting from running off the rails";
+
+/// Left hand: "a" to "t", right hand: "u" to "z" and "A" to "N", thumbs "O" (left) and "P"
+/// (right).
+fn layout() -> Layout {
+    toy_layout("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP")
+}
+
+/// The bigrams of [`CORPUS`] with their counts as weights
+fn bigrams(layout: &Layout) -> Vec<((&LayerKey, &LayerKey), f64)> {
+    let symbols: Vec<char> = CORPUS
+        .chars()
+        .map(|c| if c == ' ' { 'O' } else { c })
+        .collect();
+
+    let mut counts: AHashMap<(char, char), f64> = AHashMap::default();
+    for pair in symbols.windows(2) {
+        *counts.entry((pair[0], pair[1])).or_insert(0.0) += 1.0;
+    }
+
+    let mut counts: Vec<((char, char), f64)> = counts.into_iter().collect();
+    counts.sort_by_key(|(bigram, _)| *bigram);
+    counts
+        .into_iter()
+        .map(|((c1, c2), weight)| {
+            let k1 = layout.get_layerkey_for_symbol(&c1).unwrap();
+            let k2 = layout.get_layerkey_for_symbol(&c2).unwrap();
+            ((k1, k2), weight)
+        })
+        .collect()
+}
+
+fn total_weight(bigrams: &[((&LayerKey, &LayerKey), f64)]) -> f64 {
+    bigrams.iter().map(|(_, w)| w).sum()
+}
+
+/// Weight of the bigrams matching `predicate`
+fn weight_of(
+    bigrams: &[((&LayerKey, &LayerKey), f64)],
+    predicate: impl Fn(&LayerKey, &LayerKey) -> bool,
+) -> f64 {
+    bigrams
+        .iter()
+        .filter(|((k1, k2), _)| predicate(k1, k2))
+        .map(|(_, w)| w)
+        .sum()
+}
+
+fn assert_close(left: f64, right: f64) {
+    assert!(
+        (left - right).abs() <= 1e-9 * left.abs().max(right.abs()),
+        "{} != {}",
+        left,
+        right
+    );
+}
+
+fn is_same_finger(k1: &LayerKey, k2: &LayerKey) -> bool {
+    k1.key.hand == k2.key.hand && k1.key.finger == k2.key.finger
+}
+
+fn kla_same_finger_cost(layout: &Layout, bigrams: &[((&LayerKey, &LayerKey), f64)]) -> f64 {
+    let params: kla_same_finger::Parameters =
+        serde_yaml::from_str("{ignore_modifiers: true, fscoring: {}, hscoring: {}}").unwrap();
+    KLASameFinger::new(&params)
+        .total_cost(bigrams, None, layout)
+        .0
+}
+
+fn sfb_cost(layout: &Layout, bigrams: &[((&LayerKey, &LayerKey), f64)], filter: &str) -> f64 {
+    let params: sfb::Parameters =
+        serde_yaml::from_str(&format!("{{default_cost: 1.0, costs: {{}}, {}}}", filter)).unwrap();
+    Sfb::new(&params).total_cost(bigrams, None, layout).0
+}
+
+fn sfb_share(bigrams: &[((&LayerKey, &LayerKey), f64)], filter: &str) -> f64 {
+    let params: bigram_stats::Parameters =
+        serde_yaml::from_str(&format!("{{{}}}", filter)).unwrap();
+    BigramStats::new(&params)
+        .compute_statistics(bigrams, None)
+        .sfb_percent
+}
+
+#[test]
+fn corpus_has_repeats_and_thumb_bigrams() {
+    let layout = layout();
+    let bigrams = bigrams(&layout);
+
+    assert!(weight_of(&bigrams, |k1, k2| k1 == k2) > 0.0);
+    assert!(weight_of(&bigrams, |k1, k2| k1 != k2 && is_same_finger(k1, k2)) > 0.0);
+    assert!(
+        weight_of(&bigrams, |k1, k2| k1 == k2
+            && k1.key.finger == Finger::Thumb)
+            > 0.0
+    );
+}
+
+/// With repeats included, unit costs and without modifiers, all three metrics count the same
+/// bigrams.
+#[test]
+fn kla_same_finger_matches_sfb() {
+    let layout = layout();
+    let bigrams = bigrams(&layout);
+    let filter = "ignore_thumbs: false, exclude_same_position_repeats: false";
+
+    let kla = kla_same_finger_cost(&layout, &bigrams);
+    let sfb = sfb_cost(&layout, &bigrams, filter);
+    let share = sfb_share(&bigrams, filter);
+
+    assert_close(kla, weight_of(&bigrams, is_same_finger));
+    assert_close(kla, sfb);
+    assert_close(100.0 * kla / total_weight(&bigrams), share);
+}
+
+/// By default, `sfb` skips repeats of the same key, which `kla_same_finger` counts.
+#[test]
+fn kla_same_finger_counts_repeats() {
+    let layout = layout();
+    let bigrams = bigrams(&layout);
+    let filter = "ignore_thumbs: false";
+
+    let kla = kla_same_finger_cost(&layout, &bigrams);
+    let sfb = sfb_cost(&layout, &bigrams, filter);
+    let share = sfb_share(&bigrams, filter);
+    let repeats = weight_of(&bigrams, |k1, k2| k1 == k2);
+
+    assert_close(kla - repeats, sfb);
+    assert_close(100.0 * sfb / total_weight(&bigrams), share);
+}
+
+/// `kla_same_finger` always includes the thumbs, `sfb` can ignore them.
+#[test]
+fn kla_same_finger_counts_thumbs() {
+    let layout = layout();
+    let bigrams = bigrams(&layout);
+    let filter = "ignore_thumbs: true, exclude_same_position_repeats: false";
+
+    let kla = kla_same_finger_cost(&layout, &bigrams);
+    let sfb = sfb_cost(&layout, &bigrams, filter);
+    let thumbs = weight_of(&bigrams, |k1, k2| {
+        is_same_finger(k1, k2) && k1.key.finger == Finger::Thumb
+    });
+
+    assert_close(kla - thumbs, sfb);
+}
+
+/// `kla_same_hand` counts all bigrams on one hand, including same-finger bigrams and repeats,
+/// i.e. everything the bigram statistics categorize.
+#[test]
+fn kla_same_hand_counts_all_same_hand_bigrams() {
+    let layout = layout();
+    let bigrams = bigrams(&layout);
+    let same_hand = |ignore_thumbs: bool| {
+        let params = kla_same_hand::Parameters {
+            ignore_modifiers: true,
+            ignore_thumbs,
+            hscoring: AHashMap::default(),
+        };
+        KLASameHand::new(&params)
+            .total_cost(&bigrams, None, &layout)
+            .0
+    };
+
+    assert_close(
+        same_hand(false),
+        weight_of(&bigrams, |k1, k2| k1.key.hand == k2.key.hand),
+    );
+    assert_close(
+        same_hand(true),
+        weight_of(&bigrams, |k1, k2| {
+            k1.key.hand == k2.key.hand
+                && k1.key.finger != Finger::Thumb
+                && k2.key.finger != Finger::Thumb
+        }),
+    );
+
+    let params: bigram_stats::Parameters =
+        serde_yaml::from_str("{ignore_thumbs: false, exclude_same_position_repeats: false}")
+            .unwrap();
+    let stats = BigramStats::new(&params).compute_statistics(&bigrams, None);
+    let categorized = stats.sfb_percent
+        + stats.vertical_percent
+        + stats.squeeze_percent
+        + stats.splay_percent
+        + stats.diagonal_percent
+        + stats.lateral_percent
+        + stats.lsb_percent
+        + stats.extend_up_percent
+        + stats.extend_down_percent;
+
+    assert!(categorized > 0.0);
+    assert!(categorized <= 100.0 * same_hand(false) / total_weight(&bigrams) + 1e-9);
+}
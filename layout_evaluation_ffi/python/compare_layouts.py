@@ -0,0 +1,38 @@
+"""Evaluate two layouts and compare their costs and SFB shares.
+
+    python3 layout_evaluation_ffi/python/compare_layouts.py LAYOUT_A LAYOUT_B \
+        [--layout-config config/keyboard/sval.yml] [--eval-config config/evaluation/sval.yml] \
+        [--ngrams ngrams/eng_shai]
+"""
+
+import argparse
+import sys
+
+from svalboard_eval import EvaluationError, Evaluator
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+    parser.add_argument("layouts", nargs=2, metavar="LAYOUT")
+    parser.add_argument("--layout-config", default="config/keyboard/sval.yml")
+    parser.add_argument("--eval-config", default="config/evaluation/sval.yml")
+    parser.add_argument("--ngrams", default="ngrams/eng_shai")
+    args = parser.parse_args()
+
+    try:
+        with Evaluator.from_files(args.layout_config, args.eval_config, ngrams=args.ngrams) as e:
+            results = [e.evaluate(layout) for layout in args.layouts]
+    except EvaluationError as err:
+        print(err, file=sys.stderr)
+        return 1
+
+    for result in results:
+        print(f"{result.layout}  cost {result.total_cost:8.2f}  SFB {result.statistic('sfb'):5.2f}%")
+
+    a, b = results
+    print(f"SFB difference (second - first): {b.statistic('sfb') - a.statistic('sfb'):+.2f}%")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
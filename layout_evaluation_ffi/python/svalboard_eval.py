@@ -0,0 +1,197 @@
+"""Python bindings for the layout evaluation (via the C ABI of `layout_evaluation_ffi`).
+
+Build the library with `cargo build --release -p layout_evaluation_ffi` first. It is looked up
+in the `SVAL_EVAL_LIB` environment variable or in the `target/release` and `target/debug`
+directories of the repository.
+
+    from svalboard_eval import Evaluator
+
+    evaluator = Evaluator.from_files("config/keyboard/sval.yml", "config/evaluation/sval.yml",
+                                     ngrams="ngrams/eng_shai")
+    result = evaluator.evaluate("?öcäb-üiqy')ezo.,a(umkhjlg{txdw□n}pv□s□fr")
+    print(result.total_cost, result.statistic("sfb"))
+"""
+
+import ctypes
+import json
+import os
+import sys
+from pathlib import Path
+
+_REPO_DIR = Path(__file__).resolve().parents[2]
+
+_STATUS_NAMES = {1: "invalid argument", 2: "error", 3: "panic"}
+
+
+class EvaluationError(Exception):
+    """An error reported by the library."""
+
+
+def _library_path():
+    if "SVAL_EVAL_LIB" in os.environ:
+        return os.environ["SVAL_EVAL_LIB"]
+
+    if sys.platform == "win32":
+        name = "layout_evaluation_ffi.dll"
+    elif sys.platform == "darwin":
+        name = "liblayout_evaluation_ffi.dylib"
+    else:
+        name = "liblayout_evaluation_ffi.so"
+    for profile in ["release", "debug"]:
+        path = _REPO_DIR / "target" / profile / name
+        if path.exists():
+            return str(path)
+    raise EvaluationError(
+        f"could not find {name}; build it with `cargo build --release -p layout_evaluation_ffi` "
+        "or set SVAL_EVAL_LIB"
+    )
+
+
+def _load_library():
+    lib = ctypes.CDLL(_library_path())
+    c_str = ctypes.c_char_p
+    out_ptr = ctypes.POINTER(ctypes.c_void_p)
+
+    lib.sval_evaluator_from_text.argtypes = [c_str, c_str, c_str, out_ptr]
+    lib.sval_evaluator_from_text.restype = ctypes.c_int
+    lib.sval_evaluator_from_frequencies.argtypes = [c_str, c_str, c_str, c_str, c_str, out_ptr]
+    lib.sval_evaluator_from_frequencies.restype = ctypes.c_int
+    lib.sval_evaluate.argtypes = [ctypes.c_void_p, c_str, out_ptr]
+    lib.sval_evaluate.restype = ctypes.c_int
+    lib.sval_last_error.argtypes = []
+    lib.sval_last_error.restype = c_str
+    lib.sval_evaluator_free.argtypes = [ctypes.c_void_p]
+    lib.sval_evaluator_free.restype = None
+    lib.sval_string_free.argtypes = [ctypes.c_void_p]
+    lib.sval_string_free.restype = None
+
+    return lib
+
+
+_lib = None
+
+
+def _library():
+    global _lib
+    if _lib is None:
+        _lib = _load_library()
+    return _lib
+
+
+def _check(status):
+    if status != 0:
+        message = _library().sval_last_error()
+        message = message.decode("utf-8") if message else "unknown error"
+        raise EvaluationError(f"{_STATUS_NAMES.get(status, status)}: {message}")
+
+
+def _encode(s):
+    return s.encode("utf-8")
+
+
+class EvaluationResult:
+    """The result of evaluating a layout."""
+
+    def __init__(self, data):
+        self.data = data
+        self.layout = data["layout"]
+        self.total_cost = data["total_cost"]
+        self.metrics = data["metrics"]
+        self.statistics = {s["name"]: s["percent"] for s in data["statistics"]}
+
+    def statistic(self, name):
+        """Share of a bigram/trigram category in percent, e.g. "sfb" or "roll_in"."""
+        return self.statistics[name]
+
+    def metric(self, name):
+        """The entry of the metric with the given name, e.g. "SFB"."""
+        for metric in self.metrics:
+            if metric["name"] == name:
+                return metric
+        raise KeyError(name)
+
+    def __repr__(self):
+        return f"EvaluationResult(layout={self.layout!r}, total_cost={self.total_cost:.2f})"
+
+
+class Evaluator:
+    """Evaluates layout strings for a keyboard, a set of metrics, and ngrams."""
+
+    def __init__(self, handle):
+        self._handle = handle
+
+    @classmethod
+    def from_text(cls, layout_config, evaluation_config, text):
+        """Set up the evaluation from the contents of the configurations and a text."""
+        handle = ctypes.c_void_p()
+        _check(
+            _library().sval_evaluator_from_text(
+                _encode(layout_config),
+                _encode(evaluation_config),
+                _encode(text),
+                ctypes.byref(handle),
+            )
+        )
+        return cls(handle)
+
+    @classmethod
+    def from_frequencies(cls, layout_config, evaluation_config, unigrams, bigrams, trigrams):
+        """Set up the evaluation from the contents of the configurations and ngram files."""
+        handle = ctypes.c_void_p()
+        _check(
+            _library().sval_evaluator_from_frequencies(
+                _encode(layout_config),
+                _encode(evaluation_config),
+                _encode(unigrams),
+                _encode(bigrams),
+                _encode(trigrams),
+                ctypes.byref(handle),
+            )
+        )
+        return cls(handle)
+
+    @classmethod
+    def from_files(cls, layout_config_file, evaluation_config_file, ngrams=None, text_file=None):
+        """Set up the evaluation from configuration files and an ngram directory (containing
+        `1-grams.txt` etc.) or a text file."""
+        layout_config = Path(layout_config_file).read_text(encoding="utf-8")
+        evaluation_config = Path(evaluation_config_file).read_text(encoding="utf-8")
+        if text_file is not None:
+            text = Path(text_file).read_text(encoding="utf-8")
+            return cls.from_text(layout_config, evaluation_config, text)
+        if ngrams is None:
+            raise ValueError("either ngrams or text_file is required")
+
+        ngrams = Path(ngrams)
+        return cls.from_frequencies(
+            layout_config,
+            evaluation_config,
+            *[(ngrams / f"{n}-grams.txt").read_text(encoding="utf-8") for n in [1, 2, 3]],
+        )
+
+    def evaluate(self, layout):
+        """Evaluate a layout string (the symbols of the permutable keys)."""
+        if self._handle is None:
+            raise EvaluationError("the evaluator has been closed")
+
+        out = ctypes.c_void_p()
+        _check(_library().sval_evaluate(self._handle, _encode(layout), ctypes.byref(out)))
+        try:
+            data = json.loads(ctypes.string_at(out).decode("utf-8"))
+        finally:
+            _library().sval_string_free(out)
+        return EvaluationResult(data)
+
+    def close(self):
+        if self._handle is not None:
+            _library().sval_evaluator_free(self._handle)
+            self._handle = None
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *exc):
+        self.close()
+
+    def __del__(self):
+        self.close()
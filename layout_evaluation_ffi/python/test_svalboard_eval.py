@@ -0,0 +1,89 @@
+"""Tests of the Python bindings against the library (build it first, see `svalboard_eval.py`).
+
+    cargo build -p layout_evaluation_ffi
+    python3 -m unittest discover -s layout_evaluation_ffi/python
+"""
+
+import ctypes
+import unittest
+
+from svalboard_eval import _REPO_DIR, EvaluationError, Evaluator, _library
+
+LAYOUT_CONFIG = (_REPO_DIR / "config" / "keyboard" / "sval.yml").read_text(encoding="utf-8")
+EVALUATION_CONFIG = (_REPO_DIR / "config" / "evaluation" / "sval.yml").read_text(encoding="utf-8")
+
+# "h" and "k" are typed with the same finger
+LAYOUT = "?öcäb-üiqy')ezo.,a(umkhjlg{txdw□n}pv□s□fr"
+# "k" and "e" swapped, such that "h" and "k" are on different fingers
+SWAPPED = "?öcäb-üiqy')kzo.,a(umehjlg{txdw□n}pv□s□fr"
+
+TEXT = "hk" * 50
+
+
+class EvaluatorTest(unittest.TestCase):
+    @classmethod
+    def setUpClass(cls):
+        cls.evaluator = Evaluator.from_text(LAYOUT_CONFIG, EVALUATION_CONFIG, TEXT)
+
+    @classmethod
+    def tearDownClass(cls):
+        cls.evaluator.close()
+
+    def test_sfb_of_two_layouts(self):
+        same_finger = self.evaluator.evaluate(LAYOUT)
+        swapped = self.evaluator.evaluate(SWAPPED)
+
+        self.assertEqual(same_finger.layout, LAYOUT)
+        self.assertEqual(swapped.layout, SWAPPED)
+        self.assertAlmostEqual(same_finger.statistic("sfb"), 100.0)
+        self.assertAlmostEqual(swapped.statistic("sfb"), 0.0)
+        self.assertGreater(same_finger.metric("SFB")["weighted_cost"], 0.0)
+        self.assertEqual(swapped.metric("SFB")["weighted_cost"], 0.0)
+        self.assertGreater(same_finger.total_cost, swapped.total_cost)
+
+    def test_total_cost_is_the_sum_of_the_metrics(self):
+        result = self.evaluator.evaluate(LAYOUT)
+
+        self.assertAlmostEqual(
+            result.total_cost, sum(m["weighted_cost"] for m in result.metrics), places=6
+        )
+
+    def test_frequencies_give_the_same_result(self):
+        # the counts of the unigrams, bigrams, and trigrams of TEXT
+        with Evaluator.from_frequencies(
+            LAYOUT_CONFIG,
+            EVALUATION_CONFIG,
+            "50 h\n50 k\n",
+            "50 hk\n49 kh\n",
+            "49 hkh\n49 khk\n",
+        ) as evaluator:
+            result = evaluator.evaluate(LAYOUT)
+
+        self.assertEqual(result.total_cost, self.evaluator.evaluate(LAYOUT).total_cost)
+
+    def test_invalid_layout_raises(self):
+        with self.assertRaisesRegex(EvaluationError, "^error: Could not generate layout 'abc'"):
+            self.evaluator.evaluate("abc")
+
+    def test_invalid_config_raises(self):
+        with self.assertRaisesRegex(EvaluationError, "^error: Could not read layout config"):
+            Evaluator.from_text("keyboard: 1", EVALUATION_CONFIG, TEXT)
+
+    def test_invalid_arguments_are_reported(self):
+        out = ctypes.c_void_p()
+        status = _library().sval_evaluate(None, LAYOUT.encode("utf-8"), ctypes.byref(out))
+
+        self.assertEqual(status, 1)
+        self.assertEqual(_library().sval_last_error(), b"evaluator is null")
+        self.assertIsNone(out.value)
+
+    def test_closed_evaluator_raises(self):
+        evaluator = Evaluator.from_text(LAYOUT_CONFIG, EVALUATION_CONFIG, TEXT)
+        evaluator.close()
+
+        with self.assertRaisesRegex(EvaluationError, "closed"):
+            evaluator.evaluate(LAYOUT)
+
+
+if __name__ == "__main__":
+    unittest.main()
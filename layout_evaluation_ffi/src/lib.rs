@@ -0,0 +1,263 @@
+//! C ABI for the evaluation facade ([`layout_evaluation::api`]), e.g. for scripting the
+//! evaluation from Python (see `python/svalboard_eval.py`).
+//!
+//! The functions return a [`Status`] and pass their results through out-parameters. On an
+//! error, [`sval_last_error`] describes it. Panics are caught and reported as
+//! [`Status::Panic`] instead of unwinding across the FFI boundary.
+//!
+//! All strings are NUL-terminated UTF-8. Strings returned by the library must be released with
+//! [`sval_string_free`], evaluators with [`sval_evaluator_free`]. An evaluator may be used from
+//! several threads at once.
+
+use layout_evaluation::api::{Evaluator, NgramSource};
+
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+/// Result of a call
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Status {
+    Ok = 0,
+    /// A pointer was null or a string was not valid UTF-8
+    InvalidArgument = 1,
+    /// The configuration, the ngrams or the layout could not be processed
+    Error = 2,
+    /// The evaluation panicked (a bug)
+    Panic = 3,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Run `f`, storing its error (or panic) as the last error of the thread.
+fn guarded(f: impl FnOnce() -> Result<(), (Status, String)>) -> Status {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => Status::Ok,
+        Ok(Err((status, message))) => {
+            set_last_error(message);
+            status
+        }
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            set_last_error(format!("panic: {}", message));
+            Status::Panic
+        }
+    }
+}
+
+/// Borrow the string behind `s`.
+///
+/// # Safety
+/// `s` must be null or point to a NUL-terminated string that outlives the returned reference.
+unsafe fn to_str<'a>(s: *const c_char, name: &str) -> Result<&'a str, (Status, String)> {
+    if s.is_null() {
+        return Err((Status::InvalidArgument, format!("{} is null", name)));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|e| (Status::InvalidArgument, format!("{}: {}", name, e)))
+}
+
+/// Set up an evaluator and store it in `out`.
+///
+/// # Safety
+/// `out` must be a valid pointer; the strings must be null or NUL-terminated.
+unsafe fn new_evaluator<'a>(
+    layout_config: *const c_char,
+    evaluation_config: *const c_char,
+    ngrams: impl FnOnce() -> Result<NgramSource<'a>, (Status, String)>,
+    out: *mut *mut Evaluator,
+) -> Status {
+    guarded(|| {
+        if out.is_null() {
+            return Err((Status::InvalidArgument, "out is null".to_string()));
+        }
+        *out = ptr::null_mut();
+
+        let layout_config = to_str(layout_config, "layout_config")?;
+        let evaluation_config = to_str(evaluation_config, "evaluation_config")?;
+        let evaluator = Evaluator::from_config_strs(layout_config, evaluation_config, ngrams()?)
+            .map_err(|e| (Status::Error, format!("{:#}", e)))?;
+
+        *out = Box::into_raw(Box::new(evaluator));
+        Ok(())
+    })
+}
+
+/// Create an evaluator from the contents of a keyboard and an evaluation configuration and
+/// the ngrams of `text`. On success, `*out` holds the evaluator.
+///
+/// # Safety
+/// `out` must be a valid pointer; the strings must be null or NUL-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn sval_evaluator_from_text(
+    layout_config: *const c_char,
+    evaluation_config: *const c_char,
+    text: *const c_char,
+    out: *mut *mut Evaluator,
+) -> Status {
+    new_evaluator(
+        layout_config,
+        evaluation_config,
+        || Ok(NgramSource::Text(to_str(text, "text")?)),
+        out,
+    )
+}
+
+/// Create an evaluator from the contents of a keyboard and an evaluation configuration and
+/// the contents of ngram frequency files (`1-grams.txt` etc.). On success, `*out` holds the
+/// evaluator.
+///
+/// # Safety
+/// `out` must be a valid pointer; the strings must be null or NUL-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn sval_evaluator_from_frequencies(
+    layout_config: *const c_char,
+    evaluation_config: *const c_char,
+    unigrams: *const c_char,
+    bigrams: *const c_char,
+    trigrams: *const c_char,
+    out: *mut *mut Evaluator,
+) -> Status {
+    new_evaluator(
+        layout_config,
+        evaluation_config,
+        || {
+            Ok(NgramSource::Frequencies {
+                unigrams: to_str(unigrams, "unigrams")?,
+                bigrams: to_str(bigrams, "bigrams")?,
+                trigrams: to_str(trigrams, "trigrams")?,
+            })
+        },
+        out,
+    )
+}
+
+/// Evaluate the layout given by its string representation. On success, `*out` holds the result
+/// as JSON (see [`layout_evaluation::api::EvaluationResult`]).
+///
+/// # Safety
+/// `evaluator` must be null or created by this library and not yet freed; `out` must be a
+/// valid pointer; `layout` must be null or NUL-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn sval_evaluate(
+    evaluator: *const Evaluator,
+    layout: *const c_char,
+    out: *mut *mut c_char,
+) -> Status {
+    guarded(|| {
+        if out.is_null() {
+            return Err((Status::InvalidArgument, "out is null".to_string()));
+        }
+        *out = ptr::null_mut();
+
+        let evaluator = evaluator
+            .as_ref()
+            .ok_or_else(|| (Status::InvalidArgument, "evaluator is null".to_string()))?;
+        let layout = to_str(layout, "layout")?;
+        let result = evaluator
+            .evaluate(layout)
+            .map_err(|e| (Status::Error, format!("{:#}", e)))?;
+        let json = serde_json::to_string(&result).map_err(|e| {
+            (
+                Status::Error,
+                format!("could not serialize the result: {}", e),
+            )
+        })?;
+
+        *out = CString::new(json).unwrap().into_raw();
+        Ok(())
+    })
+}
+
+/// Description of the last error of the calling thread (null if there was none). The string
+/// is owned by the library and valid until the next call on the same thread.
+#[no_mangle]
+pub extern "C" fn sval_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+/// Release an evaluator.
+///
+/// # Safety
+/// `evaluator` must be null or created by this library and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn sval_evaluator_free(evaluator: *mut Evaluator) {
+    if !evaluator.is_null() {
+        drop(Box::from_raw(evaluator));
+    }
+}
+
+/// Release a string returned by this library.
+///
+/// # Safety
+/// `s` must be null or returned by this library and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn sval_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> String {
+        let error = sval_last_error();
+        assert!(!error.is_null());
+        unsafe { CStr::from_ptr(error) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn errors_are_returned_as_status() {
+        let status = guarded(|| Err((Status::Error, "no layout".to_string())));
+
+        assert_eq!(status, Status::Error);
+        assert_eq!(last_error(), "no layout");
+    }
+
+    #[test]
+    fn panics_are_caught() {
+        let status = guarded(|| panic!("evaluation failed"));
+        assert_eq!(status, Status::Panic);
+        assert_eq!(last_error(), "panic: evaluation failed");
+
+        let status = guarded(|| panic!("evaluation failed for {}", "abc"));
+        assert_eq!(status, Status::Panic);
+        assert_eq!(last_error(), "panic: evaluation failed for abc");
+    }
+
+    #[test]
+    fn null_pointers_are_invalid_arguments() {
+        let mut out: *mut c_char = ptr::null_mut();
+        let status = unsafe { sval_evaluate(ptr::null(), ptr::null(), &mut out) };
+
+        assert_eq!(status, Status::InvalidArgument);
+        assert_eq!(last_error(), "evaluator is null");
+        assert!(out.is_null());
+
+        let status = unsafe { sval_evaluate(ptr::null(), ptr::null(), ptr::null_mut()) };
+        assert_eq!(status, Status::InvalidArgument);
+        assert_eq!(last_error(), "out is null");
+    }
+}
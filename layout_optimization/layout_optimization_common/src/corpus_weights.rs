@@ -0,0 +1,177 @@
+//! The `corpus_weights` module lets an optimization configuration weight the corpora of a
+//! multi-corpus evaluation (see the `corpora` of the evaluation parameters) differently than
+//! the evaluation configuration does, e.g. to optimize one layout for
+//! `0.6 * english + 0.3 * code + 0.1 * spanish`.
+//!
+//! The optimizers minimize the combined cost, i.e. the weighted mean of the costs on the
+//! corpora. The weights are set in the evaluator before the optimization starts, such that the
+//! final evaluation reports the per-corpus results with the weights the optimization used.
+
+use layout_evaluation::evaluation::Evaluator;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Weights per corpus name
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(transparent)]
+pub struct CorpusWeights(BTreeMap<String, f64>);
+
+impl CorpusWeights {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Names of the weighted corpora.
+    pub fn corpus_names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(|name| name.as_str())
+    }
+
+    /// Check that the weights are not negative.
+    pub fn validate(&self) -> Result<()> {
+        for (name, weight) in self.0.iter() {
+            if weight.is_nan() || *weight < 0.0 {
+                bail!(
+                    "weight of corpus '{}' ({}) must not be negative",
+                    name,
+                    weight
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Set the weights of the corpora in `evaluator`.
+    pub fn apply(&self, evaluator: &mut Evaluator) {
+        for (name, weight) in self.0.iter() {
+            if !evaluator.set_corpus_weight(name, *weight) {
+                log::warn!(
+                    "Weight for unknown corpus '{}' (corpora: {})",
+                    name,
+                    evaluator.corpus_names().join(", ")
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::{layout_generator::LayoutGenerator, test_utils::ToyLayoutGenerator};
+    use layout_evaluation::{
+        evaluation::MetricParameters,
+        ngram_mapper::{
+            on_demand_ngram_mapper::{NgramMapperConfig, OnDemandNgramMapper},
+            NgramMapper,
+        },
+        ngrams::{Bigrams, Trigrams, Unigrams},
+    };
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    const LAYOUT: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP";
+    const ENGLISH: &str = "the quick brown fox jumps over the lazy dog";
+    const CODE: &str = "fn main() { let x = vec![1, 2, 3]; }";
+
+    fn ngram_mapper(text: &str) -> Box<dyn NgramMapper> {
+        Box::new(OnDemandNgramMapper::with_ngrams(
+            Unigrams::from_text(text).unwrap(),
+            Bigrams::from_text(text).unwrap(),
+            Trigrams::from_text(text).unwrap(),
+            NgramMapperConfig::default(),
+        ))
+    }
+
+    fn with_metrics(evaluator: Evaluator) -> Evaluator {
+        let params: MetricParameters = serde_json::from_str(
+            r#"{
+                "key_costs": {
+                    "enabled": true,
+                    "weight": 1.0,
+                    "normalization": {"type": "fixed", "value": 1.0},
+                    "params": {}
+                },
+                "bigram_stats": {
+                    "enabled": true,
+                    "weight": 1.0,
+                    "normalization": {"type": "fixed", "value": 1.0},
+                    "params": {"ignore_thumbs": false}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        evaluator.default_metrics(&params)
+    }
+
+    fn weights(json: &str) -> CorpusWeights {
+        serde_json::from_str(json).unwrap()
+    }
+
+    /// Random permutations of [`LAYOUT`] (the same ones for each call)
+    fn random_layouts(n: usize) -> Vec<String> {
+        let mut rng = StdRng::seed_from_u64(598);
+        let mut chars: Vec<char> = LAYOUT.chars().collect();
+        (0..n)
+            .map(|_| {
+                chars.shuffle(&mut rng);
+                chars.iter().collect()
+            })
+            .collect()
+    }
+
+    fn total_cost(evaluator: &Evaluator, layout: &str) -> f64 {
+        let layout = ToyLayoutGenerator::default().generate(layout).unwrap();
+        evaluator.evaluate_layout(&layout).total_cost()
+    }
+
+    #[test]
+    fn single_corpus_with_weight_one_is_unchanged() {
+        let single = with_metrics(Evaluator::default(ngram_mapper(ENGLISH)));
+        let mut weighted = with_metrics(Evaluator::with_corpora(vec![(
+            "english".to_string(),
+            0.3,
+            ngram_mapper(ENGLISH),
+        )]));
+        weights(r#"{"english": 1.0}"#).apply(&mut weighted);
+
+        for layout in random_layouts(20) {
+            let expected = total_cost(&single, &layout);
+            let actual = total_cost(&weighted, &layout);
+            assert_eq!(actual.to_bits(), expected.to_bits(), "{}", layout);
+        }
+    }
+
+    #[test]
+    fn combined_cost_is_the_weighted_mean() {
+        let english = with_metrics(Evaluator::default(ngram_mapper(ENGLISH)));
+        let code = with_metrics(Evaluator::default(ngram_mapper(CODE)));
+        let mut combined = with_metrics(Evaluator::with_corpora(vec![
+            ("english".to_string(), 1.0, ngram_mapper(ENGLISH)),
+            ("code".to_string(), 1.0, ngram_mapper(CODE)),
+        ]));
+        weights(r#"{"english": 0.6, "code": 0.2}"#).apply(&mut combined);
+
+        for layout in random_layouts(5) {
+            let expected = 0.75 * total_cost(&english, &layout) + 0.25 * total_cost(&code, &layout);
+            let actual = total_cost(&combined, &layout);
+            assert!(
+                (actual - expected).abs() <= 1e-9 * expected.abs(),
+                "{} != {}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn negative_weights_are_rejected() {
+        assert!(weights(r#"{"english": 0.6, "code": 0.0}"#)
+            .validate()
+            .is_ok());
+        assert!(weights(r#"{"english": -0.6}"#).validate().is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! The `early_stopping` module detects plateaus of an optimization run, i.e. phases in which
+//! the best cost did not improve significantly for a while.
+//!
+//! On a plateau, the optimizers either stop or, if restarts are configured, perturb the best
+//! layout with a number of random key swaps and continue from there.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct EarlyStoppingParameters {
+    /// Number of iterations (generations) without improvement after which the run stops or
+    /// restarts. Early stopping is disabled if not set.
+    pub patience: Option<u64>,
+    /// Minimum decrease of the best cost that counts as an improvement
+    #[serde(default)]
+    pub min_delta: f64,
+    /// Number of random key swaps applied to the best layout on a restart
+    #[serde(default)]
+    pub restart_swaps: usize,
+    /// Maximum number of restarts (0 stops at the first plateau)
+    #[serde(default)]
+    pub max_restarts: usize,
+}
+
+/// Tracks the best cost of a run and reports when it stagnates.
+#[derive(Clone, Debug)]
+pub struct PlateauDetector {
+    params: EarlyStoppingParameters,
+    reference_cost: f64,
+    last_improvement: u64,
+    restarts: usize,
+}
+
+/// Outcome of [`PlateauDetector::update`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlateauAction {
+    /// No plateau (yet)
+    Continue,
+    /// Continue from the best layout perturbed by the given number of key swaps
+    Restart(usize),
+    /// Stop the run
+    Stop,
+}
+
+impl PlateauDetector {
+    pub fn new(params: &EarlyStoppingParameters) -> Self {
+        Self {
+            params: params.clone(),
+            reference_cost: f64::INFINITY,
+            last_improvement: 0,
+            restarts: 0,
+        }
+    }
+
+    /// Register the best cost found up to `iteration` and decide how to proceed.
+    pub fn update(&mut self, iteration: u64, best_cost: f64) -> PlateauAction {
+        let patience = match self.params.patience {
+            Some(patience) => patience,
+            None => return PlateauAction::Continue,
+        };
+
+        if best_cost < self.reference_cost - self.params.min_delta {
+            self.reference_cost = best_cost;
+            self.last_improvement = iteration;
+        }
+
+        if iteration.saturating_sub(self.last_improvement) < patience {
+            PlateauAction::Continue
+        } else if self.restarts < self.params.max_restarts && self.params.restart_swaps > 0 {
+            self.restarts += 1;
+            self.last_improvement = iteration;
+            PlateauAction::Restart(self.params.restart_swaps)
+        } else {
+            PlateauAction::Stop
+        }
+    }
+
+    /// Number of restarts performed so far
+    pub fn restarts(&self) -> usize {
+        self.restarts
+    }
+
+    /// Description of the plateau that stops the run
+    pub fn stop_reason(&self) -> String {
+        format!(
+            "No improvement by more than {} within {} iterations",
+            self.params.min_delta,
+            self.params.patience.unwrap_or_default()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plateau_detector(patience: u64, min_delta: f64, max_restarts: usize) -> PlateauDetector {
+        PlateauDetector::new(&EarlyStoppingParameters {
+            patience: Some(patience),
+            min_delta,
+            restart_swaps: 3,
+            max_restarts,
+        })
+    }
+
+    #[test]
+    fn disabled_without_patience() {
+        let mut detector = PlateauDetector::new(&EarlyStoppingParameters::default());
+        for iteration in 0..1000 {
+            assert_eq!(detector.update(iteration, 1.0), PlateauAction::Continue);
+        }
+    }
+
+    #[test]
+    fn improvements_below_min_delta_do_not_count() {
+        let mut detector = plateau_detector(10, 0.5, 0);
+        assert_eq!(detector.update(0, 10.0), PlateauAction::Continue);
+        // improves by 0.1 per iteration, i.e. by more than 0.5 every sixth iteration
+        for iteration in 1..30 {
+            assert_eq!(
+                detector.update(iteration, 10.0 - 0.1 * iteration as f64),
+                PlateauAction::Continue
+            );
+        }
+
+        let mut detector = plateau_detector(10, 0.5, 0);
+        assert_eq!(detector.update(0, 10.0), PlateauAction::Continue);
+        for iteration in 1..10 {
+            assert_eq!(
+                detector.update(iteration, 10.0 - 0.04 * iteration as f64),
+                PlateauAction::Continue
+            );
+        }
+        assert_eq!(detector.update(10, 9.6), PlateauAction::Stop);
+    }
+
+    #[test]
+    fn restarts_before_stopping() {
+        let mut detector = plateau_detector(5, 0.0, 2);
+        let actions: Vec<(u64, PlateauAction)> = (0..20)
+            .map(|iteration| (iteration, detector.update(iteration, 1.0)))
+            .filter(|(_, action)| *action != PlateauAction::Continue)
+            .collect();
+
+        assert_eq!(
+            actions[..3],
+            [
+                (5, PlateauAction::Restart(3)),
+                (10, PlateauAction::Restart(3)),
+                (15, PlateauAction::Stop)
+            ]
+        );
+        assert_eq!(detector.restarts(), 2);
+    }
+}
@@ -1,3 +1,15 @@
+pub mod corpus_weights;
+pub mod early_stopping;
+pub mod linked_pairs;
+pub mod position_mask;
+pub mod progress;
+pub mod weight_schedule;
+
+use keyboard_layout::layout_generator::LayoutGenerator;
+use linked_pairs::PairLinker;
+use position_mask::PositionMask;
+
+use anyhow::Result;
 use rand::{seq::SliceRandom, thread_rng};
 
 #[derive(Clone, Debug)]
@@ -6,17 +18,34 @@ pub struct LayoutPermutator {
     perm_indices: Vec<usize>,
     fixed_keys: Vec<char>,
     fixed_indices: Vec<usize>,
+    linker: Option<PairLinker>,
 }
 
 impl LayoutPermutator {
     pub fn new(layout: &str, fixed: &str) -> Self {
+        Self::with_fixed_positions(layout, fixed, &[])
+    }
+
+    /// Like [`LayoutPermutator::new`], additionally fixing the positions selected by `mask`.
+    pub fn with_mask(
+        layout: &str,
+        fixed: &str,
+        mask: &PositionMask,
+        layout_generator: &dyn LayoutGenerator,
+    ) -> Result<Self> {
+        let fixed_positions = mask.masked_positions(layout, layout_generator)?;
+        Ok(Self::with_fixed_positions(layout, fixed, &fixed_positions))
+    }
+
+    /// Fix the symbols in `fixed` as well as the symbols at the given positions of `layout`.
+    pub fn with_fixed_positions(layout: &str, fixed: &str, fixed_positions: &[usize]) -> Self {
         let mut perm_keys = Vec::new();
         let mut perm_indices = Vec::new();
         let mut fixed_keys = Vec::new();
         let mut fixed_indices = Vec::new();
 
         for (i, c) in layout.chars().enumerate() {
-            if fixed.contains(c) {
+            if fixed.contains(c) || fixed_positions.contains(&i) {
                 fixed_keys.push(c);
                 fixed_indices.push(i);
             } else {
@@ -29,6 +58,41 @@ impl LayoutPermutator {
             perm_indices,
             fixed_keys,
             fixed_indices,
+            linker: None,
+        }
+    }
+
+    /// Keep the given symbol pairs on mirrored keys in all permutations (see [`PairLinker`]).
+    pub fn with_linked_pairs(
+        mut self,
+        pairs: &[(char, char)],
+        layout: &str,
+        layout_generator: &dyn LayoutGenerator,
+    ) -> Result<Self> {
+        self.linker = if pairs.is_empty() {
+            None
+        } else {
+            Some(PairLinker::new(
+                pairs,
+                &self.perm_keys,
+                layout,
+                layout_generator,
+            )?)
+        };
+        Ok(self)
+    }
+
+    /// The [`PairLinker`] keeping linked pairs mirrored, if any pairs are linked.
+    pub fn linker(&self) -> Option<&PairLinker> {
+        self.linker.as_ref()
+    }
+
+    /// Adjust `permutation` (derived from `previous`) such that linked pairs are mirrored
+    /// (see [`PairLinker::link`]).
+    pub fn link(&self, previous: &[usize], permutation: Vec<usize>) -> Vec<usize> {
+        match &self.linker {
+            Some(linker) => linker.link(previous, permutation),
+            None => permutation,
         }
     }
 
@@ -52,7 +116,7 @@ impl LayoutPermutator {
         let mut indices: Vec<usize> = self.perm_indices.to_vec();
         indices.shuffle(&mut thread_rng());
 
-        indices
+        self.link(&indices.clone(), indices)
     }
 
     /// Takes in a Layout, switches [nr_switches] keys in that layout, then returns it.
@@ -70,7 +134,7 @@ impl LayoutPermutator {
             indices.swap(*sw0, *sw1);
         }
 
-        indices
+        self.link(permutation, indices)
     }
 
     pub fn switch_n_keys(&self, permutation: &[usize], n_keys: usize) -> Vec<usize> {
@@ -83,15 +147,15 @@ impl LayoutPermutator {
         sw_to.shuffle(rng);
 
         // Perform nr_switches switches
-        for (from, to) in sw_from.into_iter().zip(sw_to.into_iter()) {
+        for (from, to) in sw_from.into_iter().zip(sw_to) {
             indices[*to] = permutation[*from];
         }
 
-        indices
+        self.link(permutation, indices)
     }
 
     pub fn get_permutable_indices(&self) -> Vec<usize> {
-        self.perm_indices.clone()
+        self.link(&self.perm_indices, self.perm_indices.clone())
     }
 }
 
@@ -0,0 +1,172 @@
+//! The `linked_pairs` module keeps pairs of symbols (e.g. "(" and ")") on mirrored keys during
+//! optimization, so that the optimizer does not need to find the mirrored placement itself.
+//!
+//! Two keys are mirrored if they are pressed by the same finger in the same direction on
+//! opposite hands (the default of the `symmetric_handswitches` metric). Whenever a move places
+//! one member of a pair somewhere else, the other member follows to the mirrored key, swapping
+//! places with the symbol found there. If that symbol belongs to a linked pair as well, its
+//! partner follows in turn. Moves that can not be completed this way (e.g. because the mirrored
+//! key is fixed) are rejected.
+
+use keyboard_layout::{key::Key, layout_generator::LayoutGenerator};
+
+use anyhow::{bail, Result};
+
+/// Repairs permutations (see [`crate::LayoutPermutator`]) such that linked pairs stay mirrored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PairLinker {
+    /// Indices of the linked symbols within the permutable symbols
+    pairs: Vec<(usize, usize)>,
+    /// Mirrored position (if any) for each position of the layout string
+    mirrors: Vec<Option<usize>>,
+}
+
+fn is_mirrored(k1: &Key, k2: &Key) -> bool {
+    k1.hand != k2.hand && k1.finger == k2.finger && k1.direction == k2.direction
+}
+
+impl PairLinker {
+    /// Link the given symbol pairs of `layout_str`, whose permutable symbols are `perm_keys`.
+    pub fn new(
+        pairs: &[(char, char)],
+        perm_keys: &[char],
+        layout_str: &str,
+        layout_generator: &dyn LayoutGenerator,
+    ) -> Result<Self> {
+        let symbol_index = |c: &char| -> Result<usize> {
+            match perm_keys.iter().position(|k| k == c) {
+                Some(idx) => Ok(idx),
+                None => bail!("Linked pairs: symbol '{}' is not permutable", c),
+            }
+        };
+        let pairs = pairs
+            .iter()
+            .map(|(c1, c2)| Ok((symbol_index(c1)?, symbol_index(c2)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let layout = layout_generator.generate(layout_str)?;
+        let keys: Vec<&Key> = layout
+            .non_fixed_layerkeys()
+            .iter()
+            .filter_map(|layerkeys| layerkeys.first().map(|lk| &lk.key))
+            .collect();
+        let mirrors = keys
+            .iter()
+            .map(|key| keys.iter().position(|other| is_mirrored(key, other)))
+            .collect();
+
+        Ok(Self { pairs, mirrors })
+    }
+
+    /// Mirrored position of the key holding `symbol` in `permutation`
+    fn mirror(&self, permutation: &[usize], symbol: usize) -> Option<usize> {
+        self.mirrors.get(permutation[symbol]).copied().flatten()
+    }
+
+    /// Adjust the permutation `new` (derived from `old` by some move) such that all linked
+    /// pairs are mirrored. The member of a pair that was moved determines where the other one
+    /// goes (the first one if none or both were moved, or the other one if the determining
+    /// member is on a key without mirrored counterpart). Returns `old` if that is not possible.
+    pub fn link(&self, old: &[usize], new: Vec<usize>) -> Vec<usize> {
+        let mut permutation = new;
+
+        // every round settles at least one pair unless it displaces a linked symbol
+        for _ in 0..=self.pairs.len() {
+            let mut changed = false;
+            for &(s1, s2) in self.pairs.iter() {
+                let (anchor, follower) = if permutation[s1] == old[s1] && permutation[s2] != old[s2]
+                {
+                    (s2, s1)
+                } else {
+                    (s1, s2)
+                };
+                if self.mirror(&permutation, anchor) == Some(permutation[follower]) {
+                    continue;
+                }
+
+                let (follower, target) = match self.mirror(&permutation, anchor) {
+                    Some(target) => (follower, target),
+                    None => match self.mirror(&permutation, follower) {
+                        Some(target) => (anchor, target),
+                        None => return old.to_vec(),
+                    },
+                };
+
+                // swap the follower with the symbol at the mirrored position
+                match permutation.iter().position(|pos| *pos == target) {
+                    Some(occupant) => permutation.swap(follower, occupant),
+                    None => return old.to_vec(),
+                }
+                changed = true;
+            }
+
+            if !changed {
+                return permutation;
+            }
+        }
+
+        old.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LayoutPermutator;
+
+    use keyboard_layout::test_utils::ToyLayoutGenerator;
+
+    /// The left pinky has "a" (North), "b" (Out), "c" (Center), "d" (In), and "e" (South), the
+    /// right pinky "J" (North), "K" (In), "L" (Center), "M" (Out), and "N" (South).
+    const LAYOUT: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP";
+
+    fn permutator(pairs: &[(char, char)], fixed: &str) -> LayoutPermutator {
+        LayoutPermutator::new(LAYOUT, fixed)
+            .with_linked_pairs(pairs, LAYOUT, &ToyLayoutGenerator::default())
+            .unwrap()
+    }
+
+    /// Move "a" to the key of "c" and link the result
+    fn move_a_to_c(pm: &LayoutPermutator) -> String {
+        let old = pm.get_permutable_indices();
+        let mut new = old.clone();
+        new.swap(0, 2);
+        pm.generate_string(&pm.link(&old, new))
+    }
+
+    #[test]
+    fn partner_follows_to_the_mirrored_key() {
+        let layout = move_a_to_c(&permutator(&[('a', 'J')], ""));
+        assert_eq!(&layout[..5], "cbade");
+        assert_eq!(&layout[35..40], "LKJMN");
+    }
+
+    #[test]
+    fn displaced_linked_symbols_follow_as_well() {
+        let layout = move_a_to_c(&permutator(&[('a', 'J'), ('c', 'L')], ""));
+        assert_eq!(&layout[..5], "cbade");
+        assert_eq!(&layout[35..40], "LKJMN");
+
+        // "K" and "b" are not on mirrored keys (In and Out), so linking them swaps "K" with "M"
+        let pm = permutator(&[('b', 'K')], "");
+        assert_eq!(
+            &pm.generate_string(&pm.get_permutable_indices())[35..40],
+            "JMLKN"
+        );
+    }
+
+    #[test]
+    fn moves_to_fixed_mirrored_keys_are_rejected() {
+        let layout = move_a_to_c(&permutator(&[('a', 'J')], "L"));
+        assert_eq!(layout, LAYOUT);
+    }
+
+    #[test]
+    fn unknown_symbols_are_rejected() {
+        let result = LayoutPermutator::new(LAYOUT, "J").with_linked_pairs(
+            &[('a', 'J')],
+            LAYOUT,
+            &ToyLayoutGenerator::default(),
+        );
+        assert!(result.is_err());
+    }
+}
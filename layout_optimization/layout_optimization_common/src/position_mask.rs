@@ -0,0 +1,128 @@
+//! The `position_mask` module freezes whole regions of a layout during optimization, e.g. all
+//! thumb keys or everything but the base-layer finger keys.
+//!
+//! A [`PositionMask`] selects keys by layer, by (hand, finger) cluster, by (hand, finger,
+//! direction) or by matrix position. The symbols on masked keys keep their positions and are
+//! excluded from the permutation (see [`crate::LayoutPermutator::with_mask`]).
+
+use keyboard_layout::{
+    key::{Direction, Finger, Hand, Key, MatrixPosition},
+    layout_generator::LayoutGenerator,
+};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct PositionMask {
+    /// Keys carrying a symbol on any of these layers (counted from 0 for the base layer)
+    #[serde(default)]
+    pub layers: Vec<u8>,
+    /// Keys of these finger clusters, e.g. `[Left, Thumb]`
+    #[serde(default)]
+    pub fingers: Vec<(Hand, Finger)>,
+    /// Single keys, e.g. `[Right, Pinky, South]`
+    #[serde(default)]
+    pub keys: Vec<(Hand, Finger, Direction)>,
+    /// Keys at these matrix positions (column, row)
+    #[serde(default)]
+    pub matrix_positions: Vec<MatrixPosition>,
+}
+
+impl PositionMask {
+    /// Whether the mask does not select any key.
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+            && self.fingers.is_empty()
+            && self.keys.is_empty()
+            && self.matrix_positions.is_empty()
+    }
+
+    fn matches(&self, key: &Key, n_layers: usize) -> bool {
+        self.layers.iter().any(|layer| (*layer as usize) < n_layers)
+            || self.fingers.contains(&(key.hand, key.finger))
+            || self.keys.contains(&(key.hand, key.finger, key.direction))
+            || self.matrix_positions.contains(&key.matrix_position)
+    }
+
+    /// Indices of the masked positions within `layout_str` (the non-fixed keys of the layout).
+    ///
+    /// Fails if `layout_str` can not be generated or does not cover all non-fixed keys.
+    pub fn masked_positions(
+        &self,
+        layout_str: &str,
+        layout_generator: &dyn LayoutGenerator,
+    ) -> Result<Vec<usize>> {
+        if self.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let layout = layout_generator.generate(layout_str)?;
+        let key_layerkeys = layout.non_fixed_layerkeys();
+        let n_positions = layout_str.chars().count();
+        if key_layerkeys.len() != n_positions {
+            bail!(
+                "Position mask: layout string has {} symbols for {} non-fixed keys",
+                n_positions,
+                key_layerkeys.len()
+            );
+        }
+
+        let masked = key_layerkeys
+            .iter()
+            .enumerate()
+            .filter(|(_, layerkeys)| match layerkeys.first() {
+                Some(layerkey) => self.matches(&layerkey.key, layerkeys.len()),
+                None => false,
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        Ok(masked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::ToyLayoutGenerator;
+
+    /// Left hand: "a" to "t", right hand: "u" to "z" and "A" to "N", thumbs "O" (left) and "P"
+    /// (right).
+    const LAYOUT: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP";
+
+    fn masked(mask: &str) -> Vec<usize> {
+        let mask: PositionMask = serde_json::from_str(mask).unwrap();
+        mask.masked_positions(LAYOUT, &ToyLayoutGenerator::default())
+            .unwrap()
+    }
+
+    #[test]
+    fn empty_mask() {
+        assert!(PositionMask::default().is_empty());
+        assert!(masked("{}").is_empty());
+    }
+
+    #[test]
+    fn mask_by_finger_key_and_matrix_position() {
+        assert_eq!(
+            masked(r#"{"fingers": [["Left", "Thumb"], ["Right", "Thumb"]]}"#),
+            [40, 41]
+        );
+        assert_eq!(masked(r#"{"keys": [["Right", "Pinky", "South"]]}"#), [39]);
+        // the Center key of the left pinky
+        assert_eq!(masked(r#"{"matrix_positions": [[2, 2]]}"#), [2]);
+        assert_eq!(
+            masked(r#"{"fingers": [["Left", "Index"]], "keys": [["Left", "Pinky", "North"]]}"#),
+            [0, 15, 16, 17, 18, 19]
+        );
+    }
+
+    #[test]
+    fn mask_by_layer() {
+        // all keys have a symbol on the base layer, none on the second one
+        assert_eq!(masked(r#"{"layers": [0]}"#).len(), LAYOUT.len());
+        assert!(masked(r#"{"layers": [1]}"#).is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+//! The `progress` module reports the best layout found so far at regular intervals during
+//! an optimization run, e.g. to watch the convergence of long runs.
+//!
+//! A [`ProgressReporter`] assembles [`ProgressSnapshot`]s and passes them to any number of
+//! [`OptimizationObserver`]s. [`LogObserver`] and [`JsonlObserver`] are provided, custom
+//! observers can be plugged in by implementing the trait.
+
+use keyboard_layout::layout_generator::LayoutGenerator;
+use layout_evaluation::evaluation::Evaluator;
+
+use serde::Serialize;
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    sync::Arc,
+};
+
+/// Shares (in percent) shown by the [`LogObserver`], if available
+const HEADLINE_STATISTICS: [&str; 6] = [
+    "sfb",
+    "vertical",
+    "squeeze",
+    "splay",
+    "redirect",
+    "bigram_roll_total",
+];
+
+/// The best layout of an optimization run at a given iteration (or generation)
+#[derive(Clone, Debug, Serialize)]
+pub struct ProgressSnapshot {
+    /// Name of the optimization process
+    pub process: String,
+    pub iteration: u64,
+    /// Best layout found so far
+    pub layout: String,
+    /// Total cost of the best layout
+    pub cost: f64,
+    /// Named shares (in percent) of the best layout, see [`Evaluator::statistics`]
+    pub statistics: Vec<(String, f64)>,
+}
+
+/// An observer receiving [`ProgressSnapshot`]s from a [`ProgressReporter`]
+pub trait OptimizationObserver: Send {
+    fn observe(&mut self, snapshot: &ProgressSnapshot);
+}
+
+/// Logs the snapshots (with the headline statistics) at info level.
+#[derive(Clone, Debug, Default)]
+pub struct LogObserver;
+
+impl OptimizationObserver for LogObserver {
+    fn observe(&mut self, snapshot: &ProgressSnapshot) {
+        let statistics: Vec<String> = snapshot
+            .statistics
+            .iter()
+            .filter(|(name, _)| HEADLINE_STATISTICS.contains(&name.as_str()))
+            .map(|(name, percent)| format!("{}: {:.2}%", name, percent))
+            .collect();
+
+        log::info!(
+            "{}: Progress at {}: {} ({:.1}) {}",
+            snapshot.process,
+            snapshot.iteration,
+            snapshot.layout,
+            snapshot.cost,
+            statistics.join(", ")
+        );
+    }
+}
+
+/// Appends the snapshots as JSON lines to a file.
+#[derive(Debug)]
+pub struct JsonlObserver {
+    file: File,
+}
+
+impl JsonlObserver {
+    pub fn new(filename: &str) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(filename)?;
+
+        Ok(Self { file })
+    }
+}
+
+impl OptimizationObserver for JsonlObserver {
+    fn observe(&mut self, snapshot: &ProgressSnapshot) {
+        let res = serde_json::to_string(snapshot)
+            .map_err(io::Error::from)
+            .and_then(|line| writeln!(self.file, "{}", line));
+        if let Err(e) = res {
+            log::error!("Could not write progress snapshot: {}", e);
+        }
+    }
+}
+
+/// Passes a [`ProgressSnapshot`] of the best layout to its observers every `interval`
+/// iterations.
+///
+/// The statistics of the snapshot require mapping the ngrams, but no cost computation. They
+/// are only collected if the best layout changed since the last snapshot.
+pub struct ProgressReporter {
+    interval: u64,
+    evaluator: Arc<Evaluator>,
+    layout_generator: Box<dyn LayoutGenerator>,
+    observers: Vec<Box<dyn OptimizationObserver>>,
+    last_statistics: Option<(String, Vec<(String, f64)>)>,
+}
+
+impl ProgressReporter {
+    pub fn new(
+        interval: u64,
+        evaluator: Arc<Evaluator>,
+        layout_generator: Box<dyn LayoutGenerator>,
+    ) -> Self {
+        Self {
+            interval: interval.max(1),
+            evaluator,
+            layout_generator,
+            observers: Vec::new(),
+            last_statistics: None,
+        }
+    }
+
+    /// Number of iterations between two snapshots.
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Add an observer receiving the snapshots.
+    pub fn with_observer(mut self, observer: Box<dyn OptimizationObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Report the best layout `layout` with total cost `cost` if `iteration` is a multiple of
+    /// the interval.
+    pub fn report(&mut self, process: &str, iteration: u64, layout: &str, cost: f64) {
+        if iteration % self.interval != 0 || self.observers.is_empty() {
+            return;
+        }
+
+        let statistics = match &self.last_statistics {
+            Some((last_layout, statistics)) if last_layout == layout => statistics.clone(),
+            _ => {
+                let statistics: Vec<(String, f64)> = match self.layout_generator.generate(layout) {
+                    Ok(l) => self
+                        .evaluator
+                        .statistics(&l)
+                        .into_iter()
+                        .map(|(name, percent)| (name.to_string(), percent))
+                        .collect(),
+                    Err(e) => {
+                        log::error!("Could not generate layout '{}': {:?}", layout, e);
+                        Vec::new()
+                    }
+                };
+                self.last_statistics = Some((layout.to_string(), statistics.clone()));
+                statistics
+            }
+        };
+
+        let snapshot = ProgressSnapshot {
+            process: process.to_string(),
+            iteration,
+            layout: layout.to_string(),
+            cost,
+            statistics,
+        };
+        self.observers
+            .iter_mut()
+            .for_each(|observer| observer.observe(&snapshot));
+    }
+}
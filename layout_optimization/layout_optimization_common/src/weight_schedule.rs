@@ -0,0 +1,298 @@
+//! The `weight_schedule` module anneals the weights of metrics over the course of an
+//! optimization run, e.g. to let the optimizer explore freely under a light penalty first and
+//! tighten it towards the end.
+//!
+//! A [`WeightSchedule`] interpolates between a start and an end weight depending on the progress
+//! of the run (the fraction of the iteration or generation limit that has passed). The
+//! optimizers' fitness functions re-weight each evaluation with the weights at the current
+//! progress (see [`ScheduledWeights`]), while the evaluator itself uses the end weights (see
+//! [`WeightSchedules::apply_end_weights`]). Therefore, the final evaluation reports the end
+//! weights.
+//!
+//! The progress advances in steps of 1% of the run, such that optimizers can afford to
+//! re-evaluate layouts whenever the weights change.
+//!
+//! Metrics are selected by their names as printed in the evaluation, e.g. "Finger Balance".
+
+use layout_evaluation::{evaluation::Evaluator, results::EvaluationResult};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// Number of steps the progress of a run advances in
+const PROGRESS_STEPS: f64 = 100.0;
+
+/// How the weight changes from the start to the end weight
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Interpolation {
+    /// Change the weight linearly with the progress
+    Linear,
+    /// Switch from the start to the end weight once the progress reaches `at` (0 to 1)
+    Step { at: f64 },
+}
+
+impl Default for Interpolation {
+    fn default() -> Self {
+        Interpolation::Linear
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct WeightSchedule {
+    /// Weight at the beginning of the run
+    pub start: f64,
+    /// Weight at the end of the run (and in the final evaluation)
+    pub end: f64,
+    #[serde(default)]
+    pub interpolation: Interpolation,
+}
+
+impl WeightSchedule {
+    /// Weight at the given progress (0 to 1) of the run.
+    pub fn weight(&self, progress: f64) -> f64 {
+        let progress = progress.clamp(0.0, 1.0);
+        match self.interpolation {
+            Interpolation::Linear => self.start + progress * (self.end - self.start),
+            Interpolation::Step { at } if progress < at => self.start,
+            Interpolation::Step { .. } => self.end,
+        }
+    }
+}
+
+/// Weight schedules per metric name
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(transparent)]
+pub struct WeightSchedules(BTreeMap<String, WeightSchedule>);
+
+impl WeightSchedules {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Names of the scheduled metrics.
+    pub fn metric_names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(|name| name.as_str())
+    }
+
+    /// Check that the step positions are between 0 and 1.
+    pub fn validate(&self) -> Result<()> {
+        for (name, schedule) in self.0.iter() {
+            if let Interpolation::Step { at } = schedule.interpolation {
+                if !(0.0..=1.0).contains(&at) {
+                    bail!(
+                        "weight schedule of '{}': step position ({}) must be between 0 and 1",
+                        name,
+                        at
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Set the weights of the scheduled metrics in `evaluator` to their end weights.
+    pub fn apply_end_weights(&self, evaluator: &mut Evaluator) {
+        for (name, schedule) in self.0.iter() {
+            if !evaluator.set_metric_weight(name, schedule.end) {
+                log::warn!(
+                    "Weight schedule for unknown (or disabled) metric '{}'",
+                    name
+                );
+            }
+        }
+    }
+
+    /// Track the weights over the course of a run, if there are any schedules.
+    pub fn tracker(&self) -> Option<ScheduledWeights> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(ScheduledWeights {
+                schedules: Arc::new(self.clone()),
+                progress: Arc::new(AtomicU64::new(0.0f64.to_bits())),
+            })
+        }
+    }
+}
+
+/// The weights of [`WeightSchedules`] at the current progress of a run. Clones share the
+/// progress, so that the optimization loop can update it for the (possibly parallel) fitness
+/// evaluations.
+#[derive(Clone, Debug)]
+pub struct ScheduledWeights {
+    schedules: Arc<WeightSchedules>,
+    /// Progress (0 to 1) stored as the bits of an `f64`
+    progress: Arc<AtomicU64>,
+}
+
+impl ScheduledWeights {
+    /// Register that `done` of `total` iterations (generations) have passed. Returns whether
+    /// the progress (and therefore possibly the weights) changed.
+    pub fn set_progress(&self, done: u64, total: u64) -> bool {
+        let progress = if total == 0 {
+            1.0
+        } else {
+            (PROGRESS_STEPS * done as f64 / total as f64).floor() / PROGRESS_STEPS
+        };
+        let previous = self.progress.swap(progress.to_bits(), Ordering::Relaxed);
+
+        previous != progress.to_bits()
+    }
+
+    pub fn progress(&self) -> f64 {
+        f64::from_bits(self.progress.load(Ordering::Relaxed))
+    }
+
+    /// Re-weight an evaluation with the weights at the current progress.
+    pub fn apply(&self, evaluation_result: &mut EvaluationResult) {
+        let progress = self.progress();
+        evaluation_result.reweight(|name| self.schedules.0.get(name).map(|s| s.weight(progress)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::{layout_generator::LayoutGenerator, test_utils::ToyLayoutGenerator};
+    use layout_evaluation::{
+        evaluation::MetricParameters,
+        ngram_mapper::on_demand_ngram_mapper::{NgramMapperConfig, OnDemandNgramMapper},
+        ngrams::{Bigrams, Trigrams, Unigrams},
+    };
+
+    const LAYOUT: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP";
+
+    /// Only the key costs (with weight 1.0) on a small corpus
+    fn evaluator() -> Evaluator {
+        let text = "the quick brown fox jumps over the lazy dog";
+        let ngram_mapper = OnDemandNgramMapper::with_ngrams(
+            Unigrams::from_text(text).unwrap(),
+            Bigrams::from_text(text).unwrap(),
+            Trigrams::from_text(text).unwrap(),
+            NgramMapperConfig::default(),
+        );
+        let params: MetricParameters = serde_json::from_str(
+            r#"{"key_costs": {
+                "enabled": true,
+                "weight": 1.0,
+                "normalization": {"type": "fixed", "value": 1.0},
+                "params": {}
+            }}"#,
+        )
+        .unwrap();
+
+        Evaluator::default(Box::new(ngram_mapper)).default_metrics(&params)
+    }
+
+    fn schedules(json: &str) -> WeightSchedules {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn evaluate(evaluator: &Evaluator) -> EvaluationResult {
+        let layout = ToyLayoutGenerator::default().generate(LAYOUT).unwrap();
+        evaluator.evaluate_layout(&layout)
+    }
+
+    /// Total cost of [`LAYOUT`] with the scheduled weights after `done` of 100 iterations
+    fn scheduled_cost(evaluator: &Evaluator, weights: &ScheduledWeights, done: u64) -> f64 {
+        weights.set_progress(done, 100);
+        let mut evaluation_result = evaluate(evaluator);
+        weights.apply(&mut evaluation_result);
+        evaluation_result.total_cost()
+    }
+
+    #[test]
+    fn linear_and_step_interpolation() {
+        let linear: WeightSchedule = serde_json::from_str(r#"{"start": 1, "end": 3}"#).unwrap();
+        assert_eq!(linear.weight(0.0), 1.0);
+        assert_eq!(linear.weight(0.25), 1.5);
+        assert_eq!(linear.weight(1.0), 3.0);
+        assert_eq!(linear.weight(2.0), 3.0);
+
+        let step: WeightSchedule = serde_json::from_str(
+            r#"{"start": 1, "end": 3, "interpolation": {"type": "step", "at": 0.5}}"#,
+        )
+        .unwrap();
+        assert_eq!(step.weight(0.49), 1.0);
+        assert_eq!(step.weight(0.5), 3.0);
+    }
+
+    #[test]
+    fn progress_advances_in_steps() {
+        let weights = schedules(r#"{"Key Costs": {"start": 1, "end": 3}}"#)
+            .tracker()
+            .unwrap();
+
+        assert!(!weights.set_progress(5, 1000));
+        assert_eq!(weights.progress(), 0.0);
+        assert!(weights.set_progress(10, 1000));
+        assert_eq!(weights.progress(), 0.01);
+        assert!(!weights.set_progress(19, 1000));
+        assert!(weights.set_progress(0, 0));
+        assert_eq!(weights.progress(), 1.0);
+    }
+
+    #[test]
+    fn fitness_follows_the_schedule() {
+        let evaluator = evaluator();
+        let unweighted = evaluate(&evaluator).total_cost();
+        assert!(unweighted > 0.0);
+
+        let linear = schedules(r#"{"Key Costs": {"start": 1, "end": 3}}"#)
+            .tracker()
+            .unwrap();
+        assert_eq!(scheduled_cost(&evaluator, &linear, 0), unweighted);
+        assert_eq!(scheduled_cost(&evaluator, &linear, 25), 1.5 * unweighted);
+        assert_eq!(scheduled_cost(&evaluator, &linear, 50), 2.0 * unweighted);
+        assert_eq!(scheduled_cost(&evaluator, &linear, 100), 3.0 * unweighted);
+
+        let step = schedules(
+            r#"{"Key Costs": {"start": 1, "end": 3, "interpolation": {"type": "step", "at": 0.5}}}"#,
+        )
+        .tracker()
+        .unwrap();
+        assert_eq!(scheduled_cost(&evaluator, &step, 49), unweighted);
+        assert_eq!(scheduled_cost(&evaluator, &step, 50), 3.0 * unweighted);
+    }
+
+    #[test]
+    fn final_evaluation_uses_the_end_weights() {
+        let mut evaluator = evaluator();
+        let unweighted = evaluate(&evaluator).total_cost();
+
+        schedules(r#"{"Key Costs": {"start": 1, "end": 3}, "Unknown": {"start": 1, "end": 2}}"#)
+            .apply_end_weights(&mut evaluator);
+
+        assert_eq!(evaluate(&evaluator).total_cost(), 3.0 * unweighted);
+    }
+
+    #[test]
+    fn step_positions_are_validated() {
+        let at = |at: f64| {
+            schedules(&format!(
+                r#"{{"SFB": {{"start": 1, "end": 3, "interpolation": {{"type": "step", "at": {}}}}}}}"#,
+                at
+            ))
+            .validate()
+        };
+
+        assert!(at(0.5).is_ok());
+        assert!(at(1.5).is_err());
+        assert!(at(-0.5).is_err());
+    }
+
+    #[test]
+    fn no_tracker_without_schedules() {
+        assert!(WeightSchedules::default().tracker().is_none());
+    }
+}
@@ -0,0 +1,105 @@
+//! The `diversity` module measures how different the layouts of a population are and
+//! configures the injection of fresh individuals into populations that collapsed to
+//! (almost) identical layouts.
+//!
+//! The diversity is the mean pairwise Hamming distance between the layouts of a random sample
+//! of the population, relative to the number of permutable keys. It is 0 for a population of
+//! identical layouts and close to 1 for random layouts.
+
+use rand::{seq::SliceRandom, thread_rng};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DiversityParameters {
+    /// Inject fresh individuals if the diversity falls below this value (0 to 1). Injection
+    /// is disabled if not set.
+    pub min_diversity: Option<f64>,
+    /// Fraction of the population (the least fit individuals) replaced on an injection
+    #[serde(default = "default_replace_fraction")]
+    pub replace_fraction: f64,
+    /// Number of individuals sampled for measuring the diversity
+    #[serde(default = "default_sample_size")]
+    pub sample_size: usize,
+    /// Derive the fresh individuals from the best layout by this many random key swaps instead
+    /// of generating random layouts
+    #[serde(default)]
+    pub injection_swaps: Option<usize>,
+}
+
+fn default_replace_fraction() -> f64 {
+    0.5
+}
+
+fn default_sample_size() -> usize {
+    30
+}
+
+impl Default for DiversityParameters {
+    fn default() -> Self {
+        Self {
+            min_diversity: None,
+            replace_fraction: default_replace_fraction(),
+            sample_size: default_sample_size(),
+            injection_swaps: None,
+        }
+    }
+}
+
+/// Relative Hamming distance between two layouts of the same length
+fn distance(a: &[usize], b: &[usize]) -> f64 {
+    if a.is_empty() {
+        return 0.0;
+    }
+
+    let n_different = a.iter().zip(b).filter(|(x, y)| x != y).count();
+    n_different as f64 / a.len() as f64
+}
+
+/// Mean pairwise (relative) Hamming distance between the layouts of a random sample of
+/// `sample_size` individuals.
+pub fn diversity(individuals: &[Vec<usize>], sample_size: usize) -> f64 {
+    let sample: Vec<&Vec<usize>> = individuals
+        .choose_multiple(&mut thread_rng(), sample_size)
+        .collect();
+    if sample.len() < 2 {
+        return 0.0;
+    }
+
+    let mut total_distance = 0.0;
+    let mut n_pairs = 0;
+    for (i, a) in sample.iter().enumerate() {
+        for b in sample.iter().skip(i + 1) {
+            total_distance += distance(a, b);
+            n_pairs += 1;
+        }
+    }
+
+    total_distance / n_pairs as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_hamming_distance() {
+        assert_eq!(distance(&[0, 1, 2, 3], &[0, 1, 2, 3]), 0.0);
+        assert_eq!(distance(&[0, 1, 2, 3], &[1, 0, 2, 3]), 0.5);
+        assert_eq!(distance(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn diversity_of_identical_and_distinct_layouts() {
+        let identical = vec![vec![0, 1, 2, 3]; 10];
+        assert_eq!(diversity(&identical, 5), 0.0);
+
+        // all layouts differ on every key
+        let rotations: Vec<Vec<usize>> = (0..4)
+            .map(|shift| (0..4).map(|i| (i + shift) % 4).collect())
+            .collect();
+        assert_eq!(diversity(&rotations, 4), 1.0);
+
+        // a single sampled layout has no pairs
+        assert_eq!(diversity(&rotations, 1), 0.0);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod diversity;
 pub mod optimization;
 
 #[cfg(test)]
@@ -1,31 +1,96 @@
+use crate::diversity::{diversity, DiversityParameters};
+
 use keyboard_layout::{layout::Layout, layout_generator::LayoutGenerator};
 use layout_evaluation::{cache::Cache, evaluation::Evaluator};
 
-use layout_optimization_common::LayoutPermutator;
+use layout_optimization_common::{
+    corpus_weights::CorpusWeights,
+    early_stopping::{EarlyStoppingParameters, PlateauAction, PlateauDetector},
+    linked_pairs::PairLinker,
+    position_mask::PositionMask,
+    progress::ProgressReporter,
+    weight_schedule::{ScheduledWeights, WeightSchedules},
+    LayoutPermutator,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
 use std::{fs::File, sync::Arc};
 
 use genevo::{
-    genetic::{Children, FitnessFunction, Parents},
-    operator::{prelude::*, CrossoverOp, GeneticOperator},
+    algorithm::EvaluatedPopulation,
+    genetic::{Children, FitnessFunction, Offspring, Parents},
+    operator::{prelude::*, CrossoverOp, GeneticOperator, MutationOp, ReinsertionOp, SelectionOp},
     population::Population,
     prelude::*,
-    random::SliceRandom,
+    random::{random_cut_points, random_index, SliceRandom},
     simulation::simulator::Simulator,
     types::fmt::Display,
 };
 
+/// Crossover operator for the permutations of the layout's keys
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossoverType {
+    /// No crossover, offspring only differs from its parents by mutations
+    None,
+    /// Partially mapped crossover
+    Pmx,
+    /// Order crossover
+    Ox,
+    /// Cycle crossover
+    Cycle,
+}
+
+impl Default for CrossoverType {
+    fn default() -> Self {
+        CrossoverType::None
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Parameters {
     pub population_size: usize,
     pub generation_limit: u64,
     pub num_individuals_per_parents: usize,
     pub selection_ratio: f64,
+    /// Rate of key swaps (per key of the layout)
     pub mutation_rate: f64,
     pub reinsertion_ratio: f64,
+    /// Use tournament selection with this many participants instead of selecting the fittest
+    #[serde(default)]
+    pub tournament_size: Option<usize>,
+    /// Number of the fittest individuals that are always kept in the next generation
+    #[serde(default)]
+    pub elitism: usize,
+    #[serde(default)]
+    pub crossover: CrossoverType,
+    /// Rate of 3-cycles, i.e. rotations of three random keys (per key of the layout)
+    #[serde(default)]
+    pub three_cycle_rate: f64,
+    /// Rate of reversals of a random segment of keys (per key of the layout)
+    #[serde(default)]
+    pub segment_reverse_rate: f64,
+    /// Stop (or restart from a perturbed best layout) if the best cost stagnates
+    #[serde(default)]
+    pub early_stopping: EarlyStoppingParameters,
+    /// Inject fresh individuals if the population's diversity collapses
+    #[serde(default)]
+    pub diversity: DiversityParameters,
+    /// Keep the symbols on these keys in place
+    #[serde(default)]
+    pub position_mask: PositionMask,
+    /// Symbol pairs that are kept on mirrored keys (same finger and direction, other hand)
+    #[serde(default)]
+    pub linked_pairs: Vec<(char, char)>,
+    /// Anneal the weights of these metrics (by name) over the generations
+    #[serde(default)]
+    pub weight_schedules: WeightSchedules,
+    /// Weights of the corpora (by name) of a multi-corpus evaluation, overriding the ones of
+    /// the evaluation parameters
+    #[serde(default)]
+    pub corpus_weights: CorpusWeights,
 }
 
 impl Default for Parameters {
@@ -37,6 +102,17 @@ impl Default for Parameters {
             selection_ratio: 0.7,
             mutation_rate: 0.1,
             reinsertion_ratio: 0.7,
+            tournament_size: None,
+            elitism: 0,
+            crossover: CrossoverType::None,
+            three_cycle_rate: 0.0,
+            segment_reverse_rate: 0.0,
+            early_stopping: EarlyStoppingParameters::default(),
+            diversity: DiversityParameters::default(),
+            position_mask: PositionMask::default(),
+            linked_pairs: Vec::new(),
+            weight_schedules: WeightSchedules::default(),
+            corpus_weights: CorpusWeights::default(),
         }
     }
 }
@@ -44,7 +120,46 @@ impl Default for Parameters {
 impl Parameters {
     pub fn from_yaml(filename: &str) -> Result<Self> {
         let f = File::open(filename)?;
-        Ok(serde_yaml::from_reader(f)?)
+        let params: Self = serde_yaml::from_reader(f)?;
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Check that the parameters can be combined.
+    pub fn validate(&self) -> Result<()> {
+        if self.elitism >= self.population_size {
+            bail!(
+                "elitism ({}) must be smaller than population_size ({})",
+                self.elitism,
+                self.population_size
+            );
+        }
+        if let Some(tournament_size) = self.tournament_size {
+            if tournament_size == 0 || tournament_size > self.population_size {
+                bail!(
+                    "tournament_size ({}) must be between 1 and population_size ({})",
+                    tournament_size,
+                    self.population_size
+                );
+            }
+        }
+        for (name, rate) in [
+            ("mutation_rate", self.mutation_rate),
+            ("three_cycle_rate", self.three_cycle_rate),
+            ("segment_reverse_rate", self.segment_reverse_rate),
+            (
+                "diversity.replace_fraction",
+                self.diversity.replace_fraction,
+            ),
+        ] {
+            if !(0.0..=1.0).contains(&rate) {
+                bail!("{} ({}) must be between 0 and 1", name, rate);
+            }
+        }
+        self.weight_schedules.validate()?;
+        self.corpus_weights.validate()?;
+
+        Ok(())
     }
 }
 
@@ -58,6 +173,17 @@ pub struct FitnessCalc {
     permutator: LayoutPermutator,
     layout_generator: Box<dyn LayoutGenerator>,
     result_cache: Option<Cache<usize>>,
+    scheduled_weights: Option<ScheduledWeights>,
+}
+
+impl FitnessCalc {
+    fn evaluate(&self, layout: &Layout) -> usize {
+        let mut evaluation_result = self.evaluator.evaluate_layout(layout);
+        if let Some(scheduled_weights) = &self.scheduled_weights {
+            scheduled_weights.apply(&mut evaluation_result);
+        }
+        evaluation_result.optimization_score()
+    }
 }
 
 impl FitnessFunction<Genotype, usize> for FitnessCalc {
@@ -66,11 +192,13 @@ impl FitnessFunction<Genotype, usize> for FitnessCalc {
         let l = self.layout_generator.generate(&layout_str).unwrap();
 
         // Get & return the evaluation-result
+        // (fitness values change with scheduled weights and can not be cached)
         match &self.result_cache {
-            Some(result_cache) => result_cache.get_or_insert_with(&layout_str, || {
-                self.evaluator.evaluate_layout(&l).optimization_score()
-            }),
-            None => self.evaluator.evaluate_layout(&l).optimization_score(),
+            Some(_) if self.scheduled_weights.is_some() => self.evaluate(&l),
+            Some(result_cache) => {
+                result_cache.get_or_insert_with(&layout_str, || self.evaluate(&l))
+            }
+            None => self.evaluate(&l),
         }
     }
 
@@ -114,14 +242,6 @@ struct FromGivenLayoutBuilder {
     indices: Vec<usize>,
 }
 
-impl FromGivenLayoutBuilder {
-    fn with_permutable_layout(layout_prototype: &LayoutPermutator) -> Self {
-        Self {
-            indices: layout_prototype.get_permutable_indices(),
-        }
-    }
-}
-
 impl GenomeBuilder<Vec<usize>> for FromGivenLayoutBuilder {
     fn build_genome<R>(&self, _: usize, _rng: &mut R) -> Vec<usize>
     where
@@ -206,21 +326,202 @@ impl CrossoverOp<Vec<usize>> for MyCrossover {
     }
 }
 
+impl GeneticOperator for CrossoverType {
+    fn name() -> String {
+        "Configurable-Crossover".to_string()
+    }
+}
+impl CrossoverOp<Vec<usize>> for CrossoverType {
+    fn crossover<R>(&self, parents: Parents<Vec<usize>>, rng: &mut R) -> Children<Vec<usize>>
+    where
+        R: Rng + Sized,
+    {
+        match self {
+            CrossoverType::None => NoOpCrossover::new().crossover(parents, rng),
+            CrossoverType::Pmx => PartiallyMappedCrossover::new().crossover(parents, rng),
+            CrossoverType::Ox => OrderOneCrossover::new().crossover(parents, rng),
+            CrossoverType::Cycle => MyCrossover::new().crossover(parents, rng),
+        }
+    }
+}
+
+/// Selects the fittest individuals or runs tournaments
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selector {
+    Maximize(MaximizeSelector),
+    Tournament(TournamentSelector),
+}
+impl GeneticOperator for Selector {
+    fn name() -> String {
+        "Configurable-Selection".to_string()
+    }
+}
+impl SelectionOp<Vec<usize>, usize> for Selector {
+    fn select_from<R>(
+        &self,
+        population: &EvaluatedPopulation<Vec<usize>, usize>,
+        rng: &mut R,
+    ) -> Vec<Parents<Vec<usize>>>
+    where
+        R: Rng + Sized,
+    {
+        match self {
+            Selector::Maximize(selector) => selector.select_from(population, rng),
+            Selector::Tournament(selector) => selector.select_from(population, rng),
+        }
+    }
+}
+
+/// Applies key swaps, 3-cycles and segment reversals with their individual rates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutMutator {
+    swap: SwapOrderMutator,
+    three_cycle_rate: f64,
+    segment_reverse_rate: f64,
+    linker: Option<PairLinker>,
+}
+impl LayoutMutator {
+    pub fn new(swap_rate: f64, three_cycle_rate: f64, segment_reverse_rate: f64) -> Self {
+        Self {
+            swap: SwapOrderMutator::new(swap_rate),
+            three_cycle_rate,
+            segment_reverse_rate,
+            linker: None,
+        }
+    }
+
+    /// Keep linked pairs mirrored in the mutated genomes (see [`PairLinker`]).
+    pub fn with_linker(mut self, linker: Option<PairLinker>) -> Self {
+        self.linker = linker;
+        self
+    }
+
+    /// Number of mutations with the given rate (per key) for a genome of length `len`
+    fn num_mutations<R: Rng + Sized>(rate: f64, len: usize, rng: &mut R) -> usize {
+        (len as f64 * rate + rng.gen::<f64>()).floor() as usize
+    }
+
+    /// Apply the mutations without regard to linked pairs.
+    fn mutate_unlinked<R>(&self, genome: Vec<usize>, rng: &mut R) -> Vec<usize>
+    where
+        R: Rng + Sized,
+    {
+        let mut mutated = self.swap.mutate(genome, rng);
+        let len = mutated.len();
+        if len < 3 {
+            return mutated;
+        }
+
+        for _ in 0..Self::num_mutations(self.three_cycle_rate, len, rng) {
+            let loci = rand::seq::index::sample(rng, len, 3);
+            let (a, b, c) = (loci.index(0), loci.index(1), loci.index(2));
+            let tmp = mutated[a];
+            mutated[a] = mutated[b];
+            mutated[b] = mutated[c];
+            mutated[c] = tmp;
+        }
+
+        for _ in 0..Self::num_mutations(self.segment_reverse_rate, len, rng) {
+            let (start, end) = random_cut_points(rng, len);
+            mutated[start..=end].reverse();
+        }
+
+        mutated
+    }
+}
+impl GeneticOperator for LayoutMutator {
+    fn name() -> String {
+        "Layout-Mutation".to_string()
+    }
+}
+impl MutationOp<Vec<usize>> for LayoutMutator {
+    fn mutate<R>(&self, genome: Vec<usize>, rng: &mut R) -> Vec<usize>
+    where
+        R: Rng + Sized,
+    {
+        match &self.linker {
+            Some(linker) => {
+                let mutated = self.mutate_unlinked(genome.clone(), rng);
+                linker.link(&genome, mutated)
+            }
+            None => self.mutate_unlinked(genome, rng),
+        }
+    }
+}
+
+/// Keeps the `elitism` fittest individuals of the old population and otherwise behaves like
+/// genevo's `UniformReinserter`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ElitismReinserter {
+    uniform: UniformReinserter,
+    elitism: usize,
+}
+impl ElitismReinserter {
+    pub fn new(replace_ratio: f64, elitism: usize) -> Self {
+        Self {
+            uniform: UniformReinserter::new(replace_ratio),
+            elitism,
+        }
+    }
+}
+impl GeneticOperator for ElitismReinserter {
+    fn name() -> String {
+        "Elitism-Uniform-Reinserter".to_string()
+    }
+}
+impl ReinsertionOp<Vec<usize>, usize> for ElitismReinserter {
+    fn combine<R>(
+        &self,
+        offspring: &mut Offspring<Vec<usize>>,
+        population: &EvaluatedPopulation<Vec<usize>, usize>,
+        rng: &mut R,
+    ) -> Vec<Vec<usize>>
+    where
+        R: Rng + Sized,
+    {
+        let mut new_population = self.uniform.combine(offspring, population, rng);
+        if self.elitism == 0 {
+            return new_population;
+        }
+
+        let individuals = population.individuals();
+        let fitness_values = population.fitness_values();
+        let mut by_fitness: Vec<usize> = (0..individuals.len()).collect();
+        by_fitness.sort_unstable_by(|a, b| fitness_values[*b].cmp(&fitness_values[*a]));
+
+        // the elite replaces its copy in the new population (if any) or a random individual
+        let mut elite: Vec<Vec<usize>> = Vec::with_capacity(new_population.len());
+        for idx in by_fitness.into_iter().take(self.elitism) {
+            let genome = individuals[idx].clone();
+            let replaced = match new_population.iter().position(|g| *g == genome) {
+                Some(pos) => pos,
+                None => random_index(rng, new_population.len()),
+            };
+            new_population.swap_remove(replaced);
+            elite.push(genome);
+        }
+        elite.append(&mut new_population);
+
+        elite
+    }
+}
+
 pub type MySimulator = Simulator<
     GeneticAlgorithm<
         Vec<usize>,
         usize,
         FitnessCalc,
-        MaximizeSelector,
-        // PartiallyMappedCrossover,
-        // MyCrossover,
-        NoOpCrossover,
-        SwapOrderMutator,
-        UniformReinserter, // we do not use an elitist reinserter due to performance reasons (non-parallelized evaluation)
+        Selector,
+        CrossoverType,
+        LayoutMutator,
+        ElitismReinserter, // we do not use genevo's elitist reinserter due to performance reasons (non-parallelized evaluation)
     >,
     GenerationLimit,
 >;
 
+/// Set up a simulator to be stepped by the caller. Weight schedules are not applied, as the
+/// progress of the run is only known to [`optimize`].
+#[allow(clippy::borrowed_box)]
 pub fn init_optimization(
     params: &Parameters,
     evaluator: &Evaluator,
@@ -230,17 +531,47 @@ pub fn init_optimization(
     start_with_layout: bool,
     cache_results: bool,
 ) -> (MySimulator, LayoutPermutator) {
-    let pm = LayoutPermutator::new(layout_str, fixed_characters);
-    let initial_population: Population<Genotype> = if start_with_layout {
-        build_population()
-            .with_genome_builder(FromGivenLayoutBuilder::with_permutable_layout(&pm))
-            .of_size(params.population_size)
-            .uniform_at_random()
+    let (sim, pm, _) = setup_simulator(
+        params,
+        evaluator,
+        layout_str,
+        layout_generator,
+        fixed_characters,
+        start_with_layout,
+        cache_results,
+        None,
+    );
+
+    (sim, pm)
+}
+
+/// Set up the layout permutation, the (optional) result cache, and a simulator starting from
+/// the given layout (or random layouts), shared by [`init_optimization`] and [`optimize`].
+#[allow(clippy::too_many_arguments, clippy::borrowed_box)]
+fn setup_simulator(
+    params: &Parameters,
+    evaluator: &Evaluator,
+    layout_str: &str,
+    layout_generator: &Box<dyn LayoutGenerator>,
+    fixed_characters: &str,
+    start_with_layout: bool,
+    cache_results: bool,
+    scheduled_weights: Option<ScheduledWeights>,
+) -> (MySimulator, LayoutPermutator, Option<Cache<usize>>) {
+    let pm = LayoutPermutator::with_mask(
+        layout_str,
+        fixed_characters,
+        &params.position_mask,
+        layout_generator.as_ref(),
+    )
+    .and_then(|pm| {
+        pm.with_linked_pairs(&params.linked_pairs, layout_str, layout_generator.as_ref())
+    })
+    .unwrap_or_else(|e| panic!("Could not set up the layout permutation: {:?}", e));
+    let initial_indices = if start_with_layout {
+        Some(pm.get_permutable_indices())
     } else {
-        build_population()
-            .with_genome_builder(LayoutBuilder::with_permutable_layout(&pm))
-            .of_size(params.population_size)
-            .uniform_at_random()
+        None
     };
 
     let result_cache = if cache_results {
@@ -249,32 +580,140 @@ pub fn init_optimization(
         None
     };
 
-    let sim = simulate(
+    let sim = build_simulator(
+        params,
+        evaluator,
+        &pm,
+        layout_generator,
+        build_initial_population(params, &pm, initial_indices),
+        result_cache.clone(),
+        scheduled_weights,
+        params.generation_limit,
+    );
+
+    (sim, pm, result_cache)
+}
+
+/// Build a population of mutations of `initial_indices` (or random layouts).
+fn build_initial_population(
+    params: &Parameters,
+    pm: &LayoutPermutator,
+    initial_indices: Option<Vec<usize>>,
+) -> Population<Genotype> {
+    let population: Population<Genotype> = match initial_indices {
+        Some(indices) => build_population()
+            .with_genome_builder(FromGivenLayoutBuilder { indices })
+            .of_size(params.population_size)
+            .uniform_at_random(),
+        None => build_population()
+            .with_genome_builder(LayoutBuilder::with_permutable_layout(pm))
+            .of_size(params.population_size)
+            .uniform_at_random(),
+    };
+    if pm.linker().is_none() {
+        return population;
+    }
+
+    Population::with_individuals(
+        population
+            .individuals()
+            .iter()
+            .map(|genome| pm.link(genome, genome.clone()))
+            .collect(),
+    )
+}
+
+/// Replace the least fit `params.diversity.replace_fraction` of the population by fresh
+/// individuals, either random layouts or the best layout perturbed by random key swaps.
+fn inject_fresh_individuals(
+    params: &Parameters,
+    pm: &LayoutPermutator,
+    evaluated_population: &EvaluatedPopulation<Genotype, usize>,
+) -> Population<Genotype> {
+    let individuals = evaluated_population.individuals();
+    let mut ranked: Vec<(&Genotype, usize)> = individuals
+        .iter()
+        .zip(evaluated_population.fitness_values().iter().copied())
+        .collect();
+    ranked.sort_by_key(|(_, fitness)| std::cmp::Reverse(*fitness));
+
+    let n_replaced = ((ranked.len() as f64 * params.diversity.replace_fraction).round() as usize)
+        .min(ranked.len());
+    let n_kept = ranked.len() - n_replaced;
+    let best = ranked[0].0.clone();
+
+    let mut population: Vec<Genotype> = ranked
+        .into_iter()
+        .take(n_kept)
+        .map(|(genome, _)| genome.clone())
+        .collect();
+    population.extend(
+        (0..n_replaced).map(|_| match params.diversity.injection_swaps {
+            Some(swaps) => pm.perform_n_swaps(&best, swaps),
+            None => pm.generate_random(),
+        }),
+    );
+
+    Population::with_individuals(population)
+}
+
+/// Build a simulator starting from `initial_population`.
+#[allow(clippy::too_many_arguments, clippy::borrowed_box)]
+fn build_simulator(
+    params: &Parameters,
+    evaluator: &Evaluator,
+    pm: &LayoutPermutator,
+    layout_generator: &Box<dyn LayoutGenerator>,
+    initial_population: Population<Genotype>,
+    result_cache: Option<Cache<usize>>,
+    scheduled_weights: Option<ScheduledWeights>,
+    generation_limit: u64,
+) -> MySimulator {
+    let selector = match params.tournament_size {
+        Some(tournament_size) => Selector::Tournament(TournamentSelector::new(
+            params.selection_ratio,
+            params.num_individuals_per_parents,
+            tournament_size,
+            1.0,
+            false,
+        )),
+        None => Selector::Maximize(MaximizeSelector::new(
+            params.selection_ratio,
+            params.num_individuals_per_parents,
+        )),
+    };
+
+    simulate(
         genetic_algorithm()
             .with_evaluation(FitnessCalc {
                 evaluator: Arc::new(evaluator.clone()),
                 permutator: pm.clone(),
                 layout_generator: layout_generator.clone(),
                 result_cache,
+                scheduled_weights,
             })
-            .with_selection(MaximizeSelector::new(
-                params.selection_ratio,
-                params.num_individuals_per_parents,
+            .with_selection(selector)
+            .with_crossover(params.crossover)
+            .with_mutation(
+                LayoutMutator::new(
+                    params.mutation_rate,
+                    params.three_cycle_rate,
+                    params.segment_reverse_rate,
+                )
+                .with_linker(pm.linker().cloned()),
+            )
+            .with_reinsertion(ElitismReinserter::new(
+                params.reinsertion_ratio,
+                params.elitism,
             ))
-            // .with_crossover(PartiallyMappedCrossover::new())
-            // .with_crossover(MyCrossover::new())
-            .with_crossover(NoOpCrossover::new())
-            .with_mutation(SwapOrderMutator::new(params.mutation_rate))
-            .with_reinsertion(UniformReinserter::new(params.reinsertion_ratio))
             .with_initial_population(initial_population)
             .build(),
     )
-    .until(GenerationLimit::new(params.generation_limit))
-    .build();
-
-    (sim, pm)
+    .until(GenerationLimit::new(generation_limit))
+    .build()
 }
 
+#[allow(clippy::too_many_arguments, clippy::borrowed_box)]
 pub fn optimize(
     params: &Parameters,
     evaluator: &Evaluator,
@@ -283,8 +722,10 @@ pub fn optimize(
     fixed_characters: &str,
     start_with_layout: bool,
     cache_results: bool,
+    mut progress_reporter: Option<ProgressReporter>,
 ) -> (String, Layout) {
-    let (mut sim, pm) = init_optimization(
+    let scheduled_weights = params.weight_schedules.tracker();
+    let (mut sim, pm, result_cache) = setup_simulator(
         params,
         evaluator,
         layout_str,
@@ -292,26 +733,45 @@ pub fn optimize(
         fixed_characters,
         start_with_layout,
         cache_results,
+        scheduled_weights.clone(),
     );
 
     log::info!("Starting optimization with: {:?}", params);
     let mut all_time_best: Option<(usize, Genotype)> = None;
+    let mut all_time_best_cost = f64::INFINITY;
+    let mut plateau_detector = PlateauDetector::new(&params.early_stopping);
+    // generations of the simulators replaced by restarts
+    let mut generation_offset = 0;
 
     loop {
         let result = sim.step();
         match result {
             Ok(SimResult::Intermediate(step)) => {
+                let generation = generation_offset + step.iteration;
+                if let Some(scheduled_weights) = &scheduled_weights {
+                    scheduled_weights.set_progress(generation, params.generation_limit);
+                }
                 let evaluated_population = step.result.evaluated_population;
                 let best_solution = step.result.best_solution;
                 if let Some(king) = &all_time_best {
-                    if best_solution.solution.fitness > king.0 {
+                    // scheduled metric weights change the fitness over the generations, so
+                    // compare the costs with the evaluator's (end) weights instead
+                    let improved = match &scheduled_weights {
+                        Some(_) => {
+                            let layout_str = pm.generate_string(&best_solution.solution.genome);
+                            let layout = layout_generator.generate(&layout_str).unwrap();
+                            evaluator.evaluate_layout(&layout).total_cost() < all_time_best_cost
+                        }
+                        None => best_solution.solution.fitness > king.0,
+                    };
+                    if improved {
                         let layout_str = pm.generate_string(&best_solution.solution.genome);
                         let layout = layout_generator.generate(&layout_str).unwrap();
 
                         let evaluation_result = evaluator.evaluate_layout(&layout);
                         println!(
                             "{}: {} (score: {})\n{}",
-                            format!("New best in generation {}:", step.iteration)
+                            format!("New best in generation {}:", generation)
                                 .yellow()
                                 .bold(),
                             layout_str,
@@ -323,34 +783,123 @@ pub fn optimize(
                             best_solution.solution.fitness,
                             best_solution.solution.genome.clone(),
                         ));
+                        all_time_best_cost = evaluation_result.total_cost();
                     }
                 } else {
                     all_time_best = Some((
                         best_solution.solution.fitness,
                         best_solution.solution.genome.clone(),
                     ));
+                    let layout_str = pm.generate_string(&best_solution.solution.genome);
+                    let layout = layout_generator.generate(&layout_str).unwrap();
+                    all_time_best_cost = evaluator.evaluate_layout(&layout).total_cost();
                 }
+                if let Some(reporter) = progress_reporter.as_mut() {
+                    reporter.report(
+                        "Genetic",
+                        generation,
+                        &pm.generate_string(&all_time_best.as_ref().unwrap().1),
+                        all_time_best_cost,
+                    );
+                }
+                let population_diversity = diversity(
+                    &evaluated_population.individuals(),
+                    params.diversity.sample_size,
+                );
                 log::info!(
-                    "{}, average_fitness: {}, \
+                    "{}, average_fitness: {}, diversity: {:.3}, \
                      best fitness: {}, all time best: {}, duration: {}, processing_time: {}, generation's best: {}",
-                    format!("Generation {}:", step.iteration).yellow().bold(),
+                    format!("Generation {}:", generation).yellow().bold(),
                     evaluated_population.average_fitness(),
+                    population_diversity,
                     best_solution.solution.fitness,
                     all_time_best.as_ref().unwrap().0,
                     step.duration.fmt(),
                     step.processing_time.fmt(),
                     pm.generate_string(&best_solution.solution.genome)
                 );
+
+                match plateau_detector.update(generation, all_time_best_cost) {
+                    PlateauAction::Continue => {
+                        // the initial population of a simulator is not checked, as it
+                        // consists of copies of the start layout if one is given
+                        if let Some(min_diversity) = params.diversity.min_diversity {
+                            if step.iteration > 1 && population_diversity < min_diversity {
+                                log::info!(
+                                    "{} {:.3} below {} in generation {}, replacing the least fit {}% of the population",
+                                    "Diversity:".blue(),
+                                    population_diversity,
+                                    min_diversity,
+                                    generation,
+                                    100.0 * params.diversity.replace_fraction,
+                                );
+                                sim = build_simulator(
+                                    params,
+                                    evaluator,
+                                    &pm,
+                                    layout_generator,
+                                    inject_fresh_individuals(params, &pm, &evaluated_population),
+                                    result_cache.clone(),
+                                    scheduled_weights.clone(),
+                                    params.generation_limit - generation,
+                                );
+                                generation_offset = generation;
+                            }
+                        }
+                    }
+                    PlateauAction::Restart(swaps) => {
+                        log::info!(
+                            "{} {} in generation {}, continuing from best layout with {} swaps",
+                            "Plateau:".blue(),
+                            format!("restart {}", plateau_detector.restarts()).bold(),
+                            generation,
+                            swaps,
+                        );
+                        let best_genome = &all_time_best.as_ref().unwrap().1;
+                        sim = build_simulator(
+                            params,
+                            evaluator,
+                            &pm,
+                            layout_generator,
+                            build_initial_population(
+                                params,
+                                &pm,
+                                Some(pm.perform_n_swaps(best_genome, swaps)),
+                            ),
+                            result_cache.clone(),
+                            scheduled_weights.clone(),
+                            params.generation_limit - generation,
+                        );
+                        generation_offset = generation;
+                    }
+                    PlateauAction::Stop => {
+                        let layout_str = pm.generate_string(&all_time_best.as_ref().unwrap().1);
+                        let layout = layout_generator.generate(&layout_str).unwrap();
+                        println!(
+                            "{} after generation {} ({}, {} restart(s))\n\n{}\n\n{}\n{}",
+                            "Final result".green().bold(),
+                            generation,
+                            plateau_detector.stop_reason(),
+                            plateau_detector.restarts(),
+                            layout_str,
+                            layout.plot_compact(),
+                            layout.plot()
+                        );
+                        break;
+                    }
+                }
             }
-            Ok(SimResult::Final(step, processing_time, duration, _stop_reason)) => {
+            Ok(SimResult::Final(step, processing_time, duration, stop_reason)) => {
                 let layout_str = pm.generate_string(&all_time_best.as_ref().unwrap().1);
                 let layout = layout_generator.generate(&layout_str).unwrap();
                 println!(
-                    "{} after generation {}, duration {}, processing time {}\n\n{}\n\n{}\n{}",
+                    "{} after generation {}, duration {}, processing time {} ({}, {} restart(s))\n\n{}\n\n{}\n{}",
                     "Final result".green().bold(),
-                    step.iteration,
+                    generation_offset + step.iteration,
                     duration.fmt(),
                     processing_time.fmt(),
+                    stop_reason,
+                    plateau_detector.restarts(),
                     layout_str,
                     layout.plot_compact(),
                     layout.plot()
@@ -369,3 +918,151 @@ pub fn optimize(
 
     (best_layout_str, best_layout)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::rc::Rc;
+
+    const LEN: usize = 30;
+
+    fn random_parents(rng: &mut StdRng, n: usize) -> Parents<Vec<usize>> {
+        (0..n)
+            .map(|_| {
+                let mut genome: Vec<usize> = (0..LEN).collect();
+                genome.shuffle(rng);
+                genome
+            })
+            .collect()
+    }
+
+    fn assert_permutation(genome: &[usize]) {
+        let mut sorted = genome.to_vec();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..LEN).collect::<Vec<usize>>(), "{:?}", genome);
+    }
+
+    #[test]
+    fn crossovers_produce_permutations() {
+        let mut rng = StdRng::seed_from_u64(589);
+        for crossover in [
+            CrossoverType::None,
+            CrossoverType::Pmx,
+            CrossoverType::Ox,
+            CrossoverType::Cycle,
+        ] {
+            for n_parents in [2, 3] {
+                for _ in 0..200 {
+                    let parents = random_parents(&mut rng, n_parents);
+                    let children = crossover.crossover(parents, &mut rng);
+                    assert!(!children.is_empty());
+                    children.iter().for_each(|child| assert_permutation(child));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mutations_produce_permutations() {
+        let mut rng = StdRng::seed_from_u64(589);
+        let mutator = LayoutMutator::new(0.2, 0.2, 0.2);
+        for parent in random_parents(&mut rng, 200) {
+            assert_permutation(&mutator.mutate(parent, &mut rng));
+        }
+    }
+
+    #[test]
+    fn invalid_combinations_are_rejected() {
+        let valid = Parameters::default();
+        assert!(valid.validate().is_ok());
+
+        let elitism = Parameters {
+            elitism: valid.population_size,
+            ..Parameters::default()
+        };
+        assert!(elitism.validate().is_err());
+
+        let tournament = Parameters {
+            tournament_size: Some(0),
+            ..Parameters::default()
+        };
+        assert!(tournament.validate().is_err());
+
+        let rate = Parameters {
+            three_cycle_rate: 1.5,
+            ..Parameters::default()
+        };
+        assert!(rate.validate().is_err());
+    }
+
+    /// A population of `size` copies of the same layout with decreasing fitness
+    fn collapsed_population(
+        pm: &LayoutPermutator,
+        size: usize,
+    ) -> EvaluatedPopulation<Genotype, usize> {
+        let genome = pm.get_permutable_indices();
+        let fitness_values: Vec<usize> = (0..size).rev().collect();
+        EvaluatedPopulation::new(
+            Rc::new(vec![genome; size]),
+            fitness_values,
+            size - 1,
+            0,
+            size / 2,
+        )
+    }
+
+    fn injection_parameters(injection_swaps: Option<usize>) -> Parameters {
+        Parameters {
+            diversity: DiversityParameters {
+                min_diversity: Some(0.1),
+                replace_fraction: 0.5,
+                sample_size: 20,
+                injection_swaps,
+            },
+            ..Parameters::default()
+        }
+    }
+
+    #[test]
+    fn diversity_recovers_after_injecting_random_layouts() {
+        let pm = LayoutPermutator::new("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP", "");
+        let collapsed = collapsed_population(&pm, 20);
+        assert_eq!(diversity(&collapsed.individuals(), 20), 0.0);
+
+        let params = injection_parameters(None);
+        let population = inject_fresh_individuals(&params, &pm, &collapsed);
+        let individuals = population.individuals();
+
+        assert_eq!(individuals.len(), 20);
+        // the fittest half is kept
+        assert!(individuals[..10]
+            .iter()
+            .all(|genome| *genome == collapsed.individuals()[0]));
+        individuals.iter().for_each(|genome| {
+            let mut sorted = genome.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, pm.get_permutable_indices());
+        });
+        assert!(diversity(individuals, 20) > 0.5);
+    }
+
+    #[test]
+    fn diversity_recovers_after_injecting_mutated_layouts() {
+        let pm = LayoutPermutator::new("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP", "");
+        let collapsed = collapsed_population(&pm, 20);
+
+        let params = injection_parameters(Some(5));
+        let population = inject_fresh_individuals(&params, &pm, &collapsed);
+        let individuals = population.individuals();
+
+        // each injected layout differs from the best one by at most 5 swaps
+        let best = &collapsed.individuals()[0];
+        for genome in &individuals[10..] {
+            let n_different = genome.iter().zip(best).filter(|(a, b)| a != b).count();
+            assert!(n_different <= 10);
+        }
+        assert!(diversity(individuals, 20) > params.diversity.min_diversity.unwrap());
+    }
+}
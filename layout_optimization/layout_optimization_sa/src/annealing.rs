@@ -0,0 +1,349 @@
+//! The `annealing` module provides the simulated annealing solver with configurable
+//! temperature schedules.
+//!
+//! Apart from the schedule, the solver behaves like `argmin`'s `SimulatedAnnealing`: better
+//! layouts are always accepted, worse ones with probability `1 / (1 + exp(Δcost / T))`, and
+//! the run stops if no layout was accepted for `stall_accepted` iterations.
+//!
+//! The acceptance ratio is tracked over a sliding window of the last iterations. It drives
+//! the adaptive cooling and the (optional) reheating.
+
+use crate::optimization::{AnnealingStruct, SaIterState};
+
+use argmin::{
+    core::{Error, Problem, Solver, State, TerminationReason, TerminationStatus, KV},
+    kv,
+};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+/// How the temperature changes from one iteration to the next
+#[derive(Clone, Deserialize, Debug)]
+#[serde(tag = "cooling", rename_all = "snake_case")]
+pub enum Cooling {
+    /// Multiply the temperature by `factor` in each iteration
+    Geometric { factor: f64 },
+    /// Lower the temperature by `step` in each iteration (down to almost zero)
+    Linear { step: f64 },
+    /// Lower the temperature by the relative `adjustment` while the acceptance ratio is above
+    /// `target_acceptance` and raise it (up to the initial temperature) while the ratio is below.
+    /// As worse layouts are accepted with a probability of at most 0.5, targets close to or
+    /// above 0.5 keep the temperature at the initial one.
+    Adaptive {
+        target_acceptance: f64,
+        adjustment: f64,
+    },
+}
+
+/// Reset the temperature if the acceptance ratio collapses
+#[derive(Clone, Deserialize, Debug)]
+pub struct ReheatParameters {
+    /// Reheat if the acceptance ratio drops below this value
+    pub min_acceptance: f64,
+    /// New temperature relative to the initial temperature
+    pub temperature_factor: f64,
+}
+
+#[derive(Clone, Deserialize, Debug)]
+pub struct ScheduleParameters {
+    #[serde(flatten)]
+    pub cooling: Cooling,
+    /// Number of iterations the acceptance ratio is computed from
+    #[serde(default = "default_window")]
+    pub window: usize,
+    /// Reheat if the acceptance ratio drops below a floor
+    pub reheat: Option<ReheatParameters>,
+}
+
+fn default_window() -> usize {
+    100
+}
+
+impl Default for ScheduleParameters {
+    fn default() -> Self {
+        Self {
+            cooling: Cooling::Geometric { factor: 0.998 },
+            window: default_window(),
+            reheat: None,
+        }
+    }
+}
+
+/// Sliding window counting the accepted layouts of the last iterations
+#[derive(Clone, Debug)]
+struct AcceptanceWindow {
+    size: usize,
+    accepted: VecDeque<bool>,
+    n_accepted: usize,
+}
+
+impl AcceptanceWindow {
+    fn new(size: usize) -> Self {
+        Self {
+            size: size.max(1),
+            accepted: VecDeque::with_capacity(size.max(1)),
+            n_accepted: 0,
+        }
+    }
+
+    fn push(&mut self, accepted: bool) {
+        if self.accepted.len() == self.size && self.accepted.pop_front() == Some(true) {
+            self.n_accepted -= 1;
+        }
+        self.accepted.push_back(accepted);
+        if accepted {
+            self.n_accepted += 1;
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.accepted.len() == self.size
+    }
+
+    fn ratio(&self) -> f64 {
+        if self.accepted.is_empty() {
+            1.0
+        } else {
+            self.n_accepted as f64 / self.accepted.len() as f64
+        }
+    }
+
+    fn clear(&mut self) {
+        self.accepted.clear();
+        self.n_accepted = 0;
+    }
+}
+
+/// Simulated annealing solver following a [`ScheduleParameters`] temperature schedule
+pub struct Annealing {
+    schedule: ScheduleParameters,
+    init_temp: f64,
+    cur_temp: f64,
+    stall_iter_accepted: u64,
+    stall_iter_accepted_limit: u64,
+    window: AcceptanceWindow,
+    rng: Xoshiro256PlusPlus,
+}
+
+impl Annealing {
+    pub fn new(init_temp: f64, schedule: &ScheduleParameters, stall_accepted: u64) -> Self {
+        Self {
+            schedule: schedule.clone(),
+            init_temp,
+            cur_temp: init_temp,
+            stall_iter_accepted: 0,
+            stall_iter_accepted_limit: stall_accepted,
+            window: AcceptanceWindow::new(schedule.window),
+            rng: Xoshiro256PlusPlus::from_rng(&mut rand::rng()),
+        }
+    }
+
+    /// Update the temperature for the next iteration. Returns whether the solver reheated.
+    fn update_temperature(&mut self) -> bool {
+        if let Some(reheat) = &self.schedule.reheat {
+            if self.window.is_full() && self.window.ratio() < reheat.min_acceptance {
+                self.cur_temp = self.init_temp * reheat.temperature_factor;
+                self.window.clear();
+                return true;
+            }
+        }
+
+        self.cur_temp = match self.schedule.cooling {
+            Cooling::Geometric { factor } => self.cur_temp * factor,
+            Cooling::Linear { step } => self.cur_temp - step,
+            Cooling::Adaptive {
+                target_acceptance,
+                adjustment,
+            } => {
+                if !self.window.is_full() {
+                    self.cur_temp
+                } else if self.window.ratio() > target_acceptance {
+                    self.cur_temp * (1.0 - adjustment)
+                } else {
+                    (self.cur_temp * (1.0 + adjustment)).min(self.init_temp)
+                }
+            }
+        }
+        .max(f64::MIN_POSITIVE);
+
+        false
+    }
+}
+
+impl Solver<AnnealingStruct, SaIterState> for Annealing {
+    fn name(&self) -> &str {
+        "Simulated Annealing"
+    }
+
+    fn init(
+        &mut self,
+        problem: &mut Problem<AnnealingStruct>,
+        mut state: SaIterState,
+    ) -> Result<(SaIterState, Option<KV>), Error> {
+        let param = state
+            .take_param()
+            .ok_or_else(|| Error::msg("Simulated annealing requires an initial layout"))?;
+
+        let cost = state.get_cost();
+        let cost = if cost.is_infinite() {
+            problem.cost(&param)?
+        } else {
+            cost
+        };
+
+        Ok((
+            state.param(param).cost(cost),
+            Some(kv!(
+                "initial_temperature" => self.init_temp;
+                "stall_iter_accepted_limit" => self.stall_iter_accepted_limit;
+            )),
+        ))
+    }
+
+    fn next_iter(
+        &mut self,
+        problem: &mut Problem<AnnealingStruct>,
+        mut state: SaIterState,
+    ) -> Result<(SaIterState, Option<KV>), Error> {
+        let prev_param = state
+            .take_param()
+            .ok_or_else(|| Error::msg("Simulated annealing: layout in state not set"))?;
+        let mut prev_cost = state.get_cost();
+
+        // scheduled metric weights change the costs of the current and the best layout
+        let costs_changed = problem.problem.as_ref().map_or(false, |p| {
+            p.set_progress(state.get_iter(), state.get_max_iters())
+        });
+        if costs_changed {
+            prev_cost = problem.cost(&prev_param)?;
+            if let Some(best_param) = state.get_best_param().cloned() {
+                state.best_cost = problem.cost(&best_param)?;
+            }
+        }
+        let new_param = problem.anneal(&prev_param, self.cur_temp)?;
+        let new_cost = problem.cost(&new_param)?;
+
+        let prob: f64 = self.rng.random();
+        let accepted = (new_cost < prev_cost)
+            || (1.0 / (1.0 + ((new_cost - prev_cost) / self.cur_temp).exp()) > prob);
+        let new_best_found = new_cost < state.get_best_cost();
+
+        self.stall_iter_accepted = if accepted {
+            0
+        } else {
+            self.stall_iter_accepted + 1
+        };
+        self.window.push(accepted);
+        let acceptance_ratio = self.window.ratio();
+        let reheated = self.update_temperature();
+
+        Ok((
+            if accepted {
+                state.param(new_param).cost(new_cost)
+            } else {
+                state.param(prev_param).cost(prev_cost)
+            },
+            Some(kv!(
+                "t" => self.cur_temp;
+                "new_be" => new_best_found;
+                "acc" => accepted;
+                "acc_ratio" => acceptance_ratio;
+                "st_i_ac" => self.stall_iter_accepted;
+                "reheat" => reheated;
+            )),
+        ))
+    }
+
+    fn terminate(&mut self, _state: &SaIterState) -> TerminationStatus {
+        if self.stall_iter_accepted > self.stall_iter_accepted_limit {
+            return TerminationStatus::Terminated(TerminationReason::SolverExit(
+                "AcceptedStallIterExceeded".to_string(),
+            ));
+        }
+        TerminationStatus::NotTerminated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annealing(cooling: Cooling, reheat: Option<ReheatParameters>) -> Annealing {
+        let schedule = ScheduleParameters {
+            cooling,
+            window: 10,
+            reheat,
+        };
+        Annealing::new(10.0, &schedule, 1000)
+    }
+
+    /// Push `n` iterations with the given outcome and update the temperature after each
+    fn push(annealing: &mut Annealing, n: usize, accepted: bool) -> Vec<f64> {
+        (0..n)
+            .map(|_| {
+                annealing.window.push(accepted);
+                annealing.update_temperature();
+                annealing.cur_temp
+            })
+            .collect()
+    }
+
+    #[test]
+    fn acceptance_window_slides() {
+        let mut window = AcceptanceWindow::new(4);
+        assert_eq!(window.ratio(), 1.0);
+
+        for accepted in [true, false, false, true] {
+            window.push(accepted);
+        }
+        assert!(window.is_full());
+        assert_eq!(window.ratio(), 0.5);
+
+        window.push(false);
+        window.push(false);
+        assert_eq!(window.ratio(), 0.25);
+
+        window.clear();
+        assert!(!window.is_full());
+        assert_eq!(window.ratio(), 1.0);
+    }
+
+    #[test]
+    fn adaptive_cooling_follows_the_acceptance_ratio() {
+        let cooling = Cooling::Adaptive {
+            target_acceptance: 0.4,
+            adjustment: 0.1,
+        };
+        let mut annealing = annealing(cooling, None);
+
+        // the temperature is kept until the window is full
+        let temperatures = push(&mut annealing, 12, true);
+        assert!(temperatures[..9].iter().all(|t| *t == 10.0));
+        assert!((temperatures[11] - 10.0 * 0.9f64.powi(3)).abs() < 1e-9);
+
+        // acceptance collapses: the temperature keeps falling while the ratio is above the
+        // target (up to the fifth rejection) and then rises up to the initial one
+        let temperatures = push(&mut annealing, 20, false);
+        assert!(temperatures.windows(2).take(4).all(|t| t[0] > t[1]));
+        assert!(temperatures.windows(2).skip(4).all(|t| t[0] <= t[1]));
+        assert!(temperatures[4] < temperatures[5]);
+        assert_eq!(temperatures[19], 10.0);
+    }
+
+    #[test]
+    fn reheat_when_acceptance_collapses() {
+        let reheat = ReheatParameters {
+            min_acceptance: 0.2,
+            temperature_factor: 0.5,
+        };
+        let mut annealing = annealing(Cooling::Geometric { factor: 0.5 }, Some(reheat));
+
+        let temperatures = push(&mut annealing, 10, false);
+        assert_eq!(temperatures[8], 10.0 * 0.5f64.powi(9));
+        assert_eq!(temperatures[9], 5.0);
+        // the window starts over after reheating
+        assert!(!annealing.window.is_full());
+    }
+}
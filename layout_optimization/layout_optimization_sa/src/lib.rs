@@ -1,3 +1,4 @@
+pub mod annealing;
 pub mod optimization;
 
 #[cfg(test)]
@@ -1,7 +1,16 @@
 use keyboard_layout::{layout::Layout, layout_generator::LayoutGenerator};
 use layout_evaluation::{cache::Cache, evaluation::Evaluator};
 
-use layout_optimization_common::LayoutPermutator;
+use crate::annealing::{Annealing, ScheduleParameters};
+
+use layout_optimization_common::{
+    corpus_weights::CorpusWeights,
+    early_stopping::{EarlyStoppingParameters, PlateauAction, PlateauDetector},
+    position_mask::PositionMask,
+    progress::ProgressReporter,
+    weight_schedule::{ScheduledWeights, WeightSchedules},
+    LayoutPermutator,
+};
 
 use anyhow::Result;
 use colored::Colorize;
@@ -11,9 +20,10 @@ use std::{fs::File, sync::Arc};
 use argmin::{
     core::{
         observers::{Observe, ObserverMode},
-        CostFunction, Error, Executor, IterState, State, KV,
+        CostFunction, Error, Executor, IterState, Problem, Solver, State, TerminationReason,
+        TerminationStatus, KV,
     },
-    solver::simulatedannealing::{Anneal, SATempFunc, SimulatedAnnealing},
+    solver::simulatedannealing::Anneal,
 };
 
 #[derive(Deserialize, Debug)]
@@ -24,6 +34,10 @@ pub struct Parameters {
     /// In each modification of the layout, swap this many key-pairs.
     pub key_switches: usize,
 
+    /// Temperature schedule (defaults to geometric cooling with factor 0.998)
+    #[serde(default)]
+    pub schedule: ScheduleParameters,
+
     // Parameters for the solver.
     /// Stop if there was no accepted solution after this many iterations
     pub stall_accepted: u64,
@@ -31,6 +45,23 @@ pub struct Parameters {
     // Parameters for the [Executor].
     /// Set maximum number of iterations (defaults to `std::u64::MAX`)
     pub max_iters: u64,
+
+    /// Stop (or restart from a perturbed best layout) if the best cost stagnates
+    #[serde(default)]
+    pub early_stopping: EarlyStoppingParameters,
+    /// Keep the symbols on these keys in place
+    #[serde(default)]
+    pub position_mask: PositionMask,
+    /// Symbol pairs that are kept on mirrored keys (same finger and direction, other hand)
+    #[serde(default)]
+    pub linked_pairs: Vec<(char, char)>,
+    /// Anneal the weights of these metrics (by name) over the iterations
+    #[serde(default)]
+    pub weight_schedules: WeightSchedules,
+    /// Weights of the corpora (by name) of a multi-corpus evaluation, overriding the ones of
+    /// the evaluation parameters
+    #[serde(default)]
+    pub corpus_weights: CorpusWeights,
 }
 
 impl Default for Parameters {
@@ -38,10 +69,16 @@ impl Default for Parameters {
         Parameters {
             init_temp: Some(150.0),
             key_switches: 1,
+            schedule: ScheduleParameters::default(),
             // Parameters for the solver.
             stall_accepted: 5000,
             // Parameters for the [Executor].
             max_iters: 100_000,
+            early_stopping: EarlyStoppingParameters::default(),
+            position_mask: PositionMask::default(),
+            linked_pairs: Vec::new(),
+            weight_schedules: WeightSchedules::default(),
+            corpus_weights: CorpusWeights::default(),
         }
     }
 }
@@ -49,7 +86,10 @@ impl Default for Parameters {
 impl Parameters {
     pub fn from_yaml(filename: &str) -> Result<Self> {
         let f = File::open(filename)?;
-        Ok(serde_yaml::from_reader(f)?)
+        let params: Self = serde_yaml::from_reader(f)?;
+        params.weight_schedules.validate()?;
+        params.corpus_weights.validate()?;
+        Ok(params)
     }
     /// Makes sure that [self.init_temp] is greater than zero.
     /// => Negative values and zero get turned into `f64::MIN_POSITIVE`.
@@ -71,6 +111,18 @@ pub struct AnnealingStruct {
     layout_generator: Box<dyn LayoutGenerator>,
     key_switches: usize,
     result_cache: Option<Cache<f64>>,
+    scheduled_weights: Option<ScheduledWeights>,
+}
+
+impl AnnealingStruct {
+    /// Register the progress of the run for the weight schedules (if any). Returns whether the
+    /// costs of layouts changed.
+    pub(crate) fn set_progress(&self, iter: u64, max_iters: u64) -> bool {
+        match &self.scheduled_weights {
+            Some(scheduled_weights) => scheduled_weights.set_progress(iter, max_iters),
+            None => false,
+        }
+    }
 }
 
 impl CostFunction for AnnealingStruct {
@@ -81,11 +133,17 @@ impl CostFunction for AnnealingStruct {
     fn cost(&self, param: &Self::Param) -> Result<Self::Output, Error> {
         let evaluate_layout_str = |layout_str: &str| -> f64 {
             let l = self.layout_generator.generate(layout_str).unwrap();
-            self.evaluator.evaluate_layout(&l).total_cost()
+            let mut evaluation_result = self.evaluator.evaluate_layout(&l);
+            if let Some(scheduled_weights) = &self.scheduled_weights {
+                scheduled_weights.apply(&mut evaluation_result);
+            }
+            evaluation_result.total_cost()
         };
 
         let layout_string = self.permutator.generate_string(param);
+        // costs change with scheduled weights and can not be cached
         let evaluation_result = match &self.result_cache {
+            Some(_) if self.scheduled_weights.is_some() => evaluate_layout_str(&layout_string),
             Some(result_cache) => result_cache
                 .get_or_insert_with(&layout_string, || evaluate_layout_str(&layout_string)),
             None => evaluate_layout_str(&layout_string),
@@ -108,6 +166,77 @@ impl Anneal for AnnealingStruct {
 
 pub type SaIterState = IterState<Vec<usize>, (), (), (), (), f64>;
 
+/// Wraps a solver to stop the run (or to continue from a perturbed best layout) if the best
+/// cost stagnates, see [`PlateauDetector`].
+struct PlateauStopping<S> {
+    id: String,
+    solver: S,
+    detector: PlateauDetector,
+    permutator: LayoutPermutator,
+    pending_swaps: Option<usize>,
+}
+
+impl<S> Solver<AnnealingStruct, SaIterState> for PlateauStopping<S>
+where
+    S: Solver<AnnealingStruct, SaIterState>,
+{
+    fn name(&self) -> &str {
+        self.solver.name()
+    }
+
+    fn init(
+        &mut self,
+        problem: &mut Problem<AnnealingStruct>,
+        state: SaIterState,
+    ) -> Result<(SaIterState, Option<KV>), Error> {
+        self.solver.init(problem, state)
+    }
+
+    fn next_iter(
+        &mut self,
+        problem: &mut Problem<AnnealingStruct>,
+        mut state: SaIterState,
+    ) -> Result<(SaIterState, Option<KV>), Error> {
+        if let Some(swaps) = self.pending_swaps.take() {
+            let best_param = state.get_best_param().unwrap();
+            let param = self.permutator.perform_n_swaps(best_param, swaps);
+            let cost = problem.cost(&param)?;
+            state = state.param(param).cost(cost);
+        }
+
+        self.solver.next_iter(problem, state)
+    }
+
+    fn terminate(&mut self, state: &SaIterState) -> TerminationStatus {
+        let status = self.solver.terminate(state);
+        if status.terminated() {
+            return status;
+        }
+
+        let action = self
+            .detector
+            .update(state.get_iter(), state.get_best_cost());
+        match action {
+            PlateauAction::Continue => TerminationStatus::NotTerminated,
+            PlateauAction::Restart(swaps) => {
+                log::info!(
+                    "{} {} {} in iteration {}, continuing from best layout with {} swaps",
+                    format!("{}:", self.id).yellow().bold(),
+                    "Plateau:".blue(),
+                    format!("restart {}", self.detector.restarts()).bold(),
+                    state.get_iter(),
+                    swaps,
+                );
+                self.pending_swaps = Some(swaps);
+                TerminationStatus::NotTerminated
+            }
+            PlateauAction::Stop => TerminationStatus::Terminated(TerminationReason::SolverExit(
+                self.detector.stop_reason(),
+            )),
+        }
+    }
+}
+
 /// An observer that outputs important information in a more human-readable format than `Argmin`'s original implementation.
 struct BestObserver {
     id: String,
@@ -143,6 +272,24 @@ impl Observe<SaIterState> for CustomObserver {
     }
 }
 
+/// An observer that passes the best layout to a [`ProgressReporter`].
+struct ProgressObserver {
+    id: String,
+    permutator: LayoutPermutator,
+    reporter: ProgressReporter,
+}
+
+impl Observe<SaIterState> for ProgressObserver {
+    fn observe_iter(&mut self, state: &SaIterState, _kv: &KV) -> Result<(), Error> {
+        if let Some(best_param) = state.best_param.as_ref() {
+            let best_layout = self.permutator.generate_string(best_param);
+            self.reporter
+                .report(&self.id, state.iter, &best_layout, state.best_cost);
+        }
+        Ok(())
+    }
+}
+
 /// An observer that outputs important information in a more human-readable format than `Argmin`'s original implementation.
 struct IterationObserver {
     id: String,
@@ -174,15 +321,17 @@ impl Observe<SaIterState> for IterationObserver {
             time: 0.533206799 */
             let mut temperature = String::from("Not found.");
             let mut accepted = String::from("Not found");
+            let mut acceptance_ratio = String::from("Not found");
             for (key, value) in &kv.kv {
                 match key.as_str() {
                     "t" => temperature = format!("{:.5}", value),
                     "acc" => accepted = value.to_string(),
+                    "acc_ratio" => acceptance_ratio = format!("{:.2}", value),
                     _ => {}
                 }
             }
             let mut output = format!(
-                "{} {} {:>3}, {} {} ({:>6.1}), {} {} ({:>6.1}), {} {}°",
+                "{} {} {:>3}, {} {} ({:>6.1}), {} {} ({:>6.1}), {} {}°, {} {}",
                 format!("{}:", self.id).yellow().bold(),
                 "n:".bold(),
                 state.iter,
@@ -194,6 +343,8 @@ impl Observe<SaIterState> for IterationObserver {
                 state.best_cost,
                 "temp:".bold(),
                 temperature,
+                "acc. ratio:".bold(),
+                acceptance_ratio,
             );
             if self.log_everything {
                 let is_better = state.cost < state.prev_cost;
@@ -223,6 +374,7 @@ fn mean(list: &[f64]) -> f64 {
 ///
 /// This value can then be used as the initial temperature in Simulated annealing.
 /// Reference: https://link.springer.com/content/pdf/10.1007/s10732-007-9012-8.pdf
+#[allow(clippy::borrowed_box)]
 fn get_cost_sd(
     initial_indices: &[usize],
     evaluator: Arc<Evaluator>,
@@ -257,7 +409,7 @@ fn get_cost_sd(
 }
 
 /// Performs one run of Simulated Annealing, then returns the best layout found.
-#[allow(clippy::too_many_arguments)]
+#[allow(clippy::too_many_arguments, clippy::borrowed_box)]
 pub fn optimize(
     process_name: &str,
     params: &Parameters,
@@ -269,8 +421,18 @@ pub fn optimize(
     log_everything: bool,
     result_cache: Option<Cache<f64>>,
     custom_observer: Option<CustomObserver>,
+    progress_reporter: Option<ProgressReporter>,
 ) -> (String, Layout) {
-    let pm = LayoutPermutator::new(layout_str, fixed_characters);
+    let pm = LayoutPermutator::with_mask(
+        layout_str,
+        fixed_characters,
+        &params.position_mask,
+        layout_generator.as_ref(),
+    )
+    .and_then(|pm| {
+        pm.with_linked_pairs(&params.linked_pairs, layout_str, layout_generator.as_ref())
+    })
+    .unwrap_or_else(|e| panic!("Could not set up the layout permutation: {:?}", e));
     // Get initial Layout.
     let initial_indices = match start_with_layout {
         true => pm.get_permutable_indices(),
@@ -316,19 +478,22 @@ pub fn optimize(
         layout_generator: layout_generator.clone(),
         key_switches: params.key_switches,
         result_cache,
+        scheduled_weights: params.weight_schedules.tracker(),
     };
 
-    // Create new SA solver with some parameters (see docs for details)
+    // Create new SA solver following the configured temperature schedule.
     // This essentially just prepares the SA solver. It is not run yet, nor does it know anything about the problem it is about to solve.
-    let solver = SimulatedAnnealing::new(init_temp)
-        .unwrap()
-        // Optional: Define temperature function (defaults to `SATempFunc::TemperatureFast`)
-        .with_temp_func(SATempFunc::Exponential(0.998))
-        /////////////////////////
-        // Stopping criteria   //
-        /////////////////////////
-        // Optional: stop if there was no accepted solution after [params.stall_accepted] iterations
-        .with_stall_accepted(params.stall_accepted);
+    // It stops if there was no accepted solution after [params.stall_accepted] iterations.
+    let solver = Annealing::new(init_temp, &params.schedule, params.stall_accepted);
+
+    // Optional: stop (or restart) if the best cost stagnates
+    let solver = PlateauStopping {
+        id: process_name.to_string(),
+        solver,
+        detector: PlateauDetector::new(&params.early_stopping),
+        permutator: pm.clone(),
+        pending_swaps: None,
+    };
 
     // Create and run the executor, which will apply the solver to the problem, given a starting point (`init_param`)
     let mut executor = Executor::new(problem, solver)
@@ -368,6 +533,16 @@ pub fn optimize(
         }
     }
 
+    if let Some(reporter) = progress_reporter {
+        let interval = reporter.interval();
+        let progress_observer = ProgressObserver {
+            id: process_name.to_string(),
+            permutator: pm.clone(),
+            reporter,
+        };
+        executor = executor.add_observer(progress_observer, ObserverMode::Every(interval));
+    }
+
     log::info!(
         "{} Starting optimization with: initial_temperature: {:.2}°, {:?}",
         format!("{}:", process_name).yellow().bold(),
@@ -376,9 +551,395 @@ pub fn optimize(
     );
     let res = executor.run().unwrap();
 
+    log::info!(
+        "{} {} {} after {} iterations, {} restart(s)",
+        format!("{}:", process_name).yellow().bold(),
+        "Optimization stopped:".green(),
+        res.state()
+            .get_termination_reason()
+            .map(|reason| reason.text())
+            .unwrap_or("Unknown reason"),
+        res.state().get_iter(),
+        res.solver.detector.restarts(),
+    );
+
     let best_layout_param = res.state().get_best_param().unwrap();
     let best_layout_str = pm.generate_string(best_layout_param);
     let best_layout = layout_generator.generate(&best_layout_str).unwrap();
 
     (best_layout_str, best_layout)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use keyboard_layout::test_utils::ToyLayoutGenerator;
+    use layout_evaluation::{
+        evaluation::MetricParameters,
+        ngram_mapper::on_demand_ngram_mapper::{NgramMapperConfig, OnDemandNgramMapper},
+        ngrams::{Bigrams, Trigrams, Unigrams},
+    };
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    };
+
+    /// The left pinky has "c" (North), "d" (Out), "a" (Center), "e" (In), and "b" (South), the
+    /// right index finger "v" (In). All other symbols are fixed.
+    const LAYOUT: &str = "cdaebfghijklmnopqrstuvwxyzABCDEFGHIJKLMNOP";
+    const FIXED: &str = "fghijklmnopqrstuwxyzABCDEFGHIJKLMNOP";
+
+    /// Key costs on a corpus of "ab" bigrams, optionally capping the SFB share at zero
+    fn evaluator(sfb_cap: bool) -> Evaluator {
+        evaluator_for(&"ab".repeat(50), sfb_cap)
+    }
+
+    fn evaluator_for(text: &str, sfb_cap: bool) -> Evaluator {
+        let ngram_mapper = OnDemandNgramMapper::with_ngrams(
+            Unigrams::from_text(text).unwrap(),
+            Bigrams::from_text(text).unwrap(),
+            Trigrams::from_text(text).unwrap(),
+            NgramMapperConfig::default(),
+        );
+
+        let max_allowed = if sfb_cap {
+            "{percent: {sfb: 0.0}}"
+        } else {
+            "null"
+        };
+        let params: MetricParameters = serde_yaml::from_str(&format!(
+            "
+            key_costs:
+              enabled: true
+              weight: 1.0
+              normalization: {{type: fixed, value: 1.0}}
+              params: {{}}
+            bigram_stats:
+              enabled: true
+              weight: 1.0
+              normalization: {{type: fixed, value: 1.0}}
+              max_allowed: {}
+              params: {{ignore_thumbs: false}}
+            ",
+            max_allowed
+        ))
+        .unwrap();
+
+        Evaluator::default(Box::new(ngram_mapper)).default_metrics(&params)
+    }
+
+    fn run(evaluator: &Evaluator) -> Layout {
+        let params = Parameters {
+            init_temp: Some(1.0),
+            max_iters: 2000,
+            ..Parameters::default()
+        };
+        run_with(&params, evaluator, FIXED, None)
+    }
+
+    fn run_with(
+        params: &Parameters,
+        evaluator: &Evaluator,
+        fixed: &str,
+        observer: Option<CustomObserver>,
+    ) -> Layout {
+        let layout_generator: Box<dyn LayoutGenerator> = Box::new(ToyLayoutGenerator::default());
+
+        let (_, layout) = optimize(
+            "test",
+            params,
+            LAYOUT,
+            fixed,
+            &layout_generator,
+            true,
+            evaluator,
+            false,
+            None,
+            observer,
+            None,
+        );
+        layout
+    }
+
+    fn is_sfb(layout: &Layout) -> bool {
+        let a = &layout.get_layerkey_for_symbol(&'a').unwrap().key;
+        let b = &layout.get_layerkey_for_symbol(&'b').unwrap().key;
+        a.hand == b.hand && a.finger == b.finger
+    }
+
+    /// Every layout violating the cap costs more than any layout respecting it.
+    #[test]
+    fn capped_layouts_cost_more_than_all_others() {
+        let evaluator = evaluator(true);
+        let layout_generator = ToyLayoutGenerator::default();
+        let permutator = LayoutPermutator::new(LAYOUT, FIXED);
+
+        let mut indices = permutator.get_permutable_indices();
+        let mut capped_costs = Vec::new();
+        let mut other_costs = Vec::new();
+        for _ in 0..500 {
+            let layout = layout_generator
+                .generate(&permutator.generate_string(&indices))
+                .unwrap();
+            let result = evaluator.evaluate_layout(&layout);
+            assert_eq!(result.violations().is_empty(), !is_sfb(&layout));
+            if result.violations().is_empty() {
+                other_costs.push(result.total_cost());
+            } else {
+                capped_costs.push(result.total_cost());
+            }
+            indices = permutator.perform_n_swaps(&indices, 1);
+        }
+
+        let cheapest_capped = capped_costs.iter().copied().fold(f64::INFINITY, f64::min);
+        let priciest_other = other_costs.iter().copied().fold(0.0, f64::max);
+        assert!(!capped_costs.is_empty() && !other_costs.is_empty());
+        assert!(cheapest_capped > priciest_other);
+    }
+
+    /// Without the cap, the cheapest keys are on the pinky, which puts both symbols on one
+    /// finger (as in [`LAYOUT`]). With the cap, the optimizer has to move one of them to the
+    /// costly key of "v".
+    #[test]
+    fn capped_layouts_never_win() {
+        let layout_generator = ToyLayoutGenerator::default();
+        let sfb_optimum = layout_generator.generate(LAYOUT).unwrap();
+        let capped_optimum = layout_generator
+            .generate("cdbevfghijklmnopqrstuawxyzABCDEFGHIJKLMNOP")
+            .unwrap();
+
+        let uncapped = evaluator(false);
+        let layout = run(&uncapped);
+        assert!(is_sfb(&layout));
+        assert_eq!(
+            uncapped.evaluate_layout(&layout).total_cost(),
+            uncapped.evaluate_layout(&sfb_optimum).total_cost()
+        );
+
+        let capped = evaluator(true);
+        assert!(!capped.evaluate_layout(&sfb_optimum).violations().is_empty());
+        let layout = run(&capped);
+        let result = capped.evaluate_layout(&layout);
+        assert!(!is_sfb(&layout));
+        assert!(result.violations().is_empty());
+        assert_eq!(
+            result.total_cost(),
+            capped.evaluate_layout(&capped_optimum).total_cost()
+        );
+        assert!(result.total_cost() > uncapped.evaluate_layout(&sfb_optimum).total_cost());
+    }
+
+    /// Counts the iterations (the observers see them numbered from zero)
+    struct IterationCount(Arc<AtomicU64>);
+
+    impl Observe<SaIterState> for IterationCount {
+        fn observe_iter(&mut self, state: &SaIterState, _kv: &KV) -> Result<(), Error> {
+            self.0.store(state.get_iter() + 1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    /// Number of iterations of a run on a landscape without any improvements: the corpus only
+    /// contains the fixed symbol "P", so all layouts cost the same.
+    fn iterations_on_flat_landscape(early_stopping: EarlyStoppingParameters) -> u64 {
+        let params = Parameters {
+            init_temp: Some(1.0),
+            max_iters: 1000,
+            early_stopping,
+            ..Parameters::default()
+        };
+        let iterations = Arc::new(AtomicU64::new(0));
+        let observer = CustomObserver(Box::new(IterationCount(iterations.clone())));
+
+        run_with(
+            &params,
+            &evaluator_for("PPPP", false),
+            FIXED,
+            Some(observer),
+        );
+        iterations.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn stops_after_patience_on_flat_landscape() {
+        let early_stopping = EarlyStoppingParameters {
+            patience: Some(50),
+            ..EarlyStoppingParameters::default()
+        };
+        assert_eq!(iterations_on_flat_landscape(early_stopping), 50);
+    }
+
+    #[test]
+    fn restarts_on_flat_landscape() {
+        let early_stopping = EarlyStoppingParameters {
+            patience: Some(50),
+            restart_swaps: 2,
+            max_restarts: 3,
+            ..EarlyStoppingParameters::default()
+        };
+        assert_eq!(iterations_on_flat_landscape(early_stopping), 200);
+    }
+
+    #[test]
+    fn runs_to_max_iters_without_early_stopping() {
+        assert_eq!(
+            iterations_on_flat_landscape(EarlyStoppingParameters::default()),
+            1000
+        );
+    }
+
+    /// Records the temperature and acceptance ratio of each iteration
+    struct TemperatureLog(Arc<Mutex<Vec<(f64, f64)>>>);
+
+    impl Observe<SaIterState> for TemperatureLog {
+        fn observe_iter(&mut self, _state: &SaIterState, kv: &KV) -> Result<(), Error> {
+            let get = |key: &str| kv.get(key).and_then(|v| v.get_float()).unwrap();
+            self.0.lock().unwrap().push((get("t"), get("acc_ratio")));
+            Ok(())
+        }
+    }
+
+    /// On a rugged objective, where each swap changes the cost by far more than the
+    /// temperature, only improvements are accepted. Once they run out, the acceptance ratio
+    /// collapses and the adaptive schedule has to raise the temperature.
+    #[test]
+    fn adaptive_schedule_raises_temperature_when_acceptance_collapses() {
+        // each symbol with a different frequency, all of them permutable
+        let text: String = LAYOUT
+            .chars()
+            .enumerate()
+            .flat_map(|(i, c)| std::iter::repeat(c).take(i + 1))
+            .collect();
+        let mut evaluator = evaluator_for(&text, false);
+        assert!(evaluator.set_metric_weight("Key Costs", 1000.0));
+
+        let schedule: ScheduleParameters = serde_yaml::from_str(
+            "{cooling: adaptive, target_acceptance: 0.4, adjustment: 0.05, window: 20}",
+        )
+        .unwrap();
+        let params = Parameters {
+            init_temp: Some(1.0),
+            schedule,
+            max_iters: 2000,
+            ..Parameters::default()
+        };
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let observer = CustomObserver(Box::new(TemperatureLog(log.clone())));
+        run_with(&params, &evaluator, "", Some(observer));
+
+        let log = log.lock().unwrap();
+        assert!(log.iter().all(|(t, _)| *t <= 1.0));
+        let raised = log.windows(2).any(|w| w[1].0 > w[0].0 && w[1].1 <= 0.4);
+        assert!(raised);
+        // the acceptance ratio collapsed towards the end
+        assert!(log.last().unwrap().1 < 0.4);
+    }
+
+    /// Optimizing only the ten keys of the left middle and index fingers leaves all other
+    /// symbols in place.
+    #[test]
+    fn masked_positions_stay_in_place() {
+        // each symbol with a different frequency, the most frequent ones not on Center keys
+        let text: String = LAYOUT
+            .chars()
+            .enumerate()
+            .flat_map(|(i, c)| std::iter::repeat(c).take(i + 1))
+            .collect();
+        let evaluator = evaluator_for(&text, false);
+
+        let position_mask: PositionMask = serde_yaml::from_str(
+            "
+            fingers:
+              - [Left, Pinky]
+              - [Left, Ring]
+              - [Left, Thumb]
+              - [Right, Index]
+              - [Right, Middle]
+              - [Right, Ring]
+              - [Right, Pinky]
+              - [Right, Thumb]
+            ",
+        )
+        .unwrap();
+        let params = Parameters {
+            init_temp: Some(1.0),
+            max_iters: 2000,
+            position_mask,
+            ..Parameters::default()
+        };
+        let layout = run_with(&params, &evaluator, "", None);
+        let layout_str = layout.as_text();
+
+        let (start, result) = (LAYOUT.as_bytes(), layout_str.as_bytes());
+        assert_eq!(result[..10], start[..10]);
+        assert_eq!(result[20..], start[20..]);
+
+        let mut region = result[10..20].to_vec();
+        region.sort_unstable();
+        assert_eq!(region, start[10..20]);
+
+        let start_layout = ToyLayoutGenerator::default().generate(LAYOUT).unwrap();
+        assert!(
+            evaluator.evaluate_layout(&layout).total_cost()
+                < evaluator.evaluate_layout(&start_layout).total_cost()
+        );
+    }
+
+    /// Checks in each iteration that the symbols of `pairs` are on mirrored keys
+    struct MirrorCheck {
+        permutator: LayoutPermutator,
+        pairs: Vec<(char, char)>,
+        n_checked: Arc<AtomicU64>,
+    }
+
+    impl Observe<SaIterState> for MirrorCheck {
+        fn observe_iter(&mut self, state: &SaIterState, _kv: &KV) -> Result<(), Error> {
+            let layout_str = self.permutator.generate_string(state.get_param().unwrap());
+            let layout = ToyLayoutGenerator::default().generate(&layout_str).unwrap();
+            for (c1, c2) in self.pairs.iter() {
+                let k1 = &layout.get_layerkey_for_symbol(c1).unwrap().key;
+                let k2 = &layout.get_layerkey_for_symbol(c2).unwrap().key;
+                assert!(
+                    k1.hand != k2.hand && k1.finger == k2.finger && k1.direction == k2.direction,
+                    "{} and {} are not mirrored in {}",
+                    c1,
+                    c2,
+                    layout_str
+                );
+            }
+            self.n_checked.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn linked_pairs_stay_mirrored() {
+        let text: String = LAYOUT
+            .chars()
+            .enumerate()
+            .flat_map(|(i, c)| std::iter::repeat(c).take(i + 1))
+            .collect();
+        // "a" (left pinky North) and "J" (right pinky North) start mirrored, "b" (left pinky
+        // Out) and "K" (right pinky In) are mirrored by linking them
+        let pairs = vec![('a', 'J'), ('b', 'K'), ('p', 'u')];
+        let params = Parameters {
+            init_temp: Some(10.0),
+            max_iters: 500,
+            linked_pairs: pairs.clone(),
+            ..Parameters::default()
+        };
+        let permutator = LayoutPermutator::new(LAYOUT, "")
+            .with_linked_pairs(&pairs, LAYOUT, &ToyLayoutGenerator::default())
+            .unwrap();
+        let n_checked = Arc::new(AtomicU64::new(0));
+        let observer = CustomObserver(Box::new(MirrorCheck {
+            permutator,
+            pairs,
+            n_checked: n_checked.clone(),
+        }));
+
+        run_with(&params, &evaluator_for(&text, false), "", Some(observer));
+        assert_eq!(n_checked.load(Ordering::SeqCst), 500);
+    }
+}
@@ -13,6 +13,7 @@ use keyboard_layout::{
 };
 
 use layout_evaluation::{
+    api::{self, NgramSource},
     cache::Cache,
     config::EvaluationParameters,
     evaluation::Evaluator,
@@ -111,7 +112,7 @@ impl NgramProvider {
         let mut trigrams = Trigrams::from_frequencies_str(trigrams_str)
             .map_err(|e| format!("Could not load trigrams: {:?}", e))?;
 
-        let eval_params: EvaluationParameters = serde_yaml::from_str(eval_params_str)
+        let eval_params = EvaluationParameters::from_str(eval_params_str)
             .map_err(|e| format!("Could not read evaluation parameters: {:?}", e))?;
 
         let ngrams_config = eval_params.ngrams;
@@ -135,7 +136,7 @@ impl NgramProvider {
         let mut trigrams = Trigrams::from_text(text)
             .map_err(|e| format!("Could not generate trigrams from text: {:?}", e))?;
 
-        let eval_params: EvaluationParameters = serde_yaml::from_str(eval_params_str)
+        let eval_params = EvaluationParameters::from_str(eval_params_str)
             .map_err(|e| format!("Could not read evaluation parameters: {:?}", e))?;
 
         let ngrams_config = eval_params.ngrams;
@@ -174,7 +175,7 @@ impl LayoutEvaluator {
 
         let layout_generator = NeoLayoutGenerator::from_object(layout_cfg.base_layout, keyboard);
 
-        let eval_params: EvaluationParameters = serde_yaml::from_str(eval_params_str)
+        let eval_params = EvaluationParameters::from_str(eval_params_str)
             .map_err(|e| format!("Could not read evaluation parameters: {:?}", e))?;
 
         let evaluator = Evaluator::default(Box::new(ngram_provider.ngram_provider.clone()))
@@ -219,6 +220,64 @@ impl LayoutEvaluator {
     }
 }
 
+/// Scores layouts with the library facade ([`api::Evaluator`]). The page passes the
+/// configurations and the corpus (fetched or entered by the user) as strings.
+#[wasm_bindgen]
+pub struct LayoutScorer {
+    evaluator: api::Evaluator,
+}
+
+#[wasm_bindgen]
+impl LayoutScorer {
+    pub fn with_frequencies(
+        layout_cfg_str: &str,
+        eval_params_str: &str,
+        unigrams_str: &str,
+        bigrams_str: &str,
+        trigrams_str: &str,
+    ) -> Result<LayoutScorer, JsValue> {
+        utils::set_panic_hook();
+
+        let ngrams = NgramSource::Frequencies {
+            unigrams: unigrams_str,
+            bigrams: bigrams_str,
+            trigrams: trigrams_str,
+        };
+        let evaluator = api::Evaluator::from_config_strs(layout_cfg_str, eval_params_str, ngrams)
+            .map_err(|e| format!("Could not set up the evaluation: {:?}", e))?;
+
+        Ok(LayoutScorer { evaluator })
+    }
+
+    pub fn with_text(
+        layout_cfg_str: &str,
+        eval_params_str: &str,
+        text: &str,
+    ) -> Result<LayoutScorer, JsValue> {
+        utils::set_panic_hook();
+
+        let evaluator = api::Evaluator::from_config_strs(
+            layout_cfg_str,
+            eval_params_str,
+            NgramSource::Text(text),
+        )
+        .map_err(|e| format!("Could not set up the evaluation: {:?}", e))?;
+
+        Ok(LayoutScorer { evaluator })
+    }
+
+    /// Evaluate a layout, returning an object with the total cost, the metrics and the
+    /// statistics (e.g. the SFB share as the statistic named "sfb").
+    pub fn evaluate(&self, layout_str: &str) -> Result<JsValue, JsValue> {
+        let layout_str: String = layout_str.chars().filter(|c| !c.is_whitespace()).collect();
+        let res = self
+            .evaluator
+            .evaluate(&layout_str)
+            .map_err(|e| format!("Could not evaluate the layout: {:?}", e))?;
+        Ok(JsValue::from_serde(&res).unwrap())
+    }
+}
+
 #[wasm_bindgen]
 pub struct LayoutOptimizer {
     evaluator: Evaluator,
@@ -407,6 +466,7 @@ pub fn sa_optimize(
         /* log_everything: */ false,
         Some(Cache::new()),
         Some(SaCustomObserver(Box::new(observer))),
+        None,
     );
     let minus_one = JsValue::from(-1);
     let _ = update_callback.call1(&this, &minus_one);
@@ -3,11 +3,44 @@
 #![cfg(target_arch = "wasm32")]
 
 extern crate wasm_bindgen_test;
+use js_sys::{Array, Reflect};
+use layout_evaluation_wasm::LayoutScorer;
+use wasm_bindgen::JsValue;
 use wasm_bindgen_test::*;
 
 wasm_bindgen_test_configure!(run_in_browser);
 
+const LAYOUT_CONFIG: &str = include_str!("../../../config/keyboard/sval.yml");
+const EVALUATION_CONFIG: &str = include_str!("../../../config/evaluation/sval.yml");
+const LAYOUT: &str = "?öcäb-üiqy')ezo.,a(umkhjlg{txdw□n}pv□s□fr";
+
+fn get(object: &JsValue, key: &str) -> JsValue {
+    Reflect::get(object, &JsValue::from_str(key)).unwrap()
+}
+
 #[wasm_bindgen_test]
-fn pass() {
-    assert_eq!(1 + 1, 2);
+fn evaluate_returns_the_sfb_share() {
+    let scorer = LayoutScorer::with_text(
+        LAYOUT_CONFIG,
+        EVALUATION_CONFIG,
+        "the quick brown fox jumps over the lazy dog",
+    )
+    .unwrap();
+    let result = scorer.evaluate(LAYOUT).unwrap();
+
+    assert!(get(&result, "total_cost").as_f64().unwrap() > 0.0);
+
+    let sfb = Array::from(&get(&result, "statistics"))
+        .iter()
+        .find(|statistic| get(statistic, "name").as_string().as_deref() == Some("sfb"))
+        .and_then(|statistic| get(&statistic, "percent").as_f64())
+        .unwrap();
+    assert!((0.0..=100.0).contains(&sfb));
+}
+
+#[wasm_bindgen_test]
+fn invalid_layouts_are_errors() {
+    let scorer = LayoutScorer::with_text(LAYOUT_CONFIG, EVALUATION_CONFIG, "abc").unwrap();
+
+    assert!(scorer.evaluate("abc").is_err());
 }